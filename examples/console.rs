@@ -0,0 +1,24 @@
+//! Using the built-in console overlay. Press backtick to open it.
+//!
+//! Run with: `cargo run --example console --features console`
+
+use bevy::prelude::*;
+use iyes_cli::prelude::*;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins((CliPlugin, CliConsolePlugin::default()))
+        .register_clicommand_args("echo", echo)
+        .add_systems(Startup, setup)
+        .run();
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn(Camera2d);
+}
+
+/// Implementation of the "echo" command
+fn echo(In(args): In<Vec<String>>, mut output: CliOutput) {
+    output.println(args.join(" "));
+}
@@ -1,6 +1,13 @@
 use std::time::Duration;
 
-use bevy::{input::{keyboard::{Key, KeyboardInput}, ButtonState}, prelude::*, window::PrimaryWindow};
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    prelude::*,
+    window::PrimaryWindow,
+};
 use iyes_cli::prelude::*;
 use rand::prelude::*;
 
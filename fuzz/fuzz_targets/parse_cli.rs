@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let cli = String::from_utf8_lossy(data);
+    let _ = iyes_cli::parse_cli(&cli);
+    if let Ok(chain) = iyes_cli::parse_cli_chain(&cli) {
+        for link in chain.links() {
+            // Spans of chain links must be valid slices of the input
+            let _ = &cli[link.span.clone()];
+        }
+    }
+});
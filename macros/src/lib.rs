@@ -0,0 +1,259 @@
+//! Procedural macros for `iyes_cli`.
+//!
+//! Use them through `iyes_cli` (with its `macros` cargo feature), not directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{
+    parse_macro_input, Expr, ExprLit, FnArg, GenericArgument, Ident, ItemFn, Lit, LitStr, Meta,
+    Pat, PathArguments, Token, Type,
+};
+
+/// The arg types supported by `iyes_cli` signatures
+const ARG_TYPES: &[&str] = &[
+    "bool", "char", "String", "i8", "i16", "i32", "i64", "isize", "u8", "u16", "u32", "u64",
+    "usize", "f32", "f64",
+];
+
+/// Declare a "console command" from a system `fn` with typed args
+///
+/// See the documentation of `iyes_cli::cli_command`.
+#[proc_macro_attribute]
+pub fn cli_command(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr = parse_macro_input!(attr as CliCommandAttr);
+    let item = parse_macro_input!(item as ItemFn);
+    match expand(attr, item) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// The former name of [`macro@cli_command`], kept as an alias
+#[proc_macro_attribute]
+pub fn clicommand(attr: TokenStream, item: TokenStream) -> TokenStream {
+    cli_command(attr, item)
+}
+
+#[derive(Default)]
+struct CliCommandAttr {
+    name: Option<LitStr>,
+    auto: bool,
+}
+
+impl Parse for CliCommandAttr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut attr = CliCommandAttr::default();
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            match key.to_string().as_str() {
+                "name" => {
+                    input.parse::<Token![=]>()?;
+                    attr.name = Some(input.parse()?);
+                }
+                "auto" => attr.auto = true,
+                _ => return Err(syn::Error::new(key.span(), "unknown cli_command option")),
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(attr)
+    }
+}
+
+/// How a leading fn param is filled from the command args
+enum ArgKind {
+    Required,
+    Optional,
+    Variadic,
+}
+
+struct Arg {
+    ident: Ident,
+    ty: Type,
+    kind: ArgKind,
+}
+
+fn expand(attr: CliCommandAttr, item: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    let fn_ident = &item.sig.ident;
+    let vis = &item.vis;
+    let name = attr
+        .name
+        .unwrap_or_else(|| LitStr::new(&fn_ident.to_string(), fn_ident.span()));
+    let register_ident = format_ident!("register_{}", fn_ident);
+
+    let mut args = Vec::new();
+    let mut params = Vec::new();
+    for input in &item.sig.inputs {
+        let FnArg::Typed(input) = input else {
+            return Err(syn::Error::new_spanned(
+                input,
+                "cli_command fns cannot take self",
+            ));
+        };
+        let Pat::Ident(pat) = &*input.pat else {
+            return Err(syn::Error::new_spanned(
+                &input.pat,
+                "cli_command params must be plain identifiers",
+            ));
+        };
+        let ident = pat.ident.clone();
+        match arg_kind(&input.ty) {
+            Some((kind, ty)) if params.is_empty() => {
+                match (args.last().map(|arg: &Arg| &arg.kind), &kind) {
+                    (Some(ArgKind::Variadic), _) => {
+                        return Err(syn::Error::new_spanned(
+                            input,
+                            "a Vec arg must be the last arg",
+                        ));
+                    }
+                    (Some(ArgKind::Optional), ArgKind::Required) => {
+                        return Err(syn::Error::new_spanned(
+                            input,
+                            "a required arg cannot follow an Option arg",
+                        ));
+                    }
+                    _ => {}
+                }
+                args.push(Arg { ident, ty, kind });
+            }
+            _ => params.push((ident, (*input.ty).clone())),
+        }
+    }
+
+    let signature = args
+        .iter()
+        .map(|arg| {
+            // Only supported types are accepted as args, so this always has a name
+            let ty = type_name(&arg.ty).unwrap_or_default();
+            match arg.kind {
+                ArgKind::Required => format!("<{}: {}>", arg.ident, ty),
+                ArgKind::Optional => format!("[{}: {}]", arg.ident, ty),
+                ArgKind::Variadic => format!("[{}: {}...]", arg.ident, ty),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let signature = LitStr::new(&signature, Span::call_site());
+
+    let arg_values = args.iter().map(|arg| {
+        let key = LitStr::new(&arg.ident.to_string(), arg.ident.span());
+        let ty = &arg.ty;
+        match arg.kind {
+            // The signature was validated before the system runs
+            ArgKind::Required => quote!(__args.get::<#ty>(#key).unwrap()),
+            ArgKind::Optional => quote!(__args.get::<#ty>(#key)),
+            ArgKind::Variadic => quote!(__args.get_all::<#ty>(#key)),
+        }
+    });
+    let param_idents: Vec<&Ident> = params.iter().map(|(ident, _)| ident).collect();
+    let param_types = params.iter().map(|(_, ty)| ty);
+
+    let description = doc_summary(&item);
+    let set_description = description.map(|description| {
+        quote! {
+            target.set_clicommand_description(#name, #description);
+        }
+    });
+
+    // Needs the `auto-register` feature of `iyes_cli`
+    let submit = attr.auto.then(|| {
+        quote! {
+            ::iyes_cli::auto_register::inventory::submit! {
+                ::iyes_cli::auto_register::CliAutoRegister::new(#register_ident::<::bevy::app::App>)
+            }
+        }
+    });
+
+    let register_doc = format!(
+        "Register the `{}` \"console command\", implemented by [`{}`]",
+        name.value(),
+        fn_ident
+    );
+
+    Ok(quote! {
+        #item
+
+        #[doc = #register_doc]
+        #vis fn #register_ident<T: ::iyes_cli::CliCommandsRegisterExt>(target: &mut T) -> &mut T {
+            target.register_clicommand_typed(
+                #name,
+                #signature,
+                |::bevy::ecs::system::In(__args): ::bevy::ecs::system::In<::iyes_cli::args::CliArgs>,
+                 #(#param_idents: #param_types),*| {
+                    #fn_ident(#(#arg_values,)* #(#param_idents),*)
+                },
+            );
+            #set_description
+            target
+        }
+
+        #submit
+    })
+}
+
+/// If the type is one accepted as a command arg, how it is filled, and the type to parse
+fn arg_kind(ty: &Type) -> Option<(ArgKind, Type)> {
+    if type_name(ty).is_some() {
+        return Some((ArgKind::Required, ty.clone()));
+    }
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    let kind = match segment.ident.to_string().as_str() {
+        "Option" => ArgKind::Optional,
+        "Vec" => ArgKind::Variadic,
+        _ => return None,
+    };
+    let PathArguments::AngleBracketed(generics) = &segment.arguments else {
+        return None;
+    };
+    let Some(GenericArgument::Type(inner)) = generics.args.first() else {
+        return None;
+    };
+    type_name(inner)?;
+    Some((kind, inner.clone()))
+}
+
+/// The name of the type, if it is one of the supported arg types
+fn type_name(ty: &Type) -> Option<String> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let ident = path.path.get_ident()?.to_string();
+    ARG_TYPES.contains(&ident.as_str()).then_some(ident)
+}
+
+/// The first paragraph of the doc comment of the fn, if any
+fn doc_summary(item: &ItemFn) -> Option<LitStr> {
+    let mut lines = Vec::new();
+    for attr in &item.attrs {
+        let Meta::NameValue(meta) = &attr.meta else {
+            continue;
+        };
+        if !meta.path.is_ident("doc") {
+            continue;
+        }
+        let Expr::Lit(ExprLit {
+            lit: Lit::Str(doc), ..
+        }) = &meta.value
+        else {
+            continue;
+        };
+        let line = doc.value().trim().to_owned();
+        if line.is_empty() {
+            if lines.is_empty() {
+                continue;
+            }
+            break;
+        }
+        lines.push(line);
+    }
+    if lines.is_empty() {
+        return None;
+    }
+    Some(LitStr::new(&lines.join(" "), Span::call_site()))
+}
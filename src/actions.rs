@@ -0,0 +1,290 @@
+//! Representing "console commands" as reflected data, for editors and saved layouts.
+//!
+//! A [`CliAction`] is a command invocation (a name and args) as a plain
+//! reflected struct and [`Component`]. Editor crates can put actions on
+//! buttons or menu entries, save them as part of a `DynamicScene` or other
+//! serialized layout, and [run](CliAction::run) them when loaded back.
+//!
+//! To build such UIs, [`CliCommands::action_schemas`] describes every
+//! registered command as a [`CliActionSchema`]: its name, description and,
+//! for commands registered with a [signature](crate::args), its params.
+//!
+//! When a command that requires args is picked from a menu or palette, a
+//! [`CliActionForm`] can be created from its schema, to show a text box per
+//! param instead of failing. The form validates the values against the types
+//! of the params, and creates the action once they are all valid.
+//!
+//! ```rust,ignore
+//! commands.spawn((
+//!     Button,
+//!     CliAction::new("spawn").with_arg("100").with_arg("100").with_label("Spawn"),
+//! ));
+//!
+//! fn run_pressed(world: &mut World, q: &mut QueryState<(&Interaction, &CliAction)>) {
+//!     let actions: Vec<CliAction> = q
+//!         .iter(world)
+//!         .filter(|(interaction, _)| **interaction == Interaction::Pressed)
+//!         .map(|(_, action)| action.clone())
+//!         .collect();
+//!     for action in actions {
+//!         let _ = action.run(world);
+//!     }
+//! }
+//! ```
+//!
+//! [`CliPlugin`](crate::CliPlugin) registers these types for reflection.
+
+use bevy::prelude::*;
+
+use crate::args::{CliArgType, CliArgsError, CliSignature};
+use crate::error::CliError;
+use crate::{quote_cli_arg, run_cli_chain, CliCommands};
+
+/// A "console command" to run, with its args, as reflected data
+#[derive(Component, Reflect, Debug, Clone, Default, PartialEq, Eq)]
+#[reflect(Component, Debug, Default, PartialEq)]
+pub struct CliAction {
+    /// The name of the command
+    pub name: String,
+    pub args: Vec<String>,
+    /// Text to show for the action in a UI, if different from the command string
+    pub label: Option<String>,
+}
+
+impl CliAction {
+    pub fn new(name: &str) -> Self {
+        CliAction {
+            name: name.to_owned(),
+            args: vec![],
+            label: None,
+        }
+    }
+    pub fn with_arg(mut self, arg: impl ToString) -> Self {
+        self.args.push(arg.to_string());
+        self
+    }
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_owned());
+        self
+    }
+    /// The text to show for the action: its label, or its command string
+    pub fn label(&self) -> String {
+        self.label.clone().unwrap_or_else(|| self.to_cli())
+    }
+    /// The command string that runs the action, with args quoted as needed
+    pub fn to_cli(&self) -> String {
+        let mut cli = quote_cli_arg(&self.name).into_owned();
+        for arg in &self.args {
+            cli.push(' ');
+            cli.push_str(&quote_cli_arg(arg));
+        }
+        cli
+    }
+    /// Run the action immediately
+    ///
+    /// Errors are logged, like with [`run_cli`](crate::CliCommandsRunExt::run_cli),
+    /// and also returned.
+    pub fn run(&self, world: &mut World) -> Result<(), CliError> {
+        run_cli_chain(world, &self.to_cli(), true)
+    }
+}
+
+/// A param of a "console command", as described in a [`CliActionSchema`]
+#[derive(Reflect, Debug, Clone, Default, PartialEq, Eq)]
+#[reflect(Debug, Default, PartialEq)]
+pub struct CliActionParam {
+    pub name: String,
+    /// The name of the type of the param, as written in signatures (`f32`, `String`)
+    pub ty: String,
+    pub optional: bool,
+    /// Accepts any number of args
+    pub variadic: bool,
+}
+
+/// Description of a "console command", for building UIs that create [`CliAction`]s
+#[derive(Reflect, Debug, Clone, Default, PartialEq, Eq)]
+#[reflect(Debug, Default, PartialEq)]
+pub struct CliActionSchema {
+    pub name: String,
+    pub description: Option<String>,
+    pub usage: Option<String>,
+    /// The params of the command, if it was registered with a signature
+    ///
+    /// `None` if the args accepted by the command are not known.
+    pub params: Option<Vec<CliActionParam>>,
+}
+
+impl CliActionSchema {
+    /// Whether the command cannot be run without args
+    ///
+    /// False if the params of the command are not known.
+    pub fn requires_args(&self) -> bool {
+        match &self.params {
+            Some(params) => params.iter().any(|param| !param.optional),
+            None => false,
+        }
+    }
+}
+
+/// A text box of a [`CliActionForm`], for one param
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliActionField {
+    pub param: CliActionParam,
+    /// The text typed by the user
+    ///
+    /// For variadic params, the values are separated by whitespace.
+    pub value: String,
+}
+
+impl CliActionField {
+    /// The values of the field, as args
+    fn values(&self) -> Vec<&str> {
+        if self.param.variadic {
+            self.value.split_whitespace().collect()
+        } else if self.value.trim().is_empty() {
+            vec![]
+        } else {
+            vec![self.value.as_str()]
+        }
+    }
+    /// Check the value of the field against the type of the param
+    pub fn validate(&self) -> Result<(), CliArgsError> {
+        let values = self.values();
+        if values.is_empty() && !self.param.optional {
+            return Err(CliArgsError::Missing(self.param.name.clone()));
+        }
+        // Schemas come from signatures, so the type is always known
+        let Some(ty) = CliArgType::from_name(&self.param.ty) else {
+            return Ok(());
+        };
+        match values.iter().position(|value| !ty.validate(value)) {
+            Some(index) => Err(CliArgsError::Invalid {
+                param: self.param.name.clone(),
+                value: values[index].to_owned(),
+                ty,
+                index,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A form for entering the args of a command, created from its [`CliActionSchema`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliActionForm {
+    /// The name of the command
+    pub name: String,
+    /// One field per param, in order
+    pub fields: Vec<CliActionField>,
+}
+
+impl CliActionForm {
+    /// Create an empty form for a command
+    ///
+    /// Returns `None` if the params of the command are not known.
+    pub fn new(schema: &CliActionSchema) -> Option<Self> {
+        let params = schema.params.as_ref()?;
+        Some(CliActionForm {
+            name: schema.name.clone(),
+            fields: params
+                .iter()
+                .map(|param| CliActionField {
+                    param: param.clone(),
+                    value: String::new(),
+                })
+                .collect(),
+        })
+    }
+    /// Set the text of the field for the named param
+    pub fn set(&mut self, param: &str, value: &str) {
+        if let Some(field) = self.fields.iter_mut().find(|f| f.param.name == param) {
+            field.value = value.to_owned();
+        }
+    }
+    /// The problems with the values of the fields, by index of the field
+    pub fn errors(&self) -> Vec<(usize, CliArgsError)> {
+        let mut errors: Vec<_> = self
+            .fields
+            .iter()
+            .enumerate()
+            .filter_map(|(i, field)| field.validate().err().map(|e| (i, e)))
+            .collect();
+        // Optional args are positional, so a skipped one cannot be followed by a given one
+        let mut skipped = None;
+        for (i, field) in self.fields.iter().enumerate() {
+            match (field.values().is_empty(), skipped) {
+                (true, None) if field.param.optional => skipped = Some(i),
+                (false, Some(s)) => {
+                    let name = self.fields[s].param.name.clone();
+                    errors.push((s, CliArgsError::Missing(name)));
+                    break;
+                }
+                _ => {}
+            }
+        }
+        errors
+    }
+    pub fn is_valid(&self) -> bool {
+        self.errors().is_empty()
+    }
+    /// Create the action with the values of the fields, if they are all valid
+    pub fn to_action(&self) -> Result<CliAction, CliArgsError> {
+        if let Some((_, e)) = self.errors().into_iter().next() {
+            return Err(e);
+        }
+        let mut action = CliAction::new(&self.name);
+        for field in &self.fields {
+            action
+                .args
+                .extend(field.values().into_iter().map(|v| v.to_owned()));
+        }
+        Ok(action)
+    }
+}
+
+impl CliCommands {
+    /// Describe all registered commands, sorted by name
+    pub fn action_schemas(&self) -> Vec<CliActionSchema> {
+        let mut names: Vec<&str> = self.iter_names().collect();
+        names.sort_unstable();
+        names
+            .into_iter()
+            .filter_map(|name| self.action_schema(name))
+            .collect()
+    }
+    /// Describe a registered command
+    pub fn action_schema(&self, name: &str) -> Option<CliActionSchema> {
+        let info = self.get_info(name)?;
+        let params = if !info.accepts_args {
+            Some(vec![])
+        } else {
+            info.usage
+                .and_then(|usage| usage.strip_prefix(name))
+                .and_then(|params| params.trim().parse::<CliSignature>().ok())
+                .map(|signature| {
+                    signature
+                        .params()
+                        .iter()
+                        .map(|param| CliActionParam {
+                            name: param.name.clone(),
+                            ty: param.ty.name().to_owned(),
+                            optional: param.optional,
+                            variadic: param.variadic,
+                        })
+                        .collect()
+                })
+        };
+        Some(CliActionSchema {
+            name: name.to_owned(),
+            description: info.description.map(|s| s.to_owned()),
+            usage: info.usage.map(|s| s.to_owned()),
+            params,
+        })
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<CliAction>();
+    app.register_type::<CliActionParam>();
+    app.register_type::<CliActionSchema>();
+}
@@ -0,0 +1,173 @@
+//! Aliases: alternative names for command strings.
+//!
+//! An alias is replaced with its expansion when it is the command name of a
+//! command string, and any args are appended. With `alias sp "spawn 100 100"`,
+//! running `sp 5` runs `spawn 100 100 5`. Expansions may themselves use
+//! aliases, or contain multiple commands (like `a; b`).
+//!
+//! Aliases take priority over commands with the same name, except inside
+//! their own expansion, so `alias ls "ls --all"` works. An alias that would
+//! expand into itself with no command to fall back to is an error.
+//!
+//! Aliases defined from a [`CliSession`](crate::session::CliSession) only
+//! exist in that session, and shadow global aliases.
+
+use bevy::prelude::*;
+
+use crate::output::cli_error;
+use crate::session;
+use crate::status::CliStatus;
+use crate::{quote_cli_arg, CliCommands, CliCommandsRunExt};
+
+impl CliCommands {
+    /// Add an alias, which runs `expansion` when used as a command name
+    ///
+    /// If the alias already exists, it is replaced.
+    pub fn add_alias(&mut self, alias: &str, expansion: &str) {
+        self.aliases.insert(alias.to_owned(), expansion.to_owned());
+    }
+    /// Remove an alias, returning its expansion
+    pub fn remove_alias(&mut self, alias: &str) -> Option<String> {
+        self.aliases.remove(alias)
+    }
+    /// Get the expansion of an alias
+    pub fn get_alias(&self, alias: &str) -> Option<&str> {
+        self.aliases.get(alias).map(|s| s.as_str())
+    }
+    /// Iterate over all aliases and their expansions
+    pub fn iter_aliases(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.aliases.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+/// Run a command string whose command name may be an alias
+///
+/// Returns `None` if it is not an alias (or is one, but should run the
+/// command of the same name), otherwise whether it succeeded.
+pub(crate) fn run_alias(world: &mut World, name: &str, args: &[String]) -> Option<bool> {
+    let expansion = session::get_alias(world, name)?.to_owned();
+    let clicommands = world.get_resource::<CliCommands>()?;
+    if clicommands.alias_stack.iter().any(|active| active == name) {
+        if clicommands.command_available(name) {
+            return None;
+        }
+        let mut trace = clicommands.alias_stack.clone();
+        trace.push(name.to_owned());
+        let message = format!("Alias cycle: {}", trace.join(" -> "));
+        cli_error(world, message);
+        return Some(false);
+    }
+    let mut line = expansion;
+    for arg in args {
+        line.push(' ');
+        line.push_str(&quote_cli_arg(arg));
+    }
+    world
+        .resource_mut::<CliCommands>()
+        .alias_stack
+        .push(name.to_owned());
+    world.run_cli(&line);
+    world.resource_mut::<CliCommands>().alias_stack.pop();
+    let success = world
+        .get_resource::<CliStatus>()
+        .is_some_and(|status| status.last_success() == Some(true));
+    Some(success)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CliCommandsRegisterExt, CliPlugin};
+
+    #[derive(Resource, Default)]
+    struct Calls(Vec<Vec<String>>);
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.init_resource::<Calls>();
+        for name in ["spawn", "ls"] {
+            app.register_clicommand_args(
+                name,
+                |In(args): In<Vec<String>>, mut calls: ResMut<Calls>| calls.0.push(args),
+            );
+        }
+        app
+    }
+
+    #[test]
+    fn args_are_appended() {
+        let mut app = app();
+        let world = app.world_mut();
+        world
+            .resource_mut::<CliCommands>()
+            .add_alias("sp", "spawn 100 100");
+        world.run_cli("sp 5 \"a b\"");
+        assert_eq!(world.resource::<Calls>().0, [["100", "100", "5", "a b"]]);
+    }
+
+    #[test]
+    fn alias_can_use_its_own_name() {
+        let mut app = app();
+        let world = app.world_mut();
+        world
+            .resource_mut::<CliCommands>()
+            .add_alias("ls", "ls --all");
+        world.run_cli("ls");
+        assert_eq!(world.resource::<Calls>().0, [["--all"]]);
+    }
+
+    #[test]
+    fn cycles_fail() {
+        let mut app = app();
+        let world = app.world_mut();
+        let mut clicommands = world.resource_mut::<CliCommands>();
+        clicommands.add_alias("a", "b");
+        clicommands.add_alias("b", "a 1");
+        assert_eq!(run_alias(world, "a", &[]), Some(false));
+        assert!(world.resource::<Calls>().0.is_empty());
+        assert_eq!(run_alias(world, "spawn", &[]), None);
+    }
+
+    #[test]
+    #[cfg(feature = "builtin-commands")]
+    fn alias_and_unalias_commands() {
+        let mut app = app();
+        let world = app.world_mut();
+        world.run_cli("alias sp \"spawn 1\"");
+        assert_eq!(
+            world.resource::<CliCommands>().get_alias("sp"),
+            Some("spawn 1")
+        );
+        world.run_cli("unalias sp");
+        assert_eq!(world.resource::<CliCommands>().get_alias("sp"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "builtin-commands")]
+    fn session_aliases_shadow_global_ones() {
+        use crate::session::{CliRunInSessionCommand, CliSession};
+        use bevy::ecs::world::Command;
+
+        let mut app = app();
+        let world = app.world_mut();
+        let session = world.spawn(CliSession::default()).id();
+        let run = |world: &mut World, cli: &str| {
+            let cli = cli.to_owned();
+            CliRunInSessionCommand { session, cli }.apply(world);
+        };
+        world.run_cli("alias sp spawn global");
+        run(world, "alias sp spawn local");
+        run(world, "sp");
+        world.run_cli("sp");
+        assert_eq!(world.resource::<Calls>().0, [["local"], ["global"]]);
+        assert_eq!(
+            world.resource::<CliCommands>().get_alias("sp"),
+            Some("spawn global")
+        );
+
+        run(world, "unalias sp");
+        run(world, "sp");
+        assert_eq!(world.resource::<Calls>().0[2], ["global"]);
+    }
+}
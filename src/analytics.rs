@@ -0,0 +1,267 @@
+//! Statistics about which "console commands" are used and how long they
+//! take, for pruning, fixing and profiling the command surface.
+//!
+//! Requires the `analytics` cargo feature (enabled by default).
+//!
+//! Every time a command runs, its duration and outcome are added to
+//! [`CliUsageStats`]. The duration includes applying the `Commands` queued
+//! by the command's system. The built-in commands show the statistics:
+//!
+//!  - `stats [command|reset]`: how often each command ran, how often it
+//!    failed, and how long it took on average and at most
+//!  - `time <command...>`: run a command string and print how long it took
+//!
+//! Commands set with
+//! [`set_clicommand_timing`](crate::CliCommandsRegisterExt::set_clicommand_timing)
+//! print how long they took every time they run.
+//!
+//! Set [`CliConfig::usage_summary`] to report a summary when the app exits:
+//! how often each command ran, how often it failed, which commands were
+//! slowest, and which were never run.
+//!
+//! ```rust,no_run
+//! # use bevy::prelude::*;
+//! # use iyes_cli::prelude::*;
+//! # use iyes_cli::analytics::CliUsageSummary;
+//! # let mut app = App::new();
+//! app.add_plugins(CliPlugin);
+//! app.world_mut().resource_mut::<CliConfig>().usage_summary =
+//!     Some(CliUsageSummary::File("cli_usage.txt".into()));
+//! ```
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::{HashMap, Instant};
+
+use crate::output::{cli_error, cli_println, CliCommandsOutputExt, CliOutput, CliOutputLog};
+use crate::status::{cli_fail, CliStatus};
+use crate::table::{CliAlign, CliTable};
+use crate::{quote_cli_arg, CliCommands, CliCommandsRegisterExt, CliConfig};
+
+/// Where to report the summary of [`CliUsageStats`] when the app exits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliUsageSummary {
+    /// Log it, at info level
+    Log,
+    /// Write it to a file, replacing the file if it exists
+    File(PathBuf),
+}
+
+/// How a single command was used
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CliCommandUsage {
+    /// How many times the command ran
+    pub runs: u64,
+    /// How many of the runs failed
+    pub failures: u64,
+    /// The total time spent running the command
+    pub total: Duration,
+    /// The longest time a single run took
+    pub slowest: Duration,
+}
+
+impl CliCommandUsage {
+    /// The average time a run took
+    pub fn average(&self) -> Duration {
+        match self.runs {
+            0 => Duration::ZERO,
+            runs => self.total.div_f64(runs as f64),
+        }
+    }
+}
+
+/// How the commands were used since the app started
+#[derive(Resource, Debug, Default)]
+pub struct CliUsageStats {
+    commands: HashMap<String, CliCommandUsage>,
+}
+
+impl CliUsageStats {
+    /// How a command was used, if it ever ran
+    pub fn get(&self, name: &str) -> Option<&CliCommandUsage> {
+        self.commands.get(name)
+    }
+    /// Iterate over the commands that ran, in arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &CliCommandUsage)> {
+        self.commands
+            .iter()
+            .map(|(name, usage)| (name.as_str(), usage))
+    }
+    /// Forget all the statistics
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+    /// Record a run of a command
+    pub fn record(&mut self, name: &str, success: bool, duration: Duration) {
+        let usage = self.commands.entry_ref(name).or_default();
+        usage.runs += 1;
+        if !success {
+            usage.failures += 1;
+        }
+        usage.total += duration;
+        usage.slowest = usage.slowest.max(duration);
+    }
+    /// Describe the usage of the commands, as a human-readable report
+    ///
+    /// Lists the commands that ran (most used first) and the slowest ones.
+    /// If `clicommands` is given, the enabled commands that never ran are
+    /// listed too.
+    pub fn summary(&self, clicommands: Option<&CliCommands>) -> String {
+        let mut used: Vec<(&str, &CliCommandUsage)> = self.iter().collect();
+        used.sort_unstable_by(|a, b| b.1.runs.cmp(&a.1.runs).then(a.0.cmp(b.0)));
+        let runs: u64 = used.iter().map(|(_, usage)| usage.runs).sum();
+        let failures: u64 = used.iter().map(|(_, usage)| usage.failures).sum();
+        let mut summary = format!(
+            "CLI usage: {} runs of {} commands, {} failed\n",
+            runs,
+            used.len(),
+            failures
+        );
+        if !used.is_empty() {
+            let mut table = CliTable::new()
+                .with_column("COMMAND", CliAlign::Left)
+                .with_column("RUNS", CliAlign::Right)
+                .with_column("FAILED", CliAlign::Right)
+                .with_column("AVERAGE", CliAlign::Right)
+                .with_column("SLOWEST", CliAlign::Right);
+            for (name, usage) in &used {
+                table.add_row([
+                    name.to_string(),
+                    usage.runs.to_string(),
+                    usage.failures.to_string(),
+                    format!("{:.2?}", usage.average()),
+                    format!("{:.2?}", usage.slowest),
+                ]);
+            }
+            summary.push_str(&table.to_string());
+            summary.push('\n');
+            used.sort_unstable_by(|a, b| b.1.slowest.cmp(&a.1.slowest).then(a.0.cmp(b.0)));
+            let slowest: Vec<String> = used
+                .iter()
+                .take(5)
+                .map(|(name, usage)| format!("{} ({:.2?})", name, usage.slowest))
+                .collect();
+            summary.push_str(&format!("Slowest: {}\n", slowest.join(", ")));
+        }
+        if let Some(clicommands) = clicommands {
+            let mut unused: Vec<&str> = clicommands
+                .iter_names()
+                .filter(|name| clicommands.is_enabled(name) && !self.commands.contains_key(*name))
+                .collect();
+            unused.sort_unstable();
+            summary.push_str(&format!(
+                "Never run ({}): {}\n",
+                unused.len(),
+                unused.join(", ")
+            ));
+        }
+        summary
+    }
+}
+
+pub(crate) fn record(world: &mut World, name: &str, success: bool, duration: Duration) {
+    if let Some(mut stats) = world.get_resource_mut::<CliUsageStats>() {
+        stats.record(name, success, duration);
+    }
+}
+
+fn report_on_exit(
+    mut evr_exit: EventReader<AppExit>,
+    config: Res<CliConfig>,
+    stats: Res<CliUsageStats>,
+    clicommands: Res<CliCommands>,
+) {
+    if evr_exit.read().next().is_none() {
+        return;
+    }
+    let Some(target) = &config.usage_summary else {
+        return;
+    };
+    let summary = stats.summary(Some(&clicommands));
+    match target {
+        CliUsageSummary::Log => info!("{}", summary),
+        CliUsageSummary::File(path) => {
+            if let Err(e) = fs::write(path, summary) {
+                error!("Cannot write CLI usage summary to {:?}: {}", path, e);
+            }
+        }
+    }
+}
+
+/// `stats [command|reset]`: print how the commands were used
+fn stats(
+    In(args): In<Vec<String>>,
+    mut stats: ResMut<CliUsageStats>,
+    clicommands: Res<CliCommands>,
+    mut output: CliOutput,
+) {
+    match args.as_slice() {
+        [] => output.println(stats.summary(Some(&clicommands)).trim_end()),
+        [arg] if arg == "reset" => {
+            stats.clear();
+            output.println("Command statistics reset");
+        }
+        [name] => match stats.get(name) {
+            Some(usage) => output.println(format!(
+                "{}: {} runs, {} failed, {:.2?} average, {:.2?} slowest",
+                name,
+                usage.runs,
+                usage.failures,
+                usage.average(),
+                usage.slowest
+            )),
+            None => output.println(format!("{} has not run", name)),
+        },
+        _ => output.fail("Usage: stats [command|reset]"),
+    }
+}
+
+/// `time <command...>`: run a command string and print how long it took
+fn time(In(args): In<Vec<String>>, world: &mut World) {
+    if args.is_empty() {
+        cli_error(world, "Usage: time <command...>");
+        cli_fail(world);
+        return;
+    }
+    let line: Vec<_> = args.iter().map(|arg| quote_cli_arg(arg)).collect();
+    let line = line.join(" ");
+    let start = Instant::now();
+    let output = world.run_cli_captured(&line);
+    let elapsed = start.elapsed();
+    let success = world.resource::<CliStatus>().last_success() == Some(true);
+    for output_line in output {
+        world.resource_mut::<CliOutputLog>().push(output_line);
+    }
+    cli_println(world, format!("Took {:.2?}", elapsed));
+    if !success {
+        cli_fail(world);
+    }
+}
+
+/// Print how long a command took, if it is set to be timed
+pub(crate) fn report_timing(world: &mut World, name: &str, duration: Duration) {
+    let timed = world
+        .get_resource::<CliCommands>()
+        .and_then(|clicommands| clicommands.commands.get(name))
+        .is_some_and(|cmd| cmd.timed);
+    if timed {
+        cli_println(world, format!("{} took {:.2?}", name, duration));
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliUsageStats>();
+    app.add_systems(Last, report_on_exit);
+    app.register_clicommand_args("stats", stats)
+        .set_clicommand_description(
+            "stats",
+            "Print how often commands ran and how long they took",
+        )
+        .set_clicommand_usage("stats", "stats [command|reset]");
+    app.register_clicommand_args("time", time)
+        .set_clicommand_description("time", "Run a command and print how long it took")
+        .set_clicommand_usage("time", "time <command...>");
+}
@@ -0,0 +1,118 @@
+//! Running "console commands" at specific points within the frame.
+
+use bevy::prelude::*;
+use bevy::utils::{HashMap, Instant};
+
+use crate::diagnostics::record_queue_latency;
+use crate::CliCommandsRunExt;
+
+/// A point in the frame where queued "console commands" can be run
+///
+/// Use with [`CliCommandsRunExt::run_cli_at`]. Requires [`CliPlugin`](crate::CliPlugin).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CliAnchor {
+    /// Run in the [`PreUpdate`] schedule
+    PreUpdate,
+    /// Run in the [`Update`] schedule
+    Update,
+    /// Run in the [`Last`] schedule
+    Last,
+    /// Run in the [`FixedUpdate`] schedule
+    ///
+    /// Commands run on the next iteration of the fixed timestep, which
+    /// may be in a later frame, if it does not run every frame.
+    FixedUpdate,
+}
+
+/// System set containing the systems that run anchored "console commands"
+///
+/// It is present in every schedule corresponding to a [`CliAnchor`], so you
+/// can order your systems relative to it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CliAnchorSet;
+
+/// Holds the command strings waiting to be run at each [`CliAnchor`]
+///
+/// While paused, nothing is run and commands accumulate in the queue.
+#[derive(Resource, Default)]
+pub struct CliAnchorQueue {
+    queues: HashMap<CliAnchor, Vec<(String, Instant)>>,
+    paused: bool,
+}
+
+impl CliAnchorQueue {
+    /// Add a command string to be run at the given anchor
+    pub fn push(&mut self, anchor: CliAnchor, cli: String) {
+        self.queues
+            .entry(anchor)
+            .or_default()
+            .push((cli, Instant::now()));
+    }
+    /// Remove and return all command strings queued for the given anchor,
+    /// along with the time they were submitted
+    pub(crate) fn take(&mut self, anchor: CliAnchor) -> Vec<(String, Instant)> {
+        self.queues.remove(&anchor).unwrap_or_default()
+    }
+    /// Iterate over all queued command strings
+    pub fn iter(&self) -> impl Iterator<Item = (CliAnchor, &str)> {
+        self.queues
+            .iter()
+            .flat_map(|(anchor, clis)| clis.iter().map(move |(cli, _)| (*anchor, cli.as_str())))
+    }
+    /// How many command strings are queued
+    pub fn len(&self) -> usize {
+        self.queues.values().map(|clis| clis.len()).sum()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Remove all queued command strings
+    pub fn clear(&mut self) {
+        self.queues.clear();
+    }
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
+
+/// Command to queue a "console command" to be run at a given anchor
+pub struct CliRunAtCommand(pub String, pub CliAnchor);
+
+impl bevy::ecs::world::Command for CliRunAtCommand {
+    fn apply(self, world: &mut World) {
+        world.run_cli_at(&self.0, self.1);
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliAnchorQueue>();
+    app.add_systems(
+        PreUpdate,
+        run_anchored(CliAnchor::PreUpdate).in_set(CliAnchorSet),
+    );
+    app.add_systems(Update, run_anchored(CliAnchor::Update).in_set(CliAnchorSet));
+    app.add_systems(Last, run_anchored(CliAnchor::Last).in_set(CliAnchorSet));
+    app.add_systems(
+        FixedUpdate,
+        run_anchored(CliAnchor::FixedUpdate).in_set(CliAnchorSet),
+    );
+}
+
+fn run_anchored(anchor: CliAnchor) -> impl FnMut(&mut World) + Send + Sync + 'static {
+    move |world: &mut World| {
+        if world.resource::<CliAnchorQueue>().is_paused() {
+            return;
+        }
+        let clis = world.resource_mut::<CliAnchorQueue>().take(anchor);
+        for (cli, submitted) in clis {
+            world.run_cli(&cli);
+            record_queue_latency(world, submitted);
+        }
+    }
+}
@@ -0,0 +1,593 @@
+//! Declared command signatures, with automatic parsing and validation of args.
+//!
+//! Instead of every command hand-rolling `args[0].parse::<f32>()`, a command
+//! can be registered with a signature, using
+//! [`register_clicommand_typed`](crate::CliCommandsRegisterExt::register_clicommand_typed).
+//! Its args are validated before the command runs, and it receives them as [`CliArgs`].
+//!
+//! The signature syntax is a list of params, each of which is either
+//! required (`<name: type>`) or optional (`[name: type]`). The last param
+//! may be variadic (`<name: type...>` or `[name: type...]`), to accept any
+//! number of args. Optional params must come after all required params.
+//!
+//! The supported types are `bool`, `char`, `String`, `i8`..`i64`, `u8`..`u64`,
+//! `isize`, `usize`, `f32`, `f64`, and `Entity` (see [`selector`](crate::selector)).
+//!
+//! Commands can also accept GNU-style flags and options, by declaring them
+//! as [`CliFlags`] and registering with
+//! [`register_clicommand_flags`](crate::CliCommandsRegisterExt::register_clicommand_flags).
+//! The system receives a [`CliInvocation`], with the positional args
+//! separated from the flags and options:
+//!
+//!  - `--verbose`, `-v`: flags, which take no value
+//!  - `--count 5`, `--count=5`, `-c 5`, `-c5`: options, which take a value
+//!  - `-vq`: several short flags at once
+//!  - `--`: everything after it is a positional arg
+//!
+//! Args that look like negative numbers (`-5`, `-0.5`) are positional.
+//! Unknown flags and options are an error, reported with usage info.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+
+use bevy::prelude::Entity;
+
+use crate::selector::parse_cli_entity;
+use crate::source::CliSource;
+
+/// The type of a param in a [`CliSignature`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CliArgType {
+    Bool,
+    Char,
+    String,
+    I8,
+    I16,
+    I32,
+    I64,
+    Isize,
+    U8,
+    U16,
+    U32,
+    U64,
+    Usize,
+    F32,
+    F64,
+    Entity,
+}
+
+impl CliArgType {
+    /// The name of the type, as written in signatures
+    pub fn name(self) -> &'static str {
+        match self {
+            CliArgType::Bool => "bool",
+            CliArgType::Char => "char",
+            CliArgType::String => "String",
+            CliArgType::I8 => "i8",
+            CliArgType::I16 => "i16",
+            CliArgType::I32 => "i32",
+            CliArgType::I64 => "i64",
+            CliArgType::Isize => "isize",
+            CliArgType::U8 => "u8",
+            CliArgType::U16 => "u16",
+            CliArgType::U32 => "u32",
+            CliArgType::U64 => "u64",
+            CliArgType::Usize => "usize",
+            CliArgType::F32 => "f32",
+            CliArgType::F64 => "f64",
+            CliArgType::Entity => "Entity",
+        }
+    }
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "bool" => CliArgType::Bool,
+            "char" => CliArgType::Char,
+            "String" | "str" => CliArgType::String,
+            "i8" => CliArgType::I8,
+            "i16" => CliArgType::I16,
+            "i32" => CliArgType::I32,
+            "i64" => CliArgType::I64,
+            "isize" => CliArgType::Isize,
+            "u8" => CliArgType::U8,
+            "u16" => CliArgType::U16,
+            "u32" => CliArgType::U32,
+            "u64" => CliArgType::U64,
+            "usize" => CliArgType::Usize,
+            "f32" => CliArgType::F32,
+            "f64" => CliArgType::F64,
+            "Entity" => CliArgType::Entity,
+            _ => return None,
+        })
+    }
+    /// Check if the string is a valid value of this type
+    pub fn validate(self, s: &str) -> bool {
+        match self {
+            CliArgType::Bool => s.parse::<bool>().is_ok(),
+            CliArgType::Char => s.parse::<char>().is_ok(),
+            CliArgType::String => true,
+            CliArgType::I8 => s.parse::<i8>().is_ok(),
+            CliArgType::I16 => s.parse::<i16>().is_ok(),
+            CliArgType::I32 => s.parse::<i32>().is_ok(),
+            CliArgType::I64 => s.parse::<i64>().is_ok(),
+            CliArgType::Isize => s.parse::<isize>().is_ok(),
+            CliArgType::U8 => s.parse::<u8>().is_ok(),
+            CliArgType::U16 => s.parse::<u16>().is_ok(),
+            CliArgType::U32 => s.parse::<u32>().is_ok(),
+            CliArgType::U64 => s.parse::<u64>().is_ok(),
+            CliArgType::Usize => s.parse::<usize>().is_ok(),
+            CliArgType::F32 => s.parse::<f32>().is_ok(),
+            CliArgType::F64 => s.parse::<f64>().is_ok(),
+            CliArgType::Entity => parse_cli_entity(s).is_some(),
+        }
+    }
+}
+
+/// A single param in a [`CliSignature`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliParam {
+    pub name: String,
+    pub ty: CliArgType,
+    pub optional: bool,
+    /// Accepts any number of args (only allowed for the last param)
+    pub variadic: bool,
+}
+
+impl fmt::Display for CliParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dots = if self.variadic { "..." } else { "" };
+        if self.optional {
+            write!(f, "[{}: {}{}]", self.name, self.ty.name(), dots)
+        } else {
+            write!(f, "<{}: {}{}>", self.name, self.ty.name(), dots)
+        }
+    }
+}
+
+/// The declared params of a "console command"
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CliSignature {
+    params: Vec<CliParam>,
+}
+
+/// Error when a signature string is malformed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliSignatureError {
+    /// A param is not of the form `<name: type>` or `[name: type]`
+    Malformed(String),
+    /// A param has a type that is not supported
+    UnknownType(String),
+    /// A required param comes after an optional one
+    RequiredAfterOptional(String),
+    /// A variadic param is not the last one
+    VariadicNotLast(String),
+}
+
+impl fmt::Display for CliSignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliSignatureError::Malformed(s) => write!(f, "malformed param {:?}", s),
+            CliSignatureError::UnknownType(s) => write!(f, "unknown type {:?}", s),
+            CliSignatureError::RequiredAfterOptional(s) => {
+                write!(f, "required param {:?} after optional params", s)
+            }
+            CliSignatureError::VariadicNotLast(s) => {
+                write!(f, "variadic param {:?} is not the last param", s)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CliSignatureError {}
+
+impl FromStr for CliSignature {
+    type Err = CliSignatureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut params: Vec<CliParam> = Vec::new();
+        let mut rest = s.trim_start();
+        while let Some(open) = rest.chars().next() {
+            let (close, optional) = match open {
+                '<' => ('>', false),
+                '[' => (']', true),
+                _ => return Err(CliSignatureError::Malformed(rest.to_owned())),
+            };
+            let end = rest
+                .find(close)
+                .ok_or_else(|| CliSignatureError::Malformed(rest.to_owned()))?;
+            let whole = &rest[..=end];
+            let inner = &rest[1..end];
+            rest = rest[end + 1..].trim_start();
+
+            let (name, ty) = inner
+                .split_once(':')
+                .ok_or_else(|| CliSignatureError::Malformed(whole.to_owned()))?;
+            let name = name.trim();
+            let ty = ty.trim();
+            let (ty, variadic) = match ty.strip_suffix("...") {
+                Some(ty) => (ty.trim_end(), true),
+                None => (ty, false),
+            };
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                return Err(CliSignatureError::Malformed(whole.to_owned()));
+            }
+            let ty = CliArgType::from_name(ty)
+                .ok_or_else(|| CliSignatureError::UnknownType(ty.to_owned()))?;
+            if let Some(last) = params.last() {
+                if last.variadic {
+                    return Err(CliSignatureError::VariadicNotLast(last.name.clone()));
+                }
+                if last.optional && !optional {
+                    return Err(CliSignatureError::RequiredAfterOptional(name.to_owned()));
+                }
+            }
+            params.push(CliParam {
+                name: name.to_owned(),
+                ty,
+                optional,
+                variadic,
+            });
+        }
+        Ok(CliSignature { params })
+    }
+}
+
+impl fmt::Display for CliSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, param) in self.params.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", param)?;
+        }
+        Ok(())
+    }
+}
+
+/// Error when args do not match a [`CliSignature`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliArgsError {
+    /// A required param was not given
+    Missing(String),
+    /// More args were given than the signature accepts
+    TooMany(usize),
+    /// An arg is not a valid value of the param's type
+    Invalid {
+        param: String,
+        value: String,
+        ty: CliArgType,
+        /// The index of the arg, among the args being validated
+        index: usize,
+    },
+}
+
+impl fmt::Display for CliArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliArgsError::Missing(param) => write!(f, "missing arg <{}>", param),
+            CliArgsError::TooMany(n) => write!(f, "too many args (expected at most {})", n),
+            CliArgsError::Invalid {
+                param, value, ty, ..
+            } => {
+                write!(f, "invalid {} for <{}>: {:?}", ty.name(), param, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CliArgsError {}
+
+impl CliArgsError {
+    /// The index of the arg the error is about (`None` if one is missing)
+    pub fn arg_index(&self) -> Option<usize> {
+        match *self {
+            CliArgsError::Missing(_) => None,
+            CliArgsError::TooMany(n) => Some(n),
+            CliArgsError::Invalid { index, .. } => Some(index),
+        }
+    }
+}
+
+impl CliSignature {
+    /// The declared params, in order
+    pub fn params(&self) -> &[CliParam] {
+        &self.params
+    }
+    /// A usage string for a command with this signature, like `spawn <x: f32> [count: u32]`
+    pub fn usage(&self, name: &str) -> String {
+        if self.params.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{} {}", name, self)
+        }
+    }
+    /// Validate args against the signature
+    pub fn parse_args(&self, args: Vec<String>) -> Result<CliArgs, CliArgsError> {
+        let mut values = Vec::with_capacity(self.params.len());
+        let mut args = args.into_iter();
+        let mut index = 0;
+        for param in &self.params {
+            let given: Vec<String> = if param.variadic {
+                args.by_ref().collect()
+            } else {
+                args.next().into_iter().collect()
+            };
+            if given.is_empty() && !param.optional {
+                return Err(CliArgsError::Missing(param.name.clone()));
+            }
+            if let Some(i) = given.iter().position(|value| !param.ty.validate(value)) {
+                return Err(CliArgsError::Invalid {
+                    param: param.name.clone(),
+                    value: given[i].clone(),
+                    ty: param.ty,
+                    index: index + i,
+                });
+            }
+            index += given.len();
+            values.push(given);
+        }
+        if args.next().is_some() {
+            return Err(CliArgsError::TooMany(self.params.len()));
+        }
+        Ok(CliArgs {
+            names: self.params.iter().map(|p| p.name.clone()).collect(),
+            values,
+        })
+    }
+}
+
+/// Args that have been validated against a [`CliSignature`]
+///
+/// Values can be accessed by param name, as any type that can be parsed
+/// from a string. The values have already been validated against the
+/// declared types, so accessing them as the declared type never fails.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CliArgs {
+    names: Vec<String>,
+    values: Vec<Vec<String>>,
+}
+
+impl CliArgs {
+    /// Get the value of a param, parsed as `T`
+    ///
+    /// Returns `None` if the param was optional and not given, if there is
+    /// no param with that name, or if the value cannot be parsed as `T`.
+    /// For variadic params, this is the first value.
+    pub fn get<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.get_str(name).and_then(|s| s.parse().ok())
+    }
+    /// Get the value of a param, parsed as `T`, or the given default
+    pub fn get_or<T: FromStr>(&self, name: &str, default: T) -> T {
+        self.get(name).unwrap_or(default)
+    }
+    /// Get the value of an `Entity` param
+    ///
+    /// Entities are usually given using selectors (see [`selector`](crate::selector)).
+    pub fn get_entity(&self, name: &str) -> Option<Entity> {
+        self.get_str(name).and_then(parse_cli_entity)
+    }
+    /// Get the value of a param, as it was typed
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.get_all_str(name).first().map(|s| s.as_str())
+    }
+    /// Get all the values of a variadic param, parsed as `T`
+    ///
+    /// Values that cannot be parsed as `T` are skipped.
+    pub fn get_all<T: FromStr>(&self, name: &str) -> Vec<T> {
+        self.get_all_str(name)
+            .iter()
+            .filter_map(|s| s.parse().ok())
+            .collect()
+    }
+    /// Get all the values of a variadic param, as they were typed
+    pub fn get_all_str(&self, name: &str) -> &[String] {
+        self.names
+            .iter()
+            .position(|n| n == name)
+            .map(|i| self.values[i].as_slice())
+            .unwrap_or(&[])
+    }
+    /// Was a value given for the param?
+    pub fn is_present(&self, name: &str) -> bool {
+        !self.get_all_str(name).is_empty()
+    }
+}
+
+/// A flag or option accepted by a command, in [`CliFlags`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliFlag {
+    /// The long name, used as `--name`
+    pub long: String,
+    /// The short name, used as `-n`
+    pub short: Option<char>,
+    /// Whether it is an option, which takes a value
+    pub takes_value: bool,
+}
+
+impl fmt::Display for CliFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[--{}", self.long)?;
+        if let Some(short) = self.short {
+            write!(f, "|-{}", short)?;
+        }
+        if self.takes_value {
+            write!(f, " <value>")?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// The flags and options accepted by a "console command"
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CliFlags {
+    flags: Vec<CliFlag>,
+}
+
+/// Error when args contain flags not declared in [`CliFlags`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliFlagsError {
+    /// A flag or option that is not accepted by the command
+    Unknown(String),
+    /// An option was given without a value
+    MissingValue(String),
+    /// A flag was given a value
+    UnexpectedValue(String),
+}
+
+impl fmt::Display for CliFlagsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliFlagsError::Unknown(flag) => write!(f, "unknown flag {:?}", flag),
+            CliFlagsError::MissingValue(flag) => write!(f, "missing value for {:?}", flag),
+            CliFlagsError::UnexpectedValue(flag) => write!(f, "flag {:?} takes no value", flag),
+        }
+    }
+}
+
+impl std::error::Error for CliFlagsError {}
+
+impl CliFlags {
+    pub fn new() -> Self {
+        CliFlags::default()
+    }
+    /// Accept a flag, which takes no value
+    pub fn flag(mut self, long: &str, short: Option<char>) -> Self {
+        self.flags.push(CliFlag {
+            long: long.to_owned(),
+            short,
+            takes_value: false,
+        });
+        self
+    }
+    /// Accept an option, which takes a value
+    pub fn option(mut self, long: &str, short: Option<char>) -> Self {
+        self.flags.push(CliFlag {
+            long: long.to_owned(),
+            short,
+            takes_value: true,
+        });
+        self
+    }
+    /// The declared flags and options, in order
+    pub fn iter(&self) -> impl Iterator<Item = &CliFlag> {
+        self.flags.iter()
+    }
+    /// A usage string for a command with these flags, like `spawn [--count|-c <value>] [args...]`
+    pub fn usage(&self, name: &str) -> String {
+        let mut usage = name.to_owned();
+        for flag in &self.flags {
+            usage.push(' ');
+            usage.push_str(&flag.to_string());
+        }
+        usage.push_str(" [args...]");
+        usage
+    }
+    fn find_long(&self, long: &str) -> Option<&CliFlag> {
+        self.flags.iter().find(|flag| flag.long == long)
+    }
+    fn find_short(&self, short: char) -> Option<&CliFlag> {
+        self.flags.iter().find(|flag| flag.short == Some(short))
+    }
+    /// Separate args into positional args, flags and options
+    pub fn parse_args(&self, args: Vec<String>) -> Result<CliInvocation, CliFlagsError> {
+        let mut invocation = CliInvocation::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                invocation.args.extend(args);
+                break;
+            }
+            if let Some(long) = arg.strip_prefix("--") {
+                let (long, value) = match long.split_once('=') {
+                    Some((long, value)) => (long, Some(value.to_owned())),
+                    None => (long, None),
+                };
+                let flag = self
+                    .find_long(long)
+                    .ok_or_else(|| CliFlagsError::Unknown(arg.clone()))?;
+                invocation.set(flag, value, &mut args)?;
+                continue;
+            }
+            let shorts = arg.strip_prefix('-').filter(|s| !s.is_empty());
+            let is_negative_number = shorts
+                .is_some_and(|s| s.starts_with(|c: char| c.is_ascii_digit() || c == '.'))
+                && arg.parse::<f64>().is_ok();
+            let Some(shorts) = shorts.filter(|_| !is_negative_number) else {
+                invocation.args.push(arg);
+                continue;
+            };
+            for (i, short) in shorts.char_indices() {
+                let flag = self
+                    .find_short(short)
+                    .ok_or_else(|| CliFlagsError::Unknown(format!("-{}", short)))?;
+                if flag.takes_value {
+                    // The rest of the arg is the value (`-c5`), if any
+                    let rest = &shorts[i + short.len_utf8()..];
+                    let value = (!rest.is_empty()).then(|| rest.to_owned());
+                    invocation.set(flag, value, &mut args)?;
+                    break;
+                }
+                invocation.set(flag, None, &mut args)?;
+            }
+        }
+        Ok(invocation)
+    }
+}
+
+/// The args of a "console command" registered with [`CliFlags`]
+///
+/// Flags and options are identified by their long name, whether they were
+/// given by their long or short name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CliInvocation {
+    /// The positional args, in order
+    pub args: Vec<String>,
+    /// The flags that were given
+    pub flags: BTreeSet<String>,
+    /// The values of the options that were given (the last one, if repeated)
+    pub options: BTreeMap<String, String>,
+    /// Who or what ran the command, if known (see [`source`](crate::source))
+    pub source: Option<CliSource>,
+}
+
+impl CliInvocation {
+    /// Was the flag (or option) given?
+    pub fn has_flag(&self, long: &str) -> bool {
+        self.flags.contains(long) || self.options.contains_key(long)
+    }
+    /// Get the value of an option, parsed as `T`
+    ///
+    /// Returns `None` if the option was not given, or if the value cannot be
+    /// parsed as `T`.
+    pub fn option<T: FromStr>(&self, long: &str) -> Option<T> {
+        self.option_str(long).and_then(|s| s.parse().ok())
+    }
+    /// Get the value of an option, parsed as `T`, or the given default
+    pub fn option_or<T: FromStr>(&self, long: &str, default: T) -> T {
+        self.option(long).unwrap_or(default)
+    }
+    /// Get the value of an option, as it was typed
+    pub fn option_str(&self, long: &str) -> Option<&str> {
+        self.options.get(long).map(|s| s.as_str())
+    }
+    fn set(
+        &mut self,
+        flag: &CliFlag,
+        value: Option<String>,
+        rest: &mut impl Iterator<Item = String>,
+    ) -> Result<(), CliFlagsError> {
+        match (flag.takes_value, value) {
+            (false, None) => {
+                self.flags.insert(flag.long.clone());
+            }
+            (false, Some(_)) => return Err(CliFlagsError::UnexpectedValue(flag.long.clone())),
+            (true, value) => {
+                let value = value
+                    .or_else(|| rest.next())
+                    .ok_or_else(|| CliFlagsError::MissingValue(flag.long.clone()))?;
+                self.options.insert(flag.long.clone(), value);
+            }
+        }
+        Ok(())
+    }
+}
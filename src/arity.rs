@@ -0,0 +1,157 @@
+//! Checking the args of "console commands" before they run.
+//!
+//! Commands registered with
+//! [`register_clicommand_args`](crate::CliCommandsRegisterExt::register_clicommand_args)
+//! receive whatever args were typed. Instead of validating them in every
+//! command body, declare how many args a command accepts with
+//! [`set_clicommand_arity`](crate::CliCommandsRegisterExt::set_clicommand_arity),
+//! and what each arg must look like with
+//! [`set_clicommand_arg_check`](crate::CliCommandsRegisterExt::set_clicommand_arg_check):
+//!
+//! ```rust,ignore
+//! app.register_clicommand_args("spawn", spawn)
+//!     .set_clicommand_usage("spawn", "spawn <x> <y>")
+//!     .set_clicommand_arity("spawn", 2..=2)
+//!     .set_clicommand_arg_check("spawn", 0, |arg| {
+//!         arg.parse::<f32>().map(drop).map_err(|e| e.to_string())
+//!     });
+//! ```
+//!
+//! If the args do not match, the command does not run, and fails with
+//! [`CliError::InvalidArgs`](crate::error::CliError::InvalidArgs). The
+//! error is reported pointing at the offending arg, followed by the usage
+//! string of the command, if set.
+//!
+//! Commands registered with a signature (see [`args`](crate::args)) are
+//! already checked against it.
+
+use std::ops::{Bound, RangeBounds};
+use std::sync::Arc;
+
+/// A function checking a single arg, returning why it is invalid
+pub type CliArgCheckFn = dyn Fn(&str) -> Result<(), String> + Send + Sync;
+
+/// The args a command accepts
+#[derive(Clone, Default)]
+pub(crate) struct CliArgSpec {
+    pub(crate) min: usize,
+    pub(crate) max: Option<usize>,
+    checks: Vec<(usize, Arc<CliArgCheckFn>)>,
+}
+
+impl CliArgSpec {
+    pub(crate) fn set_arity(&mut self, range: impl RangeBounds<usize>) {
+        self.min = match range.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n + 1,
+            Bound::Unbounded => 0,
+        };
+        self.max = match range.end_bound() {
+            Bound::Included(&n) => Some(n),
+            Bound::Excluded(&n) => Some(n.saturating_sub(1)),
+            Bound::Unbounded => None,
+        };
+    }
+    pub(crate) fn add_check(&mut self, index: usize, check: Arc<CliArgCheckFn>) {
+        self.checks.push((index, check));
+    }
+    /// Check args, returning why they are invalid, and the index of the
+    /// offending arg (`None` if args are missing)
+    pub(crate) fn check(&self, args: &[String]) -> Result<(), (String, Option<usize>)> {
+        if args.len() < self.min {
+            let message = match self.max {
+                Some(max) if max == self.min => {
+                    format!("expected {} args, got {}", max, args.len())
+                }
+                _ => format!("expected at least {} args, got {}", self.min, args.len()),
+            };
+            return Err((message, None));
+        }
+        if let Some(max) = self.max.filter(|&max| args.len() > max) {
+            let message = match max {
+                0 => "expected no args".to_owned(),
+                _ => format!("too many args (expected at most {})", max),
+            };
+            return Err((message, Some(max)));
+        }
+        for (index, check) in &self.checks {
+            let Some(arg) = args.get(*index) else {
+                continue;
+            };
+            if let Err(reason) = check(arg) {
+                let message = format!("invalid arg {:?}: {}", arg, reason);
+                return Err((message, Some(*index)));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|&arg| arg.to_owned()).collect()
+    }
+
+    fn spec(range: impl RangeBounds<usize>) -> CliArgSpec {
+        let mut spec = CliArgSpec::default();
+        spec.set_arity(range);
+        spec
+    }
+
+    #[test]
+    fn range_bounds() {
+        let exact = spec(2..=2);
+        assert_eq!((exact.min, exact.max), (2, Some(2)));
+        let half_open = spec(1..3);
+        assert_eq!((half_open.min, half_open.max), (1, Some(2)));
+        let at_least = spec(1..);
+        assert_eq!((at_least.min, at_least.max), (1, None));
+        let none = spec(..1);
+        assert_eq!((none.min, none.max), (0, Some(0)));
+    }
+
+    #[test]
+    fn arg_counts() {
+        let exact = spec(2..=2);
+        assert_eq!(exact.check(&args(&["1", "2"])), Ok(()));
+        assert_eq!(
+            exact.check(&args(&["1"])),
+            Err(("expected 2 args, got 1".to_owned(), None))
+        );
+        assert_eq!(
+            exact.check(&args(&["1", "2", "3", "4"])),
+            Err(("too many args (expected at most 2)".to_owned(), Some(2)))
+        );
+        assert_eq!(
+            spec(2..).check(&args(&["1"])),
+            Err(("expected at least 2 args, got 1".to_owned(), None))
+        );
+        assert_eq!(
+            spec(..=0).check(&args(&["1"])),
+            Err(("expected no args".to_owned(), Some(0)))
+        );
+    }
+
+    #[test]
+    fn arg_checks() {
+        let mut spec = spec(1..=3);
+        spec.add_check(
+            1,
+            Arc::new(|arg: &str| arg.parse::<f32>().map(drop).map_err(|e| e.to_string())),
+        );
+        assert_eq!(spec.check(&args(&["a"])), Ok(()));
+        assert_eq!(spec.check(&args(&["a", "1.5", "b"])), Ok(()));
+        assert_eq!(
+            spec.check(&args(&["a", "x"])),
+            Err((
+                "invalid arg \"x\": invalid float literal".to_owned(),
+                Some(1)
+            ))
+        );
+        // The count is checked before the args
+        assert_eq!(spec.check(&args(&[])).unwrap_err().1, None);
+    }
+}
@@ -0,0 +1,58 @@
+//! Collecting "console commands" declared with `#[cli_command(auto)]`.
+//!
+//! Requires the `auto-register` cargo feature. Every `fn` marked with
+//! `#[cli_command(auto)]`, in any crate linked into the app, submits its
+//! generated `register_<fn name>` function as a [`CliAutoRegister`], using
+//! the [`inventory`] crate. Adding [`CliAutoRegisterPlugin`] registers all of
+//! them, without a hand-written list:
+//!
+//! ```rust,ignore
+//! /// Spawn some sprites at the given position
+//! #[cli_command(auto)]
+//! fn spawn(x: f32, y: f32, mut commands: Commands) {
+//!     // ...
+//! }
+//!
+//! app.add_plugins((CliPlugin, CliAutoRegisterPlugin));
+//! ```
+
+use bevy::prelude::*;
+
+#[doc(hidden)]
+pub use inventory;
+
+/// A "console command" submitted by `#[cli_command(auto)]`
+pub struct CliAutoRegister {
+    register: fn(&mut App) -> &mut App,
+}
+
+impl CliAutoRegister {
+    /// Used by the code generated by `#[cli_command(auto)]`
+    #[doc(hidden)]
+    pub const fn new(register: fn(&mut App) -> &mut App) -> Self {
+        CliAutoRegister { register }
+    }
+    /// Register the command into the app
+    pub fn register(&self, app: &mut App) {
+        (self.register)(app);
+    }
+    /// All the submitted commands, from every crate linked into the app
+    pub fn iter() -> impl Iterator<Item = &'static CliAutoRegister> {
+        inventory::iter::<CliAutoRegister>.into_iter()
+    }
+}
+
+inventory::collect!(CliAutoRegister);
+
+/// Plugin that registers every command declared with `#[cli_command(auto)]`
+///
+/// See [`auto_register`](crate::auto_register).
+pub struct CliAutoRegisterPlugin;
+
+impl Plugin for CliAutoRegisterPlugin {
+    fn build(&self, app: &mut App) {
+        for command in CliAutoRegister::iter() {
+            command.register(app);
+        }
+    }
+}
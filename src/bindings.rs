@@ -0,0 +1,791 @@
+//! Key bindings: running command strings when keys (or gamepad buttons) are
+//! pressed.
+//!
+//! Requires the `bindings` cargo feature. Add [`CliBindingsPlugin`] (together
+//! with [`CliPlugin`](crate::CliPlugin)). Then, bind keys from code, using
+//! the [`CliBindings`] resource, or from the console:
+//!
+//! ```text
+//! bind F5 "spawn 100 100"
+//! unbind F5
+//! ```
+//!
+//! Keys are named like the [`KeyCode`] variants (`F5`, `KeyA`, `Digit1`,
+//! `Space`), ignoring case. Letters and digits can also be given on their
+//! own (`a`, `1`).
+//!
+//! `bind` on its own prints all bindings as `bind` commands, so they can be
+//! saved to a script and run again later (see [`CliBindings::to_script`]).
+//!
+//! Gamepad axes can be bound to a command template, which is run whenever
+//! the value of the axis changes, with `$axis` replaced by the value:
+//!
+//! ```text
+//! bindaxis RightStickX --deadzone 0.2 --interval 50ms "look_sensitivity $axis"
+//! unbindaxis RightStickX
+//! ```
+//!
+//! Values within the dead zone are treated as `0`. The command is run at most
+//! once per interval, and only for changes of at least
+//! [`min_change`](CliAxisBinding::min_change), so moving a stick does not
+//! flood the game with commands. Axes are named like the [`GamepadAxis`]
+//! variants. If several gamepads are connected, the value furthest from `0`
+//! is used.
+//!
+//! Chords of gamepad buttons can be bound too, for devkits without a
+//! keyboard. Buttons are named like the [`GamepadButton`] variants, joined
+//! with `+`, and the command runs when they are all pressed together, on
+//! the same gamepad. With `--hold`, it runs once they have been held for a
+//! while instead (a long press):
+//!
+//! ```text
+//! bindpad LeftTrigger2+RightTrigger2+South "god"
+//! bindpad Select --hold 1s "screenshot"
+//! unbindpad Select --hold 1s
+//! ```
+//!
+//! The same chord can be bound both with and without `--hold`; a long
+//! press then runs both commands, one when the chord is pressed, and the
+//! other when it has been held long enough.
+//!
+//! With the `console` feature, bindings are ignored while the console is open.
+
+use std::borrow::Cow;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::reflect::{DynamicEnum, DynamicVariant, TypeInfo, Typed, VariantInfo};
+use bevy::utils::{HashMap, Instant};
+
+use crate::output::CliOutput;
+use crate::quota::run_cli_from;
+use crate::sleep::parse_duration;
+use crate::{quote_cli_arg, CliCommandsRegisterExt};
+
+/// Plugin that runs command strings bound to keys and gamepads
+pub struct CliBindingsPlugin;
+
+impl Plugin for CliBindingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CliBindings>();
+        app.add_systems(Update, (run_bindings, run_axis_bindings, run_pad_bindings));
+        app.register_clicommand_args("bind", bind)
+            .set_clicommand_description("bind", "Bind a command to a key, or list bindings")
+            .set_clicommand_usage("bind", "bind [key [command...]]");
+        app.register_clicommand_args("unbind", unbind)
+            .set_clicommand_description("unbind", "Remove the binding of a key")
+            .set_clicommand_usage("unbind", "unbind <key|--all>");
+        app.register_clicommand_args("bindaxis", bindaxis)
+            .set_clicommand_description("bindaxis", "Bind a command template to a gamepad axis")
+            .set_clicommand_usage(
+                "bindaxis",
+                "bindaxis [axis [--deadzone <value>] [--interval <duration>] [template...]]",
+            );
+        app.register_clicommand_args("unbindaxis", unbindaxis)
+            .set_clicommand_description("unbindaxis", "Remove the binding of a gamepad axis")
+            .set_clicommand_usage("unbindaxis", "unbindaxis <axis|--all>");
+        app.register_clicommand_args("bindpad", bindpad)
+            .set_clicommand_description(
+                "bindpad",
+                "Bind a command to a chord of gamepad buttons, or list them",
+            )
+            .set_clicommand_usage(
+                "bindpad",
+                "bindpad [buttons [--hold <duration>] [command...]]",
+            );
+        app.register_clicommand_args("unbindpad", unbindpad)
+            .set_clicommand_description(
+                "unbindpad",
+                "Remove the binding of a chord of gamepad buttons",
+            )
+            .set_clicommand_usage("unbindpad", "unbindpad <buttons [--hold <duration>]|--all>");
+    }
+}
+
+/// A command template bound to a gamepad axis
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliAxisBinding {
+    /// The command string to run, with `$axis` replaced by the value of the axis
+    ///
+    /// If it does not contain `$axis`, the value is appended as the last arg.
+    pub template: String,
+    /// Values closer to `0` than this are treated as `0` (default: `0.1`)
+    pub dead_zone: f32,
+    /// Changes of the value smaller than this are ignored (default: `0.01`)
+    pub min_change: f32,
+    /// The minimum time between runs of the command (default: 100ms)
+    pub min_interval: Duration,
+}
+
+impl CliAxisBinding {
+    pub fn new(template: &str) -> Self {
+        CliAxisBinding {
+            template: template.to_owned(),
+            dead_zone: 0.1,
+            min_change: 0.01,
+            min_interval: Duration::from_millis(100),
+        }
+    }
+    pub fn with_dead_zone(mut self, dead_zone: f32) -> Self {
+        self.dead_zone = dead_zone;
+        self
+    }
+    pub fn with_min_change(mut self, min_change: f32) -> Self {
+        self.min_change = min_change;
+        self
+    }
+    pub fn with_min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+    /// The command string to run for the given value of the axis
+    pub fn expand(&self, value: f32) -> String {
+        let value = format!("{:.3}", value);
+        if self.template.contains("$axis") {
+            self.template.replace("$axis", &value)
+        } else {
+            format!("{} {}", self.template, value)
+        }
+    }
+    /// The binding as a `bindaxis` command
+    fn to_cli(&self, axis: GamepadAxis) -> String {
+        format!(
+            "bindaxis {} --deadzone {} --interval {}ms {}",
+            axis_name(axis),
+            self.dead_zone,
+            self.min_interval.as_millis(),
+            quote_cli_arg(&self.template)
+        )
+    }
+}
+
+/// Gamepad buttons pressed together, optionally held for a while, that a
+/// command string can be bound to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CliPadChord {
+    buttons: Vec<GamepadButton>,
+    hold: Option<Duration>,
+}
+
+impl CliPadChord {
+    /// Press all the buttons together, in any order
+    pub fn new(buttons: impl IntoIterator<Item = GamepadButton>) -> Self {
+        let mut buttons: Vec<_> = buttons.into_iter().collect();
+        buttons.sort_unstable();
+        buttons.dedup();
+        CliPadChord {
+            buttons,
+            hold: None,
+        }
+    }
+    /// Hold the buttons for a while, for a long press
+    pub fn with_hold(mut self, hold: Duration) -> Self {
+        self.hold = Some(hold);
+        self
+    }
+    pub fn buttons(&self) -> &[GamepadButton] {
+        &self.buttons
+    }
+    /// How long the buttons must be held, if this is a long press
+    pub fn hold(&self) -> Option<Duration> {
+        self.hold
+    }
+    /// The name of the buttons, as accepted by [`parse_chord`]
+    pub fn name(&self) -> String {
+        let names: Vec<_> = self.buttons.iter().map(|b| format!("{:?}", b)).collect();
+        names.join("+")
+    }
+    /// The chord as the args of `bindpad` and `unbindpad`
+    fn to_cli(&self) -> String {
+        match self.hold {
+            Some(hold) => format!("{} --hold {}ms", self.name(), hold.as_millis()),
+            None => self.name(),
+        }
+    }
+}
+
+/// When a bound chord started being held
+#[derive(Debug, Clone, Copy, Default)]
+struct CliPadState {
+    since: Option<Instant>,
+    /// Whether the long press already ran its command
+    fired: bool,
+}
+
+/// The last value of a bound axis that a command was run for
+#[derive(Debug, Clone, Copy)]
+struct CliAxisState {
+    value: f32,
+    last_run: Option<Instant>,
+}
+
+/// The command strings bound to keys and chords of gamepad buttons, and the
+/// command templates bound to gamepad axes
+#[derive(Resource, Debug, Clone, Default)]
+pub struct CliBindings {
+    bindings: HashMap<KeyCode, String>,
+    axes: HashMap<GamepadAxis, (CliAxisBinding, CliAxisState)>,
+    pads: HashMap<CliPadChord, (String, CliPadState)>,
+}
+
+impl CliBindings {
+    /// Bind a command string to a key
+    ///
+    /// If the key is already bound, the binding is replaced.
+    pub fn bind(&mut self, key: KeyCode, cli: &str) {
+        self.bindings.insert(key, cli.to_owned());
+    }
+    /// Remove the binding of a key, returning its command string
+    pub fn unbind(&mut self, key: KeyCode) -> Option<String> {
+        self.bindings.remove(&key)
+    }
+    /// Remove all bindings, of keys, axes and gamepad buttons
+    pub fn clear(&mut self) {
+        self.bindings.clear();
+        self.axes.clear();
+        self.pads.clear();
+    }
+    /// Get the command string bound to a key
+    pub fn get(&self, key: KeyCode) -> Option<&str> {
+        self.bindings.get(&key).map(|s| s.as_str())
+    }
+    /// Iterate over all bindings, sorted by key name
+    pub fn iter(&self) -> impl Iterator<Item = (KeyCode, &str)> {
+        let mut bindings: Vec<_> = self
+            .bindings
+            .iter()
+            .map(|(key, cli)| (*key, cli.as_str()))
+            .collect();
+        bindings.sort_by_cached_key(|(key, _)| key_name(*key));
+        bindings.into_iter()
+    }
+    /// Bind a command template to a gamepad axis
+    ///
+    /// If the axis is already bound, the binding is replaced.
+    pub fn bind_axis(&mut self, axis: GamepadAxis, binding: CliAxisBinding) {
+        let state = CliAxisState {
+            value: 0.0,
+            last_run: None,
+        };
+        self.axes.insert(axis, (binding, state));
+    }
+    /// Remove the binding of a gamepad axis
+    pub fn unbind_axis(&mut self, axis: GamepadAxis) -> Option<CliAxisBinding> {
+        self.axes.remove(&axis).map(|(binding, _)| binding)
+    }
+    /// Get the binding of a gamepad axis
+    pub fn get_axis(&self, axis: GamepadAxis) -> Option<&CliAxisBinding> {
+        self.axes.get(&axis).map(|(binding, _)| binding)
+    }
+    /// Iterate over all axis bindings, sorted by axis name
+    pub fn iter_axes(&self) -> impl Iterator<Item = (GamepadAxis, &CliAxisBinding)> {
+        let mut axes: Vec<_> = self
+            .axes
+            .iter()
+            .map(|(axis, (binding, _))| (*axis, binding))
+            .collect();
+        axes.sort_by_cached_key(|(axis, _)| axis_name(*axis));
+        axes.into_iter()
+    }
+    /// Bind a command string to a chord of gamepad buttons
+    ///
+    /// If the chord is already bound, the binding is replaced.
+    pub fn bind_pad(&mut self, chord: CliPadChord, cli: &str) {
+        self.pads
+            .insert(chord, (cli.to_owned(), CliPadState::default()));
+    }
+    /// Remove the binding of a chord of gamepad buttons, returning its command string
+    pub fn unbind_pad(&mut self, chord: &CliPadChord) -> Option<String> {
+        self.pads.remove(chord).map(|(cli, _)| cli)
+    }
+    /// Get the command string bound to a chord of gamepad buttons
+    pub fn get_pad(&self, chord: &CliPadChord) -> Option<&str> {
+        self.pads.get(chord).map(|(cli, _)| cli.as_str())
+    }
+    /// Iterate over all gamepad button bindings, sorted by chord
+    pub fn iter_pads(&self) -> impl Iterator<Item = (&CliPadChord, &str)> {
+        let mut pads: Vec<_> = self
+            .pads
+            .iter()
+            .map(|(chord, (cli, _))| (chord, cli.as_str()))
+            .collect();
+        pads.sort_by_cached_key(|(chord, _)| chord.to_cli());
+        pads.into_iter()
+    }
+    /// All bindings as a script of `bind`, `bindaxis` and `bindpad`
+    /// commands, one per line
+    pub fn to_script(&self) -> String {
+        let keys = self
+            .iter()
+            .map(|(key, cli)| format!("bind {} {}\n", key_name(key), quote_cli_arg(cli)));
+        let axes = self
+            .iter_axes()
+            .map(|(axis, binding)| format!("{}\n", binding.to_cli(axis)));
+        let pads = self
+            .iter_pads()
+            .map(|(chord, cli)| format!("bindpad {} {}\n", chord.to_cli(), quote_cli_arg(cli)));
+        keys.chain(axes).chain(pads).collect()
+    }
+}
+
+/// The name of a key, as accepted by [`parse_key`]
+pub fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+/// Parse the name of a key (see the [module docs](self))
+pub fn parse_key(s: &str) -> Option<KeyCode> {
+    parse_unit_variant(&[s.to_owned(), format!("Key{}", s), format!("Digit{}", s)])
+}
+
+/// The name of a gamepad axis, as accepted by [`parse_axis`]
+pub fn axis_name(axis: GamepadAxis) -> String {
+    format!("{:?}", axis)
+}
+
+/// Parse the name of a gamepad axis (see the [module docs](self))
+pub fn parse_axis(s: &str) -> Option<GamepadAxis> {
+    parse_unit_variant(&[s.to_owned()])
+}
+
+/// Parse the names of gamepad buttons joined with `+`, like `Select+South`
+/// (see the [module docs](self))
+pub fn parse_chord(s: &str) -> Option<CliPadChord> {
+    let buttons = s
+        .split('+')
+        .map(|button| parse_unit_variant::<GamepadButton>(&[button.to_owned()]))
+        .collect::<Option<Vec<_>>>()?;
+    Some(CliPadChord::new(buttons))
+}
+
+/// Find the first unit variant of an enum named like one of the candidates,
+/// ignoring case
+fn parse_unit_variant<T: Typed + FromReflect>(candidates: &[String]) -> Option<T> {
+    let TypeInfo::Enum(info) = T::type_info() else {
+        return None;
+    };
+    for candidate in candidates {
+        let variant = info.iter().find(|variant| {
+            matches!(variant, VariantInfo::Unit(_))
+                && variant.name().eq_ignore_ascii_case(candidate)
+        });
+        if let Some(variant) = variant {
+            let dynamic = DynamicEnum::new(variant.name(), DynamicVariant::Unit);
+            return T::from_reflect(&dynamic);
+        }
+    }
+    None
+}
+
+fn is_console_open(world: &World) -> bool {
+    #[cfg(feature = "console")]
+    if world
+        .get_resource::<crate::console::CliConsoleState>()
+        .is_some_and(|state| state.is_open())
+    {
+        return true;
+    }
+    let _ = world;
+    false
+}
+
+fn run_bindings(world: &mut World) {
+    if is_console_open(world) {
+        return;
+    }
+    let (Some(input), Some(bindings)) = (
+        world.get_resource::<ButtonInput<KeyCode>>(),
+        world.get_resource::<CliBindings>(),
+    ) else {
+        return;
+    };
+    let lines: Vec<String> = input
+        .get_just_pressed()
+        .filter_map(|key| bindings.get(*key))
+        .map(|cli| cli.to_owned())
+        .collect();
+    for cli in lines {
+        run_cli_from(world, "bindings", &cli);
+    }
+}
+
+fn run_axis_bindings(world: &mut World, mut q_gamepads: Local<QueryState<&Gamepad>>) {
+    if is_console_open(world) {
+        return;
+    }
+    let Some(bindings) = world.get_resource::<CliBindings>() else {
+        return;
+    };
+    if bindings.axes.is_empty() {
+        return;
+    }
+    let axes: Vec<GamepadAxis> = bindings.axes.keys().copied().collect();
+    let gamepads: Vec<&Gamepad> = q_gamepads.iter(world).collect();
+    let values: Vec<(GamepadAxis, f32)> = axes
+        .into_iter()
+        .map(|axis| {
+            let value = gamepads
+                .iter()
+                .filter_map(|gamepad| gamepad.get(axis))
+                .fold(0.0f32, |a, b| if b.abs() > a.abs() { b } else { a });
+            (axis, value)
+        })
+        .collect();
+    let now = Instant::now();
+    let mut lines = Vec::new();
+    let mut bindings = world.resource_mut::<CliBindings>();
+    for (axis, value) in values {
+        let Some((binding, state)) = bindings.axes.get_mut(&axis) else {
+            continue;
+        };
+        let value = if value.abs() < binding.dead_zone {
+            0.0
+        } else {
+            value
+        };
+        // Always report returning to rest, even if the change is small
+        let changed = (value - state.value).abs() >= binding.min_change
+            || (value == 0.0 && state.value != 0.0);
+        let too_soon = state
+            .last_run
+            .is_some_and(|last| now.duration_since(last) < binding.min_interval);
+        if changed && !too_soon {
+            state.value = value;
+            state.last_run = Some(now);
+            lines.push(binding.expand(value));
+        }
+    }
+    for cli in lines {
+        run_cli_from(world, "bindings", &cli);
+    }
+}
+
+fn run_pad_bindings(world: &mut World, mut q_gamepads: Local<QueryState<&Gamepad>>) {
+    if is_console_open(world) {
+        return;
+    }
+    let Some(bindings) = world.get_resource::<CliBindings>() else {
+        return;
+    };
+    if bindings.pads.is_empty() {
+        return;
+    }
+    let gamepads: Vec<&Gamepad> = q_gamepads.iter(world).collect();
+    // Whether each chord is held on some gamepad, and whether it was just completed
+    let chords: Vec<(CliPadChord, bool, bool)> = bindings
+        .pads
+        .keys()
+        .map(|chord| {
+            let buttons = || chord.buttons.iter().copied();
+            let held: Vec<&&Gamepad> = gamepads
+                .iter()
+                .filter(|gamepad| !chord.buttons.is_empty() && gamepad.all_pressed(buttons()))
+                .collect();
+            let pressed = held
+                .iter()
+                .any(|gamepad| buttons().any(|button| gamepad.just_pressed(button)));
+            (chord.clone(), !held.is_empty(), pressed)
+        })
+        .collect();
+    let now = Instant::now();
+    let mut lines = Vec::new();
+    let mut bindings = world.resource_mut::<CliBindings>();
+    for (chord, held, pressed) in chords {
+        let Some((cli, state)) = bindings.pads.get_mut(&chord) else {
+            continue;
+        };
+        if !held {
+            *state = CliPadState::default();
+            continue;
+        }
+        match chord.hold {
+            None if pressed => lines.push(cli.clone()),
+            None => {}
+            Some(hold) => {
+                let since = *state.since.get_or_insert(now);
+                if !state.fired && now.duration_since(since) >= hold {
+                    state.fired = true;
+                    lines.push(cli.clone());
+                }
+            }
+        }
+    }
+    for cli in lines {
+        run_cli_from(world, "bindings", &cli);
+    }
+}
+
+/// `bind [key [command...]]`: bind a command string to a key, or show bindings
+fn bind(In(args): In<Vec<String>>, mut bindings: ResMut<CliBindings>, mut output: CliOutput) {
+    let Some((key_arg, command)) = args.split_first() else {
+        output.println(bindings.to_script().trim_end());
+        return;
+    };
+    let Some(key) = parse_key(key_arg) else {
+        output.fail(format!("Unknown key {:?}!", key_arg));
+        return;
+    };
+    match command {
+        [] => match bindings.get(key) {
+            Some(cli) => output.println(format!("bind {} {}", key_name(key), quote_cli_arg(cli))),
+            None => output.println(format!("{} is not bound", key_name(key))),
+        },
+        // `bind F5 "spawn 100 100"`
+        [cli] => bindings.bind(key, cli),
+        // `bind F5 spawn 100 100`
+        words => {
+            let words: Vec<_> = words.iter().map(|word| quote_cli_arg(word)).collect();
+            bindings.bind(key, &words.join(" "));
+        }
+    }
+}
+
+/// `unbind <key|--all>`: remove the binding of a key, or all bindings
+fn unbind(In(args): In<Vec<String>>, mut bindings: ResMut<CliBindings>, mut output: CliOutput) {
+    let [key_arg] = args.as_slice() else {
+        output.fail("Usage: unbind <key|--all>");
+        return;
+    };
+    if key_arg == "--all" {
+        bindings.clear();
+        return;
+    }
+    let Some(key) = parse_key(key_arg) else {
+        output.fail(format!("Unknown key {:?}!", key_arg));
+        return;
+    };
+    if bindings.unbind(key).is_none() {
+        output.fail(format!("{} is not bound!", key_name(key)));
+    }
+}
+
+/// `bindaxis [axis [--deadzone <value>] [--interval <duration>] [template...]]`:
+/// bind a command template to a gamepad axis, or show axis bindings
+fn bindaxis(In(args): In<Vec<String>>, mut bindings: ResMut<CliBindings>, mut output: CliOutput) {
+    let Some((axis_arg, mut rest)) = args.split_first() else {
+        for (axis, binding) in bindings.iter_axes() {
+            output.println(binding.to_cli(axis));
+        }
+        return;
+    };
+    let Some(axis) = parse_axis(axis_arg) else {
+        output.fail(format!("Unknown gamepad axis {:?}!", axis_arg));
+        return;
+    };
+    let mut binding = bindings
+        .get_axis(axis)
+        .cloned()
+        .unwrap_or_else(|| CliAxisBinding::new(""));
+    while let [flag, value, tail @ ..] = rest {
+        match flag.as_str() {
+            "--deadzone" => match value.parse::<f32>() {
+                Ok(dead_zone) if dead_zone >= 0.0 => binding.dead_zone = dead_zone,
+                _ => {
+                    output.fail(format!("Invalid dead zone {:?}!", value));
+                    return;
+                }
+            },
+            "--interval" => match parse_duration(value) {
+                Some(interval) => binding.min_interval = interval,
+                None => {
+                    output.fail(format!("Invalid interval {:?}!", value));
+                    return;
+                }
+            },
+            _ => break,
+        }
+        rest = tail;
+    }
+    match rest {
+        [] if binding.template.is_empty() => {
+            output.println(format!("{} is not bound", axis_name(axis)));
+            return;
+        }
+        [] => {}
+        // `bindaxis RightStickX "look_sensitivity $axis"`
+        [template] => binding.template = template.clone(),
+        // `bindaxis RightStickX look_sensitivity $axis`
+        words => {
+            let words: Vec<_> = words
+                .iter()
+                .map(|word| {
+                    // Keep `$axis` as it is, for it to be replaced by the value
+                    let parts: Vec<_> = word
+                        .split("$axis")
+                        .map(|part| match part {
+                            "" => Cow::Borrowed(""),
+                            part => quote_cli_arg(part),
+                        })
+                        .collect();
+                    parts.join("$axis")
+                })
+                .collect();
+            binding.template = words.join(" ");
+        }
+    }
+    if args.len() == 1 {
+        output.println(binding.to_cli(axis));
+        return;
+    }
+    bindings.bind_axis(axis, binding);
+}
+
+/// `unbindaxis <axis|--all>`: remove the binding of a gamepad axis, or all axis bindings
+fn unbindaxis(In(args): In<Vec<String>>, mut bindings: ResMut<CliBindings>, mut output: CliOutput) {
+    let [axis_arg] = args.as_slice() else {
+        output.fail("Usage: unbindaxis <axis|--all>");
+        return;
+    };
+    if axis_arg == "--all" {
+        bindings.axes.clear();
+        return;
+    }
+    let Some(axis) = parse_axis(axis_arg) else {
+        output.fail(format!("Unknown gamepad axis {:?}!", axis_arg));
+        return;
+    };
+    if bindings.unbind_axis(axis).is_none() {
+        output.fail(format!("{} is not bound!", axis_name(axis)));
+    }
+}
+
+/// Parse `<buttons> [--hold <duration>]`, for `bindpad` and `unbindpad`,
+/// returning the chord and the rest of the args
+fn parse_chord_args<'a>(
+    args: &'a [String],
+    output: &mut CliOutput,
+) -> Option<(CliPadChord, &'a [String])> {
+    let (chord_arg, rest) = args.split_first()?;
+    let Some(mut chord) = parse_chord(chord_arg) else {
+        output.fail(format!("Unknown gamepad buttons {:?}!", chord_arg));
+        return None;
+    };
+    match rest {
+        [flag, duration, rest @ ..] if flag == "--hold" => match parse_duration(duration) {
+            Some(hold) => {
+                chord = chord.with_hold(hold);
+                Some((chord, rest))
+            }
+            None => {
+                output.fail(format!("Invalid duration {:?}!", duration));
+                None
+            }
+        },
+        rest => Some((chord, rest)),
+    }
+}
+
+/// `bindpad [buttons [--hold <duration>] [command...]]`: bind a command
+/// string to a chord of gamepad buttons, or show gamepad button bindings
+fn bindpad(In(args): In<Vec<String>>, mut bindings: ResMut<CliBindings>, mut output: CliOutput) {
+    if args.is_empty() {
+        for (chord, cli) in bindings.iter_pads() {
+            output.println(format!("bindpad {} {}", chord.to_cli(), quote_cli_arg(cli)));
+        }
+        return;
+    }
+    let Some((chord, command)) = parse_chord_args(&args, &mut output) else {
+        return;
+    };
+    match command {
+        [] => match bindings.get_pad(&chord) {
+            Some(cli) => {
+                output.println(format!("bindpad {} {}", chord.to_cli(), quote_cli_arg(cli)))
+            }
+            None => output.println(format!("{} is not bound", chord.to_cli())),
+        },
+        // `bindpad Select+South "spawn 100 100"`
+        [cli] => bindings.bind_pad(chord, cli),
+        // `bindpad Select+South spawn 100 100`
+        words => {
+            let words: Vec<_> = words.iter().map(|word| quote_cli_arg(word)).collect();
+            bindings.bind_pad(chord, &words.join(" "));
+        }
+    }
+}
+
+/// `unbindpad <buttons [--hold <duration>]|--all>`: remove the binding of a
+/// chord of gamepad buttons, or all gamepad button bindings
+fn unbindpad(In(args): In<Vec<String>>, mut bindings: ResMut<CliBindings>, mut output: CliOutput) {
+    if let [all] = args.as_slice() {
+        if all == "--all" {
+            bindings.pads.clear();
+            return;
+        }
+    }
+    let Some((chord, rest)) = parse_chord_args(&args, &mut output) else {
+        if args.is_empty() {
+            output.fail("Usage: unbindpad <buttons [--hold <duration>]|--all>");
+        }
+        return;
+    };
+    if !rest.is_empty() {
+        output.fail("Usage: unbindpad <buttons [--hold <duration>]|--all>");
+        return;
+    }
+    if bindings.unbind_pad(&chord).is_none() {
+        output.fail(format!("{} is not bound!", chord.to_cli()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CliCommandsRunExt, CliPlugin};
+
+    #[derive(Resource, Default)]
+    struct Marks(Vec<String>);
+
+    /// Change the buttons of a gamepad, and run a frame
+    fn update(app: &mut App, gamepad: Entity, f: impl FnOnce(&mut ButtonInput<GamepadButton>)) {
+        let mut gamepad = app.world_mut().get_mut::<Gamepad>(gamepad).unwrap();
+        gamepad.digital_mut().clear();
+        f(gamepad.digital_mut());
+        app.update();
+    }
+
+    #[test]
+    fn gamepad_chords() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin, CliBindingsPlugin));
+        app.init_resource::<Marks>();
+        app.register_clicommand_args(
+            "mark",
+            |In(args): In<Vec<String>>, mut marks: ResMut<Marks>| marks.0.extend(args),
+        );
+        let marks = |app: &App| app.world().resource::<Marks>().0.clone();
+        let gamepad = app.world_mut().spawn(Gamepad::default()).id();
+        app.world_mut()
+            .run_cli("bindpad south+Select mark chord; bindpad Select --hold 20ms mark long");
+        assert_eq!(
+            app.world().resource::<CliBindings>().to_script(),
+            "bindpad Select --hold 20ms \"mark long\"\nbindpad South+Select \"mark chord\"\n"
+        );
+
+        // Pressing one button of the chord does nothing
+        update(&mut app, gamepad, |input| input.press(GamepadButton::South));
+        assert!(marks(&app).is_empty());
+        // Completing the chord runs it once
+        update(&mut app, gamepad, |input| {
+            input.press(GamepadButton::Select)
+        });
+        update(&mut app, gamepad, |_| {});
+        assert_eq!(marks(&app), ["chord"]);
+
+        // Holding runs the long press once
+        std::thread::sleep(Duration::from_millis(30));
+        update(&mut app, gamepad, |_| {});
+        update(&mut app, gamepad, |_| {});
+        assert_eq!(marks(&app), ["chord", "long"]);
+        // Releasing and pressing again starts over
+        update(&mut app, gamepad, |input| input.release_all());
+        update(&mut app, gamepad, |input| {
+            input.press(GamepadButton::Select)
+        });
+        assert_eq!(marks(&app).len(), 2);
+
+        app.world_mut().run_cli("unbindpad Select --hold 20ms");
+        assert_eq!(app.world().resource::<CliBindings>().iter_pads().count(), 1);
+        assert!(parse_chord("Select+Nope").is_none());
+    }
+}
@@ -0,0 +1,430 @@
+//! Pausing the game when a condition becomes true, for debugging.
+//!
+//! Requires the `breakpoint` cargo feature (enabled by default), which also
+//! enables `stepper`.
+//!
+//! `break when <expression> <op> <value> [--console]` checks a condition at
+//! the end of every frame, and pauses virtual time when it becomes true,
+//! to catch transient states without attaching a debugger:
+//!
+//! ```text
+//! > break when player.health < 10 --console
+//! Breakpoint 1 added
+//! Breakpoint 1 hit: player.health < 10 (player.health = 7)
+//! > step 1
+//! > break continue
+//! ```
+//!
+//! The expression is a console variable (see [`vars`](crate::vars)), or
+//! else a command string, whose output is its value (like
+//! `break when count enemies == 0`). The operators are `==`, `!=`, `<`, `<=`,
+//! `>` and `>=`. If both sides are numbers, they are compared as numbers;
+//! otherwise, `==` and `!=` compare them as text, and the other operators
+//! are never true.
+//!
+//! When a breakpoint is hit, systems driven by virtual time stop, and
+//! `step` can advance the game frame by frame (see
+//! [`stepper`](crate::stepper)). A breakpoint is hit again only after its
+//! condition has been false, so continuing does not pause again right away.
+//! With `--console`, the console is opened too.
+//!
+//!  - `break [list]`: list the breakpoints, with their ids
+//!  - `break remove <id|all>`: remove breakpoints
+//!  - `break continue`: unpause virtual time
+//!
+//! Conditions are checked with the source of the command string that added
+//! them (see [`quota`](crate::quota)), only while virtual time is running.
+
+use std::fmt;
+
+use bevy::prelude::*;
+
+use crate::output::{cli_println_severity, evaluate_cli, CliOutput, CliSeverity};
+use crate::quota::with_cli_source;
+use crate::source::CliSource;
+use crate::table::{CliAlign, CliTable};
+use crate::{quote_cli_arg, CliCommandsRegisterExt};
+
+const USAGE: &str =
+    "Usage: break [list | when <expression> <op> <value> [--console] | remove <id|all> | continue]";
+
+/// How a [`CliBreakpoint`] compares the value of its expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CliCompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CliCompareOp {
+    /// Parse an operator, like `<=`
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "==" => Some(CliCompareOp::Eq),
+            "!=" => Some(CliCompareOp::Ne),
+            "<" => Some(CliCompareOp::Lt),
+            "<=" => Some(CliCompareOp::Le),
+            ">" => Some(CliCompareOp::Gt),
+            ">=" => Some(CliCompareOp::Ge),
+            _ => None,
+        }
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CliCompareOp::Eq => "==",
+            CliCompareOp::Ne => "!=",
+            CliCompareOp::Lt => "<",
+            CliCompareOp::Le => "<=",
+            CliCompareOp::Gt => ">",
+            CliCompareOp::Ge => ">=",
+        }
+    }
+    /// Compare two values, as numbers if they both are
+    pub fn compare(&self, left: &str, right: &str) -> bool {
+        if let (Ok(left), Ok(right)) = (left.parse::<f64>(), right.parse::<f64>()) {
+            return match self {
+                CliCompareOp::Eq => left == right,
+                CliCompareOp::Ne => left != right,
+                CliCompareOp::Lt => left < right,
+                CliCompareOp::Le => left <= right,
+                CliCompareOp::Gt => left > right,
+                CliCompareOp::Ge => left >= right,
+            };
+        }
+        match self {
+            CliCompareOp::Eq => left == right,
+            CliCompareOp::Ne => left != right,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for CliCompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A condition that pauses virtual time when it becomes true
+#[derive(Debug, Clone)]
+pub struct CliBreakpoint {
+    pub id: u64,
+    /// The console variable, or command string, whose value is compared
+    pub expression: String,
+    pub op: CliCompareOp,
+    pub value: String,
+    /// Whether to open the console when it is hit
+    pub open_console: bool,
+    /// How many times it has been hit
+    pub hits: u64,
+    source: Option<CliSource>,
+    /// Whether the condition was true when last checked
+    tripped: bool,
+}
+
+impl CliBreakpoint {
+    /// The condition, like `player.health < 10`
+    pub fn condition(&self) -> String {
+        format!(
+            "{} {} {}",
+            self.expression,
+            self.op,
+            quote_cli_arg(&self.value)
+        )
+    }
+}
+
+/// The breakpoints checked at the end of every frame
+///
+/// Requires [`CliPlugin`](crate::CliPlugin).
+#[derive(Resource, Debug, Default)]
+pub struct CliBreakpoints {
+    breakpoints: Vec<CliBreakpoint>,
+    next_id: u64,
+}
+
+impl CliBreakpoints {
+    /// Pause virtual time when the value of an expression compares to a
+    /// value, returning the id of the breakpoint
+    pub fn add(&mut self, expression: &str, op: CliCompareOp, value: &str) -> u64 {
+        self.push(expression, op, value, false, None)
+    }
+    /// Like [`add`](Self::add), also opening the console when it is hit
+    pub fn add_with_console(&mut self, expression: &str, op: CliCompareOp, value: &str) -> u64 {
+        self.push(expression, op, value, true, None)
+    }
+    fn push(
+        &mut self,
+        expression: &str,
+        op: CliCompareOp,
+        value: &str,
+        open_console: bool,
+        source: Option<CliSource>,
+    ) -> u64 {
+        self.next_id += 1;
+        self.breakpoints.push(CliBreakpoint {
+            id: self.next_id,
+            expression: expression.to_owned(),
+            op,
+            value: value.to_owned(),
+            open_console,
+            hits: 0,
+            source,
+            tripped: false,
+        });
+        self.next_id
+    }
+    /// Remove a breakpoint, returning whether it existed
+    pub fn remove(&mut self, id: u64) -> bool {
+        let len = self.breakpoints.len();
+        self.breakpoints.retain(|breakpoint| breakpoint.id != id);
+        self.breakpoints.len() != len
+    }
+    /// Remove all breakpoints
+    pub fn clear(&mut self) {
+        self.breakpoints.clear();
+    }
+    /// Iterate over the breakpoints, in the order they were added
+    pub fn iter(&self) -> impl Iterator<Item = &CliBreakpoint> {
+        self.breakpoints.iter()
+    }
+    pub fn len(&self) -> usize {
+        self.breakpoints.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.breakpoints.is_empty()
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliBreakpoints>();
+    app.add_systems(Last, check_breakpoints);
+    app.register_clicommand_args("break", break_command)
+        .set_clicommand_description("break", "Pause the game when a condition becomes true")
+        .set_clicommand_usage(
+            "break",
+            "break [list | when <expression> <op> <value> [--console] | remove <id|all> | continue]",
+        );
+}
+
+fn check_breakpoints(world: &mut World) {
+    let running = world
+        .get_resource::<Time<Virtual>>()
+        .is_some_and(|time| !time.is_paused());
+    let breakpoints = world.resource::<CliBreakpoints>();
+    if !running || breakpoints.is_empty() {
+        return;
+    }
+    let checks: Vec<_> = breakpoints
+        .iter()
+        .map(|breakpoint| {
+            (
+                breakpoint.id,
+                breakpoint.expression.clone(),
+                breakpoint.source.clone(),
+            )
+        })
+        .collect();
+    let mut hits = Vec::new();
+    let mut open_console = false;
+    for (id, expression, source) in checks {
+        let value = match source {
+            Some(source) => {
+                with_cli_source(world, source, |world| evaluate_cli(world, &expression))
+            }
+            None => evaluate_cli(world, &expression),
+        };
+        let mut breakpoints = world.resource_mut::<CliBreakpoints>();
+        let Some(breakpoint) = breakpoints.breakpoints.iter_mut().find(|b| b.id == id) else {
+            continue;
+        };
+        let tripped = value
+            .as_ref()
+            .is_ok_and(|value| breakpoint.op.compare(value.trim(), &breakpoint.value));
+        if tripped && !breakpoint.tripped {
+            breakpoint.hits += 1;
+            open_console |= breakpoint.open_console;
+            hits.push(format!(
+                "Breakpoint {} hit: {} ({} = {})",
+                id,
+                breakpoint.condition(),
+                expression,
+                value.unwrap_or_default().trim()
+            ));
+        }
+        breakpoint.tripped = tripped;
+    }
+    if hits.is_empty() {
+        return;
+    }
+    if let Some(mut time) = world.get_resource_mut::<Time<Virtual>>() {
+        time.pause();
+    }
+    for hit in hits {
+        cli_println_severity(world, CliSeverity::Warn, hit);
+    }
+    if open_console {
+        #[cfg(feature = "console")]
+        if let Some(mut state) = world.get_resource_mut::<crate::console::CliConsoleState>() {
+            state.open();
+        }
+        #[cfg(feature = "egui")]
+        if let Some(mut window) = world.get_resource_mut::<crate::egui::CliConsoleWindow>() {
+            window.open();
+        }
+    }
+}
+
+/// `break [list | when <expression> <op> <value> [--console] | remove <id|all> | continue]`:
+/// manage breakpoints
+fn break_command(
+    In(args): In<Vec<String>>,
+    mut breakpoints: ResMut<CliBreakpoints>,
+    time: Option<ResMut<Time<Virtual>>>,
+    mut output: CliOutput,
+) {
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    match args.as_slice() {
+        [] | ["list"] => {
+            if breakpoints.is_empty() {
+                output.println("No breakpoints");
+                return;
+            }
+            let mut table = CliTable::new()
+                .with_column("ID", CliAlign::Right)
+                .with_column("CONDITION", CliAlign::Left)
+                .with_column("HITS", CliAlign::Right);
+            for breakpoint in breakpoints.iter() {
+                table.add_row([
+                    breakpoint.id.to_string(),
+                    breakpoint.condition(),
+                    breakpoint.hits.to_string(),
+                ]);
+            }
+            output.println(table.to_string());
+        }
+        ["when", rest @ ..] => {
+            let (rest, open_console) = match rest {
+                [rest @ .., "--console"] => (rest, true),
+                rest => (rest, false),
+            };
+            // `break when count enemies == 0`
+            let Some(i) = rest
+                .iter()
+                .position(|arg| CliCompareOp::parse(arg).is_some())
+            else {
+                output.fail(USAGE);
+                return;
+            };
+            let (words, [op, value]) = (&rest[..i], &rest[i..]) else {
+                output.fail(USAGE);
+                return;
+            };
+            let expression = match words {
+                [] => {
+                    output.fail(USAGE);
+                    return;
+                }
+                [expression] => expression.to_string(),
+                words => {
+                    let words: Vec<_> = words.iter().map(|word| quote_cli_arg(word)).collect();
+                    words.join(" ")
+                }
+            };
+            let op = CliCompareOp::parse(op).unwrap_or(CliCompareOp::Eq);
+            let source = output.source().cloned();
+            let id = breakpoints.push(&expression, op, value, open_console, source);
+            output.println(format!("Breakpoint {} added", id));
+        }
+        ["remove", "all"] => breakpoints.clear(),
+        ["remove", id] => {
+            let Ok(id) = id.parse::<u64>() else {
+                output.fail(USAGE);
+                return;
+            };
+            if !breakpoints.remove(id) {
+                output.fail(format!("No breakpoint with id {}!", id));
+            }
+        }
+        ["continue"] => match time {
+            Some(mut time) => time.unpause(),
+            None => output.fail("There is no virtual time to unpause (TimePlugin)!"),
+        },
+        _ => output.fail(USAGE),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{cli_println, CliOutputLog};
+    use crate::{CliCommandsRunExt, CliPlugin};
+
+    #[derive(Resource, Default)]
+    struct Health(i32);
+
+    fn set_health(app: &mut App, health: i32) -> bool {
+        app.world_mut().resource_mut::<Health>().0 = health;
+        app.update();
+        app.world().resource::<Time<Virtual>>().is_paused()
+    }
+
+    #[test]
+    fn breakpoints() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.insert_resource(Health(100));
+        app.register_clicommand_args("health", |In(_): In<Vec<String>>, world: &mut World| {
+            let health = world.resource::<Health>().0;
+            cli_println(world, health.to_string());
+        });
+        app.world_mut().run_cli("break when health of player < 10");
+        assert_eq!(
+            app.world()
+                .resource::<CliBreakpoints>()
+                .iter()
+                .next()
+                .unwrap()
+                .condition(),
+            "health of player < 10"
+        );
+
+        assert!(!set_health(&mut app, 50));
+        assert!(set_health(&mut app, 5));
+        let log = app.world().resource::<CliOutputLog>();
+        assert!(log
+            .iter()
+            .any(|line| line.text
+                == "Breakpoint 1 hit: health of player < 10 (health of player = 5)"));
+
+        // Still true after continuing, so not hit again
+        app.world_mut().run_cli("break continue");
+        assert!(!set_health(&mut app, 4));
+        assert!(!set_health(&mut app, 20));
+        assert!(set_health(&mut app, 3));
+        assert_eq!(
+            app.world()
+                .resource::<CliBreakpoints>()
+                .iter()
+                .next()
+                .unwrap()
+                .hits,
+            2
+        );
+
+        app.world_mut().run_cli("break remove 1; break continue");
+        assert!(!set_health(&mut app, 20));
+        assert!(!set_health(&mut app, 1));
+    }
+
+    #[test]
+    fn compare() {
+        assert!(CliCompareOp::Lt.compare("9.5", "10"));
+        assert!(!CliCompareOp::Lt.compare("abc", "abd"));
+        assert!(CliCompareOp::Eq.compare("1.0", "1"));
+        assert!(CliCompareOp::Ne.compare("idle", "running"));
+    }
+}
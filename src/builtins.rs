@@ -0,0 +1,736 @@
+//! "Console commands" provided by the crate itself.
+//!
+//! These are registered by [`CliPlugin`](crate::CliPlugin). You can replace any
+//! of them by registering your own command with the same name afterwards.
+//!
+//! Only `help`, `queue` and `return` are always registered. The generic
+//! commands (`list`, `echo`, `alias`, `unregister`, `retry`, `assert`...)
+//! require the `builtin-commands` cargo feature, which is enabled by default.
+//!
+//! The commands that change what other command strings do (`bindcmd`,
+//! `alias`, `unalias` and `unregister`) are [`Dev`](CliPermission::Dev)
+//! commands (see [`permissions`](crate::permissions)).
+
+#[cfg(feature = "builtin-commands")]
+use std::collections::BTreeMap;
+#[cfg(feature = "builtin-commands")]
+use std::time::Duration;
+
+use bevy::prelude::*;
+#[cfg(feature = "builtin-commands")]
+use bevy::utils::Instant;
+
+use crate::anchor::CliAnchorQueue;
+use crate::fixed::CliFixedQueue;
+#[cfg(feature = "builtin-commands")]
+use crate::glob::glob_match;
+use crate::localize::CliLocalizer;
+#[cfg(feature = "builtin-commands")]
+use crate::namespace::{in_namespace, owner_name};
+use crate::output::CliOutput;
+#[cfg(feature = "builtin-commands")]
+use crate::output::{cli_error, cli_println, CliCommandsOutputExt, CliOutputLog};
+use crate::permissions::CliPermission;
+use crate::queue::CliQueue;
+use crate::session::CliSession;
+use crate::sleep::CliSleepQueue;
+#[cfg(feature = "builtin-commands")]
+use crate::status::cli_fail;
+use crate::status::CliStatus;
+use crate::table::{CliAlign, CliTable};
+#[cfg(all(feature = "builtin-commands", feature = "cvars"))]
+use crate::vars::CliVars;
+use crate::{failln, outln, CliCommands, CliCommandsRegisterExt};
+#[cfg(feature = "builtin-commands")]
+use crate::{quote_cli_arg, CliCommandsRunExt};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_clicommand_args("help", help)
+        .set_clicommand_description("help", "List commands, or show help for one")
+        .set_clicommand_usage("help", "help [command|category|--by-owner]")
+        .set_clicommand_paged("help");
+    app.register_clicommand_args("queue", queue)
+        .set_clicommand_description("queue", "Manage commands waiting to be run")
+        .set_clicommand_usage("queue", "queue [list|clear|pause|resume]");
+    app.register_clicommand_args("return", return_value)
+        .set_clicommand_description("return", "End the current script or procedure")
+        .set_clicommand_usage("return", "return [value...]");
+    #[cfg(feature = "builtin-commands")]
+    generic_plugin(app);
+}
+
+/// The generic built-in commands, with the `builtin-commands` feature
+#[cfg(feature = "builtin-commands")]
+fn generic_plugin(app: &mut App) {
+    app.init_resource::<CliProfileRun>();
+    app.add_systems(Last, sample_profile);
+    app.register_clicommand_args("commands", crate::manifest::commands)
+        .set_clicommand_description("commands", "Describe the registered commands")
+        .set_clicommand_usage("commands", "commands [--json]");
+    app.register_clicommand_args("bindcmd", bindcmd)
+        .set_clicommand_description("bindcmd", "Create a new command from a template")
+        .set_clicommand_usage("bindcmd", "bindcmd <name> <template...>")
+        .set_clicommand_permission("bindcmd", CliPermission::Dev);
+    app.register_clicommand_args("alias", alias)
+        .set_clicommand_description("alias", "List, show or define aliases")
+        .set_clicommand_usage("alias", "alias [name [expansion...]]")
+        .set_clicommand_permission("alias", CliPermission::Dev);
+    app.register_clicommand_args("unalias", unalias)
+        .set_clicommand_description("unalias", "Remove an alias")
+        .set_clicommand_usage("unalias", "unalias <name>")
+        .set_clicommand_permission("unalias", CliPermission::Dev);
+    app.register_clicommand_args("list", list)
+        .set_clicommand_description("list", "List commands, aliases or console variables")
+        .set_clicommand_usage("list", "list <commands|aliases|cvars> [pattern]");
+    app.register_clicommand_noargs("retry", retry)
+        .set_clicommand_description("retry", "Run the last failed command again");
+    app.register_clicommand_args("fix", fix)
+        .set_clicommand_description("fix", "Run a corrected version of the last failed command")
+        .set_clicommand_usage("fix", "fix <command...>");
+    app.register_clicommand_args("profile", profile)
+        .set_clicommand_description("profile", "Measure frame times, then print a summary")
+        .set_clicommand_usage("profile", "profile <seconds>");
+    app.register_clicommand_args("assert", assert)
+        .set_clicommand_description("assert", "Fail if a command fails or outputs a false value")
+        .set_clicommand_usage("assert", "assert <command...>");
+    app.register_clicommand_args("fail", fail)
+        .set_clicommand_description("fail", "Print a message and fail")
+        .set_clicommand_usage("fail", "fail <message...>");
+    app.register_clicommand_noargs("namespaces", namespaces)
+        .set_clicommand_description(
+            "namespaces",
+            "List namespaces, their owners and reservations",
+        );
+    app.register_clicommand_args("unregister", unregister)
+        .set_clicommand_description("unregister", "Remove commands")
+        .set_clicommand_usage(
+            "unregister",
+            "unregister <command...|--owner <owner>|--group <group>>",
+        )
+        .set_clicommand_permission("unregister", CliPermission::Dev);
+    app.register_clicommand_args("echo", echo)
+        .set_clicommand_description("echo", "Print text")
+        .set_clicommand_usage("echo", "echo [text...]");
+}
+
+/// `help [command|category|--by-owner]`: list all commands by category, or
+/// those in one category, or show help for a specific command
+fn help(
+    In(args): In<Vec<String>>,
+    clicommands: Res<CliCommands>,
+    sessions: Query<&CliSession>,
+    localizer: Res<CliLocalizer>,
+    mut output: CliOutput,
+) {
+    if args.first().is_some_and(|arg| arg == "--by-owner") {
+        help_by_owner(&clicommands, &localizer, &mut output);
+        return;
+    }
+    if let Some(name) = args.first() {
+        let session = output
+            .session()
+            .and_then(|entity| sessions.get(entity).ok());
+        let alias = session
+            .and_then(|session| session.get_alias(name))
+            .or_else(|| clicommands.get_alias(name));
+        if let Some(expansion) = alias {
+            outln!(
+                output,
+                "help.alias",
+                "{name} is an alias for: {expansion}",
+                name = name,
+                expansion = expansion,
+            );
+            return;
+        }
+        let in_category = |command: &&str| clicommands.command_category(command) == Some(name);
+        if clicommands.get_info(name).is_none() && clicommands.iter_names().any(|n| in_category(&n))
+        {
+            let names = clicommands
+                .iter_names()
+                .filter(|name| clicommands.is_enabled(name))
+                .filter(in_category);
+            output.println(help_table(&clicommands, &localizer, names).to_string());
+            return;
+        }
+        let Some(info) = clicommands.get_info(name) else {
+            failln!(
+                output,
+                "help.not_found",
+                "CliCommand \"{name}\" not found!",
+                name = name
+            );
+            return;
+        };
+        let description = localizer.description(info.name, info.description);
+        output.markdown(description.unwrap_or(info.name));
+        outln!(
+            output,
+            "help.usage",
+            "Usage: {usage}",
+            usage = localizer.usage(info.name, info.usage).unwrap_or(info.name)
+        );
+        if info.permission != CliPermission::Normal {
+            outln!(
+                output,
+                "help.permission",
+                "Permission: {permission}",
+                permission = info.permission
+            );
+        }
+        if !info.groups.is_empty() {
+            outln!(
+                output,
+                "help.groups",
+                "Groups: {groups}",
+                groups = info.groups.join(", ")
+            );
+        }
+        return;
+    }
+    let names = clicommands
+        .iter_names()
+        .filter(|name| clicommands.is_enabled(name));
+    let mut table = help_table(&clicommands, &localizer, names);
+    let mut aliases: Vec<(&str, &str)> = clicommands.iter_aliases().collect();
+    aliases.sort_unstable();
+    for (alias, expansion) in aliases {
+        table.add_row([alias.to_owned(), format!("Alias for: {}", expansion)]);
+    }
+    output.println(table.to_string());
+}
+
+/// A table of commands and their descriptions, with a header row per category
+///
+/// Commands in a category are indented under its header.
+fn help_table<'a>(
+    clicommands: &'a CliCommands,
+    localizer: &CliLocalizer,
+    names: impl IntoIterator<Item = &'a str>,
+) -> CliTable {
+    let mut table = CliTable::new()
+        .with_column("COMMAND", CliAlign::Left)
+        .with_column("DESCRIPTION", CliAlign::Left);
+    for (category, names) in clicommands.group_by_category(names) {
+        let indent = match category {
+            Some(category) => {
+                table.add_row([format!("[{}]", category), String::new()]);
+                "  "
+            }
+            None => "",
+        };
+        for name in names {
+            let description = clicommands.get_info(name).and_then(|info| info.description);
+            let description = localizer.description(name, description).unwrap_or("");
+            table.add_row([format!("{}{}", indent, name), description.to_owned()]);
+        }
+    }
+    table
+}
+
+/// `help --by-owner`: list all commands, grouped by the plugin or crate that registered them
+fn help_by_owner(clicommands: &CliCommands, localizer: &CliLocalizer, output: &mut CliOutput) {
+    let mut rows: Vec<(Option<&str>, &str)> = clicommands
+        .iter_names()
+        .filter(|name| clicommands.is_enabled(name))
+        .map(|name| (clicommands.command_owner(name), name))
+        .collect();
+    // Commands without a known owner go last
+    rows.sort_unstable_by_key(|&(owner, name)| (owner.is_none(), owner, name));
+    let mut table = CliTable::new()
+        .with_column("OWNER", CliAlign::Left)
+        .with_column("COMMAND", CliAlign::Left)
+        .with_column("DESCRIPTION", CliAlign::Left);
+    for (owner, name) in rows {
+        let description = clicommands.get_info(name).and_then(|info| info.description);
+        let description = localizer.description(name, description).unwrap_or("");
+        table.add_row([
+            owner.unwrap_or("(unknown)").to_owned(),
+            name.to_owned(),
+            description.to_owned(),
+        ]);
+    }
+    output.println(table.to_string());
+}
+
+/// `namespaces`: list namespaces, with their commands, owners and reservations
+#[cfg(feature = "builtin-commands")]
+fn namespaces(clicommands: Res<CliCommands>, mut output: CliOutput) {
+    let mut namespaces: Vec<&str> = clicommands
+        .iter_namespaces()
+        .chain(clicommands.iter_reserved().map(|(namespace, _)| namespace))
+        .collect();
+    namespaces.sort_unstable();
+    namespaces.dedup();
+    let mut table = CliTable::new()
+        .with_column("NAMESPACE", CliAlign::Left)
+        .with_column("COMMANDS", CliAlign::Right)
+        .with_column("OWNERS", CliAlign::Left)
+        .with_column("RESERVED BY", CliAlign::Left);
+    for namespace in namespaces {
+        let names: Vec<&str> = clicommands
+            .iter_names()
+            .filter(|name| in_namespace(name, namespace))
+            .collect();
+        let mut owners: Vec<&str> = names
+            .iter()
+            .map(|name| clicommands.command_owner(name).unwrap_or("(unknown)"))
+            .collect();
+        owners.sort_unstable();
+        owners.dedup();
+        let reserved = clicommands
+            .iter_reserved()
+            .find(|(reserved, _)| *reserved == namespace)
+            .map(|(_, owner)| {
+                // Commands registered before the reservation are not rejected
+                let conflicts = names
+                    .iter()
+                    .filter(|name| clicommands.command_owner(name) != owner)
+                    .count();
+                match conflicts {
+                    0 => owner_name(owner),
+                    n => format!("{} ({} conflicting)", owner_name(owner), n),
+                }
+            })
+            .unwrap_or_default();
+        table.add_row([
+            namespace.to_owned(),
+            names.len().to_string(),
+            owners.join(", "),
+            reserved,
+        ]);
+    }
+    output.println(table.to_string());
+}
+
+/// `unregister <command...|--owner <owner>|--group <group>>`: remove commands
+#[cfg(feature = "builtin-commands")]
+fn unregister(In(args): In<Vec<String>>, world: &mut World) {
+    let names: Vec<String> = match args.as_slice() {
+        [] => {
+            cli_error(
+                world,
+                "Usage: unregister <command...|--owner <owner>|--group <group>>",
+            );
+            cli_fail(world);
+            return;
+        }
+        [flag, owner] if flag == "--owner" => {
+            let names: Vec<String> = world
+                .resource::<CliCommands>()
+                .iter_owned_by(owner)
+                .map(|name| name.to_owned())
+                .collect();
+            if names.is_empty() {
+                cli_error(world, format!("No CliCommands registered by {:?}!", owner));
+                cli_fail(world);
+                return;
+            }
+            names
+        }
+        [flag, group] if flag == "--group" => {
+            let names: Vec<String> = world
+                .resource::<CliCommands>()
+                .iter_group(group)
+                .map(|name| name.to_owned())
+                .collect();
+            if names.is_empty() {
+                cli_error(world, format!("No CliCommands in group {:?}!", group));
+                cli_fail(world);
+                return;
+            }
+            names
+        }
+        names => names.to_vec(),
+    };
+    let mut count = 0;
+    for name in names {
+        if world.resource::<CliCommands>().get_info(&name).is_none() {
+            cli_error(world, format!("CliCommand {:?} not found!", name));
+            cli_fail(world);
+            continue;
+        }
+        world.unregister_clicommand(&name);
+        count += 1;
+    }
+    cli_println(world, format!("Unregistered {} commands", count));
+}
+
+/// `alias [name [expansion...]]`: list all aliases, show one, or define one
+///
+/// Run from a [`CliSession`], aliases are only defined in that session.
+#[cfg(feature = "builtin-commands")]
+fn alias(
+    In(args): In<Vec<String>>,
+    mut clicommands: ResMut<CliCommands>,
+    mut sessions: Query<&mut CliSession>,
+    mut output: CliOutput,
+) {
+    let mut session = output
+        .session()
+        .and_then(|entity| sessions.get_mut(entity).ok());
+    match args.as_slice() {
+        [] => {
+            // Session aliases shadow global ones
+            let mut aliases: BTreeMap<&str, &str> = clicommands.iter_aliases().collect();
+            aliases.extend(session.iter().flat_map(|session| session.iter_aliases()));
+            for (alias, expansion) in aliases {
+                output.println(format!("alias {} {}", alias, quote_cli_arg(expansion)));
+            }
+        }
+        [name] => match session
+            .as_ref()
+            .and_then(|session| session.get_alias(name))
+            .or_else(|| clicommands.get_alias(name))
+        {
+            Some(expansion) => {
+                output.println(format!("alias {} {}", name, quote_cli_arg(expansion)));
+            }
+            None => failln!(
+                output,
+                "alias.not_found",
+                "Alias \"{name}\" not found!",
+                name = name
+            ),
+        },
+        [name, expansion @ ..] => match &mut session {
+            Some(session) => session.add_alias(name, &expansion.join(" ")),
+            None => clicommands.add_alias(name, &expansion.join(" ")),
+        },
+    }
+}
+
+/// `list <commands|aliases|cvars> [pattern]`: list names, optionally matching a wildcard pattern
+#[cfg(feature = "builtin-commands")]
+fn list(
+    In(args): In<Vec<String>>,
+    clicommands: Res<CliCommands>,
+    sessions: Query<&CliSession>,
+    #[cfg(feature = "cvars")] vars: Res<CliVars>,
+    mut output: CliOutput,
+) {
+    let session = output
+        .session()
+        .and_then(|entity| sessions.get(entity).ok());
+    let (kind, pattern) = match args.as_slice() {
+        [kind] => (kind.as_str(), "*"),
+        [kind, pattern] => (kind.as_str(), pattern.as_str()),
+        _ => {
+            output.fail("Usage: list <commands|aliases|cvars> [pattern]");
+            return;
+        }
+    };
+    let mut names: Vec<&str> = match kind {
+        "commands" => clicommands
+            .iter_names()
+            .filter(|name| clicommands.is_enabled(name) && glob_match(pattern, name))
+            .collect(),
+        "aliases" => clicommands
+            .iter_aliases()
+            .chain(session.iter().flat_map(|session| session.iter_aliases()))
+            .map(|(alias, _)| alias)
+            .filter(|name| glob_match(pattern, name))
+            .collect(),
+        #[cfg(feature = "cvars")]
+        "cvars" => vars.iter_matching(pattern).collect(),
+        other => {
+            failln!(
+                output,
+                "list.unknown",
+                "Unknown list \"{list}\"! Expected one of: commands, aliases, cvars.",
+                list = other,
+            );
+            return;
+        }
+    };
+    names.sort_unstable();
+    names.dedup();
+    for name in names {
+        output.println(name);
+    }
+}
+
+/// `unalias <name>`: remove an alias
+///
+/// Run from a [`CliSession`], an alias of the session is removed, if there
+/// is one, rather than the global one.
+#[cfg(feature = "builtin-commands")]
+fn unalias(
+    In(args): In<Vec<String>>,
+    mut clicommands: ResMut<CliCommands>,
+    mut sessions: Query<&mut CliSession>,
+    mut output: CliOutput,
+) {
+    let [name] = args.as_slice() else {
+        output.fail("Usage: unalias <name>");
+        return;
+    };
+    let session = output
+        .session()
+        .and_then(|entity| sessions.get_mut(entity).ok());
+    if session
+        .and_then(|mut session| session.remove_alias(name))
+        .is_some()
+    {
+        return;
+    }
+    if clicommands.remove_alias(name).is_none() {
+        failln!(
+            output,
+            "alias.not_found",
+            "Alias \"{name}\" not found!",
+            name = name
+        );
+    }
+}
+
+/// `retry`: run the last failed command string again
+///
+/// If run from a [`CliSession`], it is the last failed command string of that session.
+#[cfg(feature = "builtin-commands")]
+fn retry(world: &mut World) {
+    let status = world.get_resource::<CliStatus>();
+    let from_session = status
+        .and_then(|status| status.session)
+        .and_then(|entity| world.get::<CliSession>(entity))
+        .and_then(|session| session.last_failed());
+    let line = from_session
+        .or_else(|| status.and_then(|status| status.last_failed_line()))
+        .map(|line| line.to_owned());
+    let Some(line) = line else {
+        cli_error(world, "There is no failed command to retry!");
+        cli_fail(world);
+        return;
+    };
+    rerun(world, line);
+}
+
+/// `fix <command...>`: run a corrected version of the last failed command string
+#[cfg(feature = "builtin-commands")]
+fn fix(In(args): In<Vec<String>>, world: &mut World) {
+    if args.is_empty() {
+        cli_error(world, "Usage: fix <command...>");
+        cli_fail(world);
+        return;
+    }
+    let line: Vec<_> = args.iter().map(|arg| quote_cli_arg(arg)).collect();
+    rerun(world, line.join(" "));
+}
+
+/// `assert <command...>`: fail if the command fails, or if its last line of output is false-y
+///
+/// Output counts as false-y if it is `false`, `0`, `no` or `off` (ignoring case).
+#[cfg(feature = "builtin-commands")]
+fn assert(In(args): In<Vec<String>>, world: &mut World) {
+    if args.is_empty() {
+        cli_error(world, "Usage: assert <command...>");
+        cli_fail(world);
+        return;
+    }
+    let line: Vec<_> = args.iter().map(|arg| quote_cli_arg(arg)).collect();
+    let line = line.join(" ");
+    let output = world.run_cli_captured(&line);
+    let success = world.resource::<CliStatus>().last_success() == Some(true);
+    let falsy = output.last().is_some_and(|last| {
+        let text = last.text.trim();
+        ["false", "0", "no", "off"]
+            .iter()
+            .any(|falsy| text.eq_ignore_ascii_case(falsy))
+    });
+    let mut log = world.resource_mut::<CliOutputLog>();
+    for output_line in output {
+        log.push(output_line);
+    }
+    if !success || falsy {
+        cli_error(world, format!("Assertion failed: {}", line));
+        cli_fail(world);
+    }
+}
+
+/// `fail <message...>`: print a message and fail
+#[cfg(feature = "builtin-commands")]
+fn fail(In(args): In<Vec<String>>, world: &mut World) {
+    if !args.is_empty() {
+        cli_println(world, args.join(" "));
+    }
+    cli_fail(world);
+}
+
+/// `echo [text...]`: print the args, separated by spaces
+#[cfg(feature = "builtin-commands")]
+fn echo(In(args): In<Vec<String>>, mut output: CliOutput) {
+    output.println(args.join(" "));
+}
+
+/// `return [value...]`: end the current script or procedure, with an optional value
+fn return_value(In(args): In<Vec<String>>, mut status: ResMut<CliStatus>, mut output: CliOutput) {
+    if status.return_frames == 0 {
+        output.fail("return can only be used in scripts and procedures!");
+        return;
+    }
+    status.returned = Some(args.join(" "));
+}
+
+/// Run a command string on behalf of `retry` or `fix`
+///
+/// If it fails, it (rather than `retry` or `fix`) is remembered as the last failed command string.
+#[cfg(feature = "builtin-commands")]
+fn rerun(world: &mut World, line: String) {
+    cli_println(world, format!("> {}", line));
+    world.run_cli(&line);
+    let mut status = world.resource_mut::<CliStatus>();
+    if status.last_success() == Some(false) {
+        status.rerun = Some(line);
+        status.fail();
+    }
+}
+
+/// `bindcmd <name> <template...>`: create a new command from a template
+#[cfg(feature = "builtin-commands")]
+fn bindcmd(In(args): In<Vec<String>>, world: &mut World) {
+    let [name, template @ ..] = args.as_slice() else {
+        cli_error(world, "Usage: bindcmd <name> <template...>");
+        return;
+    };
+    if template.is_empty() {
+        cli_error(world, "Usage: bindcmd <name> <template...>");
+        return;
+    }
+    world.register_clicommand_template(name, &template.join(" "));
+}
+
+/// `queue [list|clear|pause|resume]`: manage commands waiting to be run
+fn queue(
+    In(args): In<Vec<String>>,
+    mut anchored: ResMut<CliAnchorQueue>,
+    mut fixed: ResMut<CliFixedQueue>,
+    mut sleeping: ResMut<CliSleepQueue>,
+    mut queued: ResMut<CliQueue>,
+    mut output: CliOutput,
+) {
+    match args.first().map(|s| s.as_str()) {
+        None | Some("list") => {
+            let paused = anchored.is_paused() || fixed.is_paused() || queued.is_paused();
+            outln!(
+                output,
+                if paused {
+                    "queue.summary_paused"
+                } else {
+                    "queue.summary"
+                },
+                if paused {
+                    "{queued} in order, {anchored} anchored and {fixed} tick-stamped \
+                     {total|command|commands} queued (paused)."
+                } else {
+                    "{queued} in order, {anchored} anchored and {fixed} tick-stamped \
+                     {total|command|commands} queued."
+                },
+                queued = queued.len(),
+                anchored = anchored.len(),
+                fixed = fixed.len(),
+                total = queued.len() + anchored.len() + fixed.len(),
+            );
+            if let Some(wait) = queued.wait() {
+                output.println(format!("  (waiting: {:?})", wait));
+            }
+            for cli in queued.iter() {
+                output.println(format!("  [queued] {}", cli));
+            }
+            for (anchor, cli) in anchored.iter() {
+                output.println(format!("  [{:?}] {}", anchor, cli));
+            }
+            for (tick, cli) in fixed.iter() {
+                output.println(format!("  [tick {}] {}", tick, cli));
+            }
+            for (_, clis) in sleeping.iter() {
+                for cli in clis {
+                    output.println(format!("  [sleeping] {}", cli));
+                }
+            }
+        }
+        Some("clear") => {
+            anchored.clear();
+            fixed.clear();
+            sleeping.clear();
+            queued.clear();
+        }
+        Some("pause") => {
+            anchored.pause();
+            fixed.pause();
+            queued.pause();
+        }
+        Some("resume") => {
+            anchored.resume();
+            fixed.resume();
+            queued.resume();
+        }
+        Some(other) => {
+            output.fail(format!(
+                "Unknown queue operation {:?}! Expected one of: list, clear, pause, resume.",
+                other
+            ));
+        }
+    }
+}
+
+/// A frame time capture started by the `profile` command
+#[cfg(feature = "builtin-commands")]
+#[derive(Resource, Default)]
+struct CliProfileRun {
+    end: Option<Instant>,
+    frame_times_ms: Vec<f64>,
+}
+
+/// `profile <seconds>`: measure frame times for a while, then print a summary
+#[cfg(feature = "builtin-commands")]
+fn profile(In(args): In<Vec<String>>, mut run: ResMut<CliProfileRun>, mut output: CliOutput) {
+    let Some(seconds) = args.first().and_then(|s| s.parse::<f32>().ok()) else {
+        output.error("Usage: profile <seconds>");
+        return;
+    };
+    if run.end.is_some() {
+        output.fail("A profile capture is already running!");
+        return;
+    }
+    let Ok(duration) = Duration::try_from_secs_f32(seconds) else {
+        output.fail(format!("Invalid duration: {}", seconds));
+        return;
+    };
+    run.end = Some(Instant::now() + duration);
+    run.frame_times_ms.clear();
+    outln!(
+        output,
+        "profile.start",
+        "Profiling for {duration}...",
+        duration = format!("{:?}", duration)
+    );
+}
+
+#[cfg(feature = "builtin-commands")]
+fn sample_profile(time: Res<Time<Real>>, mut run: ResMut<CliProfileRun>, mut output: CliOutput) {
+    let Some(end) = run.end else {
+        return;
+    };
+    run.frame_times_ms.push(time.delta_secs_f64() * 1000.0);
+    if Instant::now() < end {
+        return;
+    }
+    run.end = None;
+    let mut samples = std::mem::take(&mut run.frame_times_ms);
+    if samples.is_empty() {
+        return;
+    }
+    samples.sort_by(|a, b| a.total_cmp(b));
+    let avg = samples.iter().sum::<f64>() / samples.len() as f64;
+    let p95 = samples[(samples.len() - 1) * 95 / 100];
+    let max = samples[samples.len() - 1];
+
+    let mut table = CliTable::new()
+        .with_column("FRAME TIME", CliAlign::Left)
+        .with_column("MS", CliAlign::Right);
+    table.add_row(["frames".to_owned(), samples.len().to_string()]);
+    table.add_row(["average".to_owned(), format!("{:.2}", avg)]);
+    table.add_row(["95th percentile".to_owned(), format!("{:.2}", p95)]);
+    table.add_row(["slowest".to_owned(), format!("{:.2}", max)]);
+    output.println(table.to_string());
+}
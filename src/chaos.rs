@@ -0,0 +1,382 @@
+//! Running random commands with random args, to find crashes.
+//!
+//! Requires the `chaos` cargo feature (enabled by default).
+//!
+//! The built-in `chaos <seconds> [per_second] [pattern] [--group <group>] [--seed <n>]`
+//! command starts a "chaos monkey": for the given time, it runs random
+//! registered commands (whose names match the pattern, `*` by default) at
+//! the given rate (10 per second, by default). `chaos stop` stops it early.
+//! With `--group`, only the commands in that group (see
+//! [`with_clicommand_group`](crate::CliCommandsRegisterExt::with_clicommand_group))
+//! are picked, to keep chaos away from commands that are not meant for it.
+//!
+//! Commands registered with a signature (see
+//! [`register_clicommand_typed`](crate::CliCommandsRegisterExt::register_clicommand_typed))
+//! are given random valid args, biased towards edge cases like `0`, `-1` and
+//! the limits of their types. Other commands are only run without args, if
+//! they accept that. Dangerous commands and the crate's built-in commands are
+//! never picked.
+//!
+//! ```text
+//! > chaos 30 20 debug.* --group fuzz --seed 42
+//! ```
+//!
+//! The seed is printed when chaos starts, so a run can be repeated. As it
+//! runs arbitrary commands, `chaos` is a [`Dev`](CliPermission::Dev) command.
+
+use bevy::prelude::*;
+use bevy::utils::Instant;
+
+use crate::args::{CliArgType, CliSignature};
+use crate::glob::glob_match;
+use crate::output::{cli_error, cli_println};
+use crate::permissions::CliPermission;
+use crate::status::{cli_fail, CliStatus};
+use crate::{quote_cli_arg, CliCommands, CliCommandsRegisterExt, CliCommandsRunExt};
+
+/// The chaos run in progress
+#[derive(Resource, Default)]
+struct CliChaos {
+    run: Option<CliChaosRun>,
+}
+
+struct CliChaosRun {
+    end: Instant,
+    per_second: f32,
+    pattern: String,
+    group: Option<String>,
+    rng: CliRng,
+    /// Commands that should have run, but have not yet
+    due: f32,
+    runs: u32,
+    failures: u32,
+}
+
+/// A small, seedable random number generator (xorshift64*)
+pub(crate) struct CliRng(u64);
+
+impl CliRng {
+    pub(crate) fn new(seed: u64) -> Self {
+        CliRng(seed.max(1))
+    }
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+    /// A number in `0..n`
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n.max(1) as u64) as usize
+    }
+    /// True with the given probability
+    fn chance(&mut self, p: f32) -> bool {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32 <= p
+    }
+    /// A number in `-range..=range`
+    fn signed(&mut self, range: i64) -> i64 {
+        self.below(range as usize * 2 + 1) as i64 - range
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliChaos>();
+    app.add_systems(Update, run_chaos);
+    app.register_clicommand_args("chaos", chaos)
+        .set_clicommand_description("chaos", "Run random commands with random args")
+        .set_clicommand_usage(
+            "chaos",
+            "chaos <seconds|stop> [per_second] [pattern] [--group <group>] [--seed <n>]",
+        )
+        .set_clicommand_permission("chaos", CliPermission::Dev);
+}
+
+/// `chaos <seconds|stop> [per_second] [pattern] [--group <group>] [--seed <n>]`:
+/// run random commands
+fn chaos(In(mut args): In<Vec<String>>, world: &mut World) {
+    let usage = "Usage: chaos <seconds|stop> [per_second] [pattern] [--group <group>] [--seed <n>]";
+    let mut seed = None;
+    if let Some(i) = args.iter().position(|arg| arg == "--seed") {
+        match args.get(i + 1).and_then(|n| n.parse::<u64>().ok()) {
+            Some(n) => seed = Some(n),
+            None => {
+                cli_error(world, usage);
+                cli_fail(world);
+                return;
+            }
+        }
+        args.drain(i..i + 2);
+    }
+    let mut group = None;
+    if let Some(i) = args.iter().position(|arg| arg == "--group") {
+        let Some(name) = args.get(i + 1) else {
+            cli_error(world, usage);
+            cli_fail(world);
+            return;
+        };
+        if world
+            .resource::<CliCommands>()
+            .iter_group(name)
+            .next()
+            .is_none()
+        {
+            cli_error(world, format!("No CliCommands in group {:?}!", name));
+            cli_fail(world);
+            return;
+        }
+        group = Some(name.clone());
+        args.drain(i..i + 2);
+    }
+    if args.first().is_some_and(|arg| arg == "stop") {
+        stop(world);
+        return;
+    }
+    let parsed: Option<(f32, f32, &str)> = match args.as_slice() {
+        [seconds] => seconds.parse().ok().map(|s| (s, 10.0, "*")),
+        [seconds, rate] => seconds
+            .parse()
+            .ok()
+            .zip(rate.parse().ok())
+            .map(|(s, r)| (s, r, "*")),
+        [seconds, rate, pattern] => seconds
+            .parse()
+            .ok()
+            .zip(rate.parse().ok())
+            .map(|(s, r)| (s, r, pattern.as_str())),
+        _ => None,
+    };
+    let Some((seconds, per_second, pattern)) = parsed else {
+        cli_error(world, usage);
+        cli_fail(world);
+        return;
+    };
+    let Ok(duration) = std::time::Duration::try_from_secs_f32(seconds) else {
+        cli_error(world, format!("Invalid duration: {}", seconds));
+        cli_fail(world);
+        return;
+    };
+    if per_second.is_nan() || per_second <= 0.0 {
+        cli_error(world, format!("Invalid rate: {}", per_second));
+        cli_fail(world);
+        return;
+    }
+    let seed = seed.unwrap_or_else(random_seed);
+    cli_println(
+        world,
+        format!(
+            "Chaos for {:?}, {} commands per second, matching {:?}{} (seed {})",
+            duration,
+            per_second,
+            pattern,
+            group
+                .as_ref()
+                .map(|group| format!(" in group {:?}", group))
+                .unwrap_or_default(),
+            seed
+        ),
+    );
+    world.resource_mut::<CliChaos>().run = Some(CliChaosRun {
+        end: Instant::now() + duration,
+        per_second,
+        pattern: pattern.to_owned(),
+        group,
+        rng: CliRng::new(seed),
+        due: 0.0,
+        runs: 0,
+        failures: 0,
+    });
+}
+
+/// A seed for when none is given, from the time
+pub(crate) fn random_seed() -> u64 {
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH);
+    now.map_or(1, |now| now.as_nanos() as u64)
+}
+
+fn stop(world: &mut World) {
+    let Some(run) = world.resource_mut::<CliChaos>().run.take() else {
+        cli_error(world, "Chaos is not running!");
+        cli_fail(world);
+        return;
+    };
+    cli_println(
+        world,
+        format!(
+            "Chaos finished: {} commands run, {} failed",
+            run.runs, run.failures
+        ),
+    );
+}
+
+fn run_chaos(world: &mut World) {
+    let Some(mut run) = world.resource_mut::<CliChaos>().run.take() else {
+        return;
+    };
+    let delta = world
+        .get_resource::<Time<Real>>()
+        .map_or(0.0, |time| time.delta_secs());
+    run.due += delta * run.per_second;
+    let candidates = chaos_candidates(world, &run.pattern, run.group.as_deref());
+    while run.due >= 1.0 && !candidates.is_empty() {
+        run.due -= 1.0;
+        let (line, _) = random_line(&mut run.rng, &candidates);
+        cli_println(world, format!("chaos> {}", line));
+        world.run_cli(&line);
+        run.runs += 1;
+        if world
+            .get_resource::<CliStatus>()
+            .and_then(|s| s.last_success())
+            == Some(false)
+        {
+            run.failures += 1;
+        }
+    }
+    run.due = run.due.min(1.0);
+    let finished = Instant::now() >= run.end;
+    let mut chaos = world.resource_mut::<CliChaos>();
+    // `chaos stop` may have run, or a new chaos may have started
+    if chaos.run.is_none() {
+        chaos.run = Some(run);
+        if finished {
+            stop(world);
+        }
+    }
+}
+
+/// The commands chaos may run, with their signatures, if they have one
+pub(crate) fn chaos_candidates(
+    world: &World,
+    pattern: &str,
+    group: Option<&str>,
+) -> Vec<(String, Option<CliSignature>)> {
+    let Some(clicommands) = world.get_resource::<CliCommands>() else {
+        return Vec::new();
+    };
+    let mut candidates: Vec<_> = clicommands
+        .iter_names()
+        .filter_map(|name| clicommands.get_info(name))
+        .filter(|info| {
+            info.enabled
+                && !info.dangerous
+                && info.owner != Some("iyes_cli")
+                && glob_match(pattern, info.name)
+                && group.is_none_or(|group| info.groups.iter().any(|g| g == group))
+        })
+        .filter_map(|info| {
+            match (
+                clicommands.command_signature(info.name),
+                info.accepts_noargs,
+            ) {
+                (Some(signature), _) => Some((info.name.to_owned(), Some(signature))),
+                (_, true) => Some((info.name.to_owned(), None)),
+                _ => None,
+            }
+        })
+        .collect();
+    // The order of names in the registry is not stable, but should be for seeds
+    candidates.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    candidates
+}
+
+/// A random command string running one of the candidates, with its args
+pub(crate) fn random_line(
+    rng: &mut CliRng,
+    candidates: &[(String, Option<CliSignature>)],
+) -> (String, Vec<String>) {
+    let (name, signature) = &candidates[rng.below(candidates.len())];
+    let args = signature
+        .as_ref()
+        .map(|signature| random_args(rng, signature))
+        .unwrap_or_default();
+    let mut line = quote_cli_arg(name).into_owned();
+    for arg in &args {
+        line.push(' ');
+        line.push_str(&quote_cli_arg(arg));
+    }
+    (line, args)
+}
+
+/// Random valid args for a signature
+pub(crate) fn random_args(rng: &mut CliRng, signature: &CliSignature) -> Vec<String> {
+    let mut args = Vec::new();
+    for param in signature.params() {
+        if param.optional && rng.chance(0.5) {
+            break;
+        }
+        let count = if param.variadic { rng.below(4) } else { 1 };
+        for _ in 0..count {
+            args.push(random_value(rng, param.ty));
+        }
+    }
+    args
+}
+
+/// A random value of a type, biased towards edge cases
+fn random_value(rng: &mut CliRng, ty: CliArgType) -> String {
+    let edge = rng.chance(0.3);
+    macro_rules! int {
+        ($t:ty) => {
+            if edge {
+                let edges = [0, 1, <$t>::MIN, <$t>::MAX, <$t>::MIN.saturating_add(1)];
+                edges[rng.below(edges.len())].to_string()
+            } else {
+                (rng.signed(100) as $t).to_string()
+            }
+        };
+    }
+    macro_rules! uint {
+        ($t:ty) => {
+            if edge {
+                let edges = [0, 1, <$t>::MAX];
+                edges[rng.below(edges.len())].to_string()
+            } else {
+                (rng.below(101) as $t).to_string()
+            }
+        };
+    }
+    macro_rules! float {
+        ($t:ty) => {
+            if edge {
+                let edges = [
+                    0.0,
+                    -0.0,
+                    -1.0,
+                    <$t>::MIN,
+                    <$t>::MAX,
+                    <$t>::NAN,
+                    <$t>::INFINITY,
+                ];
+                edges[rng.below(edges.len())].to_string()
+            } else {
+                (rng.signed(100_000) as $t / 100.0).to_string()
+            }
+        };
+    }
+    match ty {
+        CliArgType::Bool => rng.chance(0.5).to_string(),
+        CliArgType::Char => char::from(b' ' + rng.below(95) as u8).to_string(),
+        CliArgType::String => {
+            if edge {
+                let edges = ["", " ", "-1", "\"", "\n", "ünïcödé", "*"];
+                edges[rng.below(edges.len())].to_owned()
+            } else {
+                (0..rng.below(12))
+                    .map(|_| char::from(b'a' + rng.below(26) as u8))
+                    .collect()
+            }
+        }
+        CliArgType::I8 => int!(i8),
+        CliArgType::I16 => int!(i16),
+        CliArgType::I32 => int!(i32),
+        CliArgType::I64 => int!(i64),
+        CliArgType::Isize => int!(isize),
+        CliArgType::U8 => uint!(u8),
+        CliArgType::U16 => uint!(u16),
+        CliArgType::U32 => uint!(u32),
+        CliArgType::U64 => uint!(u64),
+        CliArgType::Usize => uint!(usize),
+        CliArgType::F32 => float!(f32),
+        CliArgType::F64 => float!(f64),
+        CliArgType::Entity => format!("{}v1", rng.below(1000)),
+    }
+}
@@ -0,0 +1,762 @@
+//! The client side of the remote console protocol, for terminal clients.
+//!
+//! Requires the `remote` cargo feature. [`CliClient`] connects to a
+//! [`CliRemotePlugin`](crate::remote::CliRemotePlugin) server, and speaks
+//! its protocol (see [`remote`](crate::remote)), so a game can ship a tiny
+//! companion binary to administer its servers:
+//!
+//! ```rust,ignore
+//! use std::io::BufRead;
+//! use iyes_cli::client::{CliClient, CliClientMessage};
+//!
+//! fn main() -> std::io::Result<()> {
+//!     let password = std::env::var("MYGAME_PASSWORD").unwrap_or_default();
+//!     let mut client = CliClient::connect("127.0.0.1:27015", &password)?;
+//!     for line in std::io::stdin().lock().lines() {
+//!         let success = client.run(&line?, |message| match message {
+//!             CliClientMessage::Output(text) => println!("{}", text),
+//!             CliClientMessage::Prompt { message, .. } => println!("{} (use answer)", message),
+//!             _ => {}
+//!         })?;
+//!         if !success {
+//!             eprintln!("(failed)");
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Completions of partially typed command strings are requested with
+//! [`complete`](CliClient::complete). Output that arrives after a command
+//! string finished, like that of background [jobs](crate::jobs), can be
+//! polled with [`poll`](CliClient::poll), like the lines of the output
+//! channels the client [subscribes](CliClient::subscribe) to.
+//!
+//! The client answers the heartbeats of the server while it reads messages,
+//! so it should read them regularly. If the connection is lost, its session
+//! can be taken back with [`resume`](CliClient::resume), using the
+//! [`resume_token`](CliClient::resume_token) of the lost connection.
+
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use base64::prelude::*;
+
+use crate::output::CliSeverity;
+use crate::{parse_cli, quote_cli_arg};
+
+/// A message from the remote console server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliClientMessage {
+    /// A line of output
+    Output(String),
+    /// The command string finished, successfully or not
+    Done { success: bool },
+    /// A command asks for multi-line text
+    ///
+    /// Reply with [`CliClient::reply_editor`], or [`CliClient::cancel`].
+    Edit {
+        id: u64,
+        command: String,
+        text: String,
+    },
+    /// A command asks a question
+    ///
+    /// Reply with [`CliClient::answer`], or [`CliClient::cancel`]. Secret
+    /// answers (like passwords) should not be shown while typed.
+    Prompt {
+        id: u64,
+        secret: bool,
+        message: String,
+    },
+    /// A completion of the partial command string, as the whole new string
+    Completion(String),
+    /// A line published on a channel the client is subscribed to
+    Channel {
+        channel: String,
+        severity: CliSeverity,
+        text: String,
+    },
+    /// A file attached to the output, like a screenshot
+    ///
+    /// Follows the [`Output`](Self::Output) line describing it.
+    Attachment {
+        name: String,
+        mime: String,
+        data: Vec<u8>,
+    },
+    /// A line of output of a resumed session, from before it was resumed
+    Scrollback(String),
+    /// The server is shutting down, and will close the connection
+    Shutdown(String),
+}
+
+impl CliClientMessage {
+    /// Parse a line sent by the server, ignoring lines it does not know
+    fn parse(line: &str) -> Option<Self> {
+        if let Some(text) = line.strip_prefix("out: ") {
+            return Some(CliClientMessage::Output(text.to_owned()));
+        }
+        if let Some(line) = line.strip_prefix("completion: ") {
+            return Some(CliClientMessage::Completion(line.to_owned()));
+        }
+        if let Some(text) = line.strip_prefix("scrollback: ") {
+            return Some(CliClientMessage::Scrollback(text.to_owned()));
+        }
+        if let Some(text) = line.strip_prefix("shutdown: ") {
+            return Some(CliClientMessage::Shutdown(text.to_owned()));
+        }
+        if let Some(line) = line.strip_prefix("channel: ") {
+            let (channel, line) = line.split_once(' ')?;
+            let (severity, text) = line.split_once(' ').unwrap_or((line, ""));
+            return Some(CliClientMessage::Channel {
+                channel: channel.to_owned(),
+                severity: CliSeverity::parse(severity)?,
+                text: text.to_owned(),
+            });
+        }
+        match line {
+            "ok" => return Some(CliClientMessage::Done { success: true }),
+            "failed" => return Some(CliClientMessage::Done { success: false }),
+            _ => {}
+        }
+        let parsed = parse_cli(line).ok()?;
+        match (parsed.name(), parsed.args()) {
+            ("edit", [id, command, text]) => Some(CliClientMessage::Edit {
+                id: id.parse().ok()?,
+                command: command.clone(),
+                text: text.clone(),
+            }),
+            ("prompt", [id, kind, message]) => Some(CliClientMessage::Prompt {
+                id: id.parse().ok()?,
+                secret: kind == "secret",
+                message: message.clone(),
+            }),
+            ("attachment:", [name, mime, data]) => Some(CliClientMessage::Attachment {
+                name: name.clone(),
+                mime: mime.clone(),
+                data: BASE64_STANDARD.decode(data).ok()?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// A connection to a remote console server
+pub struct CliClient {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    /// A line being received, when reading it timed out
+    partial: Vec<u8>,
+    session_id: u64,
+    resume_token: String,
+}
+
+impl CliClient {
+    /// Connect to a server, and authenticate with its password
+    ///
+    /// Fails with [`ErrorKind::PermissionDenied`] if the password is wrong.
+    pub fn connect(addr: impl ToSocketAddrs, password: &str) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        let mut client = CliClient {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            partial: Vec::new(),
+            session_id: 0,
+            resume_token: String::new(),
+        };
+        client.send_line(password)?;
+        match client.read_line()?.as_deref() {
+            Some("auth ok") => {}
+            Some("auth failed") => {
+                return Err(io::Error::new(
+                    ErrorKind::PermissionDenied,
+                    "authentication failed",
+                ))
+            }
+            _ => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "not a remote CLI server",
+                ))
+            }
+        }
+        match client.read_line()? {
+            Some(line) if client.read_session(&line) => Ok(client),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "not a remote CLI server",
+            )),
+        }
+    }
+    /// Connect to a server, and take back the session of a lost connection,
+    /// returning the last lines of output of the session
+    ///
+    /// Fails with [`ErrorKind::NotFound`] if the session has expired, or was
+    /// not authenticated the same way.
+    pub fn resume(
+        addr: impl ToSocketAddrs,
+        password: &str,
+        resume_token: &str,
+    ) -> io::Result<(Self, Vec<String>)> {
+        let mut client = CliClient::connect(addr, password)?;
+        client.send_line(&format!("resume: {}", resume_token))?;
+        let mut scrollback = Vec::new();
+        let resumed = client.wait_done(&mut |message| {
+            if let CliClientMessage::Scrollback(text) = message {
+                scrollback.push(text.clone());
+            }
+        })?;
+        if !resumed {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                "there is no such session to resume",
+            ));
+        }
+        Ok((client, scrollback))
+    }
+    /// The id of the session of the connection on the server
+    pub fn session_id(&self) -> u64 {
+        self.session_id
+    }
+    /// The secret to [`resume`](Self::resume) the session with, if the
+    /// connection is lost
+    pub fn resume_token(&self) -> &str {
+        &self.resume_token
+    }
+    /// Send a raw line of the protocol
+    ///
+    /// Prefer [`run`](Self::run), unless the messages are read separately,
+    /// with [`next_message`](Self::next_message).
+    pub fn send_line(&mut self, line: &str) -> io::Result<()> {
+        if line.contains('\n') {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "lines cannot contain newlines",
+            ));
+        }
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()
+    }
+    /// Wait for the next message from the server
+    ///
+    /// Returns `None` once the server has closed the connection.
+    pub fn next_message(&mut self) -> io::Result<Option<CliClientMessage>> {
+        self.reader.get_ref().set_read_timeout(None)?;
+        loop {
+            let Some(line) = self.read_line()? else {
+                return Ok(None);
+            };
+            if let Some(message) = self.handle_line(&line)? {
+                return Ok(Some(message));
+            }
+        }
+    }
+    /// Get the next message from the server, if one arrives within the timeout
+    pub fn poll(&mut self, timeout: Duration) -> io::Result<Option<CliClientMessage>> {
+        self.reader
+            .get_ref()
+            .set_read_timeout(Some(timeout.max(Duration::from_millis(1))))?;
+        let line = match self.read_line() {
+            Ok(line) => line,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => None,
+            Err(e) => return Err(e),
+        };
+        match line {
+            Some(line) => self.handle_line(&line),
+            None => Ok(None),
+        }
+    }
+    /// Run a command string, passing the messages about it to a function,
+    /// and return whether it succeeded
+    pub fn run(
+        &mut self,
+        cli: &str,
+        mut on_message: impl FnMut(&CliClientMessage),
+    ) -> io::Result<bool> {
+        if cli.trim().is_empty() {
+            return Ok(true);
+        }
+        self.send_line(cli)?;
+        self.wait_done(&mut on_message)
+    }
+    /// Run a command string at most once per idempotency key, passing the
+    /// messages about it to a function, and return whether it succeeded
+    ///
+    /// If the connection is lost before the reply, connect again and retry
+    /// with the same key: if the command string already ran, its output and
+    /// result are sent again, instead of running it twice. See
+    /// [`idempotency`](crate::idempotency).
+    pub fn run_with_key(
+        &mut self,
+        key: &str,
+        cli: &str,
+        mut on_message: impl FnMut(&CliClientMessage),
+    ) -> io::Result<bool> {
+        if cli.trim().is_empty() {
+            return Ok(true);
+        }
+        self.send_line(&format!("key: {} {}", key, cli))?;
+        self.wait_done(&mut on_message)
+    }
+    /// Get the completions of a partially typed command string, as the
+    /// whole new strings
+    pub fn complete(&mut self, partial_line: &str) -> io::Result<Vec<String>> {
+        self.send_line(&format!("complete: {}", partial_line))?;
+        let mut completions = Vec::new();
+        self.wait_done(&mut |message| {
+            if let CliClientMessage::Completion(line) = message {
+                completions.push(line.clone());
+            }
+        })?;
+        Ok(completions)
+    }
+    /// Subscribe to an output channel, receiving its lines at least as
+    /// severe as `min_severity`, and containing `contains` if given, as
+    /// [`Channel`](CliClientMessage::Channel) messages
+    ///
+    /// Subscribing again replaces the filter.
+    pub fn subscribe(
+        &mut self,
+        channel: &str,
+        min_severity: CliSeverity,
+        contains: Option<&str>,
+    ) -> io::Result<bool> {
+        let mut line = format!(
+            "subscribe: {} --min {}",
+            quote_cli_arg(channel),
+            min_severity.as_str()
+        );
+        if let Some(contains) = contains {
+            line.push_str(" --grep ");
+            line.push_str(&quote_cli_arg(contains));
+        }
+        self.send_line(&line)?;
+        self.wait_done(&mut |_| {})
+    }
+    /// Stop receiving the lines of a channel, returning whether the client
+    /// was subscribed to it
+    pub fn unsubscribe(&mut self, channel: &str) -> io::Result<bool> {
+        self.send_line(&format!("unsubscribe: {}", channel))?;
+        self.wait_done(&mut |_| {})
+    }
+    /// Reply to an [`Edit`](CliClientMessage::Edit) with the edited text,
+    /// running its command, and return whether it succeeded
+    pub fn reply_editor(
+        &mut self,
+        id: u64,
+        text: &str,
+        mut on_message: impl FnMut(&CliClientMessage),
+    ) -> io::Result<bool> {
+        self.send_line(&format!("reply: {} {}", id, quote_cli_arg(text)))?;
+        self.wait_done(&mut on_message)
+    }
+    /// Answer a [`Prompt`](CliClientMessage::Prompt), and return whether
+    /// the answer was accepted
+    pub fn answer(
+        &mut self,
+        id: u64,
+        text: &str,
+        mut on_message: impl FnMut(&CliClientMessage),
+    ) -> io::Result<bool> {
+        self.send_line(&format!("answer: {} {}", id, quote_cli_arg(text)))?;
+        self.wait_done(&mut on_message)
+    }
+    /// Cancel an [`Edit`](CliClientMessage::Edit) or a
+    /// [`Prompt`](CliClientMessage::Prompt), returning whether it was pending
+    pub fn cancel(&mut self, id: u64) -> io::Result<bool> {
+        self.send_line(&format!("cancel: {}", id))?;
+        self.wait_done(&mut |_| {})
+    }
+    /// Pass messages to a function until the current request is done
+    fn wait_done(&mut self, on_message: &mut dyn FnMut(&CliClientMessage)) -> io::Result<bool> {
+        loop {
+            match self.next_message()? {
+                Some(CliClientMessage::Done { success }) => return Ok(success),
+                Some(message) => on_message(&message),
+                None => {
+                    return Err(io::Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "the server closed the connection",
+                    ))
+                }
+            }
+        }
+    }
+    /// Answer heartbeats and keep session details, parsing other lines
+    fn handle_line(&mut self, line: &str) -> io::Result<Option<CliClientMessage>> {
+        if line == "ping" {
+            self.send_line("pong")?;
+            return Ok(None);
+        }
+        if self.read_session(line) {
+            return Ok(None);
+        }
+        Ok(CliClientMessage::parse(line))
+    }
+    /// Keep the details of a `session <id> <token>` line
+    fn read_session(&mut self, line: &str) -> bool {
+        let Some((id, token)) = line
+            .strip_prefix("session ")
+            .and_then(|line| line.split_once(' '))
+        else {
+            return false;
+        };
+        let Ok(id) = id.parse() else {
+            return false;
+        };
+        self.session_id = id;
+        self.resume_token = token.to_owned();
+        true
+    }
+    /// Read a line, keeping what was read of it if that times out
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let n = self.reader.read_until(b'\n', &mut self.partial)?;
+        if n == 0 && self.partial.is_empty() {
+            return Ok(None);
+        }
+        let line = String::from_utf8_lossy(&self.partial)
+            .trim_end_matches(['\r', '\n'])
+            .to_owned();
+        self.partial.clear();
+        Ok(Some(line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{cli_attach, cli_println, CliAttachment, CliOutput};
+    use crate::remote::{CliChannels, CliRemotePlugin, CliRemoteServer, CliRemoteSession};
+    use crate::{CliCommandsRegisterExt, CliPlugin};
+    use bevy::prelude::*;
+
+    #[test]
+    fn client() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.add_plugins(CliRemotePlugin {
+            address: "127.0.0.1:0".to_owned(),
+            password: "hunter2".to_owned(),
+            ..default()
+        });
+        app.register_clicommand_args("greet", |In(args): In<Vec<String>>, world: &mut World| {
+            cli_println(world, format!("Hello, {}!", args.join(" ")));
+        });
+        app.register_clicommand_noargs("screenshot", |world: &mut World| {
+            let png = [0x89, b'P', b'N', b'G', 0, 0xff];
+            cli_attach(world, CliAttachment::new("shot 1.png", "image/png", png));
+        });
+        app.register_clicommand_noargs("ban", |mut bans: Local<u32>, mut output: CliOutput| {
+            *bans += 1;
+            output.println(format!("Banned ({})", *bans));
+        });
+        let addr = app
+            .world()
+            .resource::<CliRemoteServer>()
+            .local_addr()
+            .unwrap();
+
+        let thread = std::thread::spawn(move || {
+            assert_eq!(
+                CliClient::connect(addr, "wrong").err().map(|e| e.kind()),
+                Some(ErrorKind::PermissionDenied)
+            );
+            let mut client = CliClient::connect(addr, "hunter2").unwrap();
+            let mut output = Vec::new();
+            let success = client
+                .run("greet \"you there\"", |message| {
+                    output.push(message.clone())
+                })
+                .unwrap();
+            assert!(success);
+            assert_eq!(
+                output,
+                [CliClientMessage::Output("Hello, you there!".to_owned())]
+            );
+            assert!(!client.run("nope", |_| {}).unwrap());
+            assert_eq!(client.complete("gree").unwrap(), ["greet"]);
+
+            let mut output = Vec::new();
+            assert!(client
+                .run("screenshot", |message| output.push(message.clone()))
+                .unwrap());
+            assert_eq!(
+                output,
+                [
+                    CliClientMessage::Output(
+                        "[attachment: shot 1.png (image/png, 6 bytes)]".to_owned()
+                    ),
+                    CliClientMessage::Attachment {
+                        name: "shot 1.png".to_owned(),
+                        mime: "image/png".to_owned(),
+                        data: vec![0x89, b'P', b'N', b'G', 0, 0xff],
+                    },
+                ]
+            );
+
+            // Retries with the same key do not run again
+            for _ in 0..2 {
+                let mut output = Vec::new();
+                assert!(client
+                    .run_with_key("k1", "ban", |message| output.push(message.clone()))
+                    .unwrap());
+                assert_eq!(output, [CliClientMessage::Output("Banned (1)".to_owned())]);
+            }
+            let mut client = CliClient::connect(addr, "hunter2").unwrap();
+            let mut output = Vec::new();
+            assert!(client
+                .run_with_key("k1", "ban", |message| output.push(message.clone()))
+                .unwrap());
+            assert_eq!(output, [CliClientMessage::Output("Banned (1)".to_owned())]);
+            let mut output = Vec::new();
+            assert!(client
+                .run_with_key("k2", "ban", |message| output.push(message.clone()))
+                .unwrap());
+            assert_eq!(output, [CliClientMessage::Output("Banned (2)".to_owned())]);
+            assert!(!client.run("key: k3", |_| {}).unwrap());
+        });
+        while !thread.is_finished() {
+            app.update();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn sessions() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.add_plugins(CliRemotePlugin {
+            address: "127.0.0.1:0".to_owned(),
+            password: "hunter2".to_owned(),
+            ..default()
+        });
+        let addr = app
+            .world()
+            .resource::<CliRemoteServer>()
+            .local_addr()
+            .unwrap();
+
+        let thread = std::thread::spawn(move || {
+            let output = |client: &mut CliClient, cli: &str| {
+                let mut output = Vec::new();
+                client
+                    .run(cli, |message| {
+                        if let CliClientMessage::Output(text) = message {
+                            output.push(text.clone());
+                        }
+                    })
+                    .unwrap();
+                output
+            };
+            let mut first = CliClient::connect(addr, "hunter2").unwrap();
+            let mut second = CliClient::connect(addr, "hunter2").unwrap();
+            // Variables are per session
+            assert!(first.run("set target 4v2", |_| {}).unwrap());
+            assert_eq!(output(&mut first, "echo $target"), ["4v2"]);
+            assert_eq!(output(&mut second, "echo $target"), ["$target"]);
+
+            let who = output(&mut second, "who");
+            assert_eq!(who.len(), 4);
+            assert!(output(&mut second, "kickcli 1")[0].starts_with("Session 1 "));
+            assert!(!second.run("kickcli 1", |_| {}).unwrap());
+            assert_eq!(
+                first.next_message().unwrap(),
+                Some(CliClientMessage::Output(
+                    "Your session was closed".to_owned()
+                ))
+            );
+            assert_eq!(first.next_message().unwrap(), None);
+        });
+        while !thread.is_finished() {
+            app.update();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        thread.join().unwrap();
+        let mut q = app.world_mut().query::<&CliRemoteSession>();
+        assert_eq!(q.iter(app.world()).count(), 1);
+    }
+
+    #[test]
+    fn channels() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.add_plugins(CliRemotePlugin {
+            address: "127.0.0.1:0".to_owned(),
+            password: "hunter2".to_owned(),
+            ..default()
+        });
+        let addr = app
+            .world()
+            .resource::<CliRemoteServer>()
+            .local_addr()
+            .unwrap();
+
+        let thread = std::thread::spawn(move || {
+            let mut client = CliClient::connect(addr, "hunter2").unwrap();
+            assert!(client
+                .subscribe("chat", CliSeverity::Warn, Some("alert"))
+                .unwrap());
+            let message = loop {
+                if let Some(message) = client.poll(Duration::from_millis(10)).unwrap() {
+                    break message;
+                }
+            };
+            assert_eq!(
+                message,
+                CliClientMessage::Channel {
+                    channel: "chat".to_owned(),
+                    severity: CliSeverity::Warn,
+                    text: "red alert".to_owned(),
+                }
+            );
+            assert!(client.unsubscribe("chat").unwrap());
+            assert!(!client.unsubscribe("chat").unwrap());
+            assert!(!client.run("nope", |_| {}).unwrap());
+        });
+        while !thread.is_finished() {
+            let mut channels = app.world_mut().resource_mut::<CliChannels>();
+            channels.publish("chat", "red alert, but only info");
+            channels.publish_severity("chat", CliSeverity::Error, "not the one");
+            channels.publish_severity("chat", CliSeverity::Warn, "red alert");
+            channels.publish_severity("log", CliSeverity::Error, "alert on another channel");
+            app.update();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn heartbeat() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.add_plugins(CliRemotePlugin {
+            address: "127.0.0.1:0".to_owned(),
+            password: "hunter2".to_owned(),
+            heartbeat: Some(Duration::from_millis(20)),
+            ..default()
+        });
+        let addr = app
+            .world()
+            .resource::<CliRemoteServer>()
+            .local_addr()
+            .unwrap();
+
+        let thread = std::thread::spawn(move || {
+            // Clients that do not answer pings are disconnected
+            let mut silent = TcpStream::connect(addr).unwrap();
+            silent.write_all(b"hunter2\n").unwrap();
+            let lines: Vec<String> = BufReader::new(silent)
+                .lines()
+                .map(|line| line.unwrap())
+                .collect();
+            assert_eq!(lines[0], "auth ok");
+            assert!(lines[1].starts_with("session "));
+            assert!(lines[2..].iter().all(|line| line == "ping"));
+            assert!(lines.len() > 2);
+
+            // Clients reading messages answer them
+            let mut client = CliClient::connect(addr, "hunter2").unwrap();
+            for _ in 0..20 {
+                assert_eq!(client.poll(Duration::from_millis(10)).unwrap(), None);
+            }
+            assert!(client.run("echo still here", |_| {}).unwrap());
+        });
+        while !thread.is_finished() {
+            app.update();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn resume() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.add_plugins(CliRemotePlugin {
+            address: "127.0.0.1:0".to_owned(),
+            password: "hunter2".to_owned(),
+            ..default()
+        });
+        let addr = app
+            .world()
+            .resource::<CliRemoteServer>()
+            .local_addr()
+            .unwrap();
+
+        let thread = std::thread::spawn(move || {
+            let mut client = CliClient::connect(addr, "hunter2").unwrap();
+            assert!(client.run("set target 4v2", |_| {}).unwrap());
+            assert!(client.run("echo $target", |_| {}).unwrap());
+            let (id, token) = (client.session_id(), client.resume_token().to_owned());
+            drop(client);
+
+            let wrong = CliClient::resume(addr, "hunter2", "0123456789abcdef");
+            assert_eq!(wrong.err().map(|e| e.kind()), Some(ErrorKind::NotFound));
+            // The server notices the lost connection on its next update
+            let (mut client, scrollback) = loop {
+                match CliClient::resume(addr, "hunter2", &token) {
+                    Ok(resumed) => break resumed,
+                    Err(e) if e.kind() == ErrorKind::NotFound => {
+                        std::thread::sleep(Duration::from_millis(5))
+                    }
+                    Err(e) => panic!("{}", e),
+                }
+            };
+            assert_eq!(scrollback, ["4v2"]);
+            assert_eq!(client.session_id(), id);
+            assert_eq!(client.resume_token(), token);
+            let mut output = Vec::new();
+            client
+                .run("echo $target", |message| output.push(message.clone()))
+                .unwrap();
+            assert_eq!(output, [CliClientMessage::Output("4v2".to_owned())]);
+        });
+        while !thread.is_finished() {
+            app.update();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn shutdown() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.add_plugins(CliRemotePlugin {
+            address: "127.0.0.1:0".to_owned(),
+            password: "hunter2".to_owned(),
+            ..default()
+        });
+        let addr = app
+            .world()
+            .resource::<CliRemoteServer>()
+            .local_addr()
+            .unwrap();
+
+        let thread = std::thread::spawn(move || {
+            let mut client = CliClient::connect(addr, "hunter2").unwrap();
+            assert_eq!(
+                client.next_message().unwrap(),
+                Some(CliClientMessage::Shutdown(
+                    "The server is shutting down".to_owned()
+                ))
+            );
+            assert_eq!(client.next_message().unwrap(), None);
+        });
+        let mut exiting = false;
+        while !thread.is_finished() {
+            let mut q = app.world_mut().query::<&CliRemoteSession>();
+            if !exiting && q.iter(app.world()).count() == 1 {
+                app.world_mut().send_event(AppExit::Success);
+                exiting = true;
+            }
+            app.update();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        thread.join().unwrap();
+    }
+}
@@ -0,0 +1,217 @@
+//! Groups of "console commands", registered and removed together.
+//!
+//! A [`CliCommandSet`] collects commands, aliases, console variables and
+//! help text into one value, which is a [`Plugin`]. Each crate or module
+//! can export its own set, instead of registering everything in `main.rs`:
+//!
+//! ```rust,ignore
+//! pub fn physics_commands() -> CliCommandSet {
+//!     CliCommandSet::new("physics")
+//!         .with_command_noargs("physics.pause", pause_physics)
+//!         .with_command_args("physics.gravity", set_gravity)
+//!         .with_description("physics.gravity", "Set the gravity")
+//!         .with_usage("physics.gravity", "physics.gravity <x> <y>")
+//!         .with_alias("pp", "physics.pause")
+//!         .with_clivar("physics.substeps", 4u32)
+//! }
+//!
+//! app.add_plugins((CliPlugin, physics_commands()));
+//! ```
+//!
+//! The commands are registered with the name of the set as their owner
+//! (see [`with_clicommand_owner`](crate::CliCommandsRegisterExt::with_clicommand_owner)),
+//! so `help --by-owner` groups them. Everything the set added is removed
+//! with [`remove_cli_command_set`](CliCommandSetExt::remove_cli_command_set).
+//!
+//! Sets are identified by name: adding two sets with the same name panics,
+//! like adding any plugin twice.
+
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+#[cfg(feature = "cvars")]
+use crate::vars::{CliVarValue, CliVars, CliVarsRegisterExt};
+use crate::{CliCommands, CliCommandsRegisterExt};
+
+type CliSetEntry = Box<dyn FnOnce(&mut App) + Send>;
+
+/// A group of "console commands", aliases and console variables
+///
+/// See [`command_set`](crate::command_set).
+pub struct CliCommandSet {
+    name: String,
+    /// Taken when the set is added to the app
+    entries: Mutex<Vec<CliSetEntry>>,
+    aliases: Vec<String>,
+    #[cfg(feature = "cvars")]
+    vars: Vec<String>,
+}
+
+/// What each added set added, apart from the commands it owns
+#[derive(Resource, Default)]
+struct CliCommandSets {
+    sets: HashMap<String, CliCommandSetContents>,
+}
+
+#[derive(Default)]
+struct CliCommandSetContents {
+    aliases: Vec<String>,
+    #[cfg(feature = "cvars")]
+    vars: Vec<String>,
+}
+
+impl CliCommandSet {
+    /// Create an empty set, named after the crate or module it is for
+    pub fn new(name: &str) -> Self {
+        CliCommandSet {
+            name: name.to_owned(),
+            entries: Mutex::new(Vec::new()),
+            aliases: Vec::new(),
+            #[cfg(feature = "cvars")]
+            vars: Vec::new(),
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Add anything else to do when the set is added, like setting metadata
+    ///
+    /// Commands registered by `f` are owned by the set.
+    pub fn with(self, f: impl FnOnce(&mut App) + Send + 'static) -> Self {
+        self.entries.lock().unwrap().push(Box::new(f));
+        self
+    }
+    /// Add a command without args
+    ///
+    /// See [`register_clicommand_noargs`](CliCommandsRegisterExt::register_clicommand_noargs).
+    pub fn with_command_noargs<S, Param>(self, name: &str, system: S) -> Self
+    where
+        S: IntoSystem<(), (), Param> + Send + 'static,
+    {
+        let name = name.to_owned();
+        self.with(move |app| {
+            app.register_clicommand_noargs(&name, system);
+        })
+    }
+    /// Add a command with args
+    ///
+    /// See [`register_clicommand_args`](CliCommandsRegisterExt::register_clicommand_args).
+    pub fn with_command_args<S, Param>(self, name: &str, system: S) -> Self
+    where
+        S: IntoSystem<In<Vec<String>>, (), Param> + Send + 'static,
+    {
+        let name = name.to_owned();
+        self.with(move |app| {
+            app.register_clicommand_args(&name, system);
+        })
+    }
+    /// Set the description of a command of the set
+    pub fn with_description(self, name: &str, description: &str) -> Self {
+        let (name, description) = (name.to_owned(), description.to_owned());
+        self.with(move |app| {
+            app.set_clicommand_description(&name, &description);
+        })
+    }
+    /// Set the usage of a command of the set
+    pub fn with_usage(self, name: &str, usage: &str) -> Self {
+        let (name, usage) = (name.to_owned(), usage.to_owned());
+        self.with(move |app| {
+            app.set_clicommand_usage(&name, &usage);
+        })
+    }
+    /// Add an alias (see [`alias`](crate::CliCommands::add_alias))
+    pub fn with_alias(mut self, alias: &str, expansion: &str) -> Self {
+        self.aliases.push(alias.to_owned());
+        let (alias, expansion) = (alias.to_owned(), expansion.to_owned());
+        self.with(move |app| {
+            app.world_mut()
+                .get_resource_or_insert_with(CliCommands::default)
+                .add_alias(&alias, &expansion);
+        })
+    }
+    /// Add a console variable
+    ///
+    /// Requires the `cvars` cargo feature. See
+    /// [`register_clivar`](CliVarsRegisterExt::register_clivar).
+    #[cfg(feature = "cvars")]
+    pub fn with_clivar<T: CliVarValue>(mut self, name: &str, default: T) -> Self {
+        self.vars.push(name.to_owned());
+        let name = name.to_owned();
+        self.with(move |app| {
+            app.register_clivar(&name, default);
+        })
+    }
+}
+
+impl Plugin for CliCommandSet {
+    fn build(&self, app: &mut App) {
+        let entries = std::mem::take(&mut *self.entries.lock().unwrap());
+        app.with_clicommand_owner(&self.name, |app| {
+            for entry in entries {
+                entry(app);
+            }
+        });
+        let contents = CliCommandSetContents {
+            aliases: self.aliases.clone(),
+            #[cfg(feature = "cvars")]
+            vars: self.vars.clone(),
+        };
+        app.world_mut()
+            .get_resource_or_insert_with(CliCommandSets::default)
+            .sets
+            .insert(self.name.clone(), contents);
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Provides a method for removing a [`CliCommandSet`]
+pub trait CliCommandSetExt {
+    /// Remove the commands, aliases and console variables added by a set
+    ///
+    /// This also removes any other commands registered with the set's name
+    /// as their owner.
+    fn remove_cli_command_set(&mut self, name: &str) -> &mut Self;
+}
+
+impl CliCommandSetExt for World {
+    fn remove_cli_command_set(&mut self, name: &str) -> &mut Self {
+        let contents = self
+            .get_resource_mut::<CliCommandSets>()
+            .and_then(|mut sets| sets.sets.remove(name));
+        let commands: Vec<String> = self
+            .get_resource::<CliCommands>()
+            .map(|clicommands| clicommands.iter_owned_by(name).map(String::from).collect())
+            .unwrap_or_default();
+        if contents.is_none() && commands.is_empty() {
+            error!("Cannot remove unknown CliCommandSet {:?}!", name);
+            return self;
+        }
+        for command in &commands {
+            self.unregister_clicommand(command);
+        }
+        let contents = contents.unwrap_or_default();
+        if let Some(mut clicommands) = self.get_resource_mut::<CliCommands>() {
+            for alias in &contents.aliases {
+                clicommands.remove_alias(alias);
+            }
+        }
+        #[cfg(feature = "cvars")]
+        if let Some(mut vars) = self.get_resource_mut::<CliVars>() {
+            for var in &contents.vars {
+                vars.remove(var);
+            }
+        }
+        self
+    }
+}
+
+impl CliCommandSetExt for App {
+    fn remove_cli_command_set(&mut self, name: &str) -> &mut Self {
+        self.world_mut().remove_cli_command_set(name);
+        self
+    }
+}
@@ -0,0 +1,198 @@
+//! Completing partially typed command strings, for consoles.
+//!
+//! Command, alias and console variable names are completed out of the box,
+//! and so are [mentions](crate::mention), for args starting with `@`. Commands can have custom
+//! completers for their args (entity names, asset paths, enum variants, ...),
+//! set using
+//! [`set_clicommand_completer`](crate::CliCommandsRegisterExt::set_clicommand_completer).
+
+use std::ops::Range;
+
+use bevy::prelude::*;
+
+use crate::mention::{parse_mention, CliMentions};
+use crate::parse::{parse_cli_tokens, CliParseError, CliWord};
+use crate::resolve::CliResolution;
+#[cfg(feature = "cvars")]
+use crate::vars::CliVars;
+use crate::{quote_cli_arg, CliCommands};
+
+/// A function providing candidate values for the args of a command
+///
+/// Receives the request and read-only access to the `World`. It may return
+/// all possible values; the ones not matching the typed prefix are filtered out.
+pub type CliCompleterFn = dyn Fn(&CliCompletionRequest, &World) -> Vec<String> + Send + Sync;
+
+/// What is being completed, passed to custom completers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliCompletionRequest {
+    /// The name of the command whose arg is being completed
+    pub command: String,
+    /// The args before the one being completed
+    pub args: Vec<String>,
+    /// The index of the arg being completed
+    pub arg_index: usize,
+    /// What has been typed of the arg so far
+    pub prefix: String,
+}
+
+/// A possible completion of a partially typed command string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliCompletion {
+    /// The text to replace the word being completed with (quoted if needed)
+    pub replacement: String,
+    /// The byte range in the partial command string to replace
+    pub span: Range<usize>,
+    /// Description to show alongside, if any (the command description, for names)
+    pub description: Option<String>,
+}
+
+impl CliCompletion {
+    /// Apply the completion to the partial command string it was produced from
+    pub fn apply(&self, partial_line: &str) -> String {
+        let mut line = partial_line.to_owned();
+        if partial_line.is_char_boundary(self.span.start)
+            && partial_line.is_char_boundary(self.span.end)
+            && self.span.start <= self.span.end
+            && self.span.end <= partial_line.len()
+        {
+            line.replace_range(self.span.clone(), &self.replacement);
+        }
+        line
+    }
+}
+
+impl CliCommands {
+    /// Get the possible completions of the last word of a partially typed command string
+    ///
+    /// The first word is completed with the names of registered commands,
+    /// aliases and console variables.
+    /// Args are completed using the command's completer, if it has one.
+    /// Completions are sorted.
+    pub fn complete(&self, world: &World, partial_line: &str) -> Vec<CliCompletion> {
+        let Some((mut words, current)) = split_for_completion(partial_line) else {
+            return vec![];
+        };
+        if words.is_empty() {
+            let mut names: Vec<&str> = self
+                .iter_names()
+                .filter(|name| self.is_enabled(name))
+                .chain(self.aliases.keys().map(|alias| alias.as_str()))
+                .collect();
+            #[cfg(feature = "cvars")]
+            if let Some(vars) = world.get_resource::<CliVars>() {
+                names.extend(vars.iter_names());
+            }
+            names.retain(|name| name.starts_with(current.text.as_str()));
+            names.sort_unstable();
+            names.dedup();
+            return names
+                .into_iter()
+                .map(|name| CliCompletion {
+                    replacement: quote_cli_arg(name).into_owned(),
+                    span: current.span.clone(),
+                    description: match self.get_alias(name) {
+                        Some(expansion) => Some(format!("Alias for: {}", expansion)),
+                        None => self
+                            .commands
+                            .get(name)
+                            .and_then(|cmd| cmd.description.clone()),
+                    },
+                })
+                .collect();
+        }
+        let name = words.remove(0).text;
+        let name = match self.resolve(&name) {
+            CliResolution::Found(name) => name,
+            _ => return vec![],
+        };
+        if parse_mention(&current.text).is_some() || current.text == "@" {
+            if let Some(mentions) = world
+                .get_resource::<CliMentions>()
+                .filter(|mentions| mentions.has_resolver())
+            {
+                return mentions
+                    .names(world)
+                    .into_iter()
+                    .map(|name| format!("@{}", name))
+                    .filter(|mention| mention.starts_with(current.text.as_str()))
+                    .map(|mention| CliCompletion {
+                        replacement: quote_cli_arg(&mention).into_owned(),
+                        span: current.span.clone(),
+                        description: None,
+                    })
+                    .collect();
+            }
+        }
+        let Some(completer) = self
+            .commands
+            .get(&name)
+            .and_then(|cmd| cmd.completer.as_ref())
+        else {
+            return vec![];
+        };
+        let request = CliCompletionRequest {
+            command: name,
+            arg_index: words.len(),
+            args: words.into_iter().map(|word| word.text).collect(),
+            prefix: current.text,
+        };
+        let mut candidates: Vec<String> = completer(&request, world)
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(request.prefix.as_str()))
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+            .into_iter()
+            .map(|candidate| CliCompletion {
+                replacement: quote_cli_arg(&candidate).into_owned(),
+                span: current.span.clone(),
+                description: None,
+            })
+            .collect()
+    }
+}
+
+/// Split a partial command string into the complete words, and the word being typed
+fn split_for_completion(partial_line: &str) -> Option<(Vec<CliWord>, CliWord)> {
+    let ends_with_space = partial_line
+        .chars()
+        .next_back()
+        .is_some_and(|c| c.is_ascii_whitespace());
+    match parse_cli_tokens(partial_line) {
+        Ok(mut words) => {
+            // Only the last command of a chain is being completed
+            if let Some(i) = words.iter().rposition(|word| word.operator().is_some()) {
+                words.drain(..=i);
+            }
+            let current = match words.pop() {
+                Some(word) if !ends_with_space => word,
+                other => {
+                    words.extend(other);
+                    CliWord {
+                        text: String::new(),
+                        span: partial_line.len()..partial_line.len(),
+                        quoted: false,
+                    }
+                }
+            };
+            Some((words, current))
+        }
+        Err(CliParseError::UnterminatedQuote(start)) => {
+            // Complete the word inside the open quote
+            let mut words = parse_cli_tokens(partial_line.get(..start)?).ok()?;
+            if let Some(i) = words.iter().rposition(|word| word.operator().is_some()) {
+                words.drain(..=i);
+            }
+            let text = partial_line.get(start + 1..)?.to_owned();
+            let current = CliWord {
+                text,
+                span: start..partial_line.len(),
+                quoted: true,
+            };
+            Some((words, current))
+        }
+        Err(_) => None,
+    }
+}
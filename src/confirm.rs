@@ -0,0 +1,138 @@
+//! Dangerous "console commands", which must be confirmed before they run.
+//!
+//! Requires the `confirm` cargo feature (enabled by default). Without it, commands marked
+//! dangerous run without being confirmed.
+//!
+//! A command marked with
+//! [`set_clicommand_dangerous`](crate::CliCommandsRegisterExt::set_clicommand_dangerous)
+//! does not run when it is first invoked. Instead, it fails with
+//! [`CliError::NeedsConfirmation`], and only runs if the built-in `confirm`
+//! command follows within the timeout (5 seconds, by default):
+//!
+//! ```text
+//! > wipe_save slot1
+//! CliCommand "wipe_save" is dangerous: run `confirm` within 5s to proceed!
+//! > confirm
+//! ```
+//!
+//! Passing `--force` (anywhere in the args) runs the command immediately.
+//! The flag is removed from the args the command receives.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::Instant;
+
+use crate::error::CliError;
+use crate::output::cli_error;
+use crate::status::cli_fail;
+use crate::{quote_cli_arg, CliCommands, CliCommandsRegisterExt};
+
+/// The command waiting to be confirmed
+#[derive(Resource, Debug)]
+pub struct CliConfirm {
+    /// How long `confirm` is accepted after a dangerous command
+    pub timeout: Duration,
+    pending: Option<CliPendingConfirm>,
+}
+
+#[derive(Debug)]
+struct CliPendingConfirm {
+    name: String,
+    args: Vec<String>,
+    expires: Instant,
+}
+
+impl Default for CliConfirm {
+    fn default() -> Self {
+        CliConfirm {
+            timeout: Duration::from_secs(5),
+            pending: None,
+        }
+    }
+}
+
+impl CliConfirm {
+    /// The name of the command waiting to be confirmed, if any
+    pub fn pending(&self) -> Option<&str> {
+        self.pending
+            .as_ref()
+            .filter(|pending| pending.expires > Instant::now())
+            .map(|pending| pending.name.as_str())
+    }
+    /// Forget the command waiting to be confirmed
+    pub fn cancel(&mut self) {
+        self.pending = None;
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliConfirm>();
+    app.register_clicommand_noargs("confirm", confirm)
+        .set_clicommand_description("confirm", "Run the last dangerous command");
+}
+
+/// Check if a dangerous command may run now, removing `--force` from its args
+///
+/// If not, the command is remembered for `confirm`.
+pub(crate) fn check(world: &mut World, name: &str, args: &mut Vec<String>) -> Result<(), CliError> {
+    if !is_dangerous(world, name) {
+        return Ok(());
+    }
+    let len = args.len();
+    args.retain(|arg| arg != "--force");
+    if args.len() != len {
+        return Ok(());
+    }
+    let mut confirm = world.get_resource_or_insert_with(CliConfirm::default);
+    let timeout = confirm.timeout;
+    confirm.pending = Some(CliPendingConfirm {
+        name: name.to_owned(),
+        args: std::mem::take(args),
+        expires: Instant::now() + timeout,
+    });
+    Err(CliError::NeedsConfirmation {
+        name: name.to_owned(),
+        timeout,
+    })
+}
+
+/// Whether a command needs confirmation, and so takes the `--force` flag
+pub(crate) fn is_dangerous(world: &World, name: &str) -> bool {
+    world
+        .get_resource::<CliCommands>()
+        .and_then(|clicommands| clicommands.commands.get(name))
+        .is_some_and(|cmd| cmd.dangerous)
+}
+
+/// `confirm`: run the last dangerous command
+fn confirm(world: &mut World) {
+    let pending = world
+        .get_resource_mut::<CliConfirm>()
+        .and_then(|mut confirm| confirm.pending.take());
+    let Some(pending) = pending else {
+        cli_error(world, "There is no command to confirm!");
+        cli_fail(world);
+        return;
+    };
+    if pending.expires <= Instant::now() {
+        let message = format!("Too late to confirm {:?}, run it again!", pending.name);
+        cli_error(world, message);
+        cli_fail(world);
+        return;
+    }
+    let mut line = quote_cli_arg(&pending.name).into_owned();
+    for arg in &pending.args {
+        line.push(' ');
+        line.push_str(&quote_cli_arg(arg));
+    }
+    line.push_str(" --force");
+    match crate::run_expanded_cli_line(world, &line) {
+        Ok(()) => {}
+        Err(CliError::Failed(_)) => cli_fail(world),
+        Err(e) => {
+            cli_error(world, format!("{}!", e));
+            cli_fail(world);
+        }
+    }
+}
@@ -0,0 +1,583 @@
+//! A ready-made in-game console overlay.
+//!
+//! Requires the `console` cargo feature. Add [`CliConsolePlugin`] (together
+//! with [`CliPlugin`](crate::CliPlugin)), and press the toggle key (backtick,
+//! by default) to open the console. It has a prompt for typing commands,
+//! with history (up/down arrows) and completion (tab), and shows the output
+//! of commands.
+//!
+//! Completion candidates for command names are listed by category (see
+//! [`CliCommands::command_category`](crate::CliCommands::command_category)).
+//! Categories with many candidates are collapsed into a count, until tab is
+//! pressed again.
+//!
+//! Styled spans of the output, like those written with
+//! [`CliOutput::markdown`](crate::output::CliOutput::markdown), are shown
+//! in the colors of the [`CliConsoleStyle`]. Clicking a link that runs a
+//! command string (see [`CliLink::Run`]) runs it in the console.
+//!
+//! The console is a [`CliSession`], so you can restrict what commands it can
+//! run, by inserting your own session on the [`CliConsoleRoot`] entity.
+//!
+//! While the console is open, your game probably should not react to keyboard
+//! input. Use the [`cli_console_closed`] run condition for that.
+//!
+//! With the `a11y` cargo feature, the console exposes AccessKit nodes for
+//! screen readers: the prompt is a text input, and the output is a live
+//! region, so new lines are announced. Every line of output is also sent
+//! as a [`CliConsoleAnnouncement`] event, for custom integrations.
+
+use std::collections::VecDeque;
+
+#[cfg(feature = "a11y")]
+use accesskit::{Live, Node as AccessKitNode, Role};
+#[cfg(feature = "a11y")]
+use bevy::a11y::AccessibilityNode;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use bevy::text::{ComputedTextBlock, TextLayoutInfo};
+use bevy::window::PrimaryWindow;
+
+use crate::output::{CliLink, CliOutputLine, CliOutputLog, CliTextStyle};
+use crate::prompt::{CliAnswerPromptCommand, CliPrompt};
+use crate::session::{complete_in_session, CliRunInSessionCommand, CliSession};
+
+/// Plugin that adds a toggleable console overlay
+pub struct CliConsolePlugin {
+    /// The key that opens and closes the console
+    pub toggle_key: KeyCode,
+}
+
+impl Default for CliConsolePlugin {
+    fn default() -> Self {
+        CliConsolePlugin {
+            toggle_key: KeyCode::Backquote,
+        }
+    }
+}
+
+impl Plugin for CliConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CliConsoleStyle>();
+        app.insert_resource(CliConsoleState {
+            open: false,
+            toggle_key: self.toggle_key,
+            scrollback: VecDeque::new(),
+            listed_completion: None,
+        });
+        #[cfg(feature = "a11y")]
+        app.add_event::<CliConsoleAnnouncement>();
+        app.add_systems(Startup, spawn_console);
+        app.add_systems(
+            Update,
+            (
+                toggle_console,
+                console_input,
+                collect_output,
+                click_link,
+                update_console,
+            )
+                .chain()
+                .in_set(CliConsoleSet),
+        );
+        #[cfg(feature = "a11y")]
+        app.add_systems(
+            Update,
+            update_accessibility
+                .after(update_console)
+                .in_set(CliConsoleSet),
+        );
+    }
+}
+
+/// System set for the systems that run the console overlay
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CliConsoleSet;
+
+/// Appearance of the console overlay
+///
+/// Can be changed at any time.
+#[derive(Resource, Debug, Clone)]
+pub struct CliConsoleStyle {
+    pub background: Color,
+    pub text_color: Color,
+    pub prompt_color: Color,
+    /// The color of strong text, headings and highlights
+    pub strong_color: Color,
+    /// The color of code, like the names of commands
+    pub code_color: Color,
+    pub link_color: Color,
+    pub font: TextFont,
+    /// Height of the overlay, from the top of the window
+    pub height: Val,
+    /// How many lines of output to show
+    pub visible_lines: usize,
+    /// How many lines of output to remember
+    pub max_scrollback: usize,
+}
+
+impl Default for CliConsoleStyle {
+    fn default() -> Self {
+        CliConsoleStyle {
+            background: Color::srgba(0.05, 0.05, 0.08, 0.9),
+            text_color: Color::srgb(0.85, 0.85, 0.85),
+            prompt_color: Color::srgb(1.0, 0.85, 0.4),
+            strong_color: Color::WHITE,
+            code_color: Color::srgb(0.6, 0.85, 1.0),
+            link_color: Color::srgb(0.45, 0.65, 1.0),
+            font: TextFont {
+                font_size: 16.0,
+                ..default()
+            },
+            height: Val::Percent(40.0),
+            visible_lines: 20,
+            max_scrollback: 1000,
+        }
+    }
+}
+
+/// The state of the console overlay
+#[derive(Resource, Debug)]
+pub struct CliConsoleState {
+    open: bool,
+    toggle_key: KeyCode,
+    scrollback: VecDeque<CliOutputLine>,
+    /// The input line whose completion candidates were listed last
+    listed_completion: Option<String>,
+}
+
+impl CliConsoleState {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+    /// The lines shown in the console, oldest first
+    pub fn scrollback(&self) -> impl Iterator<Item = &str> {
+        self.scrollback.iter().map(|line| line.text.as_str())
+    }
+    pub fn clear_scrollback(&mut self) {
+        self.scrollback.clear();
+    }
+    fn push_line(&mut self, line: CliOutputLine, max: usize) {
+        self.scrollback.push_back(line);
+        while self.scrollback.len() > max {
+            self.scrollback.pop_front();
+        }
+    }
+}
+
+/// Run condition that is true while the console is open
+pub fn cli_console_open(state: Option<Res<CliConsoleState>>) -> bool {
+    state.is_some_and(|state| state.open)
+}
+
+/// Run condition that is true while the console is closed
+pub fn cli_console_closed(state: Option<Res<CliConsoleState>>) -> bool {
+    !cli_console_open(state)
+}
+
+/// Event sent for every line of output shown in the console
+///
+/// Requires the `a11y` cargo feature. Meant for forwarding the output to
+/// screen readers or text-to-speech by other means than AccessKit.
+#[cfg(feature = "a11y")]
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct CliConsoleAnnouncement {
+    pub text: String,
+}
+
+/// Marker for the root entity of the console overlay, which has the [`CliSession`]
+#[derive(Component)]
+pub struct CliConsoleRoot;
+
+#[derive(Component)]
+struct CliConsoleScrollbackText;
+
+#[derive(Component)]
+struct CliConsolePromptText;
+
+/// The command string run by clicking a span of the scrollback
+#[derive(Component)]
+struct CliConsoleLink(String);
+
+fn spawn_console(mut commands: Commands, style: Res<CliConsoleStyle>) {
+    commands
+        .spawn((
+            CliConsoleRoot,
+            CliSession::default(),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(0.0),
+                left: Val::Px(0.0),
+                width: Val::Percent(100.0),
+                height: style.height,
+                padding: UiRect::all(Val::Px(8.0)),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::FlexEnd,
+                overflow: Overflow::clip(),
+                ..default()
+            },
+            BackgroundColor(style.background),
+            GlobalZIndex(i32::MAX),
+            Visibility::Hidden,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                CliConsoleScrollbackText,
+                Text::new(""),
+                style.font.clone(),
+                TextColor(style.text_color),
+                scrollback_accessibility(),
+            ));
+            parent.spawn((
+                CliConsolePromptText,
+                Text::new("> "),
+                style.font.clone(),
+                TextColor(style.prompt_color),
+                prompt_accessibility(),
+            ));
+        });
+}
+
+#[cfg(feature = "a11y")]
+fn scrollback_accessibility() -> AccessibilityNode {
+    let mut node = AccessKitNode::new(Role::Log);
+    node.set_label("Console output");
+    node.set_live(Live::Polite);
+    AccessibilityNode::from(node)
+}
+
+#[cfg(not(feature = "a11y"))]
+fn scrollback_accessibility() {}
+
+#[cfg(feature = "a11y")]
+fn prompt_accessibility() -> AccessibilityNode {
+    let mut node = AccessKitNode::new(Role::TextInput);
+    node.set_label("Console input");
+    AccessibilityNode::from(node)
+}
+
+#[cfg(not(feature = "a11y"))]
+fn prompt_accessibility() {}
+
+fn toggle_console(kbd: Res<ButtonInput<KeyCode>>, mut state: ResMut<CliConsoleState>) {
+    if kbd.just_pressed(state.toggle_key) {
+        state.toggle();
+    }
+}
+
+fn console_input(
+    mut commands: Commands,
+    mut evr_kbd: EventReader<KeyboardInput>,
+    mut state: ResMut<CliConsoleState>,
+    style: Res<CliConsoleStyle>,
+    prompt: Option<Res<CliPrompt>>,
+    mut q_session: Query<(Entity, &mut CliSession), With<CliConsoleRoot>>,
+) {
+    if !state.open {
+        evr_kbd.clear();
+        return;
+    }
+    let Ok((entity, mut session)) = q_session.get_single_mut() else {
+        return;
+    };
+    for ev in evr_kbd.read() {
+        if ev.state == ButtonState::Released || ev.key_code == state.toggle_key {
+            continue;
+        }
+        match (&ev.key_code, &ev.logical_key) {
+            (KeyCode::Enter | KeyCode::NumpadEnter, _) => {
+                if let Some(request) = prompt.as_ref().and_then(|prompt| prompt.request()) {
+                    let answer = session.take_input();
+                    let line = prompt_line(request.message(), &answer, request.is_secret());
+                    state.push_line(CliOutputLine::new(line), style.max_scrollback);
+                    commands.queue(CliAnswerPromptCommand(answer));
+                } else if let Some(line) = session.submit() {
+                    state.push_line(
+                        CliOutputLine::new(format!("> {}", line)),
+                        style.max_scrollback,
+                    );
+                    commands.queue(CliRunInSessionCommand {
+                        session: entity,
+                        cli: line,
+                    });
+                }
+            }
+            (KeyCode::Escape, _) => {
+                state.close();
+            }
+            (KeyCode::Tab, _) => {
+                commands.queue(CliCompleteConsoleCommand(entity));
+            }
+            (KeyCode::Backspace, _) => {
+                session.input_mut().pop();
+            }
+            (KeyCode::ArrowUp, _) => {
+                session.history_prev();
+            }
+            (KeyCode::ArrowDown, _) => {
+                session.history_next();
+            }
+            (_, Key::Space) => {
+                session.input_mut().push(' ');
+            }
+            (_, Key::Character(s)) => {
+                session.input_mut().push_str(s.as_str());
+            }
+            _ => {}
+        }
+    }
+}
+
+fn collect_output(
+    log: Option<ResMut<CliOutputLog>>,
+    mut state: ResMut<CliConsoleState>,
+    style: Res<CliConsoleStyle>,
+    #[cfg(feature = "a11y")] mut evw_announce: EventWriter<CliConsoleAnnouncement>,
+) {
+    let Some(mut log) = log else {
+        return;
+    };
+    for line in log.drain() {
+        #[cfg(feature = "a11y")]
+        evw_announce.send(CliConsoleAnnouncement {
+            text: line.text.clone(),
+        });
+        state.push_line(line, style.max_scrollback);
+    }
+}
+
+/// Run the command string of the link under the mouse, when clicked
+#[allow(clippy::type_complexity)]
+fn click_link(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut state: ResMut<CliConsoleState>,
+    style: Res<CliConsoleStyle>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    q_scrollback: Query<
+        (
+            &Parent,
+            &ComputedNode,
+            &GlobalTransform,
+            &ComputedTextBlock,
+            &TextLayoutInfo,
+        ),
+        With<CliConsoleScrollbackText>,
+    >,
+    q_links: Query<&CliConsoleLink>,
+) {
+    if !state.open || !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(cursor) = q_window
+        .get_single()
+        .ok()
+        .and_then(|window| window.physical_cursor_position())
+    else {
+        return;
+    };
+    // The root of the console, with the session, is the parent
+    let Ok((root, node, transform, block, layout)) = q_scrollback.get_single() else {
+        return;
+    };
+    // Glyphs are positioned (by their center) from the top left of the node
+    let top_left = transform.translation().truncate() - node.size() / 2.0;
+    let cursor = cursor - top_left;
+    let glyph = layout
+        .glyphs
+        .iter()
+        .find(|glyph| Rect::from_center_size(glyph.position, glyph.size).contains(cursor));
+    let link = glyph
+        .and_then(|glyph| block.entities().get(glyph.span_index))
+        .and_then(|span| q_links.get(span.entity).ok());
+    if let Some(CliConsoleLink(cli)) = link {
+        state.push_line(
+            CliOutputLine::new(format!("> {}", cli)),
+            style.max_scrollback,
+        );
+        commands.queue(CliRunInSessionCommand {
+            session: root.get(),
+            cli: cli.clone(),
+        });
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_console(
+    mut commands: Commands,
+    state: Res<CliConsoleState>,
+    style: Res<CliConsoleStyle>,
+    prompt: Option<Res<CliPrompt>>,
+    mut q_root: Query<
+        (
+            &mut Visibility,
+            &mut Node,
+            &mut BackgroundColor,
+            &CliSession,
+        ),
+        With<CliConsoleRoot>,
+    >,
+    q_scrollback: Query<
+        Entity,
+        (
+            With<CliConsoleScrollbackText>,
+            Without<CliConsolePromptText>,
+        ),
+    >,
+    mut q_prompt: Query<
+        (&mut Text, &mut TextFont, &mut TextColor),
+        (
+            With<CliConsolePromptText>,
+            Without<CliConsoleScrollbackText>,
+        ),
+    >,
+) {
+    let Ok((mut visibility, mut node, mut background, session)) = q_root.get_single_mut() else {
+        return;
+    };
+    *visibility = if state.open {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    if !state.open {
+        return;
+    }
+    if style.is_changed() {
+        node.height = style.height;
+        background.0 = style.background;
+    }
+    if let Ok(entity) = q_scrollback.get_single() {
+        if state.is_changed() || style.is_changed() {
+            // Every styled part of the lines is a span
+            let skip = state.scrollback.len().saturating_sub(style.visible_lines);
+            let mut entity = commands.entity(entity);
+            entity.despawn_descendants();
+            entity.with_children(|parent| {
+                for (i, line) in state.scrollback.iter().skip(skip).enumerate() {
+                    if i > 0 {
+                        parent.spawn((TextSpan::new("\n"), style.font.clone()));
+                    }
+                    for (text, span) in line.segment_spans() {
+                        let color = match span.map(|span| span.style) {
+                            None | Some(CliTextStyle::Emphasis) => style.text_color,
+                            Some(CliTextStyle::Strong)
+                            | Some(CliTextStyle::Heading)
+                            | Some(CliTextStyle::Highlight) => style.strong_color,
+                            Some(CliTextStyle::Code) => style.code_color,
+                            Some(CliTextStyle::Link) => style.link_color,
+                        };
+                        let mut span_entity = parent.spawn((
+                            TextSpan::new(text),
+                            style.font.clone(),
+                            TextColor(color),
+                        ));
+                        if let Some(CliLink::Run(cli)) = span.and_then(|span| span.link.as_ref()) {
+                            span_entity.insert(CliConsoleLink(cli.clone()));
+                        }
+                    }
+                }
+            });
+        }
+    }
+    if let Ok((mut text, mut font, mut color)) = q_prompt.get_single_mut() {
+        let line = match prompt.as_ref().and_then(|prompt| prompt.request()) {
+            Some(request) => prompt_line(request.message(), session.input(), request.is_secret()),
+            None => format!("> {}", session.input()),
+        };
+        let line = line + "_";
+        if text.0 != line {
+            text.0 = line;
+        }
+        if style.is_changed() {
+            *font = style.font.clone();
+            color.0 = style.prompt_color;
+        }
+    }
+}
+
+/// Keep the values of the AccessKit nodes in sync with the console
+#[cfg(feature = "a11y")]
+fn update_accessibility(
+    state: Res<CliConsoleState>,
+    style: Res<CliConsoleStyle>,
+    q_root: Query<&CliSession, With<CliConsoleRoot>>,
+    mut q_scrollback: Query<
+        &mut AccessibilityNode,
+        (
+            With<CliConsoleScrollbackText>,
+            Without<CliConsolePromptText>,
+        ),
+    >,
+    mut q_prompt: Query<&mut AccessibilityNode, With<CliConsolePromptText>>,
+) {
+    if !state.open {
+        return;
+    }
+    if state.is_changed() {
+        if let Ok(mut node) = q_scrollback.get_single_mut() {
+            let skip = state.scrollback.len().saturating_sub(style.visible_lines);
+            let lines: Vec<&str> = state.scrollback().skip(skip).collect();
+            node.set_value(lines.join("\n"));
+        }
+    }
+    let (Ok(session), Ok(mut node)) = (q_root.get_single(), q_prompt.get_single_mut()) else {
+        return;
+    };
+    if node.value() != Some(session.input()) {
+        node.set_value(session.input());
+    }
+}
+
+/// The line showing a question and its answer, hiding secret answers
+fn prompt_line(message: &str, answer: &str, secret: bool) -> String {
+    if secret {
+        format!("{} {}", message, "*".repeat(answer.chars().count()))
+    } else {
+        format!("{} {}", message, answer)
+    }
+}
+
+/// Categories with more completion candidates than this are collapsed
+const COLLAPSED_CANDIDATES: usize = 8;
+
+/// Complete the input line of the console
+struct CliCompleteConsoleCommand(Entity);
+
+impl bevy::ecs::world::Command for CliCompleteConsoleCommand {
+    fn apply(self, world: &mut World) {
+        let Some(completion) = complete_in_session(world, self.0) else {
+            return;
+        };
+        if !completion.candidates.is_empty() {
+            let max = world.resource::<CliConsoleStyle>().max_scrollback;
+            let mut state = world.resource_mut::<CliConsoleState>();
+            // Pressing tab again, without typing, expands the categories
+            let expand = state.listed_completion.as_ref() == Some(&completion.input);
+            state.listed_completion = Some(completion.input.clone());
+            for (category, names) in &completion.candidates {
+                let line = match category {
+                    None => names.join("  "),
+                    Some(category) if expand || names.len() <= COLLAPSED_CANDIDATES => {
+                        format!("[{}] {}", category, names.join("  "))
+                    }
+                    Some(category) => format!("[{}] ({} more, tab to show)", category, names.len()),
+                };
+                state.push_line(CliOutputLine::new(line), max);
+            }
+        }
+        if let Some(mut session) = world.get_mut::<CliSession>(self.0) {
+            *session.input_mut() = completion.input;
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Diagnostics about "console command" execution and queue pressure.
+
+use std::time::Duration;
+
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+use bevy::prelude::*;
+use bevy::utils::Instant;
+
+use crate::anchor::{CliAnchorQueue, CliAnchorSet};
+use crate::fixed::CliFixedQueue;
+use crate::queue::CliQueue;
+
+/// Plugin that reports diagnostics about "console command" execution
+///
+/// Useful to catch scripts or remote clients flooding the command queues.
+/// The measurements are taken once per frame, in [`Last`].
+pub struct CliDiagnosticsPlugin;
+
+impl CliDiagnosticsPlugin {
+    /// How many commands are waiting in the queues (queued, anchored and tick-stamped)
+    pub const QUEUE_DEPTH: DiagnosticPath = DiagnosticPath::const_new("cli/queue_depth");
+    /// How many commands were executed during the frame
+    pub const COMMANDS_PER_FRAME: DiagnosticPath =
+        DiagnosticPath::const_new("cli/commands_per_frame");
+    /// Average time (in milliseconds) from submission to completion, for
+    /// commands queued with [`run_cli_at`](crate::CliCommandsRunExt::run_cli_at)
+    /// that completed during the frame
+    pub const QUEUE_LATENCY: DiagnosticPath = DiagnosticPath::const_new("cli/queue_latency");
+}
+
+impl Plugin for CliDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CliExecStats>();
+        app.register_diagnostic(Diagnostic::new(Self::QUEUE_DEPTH));
+        app.register_diagnostic(Diagnostic::new(Self::COMMANDS_PER_FRAME));
+        app.register_diagnostic(Diagnostic::new(Self::QUEUE_LATENCY).with_suffix("ms"));
+        app.add_systems(Last, measure.after(CliAnchorSet));
+    }
+}
+
+/// Counters accumulated during the frame, while diagnostics are enabled
+#[derive(Resource, Default)]
+pub(crate) struct CliExecStats {
+    executed: u32,
+    latencies: Vec<Duration>,
+}
+
+pub(crate) fn record_execution(world: &mut World) {
+    if let Some(mut stats) = world.get_resource_mut::<CliExecStats>() {
+        stats.executed += 1;
+    }
+}
+
+pub(crate) fn record_queue_latency(world: &mut World, submitted: Instant) {
+    if let Some(mut stats) = world.get_resource_mut::<CliExecStats>() {
+        stats.latencies.push(submitted.elapsed());
+    }
+}
+
+fn measure(
+    mut diagnostics: Diagnostics,
+    mut stats: ResMut<CliExecStats>,
+    anchored: Option<Res<CliAnchorQueue>>,
+    fixed: Option<Res<CliFixedQueue>>,
+    queued: Option<Res<CliQueue>>,
+) {
+    let depth = anchored.map(|q| q.len()).unwrap_or(0)
+        + fixed.map(|q| q.len()).unwrap_or(0)
+        + queued.map(|q| q.len()).unwrap_or(0);
+    diagnostics.add_measurement(&CliDiagnosticsPlugin::QUEUE_DEPTH, || depth as f64);
+    let executed = stats.executed;
+    diagnostics.add_measurement(&CliDiagnosticsPlugin::COMMANDS_PER_FRAME, || {
+        executed as f64
+    });
+    if !stats.latencies.is_empty() {
+        let total: Duration = stats.latencies.iter().sum();
+        let avg = total.as_secs_f64() * 1000.0 / stats.latencies.len() as f64;
+        diagnostics.add_measurement(&CliDiagnosticsPlugin::QUEUE_LATENCY, || avg);
+    }
+    stats.executed = 0;
+    stats.latencies.clear();
+}
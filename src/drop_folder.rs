@@ -0,0 +1,159 @@
+//! Running command files dropped into a folder, for driving a running app
+//! from external tools.
+//!
+//! Requires the `drop-folder` cargo feature. Add [`CliDropFolderPlugin`]
+//! (together with [`CliPlugin`](crate::CliPlugin)), and the folder is
+//! checked regularly for new `.cli` files. Each file is run like a script:
+//! one command string per line, ignoring empty lines and lines starting with
+//! `#`. The file stops at the first command that fails.
+//!
+//! After running, the file is moved into the `done` subfolder, next to a
+//! `.result` file with the same name. Its first line is `ok` or `failed`,
+//! and the other lines are the output of the commands:
+//!
+//! ```text
+//! drop/
+//!     done/
+//!         build_42.cli
+//!         build_42.result
+//! ```
+//!
+//! Files are run in the order of their names. To avoid running a file that
+//! is still being written, write it with another extension, then rename it.
+//! Output printed later (for example, by commands that finish over several
+//! frames) is not in the result file.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::output::CliCommandsOutputExt;
+use crate::status::CliStatus;
+
+/// Plugin that runs `.cli` files dropped into a folder
+pub struct CliDropFolderPlugin {
+    /// The folder to watch; it is created if it does not exist
+    pub path: PathBuf,
+    /// How often to check for new files
+    pub interval: Duration,
+}
+
+impl CliDropFolderPlugin {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        CliDropFolderPlugin {
+            path: path.into(),
+            interval: Duration::from_secs(1),
+        }
+    }
+}
+
+impl Plugin for CliDropFolderPlugin {
+    fn build(&self, app: &mut App) {
+        let done = self.path.join("done");
+        if let Err(e) = fs::create_dir_all(&done) {
+            error!(
+                "Cannot watch CLI drop folder {}: {}",
+                self.path.display(),
+                e
+            );
+            return;
+        }
+        info!("Watching CLI drop folder {}", self.path.display());
+        app.insert_resource(CliDropFolder {
+            path: self.path.clone(),
+            timer: Timer::new(self.interval, TimerMode::Repeating),
+        });
+        app.add_systems(PreUpdate, poll_drop_folder);
+    }
+}
+
+/// The folder watched for `.cli` files
+#[derive(Resource, Debug)]
+pub struct CliDropFolder {
+    path: PathBuf,
+    timer: Timer,
+}
+
+impl CliDropFolder {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+fn poll_drop_folder(world: &mut World) {
+    let delta = world
+        .get_resource::<Time<Real>>()
+        .map_or(Duration::ZERO, |time| time.delta());
+    let mut folder = world.resource_mut::<CliDropFolder>();
+    if !folder.timer.tick(delta).just_finished() {
+        return;
+    }
+    let path = folder.path.clone();
+    let entries = match fs::read_dir(&path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!("Cannot read CLI drop folder {}: {}", path.display(), e);
+            return;
+        }
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|file| file.is_file() && file.extension().is_some_and(|ext| ext == "cli"))
+        .collect();
+    files.sort();
+    for file in files {
+        run_dropped_file(world, &path, &file);
+    }
+}
+
+/// Run a dropped file, then move it to `done`, next to its result
+fn run_dropped_file(world: &mut World, folder: &Path, file: &Path) {
+    let contents = match fs::read_to_string(file) {
+        Ok(contents) => contents,
+        Err(e) => {
+            error!("Cannot read CLI file {}: {}", file.display(), e);
+            return;
+        }
+    };
+    info!("Running dropped CLI file {}", file.display());
+    let mut success = true;
+    let mut output = Vec::new();
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        output.extend(world.run_cli_captured(line).into_iter().map(|l| l.text));
+        success = world
+            .get_resource::<CliStatus>()
+            .is_some_and(|status| status.last_success() == Some(true));
+        if !success {
+            break;
+        }
+    }
+    let done = folder.join("done");
+    let Some(name) = file.file_name() else {
+        return;
+    };
+    let mut result = String::from(if success { "ok\n" } else { "failed\n" });
+    for line in output {
+        result.push_str(&line);
+        result.push('\n');
+    }
+    let result_path = done.join(name).with_extension("result");
+    if let Err(e) = fs::write(&result_path, result) {
+        error!("Cannot write {}: {}", result_path.display(), e);
+    }
+    if let Err(e) = fs::rename(file, done.join(name)) {
+        error!(
+            "Cannot move {} to {}: {}",
+            file.display(),
+            done.display(),
+            e
+        );
+        // Do not run it again
+        let _ = fs::remove_file(file);
+    }
+}
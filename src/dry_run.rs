@@ -0,0 +1,255 @@
+//! Checking command strings without running them.
+//!
+//! [`run_cli_dry`] does everything running a command string would do up to
+//! the point of running the commands: it parses the string, expands
+//! variables and aliases, resolves abbreviated and namespaced names, and
+//! checks that each command exists, can run now, and that its args pass its
+//! arity and arg checks (see [`arity`](crate::arity)) and match its
+//! signature, if it has one (see [`args`](crate::args)). It returns a
+//! [`CliPlan`] of what would run, or the first error found. Use it to report
+//! mistakes in scripts and keybind configs when they are loaded, rather than
+//! when they run:
+//!
+//! ```rust,ignore
+//! for (key, cli) in bindings.iter() {
+//!     if let Err(e) = run_cli_dry(world, cli) {
+//!         warn!("Key {:?} is bound to a bad command string {:?}: {}", key, cli, e);
+//!     }
+//! }
+//! ```
+//!
+//! Run conditions of commands are evaluated, so a command that is disabled
+//! now is reported with [`CliError::ConditionNotMet`]. Everything else is
+//! only looked at: hooks and interceptors (see [`intercept`](crate::intercept))
+//! are not run, entity selectors are not resolved, and cooldowns and quotas
+//! are not checked. All commands of a chain are checked, whatever their
+//! operators, but the args of commands receiving piped output or containing
+//! command substitutions (`$(...)`) are not, because that output is not
+//! known. The command strings of substitutions are planned before the
+//! command containing them. An alias that expands into itself is
+//! reported with [`CliError::Failed`].
+
+use bevy::prelude::*;
+
+#[cfg(feature = "confirm")]
+use crate::confirm;
+#[cfg(feature = "env")]
+use crate::env;
+use crate::error::CliError;
+use crate::explain;
+use crate::namespace::NAMESPACE_SEPARATOR;
+use crate::resolve::{self, CliResolution};
+use crate::route::CliRouter;
+use crate::session::{self, CliSession};
+use crate::status::CliStatus;
+#[cfg(feature = "cvars")]
+use crate::vars::CliVars;
+use crate::{
+    check_args, check_runnable, check_signature, parse_cli, parse_cli_chain, quote_cli_arg,
+    CliChainOp, CliCommands,
+};
+
+/// What a command string would run
+///
+/// Returned by [`run_cli_dry`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CliPlan {
+    /// The commands, in order, with aliases expanded
+    pub steps: Vec<CliPlanStep>,
+}
+
+/// A command that a command string would run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliPlanStep {
+    /// The operator before this command (`None` for the first command)
+    pub op: Option<CliChainOp>,
+    /// The name as typed (after alias expansion)
+    pub typed_name: String,
+    pub target: CliPlanTarget,
+    /// The args, after variable expansion
+    pub args: Vec<String>,
+    /// The aliases this command was expanded from, outermost first
+    pub aliases: Vec<String>,
+}
+
+/// What runs for a [`CliPlanStep`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliPlanTarget {
+    /// A command, by its full name
+    Command(String),
+    /// A console variable, shown or set (see [`vars`](crate::vars))
+    #[cfg(feature = "cvars")]
+    Var(String),
+    /// The subcommands of a namespace, listed
+    Namespace(String),
+    /// A procedure of the session (see [`session`](crate::session))
+    Proc(String),
+    /// A route, by its tag (see [`route`](crate::route))
+    Route(String),
+    /// The fallback command (see
+    /// [`set_clicommand_fallback`](crate::CliCommandsRegisterExt::set_clicommand_fallback))
+    Fallback,
+}
+
+impl CliPlan {
+    /// The full names of the commands that would run, in order
+    pub fn commands(&self) -> impl Iterator<Item = &str> {
+        self.steps.iter().filter_map(|step| match &step.target {
+            CliPlanTarget::Command(name) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+}
+
+/// Check what a command string would run, without running anything
+///
+/// See [`dry_run`](crate::dry_run).
+pub fn run_cli_dry(world: &mut World, cli: &str) -> Result<CliPlan, CliError> {
+    let mut plan = CliPlan::default();
+    plan_chain(world, cli, None, &[], &mut plan)?;
+    Ok(plan)
+}
+
+fn plan_chain(
+    world: &mut World,
+    cli: &str,
+    op: Option<CliChainOp>,
+    aliases: &[String],
+    plan: &mut CliPlan,
+) -> Result<(), CliError> {
+    let chain = parse_cli_chain(cli)?;
+    for (i, link) in chain.links().iter().enumerate() {
+        let segment = &cli[link.span.clone()];
+        // Command substitutions run first, and their output is not known
+        #[cfg(feature = "env")]
+        let substitutions = env::substitutions(segment);
+        #[cfg(not(feature = "env"))]
+        let substitutions: Vec<std::ops::Range<usize>> = Vec::new();
+        for inner in &substitutions {
+            plan_chain(world, &segment[inner.clone()], None, aliases, plan)?;
+        }
+        #[cfg(feature = "env")]
+        let expanded = env::expand_vars(world, segment).into_owned();
+        #[cfg(not(feature = "env"))]
+        let expanded = segment.to_owned();
+        // The operator of an alias' first command is the one before the alias
+        let link_op = if i == 0 { op } else { link.op };
+        let piped = link.op == Some(CliChainOp::Pipe);
+        let check = !piped && substitutions.is_empty();
+        plan_line(world, &expanded, link_op, aliases, check, plan)?;
+    }
+    Ok(())
+}
+
+fn plan_line(
+    world: &mut World,
+    cli: &str,
+    op: Option<CliChainOp>,
+    aliases: &[String],
+    check: bool,
+    plan: &mut CliPlan,
+) -> Result<(), CliError> {
+    let mut parsed = parse_cli(cli)?;
+    // Flags handled by the crate, which the args are checked without
+    explain::take_flag(&mut parsed);
+    parsed.take_flag("--page");
+    let typed_name = parsed.name;
+    #[cfg_attr(not(feature = "confirm"), allow(unused_mut))]
+    let mut args = parsed.args;
+    let step = |target: CliPlanTarget, args: Vec<String>| CliPlanStep {
+        op,
+        typed_name: typed_name.clone(),
+        target,
+        args,
+        aliases: aliases.to_vec(),
+    };
+
+    if let Some(tag) = typed_name.strip_prefix('@') {
+        plan.steps
+            .push(step(CliPlanTarget::Route(tag.to_owned()), args));
+        return Ok(());
+    }
+    let router = world.get_resource::<CliRouter>();
+    if let Some(tag) = router.and_then(|router| router.command_route(&typed_name)) {
+        plan.steps
+            .push(step(CliPlanTarget::Route(tag.to_owned()), args));
+        return Ok(());
+    }
+    let session = world.get_resource::<CliStatus>().and_then(|s| s.session);
+    let proc = session
+        .and_then(|entity| world.get::<CliSession>(entity))
+        .is_some_and(|session| session.get_proc(&typed_name).is_some());
+    if proc {
+        plan.steps
+            .push(step(CliPlanTarget::Proc(typed_name.clone()), args));
+        return Ok(());
+    }
+
+    let clicommands = world.resource::<CliCommands>();
+    if let Some(expansion) = session::get_alias(world, &typed_name) {
+        let cycle = aliases.contains(&typed_name);
+        if !cycle || !clicommands.command_available(&typed_name) {
+            if cycle {
+                return Err(CliError::Failed(typed_name.clone()));
+            }
+            let mut line = expansion.to_owned();
+            for arg in &args {
+                line.push(' ');
+                line.push_str(&quote_cli_arg(arg));
+            }
+            let mut aliases = aliases.to_vec();
+            aliases.push(typed_name.clone());
+            return plan_chain(world, &line, op, &aliases, plan);
+        }
+    }
+    if !clicommands.command_available(&typed_name) && clicommands.is_namespace(&typed_name) {
+        let Some((subcommand, args)) = args.split_first() else {
+            let target = CliPlanTarget::Namespace(typed_name.clone());
+            plan.steps.push(step(target, Vec::new()));
+            return Ok(());
+        };
+        let mut line = format!("{}{}{}", typed_name, NAMESPACE_SEPARATOR, subcommand);
+        for arg in args {
+            line.push(' ');
+            line.push_str(&quote_cli_arg(arg));
+        }
+        return plan_line(world, &line, op, aliases, check, plan);
+    }
+
+    let name = match resolve::resolve_command(world, &typed_name) {
+        CliResolution::Found(name) => name,
+        CliResolution::Ambiguous(candidates) => {
+            return Err(CliError::Ambiguous {
+                name: typed_name.clone(),
+                candidates,
+            });
+        }
+        CliResolution::NotFound => {
+            #[cfg(feature = "cvars")]
+            if world
+                .get_resource::<CliVars>()
+                .is_some_and(|vars| vars.contains(&typed_name))
+            {
+                plan.steps
+                    .push(step(CliPlanTarget::Var(typed_name.clone()), args));
+                return Ok(());
+            }
+            if world.resource::<CliCommands>().fallback.is_some() {
+                plan.steps.push(step(CliPlanTarget::Fallback, args));
+                return Ok(());
+            }
+            return Err(CliError::NotFound(typed_name.clone()));
+        }
+    };
+    check_runnable(world, &name)?;
+    #[cfg(feature = "confirm")]
+    if confirm::is_dangerous(world, &name) {
+        args.retain(|arg| arg != "--force");
+    }
+    if check {
+        check_args(world, &name, &args)?;
+        check_signature(world, &name, &args)?;
+    }
+    plan.steps.push(step(CliPlanTarget::Command(name), args));
+    Ok(())
+}
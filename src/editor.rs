@@ -0,0 +1,133 @@
+//! Multi-line text input for "console commands".
+//!
+//! Requires the `editor` cargo feature (enabled by default).
+//!
+//! Some commands take payloads that are painful to type on one line, like
+//! RON values with nested quotes. Such commands can ask the console
+//! frontend for an editor instead, by calling [`CliEditor::open`] with the
+//! command to run and the initial text. The frontend shows a multi-line
+//! editor, and when the text is submitted, the command runs with the text
+//! as its last arg:
+//!
+//! ```rust,ignore
+//! fn edit_config(mut editor: ResMut<CliEditor>, config: Res<GameConfig>) {
+//!     editor.open("config_apply", ron::to_string(&*config).unwrap());
+//! }
+//! ```
+//!
+//! The built-in `edit <command...>` command opens an empty editor for any
+//! command (`edit resource insert Gravity`).
+//!
+//! The egui console window (see [`egui`](crate::egui)) shows the editor, and
+//! the remote console server (see [`remote`](crate::remote)) forwards it to
+//! its client. Other frontends check [`CliEditor::request`], let the user
+//! edit [`CliEditorRequest::text_mut`], and run the command string returned
+//! by [`CliEditor::submit`].
+
+use bevy::prelude::*;
+
+use crate::output::cli_error;
+use crate::status::cli_fail;
+use crate::{quote_cli_arg, CliCommandsRegisterExt};
+
+/// A command waiting for multi-line text
+#[derive(Debug, Clone)]
+pub struct CliEditorRequest {
+    id: u64,
+    command: String,
+    text: String,
+}
+
+impl CliEditorRequest {
+    /// Identifies the request, for frontends that answer it later
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+    /// The command that receives the text
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+    /// The text being edited
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    pub fn text_mut(&mut self) -> &mut String {
+        &mut self.text
+    }
+    /// The command string that runs the command with the text
+    ///
+    /// The text is quoted, so it arrives as a single arg, unchanged.
+    pub fn into_cli(self) -> String {
+        // `$` would be expanded as a variable
+        let text = quote_cli_arg(&self.text).replace('$', "\\$");
+        format!("{} {}", self.command, text)
+    }
+}
+
+/// The multi-line editor requested by a command, if any
+///
+/// Requires [`CliPlugin`](crate::CliPlugin).
+#[derive(Resource, Debug, Default)]
+pub struct CliEditor {
+    request: Option<CliEditorRequest>,
+    next_id: u64,
+}
+
+impl CliEditor {
+    /// Ask the frontend for an editor, running `command` with the text
+    ///
+    /// `command` may include args; the text is added after them. Replaces
+    /// any editor that is already open.
+    pub fn open(&mut self, command: impl Into<String>, initial_text: impl Into<String>) {
+        self.next_id += 1;
+        self.request = Some(CliEditorRequest {
+            id: self.next_id,
+            command: command.into(),
+            text: initial_text.into(),
+        });
+    }
+    /// The editor that should be shown, if any
+    pub fn request(&self) -> Option<&CliEditorRequest> {
+        self.request.as_ref()
+    }
+    pub fn request_mut(&mut self) -> Option<&mut CliEditorRequest> {
+        self.request.as_mut()
+    }
+    pub fn is_open(&self) -> bool {
+        self.request.is_some()
+    }
+    /// Close the editor without running the command
+    pub fn cancel(&mut self) {
+        self.request = None;
+    }
+    /// Close the editor, returning the request, for frontends that answer it later
+    pub fn take_request(&mut self) -> Option<CliEditorRequest> {
+        self.request.take()
+    }
+    /// Close the editor, returning the command string to run
+    ///
+    /// See [`CliEditorRequest::into_cli`].
+    pub fn submit(&mut self) -> Option<String> {
+        self.request.take().map(CliEditorRequest::into_cli)
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliEditor>();
+    app.register_clicommand_args("edit", edit)
+        .set_clicommand_description("edit", "Run a command with multi-line text as its last arg")
+        .set_clicommand_usage("edit", "edit <command...>");
+}
+
+/// `edit <command...>`: open an editor for the last arg of a command
+fn edit(In(args): In<Vec<String>>, world: &mut World) {
+    if args.is_empty() {
+        cli_error(world, "Usage: edit <command...>");
+        cli_fail(world);
+        return;
+    }
+    let command: Vec<_> = args.iter().map(|arg| quote_cli_arg(arg)).collect();
+    world
+        .resource_mut::<CliEditor>()
+        .open(command.join(" "), "");
+}
@@ -0,0 +1,439 @@
+//! A console window for [`bevy_egui`].
+//!
+//! Requires the `egui` cargo feature. Add [`CliEguiConsolePlugin`]
+//! (together with [`CliPlugin`](crate::CliPlugin) and `bevy_egui`'s
+//! `EguiPlugin`), and press the toggle key (F1, by default) to open the
+//! window. It shows the output of commands in a scrollable log, and has an
+//! input line with history (up/down arrows) and completion (tab).
+//! Completion candidates for command names are listed above the input line
+//! in collapsible categories (see
+//! [`CliCommands::command_category`](crate::CliCommands::command_category)),
+//! and clicking one puts it in the input line. Clicking a link in the
+//! output (see [`CliLink`]) runs its command string, or copies its text.
+//!
+//! Like the [`console`](crate::console) overlay, the window is a
+//! [`CliSession`], on the entity with [`CliEguiConsole`]. Both drain the
+//! [`CliOutputLog`], so use only one of them. Lines are colored by their
+//! [`CliSeverity`], and their styled spans are shown. With the `log-capture`
+//! feature, captured log messages are shown too.
+//!
+//! When a command asks for multi-line text (see [`editor`](crate::editor)),
+//! an editor window is shown. Ctrl+Enter submits the text. When a command
+//! asks a question (see [`prompt`](crate::prompt)), it is shown next to the
+//! input line, and the next submitted line is the answer.
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::editor::CliEditor;
+use crate::output::{CliLink, CliOutputLine, CliOutputLog, CliSeverity, CliTextStyle};
+use crate::prompt::{CliAnswerPromptCommand, CliPrompt};
+use crate::session::{complete_in_session, CliRunInSessionCommand, CliSession};
+
+/// Plugin that adds a toggleable egui console window
+pub struct CliEguiConsolePlugin {
+    /// The key that opens and closes the window
+    pub toggle_key: KeyCode,
+}
+
+impl Default for CliEguiConsolePlugin {
+    fn default() -> Self {
+        CliEguiConsolePlugin {
+            toggle_key: KeyCode::F1,
+        }
+    }
+}
+
+impl Plugin for CliEguiConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CliConsoleWindow {
+            open: false,
+            toggle_key: self.toggle_key,
+            title: "Console".to_owned(),
+            max_scrollback: 1000,
+            scrollback: VecDeque::new(),
+            completion: None,
+            #[cfg(feature = "log-capture")]
+            last_log_seq: 0,
+        });
+        app.add_systems(Startup, spawn_session);
+        app.add_systems(
+            Update,
+            (toggle_window, collect_output, show_window, show_editor).chain(),
+        );
+    }
+}
+
+/// Marker for the entity with the [`CliSession`] of the egui console window
+#[derive(Component)]
+pub struct CliEguiConsole;
+
+/// The state of the egui console window
+#[derive(Resource, Debug)]
+pub struct CliConsoleWindow {
+    open: bool,
+    toggle_key: KeyCode,
+    /// The title of the window
+    pub title: String,
+    /// How many lines of output to keep
+    pub max_scrollback: usize,
+    scrollback: VecDeque<CliOutputLine>,
+    /// The completion candidates shown, and the input line they are for
+    completion: Option<(String, Vec<(Option<String>, Vec<String>)>)>,
+    /// The last log record that was shown
+    #[cfg(feature = "log-capture")]
+    last_log_seq: u64,
+}
+
+impl CliConsoleWindow {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+    /// The lines shown in the window, oldest first
+    pub fn scrollback(&self) -> impl Iterator<Item = &str> {
+        self.scrollback.iter().map(|line| line.text.as_str())
+    }
+    pub fn clear_scrollback(&mut self) {
+        self.scrollback.clear();
+    }
+    fn push_line(&mut self, line: CliOutputLine) {
+        self.scrollback.push_back(line);
+        while self.scrollback.len() > self.max_scrollback {
+            self.scrollback.pop_front();
+        }
+    }
+}
+
+fn spawn_session(mut commands: Commands) {
+    commands.spawn((CliEguiConsole, CliSession::default()));
+}
+
+fn toggle_window(kbd: Res<ButtonInput<KeyCode>>, mut window: ResMut<CliConsoleWindow>) {
+    if kbd.just_pressed(window.toggle_key) {
+        window.toggle();
+    }
+}
+
+fn collect_output(
+    log: Option<ResMut<CliOutputLog>>,
+    #[cfg(feature = "log-capture")] records: Option<Res<crate::log_capture::CliLogBuffer>>,
+    mut window: ResMut<CliConsoleWindow>,
+) {
+    #[cfg(feature = "log-capture")]
+    if let Some(records) = records {
+        let since = window.last_log_seq;
+        for record in records.iter_since(since) {
+            let text = format!("[{}] {}", record.level, record.message);
+            window.push_line(CliOutputLine::new(text).with_severity(record.severity()));
+            window.last_log_seq = record.seq;
+        }
+    }
+    let Some(mut log) = log else {
+        return;
+    };
+    for line in log.drain() {
+        window.push_line(line);
+    }
+}
+
+fn show_window(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut window: ResMut<CliConsoleWindow>,
+    prompt: Option<Res<CliPrompt>>,
+    mut q_session: Query<(Entity, &mut CliSession), With<CliEguiConsole>>,
+) {
+    if !window.open {
+        return;
+    }
+    let Ok((entity, mut session)) = q_session.get_single_mut() else {
+        return;
+    };
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    let request = prompt.as_ref().and_then(|prompt| prompt.request());
+    let mut open = true;
+    // The candidates are for the input line they were listed for
+    if window
+        .completion
+        .as_ref()
+        .is_some_and(|(input, _)| input != session.input())
+    {
+        window.completion = None;
+    }
+    let mut picked = None;
+    let mut clicked = None;
+    let title = window.title.clone();
+    egui::Window::new(title)
+        .open(&mut open)
+        .default_size([600.0, 400.0])
+        .show(ctx, |ui| {
+            let input_height = ui.text_style_height(&egui::TextStyle::Monospace)
+                + ui.spacing().item_spacing.y * 2.0
+                + ui.spacing().button_padding.y * 2.0;
+            egui::ScrollArea::vertical()
+                .auto_shrink([false, false])
+                .stick_to_bottom(true)
+                .max_height(ui.available_height() - input_height)
+                .show(ui, |ui| {
+                    for line in &window.scrollback {
+                        if let Some(cli) = show_line(ui, line) {
+                            clicked = Some(cli);
+                        }
+                    }
+                });
+            ui.separator();
+            let response = ui
+                .horizontal(|ui| {
+                    if let Some(request) = request {
+                        ui.monospace(request.message());
+                    }
+                    ui.add(
+                        egui::TextEdit::singleline(session.input_mut())
+                            .font(egui::TextStyle::Monospace)
+                            .desired_width(f32::INFINITY)
+                            .password(request.is_some_and(|request| request.is_secret()))
+                            // Keep tab for completion, instead of moving the focus
+                            .lock_focus(true),
+                    )
+                })
+                .inner;
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                if let Some(request) = request {
+                    let answer = session.take_input();
+                    let shown = match request.is_secret() {
+                        true => "*".repeat(answer.chars().count()),
+                        false => answer.clone(),
+                    };
+                    window.push_line(CliOutputLine::new(format!(
+                        "{} {}",
+                        request.message(),
+                        shown
+                    )));
+                    commands.queue(CliAnswerPromptCommand(answer));
+                } else if let Some(line) = session.submit() {
+                    window.push_line(CliOutputLine::new(format!("> {}", line)));
+                    commands.queue(CliRunInSessionCommand {
+                        session: entity,
+                        cli: line,
+                    });
+                }
+                response.request_focus();
+            } else if response.has_focus() {
+                if ui.input(|i| i.key_pressed(egui::Key::ArrowUp)) {
+                    session.history_prev();
+                }
+                if ui.input(|i| i.key_pressed(egui::Key::ArrowDown)) {
+                    session.history_next();
+                }
+                if ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Tab)) {
+                    commands.queue(CliCompleteEguiCommand(entity));
+                }
+            }
+            if let Some((_, candidates)) = &window.completion {
+                picked = show_completion(ui.ctx(), response.rect, candidates);
+            }
+        });
+    if let Some(cli) = clicked {
+        window.push_line(CliOutputLine::new(format!("> {}", cli)));
+        commands.queue(CliRunInSessionCommand {
+            session: entity,
+            cli,
+        });
+    }
+    if let Some(candidate) = picked {
+        // Replace the word being completed
+        let input = session.input_mut();
+        let start = input.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        input.replace_range(start.., &candidate);
+        input.push(' ');
+        window.completion = None;
+    }
+    if !open {
+        window.close();
+    }
+}
+
+/// Categories with more completion candidates than this start collapsed
+const COLLAPSED_CANDIDATES: usize = 8;
+
+/// Show the completion candidates in a popup above the input line,
+/// returning the one that was clicked
+fn show_completion(
+    ctx: &egui::Context,
+    input_rect: egui::Rect,
+    candidates: &[(Option<String>, Vec<String>)],
+) -> Option<String> {
+    let mut picked = None;
+    let mut show_names = |ui: &mut egui::Ui, names: &[String]| {
+        ui.horizontal_wrapped(|ui| {
+            for name in names {
+                if ui
+                    .small_button(egui::RichText::new(name).monospace())
+                    .clicked()
+                {
+                    picked = Some(name.clone());
+                }
+            }
+        });
+    };
+    egui::Area::new(egui::Id::new("cli_completion"))
+        .order(egui::Order::Foreground)
+        .fixed_pos(input_rect.left_top())
+        .pivot(egui::Align2::LEFT_BOTTOM)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.set_max_width(input_rect.width());
+                egui::ScrollArea::vertical()
+                    .max_height(240.0)
+                    .show(ui, |ui| {
+                        for (category, names) in candidates {
+                            match category {
+                                None => show_names(ui, names),
+                                Some(category) => {
+                                    egui::CollapsingHeader::new(format!(
+                                        "{} ({})",
+                                        category,
+                                        names.len()
+                                    ))
+                                    .id_salt(("cli_completion", category))
+                                    .default_open(names.len() <= COLLAPSED_CANDIDATES)
+                                    .show(ui, |ui| show_names(ui, names));
+                                }
+                            }
+                        }
+                    });
+            });
+        });
+    picked
+}
+
+/// Show a line of output, returning the command string of the link that
+/// was clicked, if any
+fn show_line(ui: &mut egui::Ui, line: &CliOutputLine) -> Option<String> {
+    let color = match line.severity {
+        CliSeverity::Info => ui.visuals().text_color(),
+        CliSeverity::Success => egui::Color32::LIGHT_GREEN,
+        CliSeverity::Warn => ui.visuals().warn_fg_color,
+        CliSeverity::Error => ui.visuals().error_fg_color,
+    };
+    let mut clicked = None;
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        for (text, span) in line.segment_spans() {
+            let rich = egui::RichText::new(text).monospace().color(color);
+            let rich = match span.map(|span| span.style) {
+                None => rich,
+                Some(CliTextStyle::Code) => rich.color(ui.visuals().strong_text_color()),
+                Some(CliTextStyle::Strong) => rich.strong(),
+                Some(CliTextStyle::Emphasis) => rich.italics(),
+                Some(CliTextStyle::Highlight) => {
+                    rich.background_color(ui.visuals().selection.bg_fill)
+                }
+                Some(CliTextStyle::Heading) => rich.strong().heading(),
+                Some(CliTextStyle::Link) => rich.color(ui.visuals().hyperlink_color).underline(),
+            };
+            match span.and_then(|span| span.link.as_ref()) {
+                Some(CliLink::Copy(target)) => {
+                    if ui.link(rich).on_hover_text(target).clicked() {
+                        ui.ctx().copy_text(target.clone());
+                    }
+                }
+                Some(CliLink::Run(cli)) => {
+                    if ui.link(rich).on_hover_text(cli).clicked() {
+                        clicked = Some(cli.clone());
+                    }
+                }
+                None => {
+                    ui.label(rich);
+                }
+            }
+        }
+    });
+    clicked
+}
+
+fn show_editor(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    editor: Option<ResMut<CliEditor>>,
+    mut window: ResMut<CliConsoleWindow>,
+    q_session: Query<Entity, With<CliEguiConsole>>,
+) {
+    let Some(mut editor) = editor else {
+        return;
+    };
+    let Ok(entity) = q_session.get_single() else {
+        return;
+    };
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    let Some(request) = editor.request_mut() else {
+        return;
+    };
+    let mut open = true;
+    let mut submit = false;
+    let mut cancel = false;
+    egui::Window::new(format!("Edit: {}", request.command()))
+        .open(&mut open)
+        .default_size([500.0, 300.0])
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                submit = ui.button("Submit").clicked();
+                cancel = ui.button("Cancel").clicked();
+            });
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(request.text_mut())
+                        .code_editor()
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(12),
+                );
+            });
+            submit |= ui.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Enter));
+        });
+    if !open || cancel {
+        editor.cancel();
+    } else if submit {
+        let command = request.command().to_owned();
+        if let Some(line) = editor.submit() {
+            window.push_line(CliOutputLine::new(format!("> {} <edited text>", command)));
+            commands.queue(CliRunInSessionCommand {
+                session: entity,
+                cli: line,
+            });
+        }
+    }
+}
+
+/// Complete the input line of the egui console window
+struct CliCompleteEguiCommand(Entity);
+
+impl bevy::ecs::world::Command for CliCompleteEguiCommand {
+    fn apply(self, world: &mut World) {
+        let Some(completion) = complete_in_session(world, self.0) else {
+            return;
+        };
+        world.resource_mut::<CliConsoleWindow>().completion = match completion.candidates.is_empty()
+        {
+            true => None,
+            false => Some((completion.input.clone(), completion.candidates)),
+        };
+        if let Some(mut session) = world.get_mut::<CliSession>(self.0) {
+            *session.input_mut() = completion.input;
+        }
+    }
+}
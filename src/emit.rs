@@ -0,0 +1,93 @@
+//! Sending typed events with the results of "console commands".
+//!
+//! A command can declare that it emits an event type, using
+//! [`add_clicommand_emits`](crate::CliCommandsRegisterExt::add_clicommand_emits).
+//! While running, it provides the value of the event with [`CliEmit`] (or
+//! [`cli_emit`], from exclusive systems). The event is sent once the command
+//! has completed, only if it succeeded, so gameplay systems can react to
+//! console-driven actions with normal [`EventReader`]s.
+//!
+//! ```rust,ignore
+//! #[derive(Event)]
+//! struct LevelLoaded(u32);
+//!
+//! app.add_event::<LevelLoaded>();
+//! app.register_clicommand_args("load_level", load_level)
+//!     .add_clicommand_emits::<LevelLoaded>("load_level");
+//!
+//! fn load_level(In(args): In<Vec<String>>, mut emit: CliEmit<LevelLoaded>) {
+//!     // ...
+//!     emit.emit(LevelLoaded(level));
+//! }
+//! ```
+
+use std::any::type_name;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+/// The event provided by the command that is running, not yet sent
+#[derive(Resource)]
+pub(crate) struct CliPendingEmit<E: Event>(Option<E>);
+
+impl<E: Event> Default for CliPendingEmit<E> {
+    fn default() -> Self {
+        CliPendingEmit(None)
+    }
+}
+
+/// System parameter for providing the event a "console command" emits
+///
+/// The command must be declared to emit `E`, using
+/// [`add_clicommand_emits`](crate::CliCommandsRegisterExt::add_clicommand_emits).
+#[derive(SystemParam)]
+pub struct CliEmit<'w, E: Event> {
+    pending: Option<ResMut<'w, CliPendingEmit<E>>>,
+}
+
+impl<E: Event> CliEmit<'_, E> {
+    /// Set the event to send when the command succeeds
+    ///
+    /// If called more than once, the last event is sent.
+    pub fn emit(&mut self, event: E) {
+        match &mut self.pending {
+            Some(pending) => pending.0 = Some(event),
+            None => warn!(
+                "CliCommand emitted {}, but no command is declared to emit it!",
+                type_name::<E>()
+            ),
+        }
+    }
+}
+
+/// Set the event to send when the running command succeeds, from an exclusive system
+///
+/// Equivalent to [`CliEmit::emit`].
+pub fn cli_emit<E: Event>(world: &mut World, event: E) {
+    match world.get_resource_mut::<CliPendingEmit<E>>() {
+        Some(mut pending) => pending.0 = Some(event),
+        None => warn!(
+            "CliCommand emitted {}, but no command is declared to emit it!",
+            type_name::<E>()
+        ),
+    }
+}
+
+/// Send the pending event, if the command succeeded, or discard it
+pub(crate) fn flush<E: Event>(world: &mut World, success: bool) {
+    let event = world
+        .get_resource_mut::<CliPendingEmit<E>>()
+        .and_then(|mut pending| pending.0.take());
+    let Some(event) = event.filter(|_| success) else {
+        return;
+    };
+    match world.get_resource_mut::<Events<E>>() {
+        Some(mut events) => {
+            events.send(event);
+        }
+        None => warn!(
+            "Cannot send {} from CliCommand: the event is not registered!",
+            type_name::<E>()
+        ),
+    }
+}
@@ -0,0 +1,622 @@
+//! String variables, expanded inside command strings.
+//!
+//! Requires the `env` cargo feature (enabled by default). Without it,
+//! nothing is expanded: command strings run as they are written.
+//!
+//! The [`CliEnv`] resource holds variables, which are set with the built-in
+//! `set` command (or from code). Before a command string is run, `$name` and
+//! `${name}` are replaced with the values of the variables:
+//!
+//! ```text
+//! set spawnpos "100 200"
+//! spawn $spawnpos         # runs `spawn 100 200`
+//! echo pos=${spawnpos}!
+//! ```
+//!
+//! The value is inserted as text, so a value containing spaces becomes
+//! several args, unless the variable is inside double quotes. Nothing is
+//! expanded inside single quotes, or after a backslash (`\$name`).
+//! References to variables that are not set are left unchanged.
+//!
+//! `$?` is replaced with the exit code of the previous command string (`0`
+//! if it succeeded, `1` if it failed, see [`status`](crate::status)):
+//!
+//! ```text
+//! spawn_boss || echo "spawning failed: $?"
+//! ```
+//!
+//! `$(...)` is replaced with the output of the command string inside it,
+//! which runs first. Like a variable, the output becomes several args,
+//! unless the substitution is inside double quotes (where the lines of the
+//! output are kept). Unlike a variable, the output is always taken as
+//! plain text: quotes and `$` in it have no special meaning.
+//!
+//! ```text
+//! set pos $(player_pos)
+//! echo "enemies: $(entities | grep Enemy | count)"
+//! ```
+//!
+//! If the command string inside fails, the command around it does not run.
+//!
+//! `$LAST` is the output of the previous command string, like `$(output)`
+//! (see [`scrollback`](crate::scrollback)), unless a variable named `LAST`
+//! is set.
+//!
+//! Run from a [`CliSession`], `set` sets a variable of that session, which
+//! shadows a global variable with the same name, and `export <name>` makes
+//! it global (see [`session`](crate::session)).
+//!
+//! `set`, `unset` and `export` are [`Dev`](CliPermission::Dev) commands
+//! (see [`permissions`](crate::permissions)), as variables change what other
+//! command strings do.
+//!
+//! Expansion happens separately for each command of a chain, so a value
+//! containing `;` or `&&` cannot run additional commands.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::error::CliError;
+use crate::output::{self, CliOutput};
+use crate::parse::substitution_end;
+use crate::permissions::CliPermission;
+use crate::scrollback::CliOutputHistory;
+use crate::session::{self, CliSession};
+use crate::status::CliStatus;
+use crate::{quote_cli_arg, run_cli_chain, CliCommandsRegisterExt};
+
+/// The variable that expands to the output of the previous command string
+const LAST_OUTPUT: &str = "LAST";
+
+/// String variables for expanding in command strings
+#[derive(Resource, Debug, Clone, Default)]
+pub struct CliEnv {
+    vars: HashMap<String, String>,
+}
+
+impl CliEnv {
+    pub fn set(&mut self, name: &str, value: &str) {
+        self.vars.insert(name.to_owned(), value.to_owned());
+    }
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.vars.get(name).map(|s| s.as_str())
+    }
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        self.vars.remove(name)
+    }
+    pub fn clear(&mut self) {
+        self.vars.clear();
+    }
+    /// Iterate over all variables and their values, sorted by name
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        let mut vars: Vec<_> = self
+            .vars
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        vars.sort_unstable();
+        vars.into_iter()
+    }
+    /// Replace references to variables in a command string with their values
+    ///
+    /// `$?` is left unchanged, as there is no previous command here.
+    pub fn expand<'a>(&self, cli: &'a str) -> Cow<'a, str> {
+        self.expand_with_exit_code(cli, None)
+    }
+    /// Replace references to variables, and `$?` if an exit code is given
+    fn expand_with_exit_code<'a>(&self, cli: &'a str, last_exit_code: Option<u8>) -> Cow<'a, str> {
+        if self.vars.is_empty() && last_exit_code.is_none() {
+            return Cow::Borrowed(cli);
+        }
+        expand_with(cli, last_exit_code, |name| self.get(name))
+    }
+}
+
+/// Replace references to variables with the values given by `get`, and `$?`
+/// if an exit code is given
+fn expand_with<'a, 'v>(
+    cli: &'a str,
+    last_exit_code: Option<u8>,
+    get: impl Fn(&str) -> Option<&'v str>,
+) -> Cow<'a, str> {
+    if !cli.contains('$') {
+        return Cow::Borrowed(cli);
+    }
+    let mut out = String::with_capacity(cli.len());
+    let mut in_single_quotes = false;
+    // A single quote inside double quotes does not start a quoted string
+    let mut in_double_quotes = false;
+    let mut rest = cli;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '\'' if !in_double_quotes => in_single_quotes = !in_single_quotes,
+            '"' if !in_single_quotes => in_double_quotes = !in_double_quotes,
+            '\\' if !in_single_quotes => {
+                // Keep the escaped char as it is, for the parser
+                let len = rest[1..].chars().next().map_or(0, |c| c.len_utf8());
+                out.push_str(&rest[..1 + len]);
+                rest = &rest[1 + len..];
+                continue;
+            }
+            '$' if !in_single_quotes => {
+                if let Some(code) = last_exit_code.filter(|_| rest[1..].starts_with('?')) {
+                    out.push_str(&code.to_string());
+                    rest = &rest[2..];
+                    continue;
+                }
+                let value = var_ref(&rest[1..]).and_then(|(name, len)| Some((get(name)?, len)));
+                if let Some((value, len)) = value {
+                    out.push_str(value);
+                    rest = &rest[1 + len..];
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    Cow::Owned(out)
+}
+
+/// Find the name of the variable referenced at the start of `s` (after the
+/// `$`), returning it and the length of the reference
+fn var_ref(s: &str) -> Option<(&str, usize)> {
+    let (name, len) = match s.strip_prefix('{') {
+        Some(braced) => {
+            let end = braced.find('}')?;
+            (&braced[..end], end + 2)
+        }
+        None => {
+            let end = s
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(s.len());
+            (&s[..end], end)
+        }
+    };
+    is_var_name(name).then_some((name, len))
+}
+
+fn is_var_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Run the command substitutions in a command string, then expand the
+/// variables of the [`CliEnv`] and `$?`
+///
+/// Fails if the command string of a substitution fails (after reporting why).
+pub(crate) fn expand(world: &mut World, cli: &str) -> Result<String, CliError> {
+    let last_exit_code = world
+        .get_resource::<CliStatus>()
+        .map_or(0, |status| status.last_exit_code());
+    let cli = last_output_refs(world, cli);
+    let substituted = substitute_commands(world, &cli)?;
+    Ok(expand_vars_with_exit_code(world, &substituted, last_exit_code).into_owned())
+}
+
+/// Expand the variables of the [`CliEnv`], if there is one, and `$?` in a
+/// command string, without running command substitutions
+pub(crate) fn expand_vars<'a>(world: &World, cli: &'a str) -> Cow<'a, str> {
+    let last_exit_code = world
+        .get_resource::<CliStatus>()
+        .map_or(0, |status| status.last_exit_code());
+    expand_vars_with_exit_code(world, cli, last_exit_code)
+}
+
+fn expand_vars_with_exit_code<'a>(world: &World, cli: &'a str, last_exit_code: u8) -> Cow<'a, str> {
+    expand_with(cli, Some(last_exit_code), |name| get_var(world, name))
+}
+
+/// Whether a variable is set, or is `$LAST`
+#[cfg(feature = "script")]
+pub(crate) fn is_var_set(world: &World, name: &str) -> bool {
+    let last = name == LAST_OUTPUT && world.contains_resource::<CliOutputHistory>();
+    last || get_var(world, name).is_some()
+}
+
+/// Replace references to `$LAST` with `$(output)`, unless a variable named
+/// `LAST` is set
+fn last_output_refs<'a>(world: &World, cli: &'a str) -> Cow<'a, str> {
+    if !world.contains_resource::<CliOutputHistory>() || get_var(world, LAST_OUTPUT).is_some() {
+        return Cow::Borrowed(cli);
+    }
+    let refs = var_refs(cli);
+    let mut refs = refs
+        .iter()
+        .filter(|(_, name)| *name == LAST_OUTPUT)
+        .peekable();
+    if refs.peek().is_none() {
+        return Cow::Borrowed(cli);
+    }
+    let mut out = String::with_capacity(cli.len());
+    let mut copied = 0;
+    for (range, _) in refs {
+        out.push_str(&cli[copied..range.start]);
+        out.push_str("$(output)");
+        copied = range.end;
+    }
+    out.push_str(&cli[copied..]);
+    Cow::Owned(out)
+}
+
+/// Get the value of a variable, of the session the command string is run
+/// from, or else of the [`CliEnv`]
+pub(crate) fn get_var<'w>(world: &'w World, name: &str) -> Option<&'w str> {
+    session::current_session(world)
+        .and_then(|session| session.get_var(name))
+        .or_else(|| world.get_resource::<CliEnv>()?.get(name))
+}
+
+/// The command substitutions (`$(...)`) in a command string
+///
+/// Returns the byte ranges of the command strings inside them. Nested
+/// substitutions are part of the command string around them.
+pub(crate) fn substitutions(cli: &str) -> Vec<Range<usize>> {
+    let mut substitutions = Vec::new();
+    let mut in_single_quotes = false;
+    let mut in_double_quotes = false;
+    let mut chars = cli.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\'' if !in_double_quotes => in_single_quotes = !in_single_quotes,
+            '"' if !in_single_quotes => in_double_quotes = !in_double_quotes,
+            '\\' if !in_single_quotes => {
+                chars.next();
+            }
+            '$' if !in_single_quotes && cli[i..].starts_with("$(") => {
+                let Some(end) = substitution_end(cli, i) else {
+                    break;
+                };
+                substitutions.push(i + 2..end - 1);
+                // Skip to the `)`
+                while chars.next().is_some_and(|(j, _)| j + 1 < end) {}
+            }
+            _ => {}
+        }
+    }
+    substitutions
+}
+
+/// The references to variables (`$name` and `${name}`) in a command string
+///
+/// Returns the byte range of each reference (including the `$`), and the
+/// name of the variable, whether it is set or not.
+pub(crate) fn var_refs(cli: &str) -> Vec<(Range<usize>, &str)> {
+    let mut refs = Vec::new();
+    let mut in_single_quotes = false;
+    let mut in_double_quotes = false;
+    let mut chars = cli.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\'' if !in_double_quotes => in_single_quotes = !in_single_quotes,
+            '"' if !in_single_quotes => in_double_quotes = !in_double_quotes,
+            '\\' if !in_single_quotes => {
+                chars.next();
+            }
+            '$' if !in_single_quotes => {
+                if let Some((name, len)) = var_ref(&cli[i + 1..]) {
+                    refs.push((i..i + 1 + len, name));
+                }
+            }
+            _ => {}
+        }
+    }
+    refs
+}
+
+/// Replace the command substitutions in a command string with the output
+/// of running their command strings
+fn substitute_commands<'a>(world: &mut World, cli: &'a str) -> Result<Cow<'a, str>, CliError> {
+    let substitutions = substitutions(cli);
+    if substitutions.is_empty() {
+        return Ok(Cow::Borrowed(cli));
+    }
+    let mut out = String::with_capacity(cli.len());
+    let mut copied = 0;
+    for inner in substitutions {
+        // Including the `$(` and `)`
+        let (start, end) = (inner.start - 2, inner.end + 1);
+        out.push_str(&cli[copied..start]);
+        copied = end;
+        let inner = &cli[inner];
+        let (lines, result) =
+            output::capture_output(world, |world| run_cli_chain(world, inner, true));
+        // Errors were shown, like those of a command whose output is piped
+        let lines = output::take_piped_lines(world, lines);
+        if result.is_err() {
+            return Err(CliError::Failed(inner.to_owned()));
+        }
+        if in_double_quotes(&cli[..start]) {
+            for c in lines.join("\n").chars() {
+                if matches!(c, '"' | '\\' | '$') {
+                    out.push('\\');
+                }
+                out.push(c);
+            }
+        } else {
+            let words: Vec<Cow<str>> = lines
+                .iter()
+                .flat_map(|line| line.split_ascii_whitespace())
+                .map(quote_cli_arg)
+                .collect();
+            out.push_str(&words.join(" "));
+        }
+    }
+    out.push_str(&cli[copied..]);
+    Ok(Cow::Owned(out))
+}
+
+/// Whether the end of a command string is inside double quotes
+fn in_double_quotes(cli: &str) -> bool {
+    let mut in_single_quotes = false;
+    let mut in_double_quotes = false;
+    let mut chars = cli.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' if !in_double_quotes => in_single_quotes = !in_single_quotes,
+            '"' if !in_single_quotes => in_double_quotes = !in_double_quotes,
+            '\\' if !in_single_quotes => {
+                chars.next();
+            }
+            _ => {}
+        }
+    }
+    in_double_quotes
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliEnv>();
+    app.register_clicommand_args("set", set)
+        .set_clicommand_description("set", "Set a variable, or list variables")
+        .set_clicommand_usage("set", "set [name [value...]]")
+        .set_clicommand_permission("set", CliPermission::Dev);
+    app.register_clicommand_args("unset", unset)
+        .set_clicommand_description("unset", "Remove a variable")
+        .set_clicommand_usage("unset", "unset <name>")
+        .set_clicommand_permission("unset", CliPermission::Dev);
+    app.register_clicommand_args("export", export)
+        .set_clicommand_description("export", "Make a variable of this session global")
+        .set_clicommand_usage("export", "export <name>")
+        .set_clicommand_permission("export", CliPermission::Dev);
+}
+
+/// `set [name [value...]]`: set a variable, or show variables
+///
+/// Run from a [`CliSession`], the variable is only set in that session.
+fn set(
+    In(args): In<Vec<String>>,
+    mut env: ResMut<CliEnv>,
+    mut sessions: Query<&mut CliSession>,
+    mut output: CliOutput,
+) {
+    let mut session = output
+        .session()
+        .and_then(|entity| sessions.get_mut(entity).ok());
+    let Some((name, value)) = args.split_first() else {
+        // Session variables shadow global ones
+        let mut vars: BTreeMap<&str, &str> = env.iter().collect();
+        vars.extend(session.iter().flat_map(|session| session.iter_vars()));
+        for (name, value) in vars {
+            output.println(format!("set {} {}", name, quote_cli_arg(value)));
+        }
+        return;
+    };
+    if !is_var_name(name) {
+        output.fail(format!("Invalid variable name {:?}!", name));
+        return;
+    }
+    if value.is_empty() {
+        let value = session
+            .as_ref()
+            .and_then(|session| session.get_var(name))
+            .or_else(|| env.get(name));
+        match value {
+            Some(value) => output.println(format!("set {} {}", name, quote_cli_arg(value))),
+            None => output.println(format!("{} is not set", name)),
+        }
+        return;
+    }
+    match &mut session {
+        Some(session) => session.set_var(name, &value.join(" ")),
+        None => env.set(name, &value.join(" ")),
+    }
+}
+
+/// `unset <name>`: remove a variable
+///
+/// Run from a [`CliSession`], a variable of the session is removed, if there
+/// is one, rather than the global one.
+fn unset(
+    In(args): In<Vec<String>>,
+    mut env: ResMut<CliEnv>,
+    mut sessions: Query<&mut CliSession>,
+    mut output: CliOutput,
+) {
+    let [name] = args.as_slice() else {
+        output.fail("Usage: unset <name>");
+        return;
+    };
+    let session = output
+        .session()
+        .and_then(|entity| sessions.get_mut(entity).ok());
+    if session
+        .and_then(|mut session| session.remove_var(name))
+        .is_some()
+    {
+        return;
+    }
+    if env.remove(name).is_none() {
+        output.fail(format!("{} is not set!", name));
+    }
+}
+
+/// `export <name>`: make a variable of the current [`CliSession`] global
+fn export(
+    In(args): In<Vec<String>>,
+    mut env: ResMut<CliEnv>,
+    mut sessions: Query<&mut CliSession>,
+    mut output: CliOutput,
+) {
+    let [name] = args.as_slice() else {
+        output.fail("Usage: export <name>");
+        return;
+    };
+    let session = output
+        .session()
+        .and_then(|entity| sessions.get_mut(entity).ok());
+    match session.and_then(|mut session| session.remove_var(name)) {
+        Some(value) => env.set(name, &value),
+        None => output.fail(format!("{} is not a variable of this session!", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::CliCommandsOutputExt;
+    use crate::{CliCommandsRunExt, CliPlugin};
+
+    fn env() -> CliEnv {
+        let mut env = CliEnv::default();
+        env.set("pos", "100 200");
+        env.set("name", "Bob");
+        env
+    }
+
+    #[test]
+    fn variables() {
+        let mut env = env();
+        assert_eq!(env.expand("spawn $pos"), "spawn 100 200");
+        assert_eq!(
+            env.expand("echo ${name}! $name_x $nope"),
+            "echo Bob! $name_x $nope"
+        );
+        assert_eq!(
+            env.expand(r#"echo "$name" '$name' \$name"#),
+            r#"echo "Bob" '$name' \$name"#
+        );
+        assert_eq!(env.expand("echo $ ${} ${name"), "echo $ ${} ${name");
+        // Single quotes inside double quotes are plain characters
+        env.set("who", "Alice");
+        assert_eq!(
+            env.expand(r#"echo "it's $who" '$who'"#),
+            r#"echo "it's Alice" '$who'"#
+        );
+        assert_eq!(
+            env.expand(r#"echo '"$name' "$name""#),
+            r#"echo '"$name' "Bob""#
+        );
+        assert!(matches!(env.expand("echo plain"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn set_unset_and_echo() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        let world = app.world_mut();
+
+        world.run_cli("set greeting \"hello there\"");
+        assert_eq!(
+            world.resource::<CliEnv>().get("greeting"),
+            Some("hello there")
+        );
+        let output = world.run_cli_captured("echo $greeting, Bob");
+        let texts: Vec<&str> = output.iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(texts, ["hello there, Bob"]);
+
+        world.run_cli("unset greeting");
+        world.run_cli("set 1st value");
+        assert!(world.resource::<CliEnv>().iter().next().is_none());
+    }
+
+    #[test]
+    fn quoted_args_are_not_expanded_again() {
+        let env = env();
+        let line = format!("echo {}", quote_cli_arg("$name"));
+        let expanded = env.expand(&line);
+        let parsed = crate::parse_cli(&expanded).unwrap();
+        assert_eq!(parsed.args(), ["$name"]);
+    }
+
+    #[test]
+    fn exit_code() {
+        let env = CliEnv::default();
+        assert_eq!(env.expand("echo $?"), "echo $?");
+        assert_eq!(
+            env.expand_with_exit_code("echo $? '$?' \\$?", Some(1)),
+            "echo 1 '$?' \\$?"
+        );
+        assert_eq!(
+            env.expand_with_exit_code("echo \"code $?\"", Some(0)),
+            "echo \"code 0\""
+        );
+    }
+
+    #[test]
+    fn command_substitution() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        let world = app.world_mut();
+        let texts = |output: Vec<crate::output::CliOutputLine>| -> Vec<String> {
+            output.into_iter().map(|line| line.text).collect()
+        };
+
+        world.run_cli("set who $(echo \"Bob  \\$x\")");
+        assert_eq!(world.resource::<CliEnv>().get("who"), Some("Bob $x"));
+        let output = world.try_run_cli("echo $(echo 'a  b') \"$(echo 'a  b'; echo c)\"");
+        assert_eq!(texts(output.unwrap()), ["a b a  b", "c"]);
+        let output = world.try_run_cli("echo $(echo $(echo nested)) '$(echo no)'");
+        assert_eq!(texts(output.unwrap()), ["nested $(echo no)"]);
+        let output = world.try_run_cli("echo \"$(echo '\"$x\"')\"");
+        assert_eq!(texts(output.unwrap()), ["\"$x\""]);
+
+        assert!(world.try_run_cli("echo $(missing) && set ran yes").is_err());
+        assert_eq!(world.resource::<CliEnv>().get("ran"), None);
+    }
+
+    #[test]
+    fn session_variables() {
+        use crate::output::capture_output;
+        use crate::session::CliRunInSessionCommand;
+        use bevy::ecs::world::Command;
+
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        let world = app.world_mut();
+        let session = world.spawn(CliSession::default()).id();
+        let run = |world: &mut World, cli: &str| {
+            let cli = cli.to_owned();
+            let (output, ()) = capture_output(world, |world| {
+                CliRunInSessionCommand { session, cli }.apply(world)
+            });
+            let texts: Vec<String> = output.into_iter().map(|line| line.text).collect();
+            texts
+        };
+
+        world.run_cli("set who global");
+        run(world, "set who local");
+        run(world, "set here 1");
+        assert_eq!(world.resource::<CliEnv>().get("who"), Some("global"));
+        assert_eq!(run(world, "echo $who $here"), ["local 1"]);
+        let output = world.run_cli_captured("echo $who $here");
+        assert_eq!(output[0].text, "global $here");
+
+        run(world, "export here");
+        assert_eq!(world.resource::<CliEnv>().get("here"), Some("1"));
+        let session_vars = world.get::<CliSession>(session).unwrap().iter_vars();
+        assert_eq!(session_vars.count(), 1);
+        assert_eq!(
+            run(world, "export nope"),
+            ["nope is not a variable of this session!"]
+        );
+
+        run(world, "unset who");
+        assert_eq!(run(world, "echo $who"), ["global"]);
+        assert_eq!(world.resource::<CliEnv>().get("who"), Some("global"));
+    }
+}
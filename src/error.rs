@@ -0,0 +1,153 @@
+//! Errors from running "console commands".
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::permissions::CliPermission;
+use crate::CliParseError;
+
+/// Why a command string could not be run, or failed
+///
+/// Returned by [`try_run_cli`](crate::output::CliCommandsOutputExt::try_run_cli).
+/// [`run_cli`](crate::CliCommandsRunExt::run_cli) logs these instead.
+///
+/// More variants may be added as the crate grows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CliError {
+    /// The command string could not be parsed
+    Parse(CliParseError),
+    /// There is no command with the given name
+    NotFound(String),
+    /// The name is an abbreviation of more than one command
+    Ambiguous {
+        name: String,
+        candidates: Vec<String>,
+    },
+    /// The command exists, but one of its run conditions is false
+    ConditionNotMet(String),
+    /// The command exists, but its permission level is not allowed
+    /// by [`CliPermissions`](crate::permissions::CliPermissions)
+    NotPermitted {
+        name: String,
+        permission: CliPermission,
+    },
+    /// The command exists, but resources it requires do not, because
+    /// the plugins providing them were not added
+    MissingPlugin { name: String, plugins: Vec<String> },
+    /// The command is dangerous, and must be confirmed with the `confirm`
+    /// command within the timeout (see [`confirm`](crate::confirm))
+    NeedsConfirmation { name: String, timeout: Duration },
+    /// The command ran too recently, because of its cooldown or rate limit
+    /// (see [`throttle`](crate::throttle))
+    OnCooldown { name: String, remaining: Duration },
+    /// An interceptor did not let the command run (see
+    /// [`intercept`](crate::intercept))
+    Intercepted { name: String, hint: String },
+    /// An entity selector in the args is unknown, invalid, or selects no
+    /// entities (see [`selector`](crate::selector))
+    Selector { selector: String, message: String },
+    /// A mention in the args could not be resolved (see
+    /// [`mention`](crate::mention))
+    Mention { mention: String, message: String },
+    /// The source of the command has run too many commands recently (see
+    /// [`quota`](crate::quota))
+    QuotaExceeded { source: String, remaining: Duration },
+    /// The args do not match the arity or arg checks of the command (see
+    /// [`arity`](crate::arity))
+    InvalidArgs {
+        name: String,
+        message: String,
+        /// The index of the offending arg (`None` if args are missing)
+        index: Option<usize>,
+        usage: Option<String>,
+    },
+    /// There is no route with the given tag (see [`route`](crate::route))
+    NoRoute(String),
+    /// The system of the command could not be run
+    SystemFailed { name: String, message: String },
+    /// The command ran, but reported that it failed
+    ///
+    /// The command itself is responsible for reporting why.
+    Failed(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Parse(e) => write!(f, "cannot parse CLI string: {}", e),
+            CliError::NotFound(name) => write!(f, "CliCommand {:?} not found", name),
+            CliError::Ambiguous { name, candidates } => write!(
+                f,
+                "CliCommand {:?} is ambiguous, could be: {}",
+                name,
+                candidates.join(", ")
+            ),
+            CliError::ConditionNotMet(name) => {
+                write!(f, "CliCommand {:?} cannot be run right now", name)
+            }
+            CliError::NotPermitted { name, permission } => match permission {
+                CliPermission::Cheat => {
+                    write!(f, "CliCommand {:?} requires cheats to be enabled", name)
+                }
+                _ => write!(
+                    f,
+                    "CliCommand {:?} requires {} permission",
+                    name, permission
+                ),
+            },
+            CliError::MissingPlugin { name, plugins } => {
+                write!(f, "CliCommand {:?} requires {}", name, plugins.join(", "))
+            }
+            CliError::NeedsConfirmation { name, timeout } => write!(
+                f,
+                "CliCommand {:?} is dangerous: run `confirm` within {:?} to proceed",
+                name, timeout
+            ),
+            CliError::OnCooldown { name, remaining } => write!(
+                f,
+                "CliCommand {:?} is on cooldown, {:.1}s remaining",
+                name,
+                remaining.as_secs_f32()
+            ),
+            CliError::Intercepted { name, hint } => {
+                write!(f, "CliCommand {:?} was blocked: {}", name, hint)
+            }
+            CliError::Selector { selector, message } => {
+                write!(f, "cannot resolve {}: {}", selector, message)
+            }
+            CliError::Mention { mention, message } => {
+                write!(f, "cannot resolve {}: {}", mention, message)
+            }
+            CliError::QuotaExceeded { source, remaining } => write!(
+                f,
+                "{} has run too many commands, wait {:.0}s",
+                source,
+                remaining.as_secs_f32().ceil()
+            ),
+            CliError::InvalidArgs { name, message, .. } => {
+                write!(f, "invalid args for CliCommand {:?}: {}", name, message)
+            }
+            CliError::NoRoute(tag) => write!(f, "no CLI route {:?}", tag),
+            CliError::SystemFailed { name, message } => {
+                write!(f, "CliCommand {:?} failed to run: {}", name, message)
+            }
+            CliError::Failed(name) => write!(f, "CliCommand {:?} failed", name),
+        }
+    }
+}
+
+impl std::error::Error for CliError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CliError::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<CliParseError> for CliError {
+    fn from(e: CliParseError) -> Self {
+        CliError::Parse(e)
+    }
+}
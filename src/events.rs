@@ -0,0 +1,60 @@
+//! Events sent around the execution of "console commands".
+//!
+//! [`CliPlugin`](crate::CliPlugin) registers these events. Whenever a
+//! registered command runs, [`CliCommandStarted`] is sent before its systems
+//! run, and [`CliCommandCompleted`] after, along with [`CliCommandFailed`]
+//! if it failed. Use them to show commands on a HUD, record analytics, or
+//! anything else that needs to observe console usage.
+//!
+//! Command strings that do not run a registered command (aliases, console
+//! variables, unknown names) do not send these events.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::error::CliError;
+
+/// Event sent when a "console command" is about to run
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct CliCommandStarted {
+    /// The name of the command, as registered
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+/// Event sent when a "console command" has finished running
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct CliCommandCompleted {
+    /// The name of the command, as registered
+    pub name: String,
+    pub args: Vec<String>,
+    pub result: Result<(), CliError>,
+    /// How long the systems of the command took to run
+    pub duration: Duration,
+}
+
+/// Event sent when a "console command" has failed, after [`CliCommandCompleted`]
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct CliCommandFailed {
+    /// The name of the command, as registered
+    pub name: String,
+    pub args: Vec<String>,
+    pub error: CliError,
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_event::<CliCommandStarted>();
+    app.add_event::<CliCommandCompleted>();
+    app.add_event::<CliCommandFailed>();
+}
+
+/// Send an event, if it is registered
+///
+/// Commands can be run without [`CliPlugin`](crate::CliPlugin), so the
+/// events may not exist.
+pub(crate) fn send<E: Event>(world: &mut World, event: E) {
+    if let Some(mut events) = world.get_resource_mut::<Events<E>>() {
+        events.send(event);
+    }
+}
@@ -0,0 +1,117 @@
+//! Reporting what a "console command" changed in the `World`.
+//!
+//! Passing `--explain` (anywhere in the args, unquoted and before any `--`)
+//! to any command makes it print, after it has run, the resources and
+//! components that were added or modified during its execution. This uses Bevy's change detection ticks,
+//! so it works with any types, not only reflected ones, and it reports
+//! mutable accesses (even if the value stayed the same). The flag is removed
+//! from the args the command receives.
+//!
+//! ```text
+//! > spawn_wave 3 --explain
+//! Changes made by "spawn_wave":
+//! ~ resource WaveCounter
+//! + entity 12v1: Transform, Enemy, Health
+//! ~ entity 4v1: Transform, +Target
+//! ```
+//!
+//! Despawned entities and removed components are not listed. Neither are
+//! the resources and components of this crate, which are updated whenever a
+//! command runs.
+
+use bevy::ecs::component::Tick;
+use bevy::prelude::*;
+
+use crate::output::cli_println;
+use crate::parse::ParsedCli;
+use crate::short_name::ShortName;
+
+/// Remove `--explain` from the args of a parsed command, returning whether it was there
+///
+/// A quoted `"--explain"`, or one after a bare `--`, is an ordinary arg.
+pub(crate) fn take_flag(parsed: &mut ParsedCli) -> bool {
+    parsed.take_flag("--explain")
+}
+
+/// Print the resources and components changed after the `since` tick
+pub(crate) fn report(world: &mut World, name: &str, since: Tick) {
+    let this_run = world.change_tick();
+    let mut lines = vec![format!("Changes made by {:?}:", name)];
+    for (info, _) in world.iter_resources() {
+        // Also skips `Events<iyes_cli::...>`
+        if info.name().contains("iyes_cli::") {
+            continue;
+        }
+        let Some(ticks) = world.get_resource_change_ticks_by_id(info.id()) else {
+            continue;
+        };
+        if ticks.is_added(since, this_run) {
+            lines.push(format!("+ resource {}", ShortName(info.name())));
+        } else if ticks.is_changed(since, this_run) {
+            lines.push(format!("~ resource {}", ShortName(info.name())));
+        }
+    }
+    for entity in world.iter_entities() {
+        let mut all_added = true;
+        let mut changed = Vec::new();
+        for id in entity.archetype().components() {
+            let Some(info) = world.components().get_info(id) else {
+                continue;
+            };
+            let Some(ticks) = entity.get_change_ticks_by_id(id) else {
+                continue;
+            };
+            if info.name().starts_with("iyes_cli::") {
+                continue;
+            }
+            let added = ticks.is_added(since, this_run);
+            all_added &= added;
+            if added {
+                changed.push(format!("+{}", ShortName(info.name())));
+            } else if ticks.is_changed(since, this_run) {
+                changed.push(ShortName(info.name()).to_string());
+            }
+        }
+        if changed.is_empty() {
+            continue;
+        }
+        if all_added {
+            let names: Vec<&str> = changed.iter().map(|s| &s[1..]).collect();
+            lines.push(format!("+ entity {}: {}", entity.id(), names.join(", ")));
+        } else {
+            lines.push(format!("~ entity {}: {}", entity.id(), changed.join(", ")));
+        }
+    }
+    if lines.len() == 1 {
+        lines.push("(no changes)".to_owned());
+    }
+    cli_println(world, lines.join("\n"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_cli;
+
+    fn take(cli: &str) -> (bool, Vec<String>) {
+        let mut parsed = parse_cli(cli).unwrap();
+        let found = take_flag(&mut parsed);
+        assert_eq!(parsed.tokens.len(), parsed.args.len() + 1);
+        assert_eq!(parsed.arg_spans.len(), parsed.args.len());
+        (found, parsed.args)
+    }
+
+    #[test]
+    fn only_unquoted_flag_before_double_dash() {
+        assert_eq!(take("spawn --explain 3"), (true, vec!["3".to_owned()]));
+        assert_eq!(take("spawn 3"), (false, vec!["3".to_owned()]));
+        assert_eq!(
+            take(r#"echo "--explain" \--explain"#),
+            (false, vec!["--explain".to_owned(), "--explain".to_owned()])
+        );
+        assert_eq!(
+            take("echo --explain -- --explain"),
+            (true, vec!["--".to_owned(), "--explain".to_owned()])
+        );
+    }
+}
@@ -0,0 +1,123 @@
+//! Deterministic execution of "console commands" at fixed timestep ticks.
+//!
+//! For networked or deterministic simulations, it is important that
+//! commands affecting the simulation run at exact simulation ticks,
+//! regardless of frame rate. Commands can be queued for a specific tick
+//! using [`CliCommandsRunExt::run_cli_at_tick`], and they will be run
+//! inside [`FixedUpdate`] when that tick is reached.
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+
+#[cfg(feature = "replay")]
+use crate::replay::CliTickRecorder;
+use crate::CliCommandsRunExt;
+
+/// The current fixed timestep tick, as seen by the deterministic queue
+///
+/// Incremented at the end of every run of the fixed timestep (in [`FixedLast`]).
+/// If your game has its own notion of simulation ticks, you can overwrite
+/// this value to keep them in sync.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CliFixedTick(pub u64);
+
+/// System set containing the system that runs tick-stamped "console commands"
+///
+/// It is in the [`FixedUpdate`] schedule.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CliFixedSet;
+
+/// Holds command strings waiting to be run at specific fixed timestep ticks
+///
+/// Commands for the same tick run in the order they were queued.
+///
+/// While paused, nothing is run. Commands whose tick passes while the queue
+/// is paused run late, after it is resumed.
+#[derive(Resource, Default)]
+pub struct CliFixedQueue {
+    entries: BTreeMap<u64, Vec<String>>,
+    paused: bool,
+}
+
+impl CliFixedQueue {
+    /// Add a command string to be run at the given tick
+    pub fn push(&mut self, tick: u64, cli: String) {
+        self.entries.entry(tick).or_default().push(cli);
+    }
+    /// Iterate over all queued entries, in the order they will run
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &str)> {
+        self.entries
+            .iter()
+            .flat_map(|(tick, clis)| clis.iter().map(move |cli| (*tick, cli.as_str())))
+    }
+    /// How many command strings are queued
+    pub fn len(&self) -> usize {
+        self.entries.values().map(|clis| clis.len()).sum()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// Remove all queued command strings
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    /// Remove and return all entries stamped with the given tick or earlier
+    fn take_due(&mut self, tick: u64) -> Vec<(u64, String)> {
+        let later = self.entries.split_off(&tick.saturating_add(1));
+        let due = std::mem::replace(&mut self.entries, later);
+        due.into_iter()
+            .flat_map(|(tick, clis)| clis.into_iter().map(move |cli| (tick, cli)))
+            .collect()
+    }
+}
+
+/// Command to queue a "console command" to be run at a given tick
+pub struct CliRunAtTickCommand(pub String, pub u64);
+
+impl bevy::ecs::world::Command for CliRunAtTickCommand {
+    fn apply(self, world: &mut World) {
+        world.run_cli_at_tick(&self.0, self.1);
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliFixedTick>();
+    app.init_resource::<CliFixedQueue>();
+    app.add_systems(FixedUpdate, run_due_clis.in_set(CliFixedSet));
+    app.add_systems(FixedLast, advance_tick);
+}
+
+fn run_due_clis(world: &mut World) {
+    if world.resource::<CliFixedQueue>().is_paused() {
+        return;
+    }
+    let current = world.resource::<CliFixedTick>().0;
+    let due = world.resource_mut::<CliFixedQueue>().take_due(current);
+    for (tick, cli) in due {
+        if tick < current {
+            warn!(
+                "CliCommand {:?} was queued for tick {}, but is running late at tick {}!",
+                cli, tick, current
+            );
+        }
+        #[cfg(feature = "replay")]
+        if let Some(mut recorder) = world.get_resource_mut::<CliTickRecorder>() {
+            recorder.record(current, &cli);
+        }
+        world.run_cli(&cli);
+    }
+}
+
+fn advance_tick(mut tick: ResMut<CliFixedTick>) {
+    tick.0 += 1;
+}
@@ -0,0 +1,170 @@
+//! Console commands generated from Bevy's reflected functions.
+//!
+//! Any function registered into the [`AppFunctionRegistry`] (for example,
+//! using `app.register_function(...)`) can be exposed as a console command.
+//! The string arguments are converted into the types expected by the
+//! function, based on its reflected signature.
+
+use std::any::TypeId;
+
+use bevy::ecs::reflect::AppFunctionRegistry;
+use bevy::prelude::*;
+use bevy::reflect::func::{ArgList, FunctionInfo, Return};
+use bevy::reflect::PartialReflect;
+
+use crate::output::CliOutput;
+use crate::CliCommandsRegisterExt;
+
+/// Provides methods for creating "console commands" from reflected functions
+pub trait CliFunctionsRegisterExt {
+    /// Create a new "console command" that calls a reflected function
+    ///
+    /// `function_name` is the name the function was registered with in the
+    /// [`AppFunctionRegistry`]. The function is looked up every time the
+    /// command runs, so it may be registered before or after the command.
+    ///
+    /// Arguments are parsed according to the function's signature. Supported
+    /// argument types are `bool`, `char`, `String`, and all the primitive
+    /// integer and float types. If the function returns a value, it is printed
+    /// to the output.
+    ///
+    /// Overloaded functions are not supported: Bevy 0.15 reflects a single
+    /// signature per function, which is the one the args are checked against.
+    ///
+    /// If a command with the same name already exists, it is replaced.
+    fn register_clicommand_function(&mut self, name: &str, function_name: &str) -> &mut Self;
+}
+
+impl CliFunctionsRegisterExt for World {
+    fn register_clicommand_function(&mut self, name: &str, function_name: &str) -> &mut Self {
+        let function_name = function_name.to_owned();
+        self.register_clicommand_args(
+            name,
+            move |In(args): In<Vec<String>>,
+                  registry: Res<AppFunctionRegistry>,
+                  mut output: CliOutput| {
+                match call_function(&registry, &function_name, &args) {
+                    Ok(Some(value)) => output.println(format!("{}: {}", function_name, value)),
+                    Ok(None) => {}
+                    Err(message) => output.fail(message),
+                }
+            },
+        )
+    }
+}
+
+impl CliFunctionsRegisterExt for App {
+    fn register_clicommand_function(&mut self, name: &str, function_name: &str) -> &mut Self {
+        self.world_mut()
+            .register_clicommand_function(name, function_name);
+        self
+    }
+}
+
+/// Call a reflected function, returning its return value, formatted, if any
+fn call_function(
+    registry: &AppFunctionRegistry,
+    function_name: &str,
+    args: &[String],
+) -> Result<Option<String>, String> {
+    let registry = registry.read();
+    let Some(function) = registry.get(function_name) else {
+        return Err(format!("Reflected function {:?} not found!", function_name));
+    };
+
+    let signature = function.info();
+    if signature.arg_count() != args.len() {
+        return Err(format!(
+            "Reflected function {:?} does not take {} args!",
+            function_name,
+            args.len()
+        ));
+    }
+
+    let arg_list = parse_args(signature, args)?;
+    match function.call(arg_list) {
+        Ok(ret) => Ok(format_return(ret)),
+        Err(e) => Err(format!(
+            "Reflected function {:?} failed: {}",
+            function_name, e
+        )),
+    }
+}
+
+fn parse_args<'a>(signature: &FunctionInfo, args: &[String]) -> Result<ArgList<'a>, String> {
+    let mut arg_list = ArgList::new();
+    for (info, arg) in signature.args().iter().zip(args) {
+        let Some(value) = parse_value(info.type_id(), arg) else {
+            return Err(format!(
+                "Cannot convert {:?} into a value of type {}!",
+                arg,
+                info.type_path()
+            ));
+        };
+        arg_list = arg_list.push_boxed(value);
+    }
+    Ok(arg_list)
+}
+
+fn parse_value(type_id: TypeId, s: &str) -> Option<Box<dyn PartialReflect>> {
+    macro_rules! try_parse {
+        ($($t:ty),*) => {
+            $(
+                if type_id == TypeId::of::<$t>() {
+                    return s.parse::<$t>().ok().map(|v| Box::new(v) as Box<dyn PartialReflect>);
+                }
+            )*
+        };
+    }
+    try_parse!(bool, char, String);
+    try_parse!(u8, u16, u32, u64, u128, usize);
+    try_parse!(i8, i16, i32, i64, i128, isize);
+    try_parse!(f32, f64);
+    None
+}
+
+fn format_return(ret: Return) -> Option<String> {
+    if ret.is_unit() {
+        return None;
+    }
+    Some(match ret {
+        Return::Owned(value) => format!("{:?}", value),
+        Return::Ref(value) => format!("{:?}", value),
+        Return::Mut(value) => format!("{:?}", value),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    #[test]
+    fn primitive_values() {
+        let value = parse_value(TypeId::of::<u8>(), "42").unwrap();
+        assert_eq!(value.try_downcast_ref::<u8>(), Some(&42));
+        let value = parse_value(TypeId::of::<f32>(), "-0.5").unwrap();
+        assert_eq!(value.try_downcast_ref::<f32>(), Some(&-0.5));
+        let value = parse_value(TypeId::of::<String>(), "hi").unwrap();
+        assert_eq!(value.try_downcast_ref::<String>().unwrap(), "hi");
+        assert!(parse_value(TypeId::of::<u8>(), "256").is_none());
+        assert!(parse_value(TypeId::of::<bool>(), "yes").is_none());
+        assert!(parse_value(TypeId::of::<Vec<u8>>(), "1").is_none());
+    }
+
+    #[test]
+    fn args_follow_the_signature() {
+        let registry = AppFunctionRegistry::default();
+        registry.write().register_with_name("add", add).unwrap();
+        let registry = registry.read();
+        let function = registry.get("add").unwrap();
+
+        let args = ["2".to_owned(), "40".to_owned()];
+        let arg_list = parse_args(function.info(), &args).unwrap();
+        let ret = function.call(arg_list).unwrap().unwrap_owned();
+        assert_eq!(ret.try_downcast_ref::<i32>(), Some(&42));
+    }
+}
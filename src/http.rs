@@ -0,0 +1,500 @@
+//! Running commands over HTTP, for web dashboards and scripts.
+//!
+//! Requires the `http` cargo feature. Add [`CliHttpPlugin`] (together with
+//! [`CliPlugin`](crate::CliPlugin)) to listen for requests. Command strings
+//! are run with `POST /cli`, with a JSON body holding the command string
+//! and the token set in the plugin:
+//!
+//! ```text
+//! POST /cli HTTP/1.1
+//! Content-Type: application/json
+//!
+//! {"command": "gravity 12", "token": "hunter2"}
+//! ```
+//!
+//! The reply is a JSON object with whether the command string succeeded,
+//! its output, and why it failed:
+//!
+//! ```text
+//! HTTP/1.1 200 OK
+//! Content-Type: application/json
+//!
+//! {"success":true,"output":[{"text":"gravity = 12","severity":"info"}],"error":null}
+//! ```
+//!
+//! The severity of a line is one of `info`, `success`, `warn` and `error`.
+//! A line describing a file attached to the output (see
+//! [`CliAttachment`](crate::output::CliAttachment)) also has an
+//! `attachment`, with its `name`, `mime` type, and `data` in base64.
+//! Requests that are not valid get a `4xx` status (`400` for a body that is
+//! not valid JSON, `401` for a wrong token, `429` over the rate limit), with
+//! the same JSON object, explaining why in `error`. Every request is
+//! answered on its own connection, which is then closed.
+//!
+//! Commands run with the [`CliSource::Remote`] source of the address of the
+//! client, like those of the [`remote`](crate::remote) console. Anyone with
+//! the token can run any command. Which addresses may send requests, how
+//! many, and tokens with fewer permissions are set in the
+//! [`CliRemoteSecurityConfig`], shared with the `remote` console. Requests
+//! are not encrypted, unless [`CliHttpPlugin::tls`] is set (with the `tls`
+//! cargo feature) to serve HTTPS. Otherwise, only listen on trusted
+//! networks, or put a proxy doing TLS in front.
+//!
+//! ```sh
+//! curl -d '{"command": "time", "token": "hunter2"}' http://127.0.0.1:8080/cli
+//! ```
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener};
+
+use base64::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::describe_error;
+use crate::output::{CliCommandsOutputExt, CliOutputLine};
+use crate::quota::with_cli_source;
+use crate::security::{
+    self, CliRemoteSecurityConfig, CliRemoteStream, CliTlsAcceptor, CliTlsConfig,
+};
+use crate::source::CliSource;
+
+/// Largest request a client may send, headers and body, in bytes
+const MAX_REQUEST_LEN: usize = 64 * 1024;
+
+/// Plugin that runs an HTTP server for running commands
+pub struct CliHttpPlugin {
+    /// The address to listen on, like `127.0.0.1:8080`
+    pub address: String,
+    /// The token requests must include to be allowed to run commands
+    pub token: String,
+    /// The certificate to encrypt connections with (HTTPS), if any
+    ///
+    /// See [`security`](crate::security).
+    pub tls: Option<CliTlsConfig>,
+}
+
+impl Plugin for CliHttpPlugin {
+    fn build(&self, app: &mut App) {
+        if self.token.is_empty() {
+            error!("Refusing to start HTTP CLI server without a token!");
+            return;
+        }
+        let tls = match CliTlsAcceptor::new(self.tls.as_ref()) {
+            Ok(tls) => tls,
+            Err(e) => {
+                error!("Cannot start HTTP CLI server on {}: {}", self.address, e);
+                return;
+            }
+        };
+        let listener = match TcpListener::bind(&self.address) {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("Cannot start HTTP CLI server on {}: {}", self.address, e);
+                return;
+            }
+        };
+        if let Err(e) = listener.set_nonblocking(true) {
+            error!("Cannot start HTTP CLI server on {}: {}", self.address, e);
+            return;
+        }
+        info!("HTTP CLI server listening on {}", self.address);
+        app.init_resource::<CliRemoteSecurityConfig>();
+        app.insert_resource(CliHttpServer {
+            listener,
+            tls,
+            token: self.token.clone(),
+            connections: Vec::new(),
+        });
+        app.add_systems(PreUpdate, serve_http);
+    }
+}
+
+/// The state of the HTTP server
+#[derive(Resource)]
+pub struct CliHttpServer {
+    listener: TcpListener,
+    tls: CliTlsAcceptor,
+    token: String,
+    connections: Vec<CliHttpConnection>,
+}
+
+impl CliHttpServer {
+    /// The address the server is listening on
+    ///
+    /// Useful to find the port chosen by the system, when listening on port `0`.
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+}
+
+/// The body of a `POST /cli` request
+#[derive(Deserialize)]
+struct CliHttpRequest {
+    command: String,
+    token: String,
+}
+
+/// The body of every reply
+#[derive(Serialize)]
+struct CliHttpReply {
+    success: bool,
+    output: Vec<CliHttpLine>,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CliHttpLine {
+    text: String,
+    severity: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attachment: Option<CliHttpAttachment>,
+}
+
+/// A file attached to a line, with its data in base64
+#[derive(Serialize)]
+struct CliHttpAttachment {
+    name: String,
+    mime: String,
+    data: String,
+}
+
+impl From<CliOutputLine> for CliHttpLine {
+    fn from(line: CliOutputLine) -> Self {
+        CliHttpLine {
+            severity: line.severity.as_str(),
+            text: line.text,
+            attachment: line.attachment.map(|attachment| CliHttpAttachment {
+                data: BASE64_STANDARD.encode(&attachment.data),
+                name: attachment.name,
+                mime: attachment.mime,
+            }),
+        }
+    }
+}
+
+struct CliHttpConnection {
+    stream: CliRemoteStream,
+    addr: SocketAddr,
+    incoming: Vec<u8>,
+    outgoing: Vec<u8>,
+    /// The reply has been queued, the connection closes once it is sent
+    replied: bool,
+    closed: bool,
+}
+
+impl CliHttpConnection {
+    /// Read everything that is available without blocking
+    fn receive(&mut self) {
+        let mut buf = [0; 1024];
+        loop {
+            match self.stream.read(&mut buf) {
+                Ok(0) => {
+                    self.closed = true;
+                    return;
+                }
+                Ok(n) => self.incoming.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => {
+                    self.closed = true;
+                    return;
+                }
+            }
+        }
+    }
+    /// The method, path and body of the request, once all of it was received
+    ///
+    /// Requests that cannot be parsed are answered with an error.
+    fn request(&mut self) -> Option<(String, String, Vec<u8>)> {
+        let Some(end) = self
+            .incoming
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+        else {
+            if self.incoming.len() > MAX_REQUEST_LEN {
+                self.reply(413, "Payload Too Large", error_reply("request too large"));
+            }
+            return None;
+        };
+        let head = String::from_utf8_lossy(&self.incoming[..end]).into_owned();
+        let mut lines = head.split("\r\n");
+        let mut request_line = lines.next().unwrap_or_default().split(' ');
+        let (Some(method), Some(path)) = (request_line.next(), request_line.next()) else {
+            self.reply(400, "Bad Request", error_reply("invalid request line"));
+            return None;
+        };
+        let mut content_length = 0;
+        for line in lines {
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                let Ok(length) = value.trim().parse::<usize>() else {
+                    self.reply(400, "Bad Request", error_reply("invalid Content-Length"));
+                    return None;
+                };
+                content_length = length;
+            }
+        }
+        let body_start = end + 4;
+        if body_start + content_length > MAX_REQUEST_LEN {
+            self.reply(413, "Payload Too Large", error_reply("request too large"));
+            return None;
+        }
+        if self.incoming.len() < body_start + content_length {
+            return None;
+        }
+        let body = self.incoming[body_start..body_start + content_length].to_vec();
+        Some((method.to_owned(), path.to_owned(), body))
+    }
+    fn reply(&mut self, status: u16, reason: &str, reply: CliHttpReply) {
+        let body = serde_json::to_string(&reply).unwrap_or_default();
+        let head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            status,
+            reason,
+            body.len()
+        );
+        self.outgoing.extend_from_slice(head.as_bytes());
+        self.outgoing.extend_from_slice(body.as_bytes());
+        self.replied = true;
+    }
+    /// Write as much of the reply as possible without blocking
+    fn flush(&mut self) {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => {
+                    self.closed = true;
+                    return;
+                }
+                Ok(n) => {
+                    self.outgoing.drain(..n);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => {
+                    self.closed = true;
+                    return;
+                }
+            }
+        }
+        if self.replied {
+            self.stream.close();
+        }
+        match self.stream.flush() {
+            Ok(()) => self.closed |= self.replied,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(_) => self.closed = true,
+        }
+    }
+}
+
+fn error_reply(error: impl Into<String>) -> CliHttpReply {
+    CliHttpReply {
+        success: false,
+        output: Vec::new(),
+        error: Some(error.into()),
+    }
+}
+
+fn serve_http(world: &mut World) {
+    world.resource_scope(|world, mut server: Mut<CliHttpServer>| {
+        let server = &mut *server;
+        loop {
+            match server.listener.accept() {
+                Ok((stream, addr)) => {
+                    if let Err(e) = stream.set_nonblocking(true) {
+                        error!("Cannot accept HTTP CLI client {}: {}", addr, e);
+                        continue;
+                    }
+                    let stream = match server.tls.accept(stream) {
+                        Ok(stream) => stream,
+                        Err(e) => {
+                            error!("Cannot accept HTTP CLI client {}: {}", addr, e);
+                            continue;
+                        }
+                    };
+                    let mut connection = CliHttpConnection {
+                        stream,
+                        addr,
+                        incoming: Vec::new(),
+                        outgoing: Vec::new(),
+                        replied: false,
+                        closed: false,
+                    };
+                    if !security::accept(world, addr) {
+                        connection.reply(403, "Forbidden", error_reply("address not allowed"));
+                    }
+                    server.connections.push(connection);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    error!("HTTP CLI server error: {}", e);
+                    break;
+                }
+            }
+        }
+        for connection in &mut server.connections {
+            if !connection.replied {
+                connection.receive();
+                if let Some((method, path, body)) = connection.request() {
+                    handle_request(world, connection, &server.token, &method, &path, &body);
+                }
+            }
+            connection.flush();
+        }
+        server.connections.retain(|connection| !connection.closed);
+    });
+}
+
+fn handle_request(
+    world: &mut World,
+    connection: &mut CliHttpConnection,
+    token: &str,
+    method: &str,
+    path: &str,
+    body: &[u8],
+) {
+    if path != "/cli" {
+        connection.reply(404, "Not Found", error_reply("not found"));
+        return;
+    }
+    if method != "POST" {
+        connection.reply(405, "Method Not Allowed", error_reply("use POST"));
+        return;
+    }
+    let request = match serde_json::from_slice::<CliHttpRequest>(body) {
+        Ok(request) => request,
+        Err(e) => {
+            connection.reply(400, "Bad Request", error_reply(e.to_string()));
+            return;
+        }
+    };
+    if security::authenticate(world, connection.addr, &request.token, token).is_none() {
+        warn!("HTTP CLI client {} sent a wrong token", connection.addr);
+        connection.reply(401, "Unauthorized", error_reply("wrong token"));
+        return;
+    }
+    if !security::check_rate(world, connection.addr, true) {
+        security::forget(world, connection.addr);
+        connection.reply(429, "Too Many Requests", error_reply("rate limit exceeded"));
+        return;
+    }
+    info!(
+        "HTTP CLI command from {}: {}",
+        connection.addr, request.command
+    );
+    let source = CliSource::Remote(connection.addr);
+    let reply = with_cli_source(world, source, |world| {
+        match world.try_run_cli(&request.command) {
+            Ok(output) => CliHttpReply {
+                success: true,
+                output: output.into_iter().map(CliHttpLine::from).collect(),
+                error: None,
+            },
+            Err(e) => CliHttpReply {
+                success: false,
+                output: Vec::new(),
+                error: Some(describe_error(world, &e)),
+            },
+        }
+    });
+    security::forget(world, connection.addr);
+    connection.reply(200, "OK", reply);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{cli_attach, cli_println, CliAttachment};
+    use crate::status::cli_fail;
+    use crate::{CliCommandsRegisterExt, CliPlugin};
+    use std::net::TcpStream;
+    use std::time::Duration;
+
+    fn post(addr: SocketAddr, path: &str, body: &str) -> (u16, serde_json::Value) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}",
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).unwrap();
+        let (head, body) = reply.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn http() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.add_plugins(CliHttpPlugin {
+            address: "127.0.0.1:0".to_owned(),
+            token: "hunter2".to_owned(),
+            tls: None,
+        });
+        app.register_clicommand_args("greet", |In(args): In<Vec<String>>, world: &mut World| {
+            cli_println(world, format!("Hello, {}!", args.join(" ")));
+        });
+        app.register_clicommand_noargs("broken", |world: &mut World| {
+            cli_fail(world);
+        });
+        app.register_clicommand_noargs("screenshot", |world: &mut World| {
+            cli_attach(world, CliAttachment::new("shot.png", "image/png", *b"PNG"));
+        });
+        let addr = app
+            .world()
+            .resource::<CliHttpServer>()
+            .local_addr()
+            .unwrap();
+
+        let thread = std::thread::spawn(move || {
+            let (status, reply) = post(
+                addr,
+                "/cli",
+                r#"{"command": "greet \"you there\"", "token": "hunter2"}"#,
+            );
+            assert_eq!(status, 200);
+            assert_eq!(
+                reply,
+                serde_json::json!({
+                    "success": true,
+                    "output": [{"text": "Hello, you there!", "severity": "info"}],
+                    "error": null,
+                })
+            );
+            let (status, reply) = post(addr, "/cli", r#"{"command": "nope", "token": "hunter2"}"#);
+            assert_eq!(status, 200);
+            assert_eq!(reply["success"], false);
+            assert_eq!(reply["error"], "CliCommand \"nope\" not found");
+            let (_, reply) = post(addr, "/cli", r#"{"command": "broken", "token": "hunter2"}"#);
+            assert_eq!(reply["success"], false);
+            let (_, reply) = post(
+                addr,
+                "/cli",
+                r#"{"command": "screenshot", "token": "hunter2"}"#,
+            );
+            assert_eq!(
+                reply["output"][0]["attachment"],
+                serde_json::json!({"name": "shot.png", "mime": "image/png", "data": "UE5H"})
+            );
+            let (status, _) = post(addr, "/cli", r#"{"command": "greet", "token": "wrong"}"#);
+            assert_eq!(status, 401);
+            let (status, _) = post(addr, "/cli", "greet");
+            assert_eq!(status, 400);
+            let (status, _) = post(addr, "/other", "{}");
+            assert_eq!(status, 404);
+        });
+        while !thread.is_finished() {
+            app.update();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        thread.join().unwrap();
+    }
+}
@@ -0,0 +1,157 @@
+//! Running command strings at most once per key, for retried submissions.
+//!
+//! A client that loses its connection after sending a command string cannot
+//! tell whether it ran. If it sends it again with the same idempotency key,
+//! it only runs once: the next times, the result of the first run is given
+//! back. Results are kept in [`CliIdempotency`] for its
+//! [`window`](CliIdempotency::window), after which the key can be reused.
+//!
+//! Keys can be given to the [`remote`](crate::remote) protocol (with
+//! `key: <key> <command string>`), to the [`CliQueue`](crate::queue::CliQueue)
+//! (with [`push_with_key`](crate::queue::CliQueue::push_with_key)), or to
+//! [`run_cli_once`] directly:
+//!
+//! ```rust,ignore
+//! fn on_request(world: &mut World, request: AdminRequest) {
+//!     run_cli_once(world, &request.id, "ban 4v2 --force");
+//! }
+//! ```
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::{HashMap, Instant};
+
+use crate::output::{capture_output, cli_push_line, CliOutputLine};
+use crate::run_cli_chain;
+
+/// The result of a command string run with an idempotency key
+#[derive(Debug, Clone)]
+pub struct CliKeyedResult {
+    pub success: bool,
+    pub output: Vec<CliOutputLine>,
+    /// When it ran
+    pub at: Instant,
+}
+
+/// The results of the command strings run with idempotency keys
+///
+/// Requires [`CliPlugin`](crate::CliPlugin).
+#[derive(Resource, Debug)]
+pub struct CliIdempotency {
+    results: HashMap<String, CliKeyedResult>,
+    /// How long results are kept (default: 10 minutes)
+    pub window: Duration,
+}
+
+impl Default for CliIdempotency {
+    fn default() -> Self {
+        CliIdempotency {
+            results: HashMap::new(),
+            window: Duration::from_secs(600),
+        }
+    }
+}
+
+impl CliIdempotency {
+    /// The result of the command string run with a key, if it is still kept
+    pub fn get(&self, key: &str) -> Option<&CliKeyedResult> {
+        self.results
+            .get(key)
+            .filter(|result| result.at.elapsed() < self.window)
+    }
+    /// Keep the result of the command string run with a key
+    pub fn insert(&mut self, key: &str, success: bool, output: Vec<CliOutputLine>) {
+        let window = self.window;
+        self.results
+            .retain(|_, result| result.at.elapsed() < window);
+        self.results.insert(
+            key.to_owned(),
+            CliKeyedResult {
+                success,
+                output,
+                at: Instant::now(),
+            },
+        );
+    }
+    /// Forget the result of a key, so it can run again
+    pub fn remove(&mut self, key: &str) -> Option<CliKeyedResult> {
+        self.results.remove(key)
+    }
+    pub fn clear(&mut self) {
+        self.results.clear();
+    }
+}
+
+/// Run a command string, unless it already ran with the same key, returning
+/// whether it succeeded
+///
+/// If it already ran, the result of that run is returned, and its output is
+/// not printed again.
+pub fn run_cli_once(world: &mut World, key: &str, cli: &str) -> bool {
+    let cached = world
+        .get_resource::<CliIdempotency>()
+        .and_then(|results| results.get(key));
+    if let Some(result) = cached {
+        debug!("Not running {:?} again, for key {:?}", cli, key);
+        return result.success;
+    }
+    let (output, result) = capture_output(world, |world| run_cli_chain(world, cli, true));
+    for line in output.iter().cloned() {
+        cli_push_line(world, line);
+    }
+    world
+        .get_resource_or_insert_with(CliIdempotency::default)
+        .insert(key, result.is_ok(), output);
+    result.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::CliOutputLog;
+    use crate::queue::CliQueue;
+    use crate::{CliCommandsRegisterExt, CliPlugin};
+
+    #[derive(Resource, Default)]
+    struct Bans(u32);
+
+    #[test]
+    fn runs_once_per_key() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.init_resource::<Bans>();
+        app.register_clicommand_noargs("ban", |mut bans: ResMut<Bans>| bans.0 += 1);
+        let world = app.world_mut();
+
+        assert!(run_cli_once(world, "a", "ban"));
+        assert!(run_cli_once(world, "a", "ban"));
+        assert_eq!(world.resource::<Bans>().0, 1);
+        assert!(!run_cli_once(world, "b", "nope"));
+        assert!(!run_cli_once(world, "b", "ban"));
+        assert_eq!(world.resource::<Bans>().0, 1);
+        let log = world.resource::<CliOutputLog>();
+        assert_eq!(
+            log.iter().filter(|line| line.text.contains("nope")).count(),
+            1
+        );
+
+        // Queued retries, before and after the first one ran
+        let mut queue = world.resource_mut::<CliQueue>();
+        queue.push_with_key("ban".to_owned(), "c");
+        queue.push_with_key("ban".to_owned(), "c");
+        assert_eq!(queue.len(), 1);
+        app.update();
+        app.world_mut()
+            .resource_mut::<CliQueue>()
+            .push_with_key("ban".to_owned(), "c");
+        app.update();
+        assert_eq!(app.world().resource::<Bans>().0, 2);
+
+        // Until the result is forgotten
+        let world = app.world_mut();
+        world.resource_mut::<CliIdempotency>().window = Duration::ZERO;
+        assert!(run_cli_once(world, "a", "ban"));
+        assert_eq!(world.resource::<Bans>().0, 3);
+    }
+}
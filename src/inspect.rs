@@ -0,0 +1,233 @@
+//! Built-in commands for inspecting the `World`, using reflection.
+//!
+//! Requires the `builtin-commands` cargo feature. Add [`CliInspectPlugin`]
+//! (together with [`CliPlugin`](crate::CliPlugin)) to register:
+//!
+//!  - `entities [limit]`: list entities, with the names of their components
+//!    (clicking an entity in a console inspects it)
+//!  - `inspect <entity>`: print the values of the reflected components of an
+//!    entity (given as `12` or `12v1`, like entities are printed)
+//!  - `resource <type>`: print the value of a reflected resource
+//!  - `setres <type.field> <value>`: set a field of a reflected resource
+//!
+//! Types can be given by their short name (`Gravity`) or their full path
+//! (`my_game::physics::Gravity`). Only types registered with
+//! `app.register_type::<T>()` can be inspected. `setres` supports fields of
+//! type `bool`, `char`, `String`, and all the primitive integer and float
+//! types.
+
+use std::any::TypeId;
+
+use bevy::ecs::reflect::{AppTypeRegistry, ReflectComponent, ReflectResource};
+use bevy::prelude::*;
+use bevy::reflect::{GetPath, PartialReflect, TypeRegistration, TypeRegistry};
+
+use crate::output::{cli_error, cli_println, cli_push_line, CliLink, CliOutputLine};
+use crate::short_name::ShortName;
+use crate::status::cli_fail;
+use crate::CliCommandsRegisterExt;
+
+/// Plugin that adds the built-in reflection-based commands
+pub struct CliInspectPlugin;
+
+impl Plugin for CliInspectPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_clicommand_args("entities", entities)
+            .set_clicommand_description("entities", "List entities and their components")
+            .set_clicommand_usage("entities", "entities [limit]")
+            .set_clicommand_paged("entities");
+        app.register_clicommand_args("inspect", inspect)
+            .set_clicommand_description("inspect", "Print the components of an entity")
+            .set_clicommand_usage("inspect", "inspect <entity>");
+        app.register_clicommand_args("resource", resource)
+            .set_clicommand_description("resource", "Print the value of a resource")
+            .set_clicommand_usage("resource", "resource <type>");
+        app.register_clicommand_args("setres", setres)
+            .set_clicommand_description("setres", "Set a field of a resource")
+            .set_clicommand_usage("setres", "setres <type.field> <value>");
+    }
+}
+
+/// `entities [limit]`: list entities, with the names of their components
+fn entities(In(args): In<Vec<String>>, world: &mut World) {
+    let limit = match args.first().map(|arg| arg.parse::<usize>()) {
+        None => usize::MAX,
+        Some(Ok(limit)) => limit,
+        Some(Err(_)) => {
+            cli_error(world, "Usage: entities [limit]");
+            cli_fail(world);
+            return;
+        }
+    };
+    let mut lines = Vec::new();
+    for entity in world.iter_entities().take(limit) {
+        let names: Vec<String> = world
+            .inspect_entity(entity.id())
+            .map(|info| ShortName(info.name()).to_string())
+            .collect();
+        let id = entity.id().to_string();
+        let mut line = CliOutputLine::new(String::new())
+            .push_link(&id, CliLink::Run(format!("inspect {}", id)));
+        line.text.push_str(&format!(": {}", names.join(", ")));
+        lines.push(line);
+    }
+    let total = world.entities().len();
+    let shown = lines.len();
+    for line in lines {
+        cli_push_line(world, line);
+    }
+    if shown < total as usize {
+        cli_println(world, format!("({} of {} entities shown)", shown, total));
+    }
+}
+
+/// `inspect <entity>`: print the values of the reflected components of an entity
+fn inspect(In(args): In<Vec<String>>, world: &mut World) {
+    let [arg] = args.as_slice() else {
+        cli_error(world, "Usage: inspect <entity>");
+        cli_fail(world);
+        return;
+    };
+    let entity = parse_entity(arg).filter(|&entity| world.entities().contains(entity));
+    let Some(entity) = entity else {
+        cli_error(world, format!("Entity {:?} not found!", arg));
+        cli_fail(world);
+        return;
+    };
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    let entity_ref = world.entity(entity);
+    let mut lines = Vec::new();
+    for info in world.inspect_entity(entity) {
+        let name = ShortName(info.name());
+        let reflected = info
+            .type_id()
+            .and_then(|type_id| registry.get_type_data::<ReflectComponent>(type_id))
+            .and_then(|reflect| reflect.reflect(entity_ref));
+        match reflected {
+            Some(value) => lines.push(format!("{}: {:?}", name, value)),
+            None => lines.push(format!("{}: (not reflected)", name)),
+        }
+    }
+    cli_println(world, lines.join("\n"));
+}
+
+/// `resource <type>`: print the value of a reflected resource
+fn resource(In(args): In<Vec<String>>, world: &mut World) {
+    let [type_name] = args.as_slice() else {
+        cli_error(world, "Usage: resource <type>");
+        cli_fail(world);
+        return;
+    };
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    let reflect = find_type(&registry, type_name).and_then(|r| r.data::<ReflectResource>());
+    let Some(reflect) = reflect else {
+        cli_error(
+            world,
+            format!("Reflected resource type {:?} not found!", type_name),
+        );
+        cli_fail(world);
+        return;
+    };
+    let Some(value) = reflect.reflect(&*world) else {
+        cli_error(world, format!("Resource {:?} does not exist!", type_name));
+        cli_fail(world);
+        return;
+    };
+    let text = format!("{:?}", value);
+    cli_println(world, text);
+}
+
+/// `setres <type.field> <value>`: set a field of a reflected resource
+fn setres(In(args): In<Vec<String>>, world: &mut World) {
+    let [path, value] = args.as_slice() else {
+        cli_error(world, "Usage: setres <type.field> <value>");
+        cli_fail(world);
+        return;
+    };
+    let Some((type_name, field)) = path.split_once('.') else {
+        cli_error(world, "Usage: setres <type.field> <value>");
+        cli_fail(world);
+        return;
+    };
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    let reflect = find_type(&registry, type_name).and_then(|r| r.data::<ReflectResource>());
+    let Some(reflect) = reflect else {
+        cli_error(
+            world,
+            format!("Reflected resource type {:?} not found!", type_name),
+        );
+        cli_fail(world);
+        return;
+    };
+    let Some(mut resource) = reflect.reflect_mut(&mut *world) else {
+        cli_error(world, format!("Resource {:?} does not exist!", type_name));
+        cli_fail(world);
+        return;
+    };
+    let target = match resource.reflect_path_mut(field) {
+        Ok(target) => target,
+        Err(e) => {
+            cli_error(world, format!("Cannot access {:?}: {}", path, e));
+            cli_fail(world);
+            return;
+        }
+    };
+    let Some(type_id) = target
+        .get_represented_type_info()
+        .map(|info| info.type_id())
+    else {
+        cli_error(world, format!("Cannot set {:?}: unknown field type", path));
+        cli_fail(world);
+        return;
+    };
+    let Some(parsed) = parse_value(type_id, value) else {
+        cli_error(
+            world,
+            format!("Cannot convert {:?} into the type of {:?}!", value, path),
+        );
+        cli_fail(world);
+        return;
+    };
+    if let Err(e) = target.try_apply(&*parsed) {
+        cli_error(world, format!("Cannot set {:?}: {}", path, e));
+        cli_fail(world);
+    }
+}
+
+fn find_type<'a>(registry: &'a TypeRegistry, name: &str) -> Option<&'a TypeRegistration> {
+    registry
+        .get_with_type_path(name)
+        .or_else(|| registry.get_with_short_type_path(name))
+}
+
+/// Parse an entity as printed by its `Display` impl (`12v1`), or just its index
+fn parse_entity(s: &str) -> Option<Entity> {
+    match s.split_once('v') {
+        Some((index, generation)) => {
+            let index: u32 = index.parse().ok()?;
+            let generation: u32 = generation.parse().ok()?;
+            Entity::try_from_bits((u64::from(generation) << 32) | u64::from(index)).ok()
+        }
+        None => Some(Entity::from_raw(s.parse().ok()?)),
+    }
+}
+
+fn parse_value(type_id: TypeId, s: &str) -> Option<Box<dyn PartialReflect>> {
+    macro_rules! try_parse {
+        ($($t:ty),*) => {
+            $(
+                if type_id == TypeId::of::<$t>() {
+                    return s.parse::<$t>().ok().map(|v| Box::new(v) as Box<dyn PartialReflect>);
+                }
+            )*
+        };
+    }
+    try_parse!(bool, char, String);
+    try_parse!(u8, u16, u32, u64, u128, usize);
+    try_parse!(i8, i16, i32, i64, i128, isize);
+    try_parse!(f32, f64);
+    None
+}
@@ -0,0 +1,251 @@
+//! Observing, rewriting and blocking commands before they run.
+//!
+//! Interceptors are systems that see every command typed by the user (not
+//! the commands run by other commands) before it runs, and decide what
+//! happens to it. This allows teaching the console through onboarding flows:
+//!
+//! ```rust,ignore
+//! app.add_cli_interceptor(tutorial);
+//!
+//! fn tutorial(In(cmd): In<CliIntercepted>, mut step: ResMut<TutorialStep>) -> CliInterception {
+//!     match *step {
+//!         TutorialStep::Spawn if cmd.name != "spawn" => {
+//!             CliInterception::Block("try `spawn 100 100` instead".into())
+//!         }
+//!         TutorialStep::Spawn => {
+//!             *step = TutorialStep::Inspect;
+//!             CliInterception::Annotate("Well done! Now try `inspect`.".into())
+//!         }
+//!         _ => CliInterception::Allow,
+//!     }
+//! }
+//! ```
+//!
+//! Interceptors run in the order they were added. If any of them blocks the
+//! command, it fails with [`CliError::Intercepted`], and the ones after it
+//! do not run.
+//!
+//! Hooks are lower level: they see every command, including the commands
+//! run by other commands and scripts, before its name is resolved, so the
+//! name is as typed and may not exist. A hook can let the command continue,
+//! cancel it (failing with [`CliError::Intercepted`]), or rewrite its name
+//! and args. Useful for audit logging, blocking commands during cutscenes,
+//! or renaming legacy commands:
+//!
+//! ```rust,ignore
+//! app.add_cli_hook(audit).add_cli_hook(cutscene).add_cli_hook(legacy_names);
+//!
+//! fn audit(In(cmd): In<CliIntercepted>, status: Res<CliStatus>) -> CliHookDecision {
+//!     info!("{:?} ran {} {:?}", status.source(), cmd.name, cmd.args);
+//!     CliHookDecision::Continue
+//! }
+//!
+//! fn cutscene(In(_): In<CliIntercepted>, cutscene: Res<Cutscene>) -> CliHookDecision {
+//!     match cutscene.playing {
+//!         true => CliHookDecision::Cancel("not during cutscenes".into()),
+//!         false => CliHookDecision::Continue,
+//!     }
+//! }
+//!
+//! fn legacy_names(In(mut cmd): In<CliIntercepted>) -> CliHookDecision {
+//!     if cmd.name != "sv_gravity" {
+//!         return CliHookDecision::Continue;
+//!     }
+//!     cmd.name = "physics.gravity".into();
+//!     CliHookDecision::Rewrite(cmd)
+//! }
+//! ```
+//!
+//! Hooks run in the order they were added, before interceptors, and each
+//! one sees the command as rewritten by the ones before it.
+
+use bevy::ecs::system::SystemId;
+use bevy::prelude::*;
+
+use crate::error::CliError;
+use crate::local::CliCurrentCommand;
+use crate::output::{cli_println_severity, CliSeverity};
+
+/// A command about to run, given to interceptors and hooks
+#[derive(Debug, Clone)]
+pub struct CliIntercepted {
+    /// The name of the command (the full name, if it was abbreviated; as
+    /// typed, for hooks)
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+/// What should happen to an intercepted command
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CliInterception {
+    /// Run the command
+    #[default]
+    Allow,
+    /// Run the command, and print a note after its output
+    Annotate(String),
+    /// Do not run the command, and show a hint instead
+    Block(String),
+}
+
+/// What a hook does with a command
+#[derive(Debug, Clone, Default)]
+pub enum CliHookDecision {
+    /// Go on with the command
+    #[default]
+    Continue,
+    /// Go on with a different command name and args
+    Rewrite(CliIntercepted),
+    /// Do not run the command, and show why
+    Cancel(String),
+}
+
+/// The registered interceptors and hooks
+#[derive(Resource, Default)]
+struct CliInterceptors {
+    systems: Vec<SystemId<In<CliIntercepted>, CliInterception>>,
+    hooks: Vec<SystemId<In<CliIntercepted>, CliHookDecision>>,
+}
+
+/// Provides methods for adding interceptors and hooks
+pub trait CliInterceptRegisterExt {
+    /// Add a system that sees commands typed by the user before they run
+    ///
+    /// See [`intercept`](crate::intercept).
+    fn add_cli_interceptor<S, Param>(&mut self, system: S) -> &mut Self
+    where
+        S: IntoSystem<In<CliIntercepted>, CliInterception, Param> + 'static;
+
+    /// Add a system that sees every command before it runs, and may rewrite or cancel it
+    ///
+    /// See [`intercept`](crate::intercept).
+    fn add_cli_hook<S, Param>(&mut self, system: S) -> &mut Self
+    where
+        S: IntoSystem<In<CliIntercepted>, CliHookDecision, Param> + 'static;
+}
+
+impl CliInterceptRegisterExt for World {
+    fn add_cli_interceptor<S, Param>(&mut self, system: S) -> &mut Self
+    where
+        S: IntoSystem<In<CliIntercepted>, CliInterception, Param> + 'static,
+    {
+        let id = self.register_system(system);
+        self.get_resource_or_insert_with(CliInterceptors::default)
+            .systems
+            .push(id);
+        self
+    }
+    fn add_cli_hook<S, Param>(&mut self, system: S) -> &mut Self
+    where
+        S: IntoSystem<In<CliIntercepted>, CliHookDecision, Param> + 'static,
+    {
+        let id = self.register_system(system);
+        self.get_resource_or_insert_with(CliInterceptors::default)
+            .hooks
+            .push(id);
+        self
+    }
+}
+
+impl CliInterceptRegisterExt for App {
+    fn add_cli_interceptor<S, Param>(&mut self, system: S) -> &mut Self
+    where
+        S: IntoSystem<In<CliIntercepted>, CliInterception, Param> + 'static,
+    {
+        self.world_mut().add_cli_interceptor(system);
+        self
+    }
+    fn add_cli_hook<S, Param>(&mut self, system: S) -> &mut Self
+    where
+        S: IntoSystem<In<CliIntercepted>, CliHookDecision, Param> + 'static,
+    {
+        self.world_mut().add_cli_hook(system);
+        self
+    }
+}
+
+/// Run the hooks for a command, rewriting its name and args as they decide
+pub(crate) fn run_hooks(
+    world: &mut World,
+    name: &mut String,
+    args: &mut Vec<String>,
+) -> Result<(), CliError> {
+    let Some(interceptors) = world.get_resource::<CliInterceptors>() else {
+        return Ok(());
+    };
+    for id in interceptors.hooks.clone() {
+        let hooked = CliIntercepted {
+            name: name.clone(),
+            args: args.clone(),
+        };
+        match world.run_system_with_input(id, hooked) {
+            Ok(CliHookDecision::Continue) => {}
+            Ok(CliHookDecision::Rewrite(rewritten)) => {
+                debug!(
+                    "CliCommand {:?} rewritten by a hook to {:?}",
+                    name, rewritten.name
+                );
+                *name = rewritten.name;
+                *args = rewritten.args;
+            }
+            Ok(CliHookDecision::Cancel(hint)) => {
+                return Err(CliError::Intercepted {
+                    name: name.clone(),
+                    hint,
+                });
+            }
+            Err(e) => {
+                let message = format!("CliCommand hook failed to run: {}", e);
+                cli_println_severity(world, CliSeverity::Error, message);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Run the interceptors for a command, returning the annotations to print
+///
+/// Commands run by other commands are not intercepted.
+pub(crate) fn check(
+    world: &mut World,
+    name: &str,
+    args: &[String],
+) -> Result<Vec<String>, CliError> {
+    let nested = world
+        .get_resource::<CliCurrentCommand>()
+        .is_some_and(|current| current.name().is_some());
+    let Some(interceptors) = world.get_resource::<CliInterceptors>() else {
+        return Ok(Vec::new());
+    };
+    if nested {
+        return Ok(Vec::new());
+    }
+    let mut notes = Vec::new();
+    for id in interceptors.systems.clone() {
+        let intercepted = CliIntercepted {
+            name: name.to_owned(),
+            args: args.to_vec(),
+        };
+        match world.run_system_with_input(id, intercepted) {
+            Ok(CliInterception::Allow) => {}
+            Ok(CliInterception::Annotate(note)) => notes.push(note),
+            Ok(CliInterception::Block(hint)) => {
+                return Err(CliError::Intercepted {
+                    name: name.to_owned(),
+                    hint,
+                });
+            }
+            Err(e) => {
+                let message = format!("CliCommand interceptor failed to run: {}", e);
+                cli_println_severity(world, CliSeverity::Error, message);
+            }
+        }
+    }
+    Ok(notes)
+}
+
+/// Print the annotations of interceptors, after the command has run
+pub(crate) fn annotate(world: &mut World, notes: Vec<String>) {
+    for note in notes {
+        cli_println_severity(world, CliSeverity::Success, note);
+    }
+}
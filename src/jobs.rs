@@ -0,0 +1,529 @@
+//! Long-running "console commands", doing their work in background tasks.
+//!
+//! A command can start a job: a future that runs on Bevy's
+//! [`AsyncComputeTaskPool`], over as many frames as it needs. Jobs can print
+//! output and report their progress through their [`CliJobHandle`]. The
+//! output is printed to the console as it arrives, and a message is printed
+//! when the job finishes or fails.
+//!
+//! ```rust,ignore
+//! app.register_clicommand_async("bake_navmesh", |args, job| async move {
+//!     for (i, tile) in tiles(&args).enumerate() {
+//!         bake_tile(tile);
+//!         job.set_progress(i as f32 / TILE_COUNT as f32);
+//!     }
+//!     job.println("Navmesh baked");
+//!     Ok(())
+//! });
+//! ```
+//!
+//! Commands that need data from the `World` to start their job can use the
+//! [`CliJobs`] resource directly, and call [`CliJobs::spawn`].
+//!
+//! The built-in `jobs` command lists the running jobs, and `cancel <id>`
+//! stops one. Cancelled jobs are dropped at their next `.await`; jobs doing
+//! blocking work should check [`CliJobHandle::is_cancelled`].
+//!
+//! By default, a command can be run again while its job is running, and
+//! the jobs run in parallel. Commands whose jobs must not overlap can be
+//! set to another [`CliConcurrency`], with
+//! [`set_clicommand_concurrency`](crate::CliCommandsRegisterExt::set_clicommand_concurrency):
+//!
+//! ```rust,ignore
+//! app.register_clicommand_async("load_level", load_level)
+//!     .set_clicommand_concurrency("load_level", CliConcurrency::Exclusive);
+//! ```
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use bevy::tasks::futures_lite::future;
+use bevy::tasks::{block_on, AsyncComputeTaskPool, Task, TaskPool};
+use bevy::utils::Instant;
+
+use crate::output::{cli_push_line, CliOutput, CliOutputLine, CliSeverity};
+use crate::quota::{self, CliQuotas};
+use crate::source::CliSource;
+use crate::table::{CliAlign, CliTable};
+use crate::{CliCommands, CliCommandsRegisterExt};
+
+/// The result of a job: an error message if it failed
+pub type CliJobResult = Result<(), String>;
+
+/// Whether a command may start a job while another job it started is running
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CliConcurrency {
+    /// The jobs run at the same time
+    #[default]
+    Parallel,
+    /// The job is queued, and starts when the others are finished
+    Serial,
+    /// The command fails while another of its jobs is running
+    Exclusive,
+}
+
+/// How a job was started, by [`CliJobs::spawn_with`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CliJobStart {
+    /// The job is running, with this id
+    Started(u64),
+    /// The job will start after the job `after` of the same command
+    Queued { id: u64, after: u64 },
+}
+
+impl CliJobStart {
+    pub fn id(&self) -> u64 {
+        match self {
+            CliJobStart::Started(id) | CliJobStart::Queued { id, .. } => *id,
+        }
+    }
+}
+
+#[derive(Default)]
+struct CliJobShared {
+    output: Mutex<Vec<String>>,
+    progress: Mutex<Option<f32>>,
+    cancelled: AtomicBool,
+}
+
+/// Given to a job, for reporting back to the console
+#[derive(Clone)]
+pub struct CliJobHandle {
+    id: u64,
+    shared: Arc<CliJobShared>,
+}
+
+impl CliJobHandle {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+    /// Print a line of output to the console, on the next frame
+    pub fn println(&self, text: impl Into<String>) {
+        if let Ok(mut output) = self.shared.output.lock() {
+            output.push(text.into());
+        }
+    }
+    /// Report how much of the work is done, from 0.0 to 1.0
+    pub fn set_progress(&self, progress: f32) {
+        if let Ok(mut p) = self.shared.progress.lock() {
+            *p = Some(progress.clamp(0.0, 1.0));
+        }
+    }
+    /// Check if the job was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.shared.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+struct CliJob {
+    id: u64,
+    command: String,
+    /// The source that started the job (see [`quota`](crate::quota))
+    source: Option<String>,
+    /// Where the output of the job goes (see [`source`](crate::source))
+    invoker: Option<CliSource>,
+    started: Instant,
+    shared: Arc<CliJobShared>,
+    task: Task<CliJobResult>,
+}
+
+/// Spawns the task of a queued job
+type CliJobStarter = Box<dyn FnOnce(CliJobHandle) -> Task<CliJobResult> + Send + Sync>;
+
+/// A job waiting for the other jobs of its command (see [`CliConcurrency::Serial`])
+struct CliQueuedJob {
+    id: u64,
+    command: String,
+    source: Option<String>,
+    invoker: Option<CliSource>,
+    queued: Instant,
+    start: CliJobStarter,
+}
+
+/// Information about a running job
+///
+/// Obtained from [`CliJobs::iter`].
+#[derive(Debug, Clone)]
+pub struct CliJobInfo<'a> {
+    pub id: u64,
+    /// The name of the command that started the job
+    pub command: &'a str,
+    /// The source that started the job, if known (see [`quota`](crate::quota))
+    pub source: Option<&'a str>,
+    /// When the job started, or was queued
+    pub started: Instant,
+    /// The last progress reported by the job, if any
+    pub progress: Option<f32>,
+    /// Whether the job is waiting for the other jobs of its command
+    pub queued: bool,
+}
+
+/// The jobs started by "console commands" that are still running
+///
+/// Requires [`CliPlugin`](crate::CliPlugin).
+#[derive(Resource, Default)]
+pub struct CliJobs {
+    jobs: Vec<CliJob>,
+    queued: Vec<CliQueuedJob>,
+    next_id: u64,
+}
+
+impl CliJobs {
+    /// Start a job, returning its id
+    ///
+    /// `make` is called immediately with the handle of the job, and the future
+    /// it returns is run on the [`AsyncComputeTaskPool`].
+    pub fn spawn<F, Fut>(&mut self, command: &str, make: F) -> u64
+    where
+        F: FnOnce(CliJobHandle) -> Fut,
+        Fut: Future<Output = CliJobResult> + Send + 'static,
+    {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.start(id, command, None, None, |handle| {
+            AsyncComputeTaskPool::get_or_init(TaskPool::default).spawn(make(handle))
+        });
+        id
+    }
+    /// Start a job, unless the [`CliConcurrency`] of its command says
+    /// otherwise
+    ///
+    /// Like [`spawn`](Self::spawn), but fails with the id of another job of
+    /// the command if it is [`Exclusive`](CliConcurrency::Exclusive), or
+    /// queues the job if it is [`Serial`](CliConcurrency::Serial).
+    pub fn spawn_with<F, Fut>(
+        &mut self,
+        command: &str,
+        concurrency: CliConcurrency,
+        make: F,
+    ) -> Result<CliJobStart, u64>
+    where
+        F: FnOnce(CliJobHandle) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = CliJobResult> + Send + 'static,
+    {
+        let running: Vec<u64> = self
+            .iter()
+            .filter(|job| job.command == command)
+            .map(|job| job.id)
+            .collect();
+        let after = match concurrency {
+            CliConcurrency::Parallel => None,
+            CliConcurrency::Serial => running.last().copied(),
+            CliConcurrency::Exclusive => match running.first() {
+                Some(&id) => return Err(id),
+                None => None,
+            },
+        };
+        let Some(after) = after else {
+            return Ok(CliJobStart::Started(self.spawn(command, make)));
+        };
+        self.next_id += 1;
+        let id = self.next_id;
+        self.queued.push(CliQueuedJob {
+            id,
+            command: command.to_owned(),
+            source: None,
+            invoker: None,
+            queued: Instant::now(),
+            start: Box::new(|handle| {
+                AsyncComputeTaskPool::get_or_init(TaskPool::default).spawn(make(handle))
+            }),
+        });
+        Ok(CliJobStart::Queued { id, after })
+    }
+    fn start(
+        &mut self,
+        id: u64,
+        command: &str,
+        source: Option<String>,
+        invoker: Option<CliSource>,
+        spawn: impl FnOnce(CliJobHandle) -> Task<CliJobResult>,
+    ) {
+        let shared = Arc::new(CliJobShared::default());
+        let handle = CliJobHandle {
+            id,
+            shared: shared.clone(),
+        };
+        self.jobs.push(CliJob {
+            id,
+            command: command.to_owned(),
+            source,
+            invoker,
+            started: Instant::now(),
+            shared,
+            task: spawn(handle),
+        });
+    }
+    /// Cancel a job, returning whether it was running or queued
+    pub fn cancel(&mut self, id: u64) -> bool {
+        if let Some(i) = self.queued.iter().position(|job| job.id == id) {
+            self.queued.remove(i);
+            return true;
+        }
+        let Some(i) = self.jobs.iter().position(|job| job.id == id) else {
+            return false;
+        };
+        let job = self.jobs.remove(i);
+        job.shared.cancelled.store(true, Ordering::Relaxed);
+        // Dropping the task cancels it
+        drop(job.task);
+        true
+    }
+    /// Iterate over the running jobs, oldest first, then the queued ones
+    pub fn iter(&self) -> impl Iterator<Item = CliJobInfo<'_>> {
+        let running = self.jobs.iter().map(|job| CliJobInfo {
+            id: job.id,
+            command: &job.command,
+            source: job.source.as_deref(),
+            started: job.started,
+            progress: job.shared.progress.lock().ok().and_then(|p| *p),
+            queued: false,
+        });
+        let queued = self.queued.iter().map(|job| CliJobInfo {
+            id: job.id,
+            command: &job.command,
+            source: job.source.as_deref(),
+            started: job.queued,
+            progress: None,
+            queued: true,
+        });
+        running.chain(queued)
+    }
+    pub fn len(&self) -> usize {
+        self.jobs.len() + self.queued.len()
+    }
+    /// Remember which source started a job
+    pub(crate) fn set_source(&mut self, id: u64, source: Option<CliSource>) {
+        let name = source.as_ref().map(|source| source.to_string());
+        if let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) {
+            (job.source, job.invoker) = (name, source);
+        } else if let Some(job) = self.queued.iter_mut().find(|job| job.id == id) {
+            (job.source, job.invoker) = (name, source);
+        }
+    }
+    /// Start the queued jobs whose commands have no running jobs, returning
+    /// them
+    fn start_queued(&mut self) -> Vec<(Option<CliSource>, u64, String)> {
+        let mut started = Vec::new();
+        let mut i = 0;
+        while i < self.queued.len() {
+            let command = &self.queued[i].command;
+            if self.jobs.iter().any(|job| &job.command == command) {
+                i += 1;
+                continue;
+            }
+            let job = self.queued.remove(i);
+            started.push((job.invoker.clone(), job.id, job.command.clone()));
+            self.start(job.id, &job.command, job.source, job.invoker, job.start);
+        }
+        started
+    }
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty() && self.queued.is_empty()
+    }
+}
+
+/// Register a command that starts a job with the given function
+pub(crate) fn register_async<R, F, Fut>(registrar: &mut R, name: &str, f: F)
+where
+    R: CliCommandsRegisterExt + ?Sized,
+    F: Fn(Vec<String>, CliJobHandle) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = CliJobResult> + Send + 'static,
+{
+    let f = Arc::new(f);
+    registrar.register_clicommand_args(
+        name,
+        move |In(args): In<Vec<String>>,
+              current: Res<crate::local::CliCurrentCommand>,
+              clicommands: Res<CliCommands>,
+              quotas: Option<Res<CliQuotas>>,
+              mut jobs: ResMut<CliJobs>,
+              mut output: CliOutput| {
+            let source = output.source().cloned();
+            if let Some(source) = &source {
+                if !quota::may_start_job(quotas.as_deref(), &jobs, &source.to_string()) {
+                    output.fail(format!("{} has too many jobs running!", source));
+                    return;
+                }
+            }
+            let command = current.name().unwrap_or_default();
+            let concurrency = clicommands.concurrency(command);
+            let f = f.clone();
+            match jobs.spawn_with(command, concurrency, move |handle| f(args, handle)) {
+                Ok(start) => {
+                    jobs.set_source(start.id(), source);
+                    match start {
+                        CliJobStart::Started(id) => output.println(format!("Started job {}", id)),
+                        CliJobStart::Queued { id, after } => {
+                            output.println(format!("Queued job {}, after job {}", id, after))
+                        }
+                    }
+                }
+                Err(running) => output.fail(format!(
+                    "{} is already running, as job {}!",
+                    command, running
+                )),
+            }
+        },
+    );
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliJobs>();
+    app.add_systems(Update, poll_jobs);
+    app.register_clicommand_noargs("jobs", jobs)
+        .set_clicommand_description("jobs", "List the running background jobs");
+    app.register_clicommand_args("cancel", cancel)
+        .set_clicommand_description("cancel", "Cancel a background job")
+        .set_clicommand_usage("cancel", "cancel <id>");
+}
+
+fn poll_jobs(world: &mut World) {
+    let mut lines = Vec::new();
+    let mut finished = Vec::new();
+    let mut jobs = world.resource_mut::<CliJobs>();
+    jobs.jobs.retain_mut(|job| {
+        if let Ok(mut output) = job.shared.output.lock() {
+            let invoker = &job.invoker;
+            lines.extend(output.drain(..).map(|line| (invoker.clone(), line)));
+        }
+        if !job.task.is_finished() {
+            return true;
+        }
+        if let Some(result) = block_on(future::poll_once(&mut job.task)) {
+            finished.push((job.invoker.clone(), job.id, job.command.clone(), result));
+        }
+        false
+    });
+    let started = jobs.start_queued();
+    // The output goes to whoever started the job
+    let println_for = |world: &mut World, invoker: Option<CliSource>, severity, text: String| {
+        let line = CliOutputLine {
+            source: invoker,
+            ..CliOutputLine::new(text).with_severity(severity)
+        };
+        cli_push_line(world, line);
+    };
+    for (invoker, line) in lines {
+        println_for(world, invoker, CliSeverity::Info, line);
+    }
+    for (invoker, id, command, result) in finished {
+        match result {
+            Ok(()) => {
+                let text = format!("Job {} ({}) finished", id, command);
+                println_for(world, invoker, CliSeverity::Info, text);
+            }
+            Err(e) => {
+                let text = format!("Job {} ({}) failed: {}", id, command, e);
+                println_for(world, invoker, CliSeverity::Error, text);
+            }
+        }
+    }
+    for (invoker, id, command) in started {
+        let text = format!("Started job {} ({})", id, command);
+        println_for(world, invoker, CliSeverity::Info, text);
+    }
+}
+
+/// `jobs`: list the running background jobs
+fn jobs(jobs: Res<CliJobs>, mut output: CliOutput) {
+    if jobs.is_empty() {
+        output.println("No jobs running");
+        return;
+    }
+    let mut table = CliTable::new()
+        .with_column("ID", CliAlign::Right)
+        .with_column("COMMAND", CliAlign::Left)
+        .with_column("PROGRESS", CliAlign::Right)
+        .with_column("TIME", CliAlign::Right);
+    for job in jobs.iter() {
+        table.add_row([
+            job.id.to_string(),
+            job.command.to_owned(),
+            match (job.queued, job.progress) {
+                (true, _) => "queued".to_owned(),
+                (false, None) => "-".to_owned(),
+                (false, Some(p)) => format!("{:.0}%", p * 100.0),
+            },
+            format!("{:.1}s", job.started.elapsed().as_secs_f32()),
+        ]);
+    }
+    output.println(table.to_string());
+}
+
+/// `cancel <id>`: cancel a background job
+fn cancel(In(args): In<Vec<String>>, mut jobs: ResMut<CliJobs>, mut output: CliOutput) {
+    let id = match args.as_slice() {
+        [id] => id.parse::<u64>().ok(),
+        _ => None,
+    };
+    let Some(id) = id else {
+        output.fail("Usage: cancel <id>");
+        return;
+    };
+    if !jobs.cancel(id) {
+        output.fail(format!("No job with id {}!", id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{CliCommandsOutputExt, CliOutputLog};
+    use crate::CliPlugin;
+
+    /// Run a command string, returning the text of its output
+    fn run(app: &mut App, cli: &str) -> Result<Vec<String>, String> {
+        match app.world_mut().try_run_cli(cli) {
+            Ok(output) => Ok(output.into_iter().map(|line| line.text).collect()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    #[test]
+    fn concurrency() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        // Jobs that run until cancelled
+        for name in ["parallel", "serial", "exclusive"] {
+            app.register_clicommand_async(name, |_, _| future::pending());
+        }
+        app.set_clicommand_concurrency("serial", CliConcurrency::Serial)
+            .set_clicommand_concurrency("exclusive", CliConcurrency::Exclusive);
+
+        assert_eq!(run(&mut app, "parallel"), Ok(vec!["Started job 1".into()]));
+        assert_eq!(run(&mut app, "parallel"), Ok(vec!["Started job 2".into()]));
+        assert_eq!(run(&mut app, "exclusive"), Ok(vec!["Started job 3".into()]));
+        assert!(run(&mut app, "exclusive").is_err());
+        assert_eq!(run(&mut app, "serial"), Ok(vec!["Started job 4".into()]));
+        assert_eq!(
+            run(&mut app, "serial"),
+            Ok(vec!["Queued job 5, after job 4".into()])
+        );
+        assert_eq!(
+            run(&mut app, "serial"),
+            Ok(vec!["Queued job 6, after job 5".into()])
+        );
+        let queued = |app: &App| -> Vec<u64> {
+            let jobs = app.world().resource::<CliJobs>();
+            jobs.iter()
+                .filter(|job| job.queued)
+                .map(|job| job.id)
+                .collect()
+        };
+        assert_eq!(queued(&app), [5, 6]);
+        assert!(app.world_mut().resource_mut::<CliJobs>().cancel(6));
+
+        // The queued job starts once the one before it is done
+        app.world_mut().resource_mut::<CliJobs>().cancel(4);
+        app.update();
+        assert!(queued(&app).is_empty());
+        let log = app.world().resource::<CliOutputLog>();
+        assert!(log.iter().any(|line| line.text == "Started job 5 (serial)"));
+
+        app.world_mut().resource_mut::<CliJobs>().cancel(3);
+        assert_eq!(run(&mut app, "exclusive"), Ok(vec!["Started job 7".into()]));
+    }
+}
@@ -0,0 +1,112 @@
+//! Running commands given as launch options, on the command line of the
+//! process.
+//!
+//! Add [`CliLaunchArgsPlugin`] (together with [`CliPlugin`](crate::CliPlugin)),
+//! and every process argument starting with `+` begins a command string,
+//! with the arguments after it (up to the next `+`) as its args:
+//!
+//! ```text
+//! ./my_game --windowed +exec autoexec.cli +connect 1.2.3.4 +fullscreen 0
+//! ```
+//!
+//! runs `exec autoexec.cli`, then `connect 1.2.3.4`, then `fullscreen 0`.
+//! Arguments before the first `+` are left for the app itself. Args with
+//! spaces or special characters are quoted, so they reach the command as
+//! given by the shell.
+//!
+//! Optionally, the same syntax is read from an environment variable, which
+//! is run before the process arguments:
+//!
+//! ```text
+//! MY_GAME_CLI='+sv_cheats 1 +god' ./my_game
+//! ```
+//!
+//! The command strings are added to [`CliAutoexec`], so they run in
+//! [`CliStartupSet::Autoexec`](crate::startup::CliStartupSet::Autoexec),
+//! during the first update of the app: after everything registered while
+//! building the app, and after the systems in
+//! [`CliStartupSet::Register`](crate::startup::CliStartupSet::Register).
+//! Command strings already in [`CliAutoexec`] run first.
+
+use bevy::prelude::*;
+
+use crate::startup::CliAutoexec;
+use crate::{parse_cli_tokens, quote_cli_arg};
+
+/// Plugin that runs commands given as `+command args` launch options
+pub struct CliLaunchArgsPlugin {
+    /// Whether to read the arguments of the process
+    pub args: bool,
+    /// Name of an environment variable to read launch options from
+    pub env_var: Option<String>,
+}
+
+impl Default for CliLaunchArgsPlugin {
+    fn default() -> Self {
+        CliLaunchArgsPlugin {
+            args: true,
+            env_var: None,
+        }
+    }
+}
+
+impl CliLaunchArgsPlugin {
+    /// Also read launch options from the given environment variable
+    pub fn with_env_var(mut self, name: impl Into<String>) -> Self {
+        self.env_var = Some(name.into());
+        self
+    }
+}
+
+impl Plugin for CliLaunchArgsPlugin {
+    fn build(&self, app: &mut App) {
+        let mut lines = Vec::new();
+        if let Some(name) = &self.env_var {
+            if let Ok(value) = std::env::var(name) {
+                match parse_cli_tokens(&value) {
+                    Ok(words) => {
+                        lines.extend(parse_launch_args(words.into_iter().map(|w| w.text)));
+                    }
+                    Err(e) => error!("Cannot parse CLI launch options in ${}: {}", name, e),
+                }
+            }
+        }
+        if self.args {
+            lines.extend(parse_launch_args(std::env::args().skip(1)));
+        }
+        let mut autoexec = app
+            .world_mut()
+            .get_resource_or_insert_with(CliAutoexec::default);
+        for cli in lines {
+            debug!("CLI launch option: {}", cli);
+            autoexec.push(cli);
+        }
+    }
+}
+
+/// Split launch options into command strings
+///
+/// Every argument starting with `+` begins a new command string. Arguments
+/// before the first one are ignored, as is a lone `+`.
+pub fn parse_launch_args<I, S>(args: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut lines = Vec::new();
+    let mut current: Option<String> = None;
+    for arg in args {
+        let arg = arg.as_ref();
+        if let Some(name) = arg.strip_prefix('+') {
+            lines.extend(current.take());
+            if !name.is_empty() {
+                current = Some(quote_cli_arg(name).into_owned());
+            }
+        } else if let Some(cli) = &mut current {
+            cli.push(' ');
+            cli.push_str(&quote_cli_arg(arg));
+        }
+    }
+    lines.extend(current);
+    lines
+}
@@ -0,0 +1,38 @@
+//! Systems that are registered into the World on first use.
+
+use bevy::ecs::system::{BoxedSystem, RegisteredSystemError, SystemId, SystemInput};
+use bevy::prelude::*;
+
+/// A system that is registered the first time it is run
+///
+/// Used by registration helpers that are implemented on top of other commands,
+/// which only get access to the `World` when the command runs.
+pub(crate) struct LazySystem<I: SystemInput + 'static> {
+    system: Option<BoxedSystem<I, ()>>,
+    id: Option<SystemId<I, ()>>,
+}
+
+impl<I: SystemInput + 'static> LazySystem<I> {
+    pub(crate) fn new<S, Param>(system: S) -> Self
+    where
+        S: IntoSystem<I, (), Param> + 'static,
+    {
+        LazySystem {
+            system: Some(Box::new(IntoSystem::into_system(system))),
+            id: None,
+        }
+    }
+
+    pub(crate) fn run(
+        &mut self,
+        world: &mut World,
+        input: I::Inner<'_>,
+    ) -> Result<(), RegisteredSystemError<I>> {
+        let id = match (self.id, self.system.take()) {
+            (Some(id), _) => id,
+            (None, Some(system)) => *self.id.insert(world.register_boxed_system(system)),
+            (None, None) => unreachable!("LazySystem has neither a system nor an id"),
+        };
+        world.run_system_with_input(id, input)
+    }
+}
@@ -1,9 +1,510 @@
+use bevy::ecs::schedule::Condition;
 use bevy::ecs::system::SystemId;
 use bevy::prelude::*;
-use bevy::utils::HashMap;
+use bevy::utils::{HashMap, HashSet, Instant};
+use std::ops::RangeBounds;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+pub mod actions;
+mod alias;
+#[cfg(feature = "analytics")]
+pub mod analytics;
+pub mod anchor;
+pub mod args;
+pub mod arity;
+#[cfg(feature = "auto-register")]
+pub mod auto_register;
+#[cfg(feature = "bindings")]
+pub mod bindings;
+#[cfg(feature = "breakpoint")]
+pub mod breakpoint;
+mod builtins;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+#[cfg(feature = "remote")]
+pub mod client;
+pub mod command_set;
+pub mod completion;
+#[cfg(feature = "confirm")]
+pub mod confirm;
+#[cfg(feature = "console")]
+pub mod console;
+pub mod diagnostics;
+#[cfg(feature = "drop-folder")]
+pub mod drop_folder;
+pub mod dry_run;
+#[cfg(feature = "editor")]
+pub mod editor;
+#[cfg(feature = "egui")]
+pub mod egui;
+pub mod emit;
+#[cfg(feature = "env")]
+pub mod env;
+pub mod error;
+pub mod events;
+mod explain;
+pub mod fixed;
+#[cfg(feature = "reflect_functions")]
+pub mod functions;
+mod glob;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod idempotency;
+#[cfg(feature = "builtin-commands")]
+pub mod inspect;
+pub mod intercept;
+pub mod jobs;
+pub mod launch;
+mod lazy;
+pub mod local;
+#[cfg(feature = "locale")]
+pub mod locale;
+pub mod localize;
+#[cfg(feature = "log-capture")]
+pub mod log_capture;
+pub mod manifest;
+pub mod mention;
+pub mod namespace;
+pub mod output;
+#[cfg(feature = "palette")]
+pub mod palette;
+mod parse;
+pub mod permissions;
+#[cfg(feature = "parser-advanced")]
+pub mod pipe;
+#[cfg(feature = "preview")]
+pub mod preview;
+pub mod prompt;
+pub mod queue;
+#[cfg(feature = "quickbar")]
+pub mod quickbar;
+pub mod quota;
+#[cfg(feature = "reflect-commands")]
+pub mod reflected;
+#[cfg(feature = "remote")]
+pub mod remote;
+#[cfg(feature = "renderdoc")]
+pub mod renderdoc;
+#[cfg(feature = "replay")]
+pub mod replay;
+pub mod resolve;
+pub mod route;
+pub mod safemode;
+#[cfg(feature = "schedule")]
+pub mod schedule;
+#[cfg(feature = "script")]
+pub mod script;
+pub mod scrollback;
+#[cfg(any(feature = "remote", feature = "http"))]
+pub mod security;
+pub mod selector;
+#[cfg(feature = "selftest")]
+pub mod selftest;
+pub mod session;
+pub mod shell_completions;
+mod short_name;
+pub mod sleep;
+pub mod snapshot;
+pub mod source;
+pub mod startup;
+#[cfg(feature = "states")]
+pub mod state;
+pub mod status;
+#[cfg(feature = "stdin")]
+pub mod stdin;
+#[cfg(feature = "stepper")]
+pub mod stepper;
+#[cfg(feature = "dev")]
+pub mod stress;
+pub mod table;
+mod template;
+pub mod testing;
+pub mod throttle;
+pub mod toggle;
+#[cfg(feature = "cvars")]
+pub mod vars;
+#[cfg(all(feature = "wasm-console", target_arch = "wasm32"))]
+pub mod wasm_console;
+#[cfg(feature = "watch")]
+pub mod watch;
+
+use crate::anchor::{CliAnchor, CliAnchorQueue, CliRunAtCommand};
+use crate::args::{CliArgs, CliFlags, CliInvocation, CliSignature};
+use crate::completion::{CliCompleterFn, CliCompletionRequest};
+use crate::error::CliError;
+use crate::events::{CliCommandCompleted, CliCommandFailed, CliCommandStarted};
+use crate::fixed::{CliFixedQueue, CliRunAtTickCommand};
+use crate::local::CliLocals;
+use crate::namespace::CliNamespace;
+use crate::permissions::{CliPermission, CliPermissions};
+use crate::queue::{CliQueue, CliRunQueuedCommand};
+use crate::resolve::{CliCommandResolver, CliExactResolver, CliResolution};
+#[cfg(feature = "schedule")]
+use crate::schedule::{CliRunAfterCommand, CliRunAtTimeCommand, CliRunEveryCommand, CliSchedule};
+use crate::source::CliSourcePermissions;
+
+/// Declare a "console command" from a system `fn` with typed args
+///
+/// Requires the `macros` cargo feature. The leading params of the `fn` whose
+/// types are supported by [signatures](args) become the args of the command,
+/// in order. `Option<T>` params are optional args, and a `Vec<T>` param
+/// accepts any number of args. The remaining params are ordinary system params.
+/// Required args cannot follow optional ones, and a `Vec<T>` must come last.
+///
+/// This generates a `register_<fn name>` function that registers the command
+/// (using [`register_clicommand_typed`](CliCommandsRegisterExt::register_clicommand_typed)),
+/// named after the `fn` unless given a `name`. The first paragraph of the doc
+/// comment of the `fn` becomes its description.
+///
+/// With the `auto-register` cargo feature, `#[cli_command(auto)]` also submits
+/// the command for [`CliAutoRegisterPlugin`](auto_register::CliAutoRegisterPlugin),
+/// which registers all such commands, so that they do not need to be listed by hand.
+///
+/// ```rust,ignore
+/// /// Spawn some sprites at the given position
+/// #[cli_command]
+/// fn spawn(x: f32, y: f32, count: Option<u32>, mut commands: Commands) {
+///     // ...
+/// }
+///
+/// #[cli_command(name = "physics.gravity")]
+/// fn set_gravity(value: f32, mut gravity: ResMut<Gravity>) {
+///     // ...
+/// }
+///
+/// register_spawn(&mut app); // "spawn <x: f32> <y: f32> [count: u32]"
+/// register_set_gravity(&mut app);
+/// ```
+#[cfg(feature = "macros")]
+pub use iyes_cli_macros::cli_command;
+/// The former name of [`macro@cli_command`], kept as an alias
+#[cfg(feature = "macros")]
+pub use iyes_cli_macros::clicommand;
+
+pub use crate::parse::{
+    cli_caret, parse_cli, parse_cli_chain, parse_cli_tokens, quote_cli_arg, CliChain, CliChainLink,
+    CliChainOp, CliParseError, CliToken, CliTokenKind, CliWord, ParsedCli,
+};
 
 pub mod prelude {
-    pub use crate::{CliCommandsRegisterExt, CliCommandsRunExt};
+    pub use crate::actions::CliAction;
+    pub use crate::anchor::CliAnchor;
+    pub use crate::args::{CliArgs, CliFlags, CliInvocation};
+    pub use crate::command_set::{CliCommandSet, CliCommandSetExt};
+    pub use crate::dry_run::{run_cli_dry, CliPlan};
+    pub use crate::emit::CliEmit;
+    pub use crate::error::CliError;
+    pub use crate::events::{CliCommandCompleted, CliCommandFailed, CliCommandStarted};
+    pub use crate::intercept::{
+        CliHookDecision, CliInterceptRegisterExt, CliIntercepted, CliInterception,
+    };
+    pub use crate::jobs::{CliConcurrency, CliJobHandle, CliJobs};
+    pub use crate::launch::CliLaunchArgsPlugin;
+    pub use crate::local::CliLocal;
+    pub use crate::localize::CliLocalizer;
+    pub use crate::mention::{CliMentionResolver, CliMentions, CliNameMentions};
+    pub use crate::output::{
+        CliAttachment, CliCommandsOutputExt, CliLink, CliOutput, CliOutputLine, CliSeverity,
+    };
+    pub use crate::permissions::{CliPermission, CliPermissions};
+    pub use crate::prompt::CliPrompt;
+    pub use crate::queue::CliQueue;
+    pub use crate::quota::{run_cli_from, CliQuota, CliQuotas};
+    pub use crate::route::{CliRouteAppExt, CliRouteEndpoint, CliRouter};
+    pub use crate::safemode::{CliSafeMode, CliSafeModePlugin};
+    pub use crate::scrollback::CliScrollback;
+    pub use crate::selector::{parse_cli_entity, CliSelectors};
+    pub use crate::session::CliSession;
+    pub use crate::snapshot::CliCommandsSnapshot;
+    pub use crate::source::{CliSource, CliSourcePermissions};
+    pub use crate::startup::{CliReady, CliStartupSet};
+    pub use crate::toggle::CliToggleRegisterExt;
+    pub use crate::{failln, outln};
+    pub use crate::{CliCommandsRegisterExt, CliCommandsRunExt, CliConfig, CliPlugin};
+
+    #[cfg(feature = "auto-register")]
+    pub use crate::auto_register::CliAutoRegisterPlugin;
+    #[cfg(feature = "bindings")]
+    pub use crate::bindings::{CliAxisBinding, CliBindings, CliBindingsPlugin};
+    #[cfg(feature = "confirm")]
+    pub use crate::confirm::CliConfirm;
+    #[cfg(feature = "console")]
+    pub use crate::console::CliConsolePlugin;
+    #[cfg(feature = "drop-folder")]
+    pub use crate::drop_folder::CliDropFolderPlugin;
+    #[cfg(feature = "editor")]
+    pub use crate::editor::{CliEditor, CliEditorRequest};
+    #[cfg(feature = "egui")]
+    pub use crate::egui::CliEguiConsolePlugin;
+    #[cfg(feature = "env")]
+    pub use crate::env::CliEnv;
+    #[cfg(feature = "reflect_functions")]
+    pub use crate::functions::CliFunctionsRegisterExt;
+    #[cfg(feature = "http")]
+    pub use crate::http::CliHttpPlugin;
+    #[cfg(feature = "builtin-commands")]
+    pub use crate::inspect::CliInspectPlugin;
+    #[cfg(feature = "locale")]
+    pub use crate::locale::CliLocale;
+    #[cfg(feature = "log-capture")]
+    pub use crate::log_capture::{cli_log_layer, CliLogBuffer};
+    #[cfg(feature = "palette")]
+    pub use crate::palette::CliPalettePlugin;
+    #[cfg(feature = "preview")]
+    pub use crate::preview::{CliPreviewPlugin, CliPreviewRegisterExt};
+    #[cfg(feature = "quickbar")]
+    pub use crate::quickbar::{CliQuickbar, CliQuickbarPlugin};
+    #[cfg(feature = "reflect-commands")]
+    pub use crate::reflected::CliReflectRegisterExt;
+    #[cfg(feature = "remote")]
+    pub use crate::remote::CliRemotePlugin;
+    #[cfg(feature = "schedule")]
+    pub use crate::schedule::CliSchedule;
+    #[cfg(feature = "script")]
+    pub use crate::script::{CliScript, CliScriptPlugin, CliScriptRunExt};
+    #[cfg(any(feature = "remote", feature = "http"))]
+    pub use crate::security::{CliRemoteSecurityConfig, CliTlsConfig};
+    #[cfg(feature = "selftest")]
+    pub use crate::selftest::{CliSelfTestExpect, CliSelfTestRegisterExt};
+    #[cfg(feature = "states")]
+    pub use crate::state::CliStateRegisterExt;
+    #[cfg(feature = "stdin")]
+    pub use crate::stdin::CliStdinPlugin;
+    #[cfg(feature = "stepper")]
+    pub use crate::stepper::CliDebugStepper;
+    #[cfg(feature = "cvars")]
+    pub use crate::vars::{CliVars, CliVarsRegisterExt};
+    #[cfg(all(feature = "wasm-console", target_arch = "wasm32"))]
+    pub use crate::wasm_console::CliWasmConsolePlugin;
+    #[cfg(feature = "watch")]
+    pub use crate::watch::{CliWatchPlugin, CliWatches};
+    #[cfg(feature = "macros")]
+    pub use crate::{cli_command, clicommand};
+}
+
+/// Plugin providing the systems needed for deferred "console commands"
+///
+/// Registering and immediately running commands works without this plugin.
+/// It is only needed for features that run commands later, such as
+/// [`CliCommandsRunExt::run_cli_at`].
+///
+/// It sets up the order of initialization at startup, and sends
+/// [`CliReady`](startup::CliReady) when it is done (see [`startup`]).
+///
+/// It also registers the crate's built-in commands. Those after `wait` in
+/// this list require cargo features, which are all enabled by default (the
+/// generic commands, from `bindcmd` to `unregister`, require
+/// `builtin-commands`). With `default-features = false` (the minimal
+/// profile), only the ones up to `wait` are registered:
+///  - `help [command|category|--by-owner]`: list commands by category (see
+///    [`CliCommands::command_category`]) or owner, or show help for one
+///  - `queue [list|clear|pause|resume]`: manage commands waiting to be run
+///  - `return [value...]`: end the current script or procedure, with a value
+///    (see [`status`])
+///  - `jobs`, `cancel <id>`: list and cancel background jobs (see [`jobs`])
+///  - `search <pattern>` (or `/pattern`), `page [next|prev|quit]`: search
+///    the output, and page through long output (see [`scrollback`])
+///  - `output [n|list]`: print the output of a previous command string again
+///    (see [`scrollback`])
+///  - `sleep <duration> [--real]`: delay the rest of a script or chain
+///    (see [`sleep`](crate::sleep))
+///  - `wait <frames|duration>`: delay the rest of the command queue
+///    (see [`queue`])
+///  - `bindcmd <name> <template...>`: create a command from a template
+///    (see [`CliCommandsRegisterExt::register_clicommand_template`])
+///  - `alias [name [expansion...]]`, `unalias <name>`: manage aliases
+///    (see [`CliCommands::add_alias`])
+///  - `list <commands|aliases|cvars> [pattern]`: list names
+///  - `commands [--json]`: describe the registered commands, for tools (see
+///    [`manifest`])
+///  - `retry`: run the last failed command again
+///  - `fix <command...>`: run a corrected version of the last failed command
+///  - `profile <seconds>`: measure frame times for a while, then print a summary
+///  - `assert <command...>`: fail if a command fails or outputs a false value
+///  - `fail <message...>`: print a message and fail
+///  - `echo [text...]`: print text
+///  - `namespaces`: list namespaces, with the owners of their commands and
+///    their reservations (see
+///    [`reserve_cli_namespace`](CliCommandsRegisterExt::reserve_cli_namespace))
+///  - `unregister <command...|--owner <owner>|--group <group>>`: remove
+///    commands, or all the commands registered by an owner (see
+///    [`with_clicommand_owner`](CliCommandsRegisterExt::with_clicommand_owner))
+///    or in a group (see
+///    [`with_clicommand_group`](CliCommandsRegisterExt::with_clicommand_group))
+///  - `get <pattern>`, `reset <pattern>`: read or reset console variables
+///    matching a wildcard pattern (with the `cvars` feature, see `vars`)
+///  - `cvar diff`, `cvar reset <name|--all>`: show or reset console variables
+///    changed from their defaults (with the `cvars` feature)
+///  - `preset <list|save|apply> [name]`: save and apply snapshots of console
+///    variables (with the `cvars` feature)
+///  - `grep [-i] [-v] <pattern> [lines...]`, `head [-n <count>] [lines...]`,
+///    `count [lines...]`: filter the output of another command (with the
+///    `parser-advanced` feature, see `pipe`)
+///  - `set [name [value...]]`, `unset <name>`: manage variables that are
+///    expanded in command strings, and `export <name>`: make a variable of
+///    the current session global (with the `env` feature, see `env`)
+///  - `after <duration> <command...>`, `every <duration> <command...>`,
+///    `at <time> <command...>`, `schedule [list | cancel <id|all>]`: run
+///    commands later, repeatedly or at a time,
+///    and manage them (with the `schedule` feature, see `schedule`)
+///  - `stats [command|reset]`, `time <command...>`: show how often commands
+///    ran and how long they took (with the `analytics` feature, see `analytics`)
+///  - `confirm`: run the last dangerous command (with the `confirm` feature,
+///    see `confirm`)
+///  - `edit <command...>`: run a command with multi-line text as its last arg
+///    (with the `editor` feature, see `editor`)
+///  - `step <frames> [command...]`: advance the game by some frames (with
+///    the `stepper` feature, see `stepper`)
+///  - `break [list | when <expression> <op> <value> [--console] | remove <id|all> | continue]`:
+///    pause the game when a condition becomes true (with the `breakpoint`
+///    feature, see `breakpoint`)
+///  - `selftest [pattern]`: run the self-tests of commands (with the
+///    `selftest` feature, see `selftest`)
+///  - `record <start|stop> [file]`, `replay <file|stop> [--frames]`: record
+///    the commands that are run, and play them back (with the `replay`
+///    feature, see `replay`)
+///  - `chaos <seconds|stop> [per_second] [pattern]`: run random commands with
+///    random args, to find crashes (with the `chaos` feature, see `chaos`)
+///  - `stress cli <n> [pattern]`: run many random command strings at once
+///    (with the `dev` feature, see `stress`)
+///
+/// It registers the events sent when commands run (see [`events`]).
+///
+/// Any command can be given the `--explain` flag, to print the resources and
+/// components that it added or modified.
+///
+/// Command strings starting with `@<tag>` are sent to other `World`s (see
+/// [`route`]). Args like `@name(Player)` select entities (see [`selector`]),
+/// and args like `@Bob` mention players (see [`mention`]).
+pub struct CliPlugin;
+
+impl Plugin for CliPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CliCommands>();
+        app.init_resource::<CliConfig>();
+        app.init_resource::<local::CliCurrentCommand>();
+        app.init_resource::<CliLocals>();
+        app.init_resource::<output::CliOutputLog>();
+        app.init_resource::<CliPermissions>();
+        app.init_resource::<selector::CliSelectors>();
+        app.init_resource::<mention::CliMentions>();
+        app.init_resource::<localize::CliLocalizer>();
+        app.with_clicommand_owner("iyes_cli", |app| {
+            app.add_plugins((
+                (
+                    actions::plugin,
+                    anchor::plugin,
+                    events::plugin,
+                    fixed::plugin,
+                    jobs::plugin,
+                ),
+                (
+                    prompt::plugin,
+                    queue::plugin,
+                    quota::plugin,
+                    route::plugin,
+                    scrollback::plugin,
+                    sleep::plugin,
+                    startup::plugin,
+                    source::plugin,
+                ),
+                builtins::plugin,
+            ));
+            #[cfg(feature = "analytics")]
+            app.add_plugins(analytics::plugin);
+            #[cfg(feature = "breakpoint")]
+            app.add_plugins(breakpoint::plugin);
+            #[cfg(feature = "chaos")]
+            app.add_plugins(chaos::plugin);
+            #[cfg(feature = "confirm")]
+            app.add_plugins(confirm::plugin);
+            #[cfg(feature = "editor")]
+            app.add_plugins(editor::plugin);
+            #[cfg(feature = "env")]
+            app.add_plugins(env::plugin);
+            #[cfg(feature = "replay")]
+            app.add_plugins(replay::plugin);
+            #[cfg(feature = "schedule")]
+            app.add_plugins(schedule::plugin);
+            #[cfg(feature = "selftest")]
+            app.add_plugins(selftest::plugin);
+            #[cfg(feature = "stepper")]
+            app.add_plugins(stepper::plugin);
+            #[cfg(feature = "cvars")]
+            app.add_plugins(vars::plugin);
+            #[cfg(feature = "parser-advanced")]
+            app.add_plugins(pipe::plugin);
+            #[cfg(feature = "locale")]
+            app.add_plugins(locale::plugin);
+            #[cfg(feature = "dev")]
+            app.add_plugins(stress::plugin);
+        });
+        // Remember what is built in, for safe mode
+        let core = app
+            .world()
+            .resource::<CliCommands>()
+            .iter_names()
+            .map(|name| name.to_owned())
+            .collect();
+        app.insert_resource(safemode::CliCoreCommands(core));
+        app.add_systems(Last, cleanup_removed_commands);
+    }
+}
+
+/// Settings for how command strings are interpreted
+#[derive(Resource, Debug, Clone, Default)]
+pub struct CliConfig {
+    /// Allow running commands by typing a unique prefix of their name
+    ///
+    /// For example, `desp` would run `despawn`. If the prefix is ambiguous,
+    /// an error listing the candidates is reported. This is applied when the
+    /// configured [resolver](CliCommands::set_resolver) does not find anything.
+    ///
+    /// Disabled by default, as some consider it dangerous.
+    pub allow_abbreviations: bool,
+    /// Allow running commands by typing their name with different case
+    ///
+    /// For example, `Spawn` would run `spawn`. Also applies to
+    /// abbreviations, if they are allowed. If several commands differ only
+    /// in case, an error listing them is reported. An exact match always
+    /// takes priority. See [`resolve`].
+    ///
+    /// Disabled by default.
+    pub ignore_case: bool,
+    /// The characters command names may contain, if restricted
+    ///
+    /// Registering a command with other characters fails with an error.
+    /// Whitespace and control characters are never allowed. See [`resolve`].
+    ///
+    /// Unrestricted by default.
+    pub name_chars: Option<fn(char) -> bool>,
+    /// Stop running a chain of commands at the first one that fails, even
+    /// if the next one follows a `;` (see [`parse_cli_chain`])
+    ///
+    /// Disabled by default, so `cmd1; cmd2` runs `cmd2` even if `cmd1` fails.
+    pub stop_on_error: bool,
+    /// Report statistics about the commands that were run when the app exits
+    /// (see [`analytics`])
+    ///
+    /// Disabled by default. Requires the `analytics` feature.
+    #[cfg(feature = "analytics")]
+    pub usage_summary: Option<analytics::CliUsageSummary>,
+    /// The most lines of output a command may print, if limited
+    ///
+    /// The lines after that are dropped, and replaced with a line telling
+    /// how many there were, like `... 4321 more lines, use --page`. Running
+    /// the command again with a `--page` flag pages through all of its
+    /// output instead (see [`scrollback`]). Output piped to another command
+    /// is not limited.
+    ///
+    /// Unlimited by default.
+    pub max_output_lines: Option<usize>,
+    /// The most bytes of output a command may print, if limited,
+    /// like [`max_output_lines`](Self::max_output_lines)
+    ///
+    /// Unlimited by default.
+    pub max_output_bytes: Option<usize>,
 }
 
 /// Stores all the known/available commands that can be called.
@@ -15,11 +516,147 @@ pub mod prelude {
 #[derive(Resource, Default)]
 pub struct CliCommands {
     commands: HashMap<String, CliCommandSystems>,
+    fallback: Option<SystemId<In<String>, ()>>,
+    resolver: Option<Box<dyn CliCommandResolver>>,
+    aliases: HashMap<String, String>,
+    alias_stack: Vec<String>,
+    /// The owner of commands registered now (see `with_clicommand_owner`)
+    owner_scope: Option<String>,
+    /// The groups of commands registered now (see `with_clicommand_group`)
+    group_scope: Vec<String>,
+    /// Reserved namespaces, and the owner allowed to register commands in them
+    reserved: HashMap<String, Option<String>>,
+    /// The systems of the snapshots that may still exist (see `snapshot`)
+    snapshots: Mutex<Vec<Weak<HashSet<Entity>>>>,
+    /// Systems no longer used by a command, to be removed from the `World`
+    /// once no snapshot uses them either
+    unused_systems: HashSet<Entity>,
+    /// Names of removed commands, whose state is to be cleared
+    removed_names: Vec<String>,
 }
 
+#[derive(Clone)]
 struct CliCommandSystems {
     noargs: Option<SystemId<(), ()>>,
     args: Option<SystemId<In<Vec<String>>, ()>>,
+    conditions: Vec<SystemId<(), bool>>,
+    requirements: Vec<CliRequirement>,
+    namespace: Option<String>,
+    description: Option<String>,
+    usage: Option<String>,
+    permission: CliPermission,
+    completer: Option<Arc<CliCompleterFn>>,
+    run_count: u64,
+    last_run: Option<Instant>,
+    enabled: bool,
+    /// Send the events declared with `add_clicommand_emits`, or discard them
+    emitters: Vec<fn(&mut World, bool)>,
+    /// Must be confirmed before running (see [`confirm`])
+    dangerous: bool,
+    /// The plugin or crate that registered the command
+    owner: Option<String>,
+    /// Groups for enabling, disabling or removing commands together
+    groups: Vec<String>,
+    /// Cooldown and rate limit (see [`throttle`])
+    throttle: throttle::CliThrottle,
+    /// The args the command accepts (see [`arity`])
+    spec: arity::CliArgSpec,
+    /// Print how long the command took, every time it runs (see [`analytics`])
+    timed: bool,
+    /// Page long output (see [`scrollback`])
+    paged: bool,
+    /// Whether its jobs may overlap (see [`jobs`])
+    concurrency: jobs::CliConcurrency,
+}
+
+impl CliCommandSystems {
+    /// A command without systems yet, with the default settings
+    fn new(owner: Option<String>, groups: Vec<String>) -> Self {
+        CliCommandSystems {
+            noargs: None,
+            args: None,
+            conditions: vec![],
+            requirements: vec![],
+            namespace: None,
+            description: None,
+            usage: None,
+            permission: CliPermission::Normal,
+            completer: None,
+            run_count: 0,
+            last_run: None,
+            enabled: true,
+            emitters: vec![],
+            dangerous: false,
+            owner,
+            groups,
+            throttle: default(),
+            spec: default(),
+            timed: false,
+            paged: false,
+            concurrency: default(),
+        }
+    }
+    /// The entities of the command's systems, including its run conditions
+    fn system_entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        let noargs = self.noargs.map(|id| id.entity());
+        let args = self.args.map(|id| id.entity());
+        let conditions = self.conditions.iter().map(|id| id.entity());
+        noargs.into_iter().chain(args).chain(conditions)
+    }
+    fn add_groups(&mut self, groups: impl IntoIterator<Item = String>) {
+        for group in groups {
+            if !self.groups.contains(&group) {
+                self.groups.push(group);
+            }
+        }
+    }
+}
+
+/// A resource that must exist for a command to run
+#[derive(Clone)]
+struct CliRequirement {
+    exists: fn(&World) -> bool,
+    plugin: String,
+}
+
+/// Information about a registered "console command"
+///
+/// Obtained from [`CliCommands::get_info`].
+#[derive(Debug, Clone)]
+pub struct CliCommandInfo<'a> {
+    pub name: &'a str,
+    /// The namespace the command was registered in, if any
+    pub namespace: Option<&'a str>,
+    /// Short description of what the command does, if set
+    pub description: Option<&'a str>,
+    /// Usage string showing the accepted args, if set
+    pub usage: Option<&'a str>,
+    /// Who may run the command
+    pub permission: CliPermission,
+    /// Whether the command has a variant that accepts args
+    pub accepts_args: bool,
+    /// Whether the command has a variant that runs without args
+    pub accepts_noargs: bool,
+    /// How many times the command has been run
+    pub run_count: u64,
+    /// When the command was last run, if ever
+    pub last_run: Option<Instant>,
+    /// Whether the command is enabled (see [`CliCommands::set_enabled`])
+    pub enabled: bool,
+    /// Whether the command must be confirmed before running (see [`confirm`])
+    pub dangerous: bool,
+    /// The plugin or crate that registered the command, if known
+    pub owner: Option<&'a str>,
+    /// The groups the command is in (see [`CliCommands::disable_group`])
+    pub groups: &'a [String],
+    /// How long the command cannot run after running, if set
+    pub cooldown: Option<Duration>,
+    /// How many times per second the command may run, if limited
+    pub rate_limit: Option<u32>,
+    /// The least number of args the command accepts (see [`arity`])
+    pub min_args: usize,
+    /// The most args the command accepts, if limited (see [`arity`])
+    pub max_args: Option<usize>,
 }
 
 /// Provides methods for managing the available "console commands"
@@ -51,13 +688,520 @@ pub trait CliCommandsRegisterExt {
 
     /// Remove a "console command", if it exists
     fn unregister_clicommand(&mut self, name: &str) -> &mut Self;
+
+    /// Register a group of "console commands" under a common namespace
+    ///
+    /// The closure receives a [`CliNamespace`], which can be used to register
+    /// commands just like here. Their names will be prefixed with the namespace.
+    ///
+    /// ```rust,ignore
+    /// app.register_cli_namespace("physics", |ns| {
+    ///     ns.register_clicommand_noargs("pause", pause_physics); // "physics.pause"
+    ///     ns.register_clicommand_noargs("resume", resume_physics); // "physics.resume"
+    /// });
+    /// ```
+    fn register_cli_namespace<F>(&mut self, namespace: &str, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut CliNamespace);
+
+    /// Register "console commands" on behalf of an owner, such as a plugin or crate
+    ///
+    /// The commands registered by the closure record the owner, which is shown
+    /// by `help --by-owner`. All the commands of an owner can be removed with
+    /// `unregister --owner <owner>`.
+    ///
+    /// ```rust,ignore
+    /// app.with_clicommand_owner("my_physics", |app| {
+    ///     app.register_clicommand_noargs("physics.pause", pause_physics);
+    /// });
+    /// ```
+    fn with_clicommand_owner<F>(&mut self, owner: &str, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut Self);
+
+    /// Reserve a namespace, so that only its owner can register commands in it
+    ///
+    /// The owner is the one set with
+    /// [`with_clicommand_owner`](Self::with_clicommand_owner) when reserving
+    /// (or none, if called outside of it). Commands in the namespace (or in
+    /// nested namespaces) registered with a different owner are rejected
+    /// with an error. Useful for plugins that must not clash with each other.
+    ///
+    /// ```rust,ignore
+    /// app.reserve_cli_namespace("mygame");
+    /// app.with_clicommand_owner("some_plugin", |app| {
+    ///     app.register_clicommand_noargs("mygame.cheat", cheat); // error
+    /// });
+    /// ```
+    fn reserve_cli_namespace(&mut self, namespace: &str) -> &mut Self;
+
+    /// Register "console commands" in a group
+    ///
+    /// The commands registered by the closure are added to the group, so they
+    /// can be enabled, disabled or removed together (see
+    /// [`CliCommands::disable_group`]). Groups can be nested, adding the
+    /// commands to all of them.
+    ///
+    /// ```rust,ignore
+    /// app.with_clicommand_group("debug", |app| {
+    ///     app.register_clicommand_noargs("show_colliders", show_colliders);
+    /// });
+    /// #[cfg(not(debug_assertions))]
+    /// app.world_mut().resource_mut::<CliCommands>().remove_group("debug");
+    /// ```
+    fn with_clicommand_group<F>(&mut self, group: &str, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut Self);
+
+    /// Register "console commands" on behalf of the type `T`, such as a plugin
+    ///
+    /// Like [`with_clicommand_owner`](Self::with_clicommand_owner), using the
+    /// type name of `T` as the owner.
+    fn with_clicommand_owner_of<T: ?Sized, F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut Self),
+    {
+        self.with_clicommand_owner(std::any::type_name::<T>(), f)
+    }
+
+    /// Only allow a "console command" to run if the given condition is true
+    ///
+    /// Any Bevy run condition can be used, such as `in_state(...)` (which also
+    /// works with sub-states and computed states), or your own systems/closures
+    /// returning `bool`. If multiple conditions are added, all of them must be
+    /// true. They are evaluated every time the command is about to run.
+    ///
+    /// The command must already be registered.
+    fn add_clicommand_run_condition<C, M>(&mut self, name: &str, condition: C) -> &mut Self
+    where
+        C: Condition<M> + 'static;
+
+    /// Only allow a "console command" to run if a resource exists
+    ///
+    /// Useful for commands that depend on other plugins. If the resource does
+    /// not exist, running the command fails with an error saying that `plugin`
+    /// is required, instead of the command panicking when it accesses the
+    /// resource. The command must already be registered.
+    ///
+    /// ```rust,ignore
+    /// app.register_clicommand_args("gravity", set_gravity)
+    ///     .require_clicommand_resource::<Gravity>("gravity", "PhysicsPlugins");
+    /// ```
+    fn require_clicommand_resource<R: Resource>(&mut self, name: &str, plugin: &str) -> &mut Self;
+
+    /// Set a short description of what a "console command" does
+    ///
+    /// Shown by the built-in `help` command. The command must already be registered.
+    ///
+    /// ```rust,ignore
+    /// app.register_clicommand_args("spawn", spawn)
+    ///     .set_clicommand_description("spawn", "Spawn a sprite at the given position")
+    ///     .set_clicommand_usage("spawn", "spawn <x> <y>");
+    /// ```
+    fn set_clicommand_description(&mut self, name: &str, description: &str) -> &mut Self;
+
+    /// Set a usage string showing the args a "console command" accepts
+    ///
+    /// Shown by the built-in `help` command. The command must already be registered.
+    /// Commands registered with a signature have it set automatically.
+    fn set_clicommand_usage(&mut self, name: &str, usage: &str) -> &mut Self;
+
+    /// Set who may run a "console command"
+    ///
+    /// Commands are [`Normal`](CliPermission::Normal) unless set otherwise.
+    /// See [`permissions`](crate::permissions). The command must already be registered.
+    fn set_clicommand_permission(&mut self, name: &str, permission: CliPermission) -> &mut Self;
+
+    /// Require a "console command" to be confirmed before it runs
+    ///
+    /// The command only runs if it is followed by the built-in `confirm`
+    /// command, or given the `--force` flag. See [`confirm`](crate::confirm).
+    /// The command must already be registered.
+    fn set_clicommand_dangerous(&mut self, name: &str) -> &mut Self;
+
+    /// Prevent a "console command" from running again until some time has passed
+    ///
+    /// Running it earlier fails with [`CliError::OnCooldown`]. See [`throttle`].
+    /// The command must already be registered.
+    fn set_clicommand_cooldown(&mut self, name: &str, cooldown: Duration) -> &mut Self;
+
+    /// Limit how many times per second a "console command" may run
+    ///
+    /// Running it more often fails with [`CliError::OnCooldown`]. See [`throttle`].
+    /// The command must already be registered.
+    fn set_clicommand_rate_limit(&mut self, name: &str, max_per_second: u32) -> &mut Self;
+
+    /// Print how long a "console command" took, every time it runs
+    ///
+    /// Like running it with the built-in `time` command. See [`analytics`].
+    /// The command must already be registered.
+    fn set_clicommand_timing(&mut self, name: &str) -> &mut Self;
+
+    /// Page the output of a "console command", if it is long
+    ///
+    /// Only the first page is printed, and the built-in `page` command
+    /// prints the others. See [`scrollback`]. The command must already be
+    /// registered.
+    fn set_clicommand_paged(&mut self, name: &str) -> &mut Self;
+
+    /// Set whether a "console command" may start a job while another job it
+    /// started is running
+    ///
+    /// See [`CliConcurrency`](jobs::CliConcurrency). Commands registered with
+    /// [`register_clicommand_async`](Self::register_clicommand_async) follow
+    /// it, as do jobs started with [`CliJobs::spawn_with`](jobs::CliJobs::spawn_with).
+    /// The command must already be registered.
+    fn set_clicommand_concurrency(
+        &mut self,
+        name: &str,
+        concurrency: jobs::CliConcurrency,
+    ) -> &mut Self;
+
+    /// Set how many args a "console command" accepts, like `2..=2` or `1..`
+    ///
+    /// Running it with a different number of args fails with
+    /// [`CliError::InvalidArgs`], without running the command. See [`arity`].
+    /// The command must already be registered.
+    fn set_clicommand_arity(&mut self, name: &str, range: impl RangeBounds<usize>) -> &mut Self;
+
+    /// Set a function to check an arg of a "console command", by its index
+    ///
+    /// The function returns why the arg is invalid, if it is. Running the
+    /// command with an invalid arg fails with [`CliError::InvalidArgs`],
+    /// without running the command. See [`arity`]. The command must already
+    /// be registered.
+    fn set_clicommand_arg_check<F>(&mut self, name: &str, index: usize, check: F) -> &mut Self
+    where
+        F: Fn(&str) -> Result<(), String> + Send + Sync + 'static;
+
+    /// Add a "console command" to a group
+    ///
+    /// Like registering it inside [`with_clicommand_group`](Self::with_clicommand_group).
+    /// The command must already be registered.
+    fn add_clicommand_group(&mut self, name: &str, group: &str) -> &mut Self;
+
+    /// Declare that a "console command" emits an event of type `E` when it succeeds
+    ///
+    /// The command provides the event using [`CliEmit`](crate::emit::CliEmit).
+    /// See [`emit`](crate::emit). The event must be registered with
+    /// `app.add_event::<E>()`. The command must already be registered.
+    fn add_clicommand_emits<E: Event>(&mut self, name: &str) -> &mut Self;
+
+    /// Set a function to complete the args of a "console command"
+    ///
+    /// Used by [`CliCommands::complete`]. The function receives what is being
+    /// completed, and read-only access to the `World`, and returns candidate
+    /// values. The command must already be registered.
+    ///
+    /// ```rust,ignore
+    /// app.register_clicommand_args("weather", set_weather)
+    ///     .set_clicommand_completer("weather", |_, _| {
+    ///         vec!["clear".into(), "rain".into(), "storm".into()]
+    ///     });
+    /// ```
+    fn set_clicommand_completer<F>(&mut self, name: &str, completer: F) -> &mut Self
+    where
+        F: Fn(&CliCompletionRequest, &World) -> Vec<String> + Send + Sync + 'static;
+
+    /// Set a system to handle command strings that don't match any command
+    ///
+    /// The system receives the whole command string. You can use this to
+    /// route unmatched input somewhere else, like to a chat or an NPC
+    /// dialogue parser, instead of reporting an error.
+    ///
+    /// If a fallback was already set, it is replaced.
+    fn set_clicommand_fallback<S, Param>(&mut self, system: S) -> &mut Self
+    where
+        S: IntoSystem<In<String>, (), Param> + 'static;
+
+    /// Create a new "console command" that runs another command string
+    ///
+    /// The template may contain positional placeholders (`$1`, `$2`, ...),
+    /// which are replaced with the args the new command was called with.
+    /// `$*` is replaced with all the args. If the template contains no
+    /// placeholders, any args are appended to it, like a simple alias.
+    /// `$?` is replaced with the exit code of the previous command string
+    /// (see [`CliStatus`](crate::status::CliStatus)).
+    ///
+    /// The new command fails if the command string it runs fails.
+    ///
+    /// ```rust,ignore
+    /// app.register_clicommand_template("spawn_center", "spawn 960 540");
+    /// app.register_clicommand_template("spawn_row", "spawn $1 100");
+    /// ```
+    ///
+    /// If a command with the same name already exists, it is replaced.
+    fn register_clicommand_template(&mut self, name: &str, template: &str) -> &mut Self {
+        let name_owned = name.to_owned();
+        let template = template.to_owned();
+        self.register_clicommand_args(name, move |In(args): In<Vec<String>>, world: &mut World| {
+            if !crate::template::run_template(world, &name_owned, &template, &args) {
+                status::cli_fail(world);
+            }
+        })
+    }
+
+    /// Create a new "console command" with a declared signature
+    ///
+    /// The args are validated against the signature (see the [`args`] module
+    /// for the syntax) before the system runs. The system receives them as
+    /// [`CliArgs`](crate::args::CliArgs), with typed accessors. If the args
+    /// are invalid, an error with usage info is reported instead.
+    ///
+    /// ```rust,ignore
+    /// app.register_clicommand_typed("spawn", "<x: f32> <y: f32> [count: u32]", spawn);
+    ///
+    /// fn spawn(In(args): In<CliArgs>, mut commands: Commands) {
+    ///     let x: f32 = args.get("x").unwrap();
+    ///     let y: f32 = args.get("y").unwrap();
+    ///     let count: u32 = args.get_or("count", 1);
+    ///     // ...
+    /// }
+    /// ```
+    ///
+    /// Panics if the signature is malformed.
+    ///
+    /// If a command with the same name already exists, it is replaced.
+    fn register_clicommand_typed<S, Param>(
+        &mut self,
+        name: &str,
+        signature: &str,
+        system: S,
+    ) -> &mut Self
+    where
+        S: IntoSystem<In<CliArgs>, (), Param> + 'static,
+    {
+        let signature: CliSignature = match signature.parse() {
+            Ok(signature) => signature,
+            Err(e) => panic!("Invalid signature for CliCommand {:?}: {}", name, e),
+        };
+        let usage = signature.usage(name);
+        let usage_owned = usage.clone();
+        let mut system = lazy::LazySystem::new(system);
+        let name_owned = name.to_owned();
+        self.register_clicommand_args(name, move |In(args): In<Vec<String>>, world: &mut World| {
+            let args = match signature.parse_args(args.clone()) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    let caret = args_caret(&name_owned, &args, e.arg_index());
+                    output::cli_error(world, format!("{}!\n{}\nUsage: {}", e, caret, usage));
+                    status::cli_fail(world);
+                    return;
+                }
+            };
+            if let Err(e) = system.run(world, args) {
+                let message = format!("CliCommand {:?} failed to run: {}", name_owned, e);
+                output::cli_error(world, message);
+                status::cli_fail(world);
+            }
+        })
+        .set_clicommand_usage(name, &usage_owned)
+    }
+
+    /// Create a new "console command" that accepts flags and options
+    ///
+    /// The args are separated into positional args, flags and options (see
+    /// the [`args`] module for the syntax) before the system runs. The system
+    /// receives them as a [`CliInvocation`](crate::args::CliInvocation). If
+    /// an unknown flag is given, an error with usage info is reported instead.
+    ///
+    /// ```rust,ignore
+    /// let flags = CliFlags::new().option("count", Some('c')).flag("random", Some('r'));
+    /// app.register_clicommand_flags("spawn", flags, spawn);
+    ///
+    /// // spawn --count 5 -r 10 20
+    /// fn spawn(In(inv): In<CliInvocation>, mut commands: Commands) {
+    ///     let count: u32 = inv.option_or("count", 1);
+    ///     let random = inv.has_flag("random");
+    ///     // ...
+    /// }
+    /// ```
+    ///
+    /// If a command with the same name already exists, it is replaced.
+    fn register_clicommand_flags<S, Param>(
+        &mut self,
+        name: &str,
+        flags: CliFlags,
+        system: S,
+    ) -> &mut Self
+    where
+        S: IntoSystem<In<CliInvocation>, (), Param> + 'static,
+    {
+        let usage = flags.usage(name);
+        let usage_owned = usage.clone();
+        let mut system = lazy::LazySystem::new(system);
+        let name_owned = name.to_owned();
+        self.register_clicommand_args(name, move |In(args): In<Vec<String>>, world: &mut World| {
+            let mut invocation = match flags.parse_args(args) {
+                Ok(invocation) => invocation,
+                Err(e) => {
+                    output::cli_error(world, format!("{}! Usage: {}", e, usage));
+                    status::cli_fail(world);
+                    return;
+                }
+            };
+            invocation.source = world
+                .get_resource::<status::CliStatus>()
+                .and_then(|status| status.source().cloned());
+            if let Err(e) = system.run(world, invocation) {
+                let message = format!("CliCommand {:?} failed to run: {}", name_owned, e);
+                output::cli_error(world, message);
+                status::cli_fail(world);
+            }
+        })
+        .set_clicommand_usage(name, &usage_owned)
+    }
+
+    /// Create a new "console command" that runs in the background
+    ///
+    /// Every time the command runs, the function is called with the args and
+    /// a [`CliJobHandle`](crate::jobs::CliJobHandle), and the future it
+    /// returns is run as a job on the `AsyncComputeTaskPool`, over as many
+    /// frames as it needs. See [`jobs`].
+    ///
+    /// If a command with the same name already exists, it is replaced.
+    fn register_clicommand_async<F, Fut>(&mut self, name: &str, f: F) -> &mut Self
+    where
+        F: Fn(Vec<String>, jobs::CliJobHandle) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = jobs::CliJobResult> + Send + 'static,
+    {
+        jobs::register_async(self, name, f);
+        self
+    }
+
+    /// Create a pair of "console commands" that begin and end something
+    ///
+    /// This creates `<name> start` and `<name> stop`, running the given systems.
+    /// Pairing is enforced: `stop` is an error if not started, and `start` is
+    /// an error if already started. Both systems run as the same command, so
+    /// they can share scratch state using [`CliLocal`](crate::local::CliLocal).
+    ///
+    /// ```rust,ignore
+    /// app.register_clicommand_pair("profile", start_profiling, stop_profiling);
+    ///
+    /// fn start_profiling(mut started: CliLocal<Option<Instant>>) {
+    ///     *started.get_mut() = Some(Instant::now());
+    /// }
+    /// fn stop_profiling(mut started: CliLocal<Option<Instant>>, mut output: CliOutput) {
+    ///     if let Some(t) = started.take().flatten() {
+    ///         output.println(format!("Profiled for {:?}", t.elapsed()));
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// If a command with the same name already exists, it is replaced.
+    fn register_clicommand_pair<S1, Param1, S2, Param2>(
+        &mut self,
+        name: &str,
+        start: S1,
+        stop: S2,
+    ) -> &mut Self
+    where
+        S1: IntoSystem<(), (), Param1> + 'static,
+        S2: IntoSystem<(), (), Param2> + 'static,
+    {
+        let name_owned = name.to_owned();
+        let mut start = lazy::LazySystem::new(start);
+        let mut stop = lazy::LazySystem::new(stop);
+        let mut active = false;
+        self.register_clicommand_args(name, move |In(args): In<Vec<String>>, world: &mut World| {
+            let result = match args.as_slice() {
+                [op] if op == "start" => {
+                    if active {
+                        output::cli_error(world, format!("{} is already started!", name_owned));
+                        status::cli_fail(world);
+                        return;
+                    }
+                    active = true;
+                    start.run(world, ())
+                }
+                [op] if op == "stop" => {
+                    if !active {
+                        output::cli_error(world, format!("{} is not started!", name_owned));
+                        status::cli_fail(world);
+                        return;
+                    }
+                    active = false;
+                    stop.run(world, ())
+                }
+                _ => {
+                    output::cli_error(world, format!("Usage: {} start|stop", name_owned));
+                    status::cli_fail(world);
+                    return;
+                }
+            };
+            if let Err(e) = result {
+                let message = format!("CliCommand {:?} failed to run: {}", name_owned, e);
+                output::cli_error(world, message);
+                status::cli_fail(world);
+            }
+        })
+        .set_clicommand_usage(name, &format!("{} start|stop", name))
+    }
 }
 
 /// Provides methods to run/call "console commands"
 ///
 /// You should be able to do this with exclusive `World` access, or using Bevy's `Commands`.
 pub trait CliCommandsRunExt {
-    fn run_cli(&mut self, cli: &str);
+    /// What [`run_cli`](Self::run_cli) returns
+    ///
+    /// The output of the command string, for `World` and `App`. `Commands`
+    /// only queue the command string, to run later, so they return nothing.
+    type Output;
+
+    /// Run a command string
+    ///
+    /// The string may contain multiple commands joined by `;`, `&&`, `||` or
+    /// `|` (see [`parse_cli_chain`]). Its output is added to the
+    /// [`CliOutputLog`](output::CliOutputLog) for the consoles, and returned
+    /// too (except from `Commands`). Errors, like unknown commands, are
+    /// written to the output; to get them as a [`CliError`] instead, use
+    /// [`try_run_cli`](output::CliCommandsOutputExt::try_run_cli).
+    fn run_cli(&mut self, cli: &str) -> Self::Output;
+
+    /// Queue a command string to be run at a specific point within the frame
+    ///
+    /// Requires [`CliPlugin`].
+    fn run_cli_at(&mut self, cli: &str, anchor: CliAnchor);
+
+    /// Queue a command string to be run inside [`FixedUpdate`] at an exact tick
+    ///
+    /// See [`CliFixedTick`](crate::fixed::CliFixedTick). If the tick has already
+    /// passed, the command runs on the next tick (with a warning).
+    ///
+    /// Requires [`CliPlugin`].
+    fn run_cli_at_tick(&mut self, cli: &str, tick: u64);
+
+    /// Add a command string to the [`CliQueue`], to be run in a later frame
+    ///
+    /// Queued commands run in order, a limited number per frame (see [`queue`]),
+    /// with the source of the command string that queued them, if any (see
+    /// [`source`]).
+    ///
+    /// Requires [`CliPlugin`].
+    fn run_cli_queued(&mut self, cli: &str);
+
+    /// Run a command string once, after a delay (in virtual time)
+    ///
+    /// See [`schedule`]. Requires the `schedule` feature and [`CliPlugin`].
+    #[cfg(feature = "schedule")]
+    fn run_cli_after(&mut self, cli: &str, delay: Duration);
+
+    /// Run a command string once, at a time (in elapsed virtual time)
+    ///
+    /// If the time has already passed, it runs in the next frame. See
+    /// [`schedule`]. Requires the `schedule` feature and [`CliPlugin`].
+    #[cfg(feature = "schedule")]
+    fn run_cli_at_time(&mut self, cli: &str, time: Duration);
+
+    /// Run a command string repeatedly, at an interval (in virtual time)
+    ///
+    /// It runs until cancelled with [`CliSchedule::cancel`] or the built-in
+    /// `schedule cancel` command. See [`schedule`]. Requires the `schedule`
+    /// feature and [`CliPlugin`].
+    #[cfg(feature = "schedule")]
+    fn run_cli_every(&mut self, cli: &str, interval: Duration);
 }
 
 impl CliCommandsRegisterExt for World {
@@ -66,18 +1210,30 @@ impl CliCommandsRegisterExt for World {
         S: IntoSystem<(), (), Param> + 'static,
     {
         self.init_resource::<CliCommands>();
+        let checked = resolve::check_name(self, name)
+            .and_then(|()| self.resource::<CliCommands>().check_reserved(name));
+        if let Err(e) = checked {
+            error!("Cannot register CliCommand {:?}: {}!", name, e);
+            return self;
+        }
         let new_id = self.register_system(system);
-        let cmds = &mut self.resource_mut::<CliCommands>().commands;
+        let mut clicommands = self.resource_mut::<CliCommands>();
+        let owner = clicommands.owner_scope.clone();
+        let groups = clicommands.group_scope.clone();
+        let cmds = &mut clicommands.commands;
         if let Some(cmd) = cmds.get_mut(name) {
-            cmd.noargs = Some(new_id);
+            let old_id = cmd.noargs.replace(new_id);
+            cmd.owner = owner.or(cmd.owner.take());
+            cmd.add_groups(groups);
+            // Free the system that this one replaces
+            if let Some(old_id) = old_id {
+                clicommands.unused_systems.insert(old_id.entity());
+                cleanup_removed_commands(self);
+            }
         } else {
-            cmds.insert(
-                name.to_owned(),
-                CliCommandSystems {
-                    noargs: Some(new_id),
-                    args: None,
-                },
-            );
+            let mut cmd = CliCommandSystems::new(owner, groups);
+            cmd.noargs = Some(new_id);
+            cmds.insert(name.to_owned(), cmd);
         }
         self
     }
@@ -86,18 +1242,30 @@ impl CliCommandsRegisterExt for World {
         S: IntoSystem<In<Vec<String>>, (), Param> + 'static,
     {
         self.init_resource::<CliCommands>();
+        let checked = resolve::check_name(self, name)
+            .and_then(|()| self.resource::<CliCommands>().check_reserved(name));
+        if let Err(e) = checked {
+            error!("Cannot register CliCommand {:?}: {}!", name, e);
+            return self;
+        }
         let new_id = self.register_system(system);
-        let cmds = &mut self.resource_mut::<CliCommands>().commands;
+        let mut clicommands = self.resource_mut::<CliCommands>();
+        let owner = clicommands.owner_scope.clone();
+        let groups = clicommands.group_scope.clone();
+        let cmds = &mut clicommands.commands;
         if let Some(cmd) = cmds.get_mut(name) {
-            cmd.args = Some(new_id);
+            let old_id = cmd.args.replace(new_id);
+            cmd.owner = owner.or(cmd.owner.take());
+            cmd.add_groups(groups);
+            // Free the system that this one replaces
+            if let Some(old_id) = old_id {
+                clicommands.unused_systems.insert(old_id.entity());
+                cleanup_removed_commands(self);
+            }
         } else {
-            cmds.insert(
-                name.to_owned(),
-                CliCommandSystems {
-                    args: Some(new_id),
-                    noargs: None,
-                },
-            );
+            let mut cmd = CliCommandSystems::new(owner, groups);
+            cmd.args = Some(new_id);
+            cmds.insert(name.to_owned(), cmd);
         }
         self
     }
@@ -105,112 +1273,1359 @@ impl CliCommandsRegisterExt for World {
         let Some(mut clicommands) = self.get_resource_mut::<CliCommands>() else {
             return self;
         };
-        clicommands.commands.remove(name);
+        if let Some(cmd) = clicommands.commands.remove(name) {
+            clicommands.unused_systems.extend(cmd.system_entities());
+            clicommands.removed_names.push(name.to_owned());
+            cleanup_removed_commands(self);
+        }
         self
     }
-}
-
-impl CliCommandsRegisterExt for App {
-    fn register_clicommand_noargs<S, Param>(&mut self, name: &str, system: S) -> &mut Self
+    fn register_cli_namespace<F>(&mut self, namespace: &str, f: F) -> &mut Self
     where
-        S: IntoSystem<(), (), Param> + 'static,
+        F: FnOnce(&mut CliNamespace),
     {
-        self.world_mut().register_clicommand_noargs(name, system);
+        self.init_resource::<CliCommands>();
+        f(&mut CliNamespace::new(self, namespace.to_owned()));
         self
     }
-    fn register_clicommand_args<S, Param>(&mut self, name: &str, system: S) -> &mut Self
+    fn with_clicommand_owner<F>(&mut self, owner: &str, f: F) -> &mut Self
     where
-        S: IntoSystem<In<Vec<String>>, (), Param> + 'static,
+        F: FnOnce(&mut Self),
     {
-        self.world_mut().register_clicommand_args(name, system);
+        let previous = set_owner_scope(self, Some(owner.to_owned()));
+        f(self);
+        set_owner_scope(self, previous);
         self
     }
-    fn unregister_clicommand(&mut self, name: &str) -> &mut Self {
-        self.world_mut().unregister_clicommand(name);
+    fn with_clicommand_group<F>(&mut self, group: &str, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut Self),
+    {
+        push_group_scope(self, group);
+        f(self);
+        pop_group_scope(self);
         self
     }
-}
-
-impl CliCommandsRunExt for World {
-    fn run_cli(&mut self, cli: &str) {
-        // TODO: support quotes and other such fancy syntax?
-
-        let cli = cli.trim();
-
-        let Some(name) = cli.split_ascii_whitespace().next() else {
-            error!("Attempted to run empty CLI string!");
-            return;
-        };
-
-        let args: Vec<String> = cli
-            .split_ascii_whitespace()
-            .map(|s| s.to_owned())
-            .collect();
-
-        let Some(cmd) = self.resource::<CliCommands>().commands.get(name) else {
-            error!("CliCommand {:?} not found!", name);
-            return;
-        };
-
-        if args.len() > 1 {
-            if let Some(id) = cmd.args {
-                debug!("Running CliCommand {:?} with args: {:?}", name, args);
-                if let Err(e) = self.run_system_with_input(id, args) {
-                    error!("CliCommand {:?} failed to run: {}", name, e);
-                }
-                // DONE!
-                return;
-            } else {
-                warn!(
-                    "CliCommand {:?} does not support args; discarding args!",
-                    name
-                );
-            }
-        }
-
-        if let Some(id) = cmd.noargs {
-            debug!("Running CliCommand {:?} (without args)", name);
-            if let Err(e) = self.run_system(id) {
-                error!("CliCommand {:?} failed to run: {}", name, e);
+    fn reserve_cli_namespace(&mut self, namespace: &str) -> &mut Self {
+        let mut clicommands = self.get_resource_or_insert_with(CliCommands::default);
+        let owner = clicommands.owner_scope.clone();
+        match clicommands.reserved.get(namespace) {
+            Some(reserved) if *reserved != owner => error!(
+                "Cannot reserve CLI namespace {:?}: already reserved by {}!",
+                namespace,
+                namespace::owner_name(reserved.as_deref())
+            ),
+            _ => {
+                clicommands.reserved.insert(namespace.to_owned(), owner);
             }
-        } else if let Some(id) = cmd.args {
-            debug!("Running CliCommand {:?} (empty args)", name);
-            if let Err(e) = self.run_system_with_input(id, vec![]) {
-                error!("CliCommand {:?} failed to run: {}", name, e);
-            }
-        } else {
-            panic!("Missing CliCommand system registration");
         }
+        self
     }
-}
-
-impl CliCommandsRunExt for App {
-    fn run_cli(&mut self, cli: &str) {
-        self.world_mut().run_cli(cli);
+    fn add_clicommand_run_condition<C, M>(&mut self, name: &str, condition: C) -> &mut Self
+    where
+        C: Condition<M> + 'static,
+    {
+        let known = self
+            .get_resource::<CliCommands>()
+            .is_some_and(|clicommands| clicommands.commands.contains_key(name));
+        if !known {
+            error!("Cannot add run condition to unknown CliCommand {:?}!", name);
+            return self;
+        }
+        let new_id: SystemId<(), bool> = self.register_system(condition);
+        if let Some(cmd) = self.resource_mut::<CliCommands>().commands.get_mut(name) {
+            cmd.conditions.push(new_id);
+        }
+        self
     }
-}
-
-impl CliCommandsRunExt for Commands<'_, '_> {
-    fn run_cli(&mut self, cli: &str) {
-        self.queue(CliRunCommand(cli.to_owned()));
+    fn require_clicommand_resource<R: Resource>(&mut self, name: &str, plugin: &str) -> &mut Self {
+        let cmd = self
+            .get_resource_mut::<CliCommands>()
+            .and_then(|clicommands| clicommands.into_inner().commands.get_mut(name));
+        match cmd {
+            Some(cmd) => cmd.requirements.push(CliRequirement {
+                exists: |world| world.contains_resource::<R>(),
+                plugin: plugin.to_owned(),
+            }),
+            None => error!("Cannot add requirement to unknown CliCommand {:?}!", name),
+        }
+        self
     }
-}
-
-pub struct CliRunCommand(pub String);
-
-impl bevy::ecs::world::Command for CliRunCommand {
-    fn apply(self, world: &mut World) {
-        world.run_cli(&self.0);
+    fn set_clicommand_description(&mut self, name: &str, description: &str) -> &mut Self {
+        let cmd = self
+            .get_resource_mut::<CliCommands>()
+            .and_then(|clicommands| clicommands.into_inner().commands.get_mut(name));
+        match cmd {
+            Some(cmd) => cmd.description = Some(description.to_owned()),
+            None => error!("Cannot set description of unknown CliCommand {:?}!", name),
+        }
+        self
     }
-}
+    fn set_clicommand_completer<F>(&mut self, name: &str, completer: F) -> &mut Self
+    where
+        F: Fn(&CliCompletionRequest, &World) -> Vec<String> + Send + Sync + 'static,
+    {
+        let cmd = self
+            .get_resource_mut::<CliCommands>()
+            .and_then(|clicommands| clicommands.into_inner().commands.get_mut(name));
+        match cmd {
+            Some(cmd) => cmd.completer = Some(Arc::new(completer)),
+            None => error!("Cannot set completer of unknown CliCommand {:?}!", name),
+        }
+        self
+    }
+    fn set_clicommand_usage(&mut self, name: &str, usage: &str) -> &mut Self {
+        let cmd = self
+            .get_resource_mut::<CliCommands>()
+            .and_then(|clicommands| clicommands.into_inner().commands.get_mut(name));
+        match cmd {
+            Some(cmd) => cmd.usage = Some(usage.to_owned()),
+            None => error!("Cannot set usage of unknown CliCommand {:?}!", name),
+        }
+        self
+    }
+    fn set_clicommand_permission(&mut self, name: &str, permission: CliPermission) -> &mut Self {
+        let cmd = self
+            .get_resource_mut::<CliCommands>()
+            .and_then(|clicommands| clicommands.into_inner().commands.get_mut(name));
+        match cmd {
+            Some(cmd) => cmd.permission = permission,
+            None => error!("Cannot set permission of unknown CliCommand {:?}!", name),
+        }
+        self
+    }
+    fn set_clicommand_dangerous(&mut self, name: &str) -> &mut Self {
+        let cmd = self
+            .get_resource_mut::<CliCommands>()
+            .and_then(|clicommands| clicommands.into_inner().commands.get_mut(name));
+        match cmd {
+            Some(cmd) => cmd.dangerous = true,
+            None => error!("Cannot make unknown CliCommand {:?} dangerous!", name),
+        }
+        self
+    }
+    fn set_clicommand_cooldown(&mut self, name: &str, cooldown: Duration) -> &mut Self {
+        let cmd = self
+            .get_resource_mut::<CliCommands>()
+            .and_then(|clicommands| clicommands.into_inner().commands.get_mut(name));
+        match cmd {
+            Some(cmd) => cmd.throttle.cooldown = Some(cooldown),
+            None => error!("Cannot set cooldown of unknown CliCommand {:?}!", name),
+        }
+        self
+    }
+    fn set_clicommand_rate_limit(&mut self, name: &str, max_per_second: u32) -> &mut Self {
+        let cmd = self
+            .get_resource_mut::<CliCommands>()
+            .and_then(|clicommands| clicommands.into_inner().commands.get_mut(name));
+        match cmd {
+            Some(cmd) => cmd.throttle.max_per_second = Some(max_per_second),
+            None => error!("Cannot set rate limit of unknown CliCommand {:?}!", name),
+        }
+        self
+    }
+    fn set_clicommand_timing(&mut self, name: &str) -> &mut Self {
+        let cmd = self
+            .get_resource_mut::<CliCommands>()
+            .and_then(|clicommands| clicommands.into_inner().commands.get_mut(name));
+        match cmd {
+            Some(cmd) => cmd.timed = true,
+            None => error!("Cannot time unknown CliCommand {:?}!", name),
+        }
+        self
+    }
+    fn set_clicommand_paged(&mut self, name: &str) -> &mut Self {
+        let cmd = self
+            .get_resource_mut::<CliCommands>()
+            .and_then(|clicommands| clicommands.into_inner().commands.get_mut(name));
+        match cmd {
+            Some(cmd) => cmd.paged = true,
+            None => error!("Cannot page unknown CliCommand {:?}!", name),
+        }
+        self
+    }
+    fn set_clicommand_concurrency(
+        &mut self,
+        name: &str,
+        concurrency: jobs::CliConcurrency,
+    ) -> &mut Self {
+        let cmd = self
+            .get_resource_mut::<CliCommands>()
+            .and_then(|clicommands| clicommands.into_inner().commands.get_mut(name));
+        match cmd {
+            Some(cmd) => cmd.concurrency = concurrency,
+            None => error!("Cannot set concurrency of unknown CliCommand {:?}!", name),
+        }
+        self
+    }
+    fn set_clicommand_arity(&mut self, name: &str, range: impl RangeBounds<usize>) -> &mut Self {
+        let cmd = self
+            .get_resource_mut::<CliCommands>()
+            .and_then(|clicommands| clicommands.into_inner().commands.get_mut(name));
+        match cmd {
+            Some(cmd) => cmd.spec.set_arity(range),
+            None => error!("Cannot set arity of unknown CliCommand {:?}!", name),
+        }
+        self
+    }
+    fn set_clicommand_arg_check<F>(&mut self, name: &str, index: usize, check: F) -> &mut Self
+    where
+        F: Fn(&str) -> Result<(), String> + Send + Sync + 'static,
+    {
+        let cmd = self
+            .get_resource_mut::<CliCommands>()
+            .and_then(|clicommands| clicommands.into_inner().commands.get_mut(name));
+        match cmd {
+            Some(cmd) => cmd.spec.add_check(index, Arc::new(check)),
+            None => error!("Cannot set arg check of unknown CliCommand {:?}!", name),
+        }
+        self
+    }
+    fn add_clicommand_group(&mut self, name: &str, group: &str) -> &mut Self {
+        let cmd = self
+            .get_resource_mut::<CliCommands>()
+            .and_then(|clicommands| clicommands.into_inner().commands.get_mut(name));
+        match cmd {
+            Some(cmd) => cmd.add_groups([group.to_owned()]),
+            None => error!("Cannot add unknown CliCommand {:?} to a group!", name),
+        }
+        self
+    }
+    fn add_clicommand_emits<E: Event>(&mut self, name: &str) -> &mut Self {
+        self.init_resource::<emit::CliPendingEmit<E>>();
+        let cmd = self
+            .get_resource_mut::<CliCommands>()
+            .and_then(|clicommands| clicommands.into_inner().commands.get_mut(name));
+        match cmd {
+            Some(cmd) => cmd.emitters.push(emit::flush::<E>),
+            None => error!("Cannot add emitted event to unknown CliCommand {:?}!", name),
+        }
+        self
+    }
+    fn set_clicommand_fallback<S, Param>(&mut self, system: S) -> &mut Self
+    where
+        S: IntoSystem<In<String>, (), Param> + 'static,
+    {
+        self.init_resource::<CliCommands>();
+        let new_id = self.register_system(system);
+        self.resource_mut::<CliCommands>().fallback = Some(new_id);
+        self
+    }
+}
+
+impl CliCommandsRegisterExt for App {
+    fn register_clicommand_noargs<S, Param>(&mut self, name: &str, system: S) -> &mut Self
+    where
+        S: IntoSystem<(), (), Param> + 'static,
+    {
+        self.world_mut().register_clicommand_noargs(name, system);
+        self
+    }
+    fn register_clicommand_args<S, Param>(&mut self, name: &str, system: S) -> &mut Self
+    where
+        S: IntoSystem<In<Vec<String>>, (), Param> + 'static,
+    {
+        self.world_mut().register_clicommand_args(name, system);
+        self
+    }
+    fn unregister_clicommand(&mut self, name: &str) -> &mut Self {
+        self.world_mut().unregister_clicommand(name);
+        self
+    }
+    fn register_cli_namespace<F>(&mut self, namespace: &str, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut CliNamespace),
+    {
+        self.world_mut().register_cli_namespace(namespace, f);
+        self
+    }
+    fn with_clicommand_owner<F>(&mut self, owner: &str, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut Self),
+    {
+        let previous = set_owner_scope(self.world_mut(), Some(owner.to_owned()));
+        f(self);
+        set_owner_scope(self.world_mut(), previous);
+        self
+    }
+    fn with_clicommand_group<F>(&mut self, group: &str, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut Self),
+    {
+        push_group_scope(self.world_mut(), group);
+        f(self);
+        pop_group_scope(self.world_mut());
+        self
+    }
+    fn reserve_cli_namespace(&mut self, namespace: &str) -> &mut Self {
+        self.world_mut().reserve_cli_namespace(namespace);
+        self
+    }
+    fn add_clicommand_run_condition<C, M>(&mut self, name: &str, condition: C) -> &mut Self
+    where
+        C: Condition<M> + 'static,
+    {
+        self.world_mut()
+            .add_clicommand_run_condition(name, condition);
+        self
+    }
+    fn require_clicommand_resource<R: Resource>(&mut self, name: &str, plugin: &str) -> &mut Self {
+        self.world_mut()
+            .require_clicommand_resource::<R>(name, plugin);
+        self
+    }
+    fn set_clicommand_description(&mut self, name: &str, description: &str) -> &mut Self {
+        self.world_mut()
+            .set_clicommand_description(name, description);
+        self
+    }
+    fn set_clicommand_usage(&mut self, name: &str, usage: &str) -> &mut Self {
+        self.world_mut().set_clicommand_usage(name, usage);
+        self
+    }
+    fn set_clicommand_permission(&mut self, name: &str, permission: CliPermission) -> &mut Self {
+        self.world_mut().set_clicommand_permission(name, permission);
+        self
+    }
+    fn set_clicommand_dangerous(&mut self, name: &str) -> &mut Self {
+        self.world_mut().set_clicommand_dangerous(name);
+        self
+    }
+    fn set_clicommand_cooldown(&mut self, name: &str, cooldown: Duration) -> &mut Self {
+        self.world_mut().set_clicommand_cooldown(name, cooldown);
+        self
+    }
+    fn set_clicommand_rate_limit(&mut self, name: &str, max_per_second: u32) -> &mut Self {
+        self.world_mut()
+            .set_clicommand_rate_limit(name, max_per_second);
+        self
+    }
+    fn set_clicommand_timing(&mut self, name: &str) -> &mut Self {
+        self.world_mut().set_clicommand_timing(name);
+        self
+    }
+    fn set_clicommand_paged(&mut self, name: &str) -> &mut Self {
+        self.world_mut().set_clicommand_paged(name);
+        self
+    }
+    fn set_clicommand_concurrency(
+        &mut self,
+        name: &str,
+        concurrency: jobs::CliConcurrency,
+    ) -> &mut Self {
+        self.world_mut()
+            .set_clicommand_concurrency(name, concurrency);
+        self
+    }
+    fn set_clicommand_arity(&mut self, name: &str, range: impl RangeBounds<usize>) -> &mut Self {
+        self.world_mut().set_clicommand_arity(name, range);
+        self
+    }
+    fn set_clicommand_arg_check<F>(&mut self, name: &str, index: usize, check: F) -> &mut Self
+    where
+        F: Fn(&str) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.world_mut()
+            .set_clicommand_arg_check(name, index, check);
+        self
+    }
+    fn add_clicommand_group(&mut self, name: &str, group: &str) -> &mut Self {
+        self.world_mut().add_clicommand_group(name, group);
+        self
+    }
+    fn add_clicommand_emits<E: Event>(&mut self, name: &str) -> &mut Self {
+        self.world_mut().add_clicommand_emits::<E>(name);
+        self
+    }
+    fn set_clicommand_completer<F>(&mut self, name: &str, completer: F) -> &mut Self
+    where
+        F: Fn(&CliCompletionRequest, &World) -> Vec<String> + Send + Sync + 'static,
+    {
+        self.world_mut().set_clicommand_completer(name, completer);
+        self
+    }
+    fn set_clicommand_fallback<S, Param>(&mut self, system: S) -> &mut Self
+    where
+        S: IntoSystem<In<String>, (), Param> + 'static,
+    {
+        self.world_mut().set_clicommand_fallback(system);
+        self
+    }
+}
+
+impl CliCommandsRunExt for World {
+    type Output = Vec<output::CliOutputLine>;
+
+    fn run_cli(&mut self, cli: &str) -> Vec<output::CliOutputLine> {
+        self.get_resource_or_insert_with(output::CliOutputLog::default)
+            .begin_collecting();
+        let _ = run_cli_chain(self, cli, true);
+        self.resource_mut::<output::CliOutputLog>().end_collecting()
+    }
+    fn run_cli_at(&mut self, cli: &str, anchor: CliAnchor) {
+        self.get_resource_or_insert_with(CliAnchorQueue::default)
+            .push(anchor, cli.to_owned());
+    }
+    fn run_cli_at_tick(&mut self, cli: &str, tick: u64) {
+        self.get_resource_or_insert_with(CliFixedQueue::default)
+            .push(tick, cli.to_owned());
+    }
+    fn run_cli_queued(&mut self, cli: &str) {
+        let source = quota::current_cli_source(self);
+        let mut queue = self.get_resource_or_insert_with(CliQueue::default);
+        match source {
+            Some(source) => queue.push_from(cli.to_owned(), source),
+            None => queue.push(cli.to_owned()),
+        }
+    }
+    #[cfg(feature = "schedule")]
+    fn run_cli_after(&mut self, cli: &str, delay: Duration) {
+        let source = quota::current_cli_source(self);
+        let mut schedule = self.get_resource_or_insert_with(CliSchedule::default);
+        let id = schedule.after(delay, cli);
+        if let Some(source) = source {
+            schedule.set_source(id, source);
+        }
+    }
+    #[cfg(feature = "schedule")]
+    fn run_cli_at_time(&mut self, cli: &str, time: Duration) {
+        let source = quota::current_cli_source(self);
+        let mut schedule = self.get_resource_or_insert_with(CliSchedule::default);
+        let id = schedule.at(time, cli);
+        if let Some(source) = source {
+            schedule.set_source(id, source);
+        }
+    }
+    #[cfg(feature = "schedule")]
+    fn run_cli_every(&mut self, cli: &str, interval: Duration) {
+        let source = quota::current_cli_source(self);
+        let mut schedule = self.get_resource_or_insert_with(CliSchedule::default);
+        let id = schedule.every(interval, cli);
+        if let Some(source) = source {
+            schedule.set_source(id, source);
+        }
+    }
+}
+
+/// Describe an error, translated by the [`CliLocalizer`](localize::CliLocalizer)
+pub(crate) fn describe_error(world: &World, e: &CliError) -> String {
+    match world.get_resource::<localize::CliLocalizer>() {
+        Some(localizer) => localizer.error(e),
+        None => e.to_string(),
+    }
+}
+
+/// Run a command string that may be a chain, returning the outcome of the last command run
+///
+/// If `log` is set, errors are logged (except [`CliError::Failed`], which the
+/// command reports itself). Unless it runs inside another command string,
+/// its output is kept in the [`CliOutputHistory`](scrollback::CliOutputHistory).
+pub(crate) fn run_cli_chain(world: &mut World, cli: &str, log: bool) -> Result<(), CliError> {
+    let recording = world
+        .get_resource_mut::<output::CliOutputLog>()
+        .is_some_and(|mut log| log.begin_recording());
+    let result = run_chain_links(world, cli, log);
+    if recording {
+        let lines = world.resource_mut::<output::CliOutputLog>().end_recording();
+        scrollback::record_output(world, cli, lines);
+    }
+    result
+}
+
+pub(crate) fn run_chain_links(world: &mut World, cli: &str, log: bool) -> Result<(), CliError> {
+    let report = |world: &mut World, line: &str, result: Result<(), CliError>| {
+        match &result {
+            Err(CliError::Failed(_)) | Ok(()) => {}
+            Err(e @ CliError::NotFound(name)) if log => {
+                let mut message = format!("{}!", describe_error(world, e));
+                if let Some(hint) = resolve::did_you_mean(world, name) {
+                    message = format!("{} {}", message, hint);
+                }
+                output::cli_error(world, message);
+            }
+            Err(
+                e @ CliError::InvalidArgs {
+                    name, index, usage, ..
+                },
+            ) if log => {
+                let mut message = format!("{}!", describe_error(world, e));
+                if let Ok(mut parsed) = parse_cli(line) {
+                    // The args were checked without these
+                    explain::take_flag(&mut parsed);
+                    parsed.take_flag("--page");
+                    #[cfg(feature = "confirm")]
+                    if confirm::is_dangerous(world, name) {
+                        parsed.args.retain(|arg| arg != "--force");
+                    }
+                    let caret = args_caret(&parsed.name, &parsed.args, *index);
+                    message = format!("{}\n{}", message, caret);
+                }
+                let localizer = world.get_resource::<localize::CliLocalizer>();
+                let usage = match localizer {
+                    Some(localizer) => localizer.usage(name, usage.as_deref()),
+                    None => usage.as_deref(),
+                };
+                if let Some(usage) = usage {
+                    let usage = match localizer {
+                        Some(localizer) => localizer.format(
+                            "help.usage",
+                            "Usage: {usage}",
+                            &[("usage", &usage as &dyn std::fmt::Display)],
+                        ),
+                        None => format!("Usage: {}", usage),
+                    };
+                    message = format!("{}\n{}", message, usage);
+                }
+                output::cli_error(world, message);
+            }
+            Err(e @ CliError::Parse(parse_error)) if log => {
+                let mut message = format!("{}!", describe_error(world, e));
+                if let Some(span) = parse_error.span(line) {
+                    message = format!("{}\n{}", message, cli_caret(line, span));
+                }
+                output::cli_error(world, message);
+            }
+            Err(e) if log => {
+                let message = format!("{}!", describe_error(world, e));
+                output::cli_error(world, message);
+            }
+            Err(_) => {}
+        }
+        result
+    };
+    let chain = match parse_cli_chain(cli) {
+        Ok(chain) => chain,
+        Err(e) => {
+            status::record(world, cli, false);
+            return report(world, cli, Err(e.into()));
+        }
+    };
+    let stop_on_error = world
+        .get_resource::<CliConfig>()
+        .is_some_and(|config| config.stop_on_error);
+    let links = chain.links();
+    let mut result = Ok(());
+    // Output of the previous command, for a `|` (see [`pipe`])
+    let mut piped: Option<Vec<String>> = None;
+    for (i, link) in links.iter().enumerate() {
+        let input = piped.take();
+        let success = result.is_ok();
+        if !success && stop_on_error && link.op == Some(CliChainOp::Then) {
+            break;
+        }
+        if link.op.is_some_and(|op| !op.should_run(success)) {
+            continue;
+        }
+        let segment = &cli[link.span.clone()];
+        sleep::clear_sleep_request(world);
+        // Expanded here, so that errors can point into the expanded line
+        #[cfg(feature = "env")]
+        let mut expanded = match env::expand(world, segment) {
+            Ok(expanded) => expanded,
+            Err(e) => {
+                result = report(world, segment, Err(e));
+                status::record(world, segment, false);
+                if status::is_returning(world) {
+                    break;
+                }
+                continue;
+            }
+        };
+        #[cfg(not(feature = "env"))]
+        let mut expanded = segment.to_owned();
+        for line in input.into_iter().flatten() {
+            expanded.push(' ');
+            expanded.push_str(&quote_cli_arg(&line));
+        }
+        let pipes = links
+            .get(i + 1)
+            .is_some_and(|next| next.op == Some(CliChainOp::Pipe));
+        let line_result = if pipes {
+            let (output, line_result) =
+                output::capture_output(world, |world| run_recorded_cli_line(world, &expanded));
+            piped = Some(output::take_piped_lines(world, output));
+            line_result
+        } else {
+            scrollback::run_limited(world, |world| run_recorded_cli_line(world, &expanded))
+        };
+        result = report(world, &expanded, line_result);
+        let success = result.is_ok();
+        status::record(world, segment, success);
+        if status::is_returning(world) {
+            break;
+        }
+        if sleep::is_sleep_requested(world) {
+            // Defer the rest of the chain until the `sleep` is over
+            let next = links[i + 1..]
+                .iter()
+                .find(|link| link.op.is_some_and(|op| op.should_run(success)));
+            if let Some(next) = next {
+                sleep::defer_until_wake(world, cli[next.span.start..].to_owned());
+            }
+            break;
+        }
+    }
+    result
+}
+
+/// Run a command string containing a single command
+fn run_cli_line(world: &mut World, cli: &str) -> Result<(), CliError> {
+    #[cfg(feature = "env")]
+    let cli = &env::expand(world, cli)?;
+    run_recorded_cli_line(world, cli)
+}
+
+/// Run a command string containing a single command, after variable
+/// expansion, recording it for `replay`
+fn run_recorded_cli_line(world: &mut World, expanded: &str) -> Result<(), CliError> {
+    #[cfg(feature = "replay")]
+    let recorded = replay::begin_record(world, expanded);
+    let result = run_expanded_cli_line(world, expanded);
+    #[cfg(feature = "replay")]
+    if recorded {
+        replay::end_record(world);
+    }
+    result
+}
+
+/// Point out an arg of a command with [`cli_caret`], or the end if `index` is `None`
+fn args_caret(name: &str, args: &[String], index: Option<usize>) -> String {
+    let mut line = quote_cli_arg(name).into_owned();
+    let mut span = None;
+    for (i, arg) in args.iter().enumerate() {
+        line.push(' ');
+        let start = line.len();
+        line.push_str(&quote_cli_arg(arg));
+        if index == Some(i) {
+            span = Some(start..line.len());
+        }
+    }
+    let span = span.unwrap_or(line.len()..line.len());
+    cli_caret(&line, span)
+}
+
+/// Run a command string containing a single command, after variable expansion
+fn run_expanded_cli_line(world: &mut World, cli: &str) -> Result<(), CliError> {
+    let mut parsed = parse_cli(cli)?;
+    // All of the output, instead of what fits in `CliConfig::max_output_lines`
+    if parsed.take_flag("--page") && world.contains_resource::<scrollback::CliScrollback>() {
+        return scrollback::run_paged(world, |world| run_explained_cli_line(world, cli, parsed));
+    }
+    run_explained_cli_line(world, cli, parsed)
+}
+
+/// Run a parsed command string, reporting what it changed if it has an
+/// `--explain` flag (see [`explain`])
+fn run_explained_cli_line(
+    world: &mut World,
+    cli: &str,
+    mut parsed: ParsedCli,
+) -> Result<(), CliError> {
+    if !explain::take_flag(&mut parsed) {
+        return run_parsed_cli_line(world, cli, parsed);
+    }
+    let typed_name = parsed.name.clone();
+    let since = world.increment_change_tick();
+    let result = run_parsed_cli_line(world, cli, parsed);
+    // Not if the command could not run at all
+    if matches!(result, Ok(()) | Err(CliError::Failed(_))) {
+        explain::report(world, &typed_name, since);
+    }
+    result
+}
+
+/// Run a parsed command string, see [`run_expanded_cli_line`]
+fn run_parsed_cli_line(world: &mut World, cli: &str, parsed: ParsedCli) -> Result<(), CliError> {
+    let mut typed_name = parsed.name;
+    let mut args = parsed.args;
+    intercept::run_hooks(world, &mut typed_name, &mut args)?;
+    let typed_name = typed_name.as_str();
+
+    let _span = info_span!("clicommand", command = typed_name, args = ?args).entered();
+
+    let failed = |success: bool| match success {
+        true => Ok(()),
+        false => Err(CliError::Failed(typed_name.to_owned())),
+    };
+    if let Some(result) = route::dispatch(world, typed_name, &args) {
+        return result;
+    }
+    if let Some(success) = session::run_session_proc(world, typed_name, &args) {
+        return failed(success);
+    }
+    if let Some(success) = alias::run_alias(world, typed_name, &args) {
+        return failed(success);
+    }
+
+    if let Some(result) = namespace::run_subcommand(world, typed_name, &args) {
+        return result;
+    }
+
+    let name = match resolve::resolve_command(world, typed_name) {
+        CliResolution::Found(name) => name,
+        CliResolution::Ambiguous(candidates) => {
+            return Err(CliError::Ambiguous {
+                name: typed_name.to_owned(),
+                candidates,
+            });
+        }
+        CliResolution::NotFound => {
+            #[cfg(feature = "cvars")]
+            if let Some(success) = vars::run_clivar(world, typed_name, &args) {
+                return failed(success);
+            }
+            let fallback = world
+                .get_resource::<CliCommands>()
+                .and_then(|clicommands| clicommands.fallback);
+            let Some(id) = fallback else {
+                return Err(CliError::NotFound(typed_name.to_owned()));
+            };
+            debug!("Running CliCommand fallback for {:?}", cli);
+            return world
+                .run_system_with_input(id, cli.trim().to_owned())
+                .map_err(|e| CliError::SystemFailed {
+                    name: typed_name.to_owned(),
+                    message: e.to_string(),
+                });
+        }
+    };
+    let name = name.as_str();
+
+    check_runnable(world, name)?;
+
+    let notes = intercept::check(world, name, &args)?;
+
+    quota::check(world)?;
+    let now = Instant::now();
+    if let Some(cmd) = world.resource_mut::<CliCommands>().commands.get_mut(name) {
+        if let Err(remaining) = cmd.throttle.check(now) {
+            return Err(CliError::OnCooldown {
+                name: name.to_owned(),
+                remaining,
+            });
+        }
+    }
+
+    #[cfg(feature = "confirm")]
+    confirm::check(world, name, &mut args)?;
+    check_args(world, name, &args)?;
+    selector::resolve_args(world, &mut args)?;
+    mention::resolve_args(world, &mut args)?;
+
+    diagnostics::record_execution(world);
+
+    let mut clicommands = world.resource_mut::<CliCommands>();
+    let Some(cmd) = clicommands.commands.get_mut(name) else {
+        return Err(CliError::NotFound(name.to_owned()));
+    };
+    cmd.run_count += 1;
+    cmd.last_run = Some(now);
+    cmd.throttle.record(now);
+    let paged = cmd.paged;
+    let paged = scrollback::should_page(world, paged);
+
+    events::send(
+        world,
+        CliCommandStarted {
+            name: name.to_owned(),
+            args: args.clone(),
+        },
+    );
+    let emitters = command_emitters(world, name);
+    for flush in &emitters {
+        // Discard anything emitted outside of this command
+        flush(world, false);
+    }
+    let start = Instant::now();
+    let mut result = Ok(());
+    let success = local::with_current_command(world, name, |world| {
+        status::run_tracked(world, |world| {
+            result = match paged {
+                true => scrollback::run_paged(world, |world| {
+                    run_command_systems(world, name, args.clone())
+                }),
+                false => run_command_systems(world, name, args.clone()),
+            };
+            result.is_ok()
+        })
+    });
+    let result = match result {
+        Ok(()) if !success => Err(CliError::Failed(name.to_owned())),
+        result => result,
+    };
+    for flush in &emitters {
+        flush(world, result.is_ok());
+    }
+    let duration = start.elapsed();
+    #[cfg(feature = "analytics")]
+    analytics::record(world, name, result.is_ok(), duration);
+    #[cfg(feature = "analytics")]
+    analytics::report_timing(world, name, duration);
+    intercept::annotate(world, notes);
+    events::send(
+        world,
+        CliCommandCompleted {
+            name: name.to_owned(),
+            args: args.clone(),
+            result: result.clone(),
+            duration,
+        },
+    );
+    if let Err(error) = &result {
+        events::send(
+            world,
+            CliCommandFailed {
+                name: name.to_owned(),
+                args,
+                error: error.clone(),
+            },
+        );
+    }
+    result
+}
+
+impl CliCommands {
+    /// The systems of the registered commands and the fallback
+    fn used_systems(&self) -> HashSet<Entity> {
+        let systems = self.commands.values().flat_map(|cmd| cmd.system_entities());
+        systems.chain(self.fallback.map(|id| id.entity())).collect()
+    }
+
+    /// Take the unused systems that can be removed from the `World`
+    ///
+    /// Systems used by a command again are no longer unused. Those used by a
+    /// snapshot that still exists are kept for later.
+    fn take_unused_systems(&mut self) -> Vec<Entity> {
+        let used = self.used_systems();
+        let snapshots: Vec<Arc<HashSet<Entity>>> = self
+            .snapshots
+            .get_mut()
+            .unwrap()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect();
+        let mut free = Vec::new();
+        self.unused_systems.retain(|entity| {
+            if used.contains(entity) {
+                return false;
+            }
+            if snapshots.iter().any(|systems| systems.contains(entity)) {
+                return true;
+            }
+            free.push(*entity);
+            false
+        });
+        free
+    }
+}
+
+/// Clean up after commands that were removed from the registry
+///
+/// Their unused systems are removed from the `World` (see
+/// [`snapshot`](crate::snapshot) for the systems that are kept), and the
+/// [`CliLocal`](local::CliLocal) state and self tests of their names are
+/// cleared, unless a command of the same name is registered again.
+///
+/// Runs in `Last`, for [`CliCommands::remove_group`], which has no access to
+/// the `World`. Everything else that removes commands calls it right away.
+pub(crate) fn cleanup_removed_commands(world: &mut World) {
+    let Some(clicommands) = world.get_resource::<CliCommands>() else {
+        return;
+    };
+    if clicommands.unused_systems.is_empty() && clicommands.removed_names.is_empty() {
+        return;
+    }
+    let mut clicommands = world.resource_mut::<CliCommands>();
+    let systems = clicommands.take_unused_systems();
+    let mut names = std::mem::take(&mut clicommands.removed_names);
+    names.retain(|name| !clicommands.commands.contains_key(name));
+    for entity in systems {
+        if let Ok(entity) = world.get_entity_mut(entity) {
+            entity.despawn();
+        }
+    }
+    for name in &names {
+        if let Some(mut locals) = world.get_resource_mut::<CliLocals>() {
+            locals.clear_command(name);
+        }
+        #[cfg(feature = "selftest")]
+        if let Some(mut tests) = world.get_resource_mut::<selftest::CliSelfTests>() {
+            tests.clear_command(name);
+        }
+    }
+}
+
+/// Set the owner of the commands registered from now on, returning the previous one
+pub(crate) fn set_owner_scope(world: &mut World, owner: Option<String>) -> Option<String> {
+    let mut clicommands = world.get_resource_or_insert_with(CliCommands::default);
+    std::mem::replace(&mut clicommands.owner_scope, owner)
+}
+
+/// Add a group to the commands registered from now on
+pub(crate) fn push_group_scope(world: &mut World, group: &str) {
+    let mut clicommands = world.get_resource_or_insert_with(CliCommands::default);
+    clicommands.group_scope.push(group.to_owned());
+}
+
+/// Undo [`push_group_scope`]
+pub(crate) fn pop_group_scope(world: &mut World) {
+    let mut clicommands = world.get_resource_or_insert_with(CliCommands::default);
+    clicommands.group_scope.pop();
+}
+
+/// Check that a command can run now, with [`CliCommands::is_runnable`]
+fn check_runnable(world: &mut World, name: &str) -> Result<(), CliError> {
+    match CliCommands::is_runnable(world, name) {
+        CliAvailability::Available => Ok(()),
+        CliAvailability::NotFound => Err(CliError::NotFound(name.to_owned())),
+        CliAvailability::ConditionNotMet => Err(CliError::ConditionNotMet(name.to_owned())),
+        CliAvailability::NotPermitted(permission) => Err(CliError::NotPermitted {
+            name: name.to_owned(),
+            permission,
+        }),
+        CliAvailability::MissingPlugin => {
+            let plugins = world.resource::<CliCommands>().missing_plugins(world, name);
+            Err(CliError::MissingPlugin {
+                name: name.to_owned(),
+                plugins,
+            })
+        }
+    }
+}
+
+/// Check args against the arity and arg checks of a command (see [`arity`])
+fn check_args(world: &World, name: &str, args: &[String]) -> Result<(), CliError> {
+    let Some(cmd) = world.resource::<CliCommands>().commands.get(name) else {
+        return Ok(());
+    };
+    cmd.spec
+        .check(args)
+        .map_err(|(message, index)| CliError::InvalidArgs {
+            name: name.to_owned(),
+            message,
+            index,
+            usage: cmd.usage.clone(),
+        })
+}
+
+/// Check args against the signature of a command, if it has one
+///
+/// Commands with a signature check it themselves when they run, so this is
+/// only needed to find mistakes without running them.
+pub(crate) fn check_signature(world: &World, name: &str, args: &[String]) -> Result<(), CliError> {
+    let clicommands = world.resource::<CliCommands>();
+    let Some(signature) = clicommands.command_signature(name) else {
+        return Ok(());
+    };
+    signature
+        .parse_args(args.to_vec())
+        .map(drop)
+        .map_err(|e| CliError::InvalidArgs {
+            name: name.to_owned(),
+            message: e.to_string(),
+            index: e.arg_index(),
+            usage: clicommands
+                .commands
+                .get(name)
+                .and_then(|cmd| cmd.usage.clone()),
+        })
+}
+
+fn command_emitters(world: &World, name: &str) -> Vec<fn(&mut World, bool)> {
+    world
+        .get_resource::<CliCommands>()
+        .and_then(|clicommands| clicommands.commands.get(name))
+        .map(|cmd| cmd.emitters.clone())
+        .unwrap_or_default()
+}
+
+fn run_command_systems(world: &mut World, name: &str, args: Vec<String>) -> Result<(), CliError> {
+    let Some(cmd) = world.resource::<CliCommands>().commands.get(name) else {
+        return Err(CliError::NotFound(name.to_owned()));
+    };
+    let system_failed = |e: &dyn std::fmt::Display| CliError::SystemFailed {
+        name: name.to_owned(),
+        message: e.to_string(),
+    };
+
+    if !args.is_empty() {
+        if let Some(id) = cmd.args {
+            debug!("Running CliCommand {:?} with args: {:?}", name, args);
+            // DONE!
+            return world
+                .run_system_with_input(id, args)
+                .map_err(|e| system_failed(&e));
+        } else {
+            warn!(
+                "CliCommand {:?} does not support args; discarding args!",
+                name
+            );
+        }
+    }
+
+    if let Some(id) = cmd.noargs {
+        debug!("Running CliCommand {:?} (without args)", name);
+        world.run_system(id).map_err(|e| system_failed(&e))
+    } else if let Some(id) = cmd.args {
+        debug!("Running CliCommand {:?} (empty args)", name);
+        world
+            .run_system_with_input(id, vec![])
+            .map_err(|e| system_failed(&e))
+    } else {
+        panic!("Missing CliCommand system registration");
+    }
+}
+
+impl CliCommandsRunExt for App {
+    type Output = Vec<output::CliOutputLine>;
+
+    fn run_cli(&mut self, cli: &str) -> Vec<output::CliOutputLine> {
+        self.world_mut().run_cli(cli)
+    }
+    fn run_cli_at(&mut self, cli: &str, anchor: CliAnchor) {
+        self.world_mut().run_cli_at(cli, anchor);
+    }
+    fn run_cli_at_tick(&mut self, cli: &str, tick: u64) {
+        self.world_mut().run_cli_at_tick(cli, tick);
+    }
+    fn run_cli_queued(&mut self, cli: &str) {
+        self.world_mut().run_cli_queued(cli);
+    }
+    #[cfg(feature = "schedule")]
+    fn run_cli_after(&mut self, cli: &str, delay: Duration) {
+        self.world_mut().run_cli_after(cli, delay);
+    }
+    #[cfg(feature = "schedule")]
+    fn run_cli_at_time(&mut self, cli: &str, time: Duration) {
+        self.world_mut().run_cli_at_time(cli, time);
+    }
+    #[cfg(feature = "schedule")]
+    fn run_cli_every(&mut self, cli: &str, interval: Duration) {
+        self.world_mut().run_cli_every(cli, interval);
+    }
+}
+
+impl CliCommandsRunExt for Commands<'_, '_> {
+    type Output = ();
+
+    fn run_cli(&mut self, cli: &str) {
+        self.queue(CliRunCommand(cli.to_owned()));
+    }
+    fn run_cli_at(&mut self, cli: &str, anchor: CliAnchor) {
+        self.queue(CliRunAtCommand(cli.to_owned(), anchor));
+    }
+    fn run_cli_at_tick(&mut self, cli: &str, tick: u64) {
+        self.queue(CliRunAtTickCommand(cli.to_owned(), tick));
+    }
+    fn run_cli_queued(&mut self, cli: &str) {
+        self.queue(CliRunQueuedCommand(cli.to_owned()));
+    }
+    #[cfg(feature = "schedule")]
+    fn run_cli_after(&mut self, cli: &str, delay: Duration) {
+        self.queue(CliRunAfterCommand(cli.to_owned(), delay));
+    }
+    #[cfg(feature = "schedule")]
+    fn run_cli_at_time(&mut self, cli: &str, time: Duration) {
+        self.queue(CliRunAtTimeCommand(cli.to_owned(), time));
+    }
+    #[cfg(feature = "schedule")]
+    fn run_cli_every(&mut self, cli: &str, interval: Duration) {
+        self.queue(CliRunEveryCommand(cli.to_owned(), interval));
+    }
+}
+
+pub struct CliRunCommand(pub String);
+
+impl bevy::ecs::world::Command for CliRunCommand {
+    fn apply(self, world: &mut World) {
+        world.run_cli(&self.0);
+    }
+}
+
+/// Whether a "console command" can be run right now
+///
+/// See [`CliCommands::is_runnable`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CliAvailability {
+    /// The command exists and all of its run conditions are true
+    Available,
+    /// There is no command with the given name
+    NotFound,
+    /// The command exists, but one of its run conditions is false, or it
+    /// is disabled
+    ConditionNotMet,
+    /// The command exists, but its permission level is not allowed
+    /// by [`CliPermissions`]
+    NotPermitted(CliPermission),
+    /// The command exists, but a resource it requires does not (see
+    /// [`CliCommandsRegisterExt::require_clicommand_resource`])
+    MissingPlugin,
+}
+
+impl CliAvailability {
+    pub fn is_available(self) -> bool {
+        self == CliAvailability::Available
+    }
+}
 
 impl CliCommands {
+    /// Check if a command could be run right now, without running it
+    ///
+    /// This checks the command's permission level and evaluates its run
+    /// conditions, so it requires exclusive `World` access. Useful for UIs
+    /// that want to show which commands are currently available.
+    pub fn is_runnable(world: &mut World, name: &str) -> CliAvailability {
+        let Some(cmd) = world
+            .get_resource::<CliCommands>()
+            .and_then(|clicommands| clicommands.commands.get(name))
+        else {
+            return CliAvailability::NotFound;
+        };
+        if !cmd.enabled {
+            return CliAvailability::ConditionNotMet;
+        }
+        let permitted = match world.get_resource::<CliPermissions>() {
+            Some(permissions) => permissions.allows(cmd.permission),
+            None => CliPermissions::default().allows(cmd.permission),
+        };
+        let source = world
+            .get_resource::<status::CliStatus>()
+            .and_then(|status| status.source());
+        let permitted = permitted
+            && match (source, world.get_resource::<CliSourcePermissions>()) {
+                (Some(source), Some(permissions)) => permissions.allows(source, cmd.permission),
+                _ => true,
+            };
+        if !permitted {
+            return CliAvailability::NotPermitted(cmd.permission);
+        }
+        if cmd.requirements.iter().any(|req| !(req.exists)(world)) {
+            return CliAvailability::MissingPlugin;
+        }
+        let conditions = cmd.conditions.clone();
+        for id in conditions {
+            match world.run_system(id) {
+                Ok(true) => {}
+                Ok(false) => return CliAvailability::ConditionNotMet,
+                Err(e) => {
+                    error!("CliCommand {:?} run condition failed to run: {}", name, e);
+                    return CliAvailability::ConditionNotMet;
+                }
+            }
+        }
+        CliAvailability::Available
+    }
+    /// Set the resolver used to find which command a typed name refers to
+    ///
+    /// The default is [`CliExactResolver`](crate::resolve::CliExactResolver).
+    pub fn set_resolver(&mut self, resolver: impl CliCommandResolver) {
+        self.resolver = Some(Box::new(resolver));
+    }
+    /// Find which registered command a typed command name refers to
+    pub fn resolve(&self, name: &str) -> CliResolution {
+        match &self.resolver {
+            Some(resolver) => resolver.resolve(name, self),
+            None => CliExactResolver.resolve(name, self),
+        }
+    }
+    /// The plugins required by a command whose resources do not exist
+    pub fn missing_plugins(&self, world: &World, name: &str) -> Vec<String> {
+        let Some(cmd) = self.commands.get(name) else {
+            return vec![];
+        };
+        cmd.requirements
+            .iter()
+            .filter(|req| !(req.exists)(world))
+            .map(|req| req.plugin.clone())
+            .collect()
+    }
     pub fn iter_names(&self) -> impl Iterator<Item = &str> {
         self.commands.keys().map(|s| s.as_str())
     }
     pub fn command_available(&self, name: &str) -> bool {
         self.commands.contains_key(name)
     }
+    /// Enable or disable a command, without unregistering it
+    ///
+    /// Disabled commands cannot be run, and are not shown by `help` or
+    /// offered as completions. Commands are enabled when registered.
+    /// Returns `false` if there is no command with the given name.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        let Some(cmd) = self.commands.get_mut(name) else {
+            return false;
+        };
+        cmd.enabled = enabled;
+        true
+    }
+    /// Check if a command exists and is enabled
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.commands.get(name).is_some_and(|cmd| cmd.enabled)
+    }
+    /// Get information about a registered command, such as how often it is run
+    pub fn get_info(&self, name: &str) -> Option<CliCommandInfo<'_>> {
+        let (name, cmd) = self.commands.get_key_value(name)?;
+        Some(CliCommandInfo {
+            name,
+            namespace: cmd.namespace.as_deref(),
+            description: cmd.description.as_deref(),
+            usage: cmd.usage.as_deref(),
+            permission: cmd.permission,
+            accepts_args: cmd.args.is_some(),
+            accepts_noargs: cmd.noargs.is_some(),
+            run_count: cmd.run_count,
+            last_run: cmd.last_run,
+            enabled: cmd.enabled,
+            dangerous: cmd.dangerous,
+            owner: cmd.owner.as_deref(),
+            groups: &cmd.groups,
+            cooldown: cmd.throttle.cooldown,
+            rate_limit: cmd.throttle.max_per_second,
+            min_args: cmd.spec.min,
+            max_args: cmd.spec.max,
+        })
+    }
+    /// Get the namespace a command was registered in, if any
+    pub fn command_namespace(&self, name: &str) -> Option<&str> {
+        self.commands.get(name)?.namespace.as_deref()
+    }
+    /// Get the plugin or crate that registered a command, if known
+    ///
+    /// See [`with_clicommand_owner`](CliCommandsRegisterExt::with_clicommand_owner).
+    pub fn command_owner(&self, name: &str) -> Option<&str> {
+        self.commands.get(name)?.owner.as_deref()
+    }
+    /// Iterate over the names of all commands registered by the given owner
+    pub fn iter_owned_by<'a>(&'a self, owner: &'a str) -> impl Iterator<Item = &'a str> {
+        self.commands
+            .iter()
+            .filter(move |(_, cmd)| cmd.owner.as_deref() == Some(owner))
+            .map(|(name, _)| name.as_str())
+    }
+    /// Iterate over the names of all commands in the given group
+    ///
+    /// See [`with_clicommand_group`](CliCommandsRegisterExt::with_clicommand_group).
+    pub fn iter_group<'a>(&'a self, group: &'a str) -> impl Iterator<Item = &'a str> {
+        self.commands
+            .iter()
+            .filter(move |(_, cmd)| cmd.groups.iter().any(|g| g == group))
+            .map(|(name, _)| name.as_str())
+    }
+    /// Get the names of all groups that have commands, sorted
+    pub fn groups(&self) -> Vec<&str> {
+        let mut groups: Vec<&str> = self
+            .commands
+            .values()
+            .flat_map(|cmd| cmd.groups.iter().map(|g| g.as_str()))
+            .collect();
+        groups.sort_unstable();
+        groups.dedup();
+        groups
+    }
+    /// The signature of a command that accepts args, if it was registered with one
+    ///
+    /// Recovered from its usage string, as set by
+    /// [`register_clicommand_typed`](CliCommandsRegisterExt::register_clicommand_typed).
+    pub(crate) fn command_signature(&self, name: &str) -> Option<CliSignature> {
+        let cmd = self.commands.get(name)?;
+        cmd.args.as_ref()?;
+        let params = cmd.usage.as_deref()?.strip_prefix(name)?;
+        params.trim().parse().ok()
+    }
+    /// Whether a command may start a job while another of its jobs is running
+    ///
+    /// See [`set_clicommand_concurrency`](CliCommandsRegisterExt::set_clicommand_concurrency).
+    pub fn concurrency(&self, name: &str) -> jobs::CliConcurrency {
+        self.commands
+            .get(name)
+            .map(|cmd| cmd.concurrency)
+            .unwrap_or_default()
+    }
+    /// Get the category a command is listed under, in `help` and completion
+    ///
+    /// That is its first group (see
+    /// [`with_clicommand_group`](CliCommandsRegisterExt::with_clicommand_group)),
+    /// or else its [namespace](crate::namespace). Other commands have no category.
+    pub fn command_category<'a>(&'a self, name: &'a str) -> Option<&'a str> {
+        let group = self
+            .commands
+            .get(name)
+            .and_then(|cmd| cmd.groups.first())
+            .map(|group| group.as_str());
+        group.or_else(|| {
+            name.split_once(namespace::NAMESPACE_SEPARATOR)
+                .map(|(namespace, _)| namespace)
+        })
+    }
+    /// Sort names of commands into their categories (see [`command_category`](Self::command_category))
+    ///
+    /// The names without a category come first, then the categories in
+    /// alphabetical order. The names are sorted within each category.
+    pub fn group_by_category<'a>(
+        &'a self,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Vec<(Option<&'a str>, Vec<&'a str>)> {
+        let mut names: Vec<(Option<&str>, &str)> = names
+            .into_iter()
+            .map(|name| (self.command_category(name), name))
+            .collect();
+        names.sort_unstable();
+        let mut categories: Vec<(Option<&str>, Vec<&str>)> = Vec::new();
+        for (category, name) in names {
+            match categories.last_mut() {
+                Some((last, names)) if *last == category => names.push(name),
+                _ => categories.push((category, vec![name])),
+            }
+        }
+        categories
+    }
+    /// Enable all the commands in a group
+    ///
+    /// See [`set_enabled`](Self::set_enabled). Returns how many commands
+    /// are in the group.
+    pub fn enable_group(&mut self, group: &str) -> usize {
+        self.set_group_enabled(group, true)
+    }
+    /// Disable all the commands in a group, without unregistering them
+    ///
+    /// See [`set_enabled`](Self::set_enabled). Returns how many commands
+    /// are in the group.
+    pub fn disable_group(&mut self, group: &str) -> usize {
+        self.set_group_enabled(group, false)
+    }
+    fn set_group_enabled(&mut self, group: &str, enabled: bool) -> usize {
+        let mut count = 0;
+        for cmd in self.commands.values_mut() {
+            if cmd.groups.iter().any(|g| g == group) {
+                cmd.enabled = enabled;
+                count += 1;
+            }
+        }
+        count
+    }
+    /// Unregister all the commands in a group
+    ///
+    /// Returns the names of the removed commands. Like
+    /// [`unregister_clicommand`](CliCommandsRegisterExt::unregister_clicommand),
+    /// their systems and [`CliLocal`](crate::local::CliLocal) state are freed,
+    /// but at the end of the frame (requires [`CliPlugin`]).
+    pub fn remove_group(&mut self, group: &str) -> Vec<String> {
+        let names: Vec<String> = self.iter_group(group).map(str::to_owned).collect();
+        for name in &names {
+            if let Some(cmd) = self.commands.remove(name) {
+                self.unused_systems.extend(cmd.system_entities());
+            }
+        }
+        self.removed_names.extend(names.iter().cloned());
+        names
+    }
+    /// Iterate over the names of all commands registered in the given namespace
+    pub fn iter_namespace<'a>(&'a self, namespace: &'a str) -> impl Iterator<Item = &'a str> {
+        self.commands
+            .iter()
+            .filter(move |(_, cmd)| cmd.namespace.as_deref() == Some(namespace))
+            .map(|(name, _)| name.as_str())
+    }
+    #[allow(clippy::result_unit_err)]
     pub fn rename_command(&mut self, old_name: &str, new_name: &str) -> Result<(), ()> {
         if let Some(cmd) = self.commands.remove(old_name) {
             self.commands.insert(new_name.to_owned(), cmd);
@@ -220,3 +2635,108 @@ impl CliCommands {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Resource, Default)]
+    struct Ran(Vec<&'static str>);
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.init_resource::<Ran>();
+        app
+    }
+
+    fn system_entity(world: &World, name: &str) -> Entity {
+        let cmd = &world.resource::<CliCommands>().commands[name];
+        cmd.noargs.unwrap().entity()
+    }
+
+    #[test]
+    fn unregister_frees_systems() {
+        let mut app = app();
+        app.register_clicommand_noargs("cmd", |mut ran: ResMut<Ran>| ran.0.push("cmd"));
+        let entity = system_entity(app.world(), "cmd");
+
+        app.unregister_clicommand("cmd");
+        assert!(app.world().get_entity(entity).is_err());
+    }
+
+    #[test]
+    fn reregistering_frees_the_replaced_system() {
+        let mut app = app();
+        app.register_clicommand_noargs("cmd", |mut ran: ResMut<Ran>| ran.0.push("old"));
+        let old = system_entity(app.world(), "cmd");
+
+        app.register_clicommand_noargs("cmd", |mut ran: ResMut<Ran>| ran.0.push("new"));
+        app.run_cli("cmd");
+        assert_eq!(app.world().resource::<Ran>().0, ["new"]);
+        assert!(app.world().get_entity(old).is_err());
+    }
+
+    #[test]
+    fn remove_group_frees_systems_at_the_end_of_the_frame() {
+        let mut app = app();
+        app.with_clicommand_group("debug", |app| {
+            app.register_clicommand_noargs("draw", |mut ran: ResMut<Ran>| ran.0.push("draw"));
+        });
+        let entity = system_entity(app.world(), "draw");
+
+        let removed = app
+            .world_mut()
+            .resource_mut::<CliCommands>()
+            .remove_group("debug");
+        assert_eq!(removed, ["draw"]);
+        assert!(!app
+            .world()
+            .resource::<CliCommands>()
+            .command_available("draw"));
+        assert!(app.world().get_entity(entity).is_ok());
+
+        app.update();
+        assert!(app.world().get_entity(entity).is_err());
+    }
+
+    #[test]
+    fn commands_by_category() {
+        use crate::output::CliCommandsOutputExt;
+
+        let mut app = app();
+        let noop = |_: ResMut<Ran>| {};
+        app.with_clicommand_group("debug", |app| {
+            app.register_clicommand_noargs("draw", noop);
+            app.register_clicommand_noargs("net.lag", noop);
+        });
+        app.register_clicommand_noargs("net.ping", noop);
+        app.register_clicommand_noargs("spawn", noop);
+
+        let clicommands = app.world().resource::<CliCommands>();
+        assert_eq!(clicommands.command_category("net.lag"), Some("debug"));
+        assert_eq!(clicommands.command_category("net.ping"), Some("net"));
+        assert_eq!(clicommands.command_category("spawn"), None);
+        assert_eq!(
+            clicommands.group_by_category(["net.ping", "spawn", "net.lag", "draw"]),
+            [
+                (None, vec!["spawn"]),
+                (Some("debug"), vec!["draw", "net.lag"]),
+                (Some("net"), vec!["net.ping"]),
+            ]
+        );
+
+        let output = app.run_cli_captured("help debug");
+        let lines: Vec<&str> = output.iter().map(|line| line.text.trim_end()).collect();
+        assert_eq!(
+            lines,
+            [
+                "COMMAND    DESCRIPTION",
+                "---------  -----------",
+                "[debug]",
+                "  draw",
+                "  net.lag"
+            ]
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Per-command scratch state, persisted between invocations.
+//!
+//! Stateful commands (such as a `profile start` / `profile stop` pair)
+//! often need to remember something from one run to the next. Instead of
+//! defining a resource for every such command, they can use [`CliLocal`].
+
+use std::any::{Any, TypeId};
+use std::marker::PhantomData;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+/// The "console commands" that are currently running
+///
+/// Commands can run other commands (for example, templates), so this is a stack.
+#[derive(Resource, Debug, Default)]
+pub struct CliCurrentCommand {
+    stack: Vec<String>,
+}
+
+impl CliCurrentCommand {
+    /// The name of the innermost command that is currently running, if any
+    pub fn name(&self) -> Option<&str> {
+        self.stack.last().map(|s| s.as_str())
+    }
+    /// How many commands are running, nested inside each other
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+}
+
+/// Storage for the values of all [`CliLocal`]s, keyed by command name
+#[derive(Resource, Default)]
+pub struct CliLocals {
+    values: HashMap<String, HashMap<TypeId, Box<dyn Any + Send + Sync>>>,
+}
+
+impl CliLocals {
+    /// Reset all the scratch state of the given command
+    pub fn clear_command(&mut self, name: &str) {
+        self.values.remove(name);
+    }
+    /// Reset the scratch state of all commands
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+}
+
+/// System param for scratch state that belongs to the running "console command"
+///
+/// Like Bevy's `Local`, but the value is keyed by the name of the command,
+/// rather than by the system. So if the same system is registered as multiple
+/// commands, each of them gets its own value. The values persist between
+/// invocations, until the command is unregistered.
+///
+/// Panics if accessed from a system that is not being run as a command.
+///
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use bevy::utils::Instant;
+/// # use iyes_cli::local::CliLocal;
+/// fn stopwatch(mut started: CliLocal<Option<Instant>>) {
+///     match started.get_mut().take() {
+///         Some(t) => info!("Elapsed: {:?}", t.elapsed()),
+///         None => *started.get_mut() = Some(Instant::now()),
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct CliLocal<'w, T: Default + Send + Sync + 'static> {
+    locals: ResMut<'w, CliLocals>,
+    current: Res<'w, CliCurrentCommand>,
+    marker: PhantomData<T>,
+}
+
+impl<T: Default + Send + Sync + 'static> CliLocal<'_, T> {
+    /// The name of the command this value belongs to
+    pub fn command_name(&self) -> &str {
+        self.current
+            .name()
+            .expect("CliLocal used outside of a CliCommand!")
+    }
+    /// Get the value, if it has been set
+    pub fn get(&self) -> Option<&T> {
+        self.locals
+            .values
+            .get(self.command_name())
+            .and_then(|values| values.get(&TypeId::of::<T>()))
+            .and_then(|value| value.downcast_ref())
+    }
+    /// Get the value, initializing it with `T::default()` if it has not been set
+    pub fn get_mut(&mut self) -> &mut T {
+        let name = self
+            .current
+            .name()
+            .expect("CliLocal used outside of a CliCommand!");
+        let value = self
+            .locals
+            .values
+            .entry_ref(name)
+            .or_default()
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(T::default()));
+        value
+            .downcast_mut()
+            .expect("CliLocal value has the wrong type!")
+    }
+    /// Remove the value, returning it if it had been set
+    pub fn take(&mut self) -> Option<T> {
+        let name = self
+            .current
+            .name()
+            .expect("CliLocal used outside of a CliCommand!");
+        self.locals
+            .values
+            .get_mut(name)
+            .and_then(|values| values.remove(&TypeId::of::<T>()))
+            .and_then(|value| value.downcast().ok())
+            .map(|value| *value)
+    }
+}
+
+/// Run `f` with `name` marked as the currently running command
+pub(crate) fn with_current_command<R>(
+    world: &mut World,
+    name: &str,
+    f: impl FnOnce(&mut World) -> R,
+) -> R {
+    world.init_resource::<CliLocals>();
+    world.init_resource::<crate::output::CliOutputLog>();
+    world
+        .get_resource_or_insert_with(CliCurrentCommand::default)
+        .stack
+        .push(name.to_owned());
+    let r = f(world);
+    world.resource_mut::<CliCurrentCommand>().stack.pop();
+    r
+}
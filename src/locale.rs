@@ -0,0 +1,220 @@
+//! Loading translations of the console from Fluent-style sources.
+//!
+//! [`CliLocale`] holds messages for any number of languages, and feeds the
+//! messages of the current language to the [`CliLocalizer`], so they are
+//! used for the output of [`outln!`](crate::outln), the descriptions and
+//! usage strings shown by `help`, and the errors reported for commands that
+//! cannot run (see [`localize`](crate::localize) for the keys). Keys missing
+//! from the current language are looked up in the fallback language, and
+//! then the built-in English text is used.
+//!
+//! ```rust,ignore
+//! app.add_plugins(CliPlugin)
+//!     .insert_resource(CliLocale::new("de", "en"));
+//! app.world_mut().resource_mut::<CliLocale>()
+//!     .add_source("de", include_str!("../locale/de/console.ftl"));
+//! ```
+//!
+//! The sources use a subset of the [Fluent](https://projectfluent.org)
+//! syntax: `key = message` lines, indented lines continuing the message
+//! above, attributes like `.description = ...` (stored as `key.description`),
+//! and `#` comments:
+//!
+//! ```text
+//! # console.ftl
+//! error.not_found = Befehl { $name } nicht gefunden
+//! error.on_cooldown = { $name } ist noch { $remaining }s gesperrt
+//!
+//! command.spawn =
+//!     .description = Eine Entität erzeugen
+//!     .usage = spawn <x> <y>
+//! ```
+//!
+//! Selectors are not supported; use plural selectors like
+//! `{count|one|other}` instead (see [`localize`](crate::localize)).
+//!
+//! Players can change the language with the `language [<language>]` command.
+//! Changes take effect at the start of the next frame.
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::localize::CliLocalizer;
+use crate::output::CliOutput;
+use crate::{failln, outln, CliCommandsRegisterExt};
+
+/// Translations of the console, by language
+///
+/// Requires [`CliPlugin`](crate::CliPlugin). The default language and
+/// fallback language are both `en`.
+#[derive(Resource, Debug, Clone)]
+pub struct CliLocale {
+    language: String,
+    fallback: String,
+    languages: HashMap<String, HashMap<String, String>>,
+}
+
+impl Default for CliLocale {
+    fn default() -> Self {
+        CliLocale::new("en", "en")
+    }
+}
+
+impl CliLocale {
+    pub fn new(language: &str, fallback: &str) -> Self {
+        CliLocale {
+            language: language.to_owned(),
+            fallback: fallback.to_owned(),
+            languages: HashMap::default(),
+        }
+    }
+    /// The current language
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+    pub fn set_language(&mut self, language: &str) {
+        self.language = language.to_owned();
+    }
+    /// The language used for keys missing from the current language
+    pub fn fallback(&self) -> &str {
+        &self.fallback
+    }
+    pub fn set_fallback(&mut self, language: &str) {
+        self.fallback = language.to_owned();
+    }
+    /// Iterate over the languages that have messages
+    pub fn languages(&self) -> impl Iterator<Item = &str> {
+        self.languages.keys().map(|s| s.as_str())
+    }
+    /// Set the message for a key, in a language
+    pub fn set(&mut self, language: &str, key: &str, message: &str) {
+        self.languages
+            .entry(language.to_owned())
+            .or_default()
+            .insert(key.to_owned(), message.to_owned());
+    }
+    /// Add the messages of a Fluent-style source to a language
+    ///
+    /// Lines that cannot be parsed are skipped, with a warning. Returns the
+    /// number of messages added.
+    pub fn add_source(&mut self, language: &str, source: &str) -> usize {
+        let messages = self.languages.entry(language.to_owned()).or_default();
+        let mut count = 0;
+        // The key of the message, and the key of the last value, to continue
+        let mut message: Option<String> = None;
+        let mut last: Option<String> = None;
+        for (i, line) in source.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let indented = line.starts_with(char::is_whitespace);
+            if indented && !trimmed.starts_with('.') {
+                match last.as_ref().and_then(|key| messages.get_mut(key)) {
+                    Some(value) => {
+                        if !value.is_empty() {
+                            value.push('\n');
+                        }
+                        value.push_str(trimmed);
+                    }
+                    None => warn!("CliLocale source line {}: nothing to continue", i + 1),
+                }
+                continue;
+            }
+            let Some((key, value)) = trimmed.split_once('=') else {
+                warn!("CliLocale source line {}: expected `key = message`", i + 1);
+                continue;
+            };
+            let key = key.trim();
+            let key = match (indented, &message) {
+                (false, _) => {
+                    message = Some(key.to_owned());
+                    key.to_owned()
+                }
+                (true, Some(message)) => format!("{}{}", message, key),
+                (true, None) => {
+                    warn!(
+                        "CliLocale source line {}: attribute without a message",
+                        i + 1
+                    );
+                    continue;
+                }
+            };
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                warn!("CliLocale source line {}: invalid key {:?}", i + 1, key);
+                last = None;
+                continue;
+            }
+            messages.insert(key.clone(), value.trim().to_owned());
+            last = Some(key);
+            count += 1;
+        }
+        count
+    }
+    /// Get the message for a key, in the current or fallback language
+    pub fn get(&self, key: &str) -> Option<&str> {
+        [&self.language, &self.fallback]
+            .into_iter()
+            .filter_map(|language| self.languages.get(language)?.get(key))
+            .find(|message| !message.is_empty())
+            .map(|s| s.as_str())
+    }
+    /// All the messages of the current language, with the fallback language
+    /// filling in missing keys
+    fn messages(&self) -> HashMap<String, String> {
+        let mut messages = HashMap::default();
+        for language in [&self.fallback, &self.language] {
+            let Some(language) = self.languages.get(language) else {
+                continue;
+            };
+            for (key, message) in language {
+                // Messages with only attributes have no text
+                if !message.is_empty() {
+                    messages.insert(key.clone(), message.clone());
+                }
+            }
+        }
+        messages
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliLocale>();
+    app.add_systems(First, apply_locale.run_if(resource_changed::<CliLocale>));
+    app.register_clicommand_args("language", language)
+        .set_clicommand_description("language", "Show or change the language of the console")
+        .set_clicommand_usage("language", "language [<language>]");
+}
+
+fn apply_locale(locale: Res<CliLocale>, mut localizer: ResMut<CliLocalizer>) {
+    localizer.set_locale(locale.messages());
+}
+
+/// `language [<language>]`: show or change the language of the console
+fn language(In(args): In<Vec<String>>, mut locale: ResMut<CliLocale>, mut output: CliOutput) {
+    match args.as_slice() {
+        [] => {
+            let mut languages: Vec<&str> = locale.languages().collect();
+            languages.sort_unstable();
+            outln!(
+                output,
+                "language.current",
+                "Language: {language} (available: {available})",
+                language = locale.language(),
+                available = languages.join(", "),
+            );
+        }
+        [language] if locale.languages().any(|known| known == language.as_str()) => {
+            locale.set_language(language);
+        }
+        [language] => {
+            failln!(
+                output,
+                "language.unknown",
+                "Unknown language {language}!",
+                language = language,
+            );
+        }
+        _ => output.fail("Usage: language [<language>]"),
+    }
+}
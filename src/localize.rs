@@ -0,0 +1,262 @@
+//! Translating the output of "console commands".
+//!
+//! Output written with the [`outln!`](crate::outln) and
+//! [`failln!`](crate::failln) macros is identified by a key, and looked up
+//! in the [`CliLocalizer`] resource. If there is no translation for the key,
+//! the default text given to the macro is used.
+//!
+//! ```rust,ignore
+//! fn despawn_all(mut commands: Commands, query: Query<Entity>, mut output: CliOutput) {
+//!     let count = query.iter().count();
+//!     // ...
+//!     outln!(output, "despawn.done", "Despawned {count} {count|entity|entities}", count = count);
+//! }
+//!
+//! app.world_mut().resource_mut::<CliLocalizer>()
+//!     .set("despawn.done", "{count} {count|Entität|Entitäten} entfernt");
+//! ```
+//!
+//! Messages can contain `{name}` placeholders, which are replaced with the
+//! value of the arg with that name, and plural selectors like
+//! `{count|one|other}`, which are replaced with `one` if the arg is `1`, and
+//! with `other` otherwise. Placeholders may also be written Fluent-style,
+//! like `{ $name }`. `{{` and `}}` are literal braces.
+//!
+//! The descriptions and usage strings of commands, and the errors reported
+//! for commands that cannot run, are looked up the same way:
+//!
+//!  - `command.<name>.description` and `command.<name>.usage`, shown by `help`
+//!  - `error.<kind>`, like `error.not_found` or `error.on_cooldown`, with the
+//!    fields of the [`CliError`] as args (see [`CliLocalizer::error`])
+//!
+//! With the `locale` cargo feature, whole languages can be loaded from
+//! Fluent-style sources (see [`locale`](crate::locale)).
+
+use std::fmt::Display;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::error::CliError;
+use crate::permissions::CliPermission;
+
+/// Translations of the output of "console commands", by key
+///
+/// Messages set here take precedence over the messages of the current
+/// language of the [`CliLocale`](crate::locale::CliLocale), if any.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct CliLocalizer {
+    messages: HashMap<String, String>,
+    /// The messages of the current language, from the `CliLocale`
+    locale: HashMap<String, String>,
+}
+
+impl CliLocalizer {
+    /// Set the message for a key
+    pub fn set(&mut self, key: &str, message: &str) {
+        self.messages.insert(key.to_owned(), message.to_owned());
+    }
+    /// Remove the message for a key, so the default text is used
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.messages.remove(key)
+    }
+    /// Remove all messages
+    pub fn clear(&mut self) {
+        self.messages.clear();
+    }
+    /// Get the message for a key
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.messages
+            .get(key)
+            .or_else(|| self.locale.get(key))
+            .map(|s| s.as_str())
+    }
+    /// Look up the message for a key (or use `default`) and fill in the args
+    pub fn format(&self, key: &str, default: &str, args: &[(&str, &dyn Display)]) -> String {
+        format_message(self.get(key).unwrap_or(default), args)
+    }
+    /// Get the description of a command (or use `default`)
+    pub fn description<'a>(&'a self, name: &str, default: Option<&'a str>) -> Option<&'a str> {
+        self.get(&format!("command.{}.description", name))
+            .or(default)
+    }
+    /// Get the usage string of a command (or use `default`)
+    pub fn usage<'a>(&'a self, name: &str, default: Option<&'a str>) -> Option<&'a str> {
+        self.get(&format!("command.{}.usage", name)).or(default)
+    }
+    /// Describe an error, looking up the message for its kind
+    ///
+    /// The key is `error.` followed by the kind of error in snake case, like
+    /// `error.not_found`. The args are named after the fields of the error;
+    /// durations are in seconds. If there is no message for the key, the
+    /// error is described in English.
+    pub fn error(&self, error: &CliError) -> String {
+        let secs = |duration: &std::time::Duration| format!("{:.1}", duration.as_secs_f32());
+        let (key, args): (&str, Vec<(&str, String)>) = match error {
+            CliError::Parse(e) => ("error.parse", vec![("message", e.to_string())]),
+            CliError::NotFound(name) => ("error.not_found", vec![("name", name.clone())]),
+            CliError::Ambiguous { name, candidates } => (
+                "error.ambiguous",
+                vec![
+                    ("name", name.clone()),
+                    ("candidates", candidates.join(", ")),
+                ],
+            ),
+            CliError::ConditionNotMet(name) => {
+                ("error.condition_not_met", vec![("name", name.clone())])
+            }
+            CliError::NotPermitted {
+                name,
+                permission: CliPermission::Cheat,
+            } => ("error.needs_cheats", vec![("name", name.clone())]),
+            CliError::NotPermitted { name, permission } => (
+                "error.not_permitted",
+                vec![
+                    ("name", name.clone()),
+                    ("permission", permission.to_string()),
+                ],
+            ),
+            CliError::MissingPlugin { name, plugins } => (
+                "error.missing_plugin",
+                vec![("name", name.clone()), ("plugins", plugins.join(", "))],
+            ),
+            CliError::NeedsConfirmation { name, timeout } => (
+                "error.needs_confirmation",
+                vec![("name", name.clone()), ("timeout", secs(timeout))],
+            ),
+            CliError::OnCooldown { name, remaining } => (
+                "error.on_cooldown",
+                vec![("name", name.clone()), ("remaining", secs(remaining))],
+            ),
+            CliError::Intercepted { name, hint } => (
+                "error.intercepted",
+                vec![("name", name.clone()), ("hint", hint.clone())],
+            ),
+            CliError::Selector { selector, message } => (
+                "error.selector",
+                vec![("selector", selector.clone()), ("message", message.clone())],
+            ),
+            CliError::Mention { mention, message } => (
+                "error.mention",
+                vec![("mention", mention.clone()), ("message", message.clone())],
+            ),
+            CliError::QuotaExceeded { source, remaining } => (
+                "error.quota_exceeded",
+                vec![
+                    ("source", source.clone()),
+                    ("remaining", remaining.as_secs_f32().ceil().to_string()),
+                ],
+            ),
+            CliError::InvalidArgs { name, message, .. } => (
+                "error.invalid_args",
+                vec![("name", name.clone()), ("message", message.clone())],
+            ),
+            CliError::NoRoute(tag) => ("error.no_route", vec![("tag", tag.clone())]),
+            CliError::SystemFailed { name, message } => (
+                "error.system_failed",
+                vec![("name", name.clone()), ("message", message.clone())],
+            ),
+            CliError::Failed(name) => ("error.failed", vec![("name", name.clone())]),
+        };
+        let Some(message) = self.get(key) else {
+            return error.to_string();
+        };
+        let args: Vec<(&str, &dyn Display)> = args
+            .iter()
+            .map(|(name, value)| (*name, value as &dyn Display))
+            .collect();
+        format_message(message, &args)
+    }
+    /// Replace the messages of the current language
+    #[cfg(feature = "locale")]
+    pub(crate) fn set_locale(&mut self, messages: HashMap<String, String>) {
+        self.locale = messages;
+    }
+}
+
+/// Fill in the placeholders and plural selectors of a message
+///
+/// Unknown placeholders are left as they are.
+pub fn format_message(message: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut out = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(i) = rest.find(['{', '}']) {
+        out.push_str(&rest[..i]);
+        let brace = &rest[i..i + 1];
+        rest = &rest[i + 1..];
+        if rest.starts_with(brace) {
+            // `{{` or `}}`
+            out.push_str(brace);
+            rest = &rest[1..];
+            continue;
+        }
+        let end = match (brace, rest.find('}')) {
+            ("{", Some(end)) => end,
+            _ => {
+                out.push_str(brace);
+                continue;
+            }
+        };
+        let placeholder = &rest[..end];
+        rest = &rest[end + 1..];
+        let mut parts = placeholder.split('|');
+        // `{ $name }`, like in Fluent, is the same as `{name}`
+        let name = parts
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .trim_start_matches('$');
+        let value = args
+            .iter()
+            .find(|(arg, _)| *arg == name)
+            .map(|(_, value)| value.to_string());
+        match (value, parts.next(), parts.next()) {
+            (Some(value), None, _) => out.push_str(&value),
+            (Some(value), Some(one), Some(other)) => {
+                let is_one = value.trim().parse::<f64>().is_ok_and(|n| n == 1.0);
+                out.push_str(if is_one { one } else { other });
+            }
+            _ => {
+                out.push('{');
+                out.push_str(placeholder);
+                out.push('}');
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Write localized text to a [`CliOutput`](crate::output::CliOutput)
+///
+/// Takes the output, the key of the message, the default text, and any
+/// number of `name = value` args. See the [`localize`](crate::localize) module.
+///
+/// ```rust,ignore
+/// outln!(output, "preset.saved", "Saved preset {name}", name = name);
+/// ```
+#[macro_export]
+macro_rules! outln {
+    ($output:expr, $key:expr, $default:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $output.println_localized(
+            $key,
+            $default,
+            &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),*],
+        )
+    };
+}
+
+/// Write localized text to a [`CliOutput`](crate::output::CliOutput), and
+/// mark the command as failed
+///
+/// Takes the same arguments as [`outln!`](crate::outln).
+#[macro_export]
+macro_rules! failln {
+    ($output:expr, $key:expr, $default:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $output.fail_localized(
+            $key,
+            $default,
+            &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),*],
+        )
+    };
+}
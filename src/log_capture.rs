@@ -0,0 +1,216 @@
+//! Capturing log messages, for showing them in consoles.
+//!
+//! Requires the `log-capture` cargo feature. Add the layer to Bevy's
+//! `LogPlugin`, and the records of `info!`, `warn!`, `error!`, etc. are
+//! kept in the [`CliLogBuffer`] resource:
+//!
+//! ```rust,ignore
+//! app.add_plugins(DefaultPlugins.set(LogPlugin {
+//!     custom_layer: cli_log_layer,
+//!     ..default()
+//! }));
+//! ```
+//!
+//! Consoles can show the records together with the output of commands,
+//! like a classic quake console. Every record has a sequence number, so
+//! each frontend can keep track of what it has already shown (see
+//! [`CliLogBuffer::iter_since`]).
+//!
+//! The built-in `log [level] [target]` command prints the recent records,
+//! filtered by level and target.
+//!
+//! The output of commands is not captured, even though it is logged if
+//! [`echo_to_log`](crate::output::CliOutputLog::echo_to_log) is enabled.
+
+use std::collections::VecDeque;
+use std::fmt::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use bevy::log::tracing_subscriber::layer::Context;
+use bevy::log::tracing_subscriber::Layer;
+use bevy::log::{BoxedLayer, Level};
+use bevy::prelude::*;
+use bevy::utils::tracing::field::{Field, Visit};
+use bevy::utils::tracing::{Event, Subscriber};
+
+use crate::output::{cli_error, cli_println_severity, CliSeverity};
+use crate::status::cli_fail;
+use crate::CliCommandsRegisterExt;
+
+/// A captured log message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliLogRecord {
+    /// Increases by one for every record, starting at 1
+    pub seq: u64,
+    pub level: Level,
+    /// The module path (or custom target) the message was logged from
+    pub target: String,
+    pub message: String,
+}
+
+impl CliLogRecord {
+    /// The severity to show the record with, in console output
+    pub fn severity(&self) -> CliSeverity {
+        match self.level {
+            Level::ERROR => CliSeverity::Error,
+            Level::WARN => CliSeverity::Warn,
+            _ => CliSeverity::Info,
+        }
+    }
+}
+
+#[derive(Default)]
+struct CliLogShared {
+    pending: Vec<CliLogRecord>,
+    next_seq: u64,
+}
+
+/// The most recent captured log messages
+///
+/// Requires [`cli_log_layer`] to be added to the `LogPlugin`.
+#[derive(Resource)]
+pub struct CliLogBuffer {
+    shared: Arc<Mutex<CliLogShared>>,
+    records: VecDeque<CliLogRecord>,
+    /// How many records to keep; older records are dropped
+    pub capacity: usize,
+}
+
+impl CliLogBuffer {
+    /// Iterate over the records, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &CliLogRecord> {
+        self.records.iter()
+    }
+    /// Iterate over the records with a sequence number greater than `seq`
+    pub fn iter_since(&self, seq: u64) -> impl Iterator<Item = &CliLogRecord> {
+        self.records.iter().filter(move |record| record.seq > seq)
+    }
+    /// Iterate over the records at least as important as `level`, whose
+    /// target starts with `target`
+    pub fn iter_filtered<'a>(
+        &'a self,
+        level: Level,
+        target: &'a str,
+    ) -> impl Iterator<Item = &'a CliLogRecord> {
+        self.records
+            .iter()
+            .filter(move |record| record.level <= level && record.target.starts_with(target))
+    }
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+/// The tracing layer that captures log messages into the [`CliLogBuffer`]
+struct CliLogLayer {
+    shared: Arc<Mutex<CliLogShared>>,
+}
+
+impl<S: Subscriber> Layer<S> for CliLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if metadata.target() == "iyes_cli::output" {
+            return;
+        }
+        let mut visitor = CliLogVisitor::default();
+        event.record(&mut visitor);
+        let Ok(mut shared) = self.shared.lock() else {
+            return;
+        };
+        shared.next_seq += 1;
+        let seq = shared.next_seq;
+        shared.pending.push(CliLogRecord {
+            seq,
+            level: *metadata.level(),
+            target: metadata.target().to_owned(),
+            message: visitor.message + &visitor.fields,
+        });
+    }
+}
+
+/// Formats the message of a log event, followed by any other fields
+#[derive(Default)]
+struct CliLogVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for CliLogVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// Create the log capturing layer, for `LogPlugin::custom_layer`
+///
+/// Also adds the [`CliLogBuffer`] resource and the `log` command.
+pub fn cli_log_layer(app: &mut App) -> Option<BoxedLayer> {
+    let shared = Arc::new(Mutex::new(CliLogShared::default()));
+    app.insert_resource(CliLogBuffer {
+        shared: shared.clone(),
+        records: VecDeque::new(),
+        capacity: 1000,
+    });
+    app.add_systems(First, collect_records);
+    app.register_clicommand_args("log", log)
+        .set_clicommand_description("log", "Print recent log messages")
+        .set_clicommand_usage("log", "log [error|warn|info|debug|trace] [target]");
+    Some(Box::new(CliLogLayer { shared }))
+}
+
+fn collect_records(mut buffer: ResMut<CliLogBuffer>) {
+    let pending = match buffer.shared.lock() {
+        Ok(mut shared) => std::mem::take(&mut shared.pending),
+        Err(_) => return,
+    };
+    buffer.records.extend(pending);
+    while buffer.records.len() > buffer.capacity {
+        buffer.records.pop_front();
+    }
+}
+
+/// `log [level] [target]`: print recent log messages
+fn log(In(args): In<Vec<String>>, world: &mut World) {
+    let (level, target) = match args.as_slice() {
+        [] => (Level::INFO, ""),
+        [level] => match level.parse::<Level>() {
+            Ok(level) => (level, ""),
+            // A single arg that is not a level is a target
+            Err(_) => (Level::TRACE, level.as_str()),
+        },
+        [level, target] => match level.parse::<Level>() {
+            Ok(level) => (level, target.as_str()),
+            Err(_) => {
+                cli_error(world, format!("Invalid log level: {:?}", level));
+                cli_fail(world);
+                return;
+            }
+        },
+        _ => {
+            cli_error(world, "Usage: log [error|warn|info|debug|trace] [target]");
+            cli_fail(world);
+            return;
+        }
+    };
+    let lines: Vec<(CliSeverity, String)> = world
+        .resource::<CliLogBuffer>()
+        .iter_filtered(level, target)
+        .map(|record| {
+            let text = format!("{} {}: {}", record.level, record.target, record.message);
+            (record.severity(), text)
+        })
+        .collect();
+    for (severity, text) in lines {
+        cli_println_severity(world, severity, text);
+    }
+}
@@ -0,0 +1,181 @@
+//! A description of all the registered "console commands", for tools.
+//!
+//! [`CliCommands::export_manifest`] describes every command (its name,
+//! usage, description, permission, ...) and every alias. Use it to
+//! generate documentation, or to drive a command palette in an external
+//! editor. With the `serde` cargo feature, the manifest can be serialized
+//! in any format; [`CliManifest::to_json`] is always available.
+//!
+//! The built-in `commands [--json]` command prints the manifest (with the
+//! `builtin-commands` cargo feature).
+
+#[cfg(feature = "builtin-commands")]
+use bevy::prelude::*;
+
+#[cfg(feature = "builtin-commands")]
+use crate::output::CliOutput;
+use crate::table::push_json_string;
+#[cfg(feature = "builtin-commands")]
+use crate::table::{CliAlign, CliTable};
+use crate::CliCommands;
+
+/// The registered commands and aliases
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CliManifest {
+    /// The enabled commands, sorted by name
+    pub commands: Vec<CliManifestCommand>,
+    /// The aliases, sorted by name
+    pub aliases: Vec<CliManifestAlias>,
+}
+
+/// A command in a [`CliManifest`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CliManifestCommand {
+    pub name: String,
+    pub namespace: Option<String>,
+    pub description: Option<String>,
+    /// Usage string showing the accepted args (the signature, for typed commands)
+    pub usage: Option<String>,
+    /// The [`CliPermission`](crate::permissions::CliPermission) level, like `cheat`
+    pub permission: String,
+    pub accepts_args: bool,
+    pub accepts_noargs: bool,
+    pub dangerous: bool,
+    pub owner: Option<String>,
+    pub groups: Vec<String>,
+}
+
+/// An alias in a [`CliManifest`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CliManifestAlias {
+    pub name: String,
+    /// The command string the alias runs
+    pub expansion: String,
+}
+
+impl CliCommands {
+    /// Describe all the enabled commands and the aliases
+    pub fn export_manifest(&self) -> CliManifest {
+        let mut commands: Vec<CliManifestCommand> = self
+            .iter_names()
+            .filter_map(|name| self.get_info(name))
+            .filter(|info| info.enabled)
+            .map(|info| CliManifestCommand {
+                name: info.name.to_owned(),
+                namespace: info.namespace.map(str::to_owned),
+                description: info.description.map(str::to_owned),
+                usage: info.usage.map(str::to_owned),
+                permission: info.permission.to_string(),
+                accepts_args: info.accepts_args,
+                accepts_noargs: info.accepts_noargs,
+                dangerous: info.dangerous,
+                owner: info.owner.map(str::to_owned),
+                groups: info.groups.to_vec(),
+            })
+            .collect();
+        commands.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        let mut aliases: Vec<CliManifestAlias> = self
+            .iter_aliases()
+            .map(|(name, expansion)| CliManifestAlias {
+                name: name.to_owned(),
+                expansion: expansion.to_owned(),
+            })
+            .collect();
+        aliases.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        CliManifest { commands, aliases }
+    }
+}
+
+impl CliManifest {
+    /// Render as a JSON object, with `commands` and `aliases` arrays
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\"commands\":[");
+        for (i, cmd) in self.commands.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push('{');
+            push_json_field(&mut json, "name", Some(&cmd.name));
+            json.push(',');
+            push_json_field(&mut json, "namespace", cmd.namespace.as_deref());
+            json.push(',');
+            push_json_field(&mut json, "description", cmd.description.as_deref());
+            json.push(',');
+            push_json_field(&mut json, "usage", cmd.usage.as_deref());
+            json.push(',');
+            push_json_field(&mut json, "permission", Some(&cmd.permission));
+            json.push_str(&format!(
+                ",\"accepts_args\":{},\"accepts_noargs\":{},\"dangerous\":{},",
+                cmd.accepts_args, cmd.accepts_noargs, cmd.dangerous
+            ));
+            push_json_field(&mut json, "owner", cmd.owner.as_deref());
+            json.push_str(",\"groups\":[");
+            for (i, group) in cmd.groups.iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                push_json_string(&mut json, group);
+            }
+            json.push_str("]}");
+        }
+        json.push_str("],\"aliases\":[");
+        for (i, alias) in self.aliases.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push('{');
+            push_json_field(&mut json, "name", Some(&alias.name));
+            json.push(',');
+            push_json_field(&mut json, "expansion", Some(&alias.expansion));
+            json.push('}');
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+fn push_json_field(json: &mut String, key: &str, value: Option<&str>) {
+    push_json_string(json, key);
+    json.push(':');
+    match value {
+        Some(value) => push_json_string(json, value),
+        None => json.push_str("null"),
+    }
+}
+
+/// `commands [--json]`: describe the registered commands
+#[cfg(feature = "builtin-commands")]
+pub(crate) fn commands(
+    In(args): In<Vec<String>>,
+    clicommands: Res<CliCommands>,
+    mut output: CliOutput,
+) {
+    let json = match args.as_slice() {
+        [] => false,
+        [flag] if flag == "--json" => true,
+        _ => {
+            output.fail("Usage: commands [--json]");
+            return;
+        }
+    };
+    let manifest = clicommands.export_manifest();
+    if json {
+        output.println(manifest.to_json());
+        return;
+    }
+    let mut table = CliTable::new()
+        .with_column("COMMAND", CliAlign::Left)
+        .with_column("PERMISSION", CliAlign::Left)
+        .with_column("DESCRIPTION", CliAlign::Left);
+    for cmd in &manifest.commands {
+        table.add_row([
+            cmd.usage.as_deref().unwrap_or(&cmd.name),
+            cmd.permission.as_str(),
+            cmd.description.as_deref().unwrap_or(""),
+        ]);
+    }
+    output.println(table.to_string());
+}
@@ -0,0 +1,205 @@
+//! Mentioning players in args, like `kick @Bob`, for consoles embedded in chat.
+//!
+//! An arg of the form `@name`, without parentheses (which would make it an
+//! entity [selector](crate::selector)), is a mention. Mentions are resolved
+//! by the game's [`CliMentionResolver`], set with
+//! [`CliMentions::set_resolver`], and replaced with the arg it gives, before
+//! the command runs. A mention that cannot be resolved is an error
+//! ([`CliError::Mention`]). Without a resolver, args starting with `@` are
+//! passed to commands as they are.
+//!
+//! For the common case of players being entities with a marker component
+//! and a [`Name`], use [`CliNameMentions`], which resolves mentions into the
+//! entities (as `42v1`, see [`parse_cli_entity`](crate::selector::parse_cli_entity)):
+//!
+//! ```rust,ignore
+//! world
+//!     .resource_mut::<CliMentions>()
+//!     .set_resolver(CliNameMentions::<Player>::default());
+//! ```
+//!
+//! Games with their own notion of players (like account ids from the
+//! network layer) implement [`CliMentionResolver`] instead. The names it
+//! lists are offered as completions for args starting with `@`, in any
+//! command. To mention a name with spaces, quote it: `@"Player One"`.
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use crate::error::CliError;
+
+/// Resolves the mentions in args into the args passed to commands
+pub trait CliMentionResolver: Send + Sync + 'static {
+    /// Resolve a mentioned name (without the `@`)
+    ///
+    /// Returns why it cannot be resolved, like when nobody has that name.
+    fn resolve(&self, name: &str, world: &mut World) -> Result<String, String>;
+    /// The names that can be mentioned, for completion
+    fn names(&self, world: &World) -> Vec<String>;
+}
+
+/// Resolver of mentions into the entities with a [`Name`] and a marker component
+///
+/// `@Bob` is resolved into the entity with the component `C` named `Bob`.
+pub struct CliNameMentions<C: Component>(PhantomData<fn() -> C>);
+
+impl<C: Component> Default for CliNameMentions<C> {
+    fn default() -> Self {
+        CliNameMentions(PhantomData)
+    }
+}
+
+impl<C: Component> CliMentionResolver for CliNameMentions<C> {
+    fn resolve(&self, name: &str, world: &mut World) -> Result<String, String> {
+        let mut q = world.query_filtered::<(Entity, &Name), With<C>>();
+        q.iter(world)
+            .find(|(_, n)| n.as_str() == name)
+            .map(|(entity, _)| entity.to_string())
+            .ok_or_else(|| format!("nobody is named {:?}", name))
+    }
+    fn names(&self, world: &World) -> Vec<String> {
+        let (Some(marker), Some(name)) = (world.component_id::<C>(), world.component_id::<Name>())
+        else {
+            return vec![];
+        };
+        world
+            .archetypes()
+            .iter()
+            .filter(|archetype| archetype.contains(marker) && archetype.contains(name))
+            .flat_map(|archetype| archetype.entities().iter().map(|entity| entity.id()))
+            .filter_map(|entity| world.get::<Name>(entity))
+            .map(|name| name.as_str().to_owned())
+            .collect()
+    }
+}
+
+/// The resolver of mentions in args
+///
+/// Requires [`CliPlugin`](crate::CliPlugin).
+#[derive(Resource, Default)]
+pub struct CliMentions {
+    resolver: Option<Arc<dyn CliMentionResolver>>,
+}
+
+impl CliMentions {
+    /// Set the resolver of mentions, replacing the previous one
+    pub fn set_resolver(&mut self, resolver: impl CliMentionResolver) {
+        self.resolver = Some(Arc::new(resolver));
+    }
+    /// Stop resolving mentions, passing them to commands as they are
+    pub fn clear_resolver(&mut self) {
+        self.resolver = None;
+    }
+    pub fn has_resolver(&self) -> bool {
+        self.resolver.is_some()
+    }
+    /// The names that can be mentioned, sorted
+    pub fn names(&self, world: &World) -> Vec<String> {
+        let Some(resolver) = &self.resolver else {
+            return vec![];
+        };
+        let mut names = resolver.names(world);
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+}
+
+/// The mentioned name, if an arg is a mention
+pub(crate) fn parse_mention(arg: &str) -> Option<&str> {
+    let name = arg.strip_prefix('@')?;
+    (!name.is_empty() && !name.contains('(')).then_some(name)
+}
+
+/// Replace the mentions in args with what they are resolved into
+pub(crate) fn resolve_args(world: &mut World, args: &mut [String]) -> Result<(), CliError> {
+    let Some(resolver) = world
+        .get_resource::<CliMentions>()
+        .and_then(|mentions| mentions.resolver.clone())
+    else {
+        return Ok(());
+    };
+    for arg in args.iter_mut() {
+        let Some(name) = parse_mention(arg) else {
+            continue;
+        };
+        match resolver.resolve(name, world) {
+            Ok(resolved) => *arg = resolved,
+            Err(message) => {
+                return Err(CliError::Mention {
+                    mention: arg.clone(),
+                    message,
+                })
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::CliCommandsOutputExt;
+    use crate::selector::parse_cli_entity;
+    use crate::{CliCommandsRegisterExt, CliPlugin};
+
+    #[derive(Component)]
+    struct Player;
+
+    #[derive(Resource, Default)]
+    struct Kicked(Vec<Entity>);
+
+    #[test]
+    fn mentions() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.init_resource::<Kicked>();
+        app.register_clicommand_args(
+            "kick",
+            |In(args): In<Vec<String>>, mut kicked: ResMut<Kicked>| {
+                kicked
+                    .0
+                    .extend(args.iter().filter_map(|arg| parse_cli_entity(arg)));
+            },
+        );
+        let bob = app.world_mut().spawn((Player, Name::new("Bob"))).id();
+        let one = app
+            .world_mut()
+            .spawn((Player, Name::new("Player One")))
+            .id();
+        app.world_mut().spawn(Name::new("Bobcat"));
+
+        // Without a resolver, mentions are passed as they are
+        assert!(app.try_run_cli("kick @Bob").is_ok());
+        assert!(app.world().resource::<Kicked>().0.is_empty());
+
+        app.world_mut()
+            .resource_mut::<CliMentions>()
+            .set_resolver(CliNameMentions::<Player>::default());
+        app.try_run_cli("kick @Bob @\"Player One\"").unwrap();
+        assert_eq!(app.world().resource::<Kicked>().0, [bob, one]);
+        assert_eq!(
+            app.try_run_cli("kick @Bobcat").unwrap_err(),
+            CliError::Mention {
+                mention: "@Bobcat".to_owned(),
+                message: "nobody is named \"Bobcat\"".to_owned(),
+            }
+        );
+        // Selectors are not mentions
+        app.try_run_cli("kick @name(Bobcat)").unwrap();
+        assert_eq!(app.world().resource::<Kicked>().0.len(), 3);
+
+        let world = app.world();
+        let completions = world
+            .resource::<crate::CliCommands>()
+            .complete(world, "kick @");
+        let replacements: Vec<_> = completions.iter().map(|c| c.replacement.as_str()).collect();
+        assert_eq!(replacements, ["@Bob", "\"@Player One\""]);
+        let completions = world
+            .resource::<crate::CliCommands>()
+            .complete(world, "kick @P");
+        assert_eq!(completions.len(), 1);
+    }
+}
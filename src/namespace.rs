@@ -0,0 +1,441 @@
+//! Grouping commands under a common name prefix.
+//!
+//! Commands in a namespace can be run by their full name (`entity.list`),
+//! or as subcommands, with the namespace and the name as separate words
+//! (`entity list`). This also works for nested namespaces (`audio music
+//! volume 3` runs `audio.music.volume 3`). Typing just the namespace
+//! (`entity`) lists the commands in it.
+
+use std::ops::RangeBounds;
+use std::time::Duration;
+
+use bevy::ecs::schedule::Condition;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use crate::completion::CliCompletionRequest;
+use crate::error::CliError;
+use crate::localize::CliLocalizer;
+use crate::output::cli_println;
+use crate::permissions::CliPermission;
+use crate::table::{CliAlign, CliTable};
+use crate::{quote_cli_arg, run_cli_line, CliCommands, CliCommandsRegisterExt};
+
+/// Separator between a namespace and the names of the commands inside it
+pub const NAMESPACE_SEPARATOR: char = '.';
+
+/// Registers "console commands" inside a namespace
+///
+/// Obtained from [`CliCommandsRegisterExt::register_cli_namespace`].
+/// All commands registered through this will have their names prefixed
+/// with the namespace (for example, `pause` in namespace `physics` becomes
+/// `physics.pause`), and will be tagged with the namespace in [`CliCommands`].
+///
+/// Unlike the global registration methods, registering the same command
+/// twice within a namespace is an error, and the duplicate is ignored.
+pub struct CliNamespace<'w> {
+    world: &'w mut World,
+    namespace: String,
+    noargs: HashSet<String>,
+    args: HashSet<String>,
+}
+
+impl<'w> CliNamespace<'w> {
+    pub(crate) fn new(world: &'w mut World, namespace: String) -> Self {
+        CliNamespace {
+            world,
+            namespace,
+            noargs: HashSet::default(),
+            args: HashSet::default(),
+        }
+    }
+
+    /// The full name of this namespace (including any parent namespaces)
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn full_name(&self, name: &str) -> String {
+        format!("{}{}{}", self.namespace, NAMESPACE_SEPARATOR, name)
+    }
+
+    fn tag(&mut self, full_name: &str) {
+        let namespace = self.namespace.clone();
+        let mut clicommands = self.world.resource_mut::<CliCommands>();
+        if let Some(cmd) = clicommands.commands.get_mut(full_name) {
+            cmd.namespace = Some(namespace);
+        }
+    }
+}
+
+impl CliCommandsRegisterExt for CliNamespace<'_> {
+    fn register_clicommand_noargs<S, Param>(&mut self, name: &str, system: S) -> &mut Self
+    where
+        S: IntoSystem<(), (), Param> + 'static,
+    {
+        if !self.noargs.insert(name.to_owned()) {
+            error!(
+                "CliCommand {:?} already registered in namespace {:?}!",
+                name, self.namespace
+            );
+            return self;
+        }
+        let full_name = self.full_name(name);
+        self.world.register_clicommand_noargs(&full_name, system);
+        self.tag(&full_name);
+        self
+    }
+    fn register_clicommand_args<S, Param>(&mut self, name: &str, system: S) -> &mut Self
+    where
+        S: IntoSystem<In<Vec<String>>, (), Param> + 'static,
+    {
+        if !self.args.insert(name.to_owned()) {
+            error!(
+                "CliCommand {:?} already registered in namespace {:?}!",
+                name, self.namespace
+            );
+            return self;
+        }
+        let full_name = self.full_name(name);
+        self.world.register_clicommand_args(&full_name, system);
+        self.tag(&full_name);
+        self
+    }
+    fn unregister_clicommand(&mut self, name: &str) -> &mut Self {
+        self.noargs.remove(name);
+        self.args.remove(name);
+        let full_name = self.full_name(name);
+        self.world.unregister_clicommand(&full_name);
+        self
+    }
+    fn register_cli_namespace<F>(&mut self, namespace: &str, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut CliNamespace),
+    {
+        let full_name = self.full_name(namespace);
+        self.world.register_cli_namespace(&full_name, f);
+        self
+    }
+    fn with_clicommand_owner<F>(&mut self, owner: &str, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut Self),
+    {
+        let previous = crate::set_owner_scope(self.world, Some(owner.to_owned()));
+        f(self);
+        crate::set_owner_scope(self.world, previous);
+        self
+    }
+    fn with_clicommand_group<F>(&mut self, group: &str, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut Self),
+    {
+        crate::push_group_scope(self.world, group);
+        f(self);
+        crate::pop_group_scope(self.world);
+        self
+    }
+    fn reserve_cli_namespace(&mut self, namespace: &str) -> &mut Self {
+        let full_name = self.full_name(namespace);
+        self.world.reserve_cli_namespace(&full_name);
+        self
+    }
+    fn add_clicommand_run_condition<C, M>(&mut self, name: &str, condition: C) -> &mut Self
+    where
+        C: Condition<M> + 'static,
+    {
+        let full_name = self.full_name(name);
+        self.world
+            .add_clicommand_run_condition(&full_name, condition);
+        self
+    }
+    fn require_clicommand_resource<R: Resource>(&mut self, name: &str, plugin: &str) -> &mut Self {
+        let full_name = self.full_name(name);
+        self.world
+            .require_clicommand_resource::<R>(&full_name, plugin);
+        self
+    }
+    fn set_clicommand_description(&mut self, name: &str, description: &str) -> &mut Self {
+        let full_name = self.full_name(name);
+        self.world
+            .set_clicommand_description(&full_name, description);
+        self
+    }
+    fn set_clicommand_usage(&mut self, name: &str, usage: &str) -> &mut Self {
+        let full_name = self.full_name(name);
+        self.world.set_clicommand_usage(&full_name, usage);
+        self
+    }
+    fn add_clicommand_emits<E: Event>(&mut self, name: &str) -> &mut Self {
+        let full_name = self.full_name(name);
+        self.world.add_clicommand_emits::<E>(&full_name);
+        self
+    }
+    fn set_clicommand_permission(&mut self, name: &str, permission: CliPermission) -> &mut Self {
+        let full_name = self.full_name(name);
+        self.world.set_clicommand_permission(&full_name, permission);
+        self
+    }
+    fn set_clicommand_dangerous(&mut self, name: &str) -> &mut Self {
+        let full_name = self.full_name(name);
+        self.world.set_clicommand_dangerous(&full_name);
+        self
+    }
+    fn set_clicommand_cooldown(&mut self, name: &str, cooldown: Duration) -> &mut Self {
+        let full_name = self.full_name(name);
+        self.world.set_clicommand_cooldown(&full_name, cooldown);
+        self
+    }
+    fn set_clicommand_rate_limit(&mut self, name: &str, max_per_second: u32) -> &mut Self {
+        let full_name = self.full_name(name);
+        self.world
+            .set_clicommand_rate_limit(&full_name, max_per_second);
+        self
+    }
+    fn set_clicommand_timing(&mut self, name: &str) -> &mut Self {
+        let full_name = self.full_name(name);
+        self.world.set_clicommand_timing(&full_name);
+        self
+    }
+    fn set_clicommand_paged(&mut self, name: &str) -> &mut Self {
+        let full_name = self.full_name(name);
+        self.world.set_clicommand_paged(&full_name);
+        self
+    }
+    fn set_clicommand_concurrency(
+        &mut self,
+        name: &str,
+        concurrency: crate::jobs::CliConcurrency,
+    ) -> &mut Self {
+        let full_name = self.full_name(name);
+        self.world
+            .set_clicommand_concurrency(&full_name, concurrency);
+        self
+    }
+    fn set_clicommand_arity(&mut self, name: &str, range: impl RangeBounds<usize>) -> &mut Self {
+        let full_name = self.full_name(name);
+        self.world.set_clicommand_arity(&full_name, range);
+        self
+    }
+    fn set_clicommand_arg_check<F>(&mut self, name: &str, index: usize, check: F) -> &mut Self
+    where
+        F: Fn(&str) -> Result<(), String> + Send + Sync + 'static,
+    {
+        let full_name = self.full_name(name);
+        self.world
+            .set_clicommand_arg_check(&full_name, index, check);
+        self
+    }
+    fn add_clicommand_group(&mut self, name: &str, group: &str) -> &mut Self {
+        let full_name = self.full_name(name);
+        self.world.add_clicommand_group(&full_name, group);
+        self
+    }
+    fn set_clicommand_completer<F>(&mut self, name: &str, completer: F) -> &mut Self
+    where
+        F: Fn(&CliCompletionRequest, &World) -> Vec<String> + Send + Sync + 'static,
+    {
+        let full_name = self.full_name(name);
+        self.world.set_clicommand_completer(&full_name, completer);
+        self
+    }
+    fn set_clicommand_fallback<S, Param>(&mut self, system: S) -> &mut Self
+    where
+        S: IntoSystem<In<String>, (), Param> + 'static,
+    {
+        // There is only one global fallback
+        self.world.set_clicommand_fallback(system);
+        self
+    }
+}
+
+impl CliCommands {
+    /// Whether any command is registered under the given namespace
+    /// (including in nested namespaces)
+    pub fn is_namespace(&self, namespace: &str) -> bool {
+        self.iter_names().any(|name| in_namespace(name, namespace))
+    }
+    /// Iterate over the subcommands of a namespace: the names (without the
+    /// namespace prefix) of its commands and nested namespaces, sorted
+    pub fn iter_subcommands(&self, namespace: &str) -> impl Iterator<Item = &str> {
+        let mut subcommands: Vec<&str> = self
+            .iter_names()
+            .filter(|name| in_namespace(name, namespace))
+            .map(|name| {
+                let rest = &name[namespace.len() + NAMESPACE_SEPARATOR.len_utf8()..];
+                rest.split(NAMESPACE_SEPARATOR).next().unwrap_or(rest)
+            })
+            .collect();
+        subcommands.sort_unstable();
+        subcommands.dedup();
+        subcommands.into_iter()
+    }
+    /// Iterate over the reserved namespaces, and the owners allowed to use them
+    ///
+    /// See [`reserve_cli_namespace`](CliCommandsRegisterExt::reserve_cli_namespace).
+    pub fn iter_reserved(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.reserved
+            .iter()
+            .map(|(namespace, owner)| (namespace.as_str(), owner.as_deref()))
+    }
+    /// Get the reserved namespace that a command name is in, if any
+    pub fn reservation_of(&self, name: &str) -> Option<(&str, Option<&str>)> {
+        self.iter_reserved()
+            .filter(|(namespace, _)| in_namespace(name, namespace))
+            // The innermost reservation applies
+            .max_by_key(|(namespace, _)| namespace.len())
+    }
+    /// Iterate over all namespaces that have commands (including nested ones), sorted
+    pub fn iter_namespaces(&self) -> impl Iterator<Item = &str> {
+        let mut namespaces: Vec<&str> = self
+            .iter_names()
+            .flat_map(|name| {
+                name.match_indices(NAMESPACE_SEPARATOR)
+                    .map(move |(i, _)| &name[..i])
+            })
+            .collect();
+        namespaces.sort_unstable();
+        namespaces.dedup();
+        namespaces.into_iter()
+    }
+    /// Check if a command can be registered with the current owner
+    pub(crate) fn check_reserved(&self, name: &str) -> Result<(), String> {
+        match self.reservation_of(name) {
+            Some((namespace, owner)) if owner != self.owner_scope.as_deref() => Err(format!(
+                "namespace {:?} is reserved by {}",
+                namespace,
+                owner_name(owner)
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Describe the owner of a reservation, for messages
+pub(crate) fn owner_name(owner: Option<&str>) -> String {
+    match owner {
+        Some(owner) => format!("{:?}", owner),
+        None => "the app".to_owned(),
+    }
+}
+
+pub(crate) fn in_namespace(name: &str, namespace: &str) -> bool {
+    name.strip_prefix(namespace)
+        .is_some_and(|rest| rest.starts_with(NAMESPACE_SEPARATOR))
+}
+
+/// Run a command string whose command name may be a namespace
+///
+/// Returns `None` if it is not a namespace, or there is a command with
+/// exactly the same name. With args, the first arg is run
+/// as a command inside the namespace. Without, the subcommands are listed.
+pub(crate) fn run_subcommand(
+    world: &mut World,
+    name: &str,
+    args: &[String],
+) -> Option<Result<(), CliError>> {
+    let clicommands = world.get_resource::<CliCommands>()?;
+    let localizer = world.get_resource::<CliLocalizer>();
+    if clicommands.command_available(name) || !clicommands.is_namespace(name) {
+        return None;
+    }
+    let Some((subcommand, args)) = args.split_first() else {
+        let mut table = CliTable::new()
+            .with_column("SUBCOMMAND", CliAlign::Left)
+            .with_column("DESCRIPTION", CliAlign::Left);
+        for subcommand in clicommands.iter_subcommands(name) {
+            let full_name = format!("{}{}{}", name, NAMESPACE_SEPARATOR, subcommand);
+            let description = match clicommands.get_info(&full_name) {
+                Some(info) => match localizer {
+                    Some(localizer) => localizer.description(&full_name, info.description),
+                    None => info.description,
+                }
+                .unwrap_or(""),
+                None => "(namespace)",
+            };
+            table.add_row([subcommand.to_owned(), description.to_owned()]);
+        }
+        let table = table.to_string();
+        cli_println(world, table);
+        return Some(Ok(()));
+    };
+    let mut line = format!("{}{}{}", name, NAMESPACE_SEPARATOR, subcommand);
+    for arg in args {
+        line.push(' ');
+        line.push_str(&quote_cli_arg(arg));
+    }
+    Some(run_cli_line(world, &line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CliCommandsRunExt;
+
+    #[derive(Resource, Default)]
+    struct Ran(Vec<&'static str>);
+
+    #[test]
+    fn names_are_prefixed_and_tagged() {
+        let mut world = World::new();
+        world.init_resource::<Ran>();
+        world.register_cli_namespace("physics", |ns| {
+            ns.register_clicommand_noargs("pause", |mut ran: ResMut<Ran>| ran.0.push("pause"));
+            ns.register_cli_namespace("debug", |ns| {
+                ns.register_clicommand_noargs("draw", |mut ran: ResMut<Ran>| ran.0.push("draw"));
+            });
+        });
+
+        world.run_cli("physics.pause");
+        world.run_cli("physics.debug.draw");
+        world.run_cli("pause");
+        assert_eq!(world.resource::<Ran>().0, ["pause", "draw"]);
+
+        let clicommands = world.resource::<CliCommands>();
+        assert_eq!(
+            clicommands.command_namespace("physics.pause"),
+            Some("physics")
+        );
+        assert_eq!(
+            clicommands.command_namespace("physics.debug.draw"),
+            Some("physics.debug")
+        );
+        let names: Vec<&str> = clicommands.iter_namespace("physics").collect();
+        assert_eq!(names, ["physics.pause"]);
+    }
+
+    #[test]
+    fn duplicates_are_ignored() {
+        let mut world = World::new();
+        world.init_resource::<Ran>();
+        world.register_cli_namespace("physics", |ns| {
+            ns.register_clicommand_noargs("pause", |mut ran: ResMut<Ran>| ran.0.push("first"));
+            ns.register_clicommand_noargs("pause", |mut ran: ResMut<Ran>| ran.0.push("second"));
+        });
+
+        world.run_cli("physics.pause");
+        assert_eq!(world.resource::<Ran>().0, ["first"]);
+    }
+
+    #[test]
+    fn namespaces_as_subcommands() {
+        let mut world = World::new();
+        world.init_resource::<Ran>();
+        world.register_cli_namespace("physics", |ns| {
+            ns.register_clicommand_noargs("pause", |mut ran: ResMut<Ran>| ran.0.push("pause"));
+            ns.register_cli_namespace("debug", |ns| {
+                ns.register_clicommand_noargs("draw", |mut ran: ResMut<Ran>| ran.0.push("draw"));
+            });
+        });
+
+        world.run_cli("physics pause");
+        world.run_cli("physics debug draw");
+        assert_eq!(world.resource::<Ran>().0, ["pause", "draw"]);
+
+        let clicommands = world.resource::<CliCommands>();
+        assert!(clicommands.is_namespace("physics"));
+        assert!(clicommands.is_namespace("physics.debug"));
+        assert!(!clicommands.is_namespace("physics.pause"));
+        let subcommands: Vec<&str> = clicommands.iter_subcommands("physics").collect();
+        assert_eq!(subcommands, ["debug", "pause"]);
+    }
+}
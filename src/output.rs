@@ -0,0 +1,851 @@
+//! Output produced by "console commands", for display in consoles.
+//!
+//! Commands write their results using the [`CliOutput`] system param (or
+//! [`cli_println`], from exclusive systems), instead of logging or printing
+//! to stdout. The output goes to the [`CliOutputLog`] resource, from which
+//! console UIs can take it for display.
+//!
+//! If you need the output of a specific command string (for tests, or to
+//! send it back to a remote frontend), use
+//! [`run_cli_captured`](CliCommandsOutputExt::run_cli_captured).
+//!
+//! Every line has a [`CliSeverity`], and may have styled [spans](CliOutputSpan)
+//! of text, so consoles can color-code it. The crate's own messages (such as
+//! unknown commands, or usage errors) are written as [`CliSeverity::Error`]
+//! lines too.
+//!
+//! Text written with [`CliOutput::markdown`] can use a little markup:
+//! `# headings`, `**strong**` text, `` `code` `` and `[links](text)`.
+//! The markup is removed from the text, and becomes styled spans, so
+//! plain-text sinks (like the log, or the [`remote`](crate::remote)
+//! protocol) show the text without it. Clicking a link in the
+//! [`egui`](crate::egui) console copies its text to the clipboard.
+//!
+//! ```rust,ignore
+//! output.markdown("# Spawning\nUse `spawn <x> <y>`, **not** `summon`. See [the wiki](https://example.com/wiki).");
+//! ```
+//!
+//! Output can also have links that run a command string when clicked, in
+//! the session of the console (the [`console`](crate::console) overlay and
+//! the [`egui`](crate::egui) window). Plain-text sinks only show the text
+//! of the link. For example, the built-in `entities` command links every
+//! entity to `inspect`:
+//!
+//! ```rust,ignore
+//! output.link(format!("despawn {}", entity), "despawn this");
+//!
+//! // Or as part of a line
+//! let line = CliOutputLine::new("Boss spawned, ")
+//!     .push_link("despawn it", CliLink::Run(format!("despawn {}", entity)));
+//! output.push_line(line);
+//! ```
+//!
+//! Commands can also [attach](CliOutput::attach) files to their output,
+//! like screenshots. Consoles show the line describing the attachment;
+//! the [`remote`](crate::remote) and [`http`](crate::http) servers send
+//! the data to their clients:
+//!
+//! ```rust,ignore
+//! fn screenshot(mut output: CliOutput, capture: Res<LastFrame>) {
+//!     output.attach(CliAttachment::new("screenshot.png", "image/png", capture.to_png()));
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::ops::Range;
+use std::sync::Arc;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::error::CliError;
+use crate::local::CliCurrentCommand;
+use crate::localize::{format_message, CliLocalizer};
+use crate::source::CliSource;
+use crate::status::CliStatus;
+#[cfg(any(feature = "breakpoint", feature = "watch"))]
+use crate::{describe_error, run_chain_links};
+use crate::{run_cli_chain, CliCommandsRunExt};
+
+/// How important a line of output is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CliSeverity {
+    #[default]
+    Info,
+    /// Something completed successfully
+    Success,
+    Warn,
+    Error,
+}
+
+impl CliSeverity {
+    /// The name of the severity, like `warn`, for protocols and args
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CliSeverity::Info => "info",
+            CliSeverity::Success => "success",
+            CliSeverity::Warn => "warn",
+            CliSeverity::Error => "error",
+        }
+    }
+    /// Parse the name of a severity, as given by [`as_str`](Self::as_str)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "info" => Some(CliSeverity::Info),
+            "success" => Some(CliSeverity::Success),
+            "warn" => Some(CliSeverity::Warn),
+            "error" => Some(CliSeverity::Error),
+            _ => None,
+        }
+    }
+}
+
+/// How a span of text should be displayed
+///
+/// These describe the meaning of the text; each console decides how to show it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CliTextStyle {
+    Strong,
+    Emphasis,
+    /// Names of commands, code, or values
+    Code,
+    Highlight,
+    /// A whole line that is a heading
+    Heading,
+    /// Text that can be clicked, see [`CliOutputSpan::link`]
+    Link,
+}
+
+/// What clicking a link in the output does
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CliLink {
+    /// Copy the text to the clipboard
+    Copy(String),
+    /// Run the command string, in the session of the console
+    Run(String),
+}
+
+/// A styled part of a [`CliOutputLine`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliOutputSpan {
+    /// The byte range of the text that is styled
+    pub range: Range<usize>,
+    pub style: CliTextStyle,
+    /// What clicking the text does, for [`CliTextStyle::Link`] spans
+    pub link: Option<CliLink>,
+}
+
+/// A line of output from a "console command"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliOutputLine {
+    /// The name of the command that produced the line, if known
+    pub command: Option<String>,
+    /// Who or what ran the command that produced the line, if known
+    pub source: Option<CliSource>,
+    pub severity: CliSeverity,
+    pub text: String,
+    /// Styled parts of the text, in order, not overlapping
+    pub spans: Vec<CliOutputSpan>,
+    /// A file attached to the output, described by the text
+    pub attachment: Option<CliAttachment>,
+}
+
+/// A file attached to the output of a command, like a screenshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliAttachment {
+    /// A name for the file, like `screenshot.png`
+    pub name: String,
+    /// The MIME type of the data, like `image/png`
+    pub mime: String,
+    /// Shared, as lines of output are copied around
+    pub data: Arc<[u8]>,
+}
+
+impl CliAttachment {
+    pub fn new(
+        name: impl Into<String>,
+        mime: impl Into<String>,
+        data: impl Into<Arc<[u8]>>,
+    ) -> Self {
+        CliAttachment {
+            name: name.into(),
+            mime: mime.into(),
+            data: data.into(),
+        }
+    }
+}
+
+impl CliOutputLine {
+    pub fn new(text: impl Into<String>) -> Self {
+        CliOutputLine {
+            command: None,
+            source: None,
+            severity: CliSeverity::Info,
+            text: text.into(),
+            spans: Vec::new(),
+            attachment: None,
+        }
+    }
+    /// A line describing an attachment, carrying it
+    pub fn attachment(attachment: CliAttachment) -> Self {
+        let text = format!(
+            "[attachment: {} ({}, {} bytes)]",
+            attachment.name,
+            attachment.mime,
+            attachment.data.len()
+        );
+        CliOutputLine {
+            attachment: Some(attachment),
+            ..CliOutputLine::new(text)
+        }
+    }
+    pub fn with_severity(mut self, severity: CliSeverity) -> Self {
+        self.severity = severity;
+        self
+    }
+    /// A line of text with markup, turned into styled spans
+    ///
+    /// A line starting with `#` (followed by a space) is a heading. Text can
+    /// be `**strong**` or `` `code` ``, and `[label](text)` is a link that
+    /// copies the text. Markup characters can be escaped with `\`. The
+    /// markup is removed from the text.
+    pub fn markdown(text: &str) -> Self {
+        let heading = text
+            .trim_start_matches('#')
+            .strip_prefix(' ')
+            .filter(|_| text.starts_with('#'));
+        let mut line = CliOutputLine::new(String::new());
+        let mut rest = heading.unwrap_or(text);
+        while let Some(c) = rest.chars().next() {
+            let escaped = rest
+                .strip_prefix('\\')
+                .and_then(|after| after.chars().next())
+                .filter(|next| "\\`*[]#".contains(*next));
+            if let Some(next) = escaped {
+                line.text.push(next);
+                rest = &rest[1 + next.len_utf8()..];
+                continue;
+            }
+            let strong = rest
+                .strip_prefix("**")
+                .and_then(|after| after.split_once("**"))
+                .filter(|(inner, _)| !inner.is_empty());
+            if let Some((inner, after)) = strong {
+                line = line.push_styled(inner, CliTextStyle::Strong);
+                rest = after;
+                continue;
+            }
+            let code = rest
+                .strip_prefix('`')
+                .and_then(|after| after.split_once('`'))
+                .filter(|(inner, _)| !inner.is_empty());
+            if let Some((inner, after)) = code {
+                line = line.push_styled(inner, CliTextStyle::Code);
+                rest = after;
+                continue;
+            }
+            if let Some((label, target, after)) = markdown_link(rest) {
+                line = line.push_link(label, CliLink::Copy(target.to_owned()));
+                rest = after;
+                continue;
+            }
+            line.text.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        if heading.is_some() {
+            line.spans = vec![CliOutputSpan {
+                range: 0..line.text.len(),
+                style: CliTextStyle::Heading,
+                link: None,
+            }];
+        }
+        line
+    }
+    /// Style a byte range of the text
+    pub fn with_span(mut self, range: Range<usize>, style: CliTextStyle) -> Self {
+        self.spans.push(CliOutputSpan {
+            range,
+            style,
+            link: None,
+        });
+        self
+    }
+    /// Append text to the line, with the given style
+    pub fn push_styled(mut self, text: &str, style: CliTextStyle) -> Self {
+        let start = self.text.len();
+        self.text.push_str(text);
+        let end = self.text.len();
+        self.with_span(start..end, style)
+    }
+    /// Append a link to the line
+    pub fn push_link(mut self, text: &str, link: CliLink) -> Self {
+        let start = self.text.len();
+        self.text.push_str(text);
+        self.spans.push(CliOutputSpan {
+            range: start..self.text.len(),
+            style: CliTextStyle::Link,
+            link: Some(link),
+        });
+        self
+    }
+    /// Iterate over the parts of the text, with their styles (if any)
+    pub fn segments(&self) -> impl Iterator<Item = (&str, Option<CliTextStyle>)> {
+        self.segment_spans()
+            .map(|(text, span)| (text, span.map(|span| span.style)))
+    }
+    /// Iterate over the parts of the text, with their spans (if any)
+    pub fn segment_spans(&self) -> impl Iterator<Item = (&str, Option<&CliOutputSpan>)> {
+        let mut segments = Vec::new();
+        let mut pos = 0;
+        for span in &self.spans {
+            let Some(styled) = self.text.get(span.range.clone()) else {
+                continue;
+            };
+            if span.range.start < pos {
+                continue;
+            }
+            if span.range.start > pos {
+                segments.push((&self.text[pos..span.range.start], None));
+            }
+            segments.push((styled, Some(span)));
+            pos = span.range.end;
+        }
+        if pos < self.text.len() {
+            segments.push((&self.text[pos..], None));
+        }
+        segments.into_iter()
+    }
+}
+
+/// Split `[label](target)` from the start of the text
+fn markdown_link(text: &str) -> Option<(&str, &str, &str)> {
+    let (label, after) = text.strip_prefix('[')?.split_once("](")?;
+    let (target, after) = after.split_once(')')?;
+    if label.is_empty() || label.contains(['[', ']']) || target.is_empty() {
+        return None;
+    }
+    Some((label, target, after))
+}
+
+/// Output from "console commands", waiting to be displayed
+#[derive(Resource, Debug)]
+pub struct CliOutputLog {
+    lines: VecDeque<CliOutputLine>,
+    /// Lines for private sources, like players, kept out of the consoles
+    private: VecDeque<CliOutputLine>,
+    /// Lines not yet added to the [`CliScrollback`](crate::scrollback::CliScrollback)
+    scrollback: VecDeque<CliOutputLine>,
+    captures: Vec<Vec<CliOutputLine>>,
+    /// Copies of the lines of the command string being recorded for the
+    /// [`CliOutputHistory`](crate::scrollback::CliOutputHistory), and how
+    /// many captures there were when it started
+    recording: Option<(usize, Vec<CliOutputLine>)>,
+    /// Copies of the lines of the command strings being run with
+    /// [`run_cli`](CliCommandsRunExt::run_cli), innermost last, and how many
+    /// captures there were when they started
+    collecting: Vec<(usize, Vec<CliOutputLine>)>,
+    /// How many lines to keep, if nothing takes them; older lines are dropped
+    pub max_lines: usize,
+    /// Whether to also log every line (at `info` level)
+    ///
+    /// Enabled by default, so output is visible even without a console UI.
+    pub echo_to_log: bool,
+}
+
+impl Default for CliOutputLog {
+    fn default() -> Self {
+        CliOutputLog {
+            lines: VecDeque::new(),
+            private: VecDeque::new(),
+            scrollback: VecDeque::new(),
+            captures: Vec::new(),
+            recording: None,
+            collecting: Vec::new(),
+            max_lines: 1000,
+            echo_to_log: true,
+        }
+    }
+}
+
+impl CliOutputLog {
+    /// Add a line of output
+    ///
+    /// If output is being captured, the line goes to the capture instead.
+    /// Lines for private sources (see [`CliSource::is_private`]) are kept
+    /// apart, until taken with [`drain_for`](Self::drain_for).
+    pub fn push(&mut self, line: CliOutputLine) {
+        if let Some((depth, recorded)) = &mut self.recording {
+            // Not the output of commands whose output is piped
+            if *depth == self.captures.len() {
+                recorded.push(line.clone());
+            }
+        }
+        for (depth, collected) in &mut self.collecting {
+            if *depth == self.captures.len() {
+                collected.push(line.clone());
+            }
+        }
+        if let Some(capture) = self.captures.last_mut() {
+            capture.push(line);
+            return;
+        }
+        if self.echo_to_log {
+            match line.severity {
+                CliSeverity::Info | CliSeverity::Success => info!("{}", line.text),
+                CliSeverity::Warn => warn!("{}", line.text),
+                CliSeverity::Error => error!("{}", line.text),
+            }
+        }
+        let lines = match &line.source {
+            Some(source) if source.is_private() => &mut self.private,
+            _ => {
+                self.scrollback.push_back(line.clone());
+                if self.scrollback.len() > self.max_lines {
+                    self.scrollback.pop_front();
+                }
+                &mut self.lines
+            }
+        };
+        lines.push_back(line);
+        while lines.len() > self.max_lines {
+            lines.pop_front();
+        }
+    }
+    /// Take the lines not yet added to the scrollback
+    pub(crate) fn take_scrollback(&mut self) -> VecDeque<CliOutputLine> {
+        std::mem::take(&mut self.scrollback)
+    }
+    /// Start keeping copies of the lines of a command string, unless already
+    /// keeping them for another one, returning whether it started
+    pub(crate) fn begin_recording(&mut self) -> bool {
+        if self.recording.is_some() {
+            return false;
+        }
+        self.recording = Some((self.captures.len(), Vec::new()));
+        true
+    }
+    /// Stop keeping copies of lines, returning them
+    pub(crate) fn end_recording(&mut self) -> Vec<CliOutputLine> {
+        self.recording
+            .take()
+            .map(|(_, lines)| lines)
+            .unwrap_or_default()
+    }
+    /// Start keeping copies of the lines of a command string, for returning them
+    pub(crate) fn begin_collecting(&mut self) {
+        self.collecting.push((self.captures.len(), Vec::new()));
+    }
+    /// Stop keeping copies of the lines of the innermost command string,
+    /// returning them
+    pub(crate) fn end_collecting(&mut self) -> Vec<CliOutputLine> {
+        self.collecting
+            .pop()
+            .map(|(_, lines)| lines)
+            .unwrap_or_default()
+    }
+    /// Whether output is being captured, instead of added to the log
+    pub(crate) fn is_capturing(&self) -> bool {
+        !self.captures.is_empty()
+    }
+    /// Remove and return the lines for a private source, oldest first
+    pub fn drain_for(&mut self, source: &CliSource) -> Vec<CliOutputLine> {
+        let (taken, kept): (VecDeque<_>, _) = std::mem::take(&mut self.private)
+            .into_iter()
+            .partition(|line| line.source.as_ref() == Some(source));
+        self.private = kept;
+        taken.into()
+    }
+    /// Remove and return the lines for all private sources, oldest first
+    ///
+    /// Each line has its [`source`](CliOutputLine::source) set.
+    pub fn drain_private(&mut self) -> impl Iterator<Item = CliOutputLine> + '_ {
+        self.private.drain(..)
+    }
+    /// Iterate over the lines, oldest first, without removing them
+    pub fn iter(&self) -> impl Iterator<Item = &CliOutputLine> {
+        self.lines.iter()
+    }
+    /// Remove and return all the lines, oldest first
+    pub fn drain(&mut self) -> impl Iterator<Item = CliOutputLine> + '_ {
+        self.lines.drain(..)
+    }
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+}
+
+/// System param for "console commands" to write output
+#[derive(SystemParam)]
+pub struct CliOutput<'w> {
+    log: ResMut<'w, CliOutputLog>,
+    current: Option<Res<'w, CliCurrentCommand>>,
+    status: Option<ResMut<'w, CliStatus>>,
+    localizer: Option<Res<'w, CliLocalizer>>,
+}
+
+impl CliOutput<'_> {
+    /// Who or what ran the command, if known (see [`source`](crate::source))
+    pub fn source(&self) -> Option<&CliSource> {
+        self.status.as_ref().and_then(|status| status.source())
+    }
+    /// The [`CliSession`](crate::session::CliSession) the command was run from, if any
+    pub(crate) fn session(&self) -> Option<Entity> {
+        self.status.as_ref().and_then(|status| status.session)
+    }
+    /// Write text to the output
+    ///
+    /// Text containing newlines becomes multiple lines.
+    pub fn println(&mut self, text: impl AsRef<str>) {
+        self.println_severity(CliSeverity::Info, text);
+    }
+    /// Write text to the output, reporting success
+    pub fn success(&mut self, text: impl AsRef<str>) {
+        self.println_severity(CliSeverity::Success, text);
+    }
+    /// Write a warning to the output
+    pub fn warn(&mut self, text: impl AsRef<str>) {
+        self.println_severity(CliSeverity::Warn, text);
+    }
+    /// Write an error to the output, without marking the command as failed
+    pub fn error(&mut self, text: impl AsRef<str>) {
+        self.println_severity(CliSeverity::Error, text);
+    }
+    /// Write an error to the output, and mark the command as failed
+    pub fn fail(&mut self, text: impl AsRef<str>) {
+        self.error(text);
+        if let Some(status) = &mut self.status {
+            status.fail();
+        }
+    }
+    /// Write text with the given severity to the output
+    ///
+    /// Text containing newlines becomes multiple lines.
+    pub fn println_severity(&mut self, severity: CliSeverity, text: impl AsRef<str>) {
+        for text in text.as_ref().lines() {
+            self.push_line(CliOutputLine::new(text).with_severity(severity));
+        }
+    }
+    /// Write text with markup to the output
+    ///
+    /// Text containing newlines becomes multiple lines. See
+    /// [`CliOutputLine::markdown`] for the markup.
+    pub fn markdown(&mut self, text: impl AsRef<str>) {
+        for text in text.as_ref().lines() {
+            self.push_line(CliOutputLine::markdown(text));
+        }
+    }
+    /// Write a line with a link, that runs a command string when clicked
+    pub fn link(&mut self, cli: impl Into<String>, text: impl AsRef<str>) {
+        let line =
+            CliOutputLine::new(String::new()).push_link(text.as_ref(), CliLink::Run(cli.into()));
+        self.push_line(line);
+    }
+    /// Attach a file to the output, like a screenshot
+    ///
+    /// It is written as a line describing it, which carries the data.
+    pub fn attach(&mut self, attachment: CliAttachment) {
+        self.push_line(CliOutputLine::attachment(attachment));
+    }
+    /// Write a line, possibly with styled spans, to the output
+    ///
+    /// The command that produced it, and its source, are filled in, if not set.
+    pub fn push_line(&mut self, mut line: CliOutputLine) {
+        if line.command.is_none() {
+            line.command = self
+                .current
+                .as_ref()
+                .and_then(|current| current.name())
+                .map(|name| name.to_owned());
+        }
+        if line.source.is_none() {
+            line.source = self
+                .status
+                .as_ref()
+                .and_then(|status| status.source().cloned());
+        }
+        self.log.push(line);
+    }
+    /// Write a localized message to the output
+    ///
+    /// Usually called through the [`outln!`](crate::outln) macro.
+    pub fn println_localized(&mut self, key: &str, default: &str, args: &[(&str, &dyn Display)]) {
+        let text = self.localize(key, default, args);
+        self.println(text);
+    }
+    /// Write a localized message to the output, and mark the command as failed
+    ///
+    /// Usually called through the [`failln!`](crate::failln) macro.
+    pub fn fail_localized(&mut self, key: &str, default: &str, args: &[(&str, &dyn Display)]) {
+        let text = self.localize(key, default, args);
+        self.fail(text);
+    }
+    fn localize(&self, key: &str, default: &str, args: &[(&str, &dyn Display)]) -> String {
+        match &self.localizer {
+            Some(localizer) => localizer.format(key, default, args),
+            None => format_message(default, args),
+        }
+    }
+}
+
+/// Write text to the output, from an exclusive system
+///
+/// Equivalent to [`CliOutput::println`].
+pub fn cli_println(world: &mut World, text: impl AsRef<str>) {
+    cli_println_severity(world, CliSeverity::Info, text);
+}
+
+/// Write an error to the output, from an exclusive system
+///
+/// Equivalent to [`CliOutput::error`]. Use
+/// [`cli_fail`](crate::status::cli_fail) to also mark the command as failed.
+pub fn cli_error(world: &mut World, text: impl AsRef<str>) {
+    cli_println_severity(world, CliSeverity::Error, text);
+}
+
+/// Write text with the given severity to the output, from an exclusive system
+///
+/// Equivalent to [`CliOutput::println_severity`].
+pub fn cli_println_severity(world: &mut World, severity: CliSeverity, text: impl AsRef<str>) {
+    for text in text.as_ref().lines() {
+        cli_push_line(world, CliOutputLine::new(text).with_severity(severity));
+    }
+}
+
+/// Write text with markup to the output, from an exclusive system
+///
+/// Equivalent to [`CliOutput::markdown`].
+pub fn cli_markdown(world: &mut World, text: impl AsRef<str>) {
+    for text in text.as_ref().lines() {
+        cli_push_line(world, CliOutputLine::markdown(text));
+    }
+}
+
+/// Write a line with a link, that runs a command string when clicked, from
+/// an exclusive system
+///
+/// Equivalent to [`CliOutput::link`].
+pub fn cli_link(world: &mut World, cli: impl Into<String>, text: impl AsRef<str>) {
+    let line = CliOutputLine::new(String::new()).push_link(text.as_ref(), CliLink::Run(cli.into()));
+    cli_push_line(world, line);
+}
+
+/// Attach a file to the output, from an exclusive system
+///
+/// Equivalent to [`CliOutput::attach`].
+pub fn cli_attach(world: &mut World, attachment: CliAttachment) {
+    cli_push_line(world, CliOutputLine::attachment(attachment));
+}
+
+/// Write a line, possibly with styled spans, to the output, from an exclusive system
+///
+/// Equivalent to [`CliOutput::push_line`].
+pub fn cli_push_line(world: &mut World, mut line: CliOutputLine) {
+    world.init_resource::<CliOutputLog>();
+    if line.command.is_none() {
+        line.command = world
+            .get_resource::<CliCurrentCommand>()
+            .and_then(|current| current.name())
+            .map(|name| name.to_owned());
+    }
+    if line.source.is_none() {
+        line.source = world
+            .get_resource::<CliStatus>()
+            .and_then(|status| status.source().cloned());
+    }
+    world.resource_mut::<CliOutputLog>().push(line);
+}
+
+/// Provides methods to run "console commands" and collect their output
+///
+/// Implemented for `World` and `App`. These are not part of
+/// [`CliCommandsRunExt`], because they need to run the command string
+/// immediately: `Commands` only queue command strings, to run later, so
+/// they cannot return their output, or why they failed.
+pub trait CliCommandsOutputExt {
+    /// Run a command string immediately, returning its output
+    ///
+    /// Unlike [`run_cli`](CliCommandsRunExt::run_cli), the output is not
+    /// added to the [`CliOutputLog`].
+    fn run_cli_captured(&mut self, cli: &str) -> Vec<CliOutputLine>;
+    /// Run a command string immediately, returning its output, or why it failed
+    ///
+    /// Unlike [`run_cli`](CliCommandsRunExt::run_cli), errors are returned,
+    /// instead of written to the output. If the command string is a chain, the result is that
+    /// of the last command that ran. The output is not added to the
+    /// [`CliOutputLog`].
+    fn try_run_cli(&mut self, cli: &str) -> Result<Vec<CliOutputLine>, CliError>;
+}
+
+impl CliCommandsOutputExt for World {
+    fn run_cli_captured(&mut self, cli: &str) -> Vec<CliOutputLine> {
+        capture_output(self, |world| world.run_cli(cli)).0
+    }
+    fn try_run_cli(&mut self, cli: &str) -> Result<Vec<CliOutputLine>, CliError> {
+        let (output, result) = capture_output(self, |world| run_cli_chain(world, cli, false));
+        result.map(|()| output)
+    }
+}
+
+pub(crate) fn capture_output<R>(
+    world: &mut World,
+    f: impl FnOnce(&mut World) -> R,
+) -> (Vec<CliOutputLine>, R) {
+    world.init_resource::<CliOutputLog>();
+    world
+        .resource_mut::<CliOutputLog>()
+        .captures
+        .push(Vec::new());
+    let r = f(world);
+    let output = world
+        .resource_mut::<CliOutputLog>()
+        .captures
+        .pop()
+        .unwrap_or_default();
+    (output, r)
+}
+
+/// The value of a console variable, or else the output of a command string
+///
+/// If the command string fails, its output (or the error) is returned as
+/// the error. The output is not added to the [`CliOutputLog`], nor to the
+/// [`CliOutputHistory`](crate::scrollback::CliOutputHistory).
+#[cfg(any(feature = "breakpoint", feature = "watch"))]
+pub(crate) fn evaluate_cli(world: &mut World, expression: &str) -> Result<String, String> {
+    #[cfg(feature = "cvars")]
+    if !world
+        .resource::<crate::CliCommands>()
+        .command_available(expression)
+    {
+        if let Some(value) = world
+            .get_resource::<crate::vars::CliVars>()
+            .and_then(|vars| vars.get_str(expression))
+        {
+            return Ok(value);
+        }
+    }
+    let (output, result) = capture_output(world, |world| run_chain_links(world, expression, false));
+    let text: Vec<&str> = output.iter().map(|line| line.text.as_str()).collect();
+    let text = text.join("\n");
+    match result {
+        Ok(()) => Ok(text),
+        Err(CliError::Failed(_)) if !text.is_empty() => Err(text),
+        Err(e) => Err(describe_error(world, &e)),
+    }
+}
+
+/// Split captured output into the lines to pass to the next command,
+/// showing the warnings and errors instead
+pub(crate) fn take_piped_lines(world: &mut World, output: Vec<CliOutputLine>) -> Vec<String> {
+    let mut lines = Vec::new();
+    for line in output {
+        match line.severity {
+            CliSeverity::Warn | CliSeverity::Error => cli_push_line(world, line),
+            CliSeverity::Info | CliSeverity::Success => {
+                lines.extend(line.text.lines().map(str::to_owned));
+            }
+        }
+    }
+    lines
+}
+
+impl CliCommandsOutputExt for App {
+    fn run_cli_captured(&mut self, cli: &str) -> Vec<CliOutputLine> {
+        self.world_mut().run_cli_captured(cli)
+    }
+    fn try_run_cli(&mut self, cli: &str) -> Result<Vec<CliOutputLine>, CliError> {
+        self.world_mut().try_run_cli(cli)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CliCommandsRegisterExt;
+
+    #[test]
+    fn markdown() {
+        let line =
+            CliOutputLine::markdown("Use `spawn`, **not** [the wiki](https://x.y) or \\`that\\`");
+        assert_eq!(line.text, "Use spawn, not the wiki or `that`");
+        let segments: Vec<_> = line.segments().collect();
+        assert_eq!(
+            segments,
+            [
+                ("Use ", None),
+                ("spawn", Some(CliTextStyle::Code)),
+                (", ", None),
+                ("not", Some(CliTextStyle::Strong)),
+                (" ", None),
+                ("the wiki", Some(CliTextStyle::Link)),
+                (" or `that`", None),
+            ]
+        );
+        assert_eq!(
+            line.spans[2].link,
+            Some(CliLink::Copy("https://x.y".to_owned()))
+        );
+
+        let line = CliOutputLine::markdown("## Commands for **you**");
+        assert_eq!(line.text, "Commands for you");
+        assert_eq!(
+            line.segments().collect::<Vec<_>>(),
+            [("Commands for you", Some(CliTextStyle::Heading))]
+        );
+
+        // Unclosed or empty markup is kept
+        for text in ["2 ** 3", "a `b", "[1] and [x]", "#hashtag", "``", "[a]()"] {
+            let line = CliOutputLine::markdown(text);
+            assert_eq!(line.text, text);
+            assert!(line.spans.is_empty());
+        }
+    }
+
+    #[test]
+    fn run_cli_returns_output() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, crate::CliPlugin));
+        app.register_clicommand_noargs("greet", |mut output: CliOutput| {
+            output.println("Hello!");
+        });
+        app.register_clicommand_noargs("twice", |world: &mut World| {
+            let output = world.run_cli("greet; greet");
+            cli_println(world, format!("{} lines", output.len()));
+        });
+        let world = app.world_mut();
+        world.resource_mut::<CliOutputLog>().clear();
+        let output = world.run_cli("twice; nope");
+        let texts: Vec<_> = output.iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(texts[..3], ["Hello!", "Hello!", "2 lines"]);
+        assert_eq!(output[3].severity, CliSeverity::Error);
+        assert!(output[3].text.contains("nope"));
+        // Still added to the log, for the consoles
+        let logged: Vec<_> = world.resource::<CliOutputLog>().iter().cloned().collect();
+        assert_eq!(logged, output);
+    }
+
+    #[test]
+    fn links() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, crate::CliPlugin));
+        app.register_clicommand_noargs("boss", |mut output: CliOutput| {
+            output.link("despawn 4v2", "despawn this");
+        });
+        let output = app.world_mut().run_cli_captured("boss");
+        assert_eq!(output[0].text, "despawn this");
+        assert_eq!(
+            output[0].spans,
+            [CliOutputSpan {
+                range: 0..12,
+                style: CliTextStyle::Link,
+                link: Some(CliLink::Run("despawn 4v2".to_owned())),
+            }]
+        );
+    }
+}
@@ -0,0 +1,505 @@
+//! A command palette that can be used with only a gamepad or arrow keys.
+//!
+//! Requires the `palette` cargo feature. Add [`CliPalettePlugin`] (together
+//! with [`CliPlugin`](crate::CliPlugin)), and press the toggle button
+//! (Select on a gamepad, F2 on a keyboard, by default) to open the palette.
+//! It lists the commands that can run right now, for builds without a
+//! keyboard, like on consoles.
+//!
+//! | Gamepad | Keyboard | In the list | In a form |
+//! |---------|----------|-------------|-----------|
+//! | D-pad up/down | Up/down | Select a command | Select a param |
+//! | D-pad left/right | Left/right | | Change the value |
+//! | Triggers | Page up/down | Move by a page | Change the value in big steps |
+//! | South (A) | Enter | Pick the command | Run the command |
+//! | West (X) | Backspace | | Clear the value |
+//! | East (B) | Escape | Close the palette | Back to the list |
+//!
+//! Picking a command that takes params (as declared by its
+//! [signature](crate::args)) opens a form, with a value picker per param
+//! (see [`CliActionForm`]): numbers are stepped up and down, `bool`s are
+//! toggled, and other values cycle through the candidates of the command's
+//! completer (see
+//! [`set_clicommand_completer`](crate::CliCommandsRegisterExt::set_clicommand_completer)).
+//! Other commands run immediately, without args.
+//!
+//! Commands run with the source `palette` (see [`quota`](crate::quota)), and
+//! the last lines of their output are shown in the palette.
+
+use bevy::prelude::*;
+
+use crate::actions::{CliActionForm, CliActionSchema};
+use crate::args::CliArgType;
+use crate::output::CliCommandsOutputExt;
+use crate::quota::with_cli_source;
+use crate::{parse_cli_tokens, quote_cli_arg, CliCommands};
+
+/// How many commands are shown at once, and how far the triggers move
+const PAGE_SIZE: usize = 12;
+/// How many lines of output of the last command are shown
+const OUTPUT_LINES: usize = 4;
+
+/// Plugin that adds a command palette for gamepads
+pub struct CliPalettePlugin {
+    /// The gamepad button that opens and closes the palette
+    pub toggle_button: GamepadButton,
+    /// The key that opens and closes the palette
+    pub toggle_key: KeyCode,
+}
+
+impl Default for CliPalettePlugin {
+    fn default() -> Self {
+        CliPalettePlugin {
+            toggle_button: GamepadButton::Select,
+            toggle_key: KeyCode::F2,
+        }
+    }
+}
+
+impl Plugin for CliPalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CliPaletteState {
+            open: false,
+            toggle_button: self.toggle_button,
+            toggle_key: self.toggle_key,
+            entries: Vec::new(),
+            selected: 0,
+            form: None,
+            field: 0,
+            message: Vec::new(),
+            pending: Vec::new(),
+        });
+        app.add_systems(Startup, spawn_palette);
+        app.add_systems(
+            Update,
+            (read_input, apply_input, update_palette)
+                .chain()
+                .in_set(CliPaletteSet),
+        );
+    }
+}
+
+/// System set for the systems that run the command palette
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CliPaletteSet;
+
+/// A navigation input of the palette, from a gamepad or keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CliPaletteInput {
+    Toggle,
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Confirm,
+    Clear,
+    Back,
+}
+
+/// The state of the command palette
+#[derive(Resource, Debug)]
+pub struct CliPaletteState {
+    open: bool,
+    toggle_button: GamepadButton,
+    toggle_key: KeyCode,
+    /// The commands that can be picked
+    entries: Vec<CliActionSchema>,
+    selected: usize,
+    /// The form of the picked command, if it takes params
+    form: Option<CliActionForm>,
+    /// The selected field of the form
+    field: usize,
+    /// The output of the last command, or why it could not run
+    message: Vec<String>,
+    pending: Vec<CliPaletteInput>,
+}
+
+impl CliPaletteState {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+    pub fn close(&mut self) {
+        self.open = false;
+        self.form = None;
+    }
+    pub fn toggle(&mut self) {
+        if self.open {
+            self.close();
+        } else {
+            self.open();
+        }
+    }
+}
+
+/// Run condition that is true while the palette is open
+pub fn cli_palette_open(state: Option<Res<CliPaletteState>>) -> bool {
+    state.is_some_and(|state| state.open)
+}
+
+/// Run condition that is true while the palette is closed
+pub fn cli_palette_closed(state: Option<Res<CliPaletteState>>) -> bool {
+    !cli_palette_open(state)
+}
+
+/// Marker for the root entity of the command palette
+#[derive(Component)]
+pub struct CliPaletteRoot;
+
+#[derive(Component)]
+struct CliPaletteText;
+
+fn spawn_palette(mut commands: Commands) {
+    commands
+        .spawn((
+            CliPaletteRoot,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(10.0),
+                left: Val::Percent(25.0),
+                width: Val::Percent(50.0),
+                padding: UiRect::all(Val::Px(12.0)),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.05, 0.05, 0.08, 0.92)),
+            GlobalZIndex(i32::MAX),
+            Visibility::Hidden,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                CliPaletteText,
+                Text::new(""),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.85, 0.85, 0.85)),
+            ));
+        });
+}
+
+fn read_input(
+    kbd: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut state: ResMut<CliPaletteState>,
+) {
+    const KEYS: [(KeyCode, CliPaletteInput); 9] = [
+        (KeyCode::ArrowUp, CliPaletteInput::Up),
+        (KeyCode::ArrowDown, CliPaletteInput::Down),
+        (KeyCode::ArrowLeft, CliPaletteInput::Left),
+        (KeyCode::ArrowRight, CliPaletteInput::Right),
+        (KeyCode::PageUp, CliPaletteInput::PageUp),
+        (KeyCode::PageDown, CliPaletteInput::PageDown),
+        (KeyCode::Enter, CliPaletteInput::Confirm),
+        (KeyCode::Backspace, CliPaletteInput::Clear),
+        (KeyCode::Escape, CliPaletteInput::Back),
+    ];
+    const BUTTONS: [(GamepadButton, CliPaletteInput); 9] = [
+        (GamepadButton::DPadUp, CliPaletteInput::Up),
+        (GamepadButton::DPadDown, CliPaletteInput::Down),
+        (GamepadButton::DPadLeft, CliPaletteInput::Left),
+        (GamepadButton::DPadRight, CliPaletteInput::Right),
+        (GamepadButton::LeftTrigger, CliPaletteInput::PageUp),
+        (GamepadButton::RightTrigger, CliPaletteInput::PageDown),
+        (GamepadButton::South, CliPaletteInput::Confirm),
+        (GamepadButton::West, CliPaletteInput::Clear),
+        (GamepadButton::East, CliPaletteInput::Back),
+    ];
+    let toggle_key = state.toggle_key;
+    let toggle_button = state.toggle_button;
+    if kbd.just_pressed(toggle_key)
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad.just_pressed(toggle_button))
+    {
+        state.pending.push(CliPaletteInput::Toggle);
+    }
+    if !state.open {
+        return;
+    }
+    for (key, input) in KEYS {
+        if kbd.just_pressed(key) {
+            state.pending.push(input);
+        }
+    }
+    for gamepad in &gamepads {
+        for (button, input) in BUTTONS {
+            if gamepad.just_pressed(button) {
+                state.pending.push(input);
+            }
+        }
+    }
+}
+
+fn apply_input(world: &mut World) {
+    if world.resource::<CliPaletteState>().pending.is_empty() {
+        return;
+    }
+    let pending = std::mem::take(&mut world.resource_mut::<CliPaletteState>().pending);
+    for input in pending {
+        let mut state = world.resource_mut::<CliPaletteState>();
+        if input == CliPaletteInput::Toggle {
+            state.toggle();
+            if state.open {
+                refresh_entries(world);
+            }
+            continue;
+        }
+        if !state.open {
+            continue;
+        }
+        if state.form.is_some() {
+            apply_form_input(world, input);
+        } else {
+            apply_list_input(world, input);
+        }
+    }
+}
+
+/// List the commands that can run right now
+fn refresh_entries(world: &mut World) {
+    let schemas = world
+        .get_resource::<CliCommands>()
+        .map(|clicommands| clicommands.action_schemas())
+        .unwrap_or_default();
+    let entries: Vec<CliActionSchema> = schemas
+        .into_iter()
+        .filter(|schema| CliCommands::is_runnable(world, &schema.name).is_available())
+        .collect();
+    let mut state = world.resource_mut::<CliPaletteState>();
+    state.selected = state.selected.min(entries.len().saturating_sub(1));
+    state.entries = entries;
+}
+
+fn apply_list_input(world: &mut World, input: CliPaletteInput) {
+    let mut state = world.resource_mut::<CliPaletteState>();
+    let last = state.entries.len().saturating_sub(1);
+    match input {
+        CliPaletteInput::Up => state.selected = state.selected.saturating_sub(1),
+        CliPaletteInput::Down => state.selected = (state.selected + 1).min(last),
+        CliPaletteInput::PageUp => state.selected = state.selected.saturating_sub(PAGE_SIZE),
+        CliPaletteInput::PageDown => state.selected = (state.selected + PAGE_SIZE).min(last),
+        CliPaletteInput::Back => state.close(),
+        CliPaletteInput::Confirm => {
+            let Some(schema) = state.entries.get(state.selected).cloned() else {
+                return;
+            };
+            match CliActionForm::new(&schema) {
+                Some(form) if !form.fields.is_empty() => {
+                    state.form = Some(form);
+                    state.field = 0;
+                    state.message.clear();
+                }
+                _ => run_palette_command(world, quote_cli_arg(&schema.name).into_owned()),
+            }
+        }
+        _ => {}
+    }
+}
+
+fn apply_form_input(world: &mut World, input: CliPaletteInput) {
+    let mut state = world.resource_mut::<CliPaletteState>();
+    let field = state.field;
+    let Some(form) = &state.form else {
+        return;
+    };
+    let last = form.fields.len().saturating_sub(1);
+    match input {
+        CliPaletteInput::Up => state.field = field.saturating_sub(1),
+        CliPaletteInput::Down => state.field = (field + 1).min(last),
+        CliPaletteInput::Back => {
+            state.form = None;
+            state.message.clear();
+        }
+        CliPaletteInput::Clear => {
+            if let Some(field) = state.form.as_mut().and_then(|f| f.fields.get_mut(field)) {
+                field.value.clear();
+            }
+        }
+        CliPaletteInput::Confirm => match form.to_action() {
+            Ok(action) => {
+                let cli = action.to_cli();
+                run_palette_command(world, cli);
+            }
+            Err(e) => state.message = vec![format!("{}!", e)],
+        },
+        CliPaletteInput::Left | CliPaletteInput::Right => change_value(world, input, 1),
+        CliPaletteInput::PageUp | CliPaletteInput::PageDown => change_value(world, input, 10),
+        CliPaletteInput::Toggle => {}
+    }
+}
+
+/// Change the value of the selected field of the form, with its picker
+fn change_value(world: &mut World, input: CliPaletteInput, steps: i64) {
+    let state = world.resource::<CliPaletteState>();
+    let Some(form) = &state.form else {
+        return;
+    };
+    let Some(field) = form.fields.get(state.field) else {
+        return;
+    };
+    let up = matches!(input, CliPaletteInput::Right | CliPaletteInput::PageDown);
+    let delta = if up { steps } else { -steps };
+    let ty = CliArgType::from_name(&field.param.ty);
+    let value = match ty {
+        Some(ty) if is_numeric(ty) => step_number(ty, &field.value, delta),
+        _ => {
+            let mut choices = match ty {
+                Some(CliArgType::Bool) => vec!["true".to_owned(), "false".to_owned()],
+                _ => completer_choices(world, form, state.field),
+            };
+            if field.param.optional {
+                choices.insert(0, String::new());
+            }
+            cycle(&choices, &field.value, delta.signum())
+        }
+    };
+    let mut state = world.resource_mut::<CliPaletteState>();
+    let selected = state.field;
+    if let (Some(value), Some(form)) = (value, &mut state.form) {
+        form.fields[selected].value = value;
+    }
+}
+
+fn is_numeric(ty: CliArgType) -> bool {
+    !matches!(
+        ty,
+        CliArgType::Bool | CliArgType::Char | CliArgType::String | CliArgType::Entity
+    )
+}
+
+/// Step a number up or down, by 1 for integers and 0.1 for floats
+///
+/// Returns `None` if the result is out of range for the type.
+fn step_number(ty: CliArgType, value: &str, delta: i64) -> Option<String> {
+    let stepped = match ty {
+        CliArgType::F32 | CliArgType::F64 => {
+            let value = value.parse::<f64>().unwrap_or(0.0);
+            let tenths = (value * 10.0).round() as i64 + delta;
+            format!("{}", tenths as f64 / 10.0)
+        }
+        _ => {
+            let value = value.parse::<i128>().unwrap_or(0);
+            (value + i128::from(delta)).to_string()
+        }
+    };
+    ty.validate(&stepped).then_some(stepped)
+}
+
+/// The next or previous choice after the current value
+fn cycle(choices: &[String], current: &str, direction: i64) -> Option<String> {
+    if choices.is_empty() {
+        return None;
+    }
+    let len = choices.len() as i64;
+    let next = match choices.iter().position(|choice| choice == current) {
+        Some(i) => (i as i64 + direction).rem_euclid(len),
+        None if direction > 0 => 0,
+        None => len - 1,
+    };
+    Some(choices[next as usize].clone())
+}
+
+/// The candidates of the command's completer for a field
+fn completer_choices(world: &World, form: &CliActionForm, index: usize) -> Vec<String> {
+    let Some(clicommands) = world.get_resource::<CliCommands>() else {
+        return Vec::new();
+    };
+    let mut partial = quote_cli_arg(&form.name).into_owned();
+    for field in &form.fields[..index] {
+        partial.push(' ');
+        partial.push_str(&quote_cli_arg(&field.value));
+    }
+    partial.push(' ');
+    clicommands
+        .complete(world, &partial)
+        .into_iter()
+        .filter_map(|completion| {
+            // Completions are quoted as needed
+            let words = parse_cli_tokens(&completion.replacement).ok()?;
+            words.into_iter().next().map(|word| word.text)
+        })
+        .collect()
+}
+
+fn run_palette_command(world: &mut World, cli: String) {
+    let output = with_cli_source(world, "palette", |world| world.run_cli_captured(&cli));
+    let skip = output.len().saturating_sub(OUTPUT_LINES);
+    let mut state = world.resource_mut::<CliPaletteState>();
+    state.message = output
+        .into_iter()
+        .skip(skip)
+        .map(|line| line.text)
+        .collect();
+    if state.message.is_empty() {
+        state.message.push(format!("Ran: {}", cli));
+    }
+    refresh_entries(world);
+}
+
+fn update_palette(
+    state: Res<CliPaletteState>,
+    mut q_root: Query<&mut Visibility, With<CliPaletteRoot>>,
+    mut q_text: Query<&mut Text, With<CliPaletteText>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    if let Ok(mut visibility) = q_root.get_single_mut() {
+        *visibility = if state.open {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    let Ok(mut text) = q_text.get_single_mut() else {
+        return;
+    };
+    let mut lines = Vec::new();
+    match &state.form {
+        Some(form) => {
+            lines.push(form.name.clone());
+            for (i, field) in form.fields.iter().enumerate() {
+                let cursor = if i == state.field { ">" } else { " " };
+                let param = match field.param.optional {
+                    true => format!("[{}: {}]", field.param.name, field.param.ty),
+                    false => format!("<{}: {}>", field.param.name, field.param.ty),
+                };
+                lines.push(format!("{} {} = {}", cursor, param, field.value));
+            }
+            lines.push(String::new());
+            lines.push("A: run  B: back  X: clear  Left/Right: change".to_owned());
+        }
+        None if state.entries.is_empty() => lines.push("No commands available".to_owned()),
+        None => {
+            let start = state.selected.saturating_sub(PAGE_SIZE / 2);
+            let end = (start + PAGE_SIZE).min(state.entries.len());
+            let start = end.saturating_sub(PAGE_SIZE);
+            for (i, schema) in state.entries[start..end].iter().enumerate() {
+                let cursor = if start + i == state.selected {
+                    ">"
+                } else {
+                    " "
+                };
+                match &schema.description {
+                    Some(description) => {
+                        lines.push(format!("{} {} - {}", cursor, schema.name, description))
+                    }
+                    None => lines.push(format!("{} {}", cursor, schema.name)),
+                }
+            }
+            lines.push(String::new());
+            lines.push("A: pick  B: close".to_owned());
+        }
+    }
+    if !state.message.is_empty() {
+        lines.push(String::new());
+        lines.extend(state.message.iter().cloned());
+    }
+    text.0 = lines.join("\n");
+}
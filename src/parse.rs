@@ -0,0 +1,922 @@
+//! Parsing of command strings.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::Range;
+
+use crate::mention::parse_mention;
+
+/// A command string that has been parsed, but not executed
+///
+/// Obtained from [`parse_cli`]. This is exactly how the command string
+/// would be interpreted by [`run_cli`](crate::CliCommandsRunExt::run_cli),
+/// so tools (editors, validators, highlighters) can reuse the same rules.
+///
+/// Spans are byte ranges into the original string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedCli {
+    pub(crate) name: String,
+    pub(crate) name_span: Range<usize>,
+    pub(crate) args: Vec<String>,
+    pub(crate) arg_spans: Vec<Range<usize>>,
+    pub(crate) tokens: Vec<CliToken>,
+}
+
+/// What kind of syntax a [`CliToken`] is
+///
+/// More kinds may be added as the command syntax grows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CliTokenKind {
+    /// The command name
+    Command,
+    /// A plain argument
+    Arg,
+    /// An argument containing quotes
+    String,
+    /// An unquoted argument starting with `-`, like `--force` (but not a negative number)
+    Flag,
+    /// An argument referring to a variable, like `$name` (see [`env`](crate::env))
+    Variable,
+    /// An operator chaining commands, like `&&`
+    Operator,
+    /// An argument mentioning a player, like `@Bob` (see [`mention`](crate::mention))
+    Mention,
+}
+
+/// A classified piece of a command string, for syntax highlighting
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliToken {
+    pub kind: CliTokenKind,
+    /// Byte range in the original string
+    pub span: Range<usize>,
+}
+
+impl ParsedCli {
+    /// The name of the command to run
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Where the command name is in the original string
+    pub fn name_span(&self) -> Range<usize> {
+        self.name_span.clone()
+    }
+    /// The args to pass to the command
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+    /// Where each of the args is in the original string
+    pub fn arg_spans(&self) -> &[Range<usize>] {
+        &self.arg_spans
+    }
+    /// The args that are flags, like `--force` or `-x`, in order
+    ///
+    /// These are the args whose token is a [`CliTokenKind::Flag`]: unquoted,
+    /// starting with `-`, and not a negative number. They are still part of
+    /// [`args`](Self::args).
+    pub fn flags(&self) -> impl Iterator<Item = &str> {
+        // The first token is the command name, then one per arg
+        self.args
+            .iter()
+            .zip(self.tokens.iter().skip(1))
+            .filter(|(_, token)| token.kind == CliTokenKind::Flag)
+            .map(|(arg, _)| arg.as_str())
+    }
+    /// All the tokens in the original string, classified by kind, in order
+    ///
+    /// Intended for syntax highlighting of console input or script files.
+    pub fn tokens(&self) -> &[CliToken] {
+        &self.tokens
+    }
+    /// Remove a flag handled by the crate (like `--explain`) from the args,
+    /// returning whether it was there
+    ///
+    /// A quoted flag, or one after a bare `--`, is an ordinary arg.
+    pub(crate) fn take_flag(&mut self, flag: &str) -> bool {
+        let mut found = false;
+        let mut i = 0;
+        while i < self.args.len() {
+            // The first token is the command name
+            let bare = self
+                .tokens
+                .get(i + 1)
+                .is_some_and(|token| token.kind == CliTokenKind::Flag);
+            match self.args[i].as_str() {
+                "--" if bare => break,
+                arg if bare && arg == flag => {
+                    self.args.remove(i);
+                    self.arg_spans.remove(i);
+                    self.tokens.remove(i + 1);
+                    found = true;
+                }
+                _ => i += 1,
+            }
+        }
+        found
+    }
+}
+
+/// A single word of a command string, after quotes and escapes are processed
+///
+/// Obtained from [`parse_cli_tokens`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliWord {
+    /// The text of the word, with quotes and escapes removed
+    pub text: String,
+    /// Byte range in the original string, including any quotes
+    pub span: Range<usize>,
+    /// Whether any part of the word was quoted or escaped
+    ///
+    /// Useful to tell apart a literal `"--"` from a bare `--`.
+    pub quoted: bool,
+}
+
+impl CliWord {
+    /// The chaining operator this word is, if any
+    pub fn operator(&self) -> Option<CliChainOp> {
+        if self.quoted {
+            return None;
+        }
+        match self.text.as_str() {
+            "&&" => Some(CliChainOp::And),
+            "||" => Some(CliChainOp::Or),
+            ";" => Some(CliChainOp::Then),
+            "|" if cfg!(feature = "parser-advanced") => Some(CliChainOp::Pipe),
+            _ => None,
+        }
+    }
+}
+
+/// An operator joining the commands of a [`CliChain`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CliChainOp {
+    /// `&&`: run the next command only if the previous one succeeded
+    And,
+    /// `||`: run the next command only if the previous one failed
+    Or,
+    /// `;`: run the next command regardless of the outcome
+    ///
+    /// Unless [`CliConfig::stop_on_error`](crate::CliConfig::stop_on_error) is set.
+    Then,
+    /// `|`: run the next command only if the previous one succeeded, with
+    /// its output lines as extra args (with the `parser-advanced` feature,
+    /// see `pipe`)
+    Pipe,
+}
+
+impl CliChainOp {
+    /// Whether the command after this operator should run, given the outcome so far
+    pub fn should_run(self, success: bool) -> bool {
+        match self {
+            CliChainOp::And => success,
+            CliChainOp::Or => !success,
+            CliChainOp::Then => true,
+            CliChainOp::Pipe => success,
+        }
+    }
+}
+
+/// One command of a [`CliChain`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliChainLink {
+    /// The operator before this command (`None` for the first command)
+    pub op: Option<CliChainOp>,
+    pub command: ParsedCli,
+    /// Byte range of the command (without the operator) in the original string
+    pub span: Range<usize>,
+}
+
+/// A command string containing multiple commands joined by operators
+///
+/// Obtained from [`parse_cli_chain`]. A string without operators is a chain of one command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliChain {
+    links: Vec<CliChainLink>,
+    operator_spans: Vec<Range<usize>>,
+}
+
+impl CliChain {
+    /// The commands in the chain, in order
+    pub fn links(&self) -> &[CliChainLink] {
+        &self.links
+    }
+    pub fn len(&self) -> usize {
+        self.links.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+    /// All the tokens in the original string, classified by kind, in order
+    pub fn tokens(&self) -> Vec<CliToken> {
+        let mut tokens: Vec<CliToken> = self
+            .links
+            .iter()
+            .flat_map(|link| link.command.tokens.iter().cloned())
+            .chain(self.operator_spans.iter().map(|span| CliToken {
+                kind: CliTokenKind::Operator,
+                span: span.clone(),
+            }))
+            .collect();
+        tokens.sort_by_key(|token| token.span.start);
+        tokens
+    }
+}
+
+/// Error when a command string cannot be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CliParseError {
+    /// The string does not contain a command name
+    Empty,
+    /// A quote was opened at the given byte offset, but never closed
+    UnterminatedQuote(usize),
+    /// The string ends with a backslash, at the given byte offset
+    DanglingEscape(usize),
+    /// There is no command next to the operator at the given byte offset
+    MissingCommand(usize),
+    /// The string contains an operator at the given byte offset, where only
+    /// a single command is expected (use [`parse_cli_chain`] instead)
+    UnexpectedOperator(usize),
+    /// A command substitution (`$(...)`, see [`env`](crate::env)) was
+    /// opened at the given byte offset, but never closed
+    UnterminatedSubstitution(usize),
+}
+
+impl fmt::Display for CliParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliParseError::Empty => write!(f, "empty command string"),
+            CliParseError::UnterminatedQuote(pos) => {
+                write!(f, "unterminated quote at position {}", pos)
+            }
+            CliParseError::DanglingEscape(pos) => {
+                write!(f, "dangling escape at position {}", pos)
+            }
+            CliParseError::MissingCommand(pos) => {
+                write!(f, "missing command next to operator at position {}", pos)
+            }
+            CliParseError::UnexpectedOperator(pos) => {
+                write!(f, "unexpected operator at position {}", pos)
+            }
+            CliParseError::UnterminatedSubstitution(pos) => {
+                write!(f, "unterminated command substitution at position {}", pos)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CliParseError {}
+
+impl CliParseError {
+    /// The part of the command string `cli` that the error is about, if any
+    ///
+    /// `cli` must be the string that failed to parse. Use [`cli_caret`] to
+    /// show it to the user.
+    pub fn span(&self, cli: &str) -> Option<Range<usize>> {
+        match *self {
+            CliParseError::Empty => None,
+            CliParseError::UnterminatedQuote(pos)
+            | CliParseError::DanglingEscape(pos)
+            | CliParseError::UnterminatedSubstitution(pos) => Some(pos..cli.len()),
+            CliParseError::MissingCommand(pos) | CliParseError::UnexpectedOperator(pos) => {
+                Some(pos..pos + operator_at(cli, pos).map_or(1, str::len))
+            }
+        }
+    }
+}
+
+/// Parse a command string into the command name and its args, without running it
+///
+/// The string is split into words as by [`parse_cli_tokens`]. The first word
+/// is the command name, and the rest are the args. Strings with operators
+/// (like `&&`) are an error; use [`parse_cli_chain`] for those.
+///
+/// This must never panic, regardless of input, as command strings may come
+/// from untrusted sources. It is fuzzed by the harness in the `fuzz` directory.
+pub fn parse_cli(cli: &str) -> Result<ParsedCli, CliParseError> {
+    let words = parse_cli_tokens(cli)?;
+    if let Some(op) = words.iter().find(|word| word.operator().is_some()) {
+        return Err(CliParseError::UnexpectedOperator(op.span.start));
+    }
+    parse_words(cli, words)
+}
+
+/// Parse a command string that may contain multiple commands joined by operators
+///
+/// Supported operators are:
+///  - `cmd1; cmd2`: run `cmd1`, then `cmd2`
+///  - `cmd1 && cmd2`: run `cmd2` only if `cmd1` succeeded
+///  - `cmd1 || cmd2`: run `cmd2` only if `cmd1` failed
+///  - `cmd1 | cmd2`: run `cmd2` only if `cmd1` succeeded, with the output
+///    of `cmd1` as extra args (with the `parser-advanced` feature, see `pipe`)
+///
+/// Operators are evaluated left to right, and do not need to be surrounded
+/// by whitespace. A trailing `;` is allowed. To pass `;`, `&&`, `||` or `|`
+/// as an arg, quote or escape it (`"a;b"`, `a\;b`).
+///
+/// This must never panic, regardless of input, as command strings may come
+/// from untrusted sources. It is fuzzed by the harness in the `fuzz` directory.
+pub fn parse_cli_chain(cli: &str) -> Result<CliChain, CliParseError> {
+    let mut links = Vec::new();
+    let mut operator_spans = Vec::new();
+    let mut op = None;
+    let mut words = Vec::new();
+    for word in parse_cli_tokens(cli)? {
+        let Some(next_op) = word.operator() else {
+            words.push(word);
+            continue;
+        };
+        if words.is_empty() {
+            return Err(CliParseError::MissingCommand(word.span.start));
+        }
+        links.push(chain_link(cli, op, std::mem::take(&mut words))?);
+        op = Some(next_op);
+        operator_spans.push(word.span);
+    }
+    if words.is_empty() && op == Some(CliChainOp::Then) {
+        // Trailing `;`
+        return Ok(CliChain {
+            links,
+            operator_spans,
+        });
+    }
+    if words.is_empty() {
+        return Err(match operator_spans.last() {
+            Some(span) => CliParseError::MissingCommand(span.start),
+            None => CliParseError::Empty,
+        });
+    }
+    links.push(chain_link(cli, op, words)?);
+    Ok(CliChain {
+        links,
+        operator_spans,
+    })
+}
+
+fn chain_link(
+    cli: &str,
+    op: Option<CliChainOp>,
+    words: Vec<CliWord>,
+) -> Result<CliChainLink, CliParseError> {
+    let start = words.first().map_or(0, |word| word.span.start);
+    let end = words.last().map_or(0, |word| word.span.end);
+    Ok(CliChainLink {
+        op,
+        command: parse_words(cli, words)?,
+        span: start..end,
+    })
+}
+
+fn parse_words(cli: &str, words: Vec<CliWord>) -> Result<ParsedCli, CliParseError> {
+    let mut words = words.into_iter();
+    let name = words.next().ok_or(CliParseError::Empty)?;
+    let mut tokens = vec![CliToken {
+        kind: CliTokenKind::Command,
+        span: name.span.clone(),
+    }];
+    let mut args = Vec::new();
+    let mut arg_spans = Vec::new();
+    for word in words {
+        tokens.push(CliToken {
+            kind: arg_kind(cli, &word),
+            span: word.span.clone(),
+        });
+        args.push(word.text);
+        arg_spans.push(word.span);
+    }
+    Ok(ParsedCli {
+        name: name.text,
+        name_span: name.span,
+        args,
+        arg_spans,
+        tokens,
+    })
+}
+
+/// Classify an arg for syntax highlighting
+fn arg_kind(cli: &str, word: &CliWord) -> CliTokenKind {
+    let raw = cli.get(word.span.clone()).unwrap_or_default();
+    if has_variable(raw) {
+        CliTokenKind::Variable
+    } else if parse_mention(&word.text).is_some() {
+        CliTokenKind::Mention
+    } else if word.quoted {
+        CliTokenKind::String
+    } else if word.text.len() > 1 && word.text.starts_with('-') && word.text.parse::<f64>().is_err()
+    {
+        CliTokenKind::Flag
+    } else {
+        CliTokenKind::Arg
+    }
+}
+
+/// Whether a word, as written, refers to a variable that would be expanded
+///
+/// Follows the rules of [`CliEnv::expand`](crate::env::CliEnv::expand).
+fn has_variable(raw: &str) -> bool {
+    let mut in_single_quotes = false;
+    let mut in_double_quotes = false;
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' if !in_double_quotes => in_single_quotes = !in_single_quotes,
+            '"' if !in_single_quotes => in_double_quotes = !in_double_quotes,
+            '\\' if !in_single_quotes => {
+                chars.next();
+            }
+            '$' if !in_single_quotes => {
+                let next = chars.clone().next();
+                if next
+                    .is_some_and(|c| c.is_ascii_alphabetic() || matches!(c, '_' | '{' | '?' | '('))
+                {
+                    return true;
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Split a command string into words, processing quotes and escapes
+///
+/// Words are separated by ASCII whitespace. Within a word:
+///  - `"..."` quotes a section, which may contain whitespace and the escapes below
+///  - `'...'` quotes a section literally, without processing escapes
+///  - `\` escapes the next character; `\n` and `\t` are a newline and a tab
+///
+/// Quoted and unquoted sections next to each other form a single word,
+/// so `--name="Big Bob"` is one word. `""` is an empty word.
+///
+/// A command substitution (`$(...)`, see [`env`](crate::env)) is kept as it
+/// is, as part of the word, even if it contains whitespace or operators.
+///
+/// Unquoted operators (`;`, `&&`, `||` and `|`, see [`parse_cli_chain`]) are always
+/// words of their own, even without whitespace around them.
+///
+/// Intended for console UIs that need to split input exactly like [`parse_cli`]
+/// (for example, to know which arg the cursor is in, for completions).
+pub fn parse_cli_tokens(cli: &str) -> Result<Vec<CliWord>, CliParseError> {
+    let mut words = Vec::new();
+    let mut chars = cli.char_indices().peekable();
+    loop {
+        while chars.next_if(|(_, c)| c.is_ascii_whitespace()).is_some() {}
+        let Some(&(start, _)) = chars.peek() else {
+            break;
+        };
+        if let Some(op) = operator_at(cli, start) {
+            // `op` is ASCII, so this skips exactly its chars
+            for _ in 0..op.len() {
+                chars.next();
+            }
+            words.push(CliWord {
+                text: op.to_owned(),
+                span: start..start + op.len(),
+                quoted: false,
+            });
+            continue;
+        }
+        let mut text = String::new();
+        let mut quoted = false;
+        let mut end = start;
+        while let Some((i, c)) =
+            chars.next_if(|&(i, c)| !c.is_ascii_whitespace() && operator_at(cli, i).is_none())
+        {
+            end = i + c.len_utf8();
+            match c {
+                '\\' => {
+                    let (j, escaped) = chars.next().ok_or(CliParseError::DanglingEscape(i))?;
+                    quoted = true;
+                    text.push(unescape(escaped));
+                    end = j + escaped.len_utf8();
+                }
+                '$' if cli[i..].starts_with("$(") => {
+                    end = skip_substitution(cli, i, &mut chars)?;
+                    text.push_str(&cli[i..end]);
+                }
+                '"' => {
+                    quoted = true;
+                    loop {
+                        match chars.next() {
+                            Some((j, '"')) => {
+                                end = j + 1;
+                                break;
+                            }
+                            Some((j, '$')) if cli[j..].starts_with("$(") => {
+                                let end = skip_substitution(cli, j, &mut chars)?;
+                                text.push_str(&cli[j..end]);
+                            }
+                            Some((_, '\\')) => {
+                                let (_, escaped) =
+                                    chars.next().ok_or(CliParseError::UnterminatedQuote(i))?;
+                                text.push(unescape(escaped));
+                            }
+                            Some((_, c)) => text.push(c),
+                            None => return Err(CliParseError::UnterminatedQuote(i)),
+                        }
+                    }
+                }
+                '\'' => {
+                    quoted = true;
+                    loop {
+                        match chars.next() {
+                            Some((j, '\'')) => {
+                                end = j + 1;
+                                break;
+                            }
+                            Some((_, c)) => text.push(c),
+                            None => return Err(CliParseError::UnterminatedQuote(i)),
+                        }
+                    }
+                }
+                c => text.push(c),
+            }
+        }
+        words.push(CliWord {
+            text,
+            span: start..end,
+            quoted,
+        });
+    }
+    Ok(words)
+}
+
+/// Skip the rest of the command substitution starting at byte offset `start`,
+/// whose `$` was just taken from `chars`, returning where it ends
+fn skip_substitution(
+    cli: &str,
+    start: usize,
+    chars: &mut std::iter::Peekable<std::str::CharIndices>,
+) -> Result<usize, CliParseError> {
+    let end = substitution_end(cli, start).ok_or(CliParseError::UnterminatedSubstitution(start))?;
+    while chars.next_if(|&(j, _)| j < end).is_some() {}
+    Ok(end)
+}
+
+/// Where the command substitution (`$(...)`) starting at byte offset `start` ends
+///
+/// Parentheses nest, and those in quotes or escaped do not count. Returns
+/// `None` if it is never closed.
+pub(crate) fn substitution_end(cli: &str, start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut chars = cli.get(start + 2..)?.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '\'' => {
+                chars.find(|&(_, c)| c == '\'')?;
+            }
+            '"' => loop {
+                match chars.next()? {
+                    (_, '"') => break,
+                    (_, '\\') => {
+                        chars.next();
+                    }
+                    _ => {}
+                }
+            },
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(start + 2 + i + 1),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The operator starting at byte offset `i`, if any
+fn operator_at(cli: &str, i: usize) -> Option<&'static str> {
+    let rest = cli.get(i..)?;
+    // `||` before `|`, so that it is not taken as two pipes
+    ["&&", "||", ";", "|"]
+        .into_iter()
+        .filter(|&op| op != "|" || cfg!(feature = "parser-advanced"))
+        .find(|op| rest.starts_with(op))
+}
+
+/// Point out a part of a command string, with carets under it
+///
+/// Returns two indented lines: the command string, and carets under the
+/// given byte range. An empty range (like the end of the string, for
+/// something missing) gets a single caret.
+///
+/// ```text
+///   spawn 1 abc
+///           ^^^
+/// ```
+pub fn cli_caret(cli: &str, span: Range<usize>) -> String {
+    let start = span.start.min(cli.len());
+    let end = span.end.clamp(start, cli.len());
+    let (Some(before), Some(under)) = (cli.get(..start), cli.get(start..end)) else {
+        return format!("  {}", cli);
+    };
+    // Keep tabs, so that the carets line up with the text above
+    let pad: String = before
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let carets = "^".repeat(under.chars().count().max(1));
+    format!("  {}\n  {}{}", cli, pad, carets)
+}
+
+/// Format an arg so that it is parsed back as a single word with the same text
+///
+/// `$` is escaped, so that the arg is not changed by variable expansion (see
+/// [`env`](crate::env)) when the result is run as a command string. Args
+/// that need no quoting are returned unchanged.
+pub fn quote_cli_arg(arg: &str) -> Cow<'_, str> {
+    let needs_quotes = arg.is_empty()
+        || arg.contains("&&")
+        || arg
+            .chars()
+            .any(|c| c.is_ascii_whitespace() || matches!(c, '"' | '\'' | '\\' | ';' | '|' | '$'));
+    if !needs_quotes {
+        return Cow::Borrowed(arg);
+    }
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    for c in arg.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            // So that it is not expanded again as a variable (see `env`)
+            '$' => quoted.push_str("\\$"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    Cow::Owned(quoted)
+}
+
+fn unescape(c: char) -> char {
+    match c {
+        'n' => '\n',
+        't' => '\t',
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(cli: &str) -> Vec<String> {
+        parse_cli_tokens(cli)
+            .unwrap()
+            .into_iter()
+            .map(|word| word.text)
+            .collect()
+    }
+
+    #[test]
+    fn quotes_and_escapes() {
+        assert_eq!(texts("  spawn   1\\t2  "), ["spawn", "1\t2"]);
+        assert_eq!(
+            texts(r#"say "hello world" 'a "b"'"#),
+            ["say", "hello world", "a \"b\""]
+        );
+        assert_eq!(
+            texts(r#"echo "a\"b" 'no\n' a\ b \n"#),
+            ["echo", "a\"b", "no\\n", "a b", "\n"]
+        );
+        assert_eq!(
+            texts(r#"set --name="Big Bob" "" ''"#),
+            ["set", "--name=Big Bob", "", ""]
+        );
+    }
+
+    #[test]
+    fn word_spans_and_quoting() {
+        let cli = r#"spawn "a b" c"#;
+        let words = parse_cli_tokens(cli).unwrap();
+        let spans: Vec<&str> = words.iter().map(|word| &cli[word.span.clone()]).collect();
+        assert_eq!(spans, ["spawn", "\"a b\"", "c"]);
+        let quoted: Vec<bool> = words.iter().map(|word| word.quoted).collect();
+        assert_eq!(quoted, [false, true, false]);
+    }
+
+    #[test]
+    fn tokenizer_errors() {
+        assert_eq!(
+            parse_cli_tokens("say \"hi"),
+            Err(CliParseError::UnterminatedQuote(4))
+        );
+        assert_eq!(
+            parse_cli_tokens("say 'hi"),
+            Err(CliParseError::UnterminatedQuote(4))
+        );
+        assert_eq!(
+            parse_cli_tokens("say hi\\"),
+            Err(CliParseError::DanglingEscape(6))
+        );
+        assert_eq!(parse_cli(""), Err(CliParseError::Empty));
+        assert_eq!(parse_cli("   "), Err(CliParseError::Empty));
+    }
+
+    #[test]
+    fn quote_roundtrip() {
+        for arg in [
+            "plain",
+            "",
+            "a b",
+            "say \"hi\"",
+            "it's",
+            "a\\b",
+            "x;y",
+            "a&&b",
+            "p||q",
+            "p|q",
+            "\n\t",
+            "$x",
+        ] {
+            let quoted = quote_cli_arg(arg);
+            assert_eq!(texts(&format!("cmd {}", quoted))[1..], [arg], "{}", quoted);
+        }
+        assert!(matches!(quote_cli_arg("plain"), Cow::Borrowed("plain")));
+    }
+
+    fn chain(cli: &str) -> Vec<(Option<CliChainOp>, &str)> {
+        parse_cli_chain(cli)
+            .unwrap()
+            .links()
+            .iter()
+            .map(|link| (link.op, &cli[link.span.clone()]))
+            .collect()
+    }
+
+    #[test]
+    fn and_or_chains() {
+        use CliChainOp::*;
+        assert_eq!(
+            chain("load 1 && spawn 2||echo failed"),
+            [
+                (None, "load 1"),
+                (Some(And), "spawn 2"),
+                (Some(Or), "echo failed")
+            ]
+        );
+        assert_eq!(
+            chain(r#"echo "a && b" a\|\|b"#),
+            [(None, r#"echo "a && b" a\|\|b"#)]
+        );
+        assert!(And.should_run(true) && !And.should_run(false));
+        assert!(Or.should_run(false) && !Or.should_run(true));
+    }
+
+    #[test]
+    fn chain_errors() {
+        assert_eq!(
+            parse_cli_chain("&& spawn"),
+            Err(CliParseError::MissingCommand(0))
+        );
+        assert_eq!(
+            parse_cli_chain("spawn &&"),
+            Err(CliParseError::MissingCommand(6))
+        );
+        assert_eq!(
+            parse_cli_chain("a && || b"),
+            Err(CliParseError::MissingCommand(5))
+        );
+        assert_eq!(
+            parse_cli("a && b"),
+            Err(CliParseError::UnexpectedOperator(2))
+        );
+        let error = parse_cli_chain("spawn &&").unwrap_err();
+        assert_eq!(error.span("spawn &&"), Some(6..8));
+    }
+
+    #[test]
+    fn semicolon_chains() {
+        use CliChainOp::*;
+        assert_eq!(
+            chain("spawn 1; spawn 2 ;despawn"),
+            [
+                (None, "spawn 1"),
+                (Some(Then), "spawn 2"),
+                (Some(Then), "despawn")
+            ]
+        );
+        assert_eq!(chain("spawn 1;"), [(None, "spawn 1")]);
+        assert_eq!(chain(r#"echo "a;b" a\;b"#), [(None, r#"echo "a;b" a\;b"#)]);
+        assert_eq!(
+            parse_cli_chain("a;;b"),
+            Err(CliParseError::MissingCommand(2))
+        );
+        assert_eq!(parse_cli_chain(";"), Err(CliParseError::MissingCommand(0)));
+        assert!(Then.should_run(true) && Then.should_run(false));
+    }
+
+    #[test]
+    #[cfg(feature = "parser-advanced")]
+    fn pipe_chains() {
+        use CliChainOp::*;
+        assert_eq!(
+            chain("entities | grep Enemy|head && echo done"),
+            [
+                (None, "entities"),
+                (Some(Pipe), "grep Enemy"),
+                (Some(Pipe), "head"),
+                (Some(And), "echo done")
+            ]
+        );
+        assert_eq!(chain(r#"grep "a|b" a\|b"#), [(None, r#"grep "a|b" a\|b"#)]);
+        assert_eq!(
+            parse_cli_chain("entities |"),
+            Err(CliParseError::MissingCommand(9))
+        );
+        assert_eq!(
+            parse_cli_chain("a | | b"),
+            Err(CliParseError::MissingCommand(4))
+        );
+        assert!(Pipe.should_run(true) && !Pipe.should_run(false));
+    }
+
+    #[test]
+    fn command_substitutions() {
+        use CliChainOp::*;
+        assert_eq!(
+            chain("set x $(a; b | c) && echo \"$(d \")\" e)\""),
+            [
+                (None, "set x $(a; b | c)"),
+                (Some(And), "echo \"$(d \")\" e)\"")
+            ]
+        );
+        let parsed = parse_cli("echo a$(b $(c) ')')d \"$(e)\"").unwrap();
+        assert_eq!(parsed.args(), ["a$(b $(c) ')')d", "$(e)"]);
+        assert_eq!(
+            parse_cli("echo $(a"),
+            Err(CliParseError::UnterminatedSubstitution(5))
+        );
+        assert_eq!(
+            kinds("echo $(a)"),
+            [
+                (CliTokenKind::Command, "echo"),
+                (CliTokenKind::Variable, "$(a)")
+            ]
+        );
+    }
+
+    fn kinds(cli: &str) -> Vec<(CliTokenKind, &str)> {
+        parse_cli_chain(cli)
+            .unwrap()
+            .tokens()
+            .into_iter()
+            .map(|token| (token.kind, &cli[token.span]))
+            .collect()
+    }
+
+    #[test]
+    fn token_kinds() {
+        use CliTokenKind::*;
+        assert_eq!(
+            kinds(r#"spawn 1 -2 --force -x "a b" $pos '$lit' \$esc && echo "${x}""#),
+            [
+                (Command, "spawn"),
+                (Arg, "1"),
+                (Arg, "-2"),
+                (Flag, "--force"),
+                (Flag, "-x"),
+                (String, "\"a b\""),
+                (Variable, "$pos"),
+                (String, "'$lit'"),
+                (String, "\\$esc"),
+                (Operator, "&&"),
+                (Command, "echo"),
+                (Variable, "\"${x}\""),
+            ]
+        );
+        assert_eq!(
+            kinds(r#"kick @Bob @"Player One" @name(Bob) "@(x)""#),
+            [
+                (Command, "kick"),
+                (Mention, "@Bob"),
+                (Mention, "@\"Player One\""),
+                (Arg, "@name(Bob)"),
+                (String, "\"@(x)\""),
+            ]
+        );
+        assert_eq!(
+            kinds(r#"echo "it's $who" '"$lit"'"#),
+            [
+                (Command, "echo"),
+                (Variable, "\"it's $who\""),
+                (String, "'\"$lit\"'"),
+            ]
+        );
+    }
+
+    #[test]
+    fn flags() {
+        let parsed = parse_cli(r#"spawn --force 1 -2 -x "--quoted" -- --n=3 $flag"#).unwrap();
+        assert_eq!(
+            parsed.flags().collect::<Vec<_>>(),
+            ["--force", "-x", "--", "--n=3"]
+        );
+        assert_eq!(parsed.args().len(), 8);
+    }
+}
@@ -0,0 +1,168 @@
+//! Permission levels for "console commands", to protect against cheating.
+//!
+//! Every command has a [`CliPermission`], [`Normal`](CliPermission::Normal)
+//! unless set with
+//! [`set_clicommand_permission`](crate::CliCommandsRegisterExt::set_clicommand_permission).
+//! Whether commands of each level may run is controlled by the
+//! [`CliPermissions`] resource. Trying to run a command that is not
+//! permitted fails with [`CliError::NotPermitted`](crate::error::CliError::NotPermitted),
+//! without running it.
+//!
+//! ```rust,no_run
+//! # use bevy::prelude::*;
+//! # use iyes_cli::prelude::*;
+//! # use iyes_cli::permissions::{CliPermission, CliPermissions};
+//! # fn noclip() {}
+//! # let mut app = App::new();
+//! app.register_clicommand_noargs("noclip", noclip);
+//! app.set_clicommand_permission("noclip", CliPermission::Cheat);
+//!
+//! // Later, like `sv_cheats 1` in other games:
+//! app.world_mut().resource_mut::<CliPermissions>().cheats = true;
+//! ```
+
+use bevy::prelude::*;
+
+/// Who may run a "console command"
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CliPermission {
+    /// Anyone, at any time
+    #[default]
+    Normal,
+    /// Only when cheats are enabled (see [`CliPermissions::cheats`])
+    Cheat,
+    /// Only in development (see [`CliPermissions::dev`])
+    Dev,
+}
+
+impl std::fmt::Display for CliPermission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            CliPermission::Normal => "normal",
+            CliPermission::Cheat => "cheat",
+            CliPermission::Dev => "dev",
+        })
+    }
+}
+
+/// Which [`CliPermission`] levels are allowed to run
+///
+/// Normal commands are always allowed.
+#[derive(Resource, Debug, Clone)]
+pub struct CliPermissions {
+    /// Allow [`Cheat`](CliPermission::Cheat) commands
+    ///
+    /// Disabled by default.
+    pub cheats: bool,
+    /// Allow [`Dev`](CliPermission::Dev) commands
+    ///
+    /// Enabled by default in debug builds only.
+    pub dev: bool,
+}
+
+impl Default for CliPermissions {
+    fn default() -> Self {
+        CliPermissions {
+            cheats: false,
+            dev: cfg!(debug_assertions),
+        }
+    }
+}
+
+impl CliPermissions {
+    /// Whether commands with the given permission level may run
+    pub fn allows(&self, permission: CliPermission) -> bool {
+        match permission {
+            CliPermission::Normal => true,
+            CliPermission::Cheat => self.cheats,
+            CliPermission::Dev => self.dev,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CliError;
+    use crate::output::CliCommandsOutputExt;
+    use crate::{CliCommandsRegisterExt, CliPlugin};
+
+    #[test]
+    fn levels() {
+        let permissions = CliPermissions {
+            cheats: false,
+            dev: false,
+        };
+        assert!(permissions.allows(CliPermission::Normal));
+        assert!(!permissions.allows(CliPermission::Cheat));
+        assert!(!permissions.allows(CliPermission::Dev));
+        let permissions = CliPermissions {
+            cheats: true,
+            dev: false,
+        };
+        assert!(permissions.allows(CliPermission::Cheat));
+        assert!(!permissions.allows(CliPermission::Dev));
+    }
+
+    #[test]
+    fn commands_that_are_not_permitted_do_not_run() {
+        #[derive(Resource, Default)]
+        struct Ran(usize);
+
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.init_resource::<Ran>();
+        app.register_clicommand_noargs("noclip", |mut ran: ResMut<Ran>| ran.0 += 1);
+        app.set_clicommand_permission("noclip", CliPermission::Cheat);
+        app.insert_resource(CliPermissions {
+            cheats: false,
+            dev: true,
+        });
+
+        assert!(matches!(
+            app.try_run_cli("noclip"),
+            Err(CliError::NotPermitted {
+                permission: CliPermission::Cheat,
+                ..
+            })
+        ));
+        assert_eq!(app.world().resource::<Ran>().0, 0);
+
+        app.world_mut().resource_mut::<CliPermissions>().cheats = true;
+        assert!(app.try_run_cli("noclip").is_ok());
+        assert_eq!(app.world().resource::<Ran>().0, 1);
+    }
+
+    #[test]
+    #[cfg(all(feature = "builtin-commands", feature = "env"))]
+    fn builtins_that_change_commands_are_dev() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.insert_resource(CliPermissions {
+            cheats: true,
+            dev: false,
+        });
+        for cli in [
+            "alias noclip god",
+            "unalias noclip",
+            "bindcmd hi echo hi",
+            "unregister echo",
+            "set x 1",
+            "unset x",
+            "export x",
+        ] {
+            assert!(
+                matches!(
+                    app.try_run_cli(cli),
+                    Err(CliError::NotPermitted {
+                        permission: CliPermission::Dev,
+                        ..
+                    })
+                ),
+                "{}",
+                cli
+            );
+        }
+        assert!(app.try_run_cli("echo hi").is_ok());
+    }
+}
@@ -0,0 +1,134 @@
+//! Feeding the output of a command into another command, with `|`.
+//!
+//! Requires the `parser-advanced` cargo feature (enabled by default).
+//!
+//! In `cmd1 | cmd2`, the output of `cmd1` is captured, and every line of it
+//! is added as an extra arg of `cmd2`. `cmd2` only runs if `cmd1` succeeds.
+//! Warnings and errors are not passed along; they are shown as usual.
+//!
+//! The built-in filter commands are meant for use after a pipe, and take
+//! the lines to filter as their last args:
+//!
+//!  - `grep [-i] [-v] <pattern> [lines...]`: the lines containing the
+//!    pattern (ignoring case with `-i`; not containing it with `-v`)
+//!  - `head [-n <count>] [lines...]`: the first lines (10 by default)
+//!  - `count [lines...]`: how many lines there are
+//!
+//! ```text
+//! entities | grep Enemy | head -n 3
+//! help | grep -i physics | count
+//! ```
+//!
+//! The piped lines are quoted, so they reach the next command as given,
+//! without being expanded or split into multiple args.
+
+use bevy::prelude::*;
+
+use crate::output::CliOutput;
+use crate::CliCommandsRegisterExt;
+
+/// `grep [-i] [-v] <pattern> [lines...]`: print the lines matching a pattern
+fn grep(In(args): In<Vec<String>>, mut output: CliOutput) {
+    let mut ignore_case = false;
+    let mut invert = false;
+    let mut args = args.into_iter().peekable();
+    while let Some(flag) = args.next_if(|arg| arg == "-i" || arg == "-v") {
+        match flag.as_str() {
+            "-i" => ignore_case = true,
+            _ => invert = true,
+        }
+    }
+    let Some(mut pattern) = args.next() else {
+        output.fail("Usage: grep [-i] [-v] <pattern> [lines...]");
+        return;
+    };
+    if ignore_case {
+        pattern = pattern.to_lowercase();
+    }
+    for line in args {
+        let found = if ignore_case {
+            line.to_lowercase().contains(&pattern)
+        } else {
+            line.contains(&pattern)
+        };
+        if found != invert {
+            output.println(line);
+        }
+    }
+}
+
+/// `head [-n <count>] [lines...]`: print the first lines
+fn head(In(args): In<Vec<String>>, mut output: CliOutput) {
+    let (count, lines) = match args.split_first() {
+        Some((flag, rest)) if flag == "-n" => {
+            let Some(count) = rest.first().and_then(|count| count.parse::<usize>().ok()) else {
+                output.fail("Usage: head [-n <count>] [lines...]");
+                return;
+            };
+            (count, &rest[1..])
+        }
+        _ => (10, args.as_slice()),
+    };
+    for line in lines.iter().take(count) {
+        output.println(line);
+    }
+}
+
+/// `count [lines...]`: print how many lines there are
+fn count(In(args): In<Vec<String>>, mut output: CliOutput) {
+    output.println(args.len().to_string());
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_clicommand_args("grep", grep)
+        .set_clicommand_description("grep", "Print the lines containing a pattern")
+        .set_clicommand_usage("grep", "grep [-i] [-v] <pattern> [lines...]");
+    app.register_clicommand_args("head", head)
+        .set_clicommand_description("head", "Print the first lines")
+        .set_clicommand_usage("head", "head [-n <count>] [lines...]");
+    app.register_clicommand_args("count", count)
+        .set_clicommand_description("count", "Print how many lines there are")
+        .set_clicommand_usage("count", "count [lines...]");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::output::{CliCommandsOutputExt, CliOutput, CliOutputLine};
+    use crate::{CliCommandsRegisterExt, CliError, CliPlugin};
+    use bevy::prelude::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.register_clicommand_noargs("fruits", |mut output: CliOutput| {
+            output.println("apple pie\nbanana\nCherry\nbandana");
+        });
+        app
+    }
+
+    fn texts(output: &[CliOutputLine]) -> Vec<&str> {
+        output.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    #[test]
+    fn output_lines_become_args() {
+        let mut app = app();
+        let output = app.try_run_cli("fruits | grep an").unwrap();
+        assert_eq!(texts(&output), ["banana", "bandana"]);
+        let output = app.try_run_cli("fruits | head -n 2").unwrap();
+        assert_eq!(texts(&output), ["apple pie", "banana"]);
+        let output = app
+            .try_run_cli("fruits | grep -i -v CHERRY | count")
+            .unwrap();
+        assert_eq!(texts(&output), ["3"]);
+    }
+
+    #[test]
+    fn failed_command_stops_the_pipe() {
+        let mut app = app();
+        let result = app.try_run_cli("fruits | head -n nope | count");
+        assert!(matches!(result, Err(CliError::Failed(_))));
+        let output = app.run_cli_captured("fruits | head -n nope | count");
+        assert!(!texts(&output).contains(&"0"));
+    }
+}
@@ -0,0 +1,259 @@
+//! Previewing the effects of "console commands", without applying them.
+//!
+//! Experimental. Requires the `preview` cargo feature. Add
+//! [`CliPreviewPlugin`] (together with [`CliPlugin`](crate::CliPlugin)) to
+//! register:
+//!
+//!  - `preview <command> [args...]`: run a command against a scratch `World`,
+//!    and report what it changed
+//!  - `preview_snapshot`: use the current state of the `World` as the snapshot
+//!    for `preview`
+//!
+//! The scratch `World` is built from a [`DynamicScene`] snapshot, set with
+//! [`CliPreview::set_scene`] or `preview_snapshot`, so it only contains the
+//! reflected components and resources. After running the command, the
+//! entities that were spawned or despawned and the resources that were
+//! added, removed or modified (compared using reflection) are printed. The
+//! real `World` is not touched, and the output of the command is discarded.
+//!
+//! Systems cannot be moved between worlds, so a command can only be previewed
+//! if it was registered with
+//! [`register_clicommand_previewable`](CliPreviewRegisterExt::register_clicommand_previewable),
+//! which keeps a copy of its system for registering into the scratch `World`.
+
+use std::any::TypeId;
+
+use bevy::ecs::entity::EntityHashMap;
+use bevy::ecs::reflect::{AppTypeRegistry, ReflectResource};
+use bevy::ecs::system::SystemId;
+use bevy::prelude::*;
+use bevy::reflect::PartialReflect;
+use bevy::scene::{DynamicScene, DynamicSceneBuilder};
+use bevy::utils::{HashMap, HashSet};
+
+use crate::output::{cli_error, cli_println};
+use crate::resolve::{resolve_command, CliResolution};
+use crate::short_name::ShortName;
+use crate::status::cli_fail;
+use crate::{quote_cli_arg, CliCommandsRegisterExt};
+
+/// Plugin that adds the `preview` commands
+pub struct CliPreviewPlugin;
+
+impl Plugin for CliPreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CliPreview>();
+        app.register_clicommand_args("preview", preview)
+            .set_clicommand_description("preview", "Show what a command would change")
+            .set_clicommand_usage("preview", "preview <command> [args...]");
+        app.register_clicommand_noargs("preview_snapshot", preview_snapshot)
+            .set_clicommand_description(
+                "preview_snapshot",
+                "Use the current World as the snapshot for preview",
+            );
+    }
+}
+
+type CliPreviewFactory = dyn Fn(&mut World) -> SystemId<In<Vec<String>>, ()> + Send + Sync;
+
+/// The snapshot and the previewable commands, for `preview`
+#[derive(Resource, Default)]
+pub struct CliPreview {
+    scene: Option<DynamicScene>,
+    factories: HashMap<String, Box<CliPreviewFactory>>,
+}
+
+impl CliPreview {
+    /// Set the snapshot that the scratch `World` is built from
+    pub fn set_scene(&mut self, scene: DynamicScene) {
+        self.scene = Some(scene);
+    }
+    pub fn clear_scene(&mut self) {
+        self.scene = None;
+    }
+    pub fn has_scene(&self) -> bool {
+        self.scene.is_some()
+    }
+    /// Check if a command can be previewed
+    pub fn is_previewable(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+}
+
+/// Provides a method for registering "console commands" that can be previewed
+pub trait CliPreviewRegisterExt {
+    /// Create a new "console command" that can also be run by `preview`
+    ///
+    /// See [`register_clicommand_args`](CliCommandsRegisterExt::register_clicommand_args).
+    /// The system must be `Clone` (like `fn`s and most closures), so that a
+    /// copy can be registered into the scratch `World`.
+    fn register_clicommand_previewable<S, Param>(&mut self, name: &str, system: S) -> &mut Self
+    where
+        S: IntoSystem<In<Vec<String>>, (), Param> + Clone + Send + Sync + 'static;
+}
+
+impl CliPreviewRegisterExt for App {
+    fn register_clicommand_previewable<S, Param>(&mut self, name: &str, system: S) -> &mut Self
+    where
+        S: IntoSystem<In<Vec<String>>, (), Param> + Clone + Send + Sync + 'static,
+    {
+        self.register_clicommand_args(name, system.clone());
+        let factory = move |world: &mut World| world.register_system(system.clone());
+        self.init_resource::<CliPreview>();
+        self.world_mut()
+            .resource_mut::<CliPreview>()
+            .factories
+            .insert(name.to_owned(), Box::new(factory));
+        self
+    }
+}
+
+/// `preview <command> [args...]`: run a command against a scratch `World`
+fn preview(In(args): In<Vec<String>>, world: &mut World) {
+    let Some((typed_name, cmd_args)) = args.split_first() else {
+        cli_error(world, "Usage: preview <command> [args...]");
+        cli_fail(world);
+        return;
+    };
+    let name = match resolve_command(world, typed_name) {
+        CliResolution::Found(name) => name,
+        CliResolution::NotFound => {
+            cli_error(world, format!("CliCommand {:?} not found!", typed_name));
+            cli_fail(world);
+            return;
+        }
+        CliResolution::Ambiguous(candidates) => {
+            cli_error(
+                world,
+                format!(
+                    "CliCommand {:?} is ambiguous: {}",
+                    typed_name,
+                    candidates.join(", ")
+                ),
+            );
+            cli_fail(world);
+            return;
+        }
+    };
+    let Some(registry) = world.get_resource::<AppTypeRegistry>().cloned() else {
+        cli_error(world, "Cannot preview without an AppTypeRegistry!");
+        cli_fail(world);
+        return;
+    };
+    let preview = world.resource::<CliPreview>();
+    let result = match (preview.factories.get(&name), &preview.scene) {
+        (None, _) => Err(format!("CliCommand {:?} cannot be previewed!", name)),
+        (_, None) => Err("No snapshot to preview in (use preview_snapshot)!".to_owned()),
+        (Some(factory), Some(scene)) => run_in_scratch(registry, scene, factory, cmd_args.to_vec()),
+    };
+    match result {
+        Ok(changes) => {
+            let mut lines = vec![format!(
+                "Preview of {} (not applied):",
+                std::iter::once(&name)
+                    .chain(cmd_args)
+                    .map(|arg| quote_cli_arg(arg))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )];
+            if changes.is_empty() {
+                lines.push("(no changes)".to_owned());
+            }
+            lines.extend(changes);
+            cli_println(world, lines.join("\n"));
+        }
+        Err(e) => {
+            cli_error(world, e);
+            cli_fail(world);
+        }
+    }
+}
+
+/// Run a command in a new `World` built from the scene, returning the changes
+fn run_in_scratch(
+    registry: AppTypeRegistry,
+    scene: &DynamicScene,
+    factory: &CliPreviewFactory,
+    args: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let mut scratch = World::new();
+    scratch.insert_resource(registry.clone());
+    scene
+        .write_to_world(&mut scratch, &mut EntityHashMap::default())
+        .map_err(|e| format!("Cannot build the scratch World: {}", e))?;
+    let id = factory(&mut scratch);
+
+    let entities_before: HashSet<Entity> = scratch.iter_entities().map(|e| e.id()).collect();
+    let registry = registry.read();
+    let resources: Vec<(TypeId, &str, &ReflectResource)> = registry
+        .iter()
+        .filter_map(|r| {
+            let reflect = r.data::<ReflectResource>()?;
+            Some((r.type_id(), r.type_info().type_path(), reflect))
+        })
+        .collect();
+    let resources_before: HashMap<TypeId, Box<dyn PartialReflect>> = resources
+        .iter()
+        .filter_map(|(type_id, _, reflect)| {
+            let value = reflect.reflect(&scratch)?;
+            Some((*type_id, value.clone_value()))
+        })
+        .collect();
+
+    scratch
+        .run_system_with_input(id, args)
+        .map_err(|e| format!("CliCommand failed to run: {}", e))?;
+
+    let mut changes = Vec::new();
+    for entity in scratch.iter_entities() {
+        if entities_before.contains(&entity.id()) {
+            continue;
+        }
+        let names: Vec<String> = scratch
+            .inspect_entity(entity.id())
+            .map(|info| ShortName(info.name()).to_string())
+            .collect();
+        changes.push(format!("+ entity {}: {}", entity.id(), names.join(", ")));
+    }
+    let mut despawned: Vec<Entity> = entities_before
+        .into_iter()
+        .filter(|&entity| !scratch.entities().contains(entity))
+        .collect();
+    despawned.sort_unstable();
+    for entity in despawned {
+        changes.push(format!("- entity {}", entity));
+    }
+    for (type_id, type_path, reflect) in resources {
+        let name = ShortName(type_path);
+        let after = reflect.reflect(&scratch);
+        match (resources_before.get(&type_id), after) {
+            (None, None) => {}
+            (None, Some(after)) => changes.push(format!("+ resource {}: {:?}", name, after)),
+            (Some(_), None) => changes.push(format!("- resource {}", name)),
+            (Some(before), Some(after)) => {
+                let equal = before
+                    .reflect_partial_eq(after.as_partial_reflect())
+                    .unwrap_or_else(|| format!("{:?}", before) == format!("{:?}", after));
+                if !equal {
+                    changes.push(format!("~ resource {}: {:?}", name, after));
+                }
+            }
+        }
+    }
+    Ok(changes)
+}
+
+/// `preview_snapshot`: use the current state of the `World` as the snapshot
+fn preview_snapshot(world: &mut World) {
+    let scene = DynamicSceneBuilder::from_world(world)
+        .extract_entities(world.iter_entities().map(|e| e.id()))
+        .extract_resources()
+        .build();
+    let text = format!(
+        "Snapshot taken: {} entities, {} resources",
+        scene.entities.len(),
+        scene.resources.len()
+    );
+    world.resource_mut::<CliPreview>().set_scene(scene);
+    cli_println(world, text);
+}
@@ -0,0 +1,173 @@
+//! Commands asking the user for follow-up input.
+//!
+//! A command can ask a question with [`CliPrompt::ask`]. The console then
+//! shows the question instead of its usual prompt, and the next line the
+//! user submits is delivered as the answer, instead of being run. Useful for
+//! multi-step flows, like confirmations, passwords and wizards.
+//!
+//! The answer goes to a system taking `In<String>`, registered with
+//! [`World::register_system`], or to a command, which runs with the answer
+//! as its last arg:
+//!
+//! ```rust,ignore
+//! fn new_world(mut prompt: ResMut<CliPrompt>) {
+//!     prompt.ask("Enter seed:", "worldgen --seed");
+//! }
+//!
+//! fn login(mut prompt: ResMut<CliPrompt>, handlers: Res<LoginHandlers>) {
+//!     prompt.ask_secret("Password:", handlers.check_password);
+//! }
+//! ```
+//!
+//! The answering system or command may ask again, for the next step.
+//!
+//! The built-in consoles show the question, and hide the answer to secret
+//! questions. The remote console server (see [`remote`](crate::remote))
+//! forwards the question to its client, and the stdin reader (see
+//! [`stdin`](crate::stdin)) answers with the next line. Other frontends check
+//! [`CliPrompt::request`], and deliver the answer with
+//! [`CliAnswerPromptCommand`].
+
+use bevy::ecs::system::SystemId;
+use bevy::prelude::*;
+
+use crate::output::cli_error;
+use crate::{quote_cli_arg, CliCommandsRunExt};
+
+/// Where the answer to a [`CliPrompt`] goes
+#[derive(Debug, Clone)]
+pub enum CliPromptReply {
+    /// Run a system with the answer
+    System(SystemId<In<String>, ()>),
+    /// Run a command string, with the answer added as its last arg
+    Command(String),
+}
+
+impl From<SystemId<In<String>, ()>> for CliPromptReply {
+    fn from(id: SystemId<In<String>, ()>) -> Self {
+        CliPromptReply::System(id)
+    }
+}
+
+impl From<&str> for CliPromptReply {
+    fn from(command: &str) -> Self {
+        CliPromptReply::Command(command.to_owned())
+    }
+}
+
+impl From<String> for CliPromptReply {
+    fn from(command: String) -> Self {
+        CliPromptReply::Command(command)
+    }
+}
+
+/// A question waiting for an answer
+#[derive(Debug, Clone)]
+pub struct CliPromptRequest {
+    id: u64,
+    message: String,
+    secret: bool,
+    reply: CliPromptReply,
+}
+
+impl CliPromptRequest {
+    /// Identifies the request, for frontends that answer it later
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+    /// The question to show
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+    /// Whether the answer must not be shown or remembered, like a password
+    pub fn is_secret(&self) -> bool {
+        self.secret
+    }
+    /// Deliver the answer
+    pub fn answer(self, world: &mut World, answer: String) {
+        match self.reply {
+            CliPromptReply::System(id) => {
+                if let Err(e) = world.run_system_with_input(id, answer) {
+                    cli_error(world, format!("Cannot deliver the answer: {}!", e));
+                }
+            }
+            CliPromptReply::Command(command) => {
+                // `$` would be expanded as a variable
+                let answer = quote_cli_arg(&answer).replace('$', "\\$");
+                world.run_cli(&format!("{} {}", command, answer));
+            }
+        }
+    }
+}
+
+/// The question asked by a command, if any
+///
+/// Requires [`CliPlugin`](crate::CliPlugin).
+#[derive(Resource, Debug, Default)]
+pub struct CliPrompt {
+    request: Option<CliPromptRequest>,
+    next_id: u64,
+}
+
+impl CliPrompt {
+    /// Ask a question; the next line the user submits is the answer
+    ///
+    /// Replaces any question that is already waiting. Returns the id of
+    /// the request.
+    pub fn ask(&mut self, message: impl Into<String>, reply: impl Into<CliPromptReply>) -> u64 {
+        self.push(message.into(), false, reply.into())
+    }
+    /// Ask a question whose answer must not be shown, like a password
+    pub fn ask_secret(
+        &mut self,
+        message: impl Into<String>,
+        reply: impl Into<CliPromptReply>,
+    ) -> u64 {
+        self.push(message.into(), true, reply.into())
+    }
+    fn push(&mut self, message: String, secret: bool, reply: CliPromptReply) -> u64 {
+        self.next_id += 1;
+        self.request = Some(CliPromptRequest {
+            id: self.next_id,
+            message,
+            secret,
+            reply,
+        });
+        self.next_id
+    }
+    /// The question waiting for an answer, if any
+    pub fn request(&self) -> Option<&CliPromptRequest> {
+        self.request.as_ref()
+    }
+    pub fn is_pending(&self) -> bool {
+        self.request.is_some()
+    }
+    /// Stop waiting for an answer
+    pub fn cancel(&mut self) {
+        self.request = None;
+    }
+    /// Stop waiting, returning the request, for frontends that answer it later
+    pub fn take_request(&mut self) -> Option<CliPromptRequest> {
+        self.request.take()
+    }
+}
+
+/// Command to deliver the answer to the question waiting in [`CliPrompt`]
+///
+/// Does nothing if no question is waiting.
+pub struct CliAnswerPromptCommand(pub String);
+
+impl bevy::ecs::world::Command for CliAnswerPromptCommand {
+    fn apply(self, world: &mut World) {
+        let request = world
+            .get_resource_mut::<CliPrompt>()
+            .and_then(|mut prompt| prompt.take_request());
+        if let Some(request) = request {
+            request.answer(world, self.0);
+        }
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliPrompt>();
+}
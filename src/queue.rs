@@ -0,0 +1,320 @@
+//! Spreading the execution of many "console commands" over multiple frames.
+//!
+//! Command strings submitted with [`CliCommandsRunExt::run_cli_queued`] are
+//! added to the [`CliQueue`], and run in the order they were submitted, in
+//! [`Update`]. At most [`budget`](CliQueue::budget) of them run per frame,
+//! so a script or remote client submitting hundreds of lines does not stall
+//! a single frame.
+//!
+//! A command string queued while a command string with a source runs (see
+//! [`source`](crate::source)) keeps that source, and runs with it, so the
+//! restrictions of the source still apply. [`CliQueue::push_from`] queues
+//! one for a source explicitly.
+//!
+//! The built-in `wait <frames|duration>` command delays the rest of the
+//! queue. A plain number is a count of frames (`wait 10`). With an `s` or
+//! `ms` suffix, it is a duration in virtual time (`wait 1.5s`, `wait 500ms`).
+//!
+//! ```rust,ignore
+//! app.run_cli_queued("spawn 0 0");
+//! app.run_cli_queued("wait 30");
+//! app.run_cli_queued("despawn_all");
+//! ```
+//!
+//! Command strings pushed with [`CliQueue::push_with_key`] run at most once
+//! per idempotency key (see [`idempotency`](crate::idempotency)), so a
+//! submission retried after a dropped connection is only queued, and run,
+//! once.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::Instant;
+
+use crate::diagnostics::record_queue_latency;
+use crate::idempotency::{run_cli_once, CliIdempotency};
+use crate::output::cli_error;
+use crate::quota::with_deferred_cli_source;
+use crate::sleep::parse_duration;
+use crate::source::CliSource;
+use crate::status::cli_fail;
+use crate::{CliCommandsRegisterExt, CliCommandsRunExt};
+
+/// System set containing the system that runs the [`CliQueue`]
+///
+/// It is in the [`Update`] schedule.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CliQueueSet;
+
+/// What the [`CliQueue`] is waiting for, after a `wait` command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CliQueueWait {
+    /// This many more frames
+    Frames(u32),
+    /// The elapsed virtual time to reach this value
+    Until(Duration),
+}
+
+/// Holds command strings waiting to be run, a few per frame
+///
+/// While paused, nothing is run and commands accumulate in the queue.
+#[derive(Resource)]
+pub struct CliQueue {
+    entries: VecDeque<CliQueueEntry>,
+    budget: usize,
+    wait: Option<CliQueueWait>,
+    paused: bool,
+}
+
+struct CliQueueEntry {
+    cli: String,
+    submitted: Instant,
+    key: Option<String>,
+    /// The source it runs with (see [`source`](crate::source))
+    source: Option<CliSource>,
+}
+
+impl Default for CliQueue {
+    fn default() -> Self {
+        CliQueue {
+            entries: VecDeque::new(),
+            budget: 16,
+            wait: None,
+            paused: false,
+        }
+    }
+}
+
+impl CliQueue {
+    /// Add a command string to the end of the queue
+    pub fn push(&mut self, cli: String) {
+        self.push_entry(cli, None, None);
+    }
+    /// Add a command string to the end of the queue, to run on behalf of a
+    /// source (see [`source`](crate::source))
+    pub fn push_from(&mut self, cli: String, source: impl Into<CliSource>) {
+        self.push_entry(cli, None, Some(source.into()));
+    }
+    /// Add a command string to the end of the queue, to run at most once per
+    /// idempotency key
+    ///
+    /// Nothing is added if a command string with the same key is already
+    /// queued. If one already ran, it is skipped when its turn comes.
+    pub fn push_with_key(&mut self, cli: String, key: &str) {
+        if self
+            .entries
+            .iter()
+            .any(|entry| entry.key.as_deref() == Some(key))
+        {
+            return;
+        }
+        self.push_entry(cli, Some(key.to_owned()), None);
+    }
+    fn push_entry(&mut self, cli: String, key: Option<String>, source: Option<CliSource>) {
+        self.entries.push_back(CliQueueEntry {
+            cli,
+            submitted: Instant::now(),
+            key,
+            source,
+        });
+    }
+    /// Iterate over all queued command strings, in the order they will run
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|entry| entry.cli.as_str())
+    }
+    /// How many command strings are queued
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// Remove all queued command strings, and stop waiting
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.wait = None;
+    }
+    /// The maximum number of command strings run per frame
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+    /// Set the maximum number of command strings run per frame (default: 16)
+    ///
+    /// A budget of `0` is treated as `1`, so the queue always makes progress.
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget.max(1);
+    }
+    /// What the queue is waiting for, if a `wait` command is in effect
+    pub fn wait(&self) -> Option<CliQueueWait> {
+        self.wait
+    }
+    /// Delay the rest of the queue
+    pub fn set_wait(&mut self, wait: Option<CliQueueWait>) {
+        self.wait = wait;
+    }
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
+
+/// Command to add a "console command" to the [`CliQueue`]
+pub struct CliRunQueuedCommand(pub String);
+
+impl bevy::ecs::world::Command for CliRunQueuedCommand {
+    fn apply(self, world: &mut World) {
+        world.run_cli_queued(&self.0);
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliQueue>();
+    app.init_resource::<CliIdempotency>();
+    app.add_systems(Update, run_queue.in_set(CliQueueSet));
+    app.register_clicommand_args("wait", wait)
+        .set_clicommand_description("wait", "Delay the rest of the command queue")
+        .set_clicommand_usage("wait", "wait <frames|duration>");
+}
+
+fn run_queue(world: &mut World) {
+    let virtual_elapsed = virtual_elapsed(world);
+    let mut queue = world.resource_mut::<CliQueue>();
+    if queue.is_paused() {
+        return;
+    }
+    match queue.wait {
+        Some(CliQueueWait::Frames(n)) if n > 1 => {
+            queue.wait = Some(CliQueueWait::Frames(n - 1));
+            return;
+        }
+        Some(CliQueueWait::Until(at)) if virtual_elapsed < at => return,
+        _ => queue.wait = None,
+    }
+    let budget = queue.budget;
+    for _ in 0..budget {
+        let mut queue = world.resource_mut::<CliQueue>();
+        if queue.wait.is_some() || queue.is_paused() {
+            return;
+        }
+        let Some(entry) = queue.entries.pop_front() else {
+            return;
+        };
+        with_deferred_cli_source(world, entry.source.clone(), |world| match &entry.key {
+            Some(key) => {
+                run_cli_once(world, key, &entry.cli);
+            }
+            None => {
+                world.run_cli(&entry.cli);
+            }
+        });
+        record_queue_latency(world, entry.submitted);
+    }
+}
+
+fn virtual_elapsed(world: &World) -> Duration {
+    world
+        .get_resource::<Time<Virtual>>()
+        .map_or(Duration::ZERO, |time| time.elapsed())
+}
+
+/// `wait <frames|duration>`: delay the rest of the command queue
+fn wait(In(args): In<Vec<String>>, world: &mut World) {
+    let [arg] = args.as_slice() else {
+        cli_error(world, "Usage: wait <frames|duration>");
+        cli_fail(world);
+        return;
+    };
+    let wait = match arg.parse::<u32>() {
+        Ok(0) => return,
+        Ok(frames) => CliQueueWait::Frames(frames),
+        Err(_) => match parse_duration(arg) {
+            Some(duration) => CliQueueWait::Until(virtual_elapsed(world) + duration),
+            None => {
+                cli_error(world, format!("Invalid frame count or duration: {:?}", arg));
+                cli_fail(world);
+                return;
+            }
+        },
+    };
+    world
+        .get_resource_or_insert_with(CliQueue::default)
+        .set_wait(Some(wait));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CliPlugin;
+
+    #[derive(Resource, Default)]
+    struct Marks(Vec<String>);
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.init_resource::<Marks>();
+        app.register_clicommand_args(
+            "mark",
+            |In(args): In<Vec<String>>, mut marks: ResMut<Marks>| marks.0.extend(args),
+        );
+        app
+    }
+
+    fn marks(world: &World) -> &[String] {
+        &world.resource::<Marks>().0
+    }
+
+    #[test]
+    fn budget_per_frame() {
+        let mut app = app();
+        let world = app.world_mut();
+        for i in 0..5 {
+            world.run_cli_queued(&format!("mark {}", i));
+        }
+        world.resource_mut::<CliQueue>().set_budget(2);
+        run_queue(world);
+        assert_eq!(marks(world), ["0", "1"]);
+        run_queue(world);
+        run_queue(world);
+        assert_eq!(marks(world), ["0", "1", "2", "3", "4"]);
+        assert!(world.resource::<CliQueue>().is_empty());
+    }
+
+    #[test]
+    fn wait_frames() {
+        let mut app = app();
+        let world = app.world_mut();
+        world.run_cli_queued("mark a");
+        world.run_cli_queued("wait 2");
+        world.run_cli_queued("mark b");
+        run_queue(world);
+        assert_eq!(marks(world), ["a"]);
+        assert_eq!(
+            world.resource::<CliQueue>().wait(),
+            Some(CliQueueWait::Frames(2))
+        );
+        run_queue(world);
+        assert_eq!(marks(world), ["a"]);
+        run_queue(world);
+        assert_eq!(marks(world), ["a", "b"]);
+    }
+
+    #[test]
+    fn nothing_runs_while_paused() {
+        let mut app = app();
+        let world = app.world_mut();
+        world.run_cli_queued("mark a");
+        world.resource_mut::<CliQueue>().pause();
+        run_queue(world);
+        assert!(marks(world).is_empty());
+        world.resource_mut::<CliQueue>().resume();
+        run_queue(world);
+        assert_eq!(marks(world), ["a"]);
+    }
+}
@@ -0,0 +1,290 @@
+//! An overlay of buttons running command strings, for touchscreens.
+//!
+//! Requires the `quickbar` cargo feature. Add [`CliQuickbarPlugin`] (together
+//! with [`CliPlugin`](crate::CliPlugin)) to show a small "CLI" button in a
+//! corner of the screen. Tapping it opens a grid of buttons, each running a
+//! command string, for debugging on phones and tablets where typing is
+//! impractical.
+//!
+//! The buttons are set up with the built-in `quickbar` command, usually from
+//! an autoexec script (see [`script`](crate::script)), or from code with the
+//! [`CliQuickbar`] resource:
+//!
+//! ```text
+//! # autoexec.cli
+//! quickbar add "God mode" "godmode toggle"
+//! quickbar add "Skip level" "load_level next"
+//! quickbar add Heal heal 100
+//! ```
+//!
+//! Commands run with the source `quickbar` (see [`quota`](crate::quota)).
+
+use bevy::prelude::*;
+
+use crate::output::CliOutput;
+use crate::quota::run_cli_from;
+use crate::{quote_cli_arg, CliCommandsRegisterExt};
+
+/// Plugin that adds the quick-command overlay
+pub struct CliQuickbarPlugin {
+    /// How many buttons are in each row of the grid
+    pub columns: u16,
+}
+
+impl Default for CliQuickbarPlugin {
+    fn default() -> Self {
+        CliQuickbarPlugin { columns: 3 }
+    }
+}
+
+impl Plugin for CliQuickbarPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CliQuickbar {
+            buttons: Vec::new(),
+            columns: self.columns.max(1),
+            open: false,
+        });
+        app.add_systems(Startup, spawn_quickbar);
+        app.add_systems(
+            Update,
+            (press_buttons, update_quickbar)
+                .chain()
+                .in_set(CliQuickbarSet),
+        );
+        app.register_clicommand_args("quickbar", quickbar)
+            .set_clicommand_description(
+                "quickbar",
+                "Set up the buttons of the quick-command overlay",
+            )
+            .set_clicommand_usage(
+                "quickbar",
+                "quickbar [add <label> <command...> | remove <label> | clear | show | hide]",
+            );
+    }
+}
+
+/// System set for the systems that run the quick-command overlay
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CliQuickbarSet;
+
+/// A button of the quick-command overlay
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliQuickbarButton {
+    pub label: String,
+    /// The command string run when the button is pressed
+    pub cli: String,
+}
+
+/// The buttons of the quick-command overlay
+#[derive(Resource, Debug)]
+pub struct CliQuickbar {
+    buttons: Vec<CliQuickbarButton>,
+    columns: u16,
+    open: bool,
+}
+
+impl CliQuickbar {
+    /// Add a button, or change the command string of the button with this label
+    pub fn add(&mut self, label: &str, cli: &str) {
+        match self.buttons.iter_mut().find(|button| button.label == label) {
+            Some(button) => button.cli = cli.to_owned(),
+            None => self.buttons.push(CliQuickbarButton {
+                label: label.to_owned(),
+                cli: cli.to_owned(),
+            }),
+        }
+    }
+    /// Remove the button with this label, returning whether there was one
+    pub fn remove(&mut self, label: &str) -> bool {
+        let len = self.buttons.len();
+        self.buttons.retain(|button| button.label != label);
+        self.buttons.len() != len
+    }
+    /// Remove all buttons
+    pub fn clear(&mut self) {
+        self.buttons.clear();
+    }
+    /// The buttons, in the order they are shown
+    pub fn buttons(&self) -> &[CliQuickbarButton] {
+        &self.buttons
+    }
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+}
+
+/// Marker for the root entity of the quick-command overlay
+#[derive(Component)]
+pub struct CliQuickbarRoot;
+
+/// The grid of buttons, shown while the overlay is open
+#[derive(Component)]
+struct CliQuickbarGrid;
+
+/// What pressing a button of the overlay does
+#[derive(Component, Debug, Clone)]
+enum CliQuickbarAction {
+    Toggle,
+    Run(String),
+}
+
+const BUTTON_COLOR: Color = Color::srgba(0.15, 0.15, 0.2, 0.9);
+const PRESSED_COLOR: Color = Color::srgba(0.3, 0.3, 0.4, 0.9);
+
+fn spawn_quickbar(mut commands: Commands) {
+    commands
+        .spawn((
+            CliQuickbarRoot,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(8.0),
+                right: Val::Px(8.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::End,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            GlobalZIndex(i32::MAX - 1),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                CliQuickbarGrid,
+                Node {
+                    display: Display::None,
+                    column_gap: Val::Px(8.0),
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+            ));
+            spawn_button(parent, "CLI", CliQuickbarAction::Toggle);
+        });
+}
+
+fn spawn_button(parent: &mut ChildBuilder, label: &str, action: CliQuickbarAction) {
+    parent
+        .spawn((
+            action,
+            Button,
+            Node {
+                min_width: Val::Px(64.0),
+                min_height: Val::Px(48.0),
+                padding: UiRect::axes(Val::Px(12.0), Val::Px(8.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(BUTTON_COLOR),
+        ))
+        .with_children(|button| {
+            button.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.85, 0.85, 0.85)),
+            ));
+        });
+}
+
+fn press_buttons(
+    q_buttons: Query<(&Interaction, &CliQuickbarAction), Changed<Interaction>>,
+    mut quickbar: ResMut<CliQuickbar>,
+    mut commands: Commands,
+) {
+    for (interaction, action) in &q_buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match action {
+            CliQuickbarAction::Toggle => quickbar.toggle(),
+            CliQuickbarAction::Run(cli) => {
+                let cli = cli.clone();
+                commands.queue(move |world: &mut World| {
+                    run_cli_from(world, "quickbar", &cli);
+                });
+            }
+        }
+    }
+}
+
+fn update_quickbar(
+    quickbar: Res<CliQuickbar>,
+    mut q_grid: Query<(Entity, &mut Node), With<CliQuickbarGrid>>,
+    mut q_colors: Query<(&Interaction, &mut BackgroundColor), With<CliQuickbarAction>>,
+    mut commands: Commands,
+) {
+    for (interaction, mut color) in &mut q_colors {
+        let target = match interaction {
+            Interaction::Pressed => PRESSED_COLOR,
+            _ => BUTTON_COLOR,
+        };
+        if color.0 != target {
+            color.0 = target;
+        }
+    }
+    if !quickbar.is_changed() {
+        return;
+    }
+    let Ok((grid, mut node)) = q_grid.get_single_mut() else {
+        return;
+    };
+    node.display = match quickbar.open {
+        true => Display::Grid,
+        false => Display::None,
+    };
+    node.grid_template_columns = RepeatedGridTrack::flex(quickbar.columns, 1.0);
+    commands
+        .entity(grid)
+        .despawn_descendants()
+        .with_children(|parent| {
+            for button in &quickbar.buttons {
+                let action = CliQuickbarAction::Run(button.cli.clone());
+                spawn_button(parent, &button.label, action);
+            }
+        });
+}
+
+/// `quickbar [add <label> <command...> | remove <label> | clear | show | hide]`:
+/// set up the buttons of the overlay, or list them
+fn quickbar(In(args): In<Vec<String>>, mut quickbar: ResMut<CliQuickbar>, mut output: CliOutput) {
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    match args.as_slice() {
+        [] => {
+            for button in quickbar.buttons() {
+                output.println(format!(
+                    "quickbar add {} {}",
+                    quote_cli_arg(&button.label),
+                    quote_cli_arg(&button.cli)
+                ));
+            }
+        }
+        // `quickbar add Heal "heal 100"`
+        ["add", label, cli] => quickbar.add(label, cli),
+        // `quickbar add Heal heal 100`
+        ["add", label, words @ ..] if !words.is_empty() => {
+            let words: Vec<_> = words.iter().map(|word| quote_cli_arg(word)).collect();
+            quickbar.add(label, &words.join(" "));
+        }
+        ["remove", label] => {
+            if !quickbar.remove(label) {
+                output.fail(format!("There is no quickbar button {:?}!", label));
+            }
+        }
+        ["clear"] => quickbar.clear(),
+        ["show"] => quickbar.open(),
+        ["hide"] => quickbar.close(),
+        _ => output.fail(
+            "Usage: quickbar [add <label> <command...> | remove <label> | clear | show | hide]",
+        ),
+    }
+}
@@ -0,0 +1,331 @@
+//! Limits on how many commands each source may run.
+//!
+//! A source is where command strings come from (see [`source`](crate::source)),
+//! identified by name: a player (`player:<entity>`), a remote console client
+//! (`remote:<address>`), a session (`session:<entity>`), the key bindings
+//! (`bindings`), or anything else that runs command strings with
+//! [`run_cli_from`] (like a chat bridge, `chat:<player>`). Each source has
+//! independent limits, set by pattern in [`CliQuotas`] (`*` matches any
+//! sequence of characters):
+//!
+//! ```rust,ignore
+//! let mut quotas = world.resource_mut::<CliQuotas>();
+//! quotas.set("chat:*", CliQuota { per_minute: Some(20), max_jobs: Some(1) });
+//! ```
+//!
+//! A source that has run `per_minute` commands in the last minute cannot
+//! run more until the oldest is a minute old, and fails with
+//! [`CliError::QuotaExceeded`]. A source with `max_jobs` background jobs
+//! running (see [`jobs`](crate::jobs)) cannot start more. Only the commands
+//! typed by the source count, not the commands they run.
+//!
+//! The built-in `quota show` command lists the sources and their usage.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::{HashMap, Instant};
+
+use crate::error::CliError;
+use crate::glob::glob_match;
+use crate::jobs::CliJobs;
+use crate::local::CliCurrentCommand;
+use crate::output::{CliOutput, CliOutputLine};
+use crate::permissions::CliPermission;
+use crate::source::CliSource;
+use crate::status::CliStatus;
+use crate::table::{CliAlign, CliTable};
+use crate::{CliCommandsRegisterExt, CliCommandsRunExt};
+
+const QUOTA_WINDOW: Duration = Duration::from_secs(60);
+
+/// The limits of a source
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CliQuota {
+    /// How many commands the source may run per minute
+    pub per_minute: Option<u32>,
+    /// How many background jobs the source may have running at once
+    pub max_jobs: Option<usize>,
+}
+
+/// The limits of each source, and how much of them they have used
+///
+/// Requires [`CliPlugin`](crate::CliPlugin).
+#[derive(Resource, Debug, Default)]
+pub struct CliQuotas {
+    /// Patterns of source names, and their limits
+    quotas: Vec<(String, CliQuota)>,
+    /// When the commands of each source ran, within the last minute
+    runs: HashMap<String, VecDeque<Instant>>,
+}
+
+impl CliQuotas {
+    /// Set the limits of the sources matching a pattern
+    ///
+    /// If a source matches several patterns, the one set first applies.
+    /// Setting the same pattern again replaces its limits.
+    pub fn set(&mut self, pattern: &str, quota: CliQuota) {
+        match self.quotas.iter_mut().find(|(p, _)| p == pattern) {
+            Some((_, q)) => *q = quota,
+            None => self.quotas.push((pattern.to_owned(), quota)),
+        }
+    }
+    pub fn remove(&mut self, pattern: &str) -> bool {
+        let len = self.quotas.len();
+        self.quotas.retain(|(p, _)| p != pattern);
+        self.quotas.len() != len
+    }
+    /// The limits of a source, if any
+    pub fn quota_for(&self, source: &str) -> Option<CliQuota> {
+        self.quotas
+            .iter()
+            .find(|(pattern, _)| glob_match(pattern, source))
+            .map(|(_, quota)| *quota)
+    }
+    /// How many commands a source has run in the last minute
+    pub fn runs_last_minute(&self, source: &str) -> usize {
+        let now = Instant::now();
+        self.runs.get(source).map_or(0, |runs| {
+            runs.iter()
+                .filter(|&&run| now.duration_since(run) < QUOTA_WINDOW)
+                .count()
+        })
+    }
+    /// Forget the usage of a source, like after it disconnects
+    pub fn forget(&mut self, source: &str) {
+        self.runs.remove(source);
+    }
+}
+
+/// Run a command string on behalf of a source, subject to its quota,
+/// returning its output
+///
+/// See [`source`](crate::source).
+pub fn run_cli_from(
+    world: &mut World,
+    source: impl Into<CliSource>,
+    cli: &str,
+) -> Vec<CliOutputLine> {
+    with_cli_source(world, source, |world| world.run_cli(cli))
+}
+
+/// Run a function, with command strings run by it counting for a source
+pub fn with_cli_source<R>(
+    world: &mut World,
+    source: impl Into<CliSource>,
+    f: impl FnOnce(&mut World) -> R,
+) -> R {
+    let previous = world
+        .get_resource_or_insert_with(CliStatus::default)
+        .source
+        .replace(source.into());
+    let r = f(world);
+    world.resource_mut::<CliStatus>().source = previous;
+    r
+}
+
+/// The source of the command string being run, if it has one
+///
+/// Command strings deferred by it (queued, scheduled or sleeping) keep it,
+/// and run with it later.
+pub(crate) fn current_cli_source(world: &World) -> Option<CliSource> {
+    world
+        .get_resource::<CliStatus>()
+        .and_then(|status| status.source())
+        .cloned()
+}
+
+/// Like [`with_cli_source`], if there is a source
+pub(crate) fn with_deferred_cli_source<R>(
+    world: &mut World,
+    source: Option<CliSource>,
+    f: impl FnOnce(&mut World) -> R,
+) -> R {
+    match source {
+        Some(source) => with_cli_source(world, source, f),
+        None => f(world),
+    }
+}
+
+/// Count a command against the quota of its source, if it has one
+///
+/// Commands run by other commands do not count.
+pub(crate) fn check(world: &mut World) -> Result<(), CliError> {
+    let nested = world
+        .get_resource::<CliCurrentCommand>()
+        .is_some_and(|current| current.name().is_some());
+    let source = world
+        .get_resource::<CliStatus>()
+        .and_then(|status| status.source.as_ref())
+        .map(|source| source.to_string());
+    let (false, Some(source)) = (nested, source) else {
+        return Ok(());
+    };
+    let Some(mut quotas) = world.get_resource_mut::<CliQuotas>() else {
+        return Ok(());
+    };
+    let Some(per_minute) = quotas.quota_for(&source).and_then(|q| q.per_minute) else {
+        return Ok(());
+    };
+    let now = Instant::now();
+    let runs = quotas.runs.entry(source.clone()).or_default();
+    while runs
+        .front()
+        .is_some_and(|&run| now.duration_since(run) >= QUOTA_WINDOW)
+    {
+        runs.pop_front();
+    }
+    if runs.len() >= per_minute as usize {
+        let oldest = runs.front().copied().unwrap_or(now);
+        return Err(CliError::QuotaExceeded {
+            source,
+            remaining: QUOTA_WINDOW.saturating_sub(now.duration_since(oldest)),
+        });
+    }
+    runs.push_back(now);
+    Ok(())
+}
+
+/// Check if a source may start another background job
+pub(crate) fn may_start_job(quotas: Option<&CliQuotas>, jobs: &CliJobs, source: &str) -> bool {
+    let max_jobs = quotas
+        .and_then(|quotas| quotas.quota_for(source))
+        .and_then(|quota| quota.max_jobs);
+    match max_jobs {
+        Some(max) => jobs.iter().filter(|job| job.source == Some(source)).count() < max,
+        None => true,
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliQuotas>();
+    app.register_clicommand_args("quota", quota)
+        .set_clicommand_description("quota", "Show the command quotas of sources")
+        .set_clicommand_usage("quota", "quota show")
+        .set_clicommand_permission("quota", CliPermission::Dev);
+}
+
+/// `quota show`: list the sources, their usage and their limits
+fn quota(
+    In(args): In<Vec<String>>,
+    quotas: Res<CliQuotas>,
+    jobs: Res<CliJobs>,
+    mut output: CliOutput,
+) {
+    if !matches!(args.as_slice(), [op] if op == "show") {
+        output.fail("Usage: quota show");
+        return;
+    }
+    let mut sources: Vec<&str> = quotas.runs.keys().map(|s| s.as_str()).collect();
+    sources.extend(jobs.iter().filter_map(|job| job.source));
+    sources.sort_unstable();
+    sources.dedup();
+    if sources.is_empty() {
+        output.println("No sources have run commands");
+        return;
+    }
+    let limit = |value: usize, max: Option<usize>| match max {
+        Some(max) => format!("{}/{}", value, max),
+        None => value.to_string(),
+    };
+    let mut table = CliTable::new()
+        .with_column("SOURCE", CliAlign::Left)
+        .with_column("COMMANDS/MIN", CliAlign::Right)
+        .with_column("JOBS", CliAlign::Right);
+    for source in sources {
+        let quota = quotas.quota_for(source).unwrap_or_default();
+        let running = jobs.iter().filter(|job| job.source == Some(source)).count();
+        table.add_row([
+            source.to_owned(),
+            limit(
+                quotas.runs_last_minute(source),
+                quota.per_minute.map(|n| n as usize),
+            ),
+            limit(running, quota.max_jobs),
+        ]);
+    }
+    output.println(table.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::CliCommandsOutputExt;
+    use crate::CliPlugin;
+
+    #[derive(Resource, Default)]
+    struct Marks(usize);
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.init_resource::<Marks>();
+        app.register_clicommand_noargs("mark", |mut marks: ResMut<Marks>| marks.0 += 1);
+        app.register_clicommand_noargs("twice", |world: &mut World| {
+            world.run_cli("mark");
+            world.run_cli("mark");
+        });
+        app.world_mut().resource_mut::<CliQuotas>().set(
+            "chat:*",
+            CliQuota {
+                per_minute: Some(2),
+                max_jobs: None,
+            },
+        );
+        app
+    }
+
+    fn run_from(world: &mut World, source: &str, cli: &str) -> Result<(), CliError> {
+        with_cli_source(world, source, |world| world.try_run_cli(cli).map(drop))
+    }
+
+    #[test]
+    fn patterns() {
+        let mut quotas = CliQuotas::default();
+        let limited = CliQuota {
+            per_minute: Some(1),
+            max_jobs: None,
+        };
+        quotas.set("chat:*", limited);
+        quotas.set("*", CliQuota::default());
+        assert_eq!(quotas.quota_for("chat:bob"), Some(limited));
+        assert_eq!(quotas.quota_for("remote:1"), Some(CliQuota::default()));
+        assert!(quotas.remove("*"));
+        assert_eq!(quotas.quota_for("remote:1"), None);
+    }
+
+    #[test]
+    fn commands_per_minute() {
+        let mut app = app();
+        let world = app.world_mut();
+        assert!(run_from(world, "chat:bob", "mark").is_ok());
+        assert!(run_from(world, "chat:bob", "mark").is_ok());
+        assert!(matches!(
+            run_from(world, "chat:bob", "mark"),
+            Err(CliError::QuotaExceeded { source, .. }) if source == "chat:bob"
+        ));
+        assert!(run_from(world, "chat:alice", "mark").is_ok());
+        assert!(run_from(world, "remote:1", "mark").is_ok());
+        assert_eq!(world.resource::<Marks>().0, 4);
+        assert_eq!(
+            world.resource::<CliQuotas>().runs_last_minute("chat:bob"),
+            2
+        );
+
+        world.resource_mut::<CliQuotas>().forget("chat:bob");
+        assert!(run_from(world, "chat:bob", "mark").is_ok());
+    }
+
+    #[test]
+    fn nested_commands_do_not_count() {
+        let mut app = app();
+        let world = app.world_mut();
+        assert!(run_from(world, "chat:bob", "twice").is_ok());
+        assert_eq!(world.resource::<Marks>().0, 2);
+        assert_eq!(
+            world.resource::<CliQuotas>().runs_last_minute("chat:bob"),
+            1
+        );
+    }
+}
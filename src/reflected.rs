@@ -0,0 +1,240 @@
+//! "Console commands" that send reflected events and set reflected states.
+//!
+//! Requires the `reflect-commands` cargo feature (and the `states` cargo
+//! feature, for states). Instead of writing a wrapper command for every
+//! event and state, register the types with a generic command, which
+//! constructs the value from its args, using reflection:
+//!
+//! ```rust,ignore
+//! app.register_event_command::<DamageEvent>("fire_event")
+//!     .register_event_command::<Restart>("fire_event")
+//!     .register_state_command::<GameState>("set_state");
+//! ```
+//!
+//! ```text
+//! fire_event DamageEvent {amount: 5}
+//! fire_event Restart
+//! set_state InGame
+//! set_state GameState Paused
+//! ```
+//!
+//! The first arg is the type, by its short name or full path. It can be
+//! left out if only one type is registered with the command, or (for
+//! states) if the value can only be of one of them. The rest is the value,
+//! in [RON](https://github.com/ron-rs/ron) syntax, with `{...}` accepted in
+//! place of `(...)` for structs. Values containing quotes must be quoted as
+//! a whole. Without a value, the default value is used, if the type
+//! reflects `Default`.
+//!
+//! Several types can be registered with the same command. The types are
+//! registered in the `AppTypeRegistry`, and events are added to the app.
+
+use std::any::TypeId;
+
+use bevy::prelude::*;
+use bevy::reflect::serde::TypedReflectDeserializer;
+use bevy::reflect::{GetTypeRegistration, PartialReflect};
+use bevy::utils::HashMap;
+use serde::de::DeserializeSeed;
+
+use crate::output::{cli_error, cli_println};
+use crate::short_name::ShortName;
+use crate::status::cli_fail;
+use crate::CliCommandsRegisterExt;
+
+type CliApplyFn = fn(&mut World, &dyn PartialReflect) -> Result<(), String>;
+
+/// A type whose values a command can construct
+#[derive(Clone)]
+struct CliReflectTarget {
+    type_id: TypeId,
+    short_name: String,
+    path: &'static str,
+    /// Whether the value is a state, rather than an event
+    is_state: bool,
+    apply: CliApplyFn,
+}
+
+/// The types registered with each command
+#[derive(Resource, Default)]
+struct CliReflectTargets {
+    commands: HashMap<String, Vec<CliReflectTarget>>,
+}
+
+/// Provides methods for registering commands that construct reflected values
+pub trait CliReflectRegisterExt {
+    /// Let a command send events of type `E`, constructed from its args
+    ///
+    /// The command is created, if it does not exist yet. See
+    /// [`reflected`](crate::reflected).
+    fn register_event_command<E>(&mut self, name: &str) -> &mut Self
+    where
+        E: Event + FromReflect + GetTypeRegistration;
+
+    /// Let a command set the next state of type `S`, constructed from its args
+    ///
+    /// Requires the `states` cargo feature. The command is created, if it
+    /// does not exist yet. See [`reflected`](crate::reflected).
+    #[cfg(feature = "states")]
+    fn register_state_command<S>(&mut self, name: &str) -> &mut Self
+    where
+        S: bevy::state::state::FreelyMutableState + FromReflect + GetTypeRegistration;
+}
+
+impl CliReflectRegisterExt for App {
+    fn register_event_command<E>(&mut self, name: &str) -> &mut Self
+    where
+        E: Event + FromReflect + GetTypeRegistration,
+    {
+        self.register_type::<E>().add_event::<E>();
+        add_target::<E>(self, name, false, send_event::<E>)
+    }
+
+    #[cfg(feature = "states")]
+    fn register_state_command<S>(&mut self, name: &str) -> &mut Self
+    where
+        S: bevy::state::state::FreelyMutableState + FromReflect + GetTypeRegistration,
+    {
+        self.register_type::<S>();
+        add_target::<S>(self, name, true, set_state::<S>)
+    }
+}
+
+fn add_target<'a, T: 'static>(
+    app: &'a mut App,
+    name: &str,
+    is_state: bool,
+    apply: CliApplyFn,
+) -> &'a mut App {
+    let target = CliReflectTarget {
+        type_id: TypeId::of::<T>(),
+        short_name: ShortName(std::any::type_name::<T>()).to_string(),
+        path: std::any::type_name::<T>(),
+        is_state,
+        apply,
+    };
+    let mut targets = app
+        .world_mut()
+        .get_resource_or_insert_with(CliReflectTargets::default);
+    let is_new = !targets.commands.contains_key(name);
+    let types = targets.commands.entry(name.to_owned()).or_default();
+    types.retain(|t| t.type_id != target.type_id);
+    types.push(target);
+    let (description, usage) = match is_state {
+        true => ("Set the next state", format!("{} [type] <state>", name)),
+        false => ("Send an event", format!("{} <type> [value]", name)),
+    };
+    if is_new {
+        let command = name.to_owned();
+        app.register_clicommand_args(name, move |In(args): In<Vec<String>>, world: &mut World| {
+            run_reflected(world, &command, args);
+        })
+        .set_clicommand_description(name, description)
+        .set_clicommand_usage(name, &usage);
+    }
+    app
+}
+
+fn send_event<E: Event + FromReflect>(
+    world: &mut World,
+    value: &dyn PartialReflect,
+) -> Result<(), String> {
+    let event = E::from_reflect(value).ok_or("value does not match the event type")?;
+    world.send_event(event);
+    Ok(())
+}
+
+#[cfg(feature = "states")]
+fn set_state<S>(world: &mut World, value: &dyn PartialReflect) -> Result<(), String>
+where
+    S: bevy::state::state::FreelyMutableState + FromReflect,
+{
+    let state = S::from_reflect(value).ok_or("value does not match the state type")?;
+    let mut next = world
+        .get_resource_mut::<NextState<S>>()
+        .ok_or("the state is not initialized")?;
+    next.set(state);
+    Ok(())
+}
+
+fn run_reflected(world: &mut World, command: &str, args: Vec<String>) {
+    let targets = world
+        .get_resource::<CliReflectTargets>()
+        .and_then(|targets| targets.commands.get(command))
+        .cloned()
+        .unwrap_or_default();
+    let type_names: Vec<&str> = targets.iter().map(|t| t.short_name.as_str()).collect();
+    let type_names = type_names.join(", ");
+    // The first arg may name the type
+    let named = args.first().and_then(|arg| {
+        targets
+            .iter()
+            .find(|t| t.short_name == *arg || t.path == arg)
+            .cloned()
+    });
+    let (candidates, value) = match named {
+        Some(target) => (vec![target], args[1..].join(" ")),
+        None if args.is_empty() && targets.len() > 1 => {
+            cli_error(
+                world,
+                format!("Usage: {} <type> [value] (types: {})", command, type_names),
+            );
+            cli_fail(world);
+            return;
+        }
+        None => (targets, args.join(" ")),
+    };
+    let mut last_error = None;
+    for target in &candidates {
+        // Events of different types may look alike, so the type must be given
+        if candidates.len() > 1 && !target.is_state {
+            continue;
+        }
+        let parsed = parse_reflected(world, target.type_id, &value);
+        let result = parsed.and_then(|parsed| (target.apply)(world, &*parsed));
+        match result {
+            Ok(()) => {
+                let what = if target.is_state { "Set state" } else { "Sent" };
+                let message = format!("{} {} {}", what, target.short_name, value);
+                cli_println(world, message.trim_end());
+                return;
+            }
+            Err(e) => last_error = Some(format!("{}: {}", target.short_name, e)),
+        }
+    }
+    let message = match last_error {
+        Some(e) => format!("Cannot construct {:?}: {}!", value, e),
+        None => format!(
+            "Unknown type {:?}! Types: {}",
+            args.first().map_or("", |arg| arg.as_str()),
+            type_names
+        ),
+    };
+    cli_error(world, message);
+    cli_fail(world);
+}
+
+/// Construct a value of a registered type from RON text, or its default value
+fn parse_reflected(
+    world: &World,
+    type_id: TypeId,
+    text: &str,
+) -> Result<Box<dyn PartialReflect>, String> {
+    let registry = world.resource::<AppTypeRegistry>().read();
+    let registration = registry.get(type_id).ok_or("the type is not registered")?;
+    let text = text.trim();
+    if text.is_empty() {
+        if let Some(default) = registration.data::<ReflectDefault>() {
+            return Ok(default.default().into_partial_reflect());
+        }
+    }
+    let text = match text.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+        Some(fields) => format!("({})", fields),
+        None if text.is_empty() => "()".to_owned(),
+        None => text.to_owned(),
+    };
+    let mut deserializer = ron::Deserializer::from_str(&text).map_err(|e| e.to_string())?;
+    TypedReflectDeserializer::new(registration, &registry)
+        .deserialize(&mut deserializer)
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,1107 @@
+//! Remote console server, for administering (headless) apps over TCP.
+//!
+//! Requires the `remote` cargo feature. Add [`CliRemotePlugin`] (together
+//! with [`CliPlugin`](crate::CliPlugin)) to listen for connections.
+//!
+//! The protocol is line-based text. The first line sent by the client must
+//! be the password. The server replies `auth ok`, or `auth failed` and
+//! closes the connection. After that, every line is run as a command
+//! string, and the server replies with the output of the command, one
+//! line at a time prefixed with `out: `, followed by `ok` or `failed`.
+//!
+//! ```text
+//! > hunter2
+//! < auth ok
+//! > gravity
+//! < out: gravity = 9.8
+//! < ok
+//! ```
+//!
+//! Commands that ask for multi-line text (see [`editor`](crate::editor))
+//! make the server send `edit <id> <command> <text>`, with the command and
+//! the initial text quoted like command args (newlines escaped as `\n`).
+//! The client shows an editor, and replies `reply: <id> <text>` with the
+//! edited text quoted the same way, or `cancel: <id>`. The reply is run
+//! like a command string, with its output and `ok` or `failed`:
+//!
+//! ```text
+//! > edit config_apply
+//! < edit 1 config_apply ""
+//! < ok
+//! > reply: 1 "(volume: 0.5,\n fullscreen: true)"
+//! < out: Config applied
+//! < ok
+//! ```
+//!
+//! Commands that ask a question (see [`prompt`](crate::prompt)) make the
+//! server send `prompt <id> <kind> <message>`, where the kind is `secret`
+//! for answers that must not be shown (like passwords), or `plain`. The
+//! client replies `answer: <id> <text>`, with the answer quoted like a
+//! command arg, or `cancel: <id>`:
+//!
+//! ```text
+//! > new_world
+//! < prompt 1 plain "Enter seed:"
+//! < ok
+//! > answer: 1 1234
+//! < out: Generating world with seed 1234
+//! < ok
+//! ```
+//!
+//! The client can ask for the completions of a partially typed command
+//! string with `complete: <partial>`. The server replies with a
+//! `completion: ` line per completion, with the whole completed string,
+//! followed by `ok`:
+//!
+//! ```text
+//! > complete: spaw
+//! < completion: spawn
+//! < completion: spawn_boss
+//! < ok
+//! ```
+//!
+//! Every authenticated connection has its own [`CliSession`], on an entity
+//! with a [`CliRemoteSession`]: its command history, and the variables and
+//! aliases set with `set` and `alias`, are its own. The built-in `who`
+//! command lists the sessions (including detached ones, see below), and
+//! `kickcli <session>` disconnects one, for good (as does despawning its
+//! entity):
+//!
+//! ```text
+//! > who
+//! < out: SESSION  ADDRESS         PROFILE    CONNECTED
+//! < out: -------  --------------  ---------  ---------
+//! < out:       1  10.0.0.5:50123  -               312s
+//! < out:       2  10.0.0.7:41002  moderator        25s
+//! < ok
+//! ```
+//!
+//! Files attached to the output (see [`CliAttachment`](crate::output::CliAttachment)),
+//! like screenshots, are sent after the line describing them, as
+//! `attachment: <name> <mime> <data>`, with the name and MIME type quoted
+//! like command args, and the data in base64:
+//!
+//! ```text
+//! > screenshot
+//! < out: [attachment: screenshot.png (image/png, 48213 bytes)]
+//! < attachment: screenshot.png image/png iVBORw0KGgoAAAANSUhEUgAA...
+//! < ok
+//! ```
+//!
+//! Output that comes after the reply, like that of background
+//! [jobs](crate::jobs) started by the client, is sent as more `out: ` lines
+//! as it comes, without `ok` or `failed`.
+//!
+//! Clients can also subscribe to output channels, to stream things like
+//! server logs or chat through the same connection. The game publishes
+//! lines on channels with [`CliChannels::publish`]; with the `log-capture`
+//! cargo feature, captured log records are published on the `log` channel.
+//! The client subscribes with `subscribe: <channel> [--min <severity>]
+//! [--grep <text>]`, receiving only the lines at least as severe as
+//! `--min` (one of `info`, `success`, `warn` and `error`) that contain the
+//! `--grep` text. Subscribing again replaces the filter. The lines of the
+//! channel are sent as `channel: <channel> <severity> <text>`, until
+//! `unsubscribe: <channel>`:
+//!
+//! ```text
+//! > subscribe: log --min warn
+//! < ok
+//! < channel: log warn mygame::physics: Body 4v2 fell through the floor
+//! > unsubscribe: log
+//! < ok
+//! ```
+//!
+//! Either side can send `ping` at any time, and the other replies `pong`.
+//! The server pings clients that have been silent for
+//! [`CliRemotePlugin::heartbeat`], and disconnects those silent for four
+//! times as long. When the app exits, the server sends `shutdown: <text>`
+//! to every client, before closing the connection.
+//!
+//! After `auth ok`, the server sends `session <id> <token>`. If the
+//! connection is lost, the session is kept for
+//! [`CliRemotePlugin::resume_timeout`], collecting the output of its
+//! commands. A new connection, authenticated the same way, can take it
+//! back with `resume: <token>`: the server replies `session <id> <token>`,
+//! then the last lines of output of the session as `scrollback: ` lines
+//! (including the output it missed), followed by `ok`. Otherwise, it
+//! replies `failed`, and the connection keeps its new session:
+//!
+//! ```text
+//! > hunter2
+//! < auth ok
+//! < session 3 9f0c7e2a41d8b6355a1e0c2f7d94b8e6
+//! > resume: 5e2b91c04a7f3d8e6b1c0a9f2e4d7c53
+//! < session 1 5e2b91c04a7f3d8e6b1c0a9f2e4d7c53
+//! < scrollback: gravity = 9.8
+//! < ok
+//! ```
+//!
+//! A command string prefixed with `key: <key> ` runs at most once per key
+//! (see [`idempotency`](crate::idempotency)). A client that lost the reply
+//! to a command can send it again with the same key: if it already ran,
+//! the server replies with its output and result again, without running
+//! it. Keys are scoped to the password or token the client authenticated
+//! with:
+//!
+//! ```text
+//! > key: 7d1e ban 4v2 --force
+//! < out: Banned 4v2
+//! < ok
+//! ```
+//!
+//! Anyone with the password can run any command. Which addresses may
+//! connect, how many commands they may run, and tokens with fewer
+//! permissions than the password are set in the
+//! [`CliRemoteSecurityConfig`](crate::security::CliRemoteSecurityConfig),
+//! shared with the [`http`](crate::http) server. The connection is not
+//! encrypted, unless [`CliRemotePlugin::tls`] is set (with the `tls` cargo
+//! feature). Otherwise, only listen on trusted networks, or tunnel it (over
+//! SSH, for example).
+//!
+//! The [`client`](crate::client) module implements the client side, for
+//! terminal clients.
+
+use std::collections::VecDeque;
+use std::hash::{BuildHasher, Hasher, RandomState};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::time::Duration;
+
+use base64::prelude::*;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, Instant};
+
+use crate::editor::{CliEditor, CliEditorRequest};
+use crate::idempotency::CliIdempotency;
+use crate::output::{capture_output, CliOutput, CliOutputLine, CliOutputLog, CliSeverity};
+use crate::prompt::{CliPrompt, CliPromptRequest};
+use crate::quota::with_cli_source;
+use crate::security::{
+    self, CliRemoteAuth, CliRemoteSecurityConfig, CliRemoteStream, CliTlsAcceptor, CliTlsConfig,
+};
+use crate::session::{run_in_session_as, CliSession};
+use crate::source::{constant_time_eq, CliSource};
+use crate::status::CliStatus;
+use crate::table::{CliAlign, CliTable};
+use crate::{parse_cli, quote_cli_arg, CliCommands, CliCommandsRegisterExt};
+
+/// Longest line a client may send, in bytes
+const MAX_LINE_LEN: usize = 4096;
+
+/// Plugin that runs a remote console server
+pub struct CliRemotePlugin {
+    /// The address to listen on, like `127.0.0.1:27015`
+    pub address: String,
+    /// The password clients must send to be allowed to run commands
+    pub password: String,
+    /// The certificate to encrypt connections with, if any
+    ///
+    /// See [`security`](crate::security).
+    pub tls: Option<CliTlsConfig>,
+    /// How long a client may be silent before it is sent a `ping`, if ever
+    ///
+    /// Clients silent for four times as long are disconnected.
+    pub heartbeat: Option<Duration>,
+    /// How long the session of a disconnected client is kept, to be resumed
+    pub resume_timeout: Duration,
+}
+
+impl Default for CliRemotePlugin {
+    fn default() -> Self {
+        CliRemotePlugin {
+            address: "127.0.0.1:27015".to_owned(),
+            password: String::new(),
+            tls: None,
+            heartbeat: Some(Duration::from_secs(15)),
+            resume_timeout: Duration::from_secs(300),
+        }
+    }
+}
+
+impl Plugin for CliRemotePlugin {
+    fn build(&self, app: &mut App) {
+        if self.password.is_empty() {
+            error!("Refusing to start remote CLI server without a password!");
+            return;
+        }
+        let tls = match CliTlsAcceptor::new(self.tls.as_ref()) {
+            Ok(tls) => tls,
+            Err(e) => {
+                error!("Cannot start remote CLI server on {}: {}", self.address, e);
+                return;
+            }
+        };
+        let listener = match TcpListener::bind(&self.address) {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("Cannot start remote CLI server on {}: {}", self.address, e);
+                return;
+            }
+        };
+        if let Err(e) = listener.set_nonblocking(true) {
+            error!("Cannot start remote CLI server on {}: {}", self.address, e);
+            return;
+        }
+        info!("Remote CLI server listening on {}", self.address);
+        app.init_resource::<CliRemoteSecurityConfig>();
+        app.insert_resource(CliRemoteServer {
+            listener,
+            tls,
+            password: self.password.clone(),
+            clients: Vec::new(),
+            detached: Vec::new(),
+            next_id: 0,
+            heartbeat: self.heartbeat,
+            resume_timeout: self.resume_timeout,
+            #[cfg(feature = "log-capture")]
+            last_log: 0,
+        });
+        app.init_resource::<CliChannels>();
+        app.add_systems(PreUpdate, serve_remote);
+        app.add_systems(Last, notify_shutdown);
+        app.register_clicommand_noargs("who", who)
+            .set_clicommand_description("who", "List the remote console sessions");
+        app.register_clicommand_args("kickcli", kickcli)
+            .set_clicommand_description("kickcli", "Disconnect a remote console session")
+            .set_clicommand_usage("kickcli", "kickcli <session>");
+    }
+}
+
+/// The state of the remote console server
+#[derive(Resource)]
+pub struct CliRemoteServer {
+    listener: TcpListener,
+    tls: CliTlsAcceptor,
+    password: String,
+    clients: Vec<CliRemoteClient>,
+    /// Sessions of disconnected clients, that can be resumed
+    detached: Vec<CliDetachedSession>,
+    next_id: u64,
+    heartbeat: Option<Duration>,
+    resume_timeout: Duration,
+    /// The last log record published on the `log` channel
+    #[cfg(feature = "log-capture")]
+    last_log: u64,
+}
+
+impl CliRemoteServer {
+    /// The addresses of the connected clients, and whether they have authenticated
+    pub fn clients(&self) -> impl Iterator<Item = (SocketAddr, bool)> + '_ {
+        self.clients
+            .iter()
+            .map(|client| (client.addr, client.authenticated))
+    }
+    /// The address the server is listening on
+    ///
+    /// Useful to find the port chosen by the system, when listening on port `0`.
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+    /// Disconnect all clients, telling them why
+    ///
+    /// Their sessions can be resumed, until the resume timeout of the plugin.
+    pub fn disconnect_all(&mut self, message: &str) {
+        for client in &mut self.clients {
+            client.send(&format!("shutdown: {}", message));
+            client.flush();
+            client.closed = true;
+        }
+    }
+}
+
+/// Output channels that remote clients can subscribe to
+///
+/// Lines published on a channel are sent to the clients subscribed to it,
+/// in the next update. Lines published while nobody is subscribed are
+/// dropped.
+#[derive(Resource, Debug, Default)]
+pub struct CliChannels {
+    pending: Vec<(String, CliSeverity, String)>,
+}
+
+impl CliChannels {
+    /// Publish a line of text on a channel, like `chat`
+    pub fn publish(&mut self, channel: &str, text: impl Into<String>) {
+        self.publish_severity(channel, CliSeverity::Info, text);
+    }
+    pub fn publish_severity(
+        &mut self,
+        channel: &str,
+        severity: CliSeverity,
+        text: impl Into<String>,
+    ) {
+        self.pending
+            .push((channel.to_owned(), severity, text.into()));
+    }
+}
+
+/// Which lines of a channel a client wants
+#[derive(Debug, Clone, Default)]
+struct CliChannelFilter {
+    min_severity: CliSeverity,
+    contains: Option<String>,
+}
+
+impl CliChannelFilter {
+    /// Parse the options of `subscribe: `
+    fn parse(args: &[String]) -> Option<Self> {
+        let mut filter = CliChannelFilter::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--min" => filter.min_severity = CliSeverity::parse(args.next()?)?,
+                "--grep" => filter.contains = Some(args.next()?.clone()),
+                _ => return None,
+            }
+        }
+        Some(filter)
+    }
+    fn matches(&self, severity: CliSeverity, text: &str) -> bool {
+        severity >= self.min_severity
+            && self
+                .contains
+                .as_ref()
+                .is_none_or(|contains| text.contains(contains.as_str()))
+    }
+}
+
+/// The session of an authenticated remote console client
+///
+/// On the entity of the [`CliSession`] its command strings run in.
+/// Despawning the entity disconnects the client.
+#[derive(Component, Debug, Clone)]
+pub struct CliRemoteSession {
+    /// The number of the session, shown by `who`
+    pub id: u64,
+    pub addr: SocketAddr,
+    /// The name of the profile it authenticated with, unless it used the password
+    pub profile: Option<String>,
+    pub connected: Instant,
+    /// The client disconnected, and the session waits to be resumed
+    pub detached: bool,
+}
+
+/// How many lines of output are kept for each session, to resume it
+const SCROLLBACK_LEN: usize = 200;
+
+/// The session of a client that disconnected, that can be resumed
+struct CliDetachedSession {
+    entity: Entity,
+    token: String,
+    /// The addresses the session was connected from, the last one last
+    addrs: Vec<SocketAddr>,
+    scrollback: VecDeque<String>,
+    since: Instant,
+}
+
+struct CliRemoteClient {
+    stream: CliRemoteStream,
+    addr: SocketAddr,
+    authenticated: bool,
+    /// The entity of its session, once authenticated
+    session: Option<Entity>,
+    /// The secret to resume the session with, after reconnecting
+    resume_token: String,
+    /// The last lines of output sent to the client
+    scrollback: VecDeque<String>,
+    /// The addresses the session was connected from before it was resumed
+    old_addrs: Vec<SocketAddr>,
+    /// The session was closed, so it cannot be resumed
+    kicked: bool,
+    last_received: Instant,
+    last_ping: Instant,
+    /// The client has closed its side of the connection
+    eof: bool,
+    closed: bool,
+    incoming: Vec<u8>,
+    outgoing: Vec<u8>,
+    /// Editors sent to the client, waiting for a reply
+    editors: HashMap<u64, CliEditorRequest>,
+    /// Questions sent to the client, waiting for an answer
+    prompts: HashMap<u64, CliPromptRequest>,
+    /// The channels the client is subscribed to
+    subscriptions: HashMap<String, CliChannelFilter>,
+}
+
+impl CliRemoteClient {
+    /// Read everything that is available without blocking
+    fn receive(&mut self) {
+        let mut buf = [0; 1024];
+        loop {
+            match self.stream.read(&mut buf) {
+                Ok(0) => {
+                    self.eof = true;
+                    return;
+                }
+                Ok(n) => self.incoming.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => {
+                    self.closed = true;
+                    return;
+                }
+            }
+        }
+    }
+    fn next_line(&mut self) -> Option<String> {
+        let Some(end) = self.incoming.iter().position(|&b| b == b'\n') else {
+            if self.incoming.len() > MAX_LINE_LEN {
+                self.send("line too long");
+                self.closed = true;
+            }
+            return None;
+        };
+        let line: Vec<u8> = self.incoming.drain(..=end).collect();
+        let line = String::from_utf8_lossy(&line);
+        Some(line.trim_end_matches(['\r', '\n']).to_owned())
+    }
+    fn send(&mut self, line: &str) {
+        self.outgoing.extend_from_slice(line.as_bytes());
+        self.outgoing.push(b'\n');
+    }
+    /// Send a line of output, and any attachment, keeping its text in the
+    /// scrollback
+    fn send_output(&mut self, line: &CliOutputLine) {
+        self.send(&format!("out: {}", line.text));
+        if let Some(attachment) = &line.attachment {
+            self.send(&format!(
+                "attachment: {} {} {}",
+                quote_cli_arg(&attachment.name),
+                quote_cli_arg(&attachment.mime),
+                BASE64_STANDARD.encode(&attachment.data)
+            ));
+        }
+        if self.scrollback.len() == SCROLLBACK_LEN {
+            self.scrollback.pop_front();
+        }
+        self.scrollback.push_back(line.text.clone());
+    }
+    /// Write as much of the pending output as possible without blocking
+    fn flush(&mut self) {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => {
+                    self.closed = true;
+                    return;
+                }
+                Ok(n) => {
+                    self.outgoing.drain(..n);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => {
+                    self.closed = true;
+                    return;
+                }
+            }
+        }
+        match self.stream.flush() {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(_) => self.closed = true,
+        }
+    }
+}
+
+fn serve_remote(world: &mut World) {
+    world.resource_scope(|world, mut server: Mut<CliRemoteServer>| {
+        let server = &mut *server;
+        loop {
+            match server.listener.accept() {
+                Ok((stream, addr)) => {
+                    if let Err(e) = stream.set_nonblocking(true) {
+                        error!("Cannot accept remote CLI client {}: {}", addr, e);
+                        continue;
+                    }
+                    if !security::accept(world, addr) {
+                        continue;
+                    }
+                    let stream = match server.tls.accept(stream) {
+                        Ok(stream) => stream,
+                        Err(e) => {
+                            error!("Cannot accept remote CLI client {}: {}", addr, e);
+                            continue;
+                        }
+                    };
+                    info!("Remote CLI client connected: {}", addr);
+                    server.clients.push(CliRemoteClient {
+                        stream,
+                        addr,
+                        authenticated: false,
+                        session: None,
+                        resume_token: String::new(),
+                        scrollback: VecDeque::new(),
+                        old_addrs: Vec::new(),
+                        kicked: false,
+                        last_received: Instant::now(),
+                        last_ping: Instant::now(),
+                        eof: false,
+                        closed: false,
+                        incoming: Vec::new(),
+                        outgoing: Vec::new(),
+                        editors: HashMap::new(),
+                        prompts: HashMap::new(),
+                        subscriptions: HashMap::new(),
+                    });
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    error!("Remote CLI server error: {}", e);
+                    break;
+                }
+            }
+        }
+        expire_detached(world, server);
+        #[cfg(feature = "log-capture")]
+        publish_log(world, &mut server.last_log);
+        let published = world
+            .get_resource_mut::<CliChannels>()
+            .map(|mut channels| std::mem::take(&mut channels.pending))
+            .unwrap_or_default();
+        for client in &mut server.clients {
+            for (channel, severity, text) in &published {
+                if client
+                    .subscriptions
+                    .get(channel)
+                    .is_some_and(|filter| filter.matches(*severity, text))
+                {
+                    client.send(&format!(
+                        "channel: {} {} {}",
+                        channel,
+                        severity.as_str(),
+                        text
+                    ));
+                }
+            }
+            // Kicked, with `kickcli` or by despawning its session
+            if client
+                .session
+                .is_some_and(|session| world.get_entity(session).is_err())
+            {
+                client.send("out: Your session was closed");
+                client.kicked = true;
+                client.closed = true;
+            }
+            // Output of commands the client ran earlier (see `source`)
+            if client.authenticated {
+                let addrs = client.old_addrs.iter().chain([&client.addr]);
+                for output_line in drain_output(world, addrs) {
+                    client.send_output(&output_line);
+                }
+            }
+            let received = client.incoming.len();
+            client.receive();
+            let now = Instant::now();
+            if client.incoming.len() != received {
+                client.last_received = now;
+            }
+            if let (Some(heartbeat), true) = (server.heartbeat, client.authenticated) {
+                let silent = now.duration_since(client.last_received);
+                if silent >= heartbeat * 4 {
+                    info!("Remote CLI client {} timed out", client.addr);
+                    client.closed = true;
+                } else if silent >= heartbeat && now.duration_since(client.last_ping) >= heartbeat {
+                    client.send("ping");
+                    client.last_ping = now;
+                }
+            }
+            while !client.closed {
+                let Some(line) = client.next_line() else {
+                    break;
+                };
+                if !client.authenticated {
+                    let auth = security::authenticate(world, client.addr, &line, &server.password);
+                    if let Some(auth) = auth {
+                        let profile = match auth {
+                            CliRemoteAuth::Password => None,
+                            CliRemoteAuth::Profile(name) => Some(name),
+                        };
+                        if let Some(name) = &profile {
+                            info!(
+                                "Remote CLI client {} authenticated as {}",
+                                client.addr, name
+                            );
+                        }
+                        server.next_id += 1;
+                        let session = world.spawn((
+                            CliSession::default(),
+                            CliRemoteSession {
+                                id: server.next_id,
+                                addr: client.addr,
+                                profile,
+                                connected: Instant::now(),
+                                detached: false,
+                            },
+                        ));
+                        client.session = Some(session.id());
+                        client.resume_token = resume_token();
+                        client.authenticated = true;
+                        client.send("auth ok");
+                        client.send(&format!(
+                            "session {} {}",
+                            server.next_id, client.resume_token
+                        ));
+                    } else {
+                        warn!("Remote CLI client {} failed to authenticate", client.addr);
+                        client.send("auth failed");
+                        client.closed = true;
+                    }
+                    continue;
+                }
+                match line.trim() {
+                    "" | "pong" => continue,
+                    "ping" => {
+                        client.send("pong");
+                        continue;
+                    }
+                    _ => {}
+                }
+                if let Some(token) = line.strip_prefix("resume: ") {
+                    resume_session(world, &mut server.detached, client, token.trim());
+                    continue;
+                }
+                if let Some(reply) = line.strip_prefix("reply: ") {
+                    reply_editor(world, client, reply);
+                    continue;
+                }
+                if let Some(answer) = line.strip_prefix("answer: ") {
+                    answer_prompt(world, client, answer);
+                    continue;
+                }
+                if let Some(partial_line) = line.strip_prefix("complete: ") {
+                    let completions = world
+                        .resource::<CliCommands>()
+                        .complete(world, partial_line);
+                    for completion in completions {
+                        client.send(&format!("completion: {}", completion.apply(partial_line)));
+                    }
+                    client.send("ok");
+                    continue;
+                }
+                if let Some(subscription) = line.strip_prefix("subscribe: ") {
+                    let parsed = parse_cli(subscription).ok().and_then(|parsed| {
+                        let filter = CliChannelFilter::parse(parsed.args())?;
+                        Some((parsed.name().to_owned(), filter))
+                    });
+                    let Some((channel, filter)) = parsed else {
+                        client.send("out: Invalid subscription!");
+                        client.send("failed");
+                        continue;
+                    };
+                    client.subscriptions.insert(channel, filter);
+                    client.send("ok");
+                    continue;
+                }
+                if let Some(channel) = line.strip_prefix("unsubscribe: ") {
+                    let found = client.subscriptions.remove(channel.trim()).is_some();
+                    client.send(if found { "ok" } else { "failed" });
+                    continue;
+                }
+                if let Some(id) = line.strip_prefix("cancel: ") {
+                    let found = id.trim().parse::<u64>().is_ok_and(|id| {
+                        client.editors.remove(&id).is_some() || client.prompts.remove(&id).is_some()
+                    });
+                    client.send(if found { "ok" } else { "failed" });
+                    continue;
+                }
+                if let Some(keyed) = line.strip_prefix("key: ") {
+                    run_remote_keyed(world, client, keyed);
+                    continue;
+                }
+                info!("Remote CLI command from {}: {}", client.addr, line);
+                run_remote(world, client, &line);
+            }
+            client.flush();
+            if client.eof {
+                client.closed = true;
+            }
+        }
+        for client in server.clients.iter_mut().filter(|client| client.closed) {
+            info!("Remote CLI client disconnected: {}", client.addr);
+            client.stream.close();
+            let _ = client.stream.flush();
+            let mut addrs = std::mem::take(&mut client.old_addrs);
+            addrs.push(client.addr);
+            match client.session {
+                Some(entity) if !client.kicked && !server.resume_timeout.is_zero() => {
+                    if let Some(mut session) = world.get_mut::<CliRemoteSession>(entity) {
+                        session.detached = true;
+                    }
+                    server.detached.push(CliDetachedSession {
+                        entity,
+                        token: std::mem::take(&mut client.resume_token),
+                        addrs,
+                        scrollback: std::mem::take(&mut client.scrollback),
+                        since: Instant::now(),
+                    });
+                }
+                _ => {
+                    for addr in addrs {
+                        security::forget(world, addr);
+                    }
+                }
+            }
+        }
+        server.clients.retain(|client| !client.closed);
+        // Sessions of clients that disconnected, and cannot be resumed
+        let mut q_sessions = world.query_filtered::<Entity, With<CliRemoteSession>>();
+        let closed: Vec<Entity> = q_sessions
+            .iter(world)
+            .filter(|&entity| server.clients.iter().all(|c| c.session != Some(entity)))
+            .filter(|&entity| server.detached.iter().all(|d| d.entity != entity))
+            .collect();
+        for entity in closed {
+            world.despawn(entity);
+        }
+    });
+}
+
+/// Take the output of the commands run from some addresses
+fn drain_output<'a>(
+    world: &mut World,
+    addrs: impl IntoIterator<Item = &'a SocketAddr>,
+) -> Vec<CliOutputLine> {
+    let Some(mut log) = world.get_resource_mut::<CliOutputLog>() else {
+        return Vec::new();
+    };
+    addrs
+        .into_iter()
+        .flat_map(|addr| log.drain_for(&CliSource::Remote(*addr)))
+        .collect()
+}
+
+/// Keep the output of detached sessions, and end those that waited too long
+/// to be resumed, or were kicked
+fn expire_detached(world: &mut World, server: &mut CliRemoteServer) {
+    let mut expired = Vec::new();
+    for (i, detached) in server.detached.iter_mut().enumerate() {
+        if detached.since.elapsed() >= server.resume_timeout
+            || world.get_entity(detached.entity).is_err()
+        {
+            expired.push(i);
+            continue;
+        }
+        for output_line in drain_output(world, &detached.addrs) {
+            if detached.scrollback.len() == SCROLLBACK_LEN {
+                detached.scrollback.pop_front();
+            }
+            detached.scrollback.push_back(output_line.text);
+        }
+    }
+    for i in expired.into_iter().rev() {
+        let detached = server.detached.remove(i);
+        if let Ok(entity) = world.get_entity_mut(detached.entity) {
+            entity.despawn();
+        }
+        for addr in detached.addrs {
+            security::forget(world, addr);
+        }
+    }
+}
+
+/// Attach the session of a disconnected client to a new connection,
+/// sending it the scrollback of the session
+fn resume_session(
+    world: &mut World,
+    detached: &mut Vec<CliDetachedSession>,
+    client: &mut CliRemoteClient,
+    token: &str,
+) {
+    let profile = client
+        .session
+        .and_then(|entity| world.get::<CliRemoteSession>(entity))
+        .map(|session| session.profile.clone());
+    // The session must have been authenticated the same way
+    let found = detached.iter().position(|d| {
+        constant_time_eq(d.token.as_bytes(), token.as_bytes())
+            && world
+                .get::<CliRemoteSession>(d.entity)
+                .map(|session| session.profile.clone())
+                == profile
+    });
+    let Some(i) = found else {
+        client.send("out: There is no such session to resume!");
+        client.send("failed");
+        return;
+    };
+    let resumed = detached.remove(i);
+    if let Some(entity) = client.session.replace(resumed.entity) {
+        world.despawn(entity);
+    }
+    let Some(mut session) = world.get_mut::<CliRemoteSession>(resumed.entity) else {
+        return;
+    };
+    session.addr = client.addr;
+    session.detached = false;
+    info!(
+        "Remote CLI client {} resumed session {}",
+        client.addr, session.id
+    );
+    client.send(&format!("session {} {}", session.id, resumed.token));
+    for text in &resumed.scrollback {
+        client.send(&format!("scrollback: {}", text));
+    }
+    client.resume_token = resumed.token;
+    client.scrollback = resumed.scrollback;
+    client.old_addrs = resumed.addrs;
+    client.send("ok");
+}
+
+/// A new secret for resuming a session
+fn resume_token() -> String {
+    // `RandomState` is seeded randomly by the standard library
+    let random = || RandomState::new().build_hasher().finish();
+    format!("{:016x}{:016x}", random(), random())
+}
+
+/// Tell the clients that the app is exiting
+fn notify_shutdown(mut exit: EventReader<AppExit>, server: Option<ResMut<CliRemoteServer>>) {
+    if exit.read().last().is_none() {
+        return;
+    }
+    if let Some(mut server) = server {
+        server.disconnect_all("The server is shutting down");
+    }
+}
+
+/// Publish the log records captured since the last update on the `log` channel
+#[cfg(feature = "log-capture")]
+fn publish_log(world: &mut World, last_log: &mut u64) {
+    let Some(buffer) = world.get_resource::<crate::log_capture::CliLogBuffer>() else {
+        return;
+    };
+    let records: Vec<_> = buffer
+        .iter_since(*last_log)
+        .map(|record| {
+            *last_log = record.seq;
+            (
+                record.severity(),
+                format!("{}: {}", record.target, record.message),
+            )
+        })
+        .collect();
+    let Some(mut channels) = world.get_resource_mut::<CliChannels>() else {
+        return;
+    };
+    for (severity, text) in records {
+        channels.publish_severity("log", severity, text);
+    }
+}
+
+/// Run a command string for a client, unless it already ran with the same
+/// idempotency key, sending back its output and result
+fn run_remote_keyed(world: &mut World, client: &mut CliRemoteClient, keyed: &str) {
+    let Some((key, line)) = keyed
+        .trim_start()
+        .split_once(char::is_whitespace)
+        .filter(|(_, line)| !line.trim().is_empty())
+    else {
+        client.send("out: Usage: key: <key> <command string>");
+        client.send("failed");
+        return;
+    };
+    let profile = client
+        .session
+        .and_then(|entity| world.get::<CliRemoteSession>(entity))
+        .and_then(|session| session.profile.clone())
+        .unwrap_or_default();
+    let key = format!("remote:{}:{}", profile, key);
+    let cached = world
+        .get_resource::<CliIdempotency>()
+        .and_then(|results| results.get(&key))
+        .cloned();
+    if let Some(result) = cached {
+        info!(
+            "Remote CLI command from {} already ran: {}",
+            client.addr, line
+        );
+        for output_line in &result.output {
+            client.send_output(output_line);
+        }
+        client.send(if result.success { "ok" } else { "failed" });
+        return;
+    }
+    info!("Remote CLI command from {}: {}", client.addr, line);
+    if let Some((output, success)) = run_remote(world, client, line) {
+        world
+            .get_resource_or_insert_with(CliIdempotency::default)
+            .insert(&key, success, output);
+    }
+}
+
+/// Run a command string for a client, sending back its output and result
+///
+/// Returns the output and whether it succeeded, unless it did not run.
+fn run_remote(
+    world: &mut World,
+    client: &mut CliRemoteClient,
+    line: &str,
+) -> Option<(Vec<CliOutputLine>, bool)> {
+    if !security::check_rate(world, client.addr, false) {
+        client.send("out: Rate limit exceeded, try again later!");
+        client.send("failed");
+        return None;
+    }
+    let session = client.session?;
+    if let Some(mut session) = world.get_mut::<CliSession>(session) {
+        *session.input_mut() = line.to_owned();
+        session.submit();
+    }
+    let source = CliSource::Remote(client.addr);
+    Some(run_remote_with(world, client, |world| {
+        capture_output(world, |world| {
+            run_in_session_as(world, session, line, source);
+        })
+        .0
+    }))
+}
+
+/// Run something for a client, sending back its output and result
+///
+/// Returns the output and whether it succeeded.
+fn run_remote_with(
+    world: &mut World,
+    client: &mut CliRemoteClient,
+    f: impl FnOnce(&mut World) -> Vec<CliOutputLine>,
+) -> (Vec<CliOutputLine>, bool) {
+    let editor_id = world
+        .get_resource::<CliEditor>()
+        .and_then(|editor| editor.request().map(|request| request.id()));
+    let prompt_id = world
+        .get_resource::<CliPrompt>()
+        .and_then(|prompt| prompt.request().map(|request| request.id()));
+    let output = with_cli_source(world, CliSource::Remote(client.addr), f);
+    for output_line in &output {
+        client.send_output(output_line);
+    }
+    // Forward any editor the command asked for to the client
+    let request = world
+        .get_resource_mut::<CliEditor>()
+        .filter(|editor| editor.request().map(|request| request.id()) != editor_id)
+        .and_then(|mut editor| editor.take_request());
+    if let Some(request) = request {
+        client.send(&format!(
+            "edit {} {} {}",
+            request.id(),
+            quote_cli_arg(request.command()),
+            quote_cli_arg(request.text())
+        ));
+        client.editors.insert(request.id(), request);
+    }
+    // Same for any question
+    let request = world
+        .get_resource_mut::<CliPrompt>()
+        .filter(|prompt| prompt.request().map(|request| request.id()) != prompt_id)
+        .and_then(|mut prompt| prompt.take_request());
+    if let Some(request) = request {
+        client.send(&format!(
+            "prompt {} {} {}",
+            request.id(),
+            if request.is_secret() {
+                "secret"
+            } else {
+                "plain"
+            },
+            quote_cli_arg(request.message())
+        ));
+        client.prompts.insert(request.id(), request);
+    }
+    let success = world
+        .get_resource::<CliStatus>()
+        .is_some_and(|status| status.last_success() == Some(true));
+    client.send(if success { "ok" } else { "failed" });
+    (output, success)
+}
+
+/// Run the command of an editor sent to a client, with the edited text
+fn reply_editor(world: &mut World, client: &mut CliRemoteClient, reply: &str) {
+    let (id, text) = match parse_cli(reply) {
+        Ok(parsed) => (parsed.name.parse::<u64>().ok(), parsed.args),
+        Err(_) => (None, Vec::new()),
+    };
+    let request = id.and_then(|id| client.editors.remove(&id));
+    let (Some(mut request), [text]) = (request, text.as_slice()) else {
+        client.send("out: Invalid editor reply!");
+        client.send("failed");
+        return;
+    };
+    *request.text_mut() = text.clone();
+    let line = request.into_cli();
+    info!("Remote CLI command from {}: {}", client.addr, line);
+    run_remote(world, client, &line);
+}
+
+/// Deliver the answer to a question sent to a client
+fn answer_prompt(world: &mut World, client: &mut CliRemoteClient, answer: &str) {
+    let (id, text) = match parse_cli(answer) {
+        Ok(parsed) => (parsed.name.parse::<u64>().ok(), parsed.args),
+        Err(_) => (None, Vec::new()),
+    };
+    let request = id.and_then(|id| client.prompts.remove(&id));
+    let (Some(request), [text]) = (request, text.as_slice()) else {
+        client.send("out: Invalid answer!");
+        client.send("failed");
+        return;
+    };
+    // Do not log the answer, it may be a password
+    info!(
+        "Remote CLI answer from {} to {:?}",
+        client.addr,
+        request.message()
+    );
+    let text = text.clone();
+    run_remote_with(world, client, |world| {
+        capture_output(world, |world| request.answer(world, text)).0
+    });
+}
+
+/// `who`: list the remote console sessions
+fn who(q_sessions: Query<&CliRemoteSession>, mut output: CliOutput) {
+    let mut sessions: Vec<_> = q_sessions.iter().collect();
+    if sessions.is_empty() {
+        output.println("No remote sessions");
+        return;
+    }
+    sessions.sort_by_key(|session| session.id);
+    let mut table = CliTable::new()
+        .with_column("SESSION", CliAlign::Right)
+        .with_column("ADDRESS", CliAlign::Left)
+        .with_column("PROFILE", CliAlign::Left)
+        .with_column("CONNECTED", CliAlign::Right);
+    for session in sessions {
+        table.add_row([
+            session.id.to_string(),
+            match session.detached {
+                true => format!("{} (detached)", session.addr),
+                false => session.addr.to_string(),
+            },
+            session.profile.clone().unwrap_or_else(|| "-".to_owned()),
+            format!("{}s", session.connected.elapsed().as_secs()),
+        ]);
+    }
+    output.println(table.to_string());
+}
+
+/// `kickcli <session>`: disconnect a remote console session
+fn kickcli(
+    In(args): In<Vec<String>>,
+    q_sessions: Query<(Entity, &CliRemoteSession)>,
+    mut output: CliOutput,
+    mut commands: Commands,
+) {
+    let [id] = args.as_slice() else {
+        output.fail("Usage: kickcli <session>");
+        return;
+    };
+    let Ok(id) = id.parse::<u64>() else {
+        output.fail(format!("Invalid session {:?}!", id));
+        return;
+    };
+    let Some((entity, session)) = q_sessions.iter().find(|(_, session)| session.id == id) else {
+        output.fail(format!("No remote session {}!", id));
+        return;
+    };
+    info!("Kicking remote CLI session {} ({})", id, session.addr);
+    commands.entity(entity).despawn();
+    output.println(format!("Session {} ({}) disconnected", id, session.addr));
+}
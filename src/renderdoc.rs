@@ -0,0 +1,65 @@
+//! Triggering RenderDoc frame captures from the console.
+//!
+//! Requires the `renderdoc` cargo feature. Add [`CliRenderDocPlugin`] to get
+//! the `gpu capture [frames]` command, so graphics programmers can capture
+//! a frame on a tester's machine. The game must be running with RenderDoc
+//! injected (or the RenderDoc library must be loadable), otherwise the
+//! command reports an error.
+
+use ::renderdoc::{RenderDoc, V110};
+use bevy::prelude::*;
+
+use crate::output::CliOutput;
+use crate::CliCommandsRegisterExt;
+
+/// Plugin that adds the `gpu capture [frames]` command
+pub struct CliRenderDocPlugin;
+
+impl Plugin for CliRenderDocPlugin {
+    fn build(&self, app: &mut App) {
+        match RenderDoc::<V110>::new() {
+            Ok(rd) => {
+                app.insert_non_send_resource(CliRenderDoc(rd));
+            }
+            Err(e) => {
+                warn!("RenderDoc is not available: {}", e);
+            }
+        }
+        app.register_clicommand_args("gpu", gpu)
+            .set_clicommand_description("gpu", "Capture frames with RenderDoc")
+            .set_clicommand_usage("gpu", "gpu capture [frames]");
+    }
+}
+
+struct CliRenderDoc(RenderDoc<V110>);
+
+/// `gpu capture [frames]`: trigger a RenderDoc capture of the next frame(s)
+fn gpu(In(args): In<Vec<String>>, rd: Option<NonSendMut<CliRenderDoc>>, mut output: CliOutput) {
+    let frames = match args.as_slice() {
+        [op] if op == "capture" => 1,
+        [op, n] if op == "capture" => match n.parse::<u32>() {
+            Ok(n) if n > 0 => n,
+            _ => {
+                error!("Invalid number of frames: {:?}", n);
+                return;
+            }
+        },
+        _ => {
+            error!("Usage: gpu capture [frames]");
+            return;
+        }
+    };
+    let Some(mut rd) = rd else {
+        error!("RenderDoc is not available!");
+        return;
+    };
+    if frames == 1 {
+        rd.0.trigger_capture();
+    } else {
+        rd.0.trigger_multi_frame_capture(frames);
+    }
+    output.println(format!(
+        "Triggered RenderDoc capture of {} frame(s).",
+        frames
+    ));
+}
@@ -0,0 +1,462 @@
+//! Recording and playback of "console commands".
+//!
+//! Requires the `replay` cargo feature (enabled by default).
+//!
+//! There are two kinds of recordings:
+//!
+//!  - [`CliRecording`]: every command string that is run, with the frame and
+//!    time (since the start of the recording) it ran at. Recorded by
+//!    [`CliRecorder`] and played back by [`CliPlayback`], with the original
+//!    timing. Useful for reproducible bug reports and demo sequences.
+//!  - [`CliTickRecording`]: commands run from the [`CliFixedQueue`], with the
+//!    tick they ran at. Playing it back queues them again at the same ticks,
+//!    allowing console-driven reproduction cases in lockstep or rollback
+//!    games.
+//!
+//! The text formats have one entry per line: the frame number and the time
+//! in seconds (or the tick number), followed by the command string. Empty
+//! lines and lines starting with `#` are ignored.
+//!
+//! ```text
+//! # spawn a wave and pause at tick 600
+//! 120 spawn 100 100
+//! 120 spawn 200 100
+//! 600 pause
+//! ```
+//!
+//! The built-in `record <start|stop> [file]` and `replay <file|stop> [--frames]`
+//! commands record and play back [`CliRecording`]s from the console. They
+//! read and write files, so they are [`Dev`](CliPermission::Dev) commands.
+//! A recording cannot be played back while another one is playing, so a
+//! recording that contains `replay` cannot restart itself forever.
+
+use std::fmt;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::Instant;
+
+use crate::fixed::CliFixedQueue;
+use crate::local::CliCurrentCommand;
+use crate::output::{cli_error, cli_println};
+use crate::permissions::CliPermission;
+use crate::status::cli_fail;
+use crate::{CliCommandsRegisterExt, CliCommandsRunExt};
+
+/// A list of (tick, command string) pairs
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CliTickRecording {
+    pub entries: Vec<(u64, String)>,
+}
+
+/// Error when parsing a [`CliTickRecording`] from text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CliTickRecordingParseError {
+    /// The line number (starting from 1) that could not be parsed
+    pub line: usize,
+}
+
+impl fmt::Display for CliTickRecordingParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid tick recording entry on line {}", self.line)
+    }
+}
+
+impl std::error::Error for CliTickRecordingParseError {}
+
+impl CliTickRecording {
+    /// Parse a recording from its text format
+    pub fn from_text(text: &str) -> Result<Self, CliTickRecordingParseError> {
+        let mut entries = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = CliTickRecordingParseError { line: i + 1 };
+            let (tick, cli) = line.split_once(char::is_whitespace).ok_or(err)?;
+            let tick = tick.parse::<u64>().map_err(|_| err)?;
+            let cli = cli.trim();
+            if cli.is_empty() {
+                return Err(err);
+            }
+            entries.push((tick, cli.to_owned()));
+        }
+        Ok(CliTickRecording { entries })
+    }
+    /// Convert the recording into its text format
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (tick, cli) in &self.entries {
+            text.push_str(&format!("{} {}\n", tick, cli));
+        }
+        text
+    }
+}
+
+/// Records commands run from the [`CliFixedQueue`], if active
+///
+/// Requires [`CliPlugin`](crate::CliPlugin).
+#[derive(Resource, Default)]
+pub struct CliTickRecorder {
+    recording: Option<CliTickRecording>,
+}
+
+impl CliTickRecorder {
+    /// Start a new recording, discarding any recording in progress
+    pub fn start(&mut self) {
+        self.recording = Some(CliTickRecording::default());
+    }
+    /// Stop recording and return what was recorded
+    pub fn stop(&mut self) -> Option<CliTickRecording> {
+        self.recording.take()
+    }
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+    pub(crate) fn record(&mut self, tick: u64, cli: &str) {
+        if let Some(recording) = &mut self.recording {
+            recording.entries.push((tick, cli.to_owned()));
+        }
+    }
+}
+
+/// Queue all the entries of a recording into the [`CliFixedQueue`]
+///
+/// They will run at the same ticks they were recorded at.
+pub fn play_tick_recording(world: &mut World, recording: &CliTickRecording) {
+    let mut queue = world.get_resource_or_insert_with(CliFixedQueue::default);
+    for (tick, cli) in &recording.entries {
+        queue.push(*tick, cli.clone());
+    }
+}
+
+/// Plugin to play back a [`CliTickRecording`] on startup
+///
+/// Requires [`CliPlugin`](crate::CliPlugin).
+pub struct CliTickPlaybackPlugin(pub CliTickRecording);
+
+impl Plugin for CliTickPlaybackPlugin {
+    fn build(&self, app: &mut App) {
+        let recording = self.0.clone();
+        app.add_systems(Startup, move |world: &mut World| {
+            play_tick_recording(world, &recording);
+        });
+    }
+}
+
+/// An entry of a [`CliRecording`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliRecordingEntry {
+    /// The number of frames since the start of the recording
+    pub frame: u64,
+    /// The time since the start of the recording
+    pub time: Duration,
+    /// The command string that was run
+    pub cli: String,
+}
+
+/// A list of command strings, with the frame and time they ran at
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CliRecording {
+    pub entries: Vec<CliRecordingEntry>,
+}
+
+/// Error when parsing a [`CliRecording`] from text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CliRecordingParseError {
+    /// The line number (starting from 1) that could not be parsed
+    pub line: usize,
+}
+
+impl fmt::Display for CliRecordingParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid recording entry on line {}", self.line)
+    }
+}
+
+impl std::error::Error for CliRecordingParseError {}
+
+impl CliRecording {
+    /// Parse a recording from its text format
+    pub fn from_text(text: &str) -> Result<Self, CliRecordingParseError> {
+        let mut entries = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = CliRecordingParseError { line: i + 1 };
+            let (frame, rest) = line.split_once(char::is_whitespace).ok_or(err)?;
+            let (time, cli) = rest
+                .trim_start()
+                .split_once(char::is_whitespace)
+                .ok_or(err)?;
+            let frame = frame.parse::<u64>().map_err(|_| err)?;
+            let time = time
+                .parse::<f64>()
+                .ok()
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                .ok_or(err)?;
+            let cli = cli.trim();
+            if cli.is_empty() {
+                return Err(err);
+            }
+            entries.push(CliRecordingEntry {
+                frame,
+                time,
+                cli: cli.to_owned(),
+            });
+        }
+        Ok(CliRecording { entries })
+    }
+    /// Convert the recording into its text format
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for entry in &self.entries {
+            text.push_str(&format!(
+                "{} {:.3} {}\n",
+                entry.frame,
+                entry.time.as_secs_f64(),
+                entry.cli
+            ));
+        }
+        text
+    }
+}
+
+/// Records every command string that is run, if active
+///
+/// Only command strings run from outside of other commands are recorded
+/// (not, for example, the commands run by a template or `retry`), as those
+/// run again when the recording is played back. The `record` and `replay`
+/// commands themselves are not recorded.
+///
+/// Requires [`CliPlugin`](crate::CliPlugin).
+#[derive(Resource, Default)]
+pub struct CliRecorder {
+    recording: Option<(CliRecording, Instant)>,
+    frame: u64,
+    /// Set while running a recorded command string, to skip nested ones
+    busy: bool,
+}
+
+impl CliRecorder {
+    /// Start a new recording, discarding any recording in progress
+    pub fn start(&mut self) {
+        self.recording = Some((CliRecording::default(), Instant::now()));
+        self.frame = 0;
+    }
+    /// Stop recording and return what was recorded
+    pub fn stop(&mut self) -> Option<CliRecording> {
+        self.recording.take().map(|(recording, _)| recording)
+    }
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+}
+
+/// Record a command string that is about to run, if appropriate
+///
+/// Returns whether it was recorded. If so, [`end_record`] must be called
+/// after it has run.
+pub(crate) fn begin_record(world: &mut World, cli: &str) -> bool {
+    let nested = world
+        .get_resource::<CliCurrentCommand>()
+        .is_some_and(|current| current.depth() > 0);
+    let name = cli.split_whitespace().next().unwrap_or_default();
+    if nested || name == "record" || name == "replay" {
+        return false;
+    }
+    let Some(recorder) = world.get_resource_mut::<CliRecorder>() else {
+        return false;
+    };
+    let recorder = recorder.into_inner();
+    let Some((recording, start)) = recorder.recording.as_mut().filter(|_| !recorder.busy) else {
+        return false;
+    };
+    recording.entries.push(CliRecordingEntry {
+        frame: recorder.frame,
+        time: start.elapsed(),
+        cli: cli.trim().to_owned(),
+    });
+    recorder.busy = true;
+    true
+}
+
+pub(crate) fn end_record(world: &mut World) {
+    if let Some(mut recorder) = world.get_resource_mut::<CliRecorder>() {
+        recorder.busy = false;
+    }
+}
+
+/// How a [`CliPlayback`] decides when to run each entry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CliPlaybackTiming {
+    /// At the same time since the start, as recorded
+    #[default]
+    Time,
+    /// At the same number of frames since the start, as recorded
+    Frames,
+}
+
+/// Plays back a [`CliRecording`], running its entries with the original timing
+///
+/// Requires [`CliPlugin`](crate::CliPlugin).
+#[derive(Resource, Default)]
+pub struct CliPlayback {
+    entries: std::collections::VecDeque<CliRecordingEntry>,
+    timing: CliPlaybackTiming,
+    start: Option<Instant>,
+    frame: u64,
+}
+
+impl CliPlayback {
+    /// Start playing back a recording, replacing any playback in progress
+    pub fn play(&mut self, recording: CliRecording, timing: CliPlaybackTiming) {
+        self.entries = recording.entries.into();
+        self.timing = timing;
+        self.start = Some(Instant::now());
+        self.frame = 0;
+    }
+    /// Stop playing back, skipping the remaining entries
+    pub fn stop(&mut self) {
+        self.entries.clear();
+        self.start = None;
+    }
+    pub fn is_playing(&self) -> bool {
+        self.start.is_some()
+    }
+    /// The number of entries that have not run yet
+    pub fn remaining(&self) -> usize {
+        self.entries.len()
+    }
+    fn pop_due(&mut self) -> Option<String> {
+        let start = self.start?;
+        let entry = self.entries.front()?;
+        let due = match self.timing {
+            CliPlaybackTiming::Time => entry.time <= start.elapsed(),
+            CliPlaybackTiming::Frames => entry.frame <= self.frame,
+        };
+        if !due {
+            return None;
+        }
+        self.entries.pop_front().map(|entry| entry.cli)
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliTickRecorder>();
+    app.init_resource::<CliRecorder>();
+    app.init_resource::<CliPlayback>();
+    app.add_systems(First, count_frames);
+    app.add_systems(Update, run_playback);
+    app.register_clicommand_args("record", record)
+        .set_clicommand_description("record", "Record the commands that are run")
+        .set_clicommand_usage("record", "record <start|stop> [file]")
+        .set_clicommand_permission("record", CliPermission::Dev);
+    app.register_clicommand_args("replay", replay)
+        .set_clicommand_description("replay", "Play back recorded commands")
+        .set_clicommand_usage("replay", "replay <file|stop> [--frames]")
+        .set_clicommand_permission("replay", CliPermission::Dev);
+}
+
+fn count_frames(mut recorder: ResMut<CliRecorder>, mut playback: ResMut<CliPlayback>) {
+    if recorder.is_recording() {
+        recorder.frame += 1;
+    }
+    if playback.is_playing() {
+        playback.frame += 1;
+    }
+}
+
+fn run_playback(world: &mut World) {
+    loop {
+        let mut playback = world.resource_mut::<CliPlayback>();
+        let Some(cli) = playback.pop_due() else {
+            if playback.is_playing() && playback.entries.is_empty() {
+                playback.stop();
+            }
+            return;
+        };
+        world.run_cli(&cli);
+    }
+}
+
+/// `record <start|stop> [file]`: record the commands that are run
+fn record(In(args): In<Vec<String>>, world: &mut World) {
+    let mut recorder = world.resource_mut::<CliRecorder>();
+    match args
+        .iter()
+        .map(|s| s.as_str())
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["start"] => recorder.start(),
+        ["stop", file @ ..] if file.len() <= 1 => {
+            let Some(recording) = recorder.stop() else {
+                cli_error(world, "Not recording!");
+                cli_fail(world);
+                return;
+            };
+            let text = recording.to_text();
+            match file.first() {
+                None => cli_println(world, text.trim_end()),
+                Some(path) => {
+                    if let Err(e) = std::fs::write(path, text) {
+                        cli_error(
+                            world,
+                            format!("Cannot write recording to {:?}: {}", path, e),
+                        );
+                        cli_fail(world);
+                    }
+                }
+            }
+        }
+        _ => {
+            cli_error(world, "Usage: record <start|stop> [file]");
+            cli_fail(world);
+        }
+    }
+}
+
+/// `replay <file|stop> [--frames]`: play back recorded commands
+fn replay(In(args): In<Vec<String>>, world: &mut World) {
+    let (path, timing) = match args
+        .iter()
+        .map(|s| s.as_str())
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["stop"] => {
+            world.resource_mut::<CliPlayback>().stop();
+            return;
+        }
+        [path] => (path.to_string(), CliPlaybackTiming::Time),
+        [path, "--frames"] => (path.to_string(), CliPlaybackTiming::Frames),
+        _ => {
+            cli_error(world, "Usage: replay <file|stop> [--frames]");
+            cli_fail(world);
+            return;
+        }
+    };
+    if world.resource::<CliPlayback>().is_playing() {
+        // Also stops a recording containing `replay` from restarting itself
+        cli_error(
+            world,
+            "A recording is already playing, run `replay stop` first!",
+        );
+        cli_fail(world);
+        return;
+    }
+    let recording = std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| CliRecording::from_text(&text).map_err(|e| e.to_string()));
+    match recording {
+        Ok(recording) => world.resource_mut::<CliPlayback>().play(recording, timing),
+        Err(e) => {
+            cli_error(world, format!("Cannot read recording {:?}: {}", path, e));
+            cli_fail(world);
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Mapping typed command names to registered commands.
+//!
+//! By default, the name typed by the user must exactly match the name of a
+//! registered command. You can change that by implementing
+//! [`CliCommandResolver`] and setting it with [`CliCommands::set_resolver`].
+//!
+//! [`CliConfig`] has simpler settings, applied when the resolver finds
+//! nothing: [`ignore_case`](CliConfig::ignore_case) matches names ignoring
+//! case (`Spawn` runs `spawn`), and
+//! [`allow_abbreviations`](CliConfig::allow_abbreviations) accepts unique
+//! prefixes (`desp` runs `despawn`). It also restricts which characters
+//! command names may have, with [`name_chars`](CliConfig::name_chars).
+//! Names with whitespace or control characters, which could never be typed
+//! as a single word, are always rejected.
+
+use bevy::prelude::*;
+
+use crate::{CliCommands, CliConfig};
+
+/// The outcome of resolving a typed command name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliResolution {
+    /// The name of the registered command to run
+    Found(String),
+    /// No registered command matches
+    NotFound,
+    /// Multiple registered commands match, and it is unclear which one was meant
+    Ambiguous(Vec<String>),
+}
+
+/// Decides which registered command a typed command name refers to
+///
+/// Implement this for things like abbreviations, locale-specific
+/// names, or pattern-based routing, without forking `run_cli`.
+pub trait CliCommandResolver: Send + Sync + 'static {
+    fn resolve(&self, name: &str, clicommands: &CliCommands) -> CliResolution;
+}
+
+/// The default resolver: the name must exactly match a registered command
+pub struct CliExactResolver;
+
+impl CliCommandResolver for CliExactResolver {
+    fn resolve(&self, name: &str, clicommands: &CliCommands) -> CliResolution {
+        if clicommands.command_available(name) {
+            CliResolution::Found(name.to_owned())
+        } else {
+            CliResolution::NotFound
+        }
+    }
+}
+
+/// Resolver that also accepts unique prefixes of command names
+///
+/// For example, `desp` will run `despawn`, if no other command starts with `desp`.
+/// An exact match always takes priority. If multiple commands start with
+/// the prefix, the resolution is ambiguous.
+///
+/// You can either set this as the resolver, or enable
+/// [`CliConfig::allow_abbreviations`](crate::CliConfig::allow_abbreviations)
+/// to use it whenever the configured resolver finds nothing.
+pub struct CliPrefixResolver;
+
+impl CliCommandResolver for CliPrefixResolver {
+    fn resolve(&self, name: &str, clicommands: &CliCommands) -> CliResolution {
+        if clicommands.command_available(name) {
+            return CliResolution::Found(name.to_owned());
+        }
+        resolve_matching(clicommands, |candidate| candidate.starts_with(name))
+    }
+}
+
+/// Resolve to the only command whose name matches, if there is one
+fn resolve_matching(clicommands: &CliCommands, matches: impl Fn(&str) -> bool) -> CliResolution {
+    let mut candidates: Vec<String> = clicommands
+        .iter_names()
+        .filter(|candidate| matches(candidate))
+        .map(|candidate| candidate.to_owned())
+        .collect();
+    match candidates.len() {
+        0 => CliResolution::NotFound,
+        1 => CliResolution::Found(candidates.remove(0)),
+        _ => {
+            candidates.sort();
+            CliResolution::Ambiguous(candidates)
+        }
+    }
+}
+
+/// Resolve a typed command name, taking [`CliConfig`] into account
+pub(crate) fn resolve_command(world: &World, name: &str) -> CliResolution {
+    let Some(clicommands) = world.get_resource::<CliCommands>() else {
+        return CliResolution::NotFound;
+    };
+    let resolution = clicommands.resolve(name);
+    let Some(config) = world.get_resource::<CliConfig>() else {
+        return resolution;
+    };
+    if resolution != CliResolution::NotFound {
+        return resolution;
+    }
+    let lowercase = name.to_lowercase();
+    if config.ignore_case {
+        let resolution = resolve_matching(clicommands, |candidate| {
+            candidate.to_lowercase() == lowercase
+        });
+        if resolution != CliResolution::NotFound {
+            return resolution;
+        }
+    }
+    match (config.allow_abbreviations, config.ignore_case) {
+        (true, false) => CliPrefixResolver.resolve(name, clicommands),
+        (true, true) => resolve_matching(clicommands, |candidate| {
+            candidate.to_lowercase().starts_with(&lowercase)
+        }),
+        (false, _) => CliResolution::NotFound,
+    }
+}
+
+/// Check if a command may be registered with the given name
+pub(crate) fn check_name(world: &World, name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("the name is empty".to_owned());
+    }
+    if let Some(c) = name.chars().find(|c| c.is_whitespace() || c.is_control()) {
+        return Err(format!("the name contains {:?}", c));
+    }
+    let name_chars = world
+        .get_resource::<CliConfig>()
+        .and_then(|config| config.name_chars);
+    if let Some(c) = name_chars.and_then(|allowed| name.chars().find(|&c| !allowed(c))) {
+        return Err(format!("the name contains {:?}, which is not allowed", c));
+    }
+    Ok(())
+}
+
+/// How many suggestions [`CliCommands::suggest`] returns, at most
+const MAX_SUGGESTIONS: usize = 5;
+
+impl CliCommands {
+    /// Find the commands and aliases a mistyped name may have meant
+    ///
+    /// Names that start with `name`, or are only a few edits away from it,
+    /// are returned, closest first. Useful for "did you mean?" hints, and for
+    /// showing suggestions while typing.
+    pub fn suggest(&self, name: &str) -> Vec<&str> {
+        if name.is_empty() {
+            return Vec::new();
+        }
+        let max_distance = (name.chars().count() / 3).max(1);
+        let names = self
+            .iter_names()
+            .filter(|candidate| self.is_enabled(candidate))
+            .chain(self.iter_aliases().map(|(alias, _)| alias));
+        let mut suggestions: Vec<(usize, &str)> = names
+            .filter(|&candidate| candidate != name)
+            .filter_map(|candidate| {
+                if candidate.starts_with(name) {
+                    return Some((0, candidate));
+                }
+                let distance = edit_distance(name, candidate);
+                (distance <= max_distance).then_some((distance, candidate))
+            })
+            .collect();
+        suggestions.sort_unstable();
+        suggestions.dedup_by_key(|(_, candidate)| *candidate);
+        suggestions.truncate(MAX_SUGGESTIONS);
+        suggestions
+            .into_iter()
+            .map(|(_, candidate)| candidate)
+            .collect()
+    }
+}
+
+/// The number of single-character insertions, deletions and substitutions
+/// needed to turn `a` into `b` (Levenshtein distance)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// A "did you mean" hint for a command name that was not found, if any
+pub(crate) fn did_you_mean(world: &World, name: &str) -> Option<String> {
+    let suggestions = world.get_resource::<CliCommands>()?.suggest(name);
+    if suggestions.is_empty() {
+        return None;
+    }
+    let quoted: Vec<String> = suggestions.iter().map(|s| format!("{:?}", s)).collect();
+    Some(format!("Did you mean {}?", quoted.join(", ")))
+}
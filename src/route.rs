@@ -0,0 +1,248 @@
+//! Sending "console commands" to other `World`s.
+//!
+//! [`CliCommands`] is per-`World`, so a console in the main `World` cannot
+//! run commands registered in a sub-app (like Bevy's render sub-app) or in a
+//! separate simulation `World`. Routes connect them: each route has a tag,
+//! and a command string starting with `@<tag>` is sent to that `World`:
+//!
+//! ```text
+//! > @render stats
+//! [render] 1520 draw calls
+//! ```
+//!
+//! Commands can also be routed by name, so they can be typed as if they
+//! were registered locally (see [`CliRouter::route_command`]).
+//!
+//! For sub-apps, use [`CliRouteAppExt::add_cli_route`]. For other `World`s,
+//! create the route with [`CliRouter::add_route`], insert the returned
+//! [`CliRouteEndpoint`] into the other `World`, and call
+//! [`CliRouteEndpoint::run_pending`] from it regularly.
+//!
+//! Routed commands run on the next update of the other `World`. Their output
+//! is added to the [`CliOutputLog`] of the main `World`, prefixed with the
+//! tag of the route. Their results are not reported back, so routed commands
+//! are always successful in chains.
+
+use std::sync::{Arc, Mutex};
+
+use bevy::app::AppLabel;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::error::CliError;
+use crate::output::{capture_output, CliOutputLine, CliOutputLog, CliOutputSpan};
+use crate::{quote_cli_arg, run_cli_chain};
+
+#[derive(Default)]
+struct CliRouteShared {
+    inbox: Mutex<Vec<String>>,
+    outbox: Mutex<Vec<CliOutputLine>>,
+}
+
+/// The routes to other `World`s
+///
+/// Requires [`CliPlugin`](crate::CliPlugin).
+#[derive(Resource, Default)]
+pub struct CliRouter {
+    routes: HashMap<String, Arc<CliRouteShared>>,
+    /// Names of commands that are always sent to a route
+    commands: HashMap<String, String>,
+}
+
+impl CliRouter {
+    /// Create a route, returning the endpoint to insert into the other `World`
+    ///
+    /// If a route with the same tag already exists, it is replaced.
+    pub fn add_route(&mut self, tag: &str) -> CliRouteEndpoint {
+        let shared = Arc::new(CliRouteShared::default());
+        self.routes.insert(tag.to_owned(), shared.clone());
+        CliRouteEndpoint {
+            tag: tag.to_owned(),
+            shared,
+        }
+    }
+    /// Remove a route, and the commands routed to it
+    pub fn remove_route(&mut self, tag: &str) -> bool {
+        self.commands.retain(|_, route| route != tag);
+        self.routes.remove(tag).is_some()
+    }
+    pub fn has_route(&self, tag: &str) -> bool {
+        self.routes.contains_key(tag)
+    }
+    /// Iterate over the tags of the routes
+    pub fn iter_routes(&self) -> impl Iterator<Item = &str> {
+        self.routes.keys().map(|tag| tag.as_str())
+    }
+    /// Always send a command to a route, so it can be run without `@<tag>`
+    pub fn route_command(&mut self, name: &str, tag: &str) {
+        self.commands.insert(name.to_owned(), tag.to_owned());
+    }
+    /// Stop sending a command to a route
+    pub fn unroute_command(&mut self, name: &str) -> bool {
+        self.commands.remove(name).is_some()
+    }
+    /// The route a command is sent to, if any
+    pub fn command_route(&self, name: &str) -> Option<&str> {
+        self.commands.get(name).map(|tag| tag.as_str())
+    }
+    /// Send a command string to a route, returning whether the route exists
+    pub fn send(&self, tag: &str, cli: &str) -> bool {
+        let Some(shared) = self.routes.get(tag) else {
+            return false;
+        };
+        if let Ok(mut inbox) = shared.inbox.lock() {
+            inbox.push(cli.to_owned());
+        }
+        true
+    }
+}
+
+/// The end of a route in the `World` that runs the commands
+///
+/// Obtained from [`CliRouter::add_route`].
+#[derive(Resource)]
+pub struct CliRouteEndpoint {
+    tag: String,
+    shared: Arc<CliRouteShared>,
+}
+
+impl CliRouteEndpoint {
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+    /// Run the commands sent to this `World`, sending their output back
+    pub fn run_pending(world: &mut World) {
+        let Some(endpoint) = world.get_resource::<CliRouteEndpoint>() else {
+            return;
+        };
+        let tag = endpoint.tag.clone();
+        let shared = endpoint.shared.clone();
+        let pending = match shared.inbox.lock() {
+            Ok(mut inbox) => std::mem::take(&mut *inbox),
+            Err(_) => return,
+        };
+        for cli in pending {
+            let (lines, _) = capture_output(world, |world| run_cli_chain(world, &cli, true));
+            let Ok(mut outbox) = shared.outbox.lock() else {
+                return;
+            };
+            outbox.extend(lines.into_iter().map(|line| tag_line(&tag, line)));
+        }
+    }
+}
+
+/// Prefix a line of output with the tag of the route it came from
+fn tag_line(tag: &str, line: CliOutputLine) -> CliOutputLine {
+    let prefix = format!("[{}] ", tag);
+    let offset = prefix.len();
+    CliOutputLine {
+        command: Some(match line.command {
+            Some(command) => format!("@{} {}", tag, command),
+            None => format!("@{}", tag),
+        }),
+        source: line.source,
+        severity: line.severity,
+        text: prefix + &line.text,
+        attachment: line.attachment,
+        spans: line
+            .spans
+            .into_iter()
+            .map(|span| CliOutputSpan {
+                range: span.range.start + offset..span.range.end + offset,
+                style: span.style,
+                link: span.link,
+            })
+            .collect(),
+    }
+}
+
+/// Provides methods for routing "console commands" to sub-apps
+pub trait CliRouteAppExt {
+    /// Create a route to a sub-app, with the given tag
+    ///
+    /// The commands are run in the sub-app's update schedule. Requires
+    /// [`CliPlugin`](crate::CliPlugin) in the main app. The sub-app must
+    /// already be added.
+    fn add_cli_route(&mut self, tag: &str, sub_app: impl AppLabel) -> &mut Self;
+}
+
+impl CliRouteAppExt for App {
+    fn add_cli_route(&mut self, tag: &str, label: impl AppLabel) -> &mut Self {
+        let label = label.intern();
+        let Some(sub_app) = self.get_sub_app_mut(label) else {
+            error!("Cannot add CLI route {:?}: no such sub-app!", tag);
+            return self;
+        };
+        let Some(schedule) = sub_app.update_schedule else {
+            error!(
+                "Cannot add CLI route {:?}: the sub-app has no update schedule!",
+                tag
+            );
+            return self;
+        };
+        sub_app.add_systems(schedule, CliRouteEndpoint::run_pending);
+        let endpoint = self
+            .world_mut()
+            .get_resource_or_insert_with(CliRouter::default)
+            .add_route(tag);
+        if let Some(sub_app) = self.get_sub_app_mut(label) {
+            sub_app.insert_resource(endpoint);
+        }
+        self
+    }
+}
+
+/// Send a command to a route, if it starts with `@<tag>` or is routed by name
+///
+/// Returns `None` if the command is not routed.
+pub(crate) fn dispatch(
+    world: &mut World,
+    name: &str,
+    args: &[String],
+) -> Option<Result<(), CliError>> {
+    let (tag, words): (String, Vec<&str>) = match name.strip_prefix('@') {
+        Some(tag) => (tag.to_owned(), args.iter().map(String::as_str).collect()),
+        None => {
+            let router = world.get_resource::<CliRouter>()?;
+            let tag = router.command_route(name)?.to_owned();
+            let words = std::iter::once(name).chain(args.iter().map(String::as_str));
+            (tag, words.collect())
+        }
+    };
+    if words.is_empty() {
+        return Some(Err(CliError::NotFound(name.to_owned())));
+    }
+    let cli: Vec<_> = words.into_iter().map(quote_cli_arg).collect();
+    let sent = world
+        .get_resource::<CliRouter>()
+        .is_some_and(|router| router.send(&tag, &cli.join(" ")));
+    match sent {
+        true => Some(Ok(())),
+        false => Some(Err(CliError::NoRoute(tag))),
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliRouter>();
+    app.add_systems(First, collect_output);
+}
+
+/// Add the output of routed commands to the [`CliOutputLog`]
+fn collect_output(world: &mut World) {
+    let Some(router) = world.get_resource::<CliRouter>() else {
+        return;
+    };
+    let mut lines = Vec::new();
+    for shared in router.routes.values() {
+        if let Ok(mut outbox) = shared.outbox.lock() {
+            lines.append(&mut outbox);
+        }
+    }
+    if lines.is_empty() {
+        return;
+    }
+    let mut log = world.get_resource_or_insert_with(CliOutputLog::default);
+    for line in lines {
+        log.push(line);
+    }
+}
@@ -0,0 +1,215 @@
+//! Safe mode: recovering from a broken configuration.
+//!
+//! Add [`CliSafeModePlugin`] (after [`CliPlugin`](crate::CliPlugin)). The app
+//! starts in safe mode if it is launched with `--safe-cli`, or if the last
+//! run did not exit cleanly (because it panicked or was killed during
+//! startup, for example due to a broken autoexec script). This is detected
+//! using a marker file, which exists while the app is running.
+//!
+//! In safe mode:
+//!  - the command strings in [`CliAutoexec`] are not run
+//!  - only the commands registered by [`CliPlugin`](crate::CliPlugin) and the
+//!    `recovery` commands are enabled
+//!
+//! The `recovery` commands are always available:
+//!  - `recovery.reset_cvars`: reset all console variables to their defaults
+//!    (with the `cvars` feature)
+//!  - `recovery.clear_aliases`: remove all aliases
+//!  - `recovery.clear_binds`: remove all key bindings (with the `bindings` feature)
+//!  - `recovery.exit`: leave safe mode, enabling all commands again
+
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use crate::output::cli_error;
+use crate::startup::{CliAutoexec, CliStartupSet};
+use crate::status::cli_fail;
+#[cfg(feature = "cvars")]
+use crate::vars::{CliVarChanged, CliVars};
+use crate::{CliCommands, CliCommandsRegisterExt};
+
+/// Plugin that starts the app in safe mode when needed
+pub struct CliSafeModePlugin {
+    /// The marker file that exists while the app is running
+    pub marker_path: PathBuf,
+    /// The command-line flag that forces safe mode
+    pub flag: String,
+}
+
+impl Default for CliSafeModePlugin {
+    fn default() -> Self {
+        CliSafeModePlugin {
+            marker_path: PathBuf::from(".iyes_cli_running"),
+            flag: "--safe-cli".to_owned(),
+        }
+    }
+}
+
+/// Why the app is in safe mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CliSafeModeReason {
+    /// The safe mode flag was passed on the command line
+    Flag,
+    /// The last run did not exit cleanly
+    Crashed,
+}
+
+/// Whether the app is in safe mode
+#[derive(Resource, Debug)]
+pub struct CliSafeMode {
+    reason: Option<CliSafeModeReason>,
+    marker_path: PathBuf,
+    disabled: Vec<String>,
+}
+
+impl CliSafeMode {
+    pub fn is_active(&self) -> bool {
+        self.reason.is_some()
+    }
+    pub fn reason(&self) -> Option<CliSafeModeReason> {
+        self.reason
+    }
+}
+
+/// The names of the commands registered by [`CliPlugin`](crate::CliPlugin)
+#[derive(Resource, Debug, Default)]
+pub(crate) struct CliCoreCommands(pub HashSet<String>);
+
+impl Plugin for CliSafeModePlugin {
+    fn build(&self, app: &mut App) {
+        let reason = if std::env::args().any(|arg| arg == self.flag) {
+            Some(CliSafeModeReason::Flag)
+        } else if self.marker_path.exists() {
+            Some(CliSafeModeReason::Crashed)
+        } else {
+            None
+        };
+        if let Err(e) = std::fs::write(&self.marker_path, "") {
+            warn!(
+                "Cannot create CLI safe mode marker file {:?}: {}",
+                self.marker_path, e
+            );
+        }
+        app.insert_resource(CliSafeMode {
+            reason,
+            marker_path: self.marker_path.clone(),
+            disabled: vec![],
+        });
+        app.add_systems(
+            Startup,
+            enter_safe_mode
+                .after(CliStartupSet::Register)
+                .before(CliStartupSet::Autoexec),
+        );
+        app.add_systems(Last, remove_marker_on_exit);
+        app.register_cli_namespace("recovery", |ns| {
+            #[cfg(feature = "cvars")]
+            ns.register_clicommand_noargs("reset_cvars", reset_cvars)
+                .set_clicommand_description("reset_cvars", "Reset all console variables");
+            ns.register_clicommand_noargs("clear_aliases", clear_aliases)
+                .set_clicommand_description("clear_aliases", "Remove all aliases");
+            #[cfg(feature = "bindings")]
+            ns.register_clicommand_noargs("clear_binds", clear_binds)
+                .set_clicommand_description("clear_binds", "Remove all key bindings");
+            ns.register_clicommand_noargs("exit", exit_safe_mode)
+                .set_clicommand_description("exit", "Leave safe mode");
+        });
+    }
+}
+
+fn enter_safe_mode(world: &mut World) {
+    let Some(reason) = world.resource::<CliSafeMode>().reason else {
+        return;
+    };
+    match reason {
+        CliSafeModeReason::Flag => warn!("Starting in CLI safe mode, as requested."),
+        CliSafeModeReason::Crashed => {
+            warn!("Starting in CLI safe mode, as the last run did not exit cleanly.")
+        }
+    }
+    if let Some(mut autoexec) = world.get_resource_mut::<CliAutoexec>() {
+        let skipped = std::mem::take(&mut *autoexec).iter().count();
+        if skipped > 0 {
+            warn!("Skipping {} autoexec command strings.", skipped);
+        }
+    }
+    let core = world
+        .remove_resource::<CliCoreCommands>()
+        .unwrap_or_default();
+    let Some(mut clicommands) = world.get_resource_mut::<CliCommands>() else {
+        return;
+    };
+    let disabled: Vec<String> = clicommands
+        .iter_names()
+        .filter(|name| !core.0.contains(*name) && !name.starts_with("recovery."))
+        .filter(|name| clicommands.is_enabled(name))
+        .map(|name| name.to_owned())
+        .collect();
+    for name in &disabled {
+        clicommands.set_enabled(name, false);
+    }
+    world.resource_mut::<CliSafeMode>().disabled = disabled;
+}
+
+fn remove_marker_on_exit(mut evr_exit: EventReader<AppExit>, safe_mode: Res<CliSafeMode>) {
+    if evr_exit.read().next().is_none() {
+        return;
+    }
+    if let Err(e) = std::fs::remove_file(&safe_mode.marker_path) {
+        warn!(
+            "Cannot remove CLI safe mode marker file {:?}: {}",
+            safe_mode.marker_path, e
+        );
+    }
+}
+
+/// `recovery.reset_cvars`: reset all console variables to their defaults
+#[cfg(feature = "cvars")]
+fn reset_cvars(mut vars: ResMut<CliVars>, mut evw_changed: EventWriter<CliVarChanged>) {
+    let names: Vec<String> = vars.iter_changed().map(|s| s.to_owned()).collect();
+    for name in names {
+        if vars.reset(&name).is_ok() {
+            evw_changed.send(CliVarChanged { name });
+        }
+    }
+}
+
+/// `recovery.clear_aliases`: remove all aliases
+fn clear_aliases(mut clicommands: ResMut<CliCommands>) {
+    let aliases: Vec<String> = clicommands
+        .iter_aliases()
+        .map(|(alias, _)| alias.to_owned())
+        .collect();
+    for alias in aliases {
+        clicommands.remove_alias(&alias);
+    }
+}
+
+/// `recovery.clear_binds`: remove all key bindings
+#[cfg(feature = "bindings")]
+fn clear_binds(world: &mut World) {
+    match world.get_resource_mut::<crate::bindings::CliBindings>() {
+        Some(mut bindings) => bindings.clear(),
+        None => {
+            cli_error(world, "Key bindings are not enabled (CliBindingsPlugin)!");
+            cli_fail(world);
+        }
+    }
+}
+
+/// `recovery.exit`: leave safe mode, enabling all commands again
+fn exit_safe_mode(world: &mut World) {
+    let mut safe_mode = world.resource_mut::<CliSafeMode>();
+    if safe_mode.reason.take().is_none() {
+        cli_error(world, "Not in safe mode!");
+        cli_fail(world);
+        return;
+    }
+    let disabled = std::mem::take(&mut safe_mode.disabled);
+    let mut clicommands = world.resource_mut::<CliCommands>();
+    for name in disabled {
+        clicommands.set_enabled(&name, true);
+    }
+}
@@ -0,0 +1,449 @@
+//! Running "console commands" later, or repeatedly.
+//!
+//! Requires the `schedule` cargo feature (enabled by default).
+//!
+//! [`run_cli_after`](crate::CliCommandsRunExt::run_cli_after) runs a command
+//! string once, after a delay, and
+//! [`run_cli_every`](crate::CliCommandsRunExt::run_cli_every) runs it
+//! repeatedly, at an interval, until it is cancelled.
+//! [`run_cli_at_time`](crate::CliCommandsRunExt::run_cli_at_time) runs it
+//! once, at an absolute time. The same is available from the console, which
+//! is useful for stress and soak tests:
+//!
+//!  - `after <duration> <command...>`: run a command once, later
+//!  - `every <duration> <command...>`: run a command repeatedly
+//!  - `at <time> <command...>`: run a command once, at a time
+//!  - `schedule [list]`: list the scheduled commands, with their ids
+//!  - `schedule cancel <id|all>`: stop a scheduled command
+//!
+//! ```text
+//! after 5s spawn 0 0
+//! every 2s stats
+//! ```
+//!
+//! Times are measured since the start of the app, so a script can lay out
+//! a sequence that unfolds over minutes, like for a benchmark scenario:
+//!
+//! ```text
+//! at 10 spawn_wave 1
+//! at 60 spawn_wave 2
+//! at 120.5 spawn_boss
+//! at 300 quit
+//! ```
+//!
+//! A command scheduled at a time that has already passed runs in the next
+//! frame.
+//!
+//!
+//! Time is measured in virtual time, so scheduled commands are delayed by
+//! pausing and changing the timescale. Durations are in seconds, optionally
+//! with an `s` or `ms` suffix (like for `sleep`, see [`sleep`](crate::sleep)).
+//! A repeating command runs at most once per frame, and does not catch up on
+//! runs it missed.
+//!
+//! Scheduled commands run with the source of the command string that
+//! scheduled them, if any (see [`source`](crate::source)), so they are
+//! subject to the same restrictions.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::output::CliOutput;
+use crate::quota::with_deferred_cli_source;
+use crate::sleep::parse_duration;
+use crate::source::CliSource;
+use crate::table::{CliAlign, CliTable};
+use crate::{quote_cli_arg, CliCommandsRegisterExt, CliCommandsRunExt};
+
+struct CliScheduled {
+    id: u64,
+    cli: String,
+    /// When to run next, in elapsed virtual time
+    next: Duration,
+    interval: Option<Duration>,
+    runs: u64,
+    source: Option<CliSource>,
+}
+
+/// Information about a scheduled command string
+#[derive(Debug, Clone)]
+pub struct CliScheduledInfo<'a> {
+    pub id: u64,
+    pub cli: &'a str,
+    /// How long until it runs next
+    pub remaining: Duration,
+    /// When it runs next, in elapsed virtual time
+    pub at: Duration,
+    /// How often it runs, if it repeats
+    pub interval: Option<Duration>,
+    /// How many times it has run
+    pub runs: u64,
+}
+
+/// The command strings waiting to run later, or repeatedly
+///
+/// Requires [`CliPlugin`](crate::CliPlugin).
+#[derive(Resource, Default)]
+pub struct CliSchedule {
+    scheduled: Vec<CliScheduled>,
+    next_id: u64,
+    /// The elapsed virtual time, as of the last update
+    now: Duration,
+}
+
+impl CliSchedule {
+    /// Run a command string once, after a delay, returning its id
+    pub fn after(&mut self, delay: Duration, cli: impl Into<String>) -> u64 {
+        self.push(cli.into(), self.now + delay, None)
+    }
+    /// Run a command string once, at a time (in elapsed virtual time), returning its id
+    pub fn at(&mut self, time: Duration, cli: impl Into<String>) -> u64 {
+        self.push(cli.into(), time, None)
+    }
+    /// Run a command string repeatedly, at an interval, returning its id
+    ///
+    /// The first run is after one interval.
+    pub fn every(&mut self, interval: Duration, cli: impl Into<String>) -> u64 {
+        self.push(cli.into(), self.now + interval, Some(interval))
+    }
+    fn push(&mut self, cli: String, next: Duration, interval: Option<Duration>) -> u64 {
+        self.next_id += 1;
+        self.scheduled.push(CliScheduled {
+            id: self.next_id,
+            cli,
+            next,
+            interval,
+            runs: 0,
+            source: None,
+        });
+        self.next_id
+    }
+    /// Run a scheduled command string on behalf of a source (see
+    /// [`source`](crate::source)), returning whether it exists
+    pub fn set_source(&mut self, id: u64, source: impl Into<CliSource>) -> bool {
+        let scheduled = self
+            .scheduled
+            .iter_mut()
+            .find(|scheduled| scheduled.id == id);
+        match scheduled {
+            Some(scheduled) => {
+                scheduled.source = Some(source.into());
+                true
+            }
+            None => false,
+        }
+    }
+    /// Stop a scheduled command string, returning whether it existed
+    pub fn cancel(&mut self, id: u64) -> bool {
+        let len = self.scheduled.len();
+        self.scheduled.retain(|scheduled| scheduled.id != id);
+        self.scheduled.len() != len
+    }
+    /// Stop all scheduled command strings
+    pub fn clear(&mut self) {
+        self.scheduled.clear();
+    }
+    /// Iterate over the scheduled command strings, in the order they were scheduled
+    pub fn iter(&self) -> impl Iterator<Item = CliScheduledInfo<'_>> {
+        self.scheduled.iter().map(|scheduled| CliScheduledInfo {
+            id: scheduled.id,
+            cli: &scheduled.cli,
+            remaining: scheduled.next.saturating_sub(self.now),
+            at: scheduled.next,
+            interval: scheduled.interval,
+            runs: scheduled.runs,
+        })
+    }
+    pub fn len(&self) -> usize {
+        self.scheduled.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.scheduled.is_empty()
+    }
+    /// The elapsed virtual time, as of the last update
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+}
+
+/// Command to run a command string once, after a delay
+pub struct CliRunAfterCommand(pub String, pub Duration);
+
+impl bevy::ecs::world::Command for CliRunAfterCommand {
+    fn apply(self, world: &mut World) {
+        world.run_cli_after(&self.0, self.1);
+    }
+}
+
+/// Command to run a command string once, at a time (in elapsed virtual time)
+pub struct CliRunAtTimeCommand(pub String, pub Duration);
+
+impl bevy::ecs::world::Command for CliRunAtTimeCommand {
+    fn apply(self, world: &mut World) {
+        world.run_cli_at_time(&self.0, self.1);
+    }
+}
+
+/// Command to run a command string repeatedly, at an interval
+pub struct CliRunEveryCommand(pub String, pub Duration);
+
+impl bevy::ecs::world::Command for CliRunEveryCommand {
+    fn apply(self, world: &mut World) {
+        world.run_cli_every(&self.0, self.1);
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliSchedule>();
+    app.add_systems(PreUpdate, run_scheduled);
+    app.register_clicommand_args("after", after)
+        .set_clicommand_description("after", "Run a command once, after a delay")
+        .set_clicommand_usage("after", "after <duration> <command...>");
+    app.register_clicommand_args("every", every)
+        .set_clicommand_description("every", "Run a command repeatedly, at an interval")
+        .set_clicommand_usage("every", "every <duration> <command...>");
+    app.register_clicommand_args("at", at)
+        .set_clicommand_description("at", "Run a command once, at a time")
+        .set_clicommand_usage("at", "at <time> <command...>");
+    app.register_clicommand_args("schedule", schedule)
+        .set_clicommand_description("schedule", "List or cancel scheduled commands")
+        .set_clicommand_usage("schedule", "schedule [list | cancel <id|all>]");
+}
+
+fn run_scheduled(world: &mut World) {
+    let now = world
+        .get_resource::<Time<Virtual>>()
+        .map_or(Duration::ZERO, |time| time.elapsed());
+    let mut schedule = world.resource_mut::<CliSchedule>();
+    schedule.now = now;
+    let mut due = Vec::new();
+    schedule.scheduled.retain_mut(|scheduled| {
+        if scheduled.next > now {
+            return true;
+        }
+        scheduled.runs += 1;
+        due.push((scheduled.cli.clone(), scheduled.source.clone()));
+        match scheduled.interval {
+            Some(interval) => {
+                scheduled.next = (scheduled.next + interval).max(now);
+                true
+            }
+            None => false,
+        }
+    });
+    for (cli, source) in due {
+        with_deferred_cli_source(world, source, |world| {
+            world.run_cli(&cli);
+        });
+    }
+}
+
+/// Parse `<duration> <command...>`, for `after`, `every` and `at`
+fn parse_scheduled(args: &[String]) -> Option<(Duration, String)> {
+    let [duration, command @ ..] = args else {
+        return None;
+    };
+    if command.is_empty() {
+        return None;
+    }
+    let command: Vec<_> = command.iter().map(|arg| quote_cli_arg(arg)).collect();
+    Some((parse_duration(duration)?, command.join(" ")))
+}
+
+/// `after <duration> <command...>`: run a command once, after a delay
+fn after(In(args): In<Vec<String>>, mut schedule: ResMut<CliSchedule>, mut output: CliOutput) {
+    let Some((delay, cli)) = parse_scheduled(&args) else {
+        output.fail("Usage: after <duration> <command...>");
+        return;
+    };
+    let id = schedule.after(delay, cli);
+    if let Some(source) = output.source() {
+        schedule.set_source(id, source.clone());
+    }
+    output.println(format!("Scheduled as {}", id));
+}
+
+/// `every <duration> <command...>`: run a command repeatedly, at an interval
+fn every(In(args): In<Vec<String>>, mut schedule: ResMut<CliSchedule>, mut output: CliOutput) {
+    let Some((interval, cli)) = parse_scheduled(&args) else {
+        output.fail("Usage: every <duration> <command...>");
+        return;
+    };
+    let id = schedule.every(interval, cli);
+    if let Some(source) = output.source() {
+        schedule.set_source(id, source.clone());
+    }
+    output.println(format!("Scheduled as {}", id));
+}
+
+/// `at <time> <command...>`: run a command once, at a time
+fn at(
+    In(args): In<Vec<String>>,
+    mut schedule: ResMut<CliSchedule>,
+    time: Option<Res<Time<Virtual>>>,
+    mut output: CliOutput,
+) {
+    let Some((at, cli)) = parse_scheduled(&args) else {
+        output.fail("Usage: at <time> <command...>");
+        return;
+    };
+    let now = time.map_or(Duration::ZERO, |time| time.elapsed());
+    if at < now {
+        output.warn(format!(
+            "{:.1}s has already passed ({:.1}s now), running it in the next frame",
+            at.as_secs_f32(),
+            now.as_secs_f32()
+        ));
+    }
+    let id = schedule.at(at, cli);
+    if let Some(source) = output.source() {
+        schedule.set_source(id, source.clone());
+    }
+    output.println(format!("Scheduled as {}", id));
+}
+
+/// `schedule [list | cancel <id|all>]`: list or cancel scheduled commands
+fn schedule(In(args): In<Vec<String>>, mut schedule: ResMut<CliSchedule>, mut output: CliOutput) {
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+    match args.as_slice() {
+        [] | ["list"] => {
+            if schedule.is_empty() {
+                output.println("No commands scheduled");
+                return;
+            }
+            let mut table = CliTable::new()
+                .with_column("ID", CliAlign::Right)
+                .with_column("COMMAND", CliAlign::Left)
+                .with_column("NEXT", CliAlign::Right)
+                .with_column("EVERY", CliAlign::Right)
+                .with_column("RUNS", CliAlign::Right);
+            for scheduled in schedule.iter() {
+                table.add_row([
+                    scheduled.id.to_string(),
+                    scheduled.cli.to_owned(),
+                    format!("{:.1}s", scheduled.remaining.as_secs_f32()),
+                    scheduled
+                        .interval
+                        .map_or("-".to_owned(), |i| format!("{:.1}s", i.as_secs_f32())),
+                    scheduled.runs.to_string(),
+                ]);
+            }
+            output.println(table.to_string());
+        }
+        ["cancel", "all"] => {
+            let count = schedule.len();
+            schedule.clear();
+            output.println(format!("Cancelled {} scheduled commands", count));
+        }
+        ["cancel", id] => {
+            let Ok(id) = id.parse::<u64>() else {
+                output.fail("Usage: schedule cancel <id|all>");
+                return;
+            };
+            if !schedule.cancel(id) {
+                output.fail(format!("No scheduled command with id {}!", id));
+            }
+        }
+        _ => output.fail("Usage: schedule [list | cancel <id|all>]"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CliPlugin;
+
+    #[derive(Resource, Default)]
+    struct Marks(Vec<String>);
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.init_resource::<Marks>();
+        app.register_clicommand_args(
+            "mark",
+            |In(args): In<Vec<String>>, mut marks: ResMut<Marks>| marks.0.extend(args),
+        );
+        app
+    }
+
+    fn advance(world: &mut World, millis: u64) {
+        world
+            .resource_mut::<Time<Virtual>>()
+            .advance_by(Duration::from_millis(millis));
+        run_scheduled(world);
+    }
+
+    fn marks(world: &World) -> &[String] {
+        &world.resource::<Marks>().0
+    }
+
+    #[test]
+    fn after_runs_once() {
+        let mut app = app();
+        let world = app.world_mut();
+        world.run_cli("after 1s mark \"a b\"");
+        advance(world, 500);
+        assert!(marks(world).is_empty());
+        advance(world, 500);
+        advance(world, 1000);
+        assert_eq!(marks(world), ["a b"]);
+        assert!(world.resource::<CliSchedule>().is_empty());
+    }
+
+    #[test]
+    fn every_repeats_without_catching_up() {
+        let mut app = app();
+        let world = app.world_mut();
+        let id = world
+            .resource_mut::<CliSchedule>()
+            .every(Duration::from_secs(1), "mark x");
+        advance(world, 1000);
+        advance(world, 1000);
+        assert_eq!(marks(world).len(), 2);
+        // A long frame runs it only once
+        advance(world, 5000);
+        assert_eq!(marks(world).len(), 3);
+        let info = world.resource::<CliSchedule>().iter().next().unwrap();
+        assert_eq!((info.id, info.runs), (id, 3));
+
+        world.run_cli(&format!("schedule cancel {}", id));
+        advance(world, 1000);
+        assert_eq!(marks(world).len(), 3);
+        assert!(world.resource::<CliSchedule>().is_empty());
+    }
+
+    #[test]
+    fn at_runs_at_a_time() {
+        let mut app = app();
+        let world = app.world_mut();
+        world.run_cli("at 2.5 mark late; at 1 mark early");
+        advance(world, 1000);
+        assert_eq!(marks(world), ["early"]);
+        let info = world.resource::<CliSchedule>().iter().next().unwrap();
+        assert_eq!(info.at, Duration::from_millis(2500));
+        assert_eq!(info.remaining, Duration::from_millis(1500));
+        advance(world, 1000);
+        assert_eq!(marks(world).len(), 1);
+        advance(world, 500);
+        assert_eq!(marks(world), ["early", "late"]);
+
+        // A time that has passed runs in the next frame
+        world.run_cli_at_time("mark past", Duration::from_secs(1));
+        advance(world, 0);
+        assert_eq!(marks(world).len(), 3);
+        assert!(world.resource::<CliSchedule>().is_empty());
+    }
+
+    #[test]
+    fn scheduled_args() {
+        let args = |args: &[&str]| args.iter().map(|&arg| arg.to_owned()).collect::<Vec<_>>();
+        assert_eq!(
+            parse_scheduled(&args(&["2s", "say", "hi there"])),
+            Some((Duration::from_secs(2), "say \"hi there\"".to_owned()))
+        );
+        assert_eq!(parse_scheduled(&args(&["2s"])), None);
+        assert_eq!(parse_scheduled(&args(&["soon", "say"])), None);
+    }
+}
@@ -0,0 +1,749 @@
+//! Script files: sequences of "console commands" loaded as assets.
+//!
+//! Requires the `script` cargo feature. Add [`CliScriptPlugin`] (together
+//! with [`CliPlugin`](crate::CliPlugin)) to be able to load `.cli` files
+//! using the `AssetServer`, and run them with
+//! [`run_cli_script`](CliScriptRunExt::run_cli_script) or the built-in
+//! `exec <path>` command.
+//!
+//! A script has one command string per line. Empty lines and lines starting
+//! with `#` are ignored.
+//!
+//! ```text
+//! # autoexec.cli
+//! gravity 9.8
+//! spawn 100 100
+//! ```
+//!
+//! Scripts are loaded asynchronously. If a script is not loaded yet when you
+//! ask to run it, it is run as soon as it finishes loading.
+//!
+//! Scripts can define procedures, which can then be called like commands:
+//!
+//! ```text
+//! proc greet { echo hello $1 }
+//! export proc spawn_row {
+//!     spawn $1 100
+//!     spawn $1 200
+//! }
+//! ```
+//!
+//! The body is a command template, as for
+//! [`register_clicommand_template`](crate::CliCommandsRegisterExt::register_clicommand_template),
+//! with multiple lines joined by `;`. Procedures are defined when the script
+//! starts running. If the script is run from a [`CliSession`](crate::session::CliSession),
+//! procedures are only available in that session, unless they are marked
+//! with `export`. Otherwise, they are registered as commands.
+//!
+//! Scripts can `exec` other scripts. Relative paths are then resolved
+//! against the directory of the including script. Including a script that
+//! is already running (a cycle), or nesting deeper than
+//! [`CliScriptPlugin::max_depth`], is an error that shows the script stack.
+//! Commands that run after a `sleep` are no longer part of the script stack.
+//!
+//! [`validate_cli_script`] checks a script against the commands registered
+//! now, without running it, and returns a [`CliScriptDiagnostic`] with the
+//! line and column of each problem: unknown commands, args that do not match
+//! the arity or signature of a command, and variables that are not set. By
+//! default, scripts are checked when they finish loading, logging the
+//! problems found, and again before they run: a script with errors is not
+//! run at all, and the errors are reported instead.
+//!
+//! ```text
+//! scripts/level1.cli:3:1: CliCommand "spwn" not found! Did you mean "spawn"?
+//! scripts/level1.cli:4:9: invalid args for CliCommand "spawn": missing arg <y>!
+//! ```
+//!
+//! Commands defined by the script itself (its procedures, and aliases
+//! defined with `alias` before they are used) and variables set with `set`
+//! are taken into account, but commands registered by other means while the
+//! script runs (like the exported procedures of scripts it `exec`s) are not.
+//! Disable [`CliScriptPlugin::validate`] for scripts that rely on those.
+
+use std::fmt;
+use std::string::FromUtf8Error;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AssetPath, LoadContext, LoadState};
+use bevy::prelude::*;
+
+use bevy::ecs::event::EventCursor;
+
+use crate::dry_run::run_cli_dry;
+use crate::env;
+use crate::error::CliError;
+use crate::output::{cli_error, CliSeverity};
+use crate::quota::with_cli_source;
+use crate::resolve;
+use crate::session::CliSession;
+use crate::sleep::run_cli_lines;
+use crate::source::CliSource;
+use crate::status::{cli_fail, CliStatus};
+use crate::{describe_error, parse_cli, parse_cli_chain, CliChainOp, CliCommandsRegisterExt};
+
+/// Plugin that adds the [`CliScript`] asset and the `exec` command
+pub struct CliScriptPlugin {
+    /// How many scripts can be nested inside each other using `exec`
+    pub max_depth: usize,
+    /// Whether scripts are checked with [`validate_cli_script`] when they
+    /// are loaded (logging the problems found), and before they run (not
+    /// running scripts with errors)
+    pub validate: bool,
+}
+
+impl Default for CliScriptPlugin {
+    fn default() -> Self {
+        CliScriptPlugin {
+            max_depth: 16,
+            validate: true,
+        }
+    }
+}
+
+impl Plugin for CliScriptPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CliScriptStack {
+            stack: Vec::new(),
+            max_depth: self.max_depth,
+            validate: self.validate,
+        });
+        app.init_asset::<CliScript>();
+        app.register_asset_loader(CliScriptLoader);
+        app.init_resource::<CliPendingScripts>();
+        app.add_systems(
+            PreUpdate,
+            (validate_loaded_scripts, run_pending_scripts).chain(),
+        );
+        app.register_clicommand_args("exec", exec)
+            .set_clicommand_description("exec", "Run the commands in a script file")
+            .set_clicommand_usage("exec", "exec <path>");
+    }
+}
+
+/// A sequence of command strings, loaded from a `.cli` file
+#[derive(Asset, TypePath, Debug, Default, Clone, PartialEq, Eq)]
+pub struct CliScript {
+    pub lines: Vec<String>,
+    /// The line and column (from 1) in the text where each of `lines` starts
+    pub positions: Vec<(usize, usize)>,
+    pub procs: Vec<CliProc>,
+}
+
+/// A procedure defined in a [`CliScript`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliProc {
+    pub name: String,
+    /// The command template to run
+    pub body: String,
+    /// Whether the procedure is available outside the session running the script
+    pub exported: bool,
+}
+
+/// Error when parsing a [`CliScript`] from text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CliScriptParseError {
+    /// The line number (starting from 1) of the invalid `proc` definition
+    pub line: usize,
+}
+
+impl fmt::Display for CliScriptParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid proc definition on line {}", self.line)
+    }
+}
+
+impl std::error::Error for CliScriptParseError {}
+
+impl CliScript {
+    /// Parse a script from text, skipping empty lines and comments
+    pub fn from_text(text: &str) -> Result<Self, CliScriptParseError> {
+        let mut script = CliScript::default();
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                let indent = line.chars().take_while(|c| c.is_whitespace()).count();
+                ((i + 1, indent + 1), line.trim())
+            })
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+        while let Some(((number, column), line)) = lines.next() {
+            let (exported, definition) = match line.strip_prefix("export ") {
+                Some(rest) => (true, rest.trim_start()),
+                None => (false, line),
+            };
+            let Some(definition) = definition.strip_prefix("proc ") else {
+                // Not a procedure, but the `export <name>` command
+                script.lines.push(line.to_owned());
+                script.positions.push((number, column));
+                continue;
+            };
+            let err = CliScriptParseError { line: number };
+            let (name, rest) = definition
+                .trim_start()
+                .split_once(char::is_whitespace)
+                .ok_or(err)?;
+            let rest = rest.trim_start().strip_prefix('{').ok_or(err)?.trim();
+            let body = match rest.strip_suffix('}') {
+                // Single-line `proc name { body }`
+                Some(body) => body.trim().to_owned(),
+                None => {
+                    let mut body: Vec<&str> = Vec::new();
+                    if !rest.is_empty() {
+                        body.push(rest);
+                    }
+                    loop {
+                        match lines.next() {
+                            Some((_, "}")) => break,
+                            Some((_, line)) => body.push(line),
+                            None => return Err(err),
+                        }
+                    }
+                    body.join("; ")
+                }
+            };
+            if body.is_empty() {
+                return Err(err);
+            }
+            script.procs.push(CliProc {
+                name: name.to_owned(),
+                body,
+                exported,
+            });
+        }
+        Ok(script)
+    }
+}
+
+/// A problem in a [`CliScript`], found by [`validate_cli_script`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliScriptDiagnostic {
+    /// The line number, from 1
+    pub line: usize,
+    /// The column (in chars), from 1
+    pub column: usize,
+    /// [`Error`](CliSeverity::Error) if the line cannot run, or
+    /// [`Warn`](CliSeverity::Warn) if it may not run as intended
+    pub severity: CliSeverity,
+    pub message: String,
+}
+
+impl fmt::Display for CliScriptDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+/// Check the command strings of a script against the commands registered now
+///
+/// Nothing is run. Each command of each line is checked like by
+/// [`run_cli_dry`], and references to variables that are not set (in the
+/// [`CliEnv`](crate::env::CliEnv) or the session, or by a `set` earlier in
+/// the script) are reported as
+/// warnings. The args of commands that receive piped output, or that
+/// reference variables set by the script, are not checked, as they are only
+/// known when the script runs. See [`script`](crate::script).
+pub fn validate_cli_script(world: &mut World, script: &CliScript) -> Vec<CliScriptDiagnostic> {
+    let mut diagnostics = Vec::new();
+    // Commands and variables defined by the script itself
+    let mut commands: Vec<String> = script.procs.iter().map(|proc| proc.name.clone()).collect();
+    let mut vars: Vec<String> = Vec::new();
+    for (i, line) in script.lines.iter().enumerate() {
+        let (number, column) = script.positions.get(i).copied().unwrap_or((i + 1, 1));
+        let mut report = |offset: usize, severity: CliSeverity, message: String| {
+            diagnostics.push(CliScriptDiagnostic {
+                line: number,
+                column: column + line[..offset].chars().count(),
+                severity,
+                message,
+            });
+        };
+        let chain = match parse_cli_chain(line) {
+            Ok(chain) => chain,
+            Err(e) => {
+                let offset = e.span(line).map_or(0, |span| span.start);
+                report(offset, CliSeverity::Error, describe_error(world, &e.into()));
+                continue;
+            }
+        };
+        for link in chain.links() {
+            let start = link.span.start;
+            let segment = &line[link.span.clone()];
+            let Ok(parsed) = parse_cli(segment) else {
+                continue;
+            };
+            let mut script_vars = false;
+            for (span, name) in env::var_refs(segment) {
+                if env::is_var_set(world, name) {
+                    continue;
+                }
+                script_vars = true;
+                if !vars.iter().any(|var| var == name) {
+                    let message = format!("variable {:?} is not set", name);
+                    report(start + span.start, CliSeverity::Warn, message);
+                }
+            }
+            let piped = link.op == Some(CliChainOp::Pipe);
+            match run_cli_dry(world, segment) {
+                Ok(_) => {}
+                Err(CliError::NotFound(name)) if commands.contains(&name) => {}
+                Err(CliError::InvalidArgs { .. }) if piped || script_vars => {}
+                Err(e) => {
+                    let offset = match &e {
+                        CliError::InvalidArgs { index, .. } => index
+                            .and_then(|index| parsed.arg_spans().get(index))
+                            .map_or(segment.trim_end().len(), |span| span.start),
+                        _ => parsed.name_span().start,
+                    };
+                    let severity = match &e {
+                        CliError::ConditionNotMet(_) | CliError::NotPermitted { .. } => {
+                            CliSeverity::Warn
+                        }
+                        _ => CliSeverity::Error,
+                    };
+                    let mut message = format!("{}!", describe_error(world, &e));
+                    if let CliError::NotFound(name) = &e {
+                        if let Some(hint) = resolve::did_you_mean(world, name) {
+                            message = format!("{} {}", message, hint);
+                        }
+                    }
+                    report(start + offset, severity, message);
+                }
+            }
+            match (parsed.name(), parsed.args()) {
+                ("alias", [name, _, ..]) => commands.push(name.clone()),
+                ("set", [name, ..]) => vars.push(name.clone()),
+                _ => {}
+            }
+        }
+    }
+    diagnostics
+}
+
+/// Error when loading a [`CliScript`]
+#[derive(Debug)]
+pub enum CliScriptLoadError {
+    Io(std::io::Error),
+    Utf8(FromUtf8Error),
+    Parse(CliScriptParseError),
+}
+
+impl fmt::Display for CliScriptLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliScriptLoadError::Io(e) => write!(f, "could not read CLI script: {}", e),
+            CliScriptLoadError::Utf8(e) => write!(f, "CLI script is not valid UTF-8: {}", e),
+            CliScriptLoadError::Parse(e) => write!(f, "could not parse CLI script: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CliScriptLoadError {}
+
+impl From<std::io::Error> for CliScriptLoadError {
+    fn from(e: std::io::Error) -> Self {
+        CliScriptLoadError::Io(e)
+    }
+}
+
+impl From<FromUtf8Error> for CliScriptLoadError {
+    fn from(e: FromUtf8Error) -> Self {
+        CliScriptLoadError::Utf8(e)
+    }
+}
+
+impl From<CliScriptParseError> for CliScriptLoadError {
+    fn from(e: CliScriptParseError) -> Self {
+        CliScriptLoadError::Parse(e)
+    }
+}
+
+/// Asset loader for [`CliScript`]s, from `.cli` files
+#[derive(Default)]
+pub struct CliScriptLoader;
+
+impl AssetLoader for CliScriptLoader {
+    type Asset = CliScript;
+    type Settings = ();
+    type Error = CliScriptLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<CliScript, CliScriptLoadError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let text = String::from_utf8(bytes)?;
+        CliScript::from_text(&text).map_err(CliScriptLoadError::from)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["cli"]
+    }
+}
+
+/// Scripts waiting to finish loading, before they can be run
+#[derive(Resource, Default)]
+struct CliPendingScripts {
+    scripts: Vec<CliPendingScript>,
+}
+
+struct CliPendingScript {
+    script: Handle<CliScript>,
+    /// The scripts that included it
+    parents: Vec<AssetPath<'static>>,
+    /// The session it was run from
+    session: Option<Entity>,
+    /// The source that ran it
+    source: Option<CliSource>,
+}
+
+/// The scripts that are currently running, innermost last
+#[derive(Resource, Debug)]
+pub struct CliScriptStack {
+    stack: Vec<AssetPath<'static>>,
+    max_depth: usize,
+    validate: bool,
+}
+
+impl Default for CliScriptStack {
+    fn default() -> Self {
+        let plugin = CliScriptPlugin::default();
+        CliScriptStack {
+            stack: Vec::new(),
+            max_depth: plugin.max_depth,
+            validate: plugin.validate,
+        }
+    }
+}
+
+impl CliScriptStack {
+    /// The paths of the running scripts, outermost first
+    pub fn iter(&self) -> impl Iterator<Item = &AssetPath<'static>> {
+        self.stack.iter()
+    }
+    /// How many scripts are running inside each other
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+}
+
+/// Extension trait for running [`CliScript`]s
+pub trait CliScriptRunExt {
+    /// Run all the command strings in a script, in order
+    ///
+    /// A `sleep` command in the script delays the rest of it.
+    /// If the script is not loaded yet, it is run once it finishes loading.
+    fn run_cli_script(&mut self, script: &Handle<CliScript>);
+}
+
+impl CliScriptRunExt for World {
+    fn run_cli_script(&mut self, script: &Handle<CliScript>) {
+        run_script_nested(self, script, Vec::new());
+    }
+}
+
+impl CliScriptRunExt for App {
+    fn run_cli_script(&mut self, script: &Handle<CliScript>) {
+        self.world_mut().run_cli_script(script);
+    }
+}
+
+/// Run a script, included by the scripts in `parents`
+fn run_script_nested(
+    world: &mut World,
+    script: &Handle<CliScript>,
+    parents: Vec<AssetPath<'static>>,
+) {
+    let loaded = world
+        .get_resource::<Assets<CliScript>>()
+        .and_then(|assets| assets.get(script))
+        .cloned();
+    let session = world
+        .get_resource::<CliStatus>()
+        .and_then(|status| status.session);
+    let source = world
+        .get_resource::<CliStatus>()
+        .and_then(|status| status.source().cloned());
+    let Some(loaded) = loaded else {
+        world
+            .get_resource_or_insert_with(CliPendingScripts::default)
+            .scripts
+            .push(CliPendingScript {
+                script: script.clone(),
+                parents,
+                session,
+                source,
+            });
+        return;
+    };
+    let path = world
+        .get_resource::<AssetServer>()
+        .and_then(|asset_server| asset_server.get_path(script.id()))
+        .map(|path| path.into_owned());
+    let validate = world
+        .get_resource::<CliScriptStack>()
+        .is_none_or(|stack| stack.validate);
+    if validate {
+        let errors: Vec<CliScriptDiagnostic> = validate_cli_script(world, &loaded)
+            .into_iter()
+            .filter(|diagnostic| diagnostic.severity == CliSeverity::Error)
+            .collect();
+        if !errors.is_empty() {
+            let name = path
+                .as_ref()
+                .map_or("CLI script".into(), |path| path.to_string());
+            for diagnostic in &errors {
+                cli_error(world, format!("{}:{}", name, diagnostic));
+            }
+            cli_error(
+                world,
+                format!("{} was not run, it has {} error(s)!", name, errors.len()),
+            );
+            cli_fail(world);
+            return;
+        }
+    }
+    let CliScript { lines, procs, .. } = loaded;
+    for proc in procs {
+        let in_session =
+            session.filter(|&entity| !proc.exported && world.get::<CliSession>(entity).is_some());
+        match in_session {
+            Some(entity) => {
+                if let Some(mut session) = world.get_mut::<CliSession>(entity) {
+                    session.define_proc(&proc.name, &proc.body);
+                }
+            }
+            None => {
+                world.register_clicommand_template(&proc.name, &proc.body);
+            }
+        }
+    }
+    let mut stack = parents;
+    // Scripts run by nothing else are their own source
+    let source = source.or_else(|| Some(CliSource::Script(path.as_ref()?.to_string())));
+    stack.extend(path);
+    let outer = std::mem::replace(
+        &mut world
+            .get_resource_or_insert_with(CliScriptStack::default)
+            .stack,
+        stack,
+    );
+    match source {
+        Some(source) => with_cli_source(world, source, |world| run_cli_lines(world, lines)),
+        None => run_cli_lines(world, lines),
+    }
+    world.resource_mut::<CliScriptStack>().stack = outer;
+}
+
+/// Log the problems in scripts when they finish loading
+fn validate_loaded_scripts(
+    world: &mut World,
+    mut cursor: Local<EventCursor<AssetEvent<CliScript>>>,
+) {
+    let Some(events) = world.get_resource::<Events<AssetEvent<CliScript>>>() else {
+        return;
+    };
+    let loaded: Vec<AssetId<CliScript>> = cursor
+        .read(events)
+        .filter_map(|event| match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    if loaded.is_empty() || !world.resource::<CliScriptStack>().validate {
+        return;
+    }
+    for id in loaded {
+        let Some(script) = world.resource::<Assets<CliScript>>().get(id).cloned() else {
+            continue;
+        };
+        let name = world
+            .get_resource::<AssetServer>()
+            .and_then(|asset_server| asset_server.get_path(id))
+            .map_or("CLI script".into(), |path| path.to_string());
+        for diagnostic in validate_cli_script(world, &script) {
+            match diagnostic.severity {
+                CliSeverity::Error => error!("{}:{}", name, diagnostic),
+                _ => warn!("{}:{}", name, diagnostic),
+            }
+        }
+    }
+}
+
+fn run_pending_scripts(world: &mut World) {
+    let pending = std::mem::take(&mut world.resource_mut::<CliPendingScripts>().scripts);
+    if pending.is_empty() {
+        return;
+    }
+    let mut still_pending = Vec::new();
+    for pending in pending {
+        let state = world.resource::<AssetServer>().load_state(&pending.script);
+        match state {
+            LoadState::Loaded => {
+                // Run it in the session it was run from, for its source
+                let mut status = world.get_resource_or_insert_with(CliStatus::default);
+                let prev_session = std::mem::replace(&mut status.session, pending.session);
+                let prev_source = std::mem::replace(&mut status.source, pending.source);
+                run_script_nested(world, &pending.script, pending.parents);
+                let mut status = world.resource_mut::<CliStatus>();
+                status.session = prev_session;
+                status.source = prev_source;
+            }
+            LoadState::Failed(e) => error!("CLI script failed to load: {}", e),
+            _ => still_pending.push(pending),
+        }
+    }
+    world
+        .resource_mut::<CliPendingScripts>()
+        .scripts
+        .extend(still_pending);
+}
+
+/// `exec <path>`: load a script file and run it
+///
+/// From inside a script, relative paths are relative to that script.
+fn exec(In(args): In<Vec<String>>, world: &mut World) {
+    let [path] = args.as_slice() else {
+        cli_error(world, "Usage: exec <path>");
+        cli_fail(world);
+        return;
+    };
+    let (parents, max_depth) = match world.get_resource::<CliScriptStack>() {
+        Some(stack) => (stack.stack.clone(), stack.max_depth),
+        None => (Vec::new(), CliScriptPlugin::default().max_depth),
+    };
+    let path = match parents.last() {
+        Some(current) => current.resolve(path),
+        None => AssetPath::try_parse(path).map(|path| path.into_owned()),
+    };
+    let path = match path {
+        Ok(path) => path,
+        Err(e) => {
+            error!("Invalid CLI script path: {}", e);
+            cli_fail(world);
+            return;
+        }
+    };
+    if parents.contains(&path) {
+        error!("CLI script cycle: {}", script_trace(&parents, &path));
+        cli_fail(world);
+        return;
+    }
+    if parents.len() >= max_depth {
+        error!(
+            "CLI scripts nested too deeply (max {}): {}",
+            max_depth,
+            script_trace(&parents, &path)
+        );
+        cli_fail(world);
+        return;
+    }
+    let Some(asset_server) = world.get_resource::<AssetServer>() else {
+        error!("Cannot load CLI scripts without the AssetServer!");
+        cli_fail(world);
+        return;
+    };
+    let script = asset_server.load::<CliScript>(path);
+    run_script_nested(world, &script, parents);
+}
+
+/// Format a script stack for error messages, like `a.cli -> b.cli -> c.cli`
+fn script_trace(parents: &[AssetPath<'static>], path: &AssetPath<'static>) -> String {
+    let paths: Vec<String> = parents
+        .iter()
+        .chain(std::iter::once(path))
+        .map(|path| path.to_string())
+        .collect();
+    paths.join(" -> ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::CliArgs;
+    use crate::output::CliOutputLog;
+    use crate::CliPlugin;
+
+    #[derive(Resource, Default)]
+    struct Spawned(Vec<(i32, i32)>);
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            CliPlugin,
+            CliScriptPlugin::default(),
+        ));
+        app.init_resource::<Spawned>();
+        app.register_clicommand_typed(
+            "spawn",
+            "<x: i32> <y: i32>",
+            |In(args): In<CliArgs>, mut spawned: ResMut<Spawned>| {
+                let (x, y) = (args.get("x").unwrap(), args.get("y").unwrap());
+                spawned.0.push((x, y));
+            },
+        );
+        app
+    }
+
+    #[test]
+    fn diagnostics() {
+        let mut app = app();
+        let script = CliScript::from_text(
+            "# setup\n\
+             spawn 1 2\n  \
+             spwn 1 2\n\
+             spawn 1\n\
+             set n 5\n\
+             spawn $n 2 | echo\n\
+             echo $missing && greet Bob\n\
+             proc greet { echo hi $1 }\n",
+        )
+        .unwrap();
+        let diagnostics = validate_cli_script(app.world_mut(), &script);
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.line, d.column, d.severity))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (3, 3, CliSeverity::Error),
+                (4, 8, CliSeverity::Error),
+                (7, 6, CliSeverity::Warn),
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].to_string(),
+            r#"3:3: CliCommand "spwn" not found! Did you mean "spawn"?"#
+        );
+        assert_eq!(diagnostics[2].message, r#"variable "missing" is not set"#);
+    }
+
+    #[test]
+    fn scripts_with_errors_are_not_run() {
+        let mut app = app();
+        let mut assets = app.world_mut().resource_mut::<Assets<CliScript>>();
+        let good = assets.add(CliScript::from_text("spawn 1 2\nspawn 3 4").unwrap());
+        let bad = assets.add(CliScript::from_text("spawn 5 6\n\nspawn 7").unwrap());
+        app.run_cli_script(&good);
+        app.run_cli_script(&bad);
+        assert_eq!(app.world().resource::<Spawned>().0, [(1, 2), (3, 4)]);
+        let output: Vec<&str> = app
+            .world()
+            .resource::<CliOutputLog>()
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect();
+        assert_eq!(
+            output,
+            [
+                r#"CLI script:3:8: invalid args for CliCommand "spawn": missing arg <y>!"#,
+                "CLI script was not run, it has 1 error(s)!",
+            ]
+        );
+    }
+}
@@ -0,0 +1,588 @@
+//! The history of console output, with search and paging.
+//!
+//! [`CliScrollback`] keeps the most recent lines of output (from the
+//! [`CliOutputLog`]), so frontends can render from it instead of keeping
+//! their own history. It is updated in `PostUpdate`, in [`CliScrollbackSet`].
+//!
+//! ```rust,ignore
+//! fn render(scrollback: Res<CliScrollback>, mut text: Single<&mut Text>) {
+//!     text.0 = scrollback.iter().map(|line| line.text.as_str()).collect::<Vec<_>>().join("\n");
+//! }
+//! ```
+//!
+//! Built-in commands:
+//!
+//!  - `search <pattern>` (or `/pattern`): print the lines containing the text
+//!  - `page next`, `page prev`, `page quit`: page through long output
+//!
+//! Commands that can print hundreds of lines can be set to be paged with
+//! [`set_clicommand_paged`](crate::CliCommandsRegisterExt::set_clicommand_paged).
+//! If their output is longer than [`CliScrollback::page_size`] lines, only
+//! the first page is printed, like with `more`, and `page next` prints the
+//! next one. Output that is captured (like when piped) is not paged.
+//!
+//! The output of every command can be limited with
+//! [`CliConfig::max_output_lines`] and [`CliConfig::max_output_bytes`], so
+//! a command dumping enormous text does not flood the console or remote
+//! clients. The rest of the output is dropped, with a marker line:
+//!
+//! ```text
+//! > entities
+//! 0v1 Camera
+//! ...
+//! ... 4321 more lines, use --page
+//! > entities --page
+//! ```
+//!
+//! Any command run with a `--page` flag is paged, like those set to be,
+//! including when its output is captured.
+//!
+//! [`CliOutputHistory`] keeps the output of the most recent command strings
+//! that printed something, so it can be looked at again, piped, or saved:
+//!
+//!  - `output [n]`: print the output of the `n`th most recent command string
+//!    again (the previous one by default)
+//!  - `output list`: list the kept outputs, most recent first
+//!
+//! `$LAST` expands to the output of the previous command string, like
+//! `$(output)` (unless a variable named `LAST` is set, see [`env`](crate::env)):
+//!
+//! ```text
+//! > entities
+//! ...
+//! > output | grep Sprite
+//! > set sprites "$LAST"
+//! ```
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::intercept::{CliHookDecision, CliInterceptRegisterExt, CliIntercepted};
+use crate::local::CliCurrentCommand;
+use crate::output::{
+    capture_output, cli_error, cli_println, cli_push_line, CliOutput, CliOutputLine, CliOutputLog,
+};
+use crate::source::CliSource;
+use crate::status::{cli_fail, CliStatus};
+use crate::{CliCommandsRegisterExt, CliConfig};
+
+/// System set for updating the [`CliScrollback`]
+///
+/// Frontends that render from it should run after it.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CliScrollbackSet;
+
+/// Output waiting to be paged through
+#[derive(Debug, Clone)]
+struct CliPager {
+    lines: Vec<CliOutputLine>,
+    page: usize,
+}
+
+/// The most recent lines of console output
+///
+/// Requires [`CliPlugin`](crate::CliPlugin).
+#[derive(Resource, Debug, Clone)]
+pub struct CliScrollback {
+    lines: VecDeque<CliOutputLine>,
+    /// How many lines were dropped from the front, so indices stay stable
+    dropped: usize,
+    search: Option<String>,
+    pager: Option<CliPager>,
+    /// How many pages were printed, to not limit their output
+    pages_shown: u64,
+    /// How many lines to keep; older lines are dropped
+    pub max_lines: usize,
+    /// How many lines of paged output to print at once
+    pub page_size: usize,
+}
+
+impl Default for CliScrollback {
+    fn default() -> Self {
+        CliScrollback {
+            lines: VecDeque::new(),
+            dropped: 0,
+            search: None,
+            pager: None,
+            pages_shown: 0,
+            max_lines: 1000,
+            page_size: 20,
+        }
+    }
+}
+
+impl CliScrollback {
+    /// Add a line to the end
+    pub fn push(&mut self, line: CliOutputLine) {
+        self.lines.push_back(line);
+        while self.lines.len() > self.max_lines {
+            self.lines.pop_front();
+            self.dropped += 1;
+        }
+    }
+    /// Iterate over the lines, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &CliOutputLine> {
+        self.lines.iter()
+    }
+    /// Iterate over the lines, with their indices, oldest first
+    ///
+    /// Indices keep counting up as old lines are dropped.
+    pub fn iter_indexed(&self) -> impl Iterator<Item = (usize, &CliOutputLine)> {
+        (self.dropped..).zip(self.lines.iter())
+    }
+    /// Get a line by its index, if it was not dropped yet
+    pub fn get(&self, index: usize) -> Option<&CliOutputLine> {
+        self.lines.get(index.checked_sub(self.dropped)?)
+    }
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.dropped += self.lines.len();
+        self.lines.clear();
+    }
+    /// Iterate over the lines containing a pattern (ignoring case), with their indices
+    pub fn search<'a>(
+        &'a self,
+        pattern: &str,
+    ) -> impl Iterator<Item = (usize, &'a CliOutputLine)> + 'a {
+        let pattern = pattern.to_lowercase();
+        self.iter_indexed()
+            .filter(move |(_, line)| line.text.to_lowercase().contains(&pattern))
+    }
+    /// The pattern of the last `search`, for frontends to highlight
+    pub fn search_pattern(&self) -> Option<&str> {
+        self.search.as_deref()
+    }
+    /// Whether there is paged output to page through
+    pub fn is_paging(&self) -> bool {
+        self.pager.is_some()
+    }
+    /// The current page and the number of pages, if paging
+    pub fn page(&self) -> Option<(usize, usize)> {
+        let pager = self.pager.as_ref()?;
+        Some((
+            pager.page,
+            pager.lines.len().div_ceil(self.page_size.max(1)),
+        ))
+    }
+}
+
+/// The output of a command string, kept in the [`CliOutputHistory`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliPastOutput {
+    /// The command string, as run
+    pub cli: String,
+    /// Who or what ran it, if known
+    pub source: Option<CliSource>,
+    pub lines: Vec<CliOutputLine>,
+}
+
+/// The output of the most recent command strings
+///
+/// Only command strings that printed something, and that did not run inside
+/// another command string, are kept. The `output` command only shows the
+/// outputs of command strings run by the same [`CliSource`]. Requires [`CliPlugin`](crate::CliPlugin).
+#[derive(Resource, Debug, Clone)]
+pub struct CliOutputHistory {
+    outputs: VecDeque<CliPastOutput>,
+    /// How many outputs to keep; older ones are dropped
+    pub max_outputs: usize,
+}
+
+impl Default for CliOutputHistory {
+    fn default() -> Self {
+        CliOutputHistory {
+            outputs: VecDeque::new(),
+            max_outputs: 20,
+        }
+    }
+}
+
+impl CliOutputHistory {
+    /// Add an output, as the most recent one
+    pub fn push(&mut self, output: CliPastOutput) {
+        self.outputs.push_front(output);
+        self.outputs.truncate(self.max_outputs);
+    }
+    /// Get the `n`th most recent output, starting from 1
+    pub fn get(&self, n: usize) -> Option<&CliPastOutput> {
+        self.outputs.get(n.checked_sub(1)?)
+    }
+    /// The most recent output
+    pub fn last(&self) -> Option<&CliPastOutput> {
+        self.outputs.front()
+    }
+    /// Iterate over the outputs, most recent first
+    pub fn iter(&self) -> impl Iterator<Item = &CliPastOutput> {
+        self.outputs.iter()
+    }
+    pub fn len(&self) -> usize {
+        self.outputs.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.outputs.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.outputs.clear();
+    }
+}
+
+/// Keep the output of a command string, if it printed anything
+pub(crate) fn record_output(world: &mut World, cli: &str, lines: Vec<CliOutputLine>) {
+    if lines.is_empty() {
+        return;
+    }
+    let source = world
+        .get_resource::<CliStatus>()
+        .and_then(|status| status.source().cloned());
+    if let Some(mut history) = world.get_resource_mut::<CliOutputHistory>() {
+        history.push(CliPastOutput {
+            cli: cli.trim().to_owned(),
+            source,
+            lines,
+        });
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliScrollback>();
+    app.init_resource::<CliOutputHistory>();
+    app.add_systems(PostUpdate, collect_scrollback.in_set(CliScrollbackSet));
+    app.add_cli_hook(slash_search);
+    app.register_clicommand_args("search", search)
+        .set_clicommand_description("search", "Print the lines of output containing some text")
+        .set_clicommand_usage("search", "search <pattern>");
+    app.register_clicommand_args("output", output)
+        .set_clicommand_description("output", "Print the output of a previous command again")
+        .set_clicommand_usage("output", "output [n|list]");
+    app.register_clicommand_args("page", page)
+        .set_clicommand_description("page", "Page through long output")
+        .set_clicommand_usage("page", "page [next|prev|quit]");
+}
+
+fn collect_scrollback(mut log: ResMut<CliOutputLog>, mut scrollback: ResMut<CliScrollback>) {
+    for line in log.take_scrollback() {
+        scrollback.push(line);
+    }
+}
+
+/// Turn `/pattern` into `search pattern`
+fn slash_search(In(mut cmd): In<CliIntercepted>) -> CliHookDecision {
+    let Some(pattern) = cmd.name.strip_prefix('/').filter(|p| !p.is_empty()) else {
+        return CliHookDecision::Continue;
+    };
+    cmd.args.insert(0, pattern.to_owned());
+    cmd.name = "search".to_owned();
+    CliHookDecision::Rewrite(cmd)
+}
+
+/// `search <pattern>`: print the lines of output containing some text
+fn search(In(args): In<Vec<String>>, mut scrollback: ResMut<CliScrollback>, mut output: CliOutput) {
+    if args.is_empty() {
+        output.fail("Usage: search <pattern>");
+        return;
+    }
+    let pattern = args.join(" ");
+    let found: Vec<String> = scrollback
+        .search(&pattern)
+        .map(|(index, line)| format!("[{}] {}", index, line.text))
+        .collect();
+    scrollback.search = Some(pattern.clone());
+    if found.is_empty() {
+        output.println(format!("No lines contain {:?}", pattern));
+        return;
+    }
+    for line in found {
+        output.println(line);
+    }
+}
+
+/// `output [n|list]`: print the output of the `n`th most recent command
+/// string again, or list the kept outputs
+///
+/// Only the outputs of command strings run by the same source are counted.
+fn output(In(args): In<Vec<String>>, world: &mut World) {
+    let source = world
+        .get_resource::<CliStatus>()
+        .and_then(|status| status.source());
+    let history: Vec<&CliPastOutput> = world
+        .resource::<CliOutputHistory>()
+        .iter()
+        .filter(|past| past.source.as_ref() == source)
+        .collect();
+    let n = match args.first().map(|arg| arg.as_str()) {
+        None => Ok(1),
+        Some("list") if args.len() == 1 => {
+            let listed: Vec<String> = (1..)
+                .zip(history)
+                .map(|(n, past)| format!("[{}] {} ({} lines)", n, past.cli, past.lines.len()))
+                .collect();
+            for line in listed {
+                cli_println(world, line);
+            }
+            return;
+        }
+        Some(n) if args.len() == 1 => n.parse::<usize>().map_err(|_| "Usage: output [n|list]"),
+        Some(_) => Err("Usage: output [n|list]"),
+    };
+    let past = |n: usize| history.get(n.checked_sub(1)?);
+    let lines = match n.map(|n| (n, past(n))) {
+        Ok((_, Some(past))) => past.lines.clone(),
+        Ok((n, None)) => {
+            let message = format!("No output {} (there are {})!", n, history.len());
+            cli_error(world, message);
+            cli_fail(world);
+            return;
+        }
+        Err(usage) => {
+            cli_error(world, usage);
+            cli_fail(world);
+            return;
+        }
+    };
+    for mut line in lines {
+        // Printed again by this command
+        line.source = None;
+        line.command = None;
+        cli_push_line(world, line);
+    }
+}
+
+/// `page [next|prev|quit]`: page through long output
+fn page(In(args): In<Vec<String>>, world: &mut World) {
+    let mut scrollback = world.resource_mut::<CliScrollback>();
+    let Some(pager) = &mut scrollback.pager else {
+        cli_error(world, "Nothing to page through!");
+        cli_fail(world);
+        return;
+    };
+    let error = match args.first().map(|arg| arg.as_str()) {
+        None => None,
+        Some("next") => {
+            pager.page += 1;
+            None
+        }
+        Some("prev") => {
+            pager.page = pager.page.saturating_sub(1);
+            None
+        }
+        Some("quit") => {
+            scrollback.pager = None;
+            return;
+        }
+        Some(_) => Some("Usage: page [next|prev|quit]"),
+    };
+    match error {
+        Some(e) => {
+            cli_error(world, e);
+            cli_fail(world);
+        }
+        None => show_page(world),
+    }
+}
+
+/// Whether the output of a command should be paged
+///
+/// Output is not paged when captured, or for commands run by other commands.
+pub(crate) fn should_page(world: &World, paged: bool) -> bool {
+    let nested = world
+        .get_resource::<CliCurrentCommand>()
+        .is_some_and(|current| current.name().is_some());
+    let capturing = world
+        .get_resource::<CliOutputLog>()
+        .is_some_and(|log| log.is_capturing());
+    paged && !nested && !capturing && world.contains_resource::<CliScrollback>()
+}
+
+/// Run a command, paging its output if it is too long
+pub(crate) fn run_paged<R>(world: &mut World, f: impl FnOnce(&mut World) -> R) -> R {
+    let (lines, r) = capture_output(world, f);
+    if lines.len() <= world.resource::<CliScrollback>().page_size {
+        for line in lines {
+            cli_push_line(world, line);
+        }
+        return r;
+    }
+    world.resource_mut::<CliScrollback>().pager = Some(CliPager { lines, page: 0 });
+    show_page(world);
+    r
+}
+
+/// Run a command, limiting its output to [`CliConfig::max_output_lines`]
+/// and [`CliConfig::max_output_bytes`]
+///
+/// The output of commands run by other commands is limited with theirs.
+/// Paged output is not limited, as it is printed a page at a time.
+pub(crate) fn run_limited<R>(world: &mut World, f: impl FnOnce(&mut World) -> R) -> R {
+    let (max_lines, max_bytes) = world
+        .get_resource::<CliConfig>()
+        .map(|config| (config.max_output_lines, config.max_output_bytes))
+        .unwrap_or_default();
+    let nested = world
+        .get_resource::<CliCurrentCommand>()
+        .is_some_and(|current| current.name().is_some());
+    if nested || (max_lines.is_none() && max_bytes.is_none()) {
+        return f(world);
+    }
+    let pages_shown = |world: &World| {
+        world
+            .get_resource::<CliScrollback>()
+            .map(|scrollback| scrollback.pages_shown)
+    };
+    let before = pages_shown(world);
+    let (mut lines, r) = capture_output(world, f);
+    if pages_shown(world) == before {
+        lines = limit_output(
+            lines,
+            max_lines.unwrap_or(usize::MAX),
+            max_bytes.unwrap_or(usize::MAX),
+        );
+    }
+    for line in lines {
+        cli_push_line(world, line);
+    }
+    r
+}
+
+/// Keep the lines of output that fit in the limits, followed by a line
+/// telling how many were dropped
+fn limit_output(
+    mut lines: Vec<CliOutputLine>,
+    max_lines: usize,
+    max_bytes: usize,
+) -> Vec<CliOutputLine> {
+    let mut bytes = 0;
+    let fits = lines
+        .iter()
+        .take(max_lines)
+        .take_while(|line| {
+            // Counting a newline between lines
+            bytes += line.text.len();
+            let fits = bytes <= max_bytes;
+            bytes += 1;
+            fits
+        })
+        .count();
+    let more = lines.len() - fits;
+    lines.truncate(fits);
+    match more {
+        0 => {}
+        1 => lines.push(CliOutputLine::new("... 1 more line, use --page")),
+        more => lines.push(CliOutputLine::new(format!(
+            "... {} more lines, use --page",
+            more
+        ))),
+    }
+    lines
+}
+
+/// Print the current page of the paged output, and how to get to the others
+fn show_page(world: &mut World) {
+    let scrollback = world.resource::<CliScrollback>();
+    let (Some(pager), Some((page, pages))) = (&scrollback.pager, scrollback.page()) else {
+        return;
+    };
+    let page = page.min(pages.saturating_sub(1));
+    let size = scrollback.page_size.max(1);
+    let lines: Vec<CliOutputLine> = pager
+        .lines
+        .iter()
+        .skip(page * size)
+        .take(size)
+        .cloned()
+        .collect();
+    let footer = match page + 1 == pages {
+        true => format!(
+            "-- page {}/{} (end): `page prev` or `page quit` --",
+            page + 1,
+            pages
+        ),
+        false => format!(
+            "-- page {}/{}: `page next`, `page prev` or `page quit` --",
+            page + 1,
+            pages
+        ),
+    };
+    let mut scrollback = world.resource_mut::<CliScrollback>();
+    scrollback.pages_shown += 1;
+    if let Some(pager) = &mut scrollback.pager {
+        pager.page = page;
+    }
+    for line in lines {
+        cli_push_line(world, line);
+    }
+    cli_push_line(world, CliOutputLine::new(footer));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::CliCommandsOutputExt;
+    use crate::CliPlugin;
+
+    #[test]
+    #[cfg(feature = "builtin-commands")]
+    fn previous_outputs() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        let world = app.world_mut();
+        let mut run = |cli: &str| -> Vec<String> {
+            let output = world.try_run_cli(cli).unwrap();
+            output.into_iter().map(|line| line.text).collect()
+        };
+
+        run("echo Sprite 1; echo Mesh 2");
+        run("echo other");
+        // Nothing printed, nothing kept
+        run("set x 1");
+        assert_eq!(run("output 2"), ["Sprite 1", "Mesh 2"]);
+        assert_eq!(run("echo $LAST"), ["Sprite 1 Mesh 2"]);
+        assert_eq!(run("echo \"$LAST\""), ["Sprite 1 Mesh 2"]);
+        assert_eq!(
+            run("output list")[..2],
+            ["[1] echo \"$LAST\" (1 lines)", "[2] echo $LAST (1 lines)"]
+        );
+        #[cfg(feature = "parser-advanced")]
+        assert_eq!(run("output 6 | grep Mesh"), ["Mesh 2"]);
+        assert!(world.try_run_cli("output 99").is_err());
+    }
+
+    #[test]
+    fn output_limits() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.register_clicommand_noargs("lines", |world: &mut World| {
+            for i in 1..=10 {
+                cli_println(world, format!("line {}", i));
+            }
+        });
+        let world = app.world_mut();
+        world.resource_mut::<CliConfig>().max_output_lines = Some(3);
+        world.resource_mut::<CliScrollback>().page_size = 4;
+        let run = |world: &mut World, cli: &str| -> Vec<String> {
+            let output = world.try_run_cli(cli).unwrap();
+            output.into_iter().map(|line| line.text).collect()
+        };
+
+        assert_eq!(
+            run(world, "lines"),
+            ["line 1", "line 2", "line 3", "... 7 more lines, use --page"]
+        );
+        assert_eq!(run(world, "lines --page").len(), 5);
+        assert_eq!(run(world, "page next")[0], "line 5");
+        #[cfg(feature = "parser-advanced")]
+        assert_eq!(run(world, "lines | grep 10"), ["line 10"]);
+
+        world.resource_mut::<CliConfig>().max_output_lines = None;
+        world.resource_mut::<CliConfig>().max_output_bytes = Some(14);
+        assert_eq!(
+            run(world, "lines"),
+            ["line 1", "line 2", "... 8 more lines, use --page"]
+        );
+    }
+}
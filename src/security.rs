@@ -0,0 +1,497 @@
+//! Security settings shared by the remote transports.
+//!
+//! Requires the `remote` or `http` cargo feature. The [`remote`](crate::remote)
+//! console and the [`http`](crate::http) server check every connection
+//! against the same [`CliRemoteSecurityConfig`], so a game secures all of
+//! them in one place:
+//!
+//! ```rust,ignore
+//! app.insert_resource(
+//!     CliRemoteSecurityConfig::default()
+//!         .with_allowed_ip("127.0.0.1".parse().unwrap())
+//!         .with_allowed_ip("10.0.0.5".parse().unwrap())
+//!         .with_rate_limit(60)
+//!         .with_profile("moderator", "s3cret", &[CliPermission::Normal]),
+//! );
+//! ```
+//!
+//! - Connections from addresses that are not allowed are refused. With no
+//!   allowed addresses, anyone may connect.
+//! - The password (or token) set in the plugin of a transport gives access
+//!   to every command that the local console may run. The token of a
+//!   [`CliRemoteProfile`] gives access to the commands of its permission
+//!   levels only, like a [`CliSourcePermissions`] rule for the connection.
+//! - A connection that has run its rate limit of command strings in the
+//!   last minute cannot run more until the oldest is a minute old. HTTP
+//!   requests from the same address count as one connection.
+//!
+//! With the `tls` cargo feature, the transports can also encrypt their
+//! connections with TLS (using `rustls`), so passwords and commands do not
+//! cross the network in plaintext. Set the certificate in the plugin of the
+//! transport:
+//!
+//! ```rust,ignore
+//! app.add_plugins(CliRemotePlugin {
+//!     address: "0.0.0.0:27015".to_owned(),
+//!     password: std::env::var("MYGAME_RCON_PASSWORD").unwrap(),
+//!     tls: Some(CliTlsConfig::from_files("certs/server.pem", "certs/server.key")?),
+//!     ..default()
+//! });
+//! ```
+//!
+//! A transport with a certificate only accepts TLS connections. Without the
+//! `tls` feature, it refuses to start.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::path::Path;
+#[cfg(feature = "tls")]
+use std::sync::Arc;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::{HashMap, Instant};
+
+use crate::permissions::CliPermission;
+use crate::source::{constant_time_eq, CliSource, CliSourcePermissions};
+
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// A token giving access to some permission levels only
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliRemoteProfile {
+    /// The name of the profile, for logs
+    pub name: String,
+    pub token: String,
+    /// The permission levels connections using the token may run
+    pub permissions: Vec<CliPermission>,
+}
+
+/// Security settings shared by the remote transports
+///
+/// Initialized with the defaults (anyone may connect, no rate limit, no
+/// profiles) by the plugins of the transports, unless already inserted.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct CliRemoteSecurityConfig {
+    /// The addresses allowed to connect, or anyone if empty
+    pub allowed_ips: Vec<IpAddr>,
+    /// How many command strings each connection may run per minute
+    pub rate_limit: Option<u32>,
+    /// Tokens giving access to some permission levels only
+    pub profiles: Vec<CliRemoteProfile>,
+    /// When the commands of each connection ran, within the last minute
+    runs: HashMap<String, VecDeque<Instant>>,
+}
+
+impl CliRemoteSecurityConfig {
+    /// Allow an address to connect
+    pub fn with_allowed_ip(mut self, ip: IpAddr) -> Self {
+        self.allowed_ips.push(ip);
+        self
+    }
+    /// Limit how many command strings each connection may run per minute
+    pub fn with_rate_limit(mut self, per_minute: u32) -> Self {
+        self.rate_limit = Some(per_minute);
+        self
+    }
+    /// Add a token giving access to some permission levels only
+    pub fn with_profile(mut self, name: &str, token: &str, permissions: &[CliPermission]) -> Self {
+        self.profiles.push(CliRemoteProfile {
+            name: name.to_owned(),
+            token: token.to_owned(),
+            permissions: permissions.to_vec(),
+        });
+        self
+    }
+    /// Whether an address may connect
+    pub fn is_ip_allowed(&self, ip: IpAddr) -> bool {
+        self.allowed_ips.is_empty() || self.allowed_ips.contains(&ip)
+    }
+    /// The profile of a token, if any
+    pub fn profile(&self, token: &str) -> Option<&CliRemoteProfile> {
+        // Compare with every token, so the timing does not tell which matched
+        self.profiles.iter().fold(None, |found, profile| {
+            match constant_time_eq(profile.token.as_bytes(), token.as_bytes()) {
+                true => Some(profile),
+                false => found,
+            }
+        })
+    }
+    /// Count a command string against the rate limit of a connection,
+    /// returning whether it may run
+    fn check_rate(&mut self, connection: &str) -> bool {
+        let Some(per_minute) = self.rate_limit else {
+            return true;
+        };
+        let now = Instant::now();
+        let runs = self.runs.entry(connection.to_owned()).or_default();
+        while runs
+            .front()
+            .is_some_and(|&run| now.duration_since(run) >= RATE_WINDOW)
+        {
+            runs.pop_front();
+        }
+        if runs.len() >= per_minute as usize {
+            return false;
+        }
+        runs.push_back(now);
+        true
+    }
+}
+
+/// A certificate and its private key, for encrypting the connections of a
+/// remote transport with TLS
+///
+/// Requires the `tls` cargo feature to be used.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CliTlsConfig {
+    /// The certificate chain, PEM encoded, starting with that of the server
+    pub certificate_chain: Vec<u8>,
+    /// The private key of the certificate, PEM encoded
+    pub private_key: Vec<u8>,
+}
+
+impl CliTlsConfig {
+    pub fn from_pem(
+        certificate_chain: impl Into<Vec<u8>>,
+        private_key: impl Into<Vec<u8>>,
+    ) -> Self {
+        CliTlsConfig {
+            certificate_chain: certificate_chain.into(),
+            private_key: private_key.into(),
+        }
+    }
+    /// Read the certificate chain and the private key from PEM files
+    pub fn from_files(
+        certificate_chain: impl AsRef<Path>,
+        private_key: impl AsRef<Path>,
+    ) -> io::Result<Self> {
+        Ok(CliTlsConfig {
+            certificate_chain: std::fs::read(certificate_chain)?,
+            private_key: std::fs::read(private_key)?,
+        })
+    }
+    #[cfg(feature = "tls")]
+    fn server_config(&self) -> Result<Arc<rustls::ServerConfig>, String> {
+        use rustls::pki_types::pem::PemObject;
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+        let chain = CertificateDer::pem_slice_iter(&self.certificate_chain)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("invalid certificate chain: {}", e))?;
+        let key = PrivateKeyDer::from_pem_slice(&self.private_key)
+            .map_err(|e| format!("invalid private key: {}", e))?;
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = rustls::ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|e| e.to_string())?
+            .with_no_client_auth()
+            .with_single_cert(chain, key)
+            .map_err(|e| e.to_string())?;
+        Ok(Arc::new(config))
+    }
+}
+
+// The private key is not printed
+impl fmt::Debug for CliTlsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CliTlsConfig")
+            .field(
+                "certificate_chain",
+                &String::from_utf8_lossy(&self.certificate_chain),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+/// Wraps the accepted connections of a transport in TLS, if it has a certificate
+pub(crate) struct CliTlsAcceptor {
+    #[cfg(feature = "tls")]
+    config: Option<Arc<rustls::ServerConfig>>,
+}
+
+impl CliTlsAcceptor {
+    pub(crate) fn new(tls: Option<&CliTlsConfig>) -> Result<Self, String> {
+        #[cfg(feature = "tls")]
+        {
+            let config = tls.map(CliTlsConfig::server_config).transpose()?;
+            Ok(CliTlsAcceptor { config })
+        }
+        #[cfg(not(feature = "tls"))]
+        match tls {
+            Some(_) => Err("TLS requires the `tls` cargo feature".to_owned()),
+            None => Ok(CliTlsAcceptor {}),
+        }
+    }
+    pub(crate) fn accept(&self, stream: TcpStream) -> io::Result<CliRemoteStream> {
+        #[cfg(feature = "tls")]
+        if let Some(config) = &self.config {
+            let connection =
+                rustls::ServerConnection::new(config.clone()).map_err(io::Error::other)?;
+            return Ok(CliRemoteStream::Tls(Box::new(rustls::StreamOwned::new(
+                connection, stream,
+            ))));
+        }
+        Ok(CliRemoteStream::Plain(stream))
+    }
+}
+
+/// A connection of a remote transport, encrypted or not
+pub(crate) enum CliRemoteStream {
+    Plain(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<rustls::StreamOwned<rustls::ServerConnection, TcpStream>>),
+}
+
+impl CliRemoteStream {
+    /// Tell the client that nothing more will be sent, once the rest is flushed
+    pub(crate) fn close(&mut self) {
+        #[cfg(feature = "tls")]
+        if let CliRemoteStream::Tls(stream) = self {
+            stream.conn.send_close_notify();
+        }
+    }
+}
+
+impl Read for CliRemoteStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            CliRemoteStream::Plain(stream) => stream.read(buf),
+            #[cfg(feature = "tls")]
+            CliRemoteStream::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for CliRemoteStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            CliRemoteStream::Plain(stream) => stream.write(buf),
+            // Nothing is accepted while the encrypted data waits for the socket
+            #[cfg(feature = "tls")]
+            CliRemoteStream::Tls(stream) => match stream.write(buf)? {
+                0 if !buf.is_empty() => Err(io::ErrorKind::WouldBlock.into()),
+                n => Ok(n),
+            },
+        }
+    }
+    /// Send everything that was encrypted
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            CliRemoteStream::Plain(stream) => stream.flush(),
+            #[cfg(feature = "tls")]
+            CliRemoteStream::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+/// How a connection authenticated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum CliRemoteAuth {
+    /// With the password of the transport
+    Password,
+    /// With the token of a profile
+    Profile(String),
+}
+
+/// Check the secret sent by a connection, restricting its permissions to
+/// those of its profile
+pub(crate) fn authenticate(
+    world: &mut World,
+    addr: SocketAddr,
+    secret: &str,
+    password: &str,
+) -> Option<CliRemoteAuth> {
+    if constant_time_eq(secret.as_bytes(), password.as_bytes()) {
+        return Some(CliRemoteAuth::Password);
+    }
+    let profile = world
+        .get_resource::<CliRemoteSecurityConfig>()
+        .and_then(|config| config.profile(secret))
+        .cloned()?;
+    let source = CliSource::Remote(addr).to_string();
+    world
+        .get_resource_or_insert_with(CliSourcePermissions::default)
+        .set_first(&source, &profile.permissions);
+    Some(CliRemoteAuth::Profile(profile.name))
+}
+
+/// Whether an address may connect, logging it if not
+pub(crate) fn accept(world: &World, addr: SocketAddr) -> bool {
+    let allowed = world
+        .get_resource::<CliRemoteSecurityConfig>()
+        .is_none_or(|config| config.is_ip_allowed(addr.ip()));
+    if !allowed {
+        warn!("Refused remote CLI connection from {}", addr);
+    }
+    allowed
+}
+
+/// Count a command string against the rate limit of a connection,
+/// returning whether it may run
+///
+/// `per_ip` counts all connections from the same address together.
+pub(crate) fn check_rate(world: &mut World, addr: SocketAddr, per_ip: bool) -> bool {
+    let connection = match per_ip {
+        true => addr.ip().to_string(),
+        false => addr.to_string(),
+    };
+    world
+        .get_resource_mut::<CliRemoteSecurityConfig>()
+        .is_none_or(|mut config| config.check_rate(&connection))
+}
+
+/// Forget the permissions and rate limit of a closed connection
+pub(crate) fn forget(world: &mut World, addr: SocketAddr) {
+    if let Some(mut permissions) = world.get_resource_mut::<CliSourcePermissions>() {
+        permissions.remove(&CliSource::Remote(addr).to_string());
+    }
+    if let Some(mut config) = world.get_resource_mut::<CliRemoteSecurityConfig>() {
+        config.runs.remove(&addr.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config() {
+        let local: IpAddr = "127.0.0.1".parse().unwrap();
+        let mut config = CliRemoteSecurityConfig::default();
+        assert!(config.is_ip_allowed("10.0.0.1".parse().unwrap()));
+        config = config
+            .with_allowed_ip(local)
+            .with_rate_limit(2)
+            .with_profile("moderator", "s3cret", &[CliPermission::Normal]);
+        assert!(config.is_ip_allowed(local));
+        assert!(!config.is_ip_allowed("10.0.0.1".parse().unwrap()));
+        assert_eq!(config.profile("s3cret").unwrap().name, "moderator");
+        assert!(config.profile("s3cre").is_none());
+
+        assert!(config.check_rate("a"));
+        assert!(config.check_rate("a"));
+        assert!(!config.check_rate("a"));
+        assert!(config.check_rate("b"));
+    }
+
+    #[cfg(feature = "remote")]
+    #[test]
+    fn remote_profiles() {
+        use crate::client::CliClient;
+        use crate::permissions::CliPermissions;
+        use crate::remote::{CliRemotePlugin, CliRemoteServer};
+        use crate::{CliCommandsRegisterExt, CliPlugin};
+
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.insert_resource(CliPermissions {
+            cheats: false,
+            dev: true,
+        });
+        app.insert_resource(
+            CliRemoteSecurityConfig::default()
+                .with_rate_limit(3)
+                .with_profile("moderator", "s3cret", &[CliPermission::Normal]),
+        );
+        app.add_plugins(CliRemotePlugin {
+            address: "127.0.0.1:0".to_owned(),
+            password: "hunter2".to_owned(),
+            ..default()
+        });
+        app.register_clicommand_noargs("hello", || {});
+        app.register_clicommand_noargs("debug", || {})
+            .set_clicommand_permission("debug", CliPermission::Dev);
+        let addr = app
+            .world()
+            .resource::<CliRemoteServer>()
+            .local_addr()
+            .unwrap();
+
+        let thread = std::thread::spawn(move || {
+            let mut admin = CliClient::connect(addr, "hunter2").unwrap();
+            assert!(admin.run("debug", |_| {}).unwrap());
+            let mut moderator = CliClient::connect(addr, "s3cret").unwrap();
+            assert!(moderator.run("hello", |_| {}).unwrap());
+            assert!(!moderator.run("debug", |_| {}).unwrap());
+            assert!(moderator.run("hello", |_| {}).unwrap());
+            // Over the rate limit
+            assert!(!moderator.run("hello", |_| {}).unwrap());
+            assert!(admin.run("hello", |_| {}).unwrap());
+        });
+        while !thread.is_finished() {
+            app.update();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        thread.join().unwrap();
+
+        // Only the allowed addresses may connect
+        app.insert_resource(
+            CliRemoteSecurityConfig::default().with_allowed_ip("10.0.0.1".parse().unwrap()),
+        );
+        let thread = std::thread::spawn(move || CliClient::connect(addr, "hunter2").is_err());
+        while !thread.is_finished() {
+            app.update();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(thread.join().unwrap());
+    }
+
+    #[cfg(all(feature = "tls", feature = "remote"))]
+    #[test]
+    fn tls() {
+        use crate::client::CliClient;
+        use crate::remote::{CliRemotePlugin, CliRemoteServer};
+        use crate::{CliCommandsRegisterExt, CliPlugin};
+        use std::io::{BufRead, BufReader};
+
+        let certified = rcgen::generate_simple_self_signed(["localhost".to_owned()]).unwrap();
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.add_plugins(CliRemotePlugin {
+            address: "127.0.0.1:0".to_owned(),
+            password: "hunter2".to_owned(),
+            tls: Some(CliTlsConfig::from_pem(
+                certified.cert.pem(),
+                certified.signing_key.serialize_pem(),
+            )),
+            ..default()
+        });
+        app.register_clicommand_noargs("hello", || {});
+        let addr = app
+            .world()
+            .resource::<CliRemoteServer>()
+            .local_addr()
+            .unwrap();
+
+        let thread = std::thread::spawn(move || {
+            let mut roots = rustls::RootCertStore::empty();
+            roots.add(certified.cert.der().clone()).unwrap();
+            let provider = Arc::new(rustls::crypto::ring::default_provider());
+            let config = rustls::ClientConfig::builder_with_provider(provider)
+                .with_safe_default_protocol_versions()
+                .unwrap()
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+            let connection =
+                rustls::ClientConnection::new(Arc::new(config), "localhost".try_into().unwrap())
+                    .unwrap();
+            let mut stream =
+                rustls::StreamOwned::new(connection, TcpStream::connect(addr).unwrap());
+            stream.write_all(b"hunter2\nhello\n").unwrap();
+            let mut lines = BufReader::new(stream).lines();
+            assert_eq!(lines.next().unwrap().unwrap(), "auth ok");
+            assert!(lines.next().unwrap().unwrap().starts_with("session "));
+            assert_eq!(lines.next().unwrap().unwrap(), "ok");
+            // Plaintext connections are refused
+            assert!(CliClient::connect(addr, "hunter2").is_err());
+        });
+        while !thread.is_finished() {
+            app.update();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        thread.join().unwrap();
+    }
+}
@@ -0,0 +1,176 @@
+//! Selecting entities in args, like `despawn @name(Player)`.
+//!
+//! Args of the form `@selector(argument)` are replaced with the entities
+//! they select, before the command runs. A selector that matches several
+//! entities is replaced with several args. Selecting no entities, or using
+//! an unknown selector, is an error ([`CliError::Selector`]).
+//!
+//! The built-in selectors are:
+//!  - `@id(42)` or `@id(42v1)`: the entity with that index (or index and generation)
+//!  - `@name(Player)`: the entities with that [`Name`]
+//!  - `@with(Enemy)`: the entities with a component of that type
+//!
+//! Games can add their own, with [`CliSelectors::add`]:
+//!
+//! ```rust,ignore
+//! world.resource_mut::<CliSelectors>().add("team", |team, world| {
+//!     let mut q = world.query::<(Entity, &Team)>();
+//!     Ok(q.iter(world).filter(|(_, t)| t.0 == team).map(|(e, _)| e).collect())
+//! });
+//! ```
+//!
+//! Selected entities are passed to commands as `42v1` (index and
+//! generation). Use [`parse_cli_entity`] to read them, or declare an `Entity`
+//! param in the signature of a typed command, and get it with
+//! [`CliArgs::get_entity`](crate::args::CliArgs::get_entity). To pass an
+//! arg with spaces to a selector, quote it: `@name("Player One")`.
+
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::error::CliError;
+use crate::short_name::ShortName;
+
+/// A function that finds the entities selected by a selector's argument
+pub type CliSelectorFn = dyn Fn(&str, &mut World) -> Result<Vec<Entity>, String> + Send + Sync;
+
+/// The selectors that can be used in args
+///
+/// Requires [`CliPlugin`](crate::CliPlugin).
+#[derive(Resource)]
+pub struct CliSelectors {
+    selectors: HashMap<String, Arc<CliSelectorFn>>,
+}
+
+impl Default for CliSelectors {
+    fn default() -> Self {
+        let mut selectors = CliSelectors {
+            selectors: HashMap::new(),
+        };
+        selectors.add("id", select_id);
+        selectors.add("name", select_name);
+        selectors.add("with", select_with);
+        selectors
+    }
+}
+
+impl CliSelectors {
+    /// Add a selector, used as `@name(argument)`
+    ///
+    /// If a selector with the same name already exists, it is replaced.
+    pub fn add<F>(&mut self, name: &str, selector: F)
+    where
+        F: Fn(&str, &mut World) -> Result<Vec<Entity>, String> + Send + Sync + 'static,
+    {
+        self.selectors.insert(name.to_owned(), Arc::new(selector));
+    }
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.selectors.remove(name).is_some()
+    }
+    /// Iterate over the names of the selectors
+    pub fn iter_names(&self) -> impl Iterator<Item = &str> {
+        self.selectors.keys().map(|name| name.as_str())
+    }
+}
+
+/// Parse an entity as passed to commands by selectors, like `42v1`
+///
+/// A bare index (`42`) is accepted too, for the first generation.
+pub fn parse_cli_entity(s: &str) -> Option<Entity> {
+    let (index, generation) = match s.split_once('v') {
+        Some((index, generation)) => (index.parse::<u32>().ok()?, generation.parse::<u32>().ok()?),
+        None => (s.parse::<u32>().ok()?, 1),
+    };
+    Entity::try_from_bits((u64::from(generation) << 32) | u64::from(index)).ok()
+}
+
+/// Split an arg of the form `@selector(argument)`
+fn parse_selector(arg: &str) -> Option<(&str, &str)> {
+    let (name, rest) = arg.strip_prefix('@')?.split_once('(')?;
+    let argument = rest.strip_suffix(')')?;
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    valid.then_some((name, argument))
+}
+
+/// Replace the selectors in args with the entities they select
+pub(crate) fn resolve_args(world: &mut World, args: &mut Vec<String>) -> Result<(), CliError> {
+    if !args.iter().any(|arg| parse_selector(arg).is_some()) {
+        return Ok(());
+    }
+    let mut resolved = Vec::with_capacity(args.len());
+    for arg in args.drain(..) {
+        let Some((name, argument)) = parse_selector(&arg) else {
+            resolved.push(arg);
+            continue;
+        };
+        let selector = world
+            .get_resource_or_insert_with(CliSelectors::default)
+            .selectors
+            .get(name)
+            .cloned();
+        let error = |message: String| CliError::Selector {
+            selector: arg.clone(),
+            message,
+        };
+        let Some(selector) = selector else {
+            return Err(error("unknown selector".to_owned()));
+        };
+        let entities = selector(argument, world).map_err(error)?;
+        if entities.is_empty() {
+            return Err(error("no entities selected".to_owned()));
+        }
+        resolved.extend(entities.iter().map(|entity| entity.to_string()));
+    }
+    *args = resolved;
+    Ok(())
+}
+
+/// `@id(42)`: the entity with an index (and generation)
+fn select_id(argument: &str, world: &mut World) -> Result<Vec<Entity>, String> {
+    let entity = match argument.parse::<u32>() {
+        Ok(index) => world.entities().resolve_from_id(index),
+        Err(_) => parse_cli_entity(argument),
+    };
+    let Some(entity) = entity else {
+        return Err(format!("invalid entity id {:?}", argument));
+    };
+    match world.get_entity(entity) {
+        Ok(_) => Ok(vec![entity]),
+        Err(_) => Err(format!("entity {} does not exist", entity)),
+    }
+}
+
+/// `@name(Player)`: the entities with a name
+fn select_name(argument: &str, world: &mut World) -> Result<Vec<Entity>, String> {
+    let mut q = world.query::<(Entity, &Name)>();
+    Ok(q.iter(world)
+        .filter(|(_, name)| name.as_str() == argument)
+        .map(|(entity, _)| entity)
+        .collect())
+}
+
+/// `@with(Enemy)`: the entities with a component, by its short or full type name
+fn select_with(argument: &str, world: &mut World) -> Result<Vec<Entity>, String> {
+    let components: Vec<_> = world
+        .components()
+        .iter()
+        .filter(|info| info.name() == argument || ShortName(info.name()).to_string() == argument)
+        .map(|info| info.id())
+        .collect();
+    if components.is_empty() {
+        return Err(format!("unknown component {:?}", argument));
+    }
+    let mut entities: Vec<Entity> = world
+        .archetypes()
+        .iter()
+        .filter(|archetype| components.iter().any(|&id| archetype.contains(id)))
+        .flat_map(|archetype| archetype.entities().iter().map(|entity| entity.id()))
+        .collect();
+    entities.sort_unstable();
+    Ok(entities)
+}
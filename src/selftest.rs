@@ -0,0 +1,173 @@
+//! Self-tests: quick smoke checks of the available "console commands".
+//!
+//! Requires the `selftest` cargo feature (enabled by default).
+//!
+//! Commands can register command strings that are safe to run at any time,
+//! along with the outcome they are expected to have, using
+//! [`add_clicommand_selftest`](CliSelfTestRegisterExt::add_clicommand_selftest).
+//! The built-in `selftest [pattern]` command runs them all (or those of the
+//! commands matching a wildcard pattern), and reports the ones that did not
+//! have the expected outcome.
+//!
+//! ```rust,ignore
+//! app.register_clicommand_args("spawn", spawn)
+//!     .add_clicommand_selftest("spawn", "spawn 0 0", CliSelfTestExpect::Success)
+//!     .add_clicommand_selftest("spawn", "spawn nope", CliSelfTestExpect::Failure);
+//! ```
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+
+use crate::glob::glob_match;
+use crate::output::{cli_error, cli_println, CliCommandsOutputExt};
+use crate::status::cli_fail;
+use crate::table::{CliAlign, CliTable};
+use crate::CliCommandsRegisterExt;
+
+/// The outcome a self-test expects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliSelfTestExpect {
+    /// The command string succeeds
+    Success,
+    /// The command string fails
+    Failure,
+    /// The command string succeeds, and its output contains the given text
+    OutputContains(String),
+}
+
+/// A command string to run, and the outcome it is expected to have
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliSelfTest {
+    pub cli: String,
+    pub expect: CliSelfTestExpect,
+}
+
+/// Stores the self-tests of all commands
+#[derive(Resource, Default, Debug, Clone)]
+pub struct CliSelfTests {
+    tests: BTreeMap<String, Vec<CliSelfTest>>,
+}
+
+impl CliSelfTests {
+    /// Add a self-test for a command
+    pub fn add(&mut self, command: &str, test: CliSelfTest) {
+        self.tests.entry(command.to_owned()).or_default().push(test);
+    }
+    /// Remove all self-tests of a command
+    pub fn clear_command(&mut self, command: &str) {
+        self.tests.remove(command);
+    }
+    /// Iterate over all self-tests, with the names of their commands, in order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &CliSelfTest)> {
+        self.tests
+            .iter()
+            .flat_map(|(name, tests)| tests.iter().map(move |test| (name.as_str(), test)))
+    }
+}
+
+/// Provides methods for adding self-tests to "console commands"
+pub trait CliSelfTestRegisterExt {
+    /// Add a self-test for a "console command"
+    ///
+    /// `cli` must be safe to run at any time, as `selftest` runs it
+    /// immediately. A command can have any number of self-tests.
+    fn add_clicommand_selftest(
+        &mut self,
+        name: &str,
+        cli: &str,
+        expect: CliSelfTestExpect,
+    ) -> &mut Self;
+}
+
+impl CliSelfTestRegisterExt for World {
+    fn add_clicommand_selftest(
+        &mut self,
+        name: &str,
+        cli: &str,
+        expect: CliSelfTestExpect,
+    ) -> &mut Self {
+        self.get_resource_or_insert_with(CliSelfTests::default).add(
+            name,
+            CliSelfTest {
+                cli: cli.to_owned(),
+                expect,
+            },
+        );
+        self
+    }
+}
+
+impl CliSelfTestRegisterExt for App {
+    fn add_clicommand_selftest(
+        &mut self,
+        name: &str,
+        cli: &str,
+        expect: CliSelfTestExpect,
+    ) -> &mut Self {
+        self.world_mut().add_clicommand_selftest(name, cli, expect);
+        self
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliSelfTests>();
+    app.register_clicommand_args("selftest", selftest)
+        .set_clicommand_description("selftest", "Run the self-tests of commands")
+        .set_clicommand_usage("selftest", "selftest [pattern]");
+}
+
+/// `selftest [pattern]`: run the self-tests of all commands matching a wildcard pattern
+fn selftest(In(args): In<Vec<String>>, world: &mut World) {
+    let pattern = match args.as_slice() {
+        [] => "*",
+        [pattern] => pattern.as_str(),
+        _ => {
+            cli_error(world, "Usage: selftest [pattern]");
+            cli_fail(world);
+            return;
+        }
+    };
+    let tests: Vec<(String, CliSelfTest)> = world
+        .get_resource::<CliSelfTests>()
+        .map(|tests| {
+            tests
+                .iter()
+                .filter(|(name, _)| glob_match(pattern, name))
+                .map(|(name, test)| (name.to_owned(), test.clone()))
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut table = CliTable::new()
+        .with_column("COMMAND", CliAlign::Left)
+        .with_column("TEST", CliAlign::Left)
+        .with_column("PROBLEM", CliAlign::Left);
+    let mut failed = 0;
+    for (name, test) in &tests {
+        let result = world.try_run_cli(&test.cli);
+        let problem = match (&test.expect, result) {
+            (CliSelfTestExpect::Success, Ok(_)) => None,
+            (CliSelfTestExpect::Failure, Err(_)) => None,
+            (CliSelfTestExpect::OutputContains(text), Ok(output)) => {
+                let found = output.iter().any(|line| line.text.contains(text.as_str()));
+                (!found).then(|| format!("output does not contain {:?}", text))
+            }
+            (CliSelfTestExpect::Failure, Ok(_)) => Some("expected failure".to_owned()),
+            (_, Err(e)) => Some(e.to_string()),
+        };
+        if let Some(problem) = problem {
+            failed += 1;
+            table.add_row([name.clone(), test.cli.clone(), problem]);
+        }
+    }
+    if failed > 0 {
+        cli_println(world, table.to_string());
+    }
+    cli_println(
+        world,
+        format!("{} passed, {} failed", tests.len() - failed, failed),
+    );
+    if failed > 0 {
+        cli_fail(world);
+    }
+}
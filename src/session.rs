@@ -0,0 +1,457 @@
+//! Independent "console sessions", for building in-game terminals.
+//!
+//! A [`CliSession`] is a component holding the state of one terminal: the
+//! line currently being typed and the history of submitted lines. You can
+//! have as many as you like (for example, one per diegetic terminal entity
+//! in your game world), all running commands from the same [`CliCommands`](crate::CliCommands).
+//!
+//! Sessions can be restricted to a subset of the available commands, for
+//! example for an in-game hacking minigame terminal that should only see
+//! `scan`, `connect`, and `decrypt`.
+//!
+//! Variables set with `set` and aliases defined with `alias` from a session
+//! (or from a script run from it) only exist in that session. They shadow
+//! the global variables of the [`CliEnv`](crate::env::CliEnv) and the
+//! global aliases with the same names, so that terminals and scripts do not
+//! change shared state. `export <name>` promotes a session variable to a
+//! global one:
+//!
+//! ```text
+//! set target 4v2      # only in this session
+//! export target       # now global, for every session
+//! ```
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+
+#[cfg(any(feature = "console", feature = "egui"))]
+use crate::completion::CliCompletion;
+use crate::namespace::NAMESPACE_SEPARATOR;
+use crate::output::cli_error;
+use crate::prompt::{CliAnswerPromptCommand, CliPrompt};
+use crate::quota::with_cli_source;
+use crate::resolve::{resolve_command, CliResolution};
+use crate::source::CliSource;
+use crate::status::{self, CliStatus};
+use crate::template::run_template;
+use crate::{parse_cli_chain, CliCommands, CliCommandsRunExt};
+
+/// The state of an interactive terminal for entering "console commands"
+#[derive(Component, Debug, Clone)]
+pub struct CliSession {
+    input: String,
+    history: VecDeque<String>,
+    history_cursor: Option<usize>,
+    max_history: usize,
+    allowlist: Option<CliAllowlist>,
+    last_failed: Option<String>,
+    procs: HashMap<String, String>,
+    vars: HashMap<String, String>,
+    aliases: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct CliAllowlist {
+    commands: HashSet<String>,
+    namespaces: HashSet<String>,
+}
+
+impl Default for CliSession {
+    fn default() -> Self {
+        CliSession::new(100)
+    }
+}
+
+impl CliSession {
+    /// Create a new session, remembering up to `max_history` submitted lines
+    pub fn new(max_history: usize) -> Self {
+        CliSession {
+            input: String::new(),
+            history: VecDeque::new(),
+            history_cursor: None,
+            max_history,
+            allowlist: None,
+            last_failed: None,
+            procs: HashMap::new(),
+            vars: HashMap::new(),
+            aliases: HashMap::new(),
+        }
+    }
+    /// Restrict the session to only allow the given commands
+    ///
+    /// Can be combined with [`with_allowed_namespaces`](Self::with_allowed_namespaces).
+    /// Any other commands will be treated as if they did not exist.
+    pub fn with_allowed_commands<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let allowlist = self.allowlist.get_or_insert_with(Default::default);
+        allowlist.commands.extend(names.into_iter().map(Into::into));
+        self
+    }
+    /// Restrict the session to only allow commands in the given namespaces
+    ///
+    /// Nested namespaces are included. Can be combined with
+    /// [`with_allowed_commands`](Self::with_allowed_commands).
+    /// Any other commands will be treated as if they did not exist.
+    pub fn with_allowed_namespaces<I, S>(mut self, namespaces: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let allowlist = self.allowlist.get_or_insert_with(Default::default);
+        allowlist
+            .namespaces
+            .extend(namespaces.into_iter().map(Into::into));
+        self
+    }
+    /// Is this session allowed to see and run the given command?
+    pub fn allows_command(&self, clicommands: &CliCommands, name: &str) -> bool {
+        let Some(allowlist) = &self.allowlist else {
+            return true;
+        };
+        if allowlist.commands.contains(name) {
+            return true;
+        }
+        let Some(namespace) = clicommands.command_namespace(name) else {
+            return false;
+        };
+        allowlist.namespaces.iter().any(|allowed| {
+            namespace == allowed.as_str()
+                || namespace
+                    .strip_prefix(allowed.as_str())
+                    .is_some_and(|rest| rest.starts_with(NAMESPACE_SEPARATOR))
+        })
+    }
+    /// The line currently being typed
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+    /// Edit the line currently being typed
+    pub fn input_mut(&mut self) -> &mut String {
+        &mut self.input
+    }
+    /// Previously submitted lines, oldest first
+    pub fn history(&self) -> impl Iterator<Item = &str> {
+        self.history.iter().map(|s| s.as_str())
+    }
+    /// Replace the input with the previous (older) line from the history
+    pub fn history_prev(&mut self) {
+        let cursor = match self.history_cursor {
+            None => self.history.len().checked_sub(1),
+            Some(i) => Some(i.saturating_sub(1)),
+        };
+        if let Some(i) = cursor {
+            self.history_cursor = Some(i);
+            self.input = self.history[i].clone();
+        }
+    }
+    /// Replace the input with the next (newer) line from the history
+    ///
+    /// Going past the newest line clears the input.
+    pub fn history_next(&mut self) {
+        let Some(i) = self.history_cursor else {
+            return;
+        };
+        if i + 1 < self.history.len() {
+            self.history_cursor = Some(i + 1);
+            self.input = self.history[i + 1].clone();
+        } else {
+            self.history_cursor = None;
+            self.input.clear();
+        }
+    }
+    /// The last command string run in this session that failed, if any
+    ///
+    /// This is what the built-in `retry` command runs.
+    pub fn last_failed(&self) -> Option<&str> {
+        self.last_failed.as_deref()
+    }
+    /// Define a procedure that can be called like a command, only in this session
+    ///
+    /// The body is a command template (see
+    /// [`register_clicommand_template`](crate::CliCommandsRegisterExt::register_clicommand_template)).
+    /// Procedures take priority over commands with the same name.
+    /// Scripts run from the session define their (non-exported) `proc`s here.
+    pub fn define_proc(&mut self, name: &str, body: &str) {
+        self.procs.insert(name.to_owned(), body.to_owned());
+    }
+    /// Remove a procedure, returning its body
+    pub fn remove_proc(&mut self, name: &str) -> Option<String> {
+        self.procs.remove(name)
+    }
+    /// Get the body of a procedure defined in this session
+    pub fn get_proc(&self, name: &str) -> Option<&str> {
+        self.procs.get(name).map(|s| s.as_str())
+    }
+    /// Iterate over the procedures defined in this session, and their bodies
+    pub fn iter_procs(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.procs.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+    /// Set a variable, only in this session
+    ///
+    /// Session variables shadow the global variables of the
+    /// [`CliEnv`](crate::env::CliEnv) with the same names. The built-in
+    /// `set` command sets them, when run from the session.
+    pub fn set_var(&mut self, name: &str, value: &str) {
+        self.vars.insert(name.to_owned(), value.to_owned());
+    }
+    /// Get the value of a variable of this session
+    pub fn get_var(&self, name: &str) -> Option<&str> {
+        self.vars.get(name).map(|s| s.as_str())
+    }
+    /// Remove a variable of this session, returning its value
+    pub fn remove_var(&mut self, name: &str) -> Option<String> {
+        self.vars.remove(name)
+    }
+    /// Iterate over the variables of this session, and their values
+    pub fn iter_vars(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.vars.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+    /// Add an alias, only in this session
+    ///
+    /// Session aliases shadow global aliases (see
+    /// [`CliCommands::add_alias`]) with the same names. The built-in `alias`
+    /// command adds them, when run from the session.
+    pub fn add_alias(&mut self, alias: &str, expansion: &str) {
+        self.aliases.insert(alias.to_owned(), expansion.to_owned());
+    }
+    /// Get the expansion of an alias of this session
+    pub fn get_alias(&self, alias: &str) -> Option<&str> {
+        self.aliases.get(alias).map(|s| s.as_str())
+    }
+    /// Remove an alias of this session, returning its expansion
+    pub fn remove_alias(&mut self, alias: &str) -> Option<String> {
+        self.aliases.remove(alias)
+    }
+    /// Iterate over the aliases of this session, and their expansions
+    pub fn iter_aliases(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.aliases.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+    /// Take the current input line, clearing it and adding it to the history
+    ///
+    /// Returns `None` if the input is empty.
+    pub fn submit(&mut self) -> Option<String> {
+        self.history_cursor = None;
+        let line = std::mem::take(&mut self.input);
+        if line.trim().is_empty() {
+            return None;
+        }
+        if self.history.back() != Some(&line) {
+            self.history.push_back(line.clone());
+        }
+        while self.history.len() > self.max_history {
+            self.history.pop_front();
+        }
+        Some(line)
+    }
+    /// Take the current input line, clearing it, without adding it to the history
+    ///
+    /// For answering a [`CliPrompt`](crate::prompt::CliPrompt); the answer
+    /// may be empty.
+    pub fn take_input(&mut self) -> String {
+        self.history_cursor = None;
+        std::mem::take(&mut self.input)
+    }
+}
+
+/// The result of tab-completing the input line of a [`CliSession`]
+#[cfg(any(feature = "console", feature = "egui"))]
+pub(crate) struct CliSessionCompletion {
+    /// The input line with the completion applied
+    pub input: String,
+    /// The candidates by category, if there was more than one
+    ///
+    /// Only command names have categories (see
+    /// [`CliCommands::command_category`]), other candidates are all in `None`.
+    pub candidates: Vec<(Option<String>, Vec<String>)>,
+}
+
+/// Complete the input line of the [`CliSession`] on an entity
+///
+/// With several candidates, their common prefix is applied. Commands the
+/// session cannot run are not offered.
+#[cfg(any(feature = "console", feature = "egui"))]
+pub(crate) fn complete_in_session(world: &World, entity: Entity) -> Option<CliSessionCompletion> {
+    let (Some(session), Some(clicommands)) = (
+        world.get::<CliSession>(entity),
+        world.get_resource::<CliCommands>(),
+    ) else {
+        return None;
+    };
+    let line = session.input();
+    let completions: Vec<CliCompletion> = clicommands
+        .complete(world, line)
+        .into_iter()
+        .filter(|completion| {
+            // Don't reveal commands the session cannot run
+            let is_arg = line
+                .get(..completion.span.start)
+                .is_some_and(|before| !before.trim().is_empty());
+            is_arg || session.allows_command(clicommands, &completion.replacement)
+        })
+        .collect();
+    match completions.as_slice() {
+        [] => None,
+        [only] => Some(CliSessionCompletion {
+            input: format!("{} ", only.apply(line)),
+            candidates: vec![],
+        }),
+        [first, ..] => {
+            let completing_command = line
+                .get(..first.span.start)
+                .is_some_and(|before| before.trim().is_empty());
+            let common = completions
+                .iter()
+                .map(|completion| completion.replacement.as_str())
+                .fold(first.replacement.as_str(), common_prefix);
+            let input = CliCompletion {
+                replacement: common.to_owned(),
+                ..first.clone()
+            }
+            .apply(line);
+            let names = completions
+                .iter()
+                .map(|completion| completion.replacement.as_str());
+            let candidates = match completing_command {
+                true => clicommands
+                    .group_by_category(names)
+                    .into_iter()
+                    .map(|(category, names)| {
+                        let names = names.into_iter().map(str::to_owned).collect();
+                        (category.map(str::to_owned), names)
+                    })
+                    .collect(),
+                false => vec![(None, names.map(str::to_owned).collect())],
+            };
+            Some(CliSessionCompletion { input, candidates })
+        }
+    }
+}
+
+#[cfg(any(feature = "console", feature = "egui"))]
+fn common_prefix<'a>(a: &'a str, b: &str) -> &'a str {
+    let len = a
+        .char_indices()
+        .zip(b.chars())
+        .find(|((_, ca), cb)| ca != cb)
+        .map_or(a.len().min(b.len()), |((i, _), _)| i);
+    &a[..len]
+}
+
+/// Command to submit the input line of the [`CliSession`] on an entity, and run it
+///
+/// If a [`CliPrompt`] is waiting, the line is its answer instead.
+pub struct CliSubmitSessionCommand(pub Entity);
+
+impl bevy::ecs::world::Command for CliSubmitSessionCommand {
+    fn apply(self, world: &mut World) {
+        let pending = world
+            .get_resource::<CliPrompt>()
+            .is_some_and(|prompt| prompt.is_pending());
+        let Some(mut session) = world.get_mut::<CliSession>(self.0) else {
+            error!("Entity {} does not have a CliSession!", self.0);
+            return;
+        };
+        if pending {
+            let answer = session.take_input();
+            CliAnswerPromptCommand(answer).apply(world);
+            return;
+        }
+        let Some(line) = session.submit() else {
+            return;
+        };
+        run_in_session(world, self.0, &line);
+    }
+}
+
+/// Command to run a command string as if it was entered in the [`CliSession`] on an entity
+///
+/// The session's restrictions apply, but its input and history are not affected.
+pub struct CliRunInSessionCommand {
+    pub session: Entity,
+    pub cli: String,
+}
+
+impl bevy::ecs::world::Command for CliRunInSessionCommand {
+    fn apply(self, world: &mut World) {
+        run_in_session(world, self.session, &self.cli);
+    }
+}
+
+fn run_in_session(world: &mut World, entity: Entity, line: &str) {
+    let source = CliSource::from(format!("session:{}", entity));
+    run_in_session_as(world, entity, line, source);
+}
+
+/// Run a command string in the [`CliSession`] on an entity, on behalf of a source
+pub(crate) fn run_in_session_as(world: &mut World, entity: Entity, line: &str, source: CliSource) {
+    if let Ok(chain) = parse_cli_chain(line) {
+        let denied = match (
+            world.get::<CliSession>(entity),
+            world.get_resource::<CliCommands>(),
+        ) {
+            (Some(session), Some(clicommands)) => chain.links().iter().find(|link| {
+                match resolve_command(world, link.command.name()) {
+                    CliResolution::Found(name) => !session.allows_command(clicommands, &name),
+                    // Let `run_cli` report the problem
+                    _ => false,
+                }
+            }),
+            _ => chain.links().first(),
+        };
+        if let Some(link) = denied {
+            let message = format!(
+                "CliCommand {:?} is not allowed in this session!",
+                link.command.name()
+            );
+            cli_error(world, message);
+            status::record(world, line, false);
+            if let Some(mut session) = world.get_mut::<CliSession>(entity) {
+                session.last_failed = Some(line.to_owned());
+            }
+            return;
+        }
+    }
+    let prev_session = world
+        .get_resource_or_insert_with(CliStatus::default)
+        .session
+        .replace(entity);
+    with_cli_source(world, source, |world| {
+        world.run_cli(line);
+    });
+    let mut status = world.resource_mut::<CliStatus>();
+    status.session = prev_session;
+    let failed_line = match status.last_success() {
+        Some(false) => status.last_failed_line().map(|line| line.to_owned()),
+        _ => None,
+    };
+    if let (Some(line), Some(mut session)) = (failed_line, world.get_mut::<CliSession>(entity)) {
+        session.last_failed = Some(line);
+    }
+}
+
+/// The session the command string is run from, if any
+pub(crate) fn current_session(world: &World) -> Option<&CliSession> {
+    let entity = world.get_resource::<CliStatus>()?.session?;
+    world.get::<CliSession>(entity)
+}
+
+/// Get the expansion of an alias, of the session the command string is run
+/// from, or else a global one
+pub(crate) fn get_alias<'w>(world: &'w World, alias: &str) -> Option<&'w str> {
+    current_session(world)
+        .and_then(|session| session.get_alias(alias))
+        .or_else(|| world.get_resource::<CliCommands>()?.get_alias(alias))
+}
+
+/// Run a procedure of the session the command string is run from, if it has one
+///
+/// Returns `None` if there is no such procedure, otherwise whether it succeeded.
+pub(crate) fn run_session_proc(world: &mut World, name: &str, args: &[String]) -> Option<bool> {
+    let body = current_session(world)?.get_proc(name)?.to_owned();
+    Some(run_template(world, name, &body, args))
+}
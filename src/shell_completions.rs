@@ -0,0 +1,65 @@
+//! Generating completion scripts for operating system shells.
+//!
+//! Useful for external tools that send commands to your game (such as
+//! a remote console client), so that command names can be completed
+//! on the operator's terminal.
+
+use crate::CliCommands;
+
+/// Shells that completion scripts can be generated for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CliShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl CliCommands {
+    /// Generate a completion script for the given shell
+    ///
+    /// `bin_name` is the name of the executable that accepts command strings
+    /// as its arguments. The script completes the names of all registered commands.
+    pub fn shell_completions(&self, shell: CliShell, bin_name: &str) -> String {
+        let names = self.sorted_names().join(" ");
+        match shell {
+            CliShell::Bash => {
+                let function = format!("_{}", sanitize_identifier(bin_name));
+                format!(
+                    "{function}() {{\n    \
+                        local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"\n    \
+                        if [ \"$COMP_CWORD\" -eq 1 ]; then\n        \
+                            COMPREPLY=( $(compgen -W \"{names}\" -- \"$cur\") )\n    \
+                        fi\n\
+                    }}\n\
+                    complete -F {function} {bin_name}\n"
+                )
+            }
+            CliShell::Zsh => {
+                format!("#compdef {bin_name}\n\n_arguments '1:command:({names})'\n")
+            }
+            CliShell::Fish => {
+                format!("complete -c {bin_name} -f -n '__fish_use_subcommand' -a '{names}'\n")
+            }
+        }
+    }
+    /// Generate a plain text list of all registered commands, one per line, sorted
+    pub fn commands_manifest(&self) -> String {
+        let mut manifest = String::new();
+        for name in self.sorted_names() {
+            manifest.push_str(name);
+            manifest.push('\n');
+        }
+        manifest
+    }
+    fn sorted_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.iter_names().collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+fn sanitize_identifier(s: &str) -> String {
+    s.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
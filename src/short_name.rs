@@ -0,0 +1,36 @@
+//! Type names without their module paths, for showing to the user
+
+use std::fmt;
+
+/// Displays a type name with the module paths stripped from every part
+///
+/// For example, `bevy_transform::components::transform::Transform` is shown
+/// as `Transform`, and `core::option::Option<alloc::string::String>` as
+/// `Option<String>`.
+pub(crate) struct ShortName<'a>(pub &'a str);
+
+impl ShortName<'static> {
+    /// The short name of the type `T`
+    pub fn of<T: ?Sized>() -> Self {
+        ShortName(std::any::type_name::<T>())
+    }
+}
+
+impl fmt::Display for ShortName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut rest = self.0;
+        while !rest.is_empty() {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+                .unwrap_or(rest.len());
+            let path = &rest[..end];
+            f.write_str(path.rsplit("::").next().unwrap_or(path))?;
+            let Some(delimiter) = rest[end..].chars().next() else {
+                break;
+            };
+            fmt::Write::write_char(f, delimiter)?;
+            rest = &rest[end + delimiter.len_utf8()..];
+        }
+        Ok(())
+    }
+}
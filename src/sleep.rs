@@ -0,0 +1,261 @@
+//! Pausing sequences of "console commands" with the built-in `sleep` command.
+//!
+//! `sleep <duration> [--real]` delays the rest of the sequence it is part
+//! of (a script, or a chain like `spawn; sleep 2; despawn`), without
+//! blocking the frame. The remaining command strings are queued and run
+//! once the time has passed, with the same source, if the sequence has one
+//! (see [`source`](crate::source)).
+//!
+//! By default, the duration is measured in virtual time, so it is affected
+//! by pausing and changing the timescale. With `--real`, wall-clock time is
+//! used instead. The duration is in seconds, optionally with an `s` or `ms`
+//! suffix (`1.5`, `1.5s`, `1500ms`).
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::Instant;
+
+use crate::output::cli_error;
+use crate::quota::{current_cli_source, with_deferred_cli_source};
+use crate::source::CliSource;
+use crate::status::{cli_fail, is_returning, run_returning};
+use crate::{CliCommandsRegisterExt, CliCommandsRunExt};
+
+/// When a sleeping sequence of commands should resume
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CliWake {
+    /// When the elapsed virtual time reaches this value
+    Virtual(Duration),
+    /// At this wall-clock time
+    Real(Instant),
+}
+
+/// Holds sequences of command strings waiting for a `sleep` to end
+#[derive(Resource, Default)]
+pub struct CliSleepQueue {
+    sleeping: Vec<CliSleeping>,
+    requested: Option<CliWake>,
+}
+
+/// A sequence of command strings waiting for a `sleep` to end
+struct CliSleeping {
+    wake: CliWake,
+    clis: Vec<String>,
+    /// The source of the sequence, which the rest of it runs with
+    source: Option<CliSource>,
+}
+
+impl CliSleepQueue {
+    /// Iterate over the waiting sequences of command strings
+    pub fn iter(&self) -> impl Iterator<Item = (CliWake, &[String])> {
+        self.sleeping
+            .iter()
+            .map(|sleeping| (sleeping.wake, sleeping.clis.as_slice()))
+    }
+    /// How many command strings are waiting
+    pub fn len(&self) -> usize {
+        self.sleeping
+            .iter()
+            .map(|sleeping| sleeping.clis.len())
+            .sum()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.sleeping.is_empty()
+    }
+    /// Remove all waiting command strings
+    pub fn clear(&mut self) {
+        self.sleeping.clear();
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliSleepQueue>();
+    app.add_systems(PreUpdate, wake_sleeping);
+    app.register_clicommand_args("sleep", sleep)
+        .set_clicommand_description("sleep", "Delay the rest of a script or chain")
+        .set_clicommand_usage("sleep", "sleep <duration> [--real]");
+}
+
+/// Run a sequence of command strings in order, honoring `sleep` and `return`
+pub(crate) fn run_cli_lines(world: &mut World, lines: Vec<String>) {
+    run_returning(world, |world| run_cli_lines_inner(world, lines));
+}
+
+fn run_cli_lines_inner(world: &mut World, lines: Vec<String>) {
+    let mut lines = lines.into_iter();
+    while let Some(line) = lines.next() {
+        clear_sleep_request(world);
+        world.run_cli(&line);
+        if is_returning(world) {
+            return;
+        }
+        let source = current_cli_source(world);
+        let Some(mut queue) = world.get_resource_mut::<CliSleepQueue>() else {
+            continue;
+        };
+        if let Some(wake) = queue.requested.take() {
+            let clis: Vec<String> = lines.collect();
+            if !clis.is_empty() {
+                queue.sleeping.push(CliSleeping { wake, clis, source });
+            }
+            return;
+        }
+    }
+}
+
+pub(crate) fn clear_sleep_request(world: &mut World) {
+    if let Some(mut queue) = world.get_resource_mut::<CliSleepQueue>() {
+        queue.requested = None;
+    }
+}
+
+pub(crate) fn is_sleep_requested(world: &World) -> bool {
+    world
+        .get_resource::<CliSleepQueue>()
+        .is_some_and(|queue| queue.requested.is_some())
+}
+
+/// Queue a command string to run when the requested `sleep` is over
+///
+/// The request is kept, so that any enclosing sequence is delayed too.
+pub(crate) fn defer_until_wake(world: &mut World, cli: String) {
+    let source = current_cli_source(world);
+    let mut queue = world.resource_mut::<CliSleepQueue>();
+    if let Some(wake) = queue.requested {
+        queue.sleeping.push(CliSleeping {
+            wake,
+            clis: vec![cli],
+            source,
+        });
+    }
+}
+
+fn wake_sleeping(world: &mut World) {
+    let virtual_elapsed = virtual_elapsed(world);
+    let now = Instant::now();
+    let sleeping = std::mem::take(&mut world.resource_mut::<CliSleepQueue>().sleeping);
+    let (awake, still_sleeping): (Vec<_>, Vec<_>) =
+        sleeping
+            .into_iter()
+            .partition(|sleeping| match sleeping.wake {
+                CliWake::Virtual(at) => virtual_elapsed >= at,
+                CliWake::Real(at) => now >= at,
+            });
+    world
+        .resource_mut::<CliSleepQueue>()
+        .sleeping
+        .extend(still_sleeping);
+    for sleeping in awake {
+        with_deferred_cli_source(world, sleeping.source, |world| {
+            run_cli_lines(world, sleeping.clis)
+        });
+    }
+}
+
+fn virtual_elapsed(world: &World) -> Duration {
+    world
+        .get_resource::<Time<Virtual>>()
+        .map_or(Duration::ZERO, |time| time.elapsed())
+}
+
+pub(crate) fn parse_duration(s: &str) -> Option<Duration> {
+    let secs = if let Some(ms) = s.strip_suffix("ms") {
+        ms.parse::<f32>().ok()? / 1000.0
+    } else {
+        s.strip_suffix('s').unwrap_or(s).parse::<f32>().ok()?
+    };
+    Duration::try_from_secs_f32(secs).ok()
+}
+
+/// `sleep <duration> [--real]`: delay the rest of the current sequence of commands
+fn sleep(In(args): In<Vec<String>>, world: &mut World) {
+    let (duration, real) = match args.as_slice() {
+        [duration] => (duration, false),
+        [duration, flag] if flag == "--real" => (duration, true),
+        [flag, duration] if flag == "--real" => (duration, true),
+        _ => {
+            cli_error(world, "Usage: sleep <duration> [--real]");
+            cli_fail(world);
+            return;
+        }
+    };
+    let Some(duration) = parse_duration(duration) else {
+        cli_error(world, format!("Invalid duration: {:?}", duration));
+        cli_fail(world);
+        return;
+    };
+    let wake = if real {
+        CliWake::Real(Instant::now() + duration)
+    } else {
+        CliWake::Virtual(virtual_elapsed(world) + duration)
+    };
+    world
+        .get_resource_or_insert_with(CliSleepQueue::default)
+        .requested = Some(wake);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CliPlugin;
+
+    #[derive(Resource, Default)]
+    struct Marks(Vec<String>);
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.init_resource::<Marks>();
+        app.register_clicommand_args(
+            "mark",
+            |In(args): In<Vec<String>>, mut marks: ResMut<Marks>| marks.0.extend(args),
+        );
+        app
+    }
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|&line| line.to_owned()).collect()
+    }
+
+    fn advance(world: &mut World, millis: u64) {
+        world
+            .resource_mut::<Time<Virtual>>()
+            .advance_by(Duration::from_millis(millis));
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("1.5"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("2s"), Some(Duration::from_secs(2)));
+        assert_eq!(parse_duration("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_duration("-1"), None);
+        assert_eq!(parse_duration("soon"), None);
+    }
+
+    #[test]
+    fn sleep_delays_the_rest_of_the_lines() {
+        let mut app = app();
+        let world = app.world_mut();
+        run_cli_lines(world, lines(&["mark a", "sleep 1", "mark b", "mark c"]));
+        assert_eq!(world.resource::<Marks>().0, ["a"]);
+        assert_eq!(world.resource::<CliSleepQueue>().len(), 2);
+
+        advance(world, 500);
+        wake_sleeping(world);
+        assert_eq!(world.resource::<Marks>().0, ["a"]);
+        advance(world, 500);
+        wake_sleeping(world);
+        assert_eq!(world.resource::<Marks>().0, ["a", "b", "c"]);
+        assert!(world.resource::<CliSleepQueue>().is_empty());
+    }
+
+    #[test]
+    fn invalid_sleep_does_not_delay() {
+        let mut app = app();
+        let world = app.world_mut();
+        run_cli_lines(world, lines(&["mark a", "sleep soon", "mark b"]));
+        assert_eq!(world.resource::<Marks>().0, ["a", "b"]);
+        assert!(world.resource::<CliSleepQueue>().is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+//! Saving and restoring the registered "console commands".
+//!
+//! Useful for editor-like hosts with a "play mode": take a snapshot of the
+//! registry before entering play mode (where gameplay code may register
+//! more commands, or replace existing ones), and restore it when leaving.
+//!
+//! ```rust,ignore
+//! fn enter_play_mode(world: &mut World) {
+//!     let snapshot = world.resource::<CliCommands>().snapshot();
+//!     world.insert_resource(EditorCommands(snapshot));
+//!     // ... start the game, which registers its own commands ...
+//! }
+//!
+//! fn exit_play_mode(world: &mut World) {
+//!     let EditorCommands(snapshot) = world.remove_resource().unwrap();
+//!     CliCommands::restore(world, snapshot);
+//! }
+//! ```
+//!
+//! Restoring brings back the commands, aliases, fallback and reserved
+//! namespaces as they were when the snapshot was taken, including the
+//! metadata and run statistics of the commands. The systems that were
+//! registered after the snapshot are removed from the `World`. The
+//! [resolver](CliCommands::set_resolver) is not part of the snapshot.
+//!
+//! While a snapshot exists, the systems of its commands stay in the `World`,
+//! even if the commands are replaced or removed in the meantime (including by
+//! restoring another snapshot). They are removed at the end of the frame in
+//! which the snapshot is dropped (requires [`CliPlugin`](crate::CliPlugin)).
+
+use std::sync::Arc;
+
+use bevy::ecs::system::SystemId;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+
+use crate::{cleanup_removed_commands, CliCommandSystems, CliCommands};
+
+/// The state of the [`CliCommands`] registry at some point in time
+///
+/// Obtained from [`CliCommands::snapshot`].
+#[derive(Clone)]
+pub struct CliCommandsSnapshot {
+    commands: HashMap<String, CliCommandSystems>,
+    fallback: Option<SystemId<In<String>, ()>>,
+    aliases: HashMap<String, String>,
+    reserved: HashMap<String, Option<String>>,
+    /// The systems of the commands, kept in the `World` while this exists
+    _systems: Arc<HashSet<Entity>>,
+}
+
+impl CliCommandsSnapshot {
+    /// Iterate over the names of the commands in the snapshot
+    pub fn iter_names(&self) -> impl Iterator<Item = &str> {
+        self.commands.keys().map(|name| name.as_str())
+    }
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+impl CliCommands {
+    /// Save the registered commands, to [`restore`](CliCommands::restore) them later
+    pub fn snapshot(&self) -> CliCommandsSnapshot {
+        let systems = Arc::new(self.used_systems());
+        let mut snapshots = self.snapshots.lock().unwrap();
+        snapshots.retain(|systems| systems.strong_count() > 0);
+        snapshots.push(Arc::downgrade(&systems));
+        CliCommandsSnapshot {
+            commands: self.commands.clone(),
+            fallback: self.fallback,
+            aliases: self.aliases.clone(),
+            reserved: self.reserved.clone(),
+            _systems: systems,
+        }
+    }
+
+    /// Bring the registered commands back to the state of a snapshot
+    ///
+    /// Commands registered after the snapshot was taken are removed, and
+    /// their systems (and run conditions) are unregistered from the `World`.
+    pub fn restore(world: &mut World, snapshot: CliCommandsSnapshot) {
+        let mut clicommands = world.get_resource_or_insert_with(CliCommands::default);
+        let old_commands = std::mem::replace(&mut clicommands.commands, snapshot.commands);
+        let old_fallback = std::mem::replace(&mut clicommands.fallback, snapshot.fallback);
+        clicommands.aliases = snapshot.aliases;
+        clicommands.reserved = snapshot.reserved;
+
+        // Commands that were not replaced still use the same systems
+        for cmd in old_commands.values() {
+            clicommands.unused_systems.extend(cmd.system_entities());
+        }
+        clicommands
+            .unused_systems
+            .extend(old_fallback.map(|id| id.entity()));
+        clicommands.removed_names.extend(old_commands.into_keys());
+        cleanup_removed_commands(world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::CliCommandsOutputExt;
+    use crate::{CliCommandsRegisterExt, CliError, CliPlugin};
+
+    #[derive(Resource, Default)]
+    struct Ran(Vec<&'static str>);
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.init_resource::<Ran>();
+        app
+    }
+
+    fn system_entity(world: &World, name: &str) -> Entity {
+        let cmd = &world.resource::<CliCommands>().commands[name];
+        cmd.noargs.unwrap().entity()
+    }
+
+    #[test]
+    fn restore_removes_later_commands() {
+        let mut app = app();
+        app.register_clicommand_noargs("kept", |mut ran: ResMut<Ran>| ran.0.push("kept"));
+        let snapshot = app.world().resource::<CliCommands>().snapshot();
+        let kept = system_entity(app.world(), "kept");
+
+        app.register_clicommand_noargs("added", |mut ran: ResMut<Ran>| ran.0.push("added"));
+        let added = system_entity(app.world(), "added");
+        CliCommands::restore(app.world_mut(), snapshot);
+
+        assert!(app.try_run_cli("kept").is_ok());
+        assert!(matches!(
+            app.try_run_cli("added"),
+            Err(CliError::NotFound(_))
+        ));
+        assert_eq!(app.world().resource::<Ran>().0, ["kept"]);
+        assert!(app.world().get_entity(kept).is_ok());
+        assert!(app.world().get_entity(added).is_err());
+    }
+
+    #[test]
+    fn restore_brings_back_replaced_commands() {
+        let mut app = app();
+        app.register_clicommand_noargs("cmd", |mut ran: ResMut<Ran>| ran.0.push("old"));
+        let snapshot = app.world().resource::<CliCommands>().snapshot();
+
+        app.register_clicommand_noargs("cmd", |mut ran: ResMut<Ran>| ran.0.push("new"));
+        let new = system_entity(app.world(), "cmd");
+        assert!(app.try_run_cli("cmd").is_ok());
+        CliCommands::restore(app.world_mut(), snapshot);
+        assert!(app.try_run_cli("cmd").is_ok());
+
+        assert_eq!(app.world().resource::<Ran>().0, ["new", "old"]);
+        assert!(app.world().get_entity(new).is_err());
+    }
+
+    #[test]
+    fn snapshots_keep_their_systems() {
+        let mut app = app();
+        app.register_clicommand_noargs("cmd", |mut ran: ResMut<Ran>| ran.0.push("old"));
+        let before = app.world().resource::<CliCommands>().snapshot();
+        let old = system_entity(app.world(), "cmd");
+
+        app.register_clicommand_noargs("cmd", |mut ran: ResMut<Ran>| ran.0.push("new"));
+        let after = app.world().resource::<CliCommands>().snapshot();
+        let new = system_entity(app.world(), "cmd");
+
+        CliCommands::restore(app.world_mut(), before.clone());
+        assert!(app.try_run_cli("cmd").is_ok());
+        CliCommands::restore(app.world_mut(), after);
+        assert!(app.try_run_cli("cmd").is_ok());
+        assert_eq!(app.world().resource::<Ran>().0, ["old", "new"]);
+        assert!(app.world().get_entity(old).is_ok());
+
+        drop(before);
+        let current = app.world().resource::<CliCommands>().snapshot();
+        CliCommands::restore(app.world_mut(), current);
+        assert!(app.world().get_entity(old).is_err());
+        assert!(app.world().get_entity(new).is_ok());
+    }
+
+    #[test]
+    fn restore_after_removing_commands() {
+        let mut app = app();
+        app.register_clicommand_noargs("cmd", |mut ran: ResMut<Ran>| ran.0.push("cmd"));
+        app.with_clicommand_group("debug", |app| {
+            app.register_clicommand_noargs("draw", |mut ran: ResMut<Ran>| ran.0.push("draw"));
+        });
+        let snapshot = app.world().resource::<CliCommands>().snapshot();
+        let draw = system_entity(app.world(), "draw");
+
+        app.unregister_clicommand("cmd");
+        app.world_mut()
+            .resource_mut::<CliCommands>()
+            .remove_group("debug");
+        app.update();
+        assert!(app.world().get_entity(draw).is_ok());
+
+        CliCommands::restore(app.world_mut(), snapshot);
+        assert!(app.try_run_cli("cmd").is_ok());
+        assert!(app.try_run_cli("draw").is_ok());
+        assert_eq!(app.world().resource::<Ran>().0, ["cmd", "draw"]);
+    }
+
+    #[test]
+    fn systems_are_freed_once_snapshots_are_dropped() {
+        let mut app = app();
+        app.register_clicommand_noargs("cmd", |mut ran: ResMut<Ran>| ran.0.push("cmd"));
+        let snapshot = app.world().resource::<CliCommands>().snapshot();
+        let cmd = system_entity(app.world(), "cmd");
+
+        app.unregister_clicommand("cmd");
+        app.update();
+        assert!(app.world().get_entity(cmd).is_ok());
+
+        drop(snapshot);
+        app.update();
+        assert!(app.world().get_entity(cmd).is_err());
+    }
+}
@@ -0,0 +1,314 @@
+//! Knowing who ran a "console command".
+//!
+//! Every command string is run on behalf of a [`CliSource`]: a player on a
+//! multiplayer server, a remote console client, a script, or the local
+//! console. Frontends set the source with
+//! [`run_cli_from`](crate::quota::run_cli_from) or
+//! [`with_cli_source`](crate::quota::with_cli_source):
+//!
+//! ```rust,ignore
+//! fn chat_commands(world: &mut World) {
+//!     for (player, line) in world.resource_mut::<ChatInbox>().take_commands() {
+//!         run_cli_from(world, CliSource::Player(player), &line);
+//!     }
+//! }
+//! ```
+//!
+//! Commands can read the source from [`CliStatus::source`](crate::status::CliStatus::source),
+//! or from [`CliInvocation::source`](crate::args::CliInvocation::source).
+//! Commands run by other commands, and by scripts, keep the source of the
+//! command string that started them.
+//!
+//! The source decides:
+//!
+//!  - which permission levels may run, with [`CliSourcePermissions`]
+//!  - the quota that applies (see [`quota`](crate::quota)), by the name of
+//!    the source, like `player:5v1` or `remote:127.0.0.1:50123`
+//!  - where the output goes: output of commands run by players and remote
+//!    clients is kept out of the consoles, until taken with
+//!    [`CliOutputLog::drain_for`](crate::output::CliOutputLog::drain_for),
+//!    so a player's `kill` reply can be sent back to that player only.
+//!    This includes errors, and output that comes later, like that of
+//!    background [jobs](crate::jobs) started by the player
+//!
+//! ```rust,ignore
+//! let mut permissions = world.resource_mut::<CliSourcePermissions>();
+//! // Players may not cheat, even if cheats are enabled for the host
+//! permissions.set("player:*", &[CliPermission::Normal]);
+//!
+//! fn send_replies(mut log: ResMut<CliOutputLog>, players: Query<(Entity, &Connection)>) {
+//!     for (player, connection) in &players {
+//!         for line in log.drain_for(&CliSource::Player(player)) {
+//!             connection.send_chat(&line.text);
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::fmt;
+use std::net::SocketAddr;
+
+use bevy::prelude::*;
+
+use crate::glob::glob_match;
+use crate::permissions::CliPermission;
+
+/// Who or what ran a command string
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CliSource {
+    /// The local console, or the app itself
+    Local,
+    /// A player, on a multiplayer server
+    Player(Entity),
+    /// A remote console client (see [`remote`](crate::remote))
+    Remote(SocketAddr),
+    /// A script, by its asset path (see [`script`](crate::script))
+    Script(String),
+    /// Anything else, by name, like `bindings` or `chat:alice`
+    Named(String),
+}
+
+impl CliSource {
+    /// Whether the output of commands run by this source goes only to it
+    ///
+    /// True for players and remote console clients.
+    pub fn is_private(&self) -> bool {
+        matches!(self, CliSource::Player(_) | CliSource::Remote(_))
+    }
+}
+
+/// The name of the source, used for matching patterns
+impl fmt::Display for CliSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliSource::Local => f.write_str("local"),
+            CliSource::Player(entity) => write!(f, "player:{}", entity),
+            CliSource::Remote(addr) => write!(f, "remote:{}", addr),
+            CliSource::Script(path) => write!(f, "script:{}", path),
+            CliSource::Named(name) => f.write_str(name),
+        }
+    }
+}
+
+impl From<&str> for CliSource {
+    fn from(name: &str) -> Self {
+        CliSource::Named(name.to_owned())
+    }
+}
+
+impl From<String> for CliSource {
+    fn from(name: String) -> Self {
+        CliSource::Named(name)
+    }
+}
+
+/// Which permission levels each source may run
+///
+/// Applies in addition to [`CliPermissions`](crate::permissions::CliPermissions):
+/// a command runs only if both allow it. Sources that match no pattern, and
+/// command strings without a source, are not restricted.
+///
+/// Requires [`CliPlugin`](crate::CliPlugin).
+#[derive(Resource, Debug, Default, Clone)]
+pub struct CliSourcePermissions {
+    /// Patterns of source names, and the levels they may run
+    rules: Vec<(String, Vec<CliPermission>)>,
+}
+
+impl CliSourcePermissions {
+    /// Set the permission levels the sources matching a pattern may run
+    ///
+    /// If a source matches several patterns, the one set first applies.
+    pub fn set(&mut self, pattern: &str, allowed: &[CliPermission]) {
+        match self.rules.iter_mut().find(|(p, _)| p == pattern) {
+            Some((_, levels)) => *levels = allowed.to_vec(),
+            None => self.rules.push((pattern.to_owned(), allowed.to_vec())),
+        }
+    }
+    /// Set the permission levels of a pattern, before any other rule
+    #[cfg(any(feature = "remote", feature = "http"))]
+    pub(crate) fn set_first(&mut self, pattern: &str, allowed: &[CliPermission]) {
+        self.remove(pattern);
+        self.rules.insert(0, (pattern.to_owned(), allowed.to_vec()));
+    }
+    /// Remove the rule for a pattern
+    pub fn remove(&mut self, pattern: &str) {
+        self.rules.retain(|(p, _)| p != pattern);
+    }
+    /// Whether a source may run commands with the given permission level
+    pub fn allows(&self, source: &CliSource, permission: CliPermission) -> bool {
+        let name = source.to_string();
+        self.rules
+            .iter()
+            .find(|(pattern, _)| glob_match(pattern, &name))
+            .is_none_or(|(_, allowed)| allowed.contains(&permission))
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliSourcePermissions>();
+}
+
+/// Compare secrets, like passwords of remote clients, without leaking where
+/// they differ through timing
+#[cfg(any(feature = "remote", feature = "http"))]
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CliError;
+    use crate::output::{CliCommandsOutputExt, CliOutput, CliOutputLog};
+    use crate::permissions::CliPermissions;
+    use crate::quota::{run_cli_from, with_cli_source};
+    use crate::{CliCommandsRegisterExt, CliCommandsRunExt, CliPlugin};
+
+    #[derive(Resource, Default)]
+    struct Ran(Vec<&'static str>);
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        app.init_resource::<Ran>();
+        app.insert_resource(CliPermissions {
+            cheats: true,
+            dev: true,
+        });
+        app.register_clicommand_noargs("noclip", |mut ran: ResMut<Ran>| ran.0.push("noclip"));
+        app.set_clicommand_permission("noclip", CliPermission::Cheat);
+        app.register_clicommand_noargs("wrapper", |world: &mut World| {
+            world.run_cli("noclip");
+        });
+        app.register_clicommand_noargs("greet", |mut output: CliOutput| {
+            output.println("Hello!");
+        });
+        app.world_mut()
+            .resource_mut::<CliSourcePermissions>()
+            .set("player:*", &[CliPermission::Normal]);
+        app
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(CliSource::Local.to_string(), "local");
+        let addr: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+        assert_eq!(CliSource::Remote(addr).to_string(), "remote:127.0.0.1:5000");
+        assert_eq!(CliSource::from("chat:bob").to_string(), "chat:bob");
+        assert!(CliSource::Player(Entity::PLACEHOLDER).is_private());
+        assert!(CliSource::Remote(addr).is_private());
+        assert!(!CliSource::Local.is_private());
+    }
+
+    #[test]
+    fn first_matching_rule_applies() {
+        let mut permissions = CliSourcePermissions::default();
+        permissions.set("chat:admin", &[CliPermission::Normal, CliPermission::Cheat]);
+        permissions.set("chat:*", &[CliPermission::Normal]);
+        let admin = CliSource::from("chat:admin");
+        let bob = CliSource::from("chat:bob");
+        assert!(permissions.allows(&admin, CliPermission::Cheat));
+        assert!(!permissions.allows(&bob, CliPermission::Cheat));
+        assert!(permissions.allows(&bob, CliPermission::Normal));
+        assert!(permissions.allows(&CliSource::Local, CliPermission::Dev));
+        permissions.remove("chat:*");
+        assert!(permissions.allows(&bob, CliPermission::Cheat));
+    }
+
+    #[test]
+    fn sources_are_restricted() {
+        let mut app = app();
+        let world = app.world_mut();
+        let player = CliSource::Player(world.spawn_empty().id());
+
+        let result = with_cli_source(world, player.clone(), |world| world.try_run_cli("noclip"));
+        assert!(matches!(result, Err(CliError::NotPermitted { .. })));
+        // Commands run by other commands keep the source
+        run_cli_from(world, player, "wrapper");
+        assert!(world.resource::<Ran>().0.is_empty());
+
+        run_cli_from(world, CliSource::Local, "wrapper");
+        world.run_cli("noclip");
+        assert_eq!(world.resource::<Ran>().0, ["noclip", "noclip"]);
+    }
+
+    #[test]
+    fn deferred_commands_keep_the_source() {
+        let mut app = app();
+        app.register_clicommand_noargs("later", |world: &mut World| {
+            world.run_cli_queued("noclip");
+        });
+        let player = CliSource::Player(app.world_mut().spawn_empty().id());
+        for source in [player, CliSource::Local] {
+            let world = app.world_mut();
+            run_cli_from(world, source.clone(), "later");
+            run_cli_from(world, source.clone(), "sleep 0; noclip");
+            #[cfg(feature = "schedule")]
+            run_cli_from(world, source, "after 0 noclip");
+            app.update();
+            app.update();
+        }
+        let expected = if cfg!(feature = "schedule") { 3 } else { 2 };
+        assert_eq!(app.world().resource::<Ran>().0.len(), expected);
+    }
+
+    #[test]
+    fn output_for_players_is_private() {
+        let mut app = app();
+        let world = app.world_mut();
+        let player = CliSource::Player(world.spawn_empty().id());
+        world.resource_mut::<CliOutputLog>().clear();
+
+        run_cli_from(world, player.clone(), "greet");
+        run_cli_from(world, "chat:bob", "greet");
+        let mut log = world.resource_mut::<CliOutputLog>();
+        let public: Vec<&str> = log.iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(public, ["Hello!"]);
+        let private = log.drain_for(&player);
+        assert_eq!(private.len(), 1);
+        assert_eq!(private[0].source, Some(player));
+        assert!(log.drain_private().next().is_none());
+    }
+
+    #[test]
+    fn errors_and_job_output_go_to_the_player() {
+        let mut app = app();
+        app.register_clicommand_async("download", |_, handle| async move {
+            handle.println("Downloading...");
+            Err("No connection".to_owned())
+        });
+        let player = CliSource::Player(app.world_mut().spawn_empty().id());
+        app.world_mut().resource_mut::<CliOutputLog>().clear();
+
+        run_cli_from(app.world_mut(), player.clone(), "queue bogus");
+        run_cli_from(app.world_mut(), player.clone(), "download");
+        for _ in 0..100 {
+            if app.world().resource::<crate::jobs::CliJobs>().is_empty() {
+                break;
+            }
+            app.update();
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+
+        let mut log = app.world_mut().resource_mut::<CliOutputLog>();
+        assert!(log.is_empty());
+        let private: Vec<String> = log
+            .drain_for(&player)
+            .into_iter()
+            .map(|line| line.text)
+            .collect();
+        assert!(private[0].starts_with("Unknown queue operation"));
+        assert_eq!(
+            private[1..],
+            [
+                "Started job 1",
+                "Downloading...",
+                "Job 1 (download) failed: No connection"
+            ]
+        );
+    }
+}
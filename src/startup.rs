@@ -0,0 +1,91 @@
+//! Ordering of initialization at startup.
+//!
+//! [`CliPlugin`](crate::CliPlugin) initializes the CLI in the [`Startup`]
+//! schedule, in the order of the [`CliStartupSet`] variants:
+//!
+//!  1. [`Register`](CliStartupSet::Register): put your systems that register
+//!     commands and cvars here (the built-ins are registered before any
+//!     schedule runs).
+//!  2. [`Autoexec`](CliStartupSet::Autoexec): the command strings in
+//!     [`CliAutoexec`] are run, in order.
+//!  3. [`Ready`](CliStartupSet::Ready): the [`CliReady`] event is sent.
+//!
+//! Systems that run commands depending on registered content should run
+//! after [`CliStartupSet::Register`], or wait for [`CliReady`].
+//!
+//! ```rust,no_run
+//! # use bevy::prelude::*;
+//! # use iyes_cli::prelude::*;
+//! # use iyes_cli::startup::{CliAutoexec, CliStartupSet};
+//! # fn register_level_commands(world: &mut World) {}
+//! # let mut app = App::new();
+//! app.add_systems(Startup, register_level_commands.in_set(CliStartupSet::Register));
+//! app.world_mut().resource_mut::<CliAutoexec>().push("load_level 1");
+//! ```
+
+use bevy::prelude::*;
+
+use crate::CliCommandsRunExt;
+
+/// System sets for initialization in the [`Startup`] schedule, run in order
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CliStartupSet {
+    /// Registering commands and cvars
+    Register,
+    /// Running the command strings in [`CliAutoexec`]
+    Autoexec,
+    /// Sending [`CliReady`]
+    Ready,
+}
+
+/// Event sent once at startup, after all commands are registered and
+/// [`CliAutoexec`] has run
+#[derive(Event, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CliReady;
+
+/// Command strings to run at startup, in [`CliStartupSet::Autoexec`]
+#[derive(Resource, Debug, Clone, Default)]
+pub struct CliAutoexec {
+    lines: Vec<String>,
+}
+
+impl CliAutoexec {
+    /// Add a command string to run at startup
+    pub fn push(&mut self, cli: impl Into<String>) {
+        self.lines.push(cli.into());
+    }
+    /// Iterate over the command strings, in the order they will run
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().map(|s| s.as_str())
+    }
+}
+
+fn run_autoexec(world: &mut World) {
+    let Some(autoexec) = world.get_resource_mut::<CliAutoexec>() else {
+        return;
+    };
+    let lines = std::mem::take(&mut autoexec.into_inner().lines);
+    for cli in lines {
+        world.run_cli(&cli);
+    }
+}
+
+fn send_ready(mut evw_ready: EventWriter<CliReady>) {
+    evw_ready.send(CliReady);
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliAutoexec>();
+    app.add_event::<CliReady>();
+    app.configure_sets(
+        Startup,
+        (
+            CliStartupSet::Register,
+            CliStartupSet::Autoexec,
+            CliStartupSet::Ready,
+        )
+            .chain(),
+    );
+    app.add_systems(Startup, run_autoexec.in_set(CliStartupSet::Autoexec));
+    app.add_systems(Startup, send_ready.in_set(CliStartupSet::Ready));
+}
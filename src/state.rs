@@ -0,0 +1,85 @@
+//! "Console commands" that are only available in a given Bevy state.
+//!
+//! Requires the `states` cargo feature. A command can be scoped to a
+//! [`States`] value, so it is only enabled (runnable, and shown in `help`
+//! and completions) while the app is in that state:
+//!
+//! ```rust,ignore
+//! app.init_state::<GameState>();
+//! app.register_clicommand_in_state(GameState::InGame, "restart_level", restart_level);
+//! // or, for an already registered command:
+//! app.set_clicommand_state("give", GameState::InGame);
+//! ```
+//!
+//! The command is disabled until the state is entered, and disabled again
+//! when it is exited (see [`CliCommands::set_enabled`]). It stays registered
+//! the whole time.
+
+use bevy::prelude::*;
+
+use crate::{CliCommands, CliCommandsRegisterExt};
+
+/// Provides methods for scoping "console commands" to Bevy states
+pub trait CliStateRegisterExt {
+    /// Only enable an existing "console command" while in the given state
+    fn set_clicommand_state<S: States>(&mut self, name: &str, state: S) -> &mut Self;
+
+    /// Create a new "console command" that is only enabled while in the given state
+    ///
+    /// See [`register_clicommand_args`](CliCommandsRegisterExt::register_clicommand_args).
+    fn register_clicommand_in_state<S, Sys, Param>(
+        &mut self,
+        state: S,
+        name: &str,
+        system: Sys,
+    ) -> &mut Self
+    where
+        S: States,
+        Sys: IntoSystem<In<Vec<String>>, (), Param> + 'static;
+}
+
+impl CliStateRegisterExt for App {
+    fn set_clicommand_state<S: States>(&mut self, name: &str, state: S) -> &mut Self {
+        let in_state = self
+            .world()
+            .get_resource::<State<S>>()
+            .is_some_and(|current| *current.get() == state);
+        let found = self
+            .world_mut()
+            .get_resource_mut::<CliCommands>()
+            .is_some_and(|mut c| c.set_enabled(name, in_state));
+        if !found {
+            error!("Cannot set state of unknown CliCommand {:?}!", name);
+            return self;
+        }
+        let on_enter = name.to_owned();
+        let on_exit = name.to_owned();
+        self.add_systems(
+            OnEnter(state.clone()),
+            move |mut clicommands: ResMut<CliCommands>| {
+                clicommands.set_enabled(&on_enter, true);
+            },
+        );
+        self.add_systems(
+            OnExit(state),
+            move |mut clicommands: ResMut<CliCommands>| {
+                clicommands.set_enabled(&on_exit, false);
+            },
+        );
+        self
+    }
+
+    fn register_clicommand_in_state<S, Sys, Param>(
+        &mut self,
+        state: S,
+        name: &str,
+        system: Sys,
+    ) -> &mut Self
+    where
+        S: States,
+        Sys: IntoSystem<In<Vec<String>>, (), Param> + 'static,
+    {
+        self.register_clicommand_args(name, system)
+            .set_clicommand_state(name, state)
+    }
+}
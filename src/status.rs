@@ -0,0 +1,129 @@
+//! Tracking whether "console commands" succeed or fail.
+//!
+//! A command string fails if it cannot be parsed, does not match a command,
+//! or the command cannot run. Commands can also report failure themselves,
+//! using [`CliOutput::fail`](crate::output::CliOutput::fail) or [`CliStatus::fail`].
+//!
+//! The outcome of the last command string is available as the exit code `$?`
+//! in command strings (see [`env`](crate::env)), and the last failed command
+//! string can be re-run with the built-in `retry` command.
+//!
+//! Scripts and procedures can also end early with the built-in `return`
+//! command, optionally with a value. The value is printed as output of the
+//! script or procedure, and is available from [`CliStatus::last_return_value`].
+
+use bevy::prelude::*;
+
+use crate::output::cli_println;
+use crate::source::CliSource;
+
+/// The outcome of the most recently run command strings
+#[derive(Resource, Debug, Default)]
+pub struct CliStatus {
+    running: Vec<bool>,
+    last_success: Option<bool>,
+    last_failed_line: Option<String>,
+    pub(crate) session: Option<Entity>,
+    /// Where the command strings being run come from (see [`source`](crate::source))
+    pub(crate) source: Option<CliSource>,
+    pub(crate) rerun: Option<String>,
+    /// How many scripts or procedures are running, that `return` can end
+    pub(crate) return_frames: usize,
+    /// Set by `return`, until the innermost script or procedure ends
+    pub(crate) returned: Option<String>,
+    last_return: Option<String>,
+}
+
+impl CliStatus {
+    /// Mark the currently running command as failed
+    ///
+    /// Has no effect if no command is running.
+    pub fn fail(&mut self) {
+        if let Some(failed) = self.running.last_mut() {
+            *failed = true;
+        }
+    }
+    /// Who or what ran the command strings being run, if known
+    ///
+    /// See [`source`](crate::source).
+    pub fn source(&self) -> Option<&CliSource> {
+        self.source.as_ref()
+    }
+    /// Whether the last command string succeeded, if any has been run
+    pub fn last_success(&self) -> Option<bool> {
+        self.last_success
+    }
+    /// The exit code of the last command string: `0` for success, `1` for failure
+    pub fn last_exit_code(&self) -> u8 {
+        match self.last_success {
+            Some(false) => 1,
+            _ => 0,
+        }
+    }
+    /// The last command string that failed, if any
+    pub fn last_failed_line(&self) -> Option<&str> {
+        self.last_failed_line.as_deref()
+    }
+    /// The value of the last `return` from a script or procedure, if any
+    pub fn last_return_value(&self) -> Option<&str> {
+        self.last_return.as_deref()
+    }
+}
+
+/// Mark the currently running command as failed, from an exclusive system
+pub fn cli_fail(world: &mut World) {
+    if let Some(mut status) = world.get_resource_mut::<CliStatus>() {
+        status.fail();
+    }
+}
+
+/// Run `f`, tracking whether the command it runs fails
+pub(crate) fn run_tracked(world: &mut World, f: impl FnOnce(&mut World) -> bool) -> bool {
+    world
+        .get_resource_or_insert_with(CliStatus::default)
+        .running
+        .push(false);
+    let success = f(world);
+    let failed = world
+        .resource_mut::<CliStatus>()
+        .running
+        .pop()
+        .unwrap_or(false);
+    success && !failed
+}
+
+/// Record the outcome of running a command string
+pub(crate) fn record(world: &mut World, cli: &str, success: bool) {
+    let mut status = world.get_resource_or_insert_with(CliStatus::default);
+    let rerun = status.rerun.take();
+    status.last_success = Some(success);
+    if !success {
+        status.last_failed_line = Some(rerun.unwrap_or_else(|| cli.to_owned()));
+    }
+}
+
+/// Run a script or procedure `f`, which can be ended early with `return`
+///
+/// `f` must stop running commands once [`is_returning`] is true.
+pub(crate) fn run_returning(world: &mut World, f: impl FnOnce(&mut World)) {
+    world
+        .get_resource_or_insert_with(CliStatus::default)
+        .return_frames += 1;
+    f(world);
+    let mut status = world.resource_mut::<CliStatus>();
+    status.return_frames -= 1;
+    let Some(value) = status.returned.take() else {
+        return;
+    };
+    status.last_return = Some(value.clone());
+    if !value.is_empty() {
+        cli_println(world, value);
+    }
+}
+
+/// Whether `return` was run, so the current script or procedure should end
+pub(crate) fn is_returning(world: &World) -> bool {
+    world
+        .get_resource::<CliStatus>()
+        .is_some_and(|status| status.returned.is_some())
+}
@@ -0,0 +1,125 @@
+//! Reading "console commands" from the terminal, for headless apps.
+//!
+//! Requires the `stdin` cargo feature. Add [`CliStdinPlugin`] (together
+//! with [`CliPlugin`](crate::CliPlugin)), and every line typed into the
+//! terminal the app runs in is run as a command string. The output of each
+//! command is printed to stdout.
+//!
+//! Lines are read on a background thread, so reading never blocks the app.
+//! There is no line editing or history beyond what the terminal itself
+//! provides.
+//!
+//! When a command asks a question (see [`prompt`](crate::prompt)), it is
+//! printed, and the next line is the answer. Secret answers are not hidden,
+//! because the terminal echoes what is typed.
+//!
+//! Output printed later (for example, by commands that finish over several
+//! frames) is not printed to stdout, but it is still logged, as long as
+//! [`CliOutputLog::echo_to_log`](crate::output::CliOutputLog::echo_to_log) is enabled.
+
+use std::io::BufRead;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+
+use crate::output::{capture_output, CliCommandsOutputExt};
+use crate::prompt::CliPrompt;
+
+/// Plugin that runs command strings typed into the terminal
+pub struct CliStdinPlugin;
+
+impl Plugin for CliStdinPlugin {
+    fn build(&self, app: &mut App) {
+        let (tx, rx) = mpsc::channel();
+        let spawned = std::thread::Builder::new()
+            .name("iyes_cli stdin".to_owned())
+            .spawn(move || {
+                for line in std::io::stdin().lock().lines() {
+                    let Ok(line) = line else {
+                        break;
+                    };
+                    if tx.send(line).is_err() {
+                        break;
+                    }
+                }
+            });
+        if let Err(e) = spawned {
+            error!("Cannot read CLI commands from stdin: {}", e);
+            return;
+        }
+        app.insert_resource(CliStdin {
+            lines: Mutex::new(rx),
+            prompt_shown: 0,
+        });
+        app.add_systems(PreUpdate, run_stdin_lines);
+    }
+}
+
+/// Receives the lines read from stdin by the background thread
+#[derive(Resource)]
+struct CliStdin {
+    lines: Mutex<Receiver<String>>,
+    /// The id of the last question printed
+    prompt_shown: u64,
+}
+
+fn run_stdin_lines(world: &mut World) {
+    let mut lines = Vec::new();
+    let mut disconnected = false;
+    {
+        let Some(stdin) = world.get_resource::<CliStdin>() else {
+            return;
+        };
+        let Ok(rx) = stdin.lines.lock() else {
+            return;
+        };
+        loop {
+            match rx.try_recv() {
+                Ok(line) => lines.push(line),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    disconnected = true;
+                    break;
+                }
+            }
+        }
+    }
+    for line in lines {
+        let request = world
+            .get_resource_mut::<CliPrompt>()
+            .and_then(|mut prompt| prompt.take_request());
+        let output = match request {
+            Some(request) => capture_output(world, |world| request.answer(world, line)).0,
+            None if line.trim().is_empty() => continue,
+            None => world.run_cli_captured(&line),
+        };
+        for output_line in output {
+            println!("{}", output_line.text);
+        }
+        show_prompt(world);
+    }
+    show_prompt(world);
+    if disconnected {
+        info!("Stdin closed, no longer reading CLI commands from it");
+        world.remove_resource::<CliStdin>();
+    }
+}
+
+/// Print the question waiting for an answer, if it was not printed yet
+fn show_prompt(world: &mut World) {
+    let Some(request) = world
+        .get_resource::<CliPrompt>()
+        .and_then(|prompt| prompt.request())
+        .map(|request| (request.id(), request.message().to_owned()))
+    else {
+        return;
+    };
+    let Some(mut stdin) = world.get_resource_mut::<CliStdin>() else {
+        return;
+    };
+    if stdin.prompt_shown != request.0 {
+        stdin.prompt_shown = request.0;
+        println!("{}", request.1);
+    }
+}
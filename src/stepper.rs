@@ -0,0 +1,153 @@
+//! Advancing the game by a number of frames, for debugging.
+//!
+//! Requires the `stepper` cargo feature (enabled by default).
+//!
+//! `step <frames> [command...]` unpauses virtual time for the given number
+//! of frames, then pauses it again, and runs the command string, if any,
+//! at the end of the last frame:
+//!
+//! ```text
+//! > step 10 inspect 12v1
+//! ```
+//!
+//! Systems driven by virtual time ([`Time`] in `Update`, `FixedUpdate`)
+//! advance during those frames only. The [`CliDebugStepper`] resource does
+//! the same from code, for example from a test harness (see
+//! [`CliTestHarness::step`](crate::testing::CliTestHarness::step)) that
+//! polls the results, and `step` works over the remote console like any
+//! other command.
+
+use bevy::prelude::*;
+use bevy::time::TimeSystem;
+
+use crate::output::CliOutput;
+use crate::{quote_cli_arg, CliCommandsRegisterExt, CliCommandsRunExt};
+
+/// Steps the game by a number of frames, see [`stepper`](crate::stepper)
+#[derive(Resource, Debug, Default)]
+pub struct CliDebugStepper {
+    remaining: u32,
+    /// Whether the current frame is stepped
+    stepping: bool,
+    /// Whether the current frame is the last stepped one
+    finished: bool,
+    then: Vec<String>,
+}
+
+impl CliDebugStepper {
+    /// Run the given number of frames more with virtual time unpaused, then pause it
+    ///
+    /// Stepping starts with the next frame. If already stepping, the frames
+    /// are added to the remaining ones.
+    pub fn step(&mut self, frames: u32) {
+        self.remaining = self.remaining.saturating_add(frames);
+    }
+    /// Run a command string once the current steps are done
+    pub fn then(&mut self, cli: impl Into<String>) {
+        self.then.push(cli.into());
+    }
+    /// Whether frames are still being stepped
+    ///
+    /// Becomes `false` at the end of the last stepped frame, after the
+    /// command strings given to [`then`](Self::then) have run.
+    pub fn is_stepping(&self) -> bool {
+        self.remaining > 0 || self.finished
+    }
+    /// How many frames are left to step, after the current one
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+    /// Stop stepping, without running the command strings given to [`then`](Self::then)
+    pub fn cancel(&mut self) {
+        self.remaining = 0;
+        self.finished = false;
+        self.then.clear();
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliDebugStepper>();
+    app.add_systems(First, count_step.before(TimeSystem));
+    app.add_systems(Last, finish_step);
+    app.register_clicommand_args("step", step)
+        .set_clicommand_description("step", "Run some frames, then pause virtual time")
+        .set_clicommand_usage("step", "step <frames> [command...]");
+}
+
+/// Unpause virtual time for a stepped frame, or pause it after the last one
+fn count_step(world: &mut World) {
+    let mut stepper = world.resource_mut::<CliDebugStepper>();
+    let paused = match stepper.remaining {
+        0 if !stepper.stepping => return,
+        0 => {
+            stepper.stepping = false;
+            true
+        }
+        _ => {
+            stepper.remaining -= 1;
+            stepper.stepping = true;
+            stepper.finished = stepper.remaining == 0;
+            false
+        }
+    };
+    // Before virtual time is updated, so that it applies to this frame
+    if let Some(mut time) = world.get_resource_mut::<Time<Virtual>>() {
+        match paused {
+            true => time.pause(),
+            false => time.unpause(),
+        }
+    }
+}
+
+/// Pause virtual time at the end of the last stepped frame, and run the
+/// commands waiting for it
+fn finish_step(world: &mut World) {
+    let mut stepper = world.resource_mut::<CliDebugStepper>();
+    if !stepper.finished {
+        return;
+    }
+    stepper.finished = false;
+    let then = std::mem::take(&mut stepper.then);
+    if let Some(mut time) = world.get_resource_mut::<Time<Virtual>>() {
+        time.pause();
+    }
+    for cli in then {
+        world.run_cli(&cli);
+    }
+}
+
+/// `step <frames> [command...]`: run some frames, then pause virtual time
+fn step(
+    In(args): In<Vec<String>>,
+    mut stepper: ResMut<CliDebugStepper>,
+    time: Option<ResMut<Time<Virtual>>>,
+    mut output: CliOutput,
+) {
+    let Some((frames, command)) = args.split_first() else {
+        output.fail("Usage: step <frames> [command...]");
+        return;
+    };
+    let Ok(frames) = frames.parse::<u32>() else {
+        output.fail(format!("Invalid frame count: {:?}", frames));
+        return;
+    };
+    let Some(mut time) = time else {
+        output.fail("There is no virtual time to step (TimePlugin)!");
+        return;
+    };
+    if frames == 0 {
+        time.pause();
+        return;
+    }
+    stepper.step(frames);
+    match command {
+        [] => {}
+        // `step 10 "inspect 12v1"`
+        [cli] => stepper.then(cli),
+        // `step 10 inspect 12v1`
+        words => {
+            let words: Vec<_> = words.iter().map(|word| quote_cli_arg(word)).collect();
+            stepper.then(words.join(" "));
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Formatting tabular data for display in consoles.
+
+use std::fmt;
+
+/// How the contents of a [`CliTable`] column are aligned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CliAlign {
+    #[default]
+    Left,
+    Right,
+    Center,
+}
+
+/// Builder for tables, to give command output a consistent presentation
+///
+/// Renders as aligned text for monospace consoles (via [`Display`](fmt::Display)),
+/// or as a JSON array of objects (via [`to_json`](Self::to_json)), for tools.
+///
+/// ```rust
+/// # use iyes_cli::table::{CliAlign, CliTable};
+/// let mut table = CliTable::new()
+///     .with_column("NAME", CliAlign::Left)
+///     .with_column("COUNT", CliAlign::Right);
+/// table.add_row(["spawn", "3"]);
+/// table.add_row(["despawn", "12"]);
+/// println!("{}", table);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CliTable {
+    columns: Vec<(String, CliAlign)>,
+    rows: Vec<Vec<String>>,
+    max_width: Option<usize>,
+}
+
+impl CliTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Add a column with the given header
+    pub fn with_column(mut self, header: impl Into<String>, align: CliAlign) -> Self {
+        self.columns.push((header.into(), align));
+        self
+    }
+    /// Limit the width (in characters) of every column
+    ///
+    /// Longer cells are truncated, with `…` marking the truncation.
+    pub fn with_max_width(mut self, max_width: usize) -> Self {
+        self.max_width = Some(max_width.max(1));
+        self
+    }
+    /// Add a row of cells, one per column
+    ///
+    /// Missing cells are left empty, and extra cells are ignored.
+    pub fn add_row<I>(&mut self, cells: I) -> &mut Self
+    where
+        I: IntoIterator,
+        I::Item: ToString,
+    {
+        let mut row: Vec<String> = cells
+            .into_iter()
+            .take(self.columns.len())
+            .map(|cell| cell.to_string())
+            .collect();
+        row.resize(self.columns.len(), String::new());
+        self.rows.push(row);
+        self
+    }
+    /// How many rows have been added
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+    /// Render as a JSON array, with one object per row, keyed by column header
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("[");
+        for (i, row) in self.rows.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push('{');
+            for (j, ((header, _), cell)) in self.columns.iter().zip(row).enumerate() {
+                if j > 0 {
+                    json.push(',');
+                }
+                push_json_string(&mut json, header);
+                json.push(':');
+                push_json_string(&mut json, cell);
+            }
+            json.push('}');
+        }
+        json.push(']');
+        json
+    }
+    fn truncate(&self, cell: &str) -> String {
+        match self.max_width {
+            Some(max) if cell.chars().count() > max => {
+                let mut truncated: String = cell.chars().take(max - 1).collect();
+                truncated.push('…');
+                truncated
+            }
+            _ => cell.to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for CliTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let headers: Vec<String> = self.columns.iter().map(|(h, _)| self.truncate(h)).collect();
+        let rows: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|cell| self.truncate(cell)).collect())
+            .collect();
+        let widths: Vec<usize> = (0..self.columns.len())
+            .map(|i| {
+                rows.iter()
+                    .map(|row| row[i].chars().count())
+                    .chain(std::iter::once(headers[i].chars().count()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let separator: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+        for line in std::iter::once(&headers)
+            .chain(std::iter::once(&separator))
+            .chain(&rows)
+        {
+            let mut out = String::new();
+            for (i, cell) in line.iter().enumerate() {
+                if i > 0 {
+                    out.push_str("  ");
+                }
+                let width = widths[i];
+                match self.columns[i].1 {
+                    CliAlign::Left => out.push_str(&format!("{:<width$}", cell)),
+                    CliAlign::Right => out.push_str(&format!("{:>width$}", cell)),
+                    CliAlign::Center => out.push_str(&format!("{:^width$}", cell)),
+                }
+            }
+            writeln!(f, "{}", out.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn push_json_string(json: &mut String, s: &str) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
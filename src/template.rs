@@ -0,0 +1,149 @@
+//! Expansion of command templates with positional placeholders.
+
+use bevy::prelude::*;
+
+use crate::output::cli_error;
+use crate::status::{run_returning, CliStatus};
+use crate::{quote_cli_arg, CliCommandsRunExt};
+
+/// Expand `$1`, `$2`, ... placeholders in `template` with the given args
+///
+/// `$*` expands to all the args, separated by spaces, and `$$` to a literal `$`.
+/// `$?` expands to the given exit code, and does not count as a placeholder.
+/// Args are quoted as needed, so that each is still a single arg after expansion.
+/// If the template contains no placeholders, the args are appended at the end,
+/// so that a template without placeholders behaves like a plain alias.
+///
+/// Returns the number of the first placeholder with no corresponding arg, if any.
+pub(crate) fn expand_template(
+    template: &str,
+    args: &[String],
+    last_exit_code: u8,
+) -> Result<String, usize> {
+    let mut out = String::with_capacity(template.len());
+    let mut has_placeholders = false;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some('$') => {
+                chars.next();
+                out.push('$');
+            }
+            Some('?') => {
+                chars.next();
+                out.push_str(&last_exit_code.to_string());
+            }
+            Some('*') => {
+                chars.next();
+                has_placeholders = true;
+                let quoted: Vec<_> = args.iter().map(|arg| quote_cli_arg(arg)).collect();
+                out.push_str(&quoted.join(" "));
+            }
+            Some(d) if d.is_ascii_digit() => {
+                let mut n = 0usize;
+                while let Some(d) = chars.peek().and_then(|c| c.to_digit(10)) {
+                    chars.next();
+                    n = n.saturating_mul(10).saturating_add(d as usize);
+                }
+                has_placeholders = true;
+                let arg = n.checked_sub(1).and_then(|i| args.get(i)).ok_or(n)?;
+                out.push_str(&quote_cli_arg(arg));
+            }
+            _ => out.push('$'),
+        }
+    }
+    if !has_placeholders {
+        for arg in args {
+            out.push(' ');
+            out.push_str(&quote_cli_arg(arg));
+        }
+    }
+    Ok(out)
+}
+
+/// Expand a template with the given args and run it, returning whether it succeeded
+///
+/// `name` is the name of the command being run, for error messages.
+pub(crate) fn run_template(world: &mut World, name: &str, template: &str, args: &[String]) -> bool {
+    let last_exit_code = world
+        .get_resource::<CliStatus>()
+        .map_or(0, |status| status.last_exit_code());
+    match expand_template(template, args, last_exit_code) {
+        Ok(cli) => {
+            run_returning(world, |world| {
+                world.run_cli(&cli);
+            });
+            world.resource::<CliStatus>().last_success() != Some(false)
+        }
+        Err(n) => {
+            cli_error(world, format!("CliCommand {:?} requires arg ${}!", name, n));
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|&arg| arg.to_owned()).collect()
+    }
+
+    #[test]
+    fn positional_placeholders() {
+        let expanded = expand_template("spawn $2 $1", &args(&["10", "20"]), 0);
+        assert_eq!(expanded.as_deref(), Ok("spawn 20 10"));
+        let expanded = expand_template("echo $*", &args(&["a", "b"]), 0);
+        assert_eq!(expanded.as_deref(), Ok("echo a b"));
+        let expanded = expand_template("echo $1$1", &args(&["x"]), 0);
+        assert_eq!(expanded.as_deref(), Ok("echo xx"));
+    }
+
+    #[test]
+    fn missing_arg() {
+        assert_eq!(
+            expand_template("spawn $1 $3", &args(&["1", "2"]), 0),
+            Err(3)
+        );
+        assert_eq!(expand_template("spawn $0", &args(&["1"]), 0), Err(0));
+    }
+
+    #[test]
+    fn args_appended_without_placeholders() {
+        let expanded = expand_template("spawn 960 540", &args(&["--count", "2"]), 0);
+        assert_eq!(expanded.as_deref(), Ok("spawn 960 540 --count 2"));
+    }
+
+    #[test]
+    fn exit_code_and_dollar() {
+        let expanded = expand_template("echo $? $$ $x", &args(&["a"]), 1);
+        assert_eq!(expanded.as_deref(), Ok("echo 1 $ $x a"));
+    }
+
+    #[test]
+    fn template_commands() {
+        use bevy::prelude::*;
+
+        use crate::{CliCommandsRegisterExt, CliCommandsRunExt};
+
+        #[derive(Resource, Default)]
+        struct Spawned(Vec<Vec<String>>);
+
+        let mut world = World::new();
+        world.init_resource::<Spawned>();
+        world.register_clicommand_args(
+            "spawn",
+            |In(args): In<Vec<String>>, mut spawned: ResMut<Spawned>| spawned.0.push(args),
+        );
+        world.register_clicommand_template("spawn_row", "spawn $1 100");
+
+        world.run_cli("spawn_row 5");
+        world.run_cli("spawn_row");
+        assert_eq!(world.resource::<Spawned>().0, [vec!["5", "100"]]);
+    }
+}
@@ -0,0 +1,678 @@
+//! Testing "console commands" without a window or rendering.
+//!
+//! [`CliTestHarness`] wraps a headless [`App`] with [`MinimalPlugins`] and
+//! [`CliPlugin`], runs command strings, and captures their output and errors,
+//! with helpers for asserting on them:
+//!
+//! ```rust,ignore
+//! #[test]
+//! fn spawn_command() {
+//!     let mut harness = CliTestHarness::new();
+//!     harness.app_mut().register_clicommand_args("spawn", spawn);
+//!
+//!     harness.run("spawn 1 2").assert_ok().assert_output_contains("spawned");
+//!     harness.run("spawn nope").assert_failed();
+//!     harness.run("spwan").assert_error(|e| matches!(e, CliError::NotFound(_)));
+//!
+//!     let mut sprites = harness.world_mut().query::<&Sprite>();
+//!     assert_eq!(sprites.iter(harness.world()).count(), 1);
+//! }
+//! ```
+//!
+//! The app is started (running its `Startup` schedule) when the first
+//! command string is run, or [`update`](CliTestHarness::update) is called,
+//! so commands and plugins can be added before. Commands that run later,
+//! like with `after` or `sleep`, need frames to pass; see
+//! [`update`](CliTestHarness::update). To advance game time by an exact
+//! number of frames, see [`step`](CliTestHarness::step).
+//!
+//! Longer output, like help text, listings and error messages, can be
+//! compared with a snapshot file with
+//! [`assert_output_snapshot`](CliTestRun::assert_output_snapshot):
+//!
+//! ```rust,ignore
+//! harness.run("help spawn").assert_output_snapshot("help_spawn");
+//! ```
+//!
+//! Snapshots are kept in `tests/snapshots/<name>.snap`, in the crate being
+//! tested (or in the directory in the `CLI_SNAPSHOT_DIR` environment
+//! variable). A missing snapshot is written by the first run. To accept
+//! changed output, run the tests with `CLI_UPDATE_SNAPSHOTS=1`, and review
+//! the changes to the files.
+//!
+//! Custom arg types (any type used with [`CliArgs::get`] or as a typed arg
+//! of `#[cli_command]`) must parse back what they format. Check that with
+//! [`check_arg_roundtrip`], and whole argument lists with
+//! [`check_args_roundtrip`]. With the `proptest` cargo feature,
+//! [`proptest_arg_roundtrip`] and [`proptest_args_roundtrip`] check many
+//! generated values:
+//!
+//! ```rust,ignore
+//! #[test]
+//! fn team_args_round_trip() {
+//!     proptest_arg_roundtrip((0u8..4).prop_map(Team::from_index));
+//!     proptest_args_roundtrip("<team: String> <x: f32> [ids: u32...]");
+//! }
+//! ```
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use bevy::prelude::*;
+
+use crate::args::{CliArgs, CliSignature};
+use crate::error::CliError;
+use crate::output::{capture_output, CliOutputLine, CliSeverity};
+#[cfg(feature = "stepper")]
+use crate::stepper::CliDebugStepper;
+use crate::{parse_cli, quote_cli_arg, run_cli_chain, CliPlugin};
+
+/// A headless app for testing "console commands"
+///
+/// See [`testing`](crate::testing).
+pub struct CliTestHarness {
+    app: App,
+    started: bool,
+}
+
+impl Default for CliTestHarness {
+    fn default() -> Self {
+        CliTestHarness::new()
+    }
+}
+
+impl CliTestHarness {
+    /// Create an app with [`MinimalPlugins`] and [`CliPlugin`]
+    pub fn new() -> Self {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin));
+        CliTestHarness::from_app(app)
+    }
+    /// Wrap an app that has already been set up
+    ///
+    /// The app must have [`CliPlugin`], and must not have been started.
+    pub fn from_app(app: App) -> Self {
+        CliTestHarness {
+            app,
+            started: false,
+        }
+    }
+    pub fn app(&self) -> &App {
+        &self.app
+    }
+    pub fn app_mut(&mut self) -> &mut App {
+        &mut self.app
+    }
+    pub fn world(&self) -> &World {
+        self.app.world()
+    }
+    pub fn world_mut(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+    /// Start the app, if it was not started yet, returning whether it was
+    fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        self.app.finish();
+        self.app.cleanup();
+        self.app.update();
+        true
+    }
+    /// Run one frame of the app (the first frame, if it was not started yet)
+    pub fn update(&mut self) -> &mut Self {
+        if !self.start() {
+            self.app.update();
+        }
+        self
+    }
+    /// Run some frames of the app
+    pub fn update_frames(&mut self, frames: usize) -> &mut Self {
+        for _ in 0..frames {
+            self.update();
+        }
+        self
+    }
+    /// Run frames with virtual time unpaused, then pause it (see [`stepper`](crate::stepper))
+    ///
+    /// Returns once the [`CliDebugStepper`] is done, after running the
+    /// command strings queued with [`then`](CliDebugStepper::then), if any.
+    /// Requires the `stepper` feature.
+    #[cfg(feature = "stepper")]
+    pub fn step(&mut self, frames: u32) -> &mut Self {
+        self.start();
+        let world = self.app.world_mut();
+        world.resource_mut::<CliDebugStepper>().step(frames);
+        while self.app.world().resource::<CliDebugStepper>().is_stepping() {
+            self.app.update();
+        }
+        self
+    }
+    /// Run a command string immediately, capturing its output and error
+    pub fn run(&mut self, cli: &str) -> CliTestRun {
+        self.start();
+        let (output, result) = capture_output(self.app.world_mut(), |world| {
+            run_cli_chain(world, cli, false)
+        });
+        CliTestRun {
+            cli: cli.to_owned(),
+            output,
+            result,
+        }
+    }
+}
+
+/// The outcome of a command string run by a [`CliTestHarness`]
+///
+/// The `assert_*` methods panic with the command string and its output if
+/// the assertion does not hold, and can be chained.
+#[derive(Debug, Clone)]
+pub struct CliTestRun {
+    pub cli: String,
+    pub output: Vec<CliOutputLine>,
+    /// The outcome of the last command that ran
+    pub result: Result<(), CliError>,
+}
+
+impl CliTestRun {
+    pub fn is_ok(&self) -> bool {
+        self.result.is_ok()
+    }
+    pub fn error(&self) -> Option<&CliError> {
+        self.result.as_ref().err()
+    }
+    /// The text of the output, one line per line of output
+    pub fn text(&self) -> String {
+        let lines: Vec<&str> = self.output.iter().map(|line| line.text.as_str()).collect();
+        lines.join("\n")
+    }
+    /// The text of the lines of output with the given severity
+    pub fn lines_with(&self, severity: CliSeverity) -> impl Iterator<Item = &str> {
+        self.output
+            .iter()
+            .filter(move |line| line.severity == severity)
+            .map(|line| line.text.as_str())
+    }
+    #[track_caller]
+    fn fail(&self, message: &str) -> ! {
+        panic!(
+            "{} ({:?})\nresult: {:?}\noutput:\n{}",
+            message,
+            self.cli,
+            self.result,
+            self.text()
+        )
+    }
+    /// Assert that the command string succeeded
+    #[track_caller]
+    pub fn assert_ok(&self) -> &Self {
+        if self.result.is_err() {
+            self.fail("expected the command string to succeed");
+        }
+        self
+    }
+    /// Assert that the command string failed, for any reason
+    #[track_caller]
+    pub fn assert_failed(&self) -> &Self {
+        if self.result.is_ok() {
+            self.fail("expected the command string to fail");
+        }
+        self
+    }
+    /// Assert that the command string failed with an error matching `f`
+    #[track_caller]
+    pub fn assert_error(&self, f: impl Fn(&CliError) -> bool) -> &Self {
+        match &self.result {
+            Ok(()) => self.fail("expected the command string to fail"),
+            Err(e) if !f(e) => self.fail("the command string failed with an unexpected error"),
+            Err(_) => self,
+        }
+    }
+    /// Assert that a line of output contains the given text
+    #[track_caller]
+    pub fn assert_output_contains(&self, text: &str) -> &Self {
+        if !self.output.iter().any(|line| line.text.contains(text)) {
+            self.fail(&format!("expected the output to contain {:?}", text));
+        }
+        self
+    }
+    /// Assert that no line of output contains the given text
+    #[track_caller]
+    pub fn assert_output_lacks(&self, text: &str) -> &Self {
+        if self.output.iter().any(|line| line.text.contains(text)) {
+            self.fail(&format!("expected the output not to contain {:?}", text));
+        }
+        self
+    }
+    /// Assert that the text of the output is exactly the given lines
+    #[track_caller]
+    pub fn assert_output_eq(&self, lines: &[&str]) -> &Self {
+        if !self
+            .output
+            .iter()
+            .map(|line| line.text.as_str())
+            .eq(lines.iter().copied())
+        {
+            self.fail(&format!("expected the output to be {:?}", lines));
+        }
+        self
+    }
+    /// Assert that there was no output
+    #[track_caller]
+    pub fn assert_no_output(&self) -> &Self {
+        if !self.output.is_empty() {
+            self.fail("expected no output");
+        }
+        self
+    }
+    /// The text of a snapshot of the run: the command string, the output,
+    /// and the error, if any
+    ///
+    /// Lines of output that are warnings or errors are prefixed with their
+    /// severity.
+    pub fn snapshot(&self) -> String {
+        let mut snapshot = format!("> {}\n", self.cli);
+        for line in &self.output {
+            let prefix = match line.severity {
+                CliSeverity::Info | CliSeverity::Success => "",
+                CliSeverity::Warn => "warning: ",
+                CliSeverity::Error => "error: ",
+            };
+            snapshot.push_str(&format!("{}{}\n", prefix, line.text));
+        }
+        if let Err(e) = &self.result {
+            snapshot.push_str(&format!("failed: {}\n", e));
+        }
+        snapshot
+    }
+    /// Assert that the [snapshot](Self::snapshot) of the run is the same as
+    /// the one saved with the given name
+    ///
+    /// See [`testing`](crate::testing) for where snapshots are kept, and how
+    /// to update them.
+    #[track_caller]
+    pub fn assert_output_snapshot(&self, name: &str) -> &Self {
+        let path = snapshot_dir().join(format!("{}.snap", name));
+        let update = std::env::var_os("CLI_UPDATE_SNAPSHOTS").is_some_and(|v| v != "0");
+        if let Err(message) = compare_snapshot(&path, &self.snapshot(), update) {
+            self.fail(&message);
+        }
+        self
+    }
+    /// Assert that some line of output has the given severity, like an error
+    #[track_caller]
+    pub fn assert_severity(&self, severity: CliSeverity) -> &Self {
+        if self.lines_with(severity).next().is_none() {
+            self.fail(&format!(
+                "expected a line of output with severity {:?}",
+                severity
+            ));
+        }
+        self
+    }
+}
+
+/// The directory snapshots are kept in
+fn snapshot_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("CLI_SNAPSHOT_DIR") {
+        return dir.into();
+    }
+    let root = std::env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default();
+    Path::new(&root).join("tests").join("snapshots")
+}
+
+/// Compare a snapshot with the saved one, saving it if there is none, or if
+/// `update` is set
+fn compare_snapshot(path: &Path, snapshot: &str, update: bool) -> Result<(), String> {
+    let saved = match std::fs::read_to_string(path) {
+        Ok(saved) if !update => saved,
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(format!("cannot read snapshot {}: {}", path.display(), e));
+        }
+        _ => {
+            let written = path
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|()| std::fs::write(path, snapshot));
+            return written.map_err(|e| format!("cannot write snapshot {}: {}", path.display(), e));
+        }
+    };
+    // Snapshots may have been checked out with other line endings
+    if saved.replace("\r\n", "\n") == snapshot {
+        return Ok(());
+    }
+    let mut message = format!(
+        "the output differs from snapshot {} (run with CLI_UPDATE_SNAPSHOTS=1 to accept it)",
+        path.display()
+    );
+    let saved_lines: Vec<&str> = saved.lines().collect();
+    let lines: Vec<&str> = snapshot.lines().collect();
+    for i in 0..saved_lines.len().max(lines.len()) {
+        match (saved_lines.get(i), lines.get(i)) {
+            (Some(old), Some(new)) if old == new => {}
+            (old, new) => {
+                message.push_str(&format!("\nline {}:", i + 1));
+                if let Some(old) = old {
+                    message.push_str(&format!("\n  - {}", old));
+                }
+                if let Some(new) = new {
+                    message.push_str(&format!("\n  + {}", new));
+                }
+            }
+        }
+    }
+    Err(message)
+}
+
+/// Check that a value survives being an arg in a command string
+///
+/// The value is formatted with `Display`, quoted like an arg (see
+/// [`quote_cli_arg`]), parsed back as part of a command string, and then
+/// with `FromStr`, like [`CliArgs::get`] does. Returns what went wrong, if
+/// the result is not equal to the value.
+pub fn check_arg_roundtrip<T>(value: &T) -> Result<(), String>
+where
+    T: fmt::Display + fmt::Debug + FromStr + PartialEq,
+{
+    let text = value.to_string();
+    let line = format!("cmd {}", quote_cli_arg(&text));
+    let parsed = parse_cli(&line).map_err(|e| format!("{:?} cannot be parsed: {}", line, e))?;
+    if parsed.args() != [text.as_str()] {
+        return Err(format!("{:?} is parsed as args {:?}", line, parsed.args()));
+    }
+    match text.parse::<T>() {
+        Ok(parsed) if parsed == *value => Ok(()),
+        Ok(parsed) => Err(format!("{:?} is parsed back as {:?}", value, parsed)),
+        Err(_) => Err(format!("{:?} cannot be parsed back from {:?}", value, text)),
+    }
+}
+
+/// Check that args survive being written into a command string, for a signature
+///
+/// The args must be valid for the signature. They are quoted (see
+/// [`quote_cli_arg`]), parsed back as part of a command string, and
+/// validated against the signature again. Returns what went wrong, if the
+/// resulting [`CliArgs`] are not equal.
+pub fn check_args_roundtrip(signature: &CliSignature, args: &[String]) -> Result<(), String> {
+    let expected: CliArgs = signature
+        .parse_args(args.to_vec())
+        .map_err(|e| format!("{:?} are not valid args for {}: {}", args, signature, e))?;
+    let mut line = "cmd".to_owned();
+    for arg in args {
+        line.push(' ');
+        line.push_str(&quote_cli_arg(arg));
+    }
+    let parsed = parse_cli(&line).map_err(|e| format!("{:?} cannot be parsed: {}", line, e))?;
+    let actual = signature
+        .parse_args(parsed.args().to_vec())
+        .map_err(|e| format!("{:?} is no longer valid: {}", line, e))?;
+    match actual == expected {
+        true => Ok(()),
+        false => Err(format!("{:?} is parsed as args {:?}", line, parsed.args())),
+    }
+}
+
+/// Check [`check_arg_roundtrip`] for many values generated by a proptest strategy
+///
+/// Requires the `proptest` cargo feature. Panics with the simplest failing
+/// value, if any.
+#[cfg(feature = "proptest")]
+#[track_caller]
+pub fn proptest_arg_roundtrip<T>(strategy: impl proptest::strategy::Strategy<Value = T>)
+where
+    T: fmt::Display + fmt::Debug + FromStr + PartialEq,
+{
+    use proptest::test_runner::{TestCaseError, TestRunner};
+
+    let mut runner = TestRunner::default();
+    let result = runner.run(&strategy, |value| {
+        check_arg_roundtrip(&value).map_err(TestCaseError::fail)
+    });
+    if let Err(e) = result {
+        panic!("{}", e);
+    }
+}
+
+/// Check [`check_args_roundtrip`] for many valid args generated for a signature
+///
+/// Requires the `proptest` cargo feature. The signature uses the syntax of
+/// the [`args`](crate::args) module. Panics if it is malformed, or with the
+/// simplest failing args, if any.
+#[cfg(feature = "proptest")]
+#[track_caller]
+pub fn proptest_args_roundtrip(signature: &str) {
+    use proptest::test_runner::{TestCaseError, TestRunner};
+
+    let signature: CliSignature = signature
+        .parse()
+        .unwrap_or_else(|e| panic!("Invalid signature {:?}: {}", signature, e));
+    let mut runner = TestRunner::default();
+    let result = runner.run(&cli_args_strategy(&signature), |args| {
+        check_args_roundtrip(&signature, &args).map_err(TestCaseError::fail)
+    });
+    if let Err(e) = result {
+        panic!("{}", e);
+    }
+}
+
+/// A proptest strategy generating valid args for a signature
+///
+/// Requires the `proptest` cargo feature.
+#[cfg(feature = "proptest")]
+pub fn cli_args_strategy(
+    signature: &CliSignature,
+) -> proptest::strategy::BoxedStrategy<Vec<String>> {
+    use proptest::prelude::*;
+
+    use crate::args::CliArgType;
+
+    fn value(ty: CliArgType) -> BoxedStrategy<String> {
+        fn display<T: fmt::Display>(value: T) -> String {
+            value.to_string()
+        }
+        match ty {
+            CliArgType::Bool => any::<bool>().prop_map(display).boxed(),
+            CliArgType::Char => any::<char>().prop_map(display).boxed(),
+            CliArgType::String => any::<String>().boxed(),
+            CliArgType::I8 => any::<i8>().prop_map(display).boxed(),
+            CliArgType::I16 => any::<i16>().prop_map(display).boxed(),
+            CliArgType::I32 => any::<i32>().prop_map(display).boxed(),
+            CliArgType::I64 => any::<i64>().prop_map(display).boxed(),
+            CliArgType::Isize => any::<isize>().prop_map(display).boxed(),
+            CliArgType::U8 => any::<u8>().prop_map(display).boxed(),
+            CliArgType::U16 => any::<u16>().prop_map(display).boxed(),
+            CliArgType::U32 => any::<u32>().prop_map(display).boxed(),
+            CliArgType::U64 => any::<u64>().prop_map(display).boxed(),
+            CliArgType::Usize => any::<usize>().prop_map(display).boxed(),
+            CliArgType::F32 => any::<f32>().prop_map(display).boxed(),
+            CliArgType::F64 => any::<f64>().prop_map(display).boxed(),
+            CliArgType::Entity => (0..u32::MAX, 1..=i32::MAX as u32)
+                .prop_map(|(index, generation)| format!("{}v{}", index, generation))
+                .boxed(),
+        }
+    }
+
+    let params: Vec<(bool, BoxedStrategy<Vec<String>>)> = signature
+        .params()
+        .iter()
+        .map(|param| {
+            let count = match (param.optional, param.variadic) {
+                (true, true) => 0..4,
+                (false, true) => 1..4,
+                (true, false) => 0..2,
+                (false, false) => 1..2,
+            };
+            let values = proptest::collection::vec(value(param.ty), count);
+            (param.optional, values.boxed())
+        })
+        .collect();
+    let (optional, values): (Vec<bool>, Vec<_>) = params.into_iter().unzip();
+    values
+        .prop_map(move |values| {
+            let mut args = Vec::new();
+            for (values, optional) in values.into_iter().zip(&optional) {
+                // Args are positional, so nothing can follow a missing one
+                if values.is_empty() && *optional {
+                    break;
+                }
+                args.extend(values);
+            }
+            args
+        })
+        .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::CliOutput;
+    use crate::CliCommandsRegisterExt;
+
+    fn harness() -> CliTestHarness {
+        let mut harness = CliTestHarness::new();
+        harness.app_mut().register_clicommand_args(
+            "greet",
+            |In(args): In<Vec<String>>, mut output: CliOutput| match args.as_slice() {
+                [name] => output.println(format!("Hello, {}!", name)),
+                _ => output.fail("Usage: greet <name>"),
+            },
+        );
+        harness
+    }
+
+    #[test]
+    fn captures_output_and_errors() {
+        let mut harness = harness();
+        harness
+            .run("greet Bob")
+            .assert_ok()
+            .assert_output_eq(&["Hello, Bob!"]);
+        let run = harness.run("greet");
+        run.assert_error(|e| matches!(e, CliError::Failed(_)))
+            .assert_severity(CliSeverity::Error);
+        assert_eq!(
+            run.lines_with(CliSeverity::Error).collect::<Vec<_>>(),
+            ["Usage: greet <name>"]
+        );
+        harness
+            .run("gret Bob")
+            .assert_error(|e| matches!(e, CliError::NotFound(_)))
+            .assert_no_output();
+    }
+
+    #[test]
+    fn result_of_the_last_command_run() {
+        let mut harness = harness();
+        harness
+            .run("greet && greet Bob")
+            .assert_failed()
+            .assert_output_lacks("Bob");
+        harness
+            .run("greet || greet Bob")
+            .assert_ok()
+            .assert_output_contains("Hello, Bob!");
+    }
+
+    #[test]
+    #[cfg(feature = "stepper")]
+    fn step_runs_exact_frames() {
+        use crate::status::CliStatus;
+
+        #[derive(Resource, Default)]
+        struct Frames(u32);
+
+        let mut harness = harness();
+        harness.app_mut().init_resource::<Frames>();
+        harness.app_mut().add_systems(
+            Update,
+            |time: Res<Time<Virtual>>, mut frames: ResMut<Frames>| {
+                if !time.is_paused() {
+                    frames.0 += 1;
+                }
+            },
+        );
+        harness.world_mut().resource_mut::<Time<Virtual>>().pause();
+        harness.update_frames(2);
+        assert_eq!(harness.world().resource::<Frames>().0, 0);
+
+        harness
+            .world_mut()
+            .resource_mut::<CliDebugStepper>()
+            .then("greet Alice");
+        harness.step(3);
+        assert_eq!(harness.world().resource::<Frames>().0, 3);
+        let status = harness.world().resource::<CliStatus>();
+        assert_eq!(status.last_success(), Some(true));
+        assert!(harness.world().resource::<Time<Virtual>>().is_paused());
+        harness.update_frames(2);
+        assert_eq!(harness.world().resource::<Frames>().0, 3);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Rounded(f32);
+
+    impl fmt::Display for Rounded {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{:.1}", self.0)
+        }
+    }
+
+    impl FromStr for Rounded {
+        type Err = std::num::ParseFloatError;
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            s.parse().map(Rounded)
+        }
+    }
+
+    #[test]
+    fn args_round_trip() {
+        assert_eq!(check_arg_roundtrip(&-1.5f32), Ok(()));
+        assert_eq!(
+            check_arg_roundtrip(&"say \"hi\" to $x; \\".to_owned()),
+            Ok(())
+        );
+        assert_eq!(check_arg_roundtrip(&Rounded(0.5)), Ok(()));
+        assert_eq!(
+            check_arg_roundtrip(&Rounded(0.25)),
+            Err("Rounded(0.25) is parsed back as Rounded(0.2)".to_owned())
+        );
+
+        let signature: CliSignature = "<name: String> [ids: u32...]".parse().unwrap();
+        let args = ["Big Bob".to_owned(), "1".to_owned(), "2".to_owned()];
+        assert_eq!(check_args_roundtrip(&signature, &args), Ok(()));
+        assert!(check_args_roundtrip(&signature, &args[1..]).is_ok());
+        assert!(check_args_roundtrip(&signature, &[]).is_err());
+    }
+
+    #[cfg(feature = "proptest")]
+    #[test]
+    fn generated_args_round_trip() {
+        use proptest::prelude::*;
+
+        proptest_arg_roundtrip(any::<String>());
+        proptest_arg_roundtrip(any::<f64>());
+        proptest_args_roundtrip("<name: String> <pos: f32> [target: Entity] [tags: String...]");
+    }
+
+    #[test]
+    fn output_snapshots() {
+        let mut harness = harness();
+        harness
+            .run("greet Bob; greet")
+            .assert_output_snapshot("testing_greet");
+
+        let path = std::env::temp_dir().join(format!("iyes_cli_{}.snap", std::process::id()));
+        let snapshot = harness.run("greet Bob").snapshot();
+        assert_eq!(snapshot, "> greet Bob\nHello, Bob!\n");
+        assert_eq!(compare_snapshot(&path, &snapshot, false), Ok(()));
+        assert_eq!(compare_snapshot(&path, &snapshot, false), Ok(()));
+        let changed = harness.run("greet Alice").snapshot();
+        let message = compare_snapshot(&path, &changed, false).unwrap_err();
+        assert!(message.ends_with("line 1:\n  - > greet Bob\n  + > greet Alice\nline 2:\n  - Hello, Bob!\n  + Hello, Alice!"));
+        assert_eq!(compare_snapshot(&path, &changed, true), Ok(()));
+        assert_eq!(compare_snapshot(&path, &changed, false), Ok(()));
+        std::fs::remove_file(path).unwrap();
+    }
+}
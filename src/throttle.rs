@@ -0,0 +1,61 @@
+//! Cooldowns and rate limits of "console commands".
+//!
+//! Useful for commands exposed to untrusted players (for example, through
+//! chat). A command with a cooldown (see
+//! [`set_clicommand_cooldown`](crate::CliCommandsRegisterExt::set_clicommand_cooldown))
+//! cannot run again until the cooldown has passed since its last run. A
+//! command with a rate limit (see
+//! [`set_clicommand_rate_limit`](crate::CliCommandsRegisterExt::set_clicommand_rate_limit))
+//! cannot run more than a number of times per second. Otherwise, it fails
+//! with [`CliError::OnCooldown`](crate::error::CliError::OnCooldown).
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bevy::utils::Instant;
+
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// The throttling settings and recent runs of a command
+#[derive(Clone, Default)]
+pub(crate) struct CliThrottle {
+    pub(crate) cooldown: Option<Duration>,
+    pub(crate) max_per_second: Option<u32>,
+    last_run: Option<Instant>,
+    /// The runs within the last second, if there is a rate limit
+    recent: VecDeque<Instant>,
+}
+
+impl CliThrottle {
+    /// Check if the command may run now, or how long until it may
+    pub(crate) fn check(&mut self, now: Instant) -> Result<(), Duration> {
+        let mut remaining = Duration::ZERO;
+        if let (Some(cooldown), Some(last_run)) = (self.cooldown, self.last_run) {
+            remaining = cooldown.saturating_sub(now.duration_since(last_run));
+        }
+        if let Some(max) = self.max_per_second {
+            while self
+                .recent
+                .front()
+                .is_some_and(|&run| now.duration_since(run) >= RATE_WINDOW)
+            {
+                self.recent.pop_front();
+            }
+            if self.recent.len() >= max as usize {
+                let oldest = self.recent.front().copied().unwrap_or(now);
+                remaining = remaining.max(RATE_WINDOW.saturating_sub(now.duration_since(oldest)));
+            }
+        }
+        match remaining.is_zero() {
+            true => Ok(()),
+            false => Err(remaining),
+        }
+    }
+    /// Remember that the command ran
+    pub(crate) fn record(&mut self, now: Instant) {
+        self.last_run = Some(now);
+        if self.max_per_second.is_some() {
+            self.recent.push_back(now);
+        }
+    }
+}
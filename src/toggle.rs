@@ -0,0 +1,105 @@
+//! "Console commands" that show and set a field of a resource.
+//!
+//! Many commands just read or change one value, like `godmode` or
+//! `timescale`. [`register_toggle_command`](CliToggleRegisterExt::register_toggle_command)
+//! creates such a command from a resource type, and a function returning
+//! the field:
+//!
+//! ```rust,ignore
+//! app.register_toggle_command("godmode", |cheats: &mut Cheats| &mut cheats.god_mode)
+//!     .register_toggle_command("timescale", |time: &mut Time<Virtual>| &mut time.relative_speed);
+//! ```
+//!
+//! ```text
+//! godmode          # prints `godmode = false`
+//! godmode true     # sets it (also `on`, `off`, `1` or `0`, for `bool`)
+//! godmode toggle   # flips a `bool`
+//! ```
+//!
+//! The field can be of any type that can be parsed from and formatted to a
+//! string. If the resource does not exist, the command fails, saying so.
+
+use std::any::Any;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use bevy::prelude::*;
+
+use crate::output::CliOutput;
+use crate::short_name::ShortName;
+use crate::CliCommandsRegisterExt;
+
+/// Provides a method for registering commands that show and set a field of a resource
+pub trait CliToggleRegisterExt {
+    /// Create a new "console command" showing or setting a field of a resource
+    ///
+    /// See [`toggle`](crate::toggle). If a command with the same name
+    /// already exists, it is replaced.
+    fn register_toggle_command<R, T, F>(&mut self, name: &str, field: F) -> &mut Self
+    where
+        R: Resource,
+        T: FromStr + Display + Send + Sync + 'static,
+        F: Fn(&mut R) -> &mut T + Send + Sync + 'static;
+}
+
+impl<C: CliCommandsRegisterExt> CliToggleRegisterExt for C {
+    fn register_toggle_command<R, T, F>(&mut self, name: &str, field: F) -> &mut Self
+    where
+        R: Resource,
+        T: FromStr + Display + Send + Sync + 'static,
+        F: Fn(&mut R) -> &mut T + Send + Sync + 'static,
+    {
+        let usage = usage_of::<T>(name);
+        let usage_owned = usage.clone();
+        let command = name.to_owned();
+        self.register_clicommand_args(
+            name,
+            move |In(args): In<Vec<String>>, mut resource: ResMut<R>, mut output: CliOutput| {
+                match args.as_slice() {
+                    [] => {}
+                    [arg] => {
+                        if let Err(e) = set_value(field(&mut resource), arg) {
+                            output.fail(format!("Cannot set {} to {:?}: {}!", command, arg, e));
+                            return;
+                        }
+                    }
+                    _ => {
+                        output.fail(format!("Usage: {}", usage));
+                        return;
+                    }
+                }
+                let value = field(resource.bypass_change_detection());
+                output.println(format!("{} = {}", command, value));
+            },
+        )
+        .require_clicommand_resource::<R>(name, &ShortName::of::<R>().to_string())
+        .set_clicommand_description(name, "Show or set a value")
+        .set_clicommand_usage(name, &usage_owned)
+    }
+}
+
+fn is_bool<T: 'static>() -> bool {
+    std::any::TypeId::of::<T>() == std::any::TypeId::of::<bool>()
+}
+
+fn usage_of<T: 'static>(name: &str) -> String {
+    match is_bool::<T>() {
+        true => format!("{} [true|false|toggle]", name),
+        false => format!("{} [value]", name),
+    }
+}
+
+/// Set a value from an arg, also accepting `toggle`, `on`, `off`, `1` and `0` for `bool`
+fn set_value<T: FromStr + 'static>(value: &mut T, arg: &str) -> Result<(), String> {
+    if let Some(flag) = (value as &mut dyn Any).downcast_mut::<bool>() {
+        *flag = match arg {
+            "toggle" => !*flag,
+            "true" | "on" | "1" => true,
+            "false" | "off" | "0" => false,
+            _ => return Err("expected true, false or toggle".to_owned()),
+        };
+        return Ok(());
+    }
+    *value = arg.parse().map_err(|_| "invalid value".to_owned())?;
+    Ok(())
+}
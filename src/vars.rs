@@ -0,0 +1,573 @@
+//! Console variables ("cvars"): named, typed values that can be read and set
+//! from command strings.
+//!
+//! Requires the `cvars` cargo feature (enabled by default).
+//!
+//! Register a variable with [`register_clivar`](CliVarsRegisterExt::register_clivar).
+//! Then, typing its name (like `gravity`) prints its value, and typing its
+//! name with a value (like `gravity 3.0`) sets it. Commands take priority
+//! over variables with the same name.
+//!
+//! Variables are stored in the [`CliVars`] resource, where your systems can
+//! read them. Whenever a variable is changed, a [`CliVarChanged`] event is sent.
+//!
+//! Names like `r.shadows` or `audio.volume` group variables together. The
+//! built-in `get`, `reset` and `list cvars` commands accept wildcard patterns
+//! (like `r.*`) to work with many variables at once.
+//!
+//! The built-in `cvar diff` command lists the variables that differ from
+//! their defaults, and `cvar reset <name|--all>` resets them.
+//!
+//! Presets are named snapshots of all variables, stored in [`CliVarPresets`].
+//! The built-in `preset save <name>` takes a snapshot, `preset apply <name>`
+//! sets every variable back to the value it had, and `preset list` lists them.
+
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Bound;
+use std::str::FromStr;
+
+use bevy::prelude::*;
+
+use crate::glob::glob_match;
+use crate::output::{cli_error, cli_println, CliOutput};
+use crate::table::{CliAlign, CliTable};
+use crate::{failln, CliCommandsRegisterExt};
+
+/// Types that can be used as console variables
+///
+/// Implemented automatically for all types that can be parsed from and
+/// formatted to strings, and compared (for bounds), such as numbers, `bool`, and `String`.
+pub trait CliVarValue: FromStr + ToString + PartialOrd + Clone + Send + Sync + 'static {}
+
+impl<T: FromStr + ToString + PartialOrd + Clone + Send + Sync + 'static> CliVarValue for T {}
+
+/// Event sent when a console variable is changed
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct CliVarChanged {
+    pub name: String,
+}
+
+/// Error when a console variable cannot be accessed or set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliVarError {
+    /// There is no variable with the given name
+    NotFound(String),
+    /// The variable has a different type than requested
+    WrongType(String),
+    /// The string is not a valid value for the variable
+    InvalidValue { name: String, value: String },
+}
+
+impl fmt::Display for CliVarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliVarError::NotFound(name) => write!(f, "CliVar {:?} not found", name),
+            CliVarError::WrongType(name) => write!(f, "CliVar {:?} has a different type", name),
+            CliVarError::InvalidValue { name, value } => {
+                write!(f, "invalid value for CliVar {:?}: {:?}", name, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CliVarError {}
+
+/// Stores all the console variables
+///
+/// Variables are kept sorted by name, so that wildcard patterns starting
+/// with a fixed prefix only need to look at the variables with that prefix.
+#[derive(Resource, Default)]
+pub struct CliVars {
+    vars: BTreeMap<String, Box<dyn CliVarSlot>>,
+}
+
+trait CliVarSlot: Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn get_str(&self) -> String;
+    fn default_str(&self) -> String;
+    fn is_default(&self) -> bool;
+    /// Parse and set the value, returning whether it had to be clamped
+    fn set_str(&mut self, s: &str) -> Result<bool, ()>;
+    fn reset(&mut self);
+}
+
+struct CliVarTyped<T: CliVarValue> {
+    value: T,
+    default: T,
+    bounds: Option<(T, T)>,
+}
+
+impl<T: CliVarValue> CliVarTyped<T> {
+    fn clamp(&mut self) -> bool {
+        let Some((min, max)) = &self.bounds else {
+            return false;
+        };
+        if self.value < *min {
+            self.value = min.clone();
+            true
+        } else if self.value > *max {
+            self.value = max.clone();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<T: CliVarValue> CliVarSlot for CliVarTyped<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn get_str(&self) -> String {
+        self.value.to_string()
+    }
+    fn default_str(&self) -> String {
+        self.default.to_string()
+    }
+    fn is_default(&self) -> bool {
+        self.value == self.default
+    }
+    fn set_str(&mut self, s: &str) -> Result<bool, ()> {
+        self.value = s.parse().map_err(|_| ())?;
+        Ok(self.clamp())
+    }
+    fn reset(&mut self) {
+        self.value = self.default.clone();
+    }
+}
+
+impl CliVars {
+    /// Get the value of a variable
+    pub fn get<T: CliVarValue>(&self, name: &str) -> Option<&T> {
+        self.typed::<T>(name).map(|var| &var.value)
+    }
+    /// Set the value of a variable, clamping it to its bounds
+    ///
+    /// This does not send [`CliVarChanged`]; use
+    /// [`set_clivar`](CliVarsRegisterExt::set_clivar) for that.
+    pub fn set<T: CliVarValue>(&mut self, name: &str, value: T) -> Result<(), CliVarError> {
+        let var = self
+            .vars
+            .get_mut(name)
+            .ok_or_else(|| CliVarError::NotFound(name.to_owned()))?
+            .as_any_mut()
+            .downcast_mut::<CliVarTyped<T>>()
+            .ok_or_else(|| CliVarError::WrongType(name.to_owned()))?;
+        var.value = value;
+        var.clamp();
+        Ok(())
+    }
+    /// Get the value of a variable, formatted as a string
+    pub fn get_str(&self, name: &str) -> Option<String> {
+        self.vars.get(name).map(|var| var.get_str())
+    }
+    /// Get the default value of a variable, formatted as a string
+    pub fn default_str(&self, name: &str) -> Option<String> {
+        self.vars.get(name).map(|var| var.default_str())
+    }
+    /// Parse a string and set it as the value of a variable, clamping it to its bounds
+    ///
+    /// Returns whether the value had to be clamped.
+    pub fn set_str(&mut self, name: &str, value: &str) -> Result<bool, CliVarError> {
+        self.vars
+            .get_mut(name)
+            .ok_or_else(|| CliVarError::NotFound(name.to_owned()))?
+            .set_str(value)
+            .map_err(|()| CliVarError::InvalidValue {
+                name: name.to_owned(),
+                value: value.to_owned(),
+            })
+    }
+    /// Is the variable set to its default value?
+    ///
+    /// Returns `None` if there is no such variable.
+    pub fn is_default(&self, name: &str) -> Option<bool> {
+        self.vars.get(name).map(|var| var.is_default())
+    }
+    /// Iterate over the names of the variables that differ from their defaults, sorted
+    pub fn iter_changed(&self) -> impl Iterator<Item = &str> {
+        self.vars
+            .iter()
+            .filter(|(_, var)| !var.is_default())
+            .map(|(name, _)| name.as_str())
+    }
+    /// Reset a variable to its default value
+    pub fn reset(&mut self, name: &str) -> Result<(), CliVarError> {
+        self.vars
+            .get_mut(name)
+            .ok_or_else(|| CliVarError::NotFound(name.to_owned()))?
+            .reset();
+        Ok(())
+    }
+    /// Remove a variable, returning whether it existed
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.vars.remove(name).is_some()
+    }
+    /// Is there a variable with the given name?
+    pub fn contains(&self, name: &str) -> bool {
+        self.vars.contains_key(name)
+    }
+    /// Iterate over the names of all variables, sorted
+    pub fn iter_names(&self) -> impl Iterator<Item = &str> {
+        self.vars.keys().map(|s| s.as_str())
+    }
+    /// Iterate over the names of the variables matching a wildcard pattern, sorted
+    ///
+    /// `*` in the pattern matches any sequence of characters, so `r.*`
+    /// matches all variables starting with `r.`.
+    pub fn iter_matching<'a>(&'a self, pattern: &'a str) -> impl Iterator<Item = &'a str> {
+        let prefix = pattern.split('*').next().unwrap_or_default();
+        self.vars
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .map(|(name, _)| name.as_str())
+            .take_while(move |name| name.starts_with(prefix))
+            .filter(move |name| glob_match(pattern, name))
+    }
+    fn typed<T: CliVarValue>(&self, name: &str) -> Option<&CliVarTyped<T>> {
+        self.vars.get(name)?.as_any().downcast_ref()
+    }
+}
+
+/// Provides methods for managing console variables
+pub trait CliVarsRegisterExt {
+    /// Create a new console variable with the given name and default value
+    ///
+    /// If a variable with the same name already exists, it is replaced.
+    fn register_clivar<T: CliVarValue>(&mut self, name: &str, default: T) -> &mut Self;
+
+    /// Restrict the values of a console variable to the given (inclusive) range
+    ///
+    /// Values outside of the range are clamped. The variable must already be registered.
+    fn set_clivar_bounds<T: CliVarValue>(&mut self, name: &str, min: T, max: T) -> &mut Self;
+
+    /// Set the value of a console variable, sending [`CliVarChanged`]
+    fn set_clivar<T: CliVarValue>(&mut self, name: &str, value: T) -> &mut Self;
+}
+
+impl CliVarsRegisterExt for World {
+    fn register_clivar<T: CliVarValue>(&mut self, name: &str, default: T) -> &mut Self {
+        self.init_resource::<CliVars>();
+        let var = CliVarTyped {
+            value: default.clone(),
+            default,
+            bounds: None,
+        };
+        self.resource_mut::<CliVars>()
+            .vars
+            .insert(name.to_owned(), Box::new(var));
+        self
+    }
+    fn set_clivar_bounds<T: CliVarValue>(&mut self, name: &str, min: T, max: T) -> &mut Self {
+        let var = self.get_resource_mut::<CliVars>().and_then(|vars| {
+            vars.into_inner()
+                .vars
+                .get_mut(name)?
+                .as_any_mut()
+                .downcast_mut::<CliVarTyped<T>>()
+        });
+        match var {
+            Some(var) => {
+                var.bounds = Some((min, max));
+                var.default = clamp_value(var.default.clone(), var.bounds.as_ref());
+                var.clamp();
+            }
+            None => error!("Cannot set bounds of unknown CliVar {:?}!", name),
+        }
+        self
+    }
+    fn set_clivar<T: CliVarValue>(&mut self, name: &str, value: T) -> &mut Self {
+        let result = match self.get_resource_mut::<CliVars>() {
+            Some(mut vars) => vars.set(name, value),
+            None => Err(CliVarError::NotFound(name.to_owned())),
+        };
+        match result {
+            Ok(()) => {
+                self.send_event(CliVarChanged {
+                    name: name.to_owned(),
+                });
+            }
+            Err(e) => error!("{}!", e),
+        }
+        self
+    }
+}
+
+impl CliVarsRegisterExt for App {
+    fn register_clivar<T: CliVarValue>(&mut self, name: &str, default: T) -> &mut Self {
+        self.world_mut().register_clivar(name, default);
+        self
+    }
+    fn set_clivar_bounds<T: CliVarValue>(&mut self, name: &str, min: T, max: T) -> &mut Self {
+        self.world_mut().set_clivar_bounds(name, min, max);
+        self
+    }
+    fn set_clivar<T: CliVarValue>(&mut self, name: &str, value: T) -> &mut Self {
+        self.world_mut().set_clivar(name, value);
+        self
+    }
+}
+
+fn clamp_value<T: CliVarValue>(value: T, bounds: Option<&(T, T)>) -> T {
+    match bounds {
+        Some((min, _)) if value < *min => min.clone(),
+        Some((_, max)) if value > *max => max.clone(),
+        _ => value,
+    }
+}
+
+/// Handle a command string naming a console variable, as `run_cli` does
+///
+/// Returns `None` if there is no such variable, otherwise whether it succeeded.
+pub(crate) fn run_clivar(world: &mut World, name: &str, args: &[String]) -> Option<bool> {
+    if !world
+        .get_resource::<CliVars>()
+        .is_some_and(|vars| vars.contains(name))
+    {
+        return None;
+    }
+    match args {
+        [] => {
+            let value = world
+                .resource::<CliVars>()
+                .get_str(name)
+                .unwrap_or_default();
+            cli_println(world, format!("{} = {}", name, value));
+            Some(true)
+        }
+        [value] => {
+            let result = world.resource_mut::<CliVars>().set_str(name, value);
+            match result {
+                Ok(clamped) => {
+                    if clamped {
+                        let value = world
+                            .resource::<CliVars>()
+                            .get_str(name)
+                            .unwrap_or_default();
+                        cli_println(world, format!("{} = {} (clamped)", name, value));
+                    }
+                    world.send_event(CliVarChanged {
+                        name: name.to_owned(),
+                    });
+                    Some(true)
+                }
+                Err(e) => {
+                    cli_error(world, format!("{}!", e));
+                    Some(false)
+                }
+            }
+        }
+        _ => {
+            cli_error(world, format!("Usage: {} [value]", name));
+            Some(false)
+        }
+    }
+}
+
+/// Named snapshots of the values of all console variables
+///
+/// Used by the built-in `preset` command. Presets are kept in memory only.
+#[derive(Resource, Default, Debug, Clone)]
+pub struct CliVarPresets {
+    presets: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl CliVarPresets {
+    /// Save the current values of all variables as a preset
+    ///
+    /// If the preset already exists, it is replaced.
+    pub fn save(&mut self, name: &str, vars: &CliVars) {
+        let values = vars
+            .iter_names()
+            .filter_map(|var| Some((var.to_owned(), vars.get_str(var)?)))
+            .collect();
+        self.presets.insert(name.to_owned(), values);
+    }
+    /// Set all variables to the values saved in a preset
+    ///
+    /// Returns the names of the variables that were changed, or `None` if
+    /// there is no such preset. Variables that no longer exist, or whose
+    /// saved value is no longer valid, are skipped.
+    pub fn apply(&self, name: &str, vars: &mut CliVars) -> Option<Vec<String>> {
+        let values = self.presets.get(name)?;
+        let mut changed = Vec::new();
+        for (var, value) in values {
+            if vars.get_str(var).as_ref() == Some(value) {
+                continue;
+            }
+            match vars.set_str(var, value) {
+                Ok(_) => changed.push(var.clone()),
+                Err(e) => warn!("Skipping CliVar in preset {:?}: {}", name, e),
+            }
+        }
+        Some(changed)
+    }
+    /// Remove a preset, returning whether it existed
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.presets.remove(name).is_some()
+    }
+    /// Iterate over the saved values of a preset
+    pub fn get(&self, name: &str) -> Option<impl Iterator<Item = (&str, &str)>> {
+        let values = self.presets.get(name)?;
+        Some(values.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+    }
+    /// Iterate over the names of all presets, in order
+    pub fn iter_names(&self) -> impl Iterator<Item = &str> {
+        self.presets.keys().map(|s| s.as_str())
+    }
+}
+
+/// `get <pattern>`: print the values of the console variables matching a wildcard pattern
+fn get(In(args): In<Vec<String>>, vars: Res<CliVars>, mut output: CliOutput) {
+    let [pattern] = args.as_slice() else {
+        output.fail("Usage: get <pattern>");
+        return;
+    };
+    let mut found = false;
+    for name in vars.iter_matching(pattern) {
+        found = true;
+        output.println(format!(
+            "{} = {}",
+            name,
+            vars.get_str(name).unwrap_or_default()
+        ));
+    }
+    if !found {
+        failln!(
+            output,
+            "cvar.no_match",
+            "No CliVars match \"{pattern}\"!",
+            pattern = pattern
+        );
+    }
+}
+
+/// `reset <pattern>`: reset the console variables matching a wildcard pattern to their defaults
+fn reset(
+    In(args): In<Vec<String>>,
+    mut vars: ResMut<CliVars>,
+    mut evw_changed: EventWriter<CliVarChanged>,
+    mut output: CliOutput,
+) {
+    let [pattern] = args.as_slice() else {
+        output.fail("Usage: reset <pattern>");
+        return;
+    };
+    let names: Vec<String> = vars.iter_matching(pattern).map(|s| s.to_owned()).collect();
+    if names.is_empty() {
+        failln!(
+            output,
+            "cvar.no_match",
+            "No CliVars match \"{pattern}\"!",
+            pattern = pattern
+        );
+        return;
+    }
+    for name in names {
+        if vars.reset(&name).is_ok() {
+            evw_changed.send(CliVarChanged { name });
+        }
+    }
+}
+
+/// `cvar diff | cvar reset <name|--all>`: show or reset changed console variables
+fn cvar(
+    In(args): In<Vec<String>>,
+    mut vars: ResMut<CliVars>,
+    mut evw_changed: EventWriter<CliVarChanged>,
+    mut output: CliOutput,
+) {
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    match args.as_slice() {
+        ["diff"] => {
+            let mut table = CliTable::new()
+                .with_column("CVAR", CliAlign::Left)
+                .with_column("VALUE", CliAlign::Left)
+                .with_column("DEFAULT", CliAlign::Left);
+            for name in vars.iter_changed() {
+                table.add_row([
+                    name.to_owned(),
+                    vars.get_str(name).unwrap_or_default(),
+                    vars.default_str(name).unwrap_or_default(),
+                ]);
+            }
+            output.println(table.to_string());
+        }
+        ["reset", "--all"] => {
+            let names: Vec<String> = vars.iter_changed().map(|s| s.to_owned()).collect();
+            for name in names {
+                if vars.reset(&name).is_ok() {
+                    evw_changed.send(CliVarChanged { name });
+                }
+            }
+        }
+        ["reset", name] => match vars.reset(name) {
+            Ok(()) => {
+                evw_changed.send(CliVarChanged {
+                    name: (*name).to_owned(),
+                });
+            }
+            Err(e) => output.fail(format!("{}!", e)),
+        },
+        _ => output.fail("Usage: cvar diff | cvar reset <name|--all>"),
+    }
+}
+
+/// `preset <list|save|apply> [name]`: manage named snapshots of all cvars
+fn preset(
+    In(args): In<Vec<String>>,
+    mut vars: ResMut<CliVars>,
+    mut presets: ResMut<CliVarPresets>,
+    mut evw_changed: EventWriter<CliVarChanged>,
+    mut output: CliOutput,
+) {
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    match args.as_slice() {
+        ["list"] => {
+            for name in presets.iter_names() {
+                output.println(name);
+            }
+        }
+        ["save", name] => presets.save(name, &vars),
+        ["apply", name] => match presets.apply(name, &mut vars) {
+            Some(changed) => {
+                for name in changed {
+                    evw_changed.send(CliVarChanged { name });
+                }
+            }
+            None => failln!(
+                output,
+                "preset.not_found",
+                "Preset \"{name}\" not found!",
+                name = name
+            ),
+        },
+        _ => output.fail("Usage: preset list | preset save <name> | preset apply <name>"),
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CliVars>();
+    app.init_resource::<CliVarPresets>();
+    app.add_event::<CliVarChanged>();
+    app.register_clicommand_args("get", get)
+        .set_clicommand_description("get", "Print the values of console variables")
+        .set_clicommand_usage("get", "get <pattern>");
+    app.register_clicommand_args("reset", reset)
+        .set_clicommand_description("reset", "Reset console variables to their defaults")
+        .set_clicommand_usage("reset", "reset <pattern>");
+    app.register_clicommand_args("cvar", cvar)
+        .set_clicommand_description("cvar", "Show or reset changed console variables")
+        .set_clicommand_usage("cvar", "cvar diff | cvar reset <name|--all>");
+    app.register_clicommand_args("preset", preset)
+        .set_clicommand_description("preset", "Save and apply snapshots of console variables")
+        .set_clicommand_usage(
+            "preset",
+            "preset list | preset save <name> | preset apply <name>",
+        );
+}
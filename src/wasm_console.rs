@@ -0,0 +1,104 @@
+//! Running commands from the browser's devtools console, in web builds.
+//!
+//! Requires the `wasm-console` cargo feature, and only exists when building
+//! for `wasm32`. Add [`CliWasmConsolePlugin`] (together with
+//! [`CliPlugin`](crate::CliPlugin)), and a global JavaScript function is
+//! defined, which runs a command string and returns a `Promise` of its
+//! output:
+//!
+//! ```js
+//! > await runCli("spawn 10 10")
+//! < "Spawned 1 sprite"
+//! ```
+//!
+//! The command runs in [`PreUpdate`] of the next frame. The promise is
+//! resolved with the output lines joined by newlines, or rejected with them
+//! if the command fails. Commands run with the source `browser` (see
+//! [`quota`](crate::quota)).
+
+use std::cell::RefCell;
+
+use bevy::prelude::*;
+use js_sys::{Function, Promise};
+use wasm_bindgen::prelude::*;
+
+use crate::output::CliCommandsOutputExt;
+use crate::quota::with_cli_source;
+use crate::status::CliStatus;
+
+/// Plugin that defines a JavaScript function for running commands
+pub struct CliWasmConsolePlugin {
+    /// The name of the global function
+    pub function_name: String,
+}
+
+impl Default for CliWasmConsolePlugin {
+    fn default() -> Self {
+        CliWasmConsolePlugin {
+            function_name: "runCli".to_owned(),
+        }
+    }
+}
+
+impl Plugin for CliWasmConsolePlugin {
+    fn build(&self, app: &mut App) {
+        let function = Closure::<dyn Fn(String) -> Promise>::new(submit);
+        let defined = js_sys::Reflect::set(
+            &js_sys::global(),
+            &JsValue::from_str(&self.function_name),
+            function.as_ref(),
+        );
+        if defined.is_err() {
+            error!(
+                "Cannot define JavaScript function {:?} for running CliCommands!",
+                self.function_name
+            );
+            return;
+        }
+        // The function must live as long as the page
+        function.forget();
+        app.add_systems(PreUpdate, run_pending);
+    }
+}
+
+/// A command string submitted from JavaScript, with its promise callbacks
+struct CliWasmRequest {
+    cli: String,
+    resolve: Function,
+    reject: Function,
+}
+
+thread_local! {
+    // wasm is single-threaded, and JS values cannot be sent between threads anyway
+    static PENDING: RefCell<Vec<CliWasmRequest>> = const { RefCell::new(Vec::new()) };
+}
+
+fn submit(cli: String) -> Promise {
+    Promise::new(&mut |resolve, reject| {
+        let request = CliWasmRequest {
+            cli: cli.clone(),
+            resolve,
+            reject,
+        };
+        PENDING.with_borrow_mut(|pending| pending.push(request));
+    })
+}
+
+fn run_pending(world: &mut World) {
+    let pending = PENDING.with_borrow_mut(std::mem::take);
+    for request in pending {
+        let output = with_cli_source(world, "browser", |world| {
+            world.run_cli_captured(&request.cli)
+        });
+        let text: Vec<&str> = output.iter().map(|line| line.text.as_str()).collect();
+        let success = world
+            .get_resource::<CliStatus>()
+            .is_some_and(|status| status.last_success() == Some(true));
+        let callback = if success {
+            &request.resolve
+        } else {
+            &request.reject
+        };
+        let _ = callback.call1(&JsValue::NULL, &JsValue::from_str(&text.join("\n")));
+    }
+}
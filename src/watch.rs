@@ -0,0 +1,363 @@
+//! Watching the values of commands and console variables, in an overlay.
+//!
+//! Requires the `watch` cargo feature. Add [`CliWatchPlugin`] (together with
+//! [`CliPlugin`](crate::CliPlugin)), then watch things from the console:
+//!
+//! ```text
+//! watch add gravity
+//! watch add --every 60 count_entities
+//! watch list
+//! watch remove 2
+//! ```
+//!
+//! A watch is evaluated every few frames ([`CliWatchPlugin::every`] by
+//! default), and its latest value is shown in a small overlay, in a corner
+//! of the screen. The value of a watched console variable (see
+//! [`vars`](crate::vars)) is read directly. Anything else is run as a
+//! command string, and its value is its output; if it fails, its errors are
+//! shown instead, in red. The output of watches is not added to the
+//! [`CliOutputLog`](crate::output::CliOutputLog).
+//!
+//! Watches run with the source of the command string that added them (see
+//! [`quota`](crate::quota)), so they cannot do more than it could.
+
+use bevy::prelude::*;
+
+use crate::output::{evaluate_cli, CliOutput};
+use crate::quota::with_cli_source;
+use crate::source::CliSource;
+use crate::table::{CliAlign, CliTable};
+use crate::{quote_cli_arg, CliCommandsRegisterExt};
+
+const USAGE: &str = "Usage: watch [list | add [--every <frames>] <command...> | remove <id|all>]";
+
+/// Plugin that adds the `watch` command and its overlay
+pub struct CliWatchPlugin {
+    /// How often watches are evaluated, in frames, unless set with `--every`
+    pub every: u32,
+}
+
+impl Default for CliWatchPlugin {
+    fn default() -> Self {
+        CliWatchPlugin { every: 10 }
+    }
+}
+
+impl Plugin for CliWatchPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CliWatches {
+            watches: Vec::new(),
+            next_id: 0,
+            every: self.every.max(1),
+        });
+        app.add_systems(Startup, spawn_overlay);
+        app.add_systems(
+            Update,
+            (evaluate_watches, update_overlay)
+                .chain()
+                .in_set(CliWatchSet),
+        );
+        app.register_clicommand_args("watch", watch)
+            .set_clicommand_description("watch", "Show the values of commands in an overlay")
+            .set_clicommand_usage(
+                "watch",
+                "watch [list | add [--every <frames>] <command...> | remove <id|all>]",
+            );
+    }
+}
+
+/// System set for the systems that evaluate watches and show them
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CliWatchSet;
+
+/// A command string or console variable, evaluated every few frames
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliWatch {
+    pub id: u64,
+    /// The command string, or the name of the console variable
+    pub expression: String,
+    /// How often it is evaluated, in frames
+    pub every: u32,
+    /// The latest value, if it has been evaluated
+    pub value: Option<Result<String, String>>,
+    source: Option<CliSource>,
+    /// Frames until it is evaluated next
+    countdown: u32,
+}
+
+/// The watched command strings and console variables
+#[derive(Resource, Debug)]
+pub struct CliWatches {
+    watches: Vec<CliWatch>,
+    next_id: u64,
+    every: u32,
+}
+
+impl CliWatches {
+    /// Watch a command string or console variable, evaluated every `every`
+    /// frames (or the default of the plugin), returning its id
+    ///
+    /// It is first evaluated in the next frame.
+    pub fn add(&mut self, expression: &str, every: Option<u32>) -> u64 {
+        self.push(expression, every, None)
+    }
+    fn push(&mut self, expression: &str, every: Option<u32>, source: Option<CliSource>) -> u64 {
+        self.next_id += 1;
+        self.watches.push(CliWatch {
+            id: self.next_id,
+            expression: expression.to_owned(),
+            every: every.unwrap_or(self.every).max(1),
+            value: None,
+            source,
+            countdown: 0,
+        });
+        self.next_id
+    }
+    /// Stop watching, returning whether there was a watch with this id
+    pub fn remove(&mut self, id: u64) -> bool {
+        let len = self.watches.len();
+        self.watches.retain(|watch| watch.id != id);
+        self.watches.len() != len
+    }
+    /// Stop all watches
+    pub fn clear(&mut self) {
+        self.watches.clear();
+    }
+    /// The watches, in the order they were added
+    pub fn watches(&self) -> &[CliWatch] {
+        &self.watches
+    }
+    pub fn len(&self) -> usize {
+        self.watches.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.watches.is_empty()
+    }
+}
+
+fn evaluate_watches(world: &mut World) {
+    let Some(mut watches) = world.get_resource_mut::<CliWatches>() else {
+        return;
+    };
+    // Only changed values should update the overlay
+    let watches = watches.bypass_change_detection();
+    let mut due = Vec::new();
+    for watch in &mut watches.watches {
+        if watch.countdown == 0 {
+            watch.countdown = watch.every;
+            due.push((watch.id, watch.expression.clone(), watch.source.clone()));
+        }
+        watch.countdown -= 1;
+    }
+    for (id, expression, source) in due {
+        let value = match source {
+            Some(source) => {
+                with_cli_source(world, source, |world| evaluate_cli(world, &expression))
+            }
+            None => evaluate_cli(world, &expression),
+        };
+        let mut watches = world.resource_mut::<CliWatches>();
+        let changed = watches
+            .watches
+            .iter()
+            .any(|watch| watch.id == id && watch.value.as_ref() != Some(&value));
+        if changed {
+            if let Some(watch) = watches.watches.iter_mut().find(|watch| watch.id == id) {
+                watch.value = Some(value);
+            }
+        }
+    }
+}
+
+/// Marker for the root entity of the watch overlay
+#[derive(Component)]
+pub struct CliWatchRoot;
+
+const VALUE_COLOR: Color = Color::srgb(0.85, 0.85, 0.85);
+const ERROR_COLOR: Color = Color::srgb(1.0, 0.4, 0.4);
+
+fn spawn_overlay(mut commands: Commands) {
+    commands.spawn((
+        CliWatchRoot,
+        Node {
+            display: Display::None,
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            right: Val::Px(8.0),
+            flex_direction: FlexDirection::Column,
+            padding: UiRect::all(Val::Px(6.0)),
+            row_gap: Val::Px(2.0),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        GlobalZIndex(i32::MAX - 2),
+    ));
+}
+
+fn update_overlay(
+    watches: Res<CliWatches>,
+    mut q_root: Query<(Entity, &mut Node), With<CliWatchRoot>>,
+    mut commands: Commands,
+) {
+    if !watches.is_changed() {
+        return;
+    }
+    let Ok((root, mut node)) = q_root.get_single_mut() else {
+        return;
+    };
+    node.display = match watches.is_empty() {
+        true => Display::None,
+        false => Display::Flex,
+    };
+    commands
+        .entity(root)
+        .despawn_descendants()
+        .with_children(|parent| {
+            for watch in &watches.watches {
+                let (value, color) = match &watch.value {
+                    None => ("...", VALUE_COLOR),
+                    Some(Ok(value)) => (value.as_str(), VALUE_COLOR),
+                    Some(Err(error)) => (error.as_str(), ERROR_COLOR),
+                };
+                parent.spawn((
+                    Text::new(format!("{}: {}", watch.expression, value)),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(color),
+                ));
+            }
+        });
+}
+
+/// `watch [list | add [--every <frames>] <command...> | remove <id|all>]`:
+/// watch command strings and console variables in an overlay, or list them
+fn watch(In(args): In<Vec<String>>, mut watches: ResMut<CliWatches>, mut output: CliOutput) {
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    match args.as_slice() {
+        [] | ["list"] => {
+            if watches.is_empty() {
+                output.println("Nothing is watched");
+                return;
+            }
+            let mut table = CliTable::new()
+                .with_column("ID", CliAlign::Right)
+                .with_column("WATCH", CliAlign::Left)
+                .with_column("EVERY", CliAlign::Right)
+                .with_column("VALUE", CliAlign::Left);
+            for watch in watches.watches() {
+                let value = match &watch.value {
+                    None => "-".to_owned(),
+                    Some(Ok(value)) => value.replace('\n', " "),
+                    Some(Err(error)) => format!("error: {}", error.replace('\n', " ")),
+                };
+                table.add_row([
+                    watch.id.to_string(),
+                    watch.expression.clone(),
+                    watch.every.to_string(),
+                    value,
+                ]);
+            }
+            output.println(table.to_string());
+        }
+        ["add", rest @ ..] => {
+            let (every, words) = match rest {
+                ["--every", frames, words @ ..] => match frames.parse::<u32>() {
+                    Ok(frames) if frames > 0 => (Some(frames), words),
+                    _ => {
+                        output.fail(format!("Invalid number of frames {:?}!", frames));
+                        return;
+                    }
+                },
+                words => (None, words),
+            };
+            let expression = match words {
+                [] => {
+                    output.fail(USAGE);
+                    return;
+                }
+                // `watch add "count enemies"`
+                [expression] => expression.to_string(),
+                // `watch add count enemies`
+                words => {
+                    let words: Vec<_> = words.iter().map(|word| quote_cli_arg(word)).collect();
+                    words.join(" ")
+                }
+            };
+            let source = output.source().cloned();
+            let id = watches.push(&expression, every, source);
+            output.println(format!("Watching as {}", id));
+        }
+        ["remove", "all"] => watches.clear(),
+        ["remove", id] => {
+            let Ok(id) = id.parse::<u64>() else {
+                output.fail(USAGE);
+                return;
+            };
+            if !watches.remove(id) {
+                output.fail(format!("No watch with id {}!", id));
+            }
+        }
+        _ => output.fail(USAGE),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{cli_println, CliCommandsOutputExt};
+    use crate::status::cli_fail;
+    use crate::CliPlugin;
+
+    #[derive(Resource, Default)]
+    struct Calls(u32);
+
+    #[test]
+    fn watches() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, CliPlugin, CliWatchPlugin { every: 2 }));
+        app.init_resource::<Calls>();
+        app.register_clicommand_noargs("calls", |world: &mut World| {
+            world.resource_mut::<Calls>().0 += 1;
+            let calls = world.resource::<Calls>().0;
+            cli_println(world, calls.to_string());
+        });
+        app.register_clicommand_noargs("broken", |world: &mut World| {
+            cli_println(world, "out of order");
+            cli_fail(world);
+        });
+        app.try_run_cli("watch add calls").unwrap();
+        app.try_run_cli("watch add --every 3 broken").unwrap();
+        app.try_run_cli("watch add \"nope 1\"").unwrap();
+        let values = |app: &App| -> Vec<Option<Result<String, String>>> {
+            let watches = app.world().resource::<CliWatches>().watches();
+            watches.iter().map(|watch| watch.value.clone()).collect()
+        };
+
+        app.update();
+        assert_eq!(
+            values(&app),
+            [
+                Some(Ok("1".to_owned())),
+                Some(Err("out of order".to_owned())),
+                Some(Err("CliCommand \"nope\" not found".to_owned())),
+            ]
+        );
+        app.update();
+        assert_eq!(app.world().resource::<Calls>().0, 1);
+        app.update();
+        assert_eq!(app.world().resource::<Calls>().0, 2);
+
+        let list = app.try_run_cli("watch list").unwrap();
+        assert!(list
+            .iter()
+            .any(|line| line.text.contains("broken") && line.text.contains("error: out of order")));
+        app.try_run_cli("watch remove 1").unwrap();
+        assert_eq!(app.world().resource::<CliWatches>().len(), 2);
+        assert!(app.try_run_cli("watch remove 1").is_err());
+        // Watch output is not logged
+        let log = app.world().resource::<crate::output::CliOutputLog>();
+        assert!(log.iter().all(|line| !line.text.contains("out of order")));
+    }
+}
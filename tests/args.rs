@@ -0,0 +1,45 @@
+use bevy::prelude::*;
+use iyes_cli::prelude::*;
+
+/// The args each run of a command got, or `None` if it ran without args
+#[derive(Resource, Default)]
+struct Calls(Vec<Option<Vec<String>>>);
+
+fn record_args(In(args): In<Vec<String>>, mut calls: ResMut<Calls>) {
+    calls.0.push(Some(args));
+}
+
+fn record_noargs(mut calls: ResMut<Calls>) {
+    calls.0.push(None);
+}
+
+#[test]
+fn args_do_not_include_the_command_name() {
+    let mut world = World::new();
+    world.init_resource::<Calls>();
+    world.register_clicommand_args("spawn", record_args);
+
+    world.run_cli("spawn 1 2");
+    world.run_cli("spawn");
+
+    assert_eq!(
+        world.resource::<Calls>().0,
+        [Some(vec!["1".to_owned(), "2".to_owned()]), Some(vec![])]
+    );
+}
+
+#[test]
+fn noargs_system_runs_without_args() {
+    let mut world = World::new();
+    world.init_resource::<Calls>();
+    world.register_clicommand_noargs("spawn", record_noargs);
+    world.register_clicommand_args("spawn", record_args);
+
+    world.run_cli("spawn");
+    world.run_cli("spawn 1");
+
+    assert_eq!(
+        world.resource::<Calls>().0,
+        [None, Some(vec!["1".to_owned()])]
+    );
+}
@@ -0,0 +1,51 @@
+#![cfg(feature = "auto-register")]
+
+use bevy::prelude::*;
+use iyes_cli::auto_register::{CliAutoRegister, CliAutoRegisterPlugin};
+use iyes_cli::prelude::*;
+use iyes_cli::CliCommands;
+
+#[derive(Resource, Default)]
+struct Healed(Vec<u32>);
+
+/// Heal the player
+#[cli_command(auto)]
+fn heal(amount: u32, mut healed: ResMut<Healed>) {
+    healed.0.push(amount);
+}
+
+#[cli_command(auto, name = "debug.heal_all")]
+fn heal_everyone(mut healed: ResMut<Healed>) {
+    healed.0.push(u32::MAX);
+}
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, CliPlugin, CliAutoRegisterPlugin));
+    app.init_resource::<Healed>();
+    app
+}
+
+#[test]
+fn registers_submitted_commands() {
+    assert_eq!(CliAutoRegister::iter().count(), 2);
+    let mut app = app();
+    app.run_cli("heal 5");
+    assert_eq!(app.world().resource::<Healed>().0, [5]);
+
+    let clicommands = app.world().resource::<CliCommands>();
+    let info = clicommands.get_info("heal").unwrap();
+    assert_eq!(info.description, Some("Heal the player"));
+    assert_eq!(info.usage, Some("heal <amount: u32>"));
+}
+
+#[test]
+fn name_override() {
+    let mut app = app();
+    app.run_cli("debug.heal_all");
+    assert_eq!(app.world().resource::<Healed>().0, [u32::MAX]);
+
+    let clicommands = app.world().resource::<CliCommands>();
+    assert!(clicommands.get_info("debug.heal_all").is_some());
+    assert!(clicommands.get_info("heal_everyone").is_none());
+}
@@ -0,0 +1,82 @@
+#![cfg(feature = "macros")]
+
+use bevy::prelude::*;
+use iyes_cli::prelude::*;
+use iyes_cli::CliCommands;
+
+#[derive(Resource, Default)]
+struct Spawned(Vec<(f32, f32, Option<u32>, Vec<String>)>);
+
+/// Spawn some sprites at the given position
+///
+/// Not part of the description.
+#[cli_command]
+fn spawn(x: f32, y: f32, count: Option<u32>, tags: Vec<String>, mut spawned: ResMut<Spawned>) {
+    spawned.0.push((x, y, count, tags));
+}
+
+#[cli_command(name = "physics.gravity")]
+fn set_gravity(value: f32, mut spawned: ResMut<Spawned>) {
+    spawned.0.push((value, 0.0, None, vec![]));
+}
+
+#[clicommand]
+fn old_name(mut spawned: ResMut<Spawned>) {
+    spawned.0.push((0.0, 0.0, None, vec![]));
+}
+
+fn world() -> World {
+    let mut world = World::new();
+    world.init_resource::<Spawned>();
+    register_spawn(&mut world);
+    register_set_gravity(&mut world);
+    register_old_name(&mut world);
+    world
+}
+
+#[test]
+fn typed_args() {
+    let mut world = world();
+    world.run_cli("spawn 1 2");
+    world.run_cli("spawn 1.5 2 3 a b");
+    world.run_cli("spawn nope 2");
+    assert_eq!(
+        world.resource::<Spawned>().0,
+        [
+            (1.0, 2.0, None, vec![]),
+            (1.5, 2.0, Some(3), vec!["a".to_owned(), "b".to_owned()]),
+        ]
+    );
+}
+
+#[test]
+fn name_description_and_usage() {
+    let mut world = world();
+    world.run_cli("physics.gravity 9.8");
+    assert_eq!(world.resource::<Spawned>().0, [(9.8, 0.0, None, vec![])]);
+
+    let clicommands = world.resource::<CliCommands>();
+    let info = clicommands.get_info("spawn").unwrap();
+    assert_eq!(
+        info.description,
+        Some("Spawn some sprites at the given position")
+    );
+    assert_eq!(
+        info.usage,
+        Some("spawn <x: f32> <y: f32> [count: u32] [tags: String...]")
+    );
+    assert!(clicommands.get_info("gravity").is_none());
+}
+
+#[test]
+fn clicommand_is_an_alias() {
+    let mut world = world();
+    world.run_cli("old_name");
+    assert_eq!(world.resource::<Spawned>().0.len(), 1);
+}
+
+#[test]
+fn compile_errors() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,6 @@
+use iyes_cli::prelude::*;
+
+#[cli_command]
+fn spawn(count: Option<u32>, x: f32) {}
+
+fn main() {}
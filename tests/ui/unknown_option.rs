@@ -0,0 +1,6 @@
+use iyes_cli::prelude::*;
+
+#[cli_command(alias = "s")]
+fn spawn(x: f32) {}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use iyes_cli::prelude::*;
+
+#[cli_command]
+fn tag(tags: Vec<String>, x: f32) {}
+
+fn main() {}